@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+/// What the pages of an archive are grouped by
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveGrouping {
+    /// One archive page per year, at `archive/2018/`
+    #[default]
+    Year,
+    /// One archive page per month, at `archive/2018/03/`
+    Month,
+}
+
+/// The archive pages of a section, listing its dated pages by year or month
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArchiveConfig {
+    /// The section whose pages, including the ones of its subsections, are archived, eg `blog`
+    pub section: String,
+    /// Defaults to `year`
+    pub by: ArchiveGrouping,
+    /// The template of each archive page. Defaults to `archive.html`
+    pub template: String,
+    /// The template of the page listing the archive pages. Defaults to `archive_index.html`
+    pub index_template: String,
+    /// Where the archive pages are, from the root of the site. Defaults to `archive`
+    pub path: String,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            section: String::new(),
+            by: ArchiveGrouping::Year,
+            template: "archive.html".to_string(),
+            index_template: "archive_index.html".to_string(),
+            path: "archive".to_string(),
+        }
+    }
+}
+
+impl ArchiveConfig {
+    /// The section as the components of its path, eg `["blog"]`
+    pub fn section_components(&self) -> Vec<&str> {
+        self.section.split('/').filter(|c| !c.is_empty()).collect()
+    }
+
+    pub fn validate_all(archives: &[ArchiveConfig]) -> Result<()> {
+        for (i, archive) in archives.iter().enumerate() {
+            if archive.path.trim_matches('/').is_empty() {
+                bail!("The archive of the section `{}` needs a `path`", archive.section);
+            }
+            if archives[..i]
+                .iter()
+                .any(|a| a.path.trim_matches('/') == archive.path.trim_matches('/'))
+            {
+                bail!("Several archives have the path `{}`", archive.path);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use std::path::Path;
+
+use libs::globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+fn build_glob_set(patterns: &[String], name: &str) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pat in patterns {
+        match Glob::new(pat) {
+            Ok(g) => builder.add(g),
+            Err(e) => {
+                bail!("Invalid asset_optimization.{} glob pattern: {}, error = {}", name, pat, e)
+            }
+        };
+    }
+    Ok(builder.build()?)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssetOptimization {
+    /// How hard to try to compress PNG files, from 0 (fastest) to 6 (smallest)
+    pub png_level: u8,
+    /// The quality JPEG files are re-encoded with, from 1 to 100
+    pub jpeg_quality: u8,
+    /// Glob patterns, relative to the site directory, of the images to optimize.
+    /// Nothing is optimized if empty
+    pub include: Vec<String>,
+    #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
+    pub include_globset: Option<GlobSet>,
+    /// Glob patterns, relative to the site directory, of the images to never optimize
+    pub exclude: Vec<String>,
+    #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
+    pub exclude_globset: Option<GlobSet>,
+}
+
+impl AssetOptimization {
+    pub fn resolve_globsets(&mut self) -> Result<()> {
+        if self.png_level > 6 {
+            bail!("asset_optimization.png_level must be between 0 and 6, got {}", self.png_level);
+        }
+        if self.jpeg_quality == 0 || self.jpeg_quality > 100 {
+            bail!(
+                "asset_optimization.jpeg_quality must be between 1 and 100, got {}",
+                self.jpeg_quality
+            );
+        }
+        self.include_globset = Some(build_glob_set(&self.include, "include")?);
+        self.exclude_globset = Some(build_glob_set(&self.exclude, "exclude")?);
+        Ok(())
+    }
+
+    /// Whether the file at the given path, relative to the site directory, should be optimized
+    pub fn should_optimize(&self, relative_path: &Path) -> bool {
+        let included = self.include_globset.as_ref().is_some_and(|gs| gs.is_match(relative_path));
+        let excluded = self.exclude_globset.as_ref().is_some_and(|gs| gs.is_match(relative_path));
+        included && !excluded
+    }
+}
+
+impl Default for AssetOptimization {
+    fn default() -> Self {
+        Self {
+            png_level: 3,
+            jpeg_quality: 82,
+            include: Vec::new(),
+            include_globset: None,
+            exclude: Vec::new(),
+            exclude_globset: None,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use libs::chrono::{DateTime, SecondsFormat};
+use libs::time::OffsetDateTime;
+use serde::Serialize;
+
+/// When and from what the site was built, available as `build_info` in every template and
+/// shortcode
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct BuildInfo {
+    /// The version of Zola building the site
+    pub version: String,
+    /// When the build started, in RFC 3339 and UTC. `SOURCE_DATE_EPOCH` overrides it
+    pub timestamp: String,
+    /// The short hash of the commit checked out, if the site is in a git repository
+    pub git_hash: Option<String>,
+    /// The same time as `timestamp`, in seconds since the Unix epoch
+    #[serde(skip_serializing)]
+    pub unix_timestamp: i64,
+    /// Whether the output should only depend on the sources, with `reproducible` or
+    /// `SOURCE_DATE_EPOCH`: what is usually random is derived from the sources instead
+    #[serde(skip_serializing)]
+    pub reproducible: bool,
+}
+
+impl BuildInfo {
+    pub fn new(version: &str, unix_timestamp: i64, git_hash: Option<String>) -> Self {
+        let timestamp = DateTime::from_timestamp(unix_timestamp, 0)
+            .map(|d| d.to_rfc3339_opts(SecondsFormat::Secs, true))
+            .unwrap_or_default();
+        Self {
+            version: version.to_string(),
+            timestamp,
+            git_hash,
+            unix_timestamp,
+            reproducible: false,
+        }
+    }
+
+    /// When the build started, to compare the dates of the pages with. Falls back to now when
+    /// there is no build, eg when a page is parsed on its own
+    pub fn time(&self) -> OffsetDateTime {
+        if self.timestamp.is_empty() {
+            return OffsetDateTime::now_utc();
+        }
+        OffsetDateTime::from_unix_timestamp(self.unix_timestamp)
+            .unwrap_or_else(|_| OffsetDateTime::now_utc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_format_build_timestamp() {
+        let info = BuildInfo::new("0.19.2", 1714564800, Some("abc1234".to_string()));
+        assert_eq!(info.timestamp, "2024-05-01T12:00:00Z");
+        assert_eq!(info.unix_timestamp, 1714564800);
+        assert_eq!(info.time().unix_timestamp(), 1714564800);
+    }
+}
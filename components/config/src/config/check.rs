@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::html_validation::HtmlValidationLevel;
+
+/// How severe each kind of problem found by `zola check --a11y` is.
+/// Each of them can be set to "error", "warn" or "off".
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct A11yCheck {
+    /// Headings skipping a level, like a `<h4>` after a `<h2>`
+    pub heading_skips: HtmlValidationLevel,
+    /// `<img>` elements without an `alt`
+    pub images_without_alt: HtmlValidationLevel,
+    /// Links whose text is only "here" or "click here"
+    pub vague_link_text: HtmlValidationLevel,
+    /// Documents without a `<main>` landmark
+    pub missing_main: HtmlValidationLevel,
+}
+
+impl Default for A11yCheck {
+    fn default() -> Self {
+        Self {
+            heading_skips: HtmlValidationLevel::Warn,
+            images_without_alt: HtmlValidationLevel::Warn,
+            vague_link_text: HtmlValidationLevel::Warn,
+            missing_main: HtmlValidationLevel::Warn,
+        }
+    }
+}
+
+/// The options of `zola check`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Check {
+    pub a11y: A11yCheck,
+}
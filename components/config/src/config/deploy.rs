@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeployTarget {
+    Rsync,
+    Git,
+    S3,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RsyncTarget {
+    /// The host to copy to, eg `user@example.com`. The path is local if empty
+    pub host: String,
+    /// The directory to copy the site to
+    pub path: String,
+    /// The flags given to rsync
+    pub flags: Vec<String>,
+}
+
+impl Default for RsyncTarget {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            path: String::new(),
+            flags: vec!["-avz".to_string(), "--delete".to_string()],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitTarget {
+    /// The branch the output directory is committed to
+    pub branch: String,
+    /// The remote to push the branch to. The branch is only committed to if empty
+    pub remote: String,
+    /// The message of the commit
+    pub message: String,
+}
+
+impl Default for GitTarget {
+    fn default() -> Self {
+        Self {
+            branch: "gh-pages".to_string(),
+            remote: "origin".to_string(),
+            message: "Publish site".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct S3Target {
+    /// The URL of the S3-compatible API, eg `https://s3.eu-west-1.amazonaws.com`
+    pub endpoint: String,
+    pub bucket: String,
+    /// Prefix of the keys of the uploaded files
+    pub prefix: String,
+    pub region: String,
+    /// The `Cache-Control` header of the uploaded files
+    pub cache_control: String,
+    /// The `Cache-Control` header of the files with a content-hashed filename
+    pub hashed_cache_control: String,
+}
+
+impl Default for S3Target {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            bucket: String::new(),
+            prefix: String::new(),
+            region: "us-east-1".to_string(),
+            cache_control: "public, max-age=3600".to_string(),
+            hashed_cache_control: "public, max-age=31536000, immutable".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Deploy {
+    /// Where `zola publish` sends the site
+    pub target: Option<DeployTarget>,
+    pub rsync: RsyncTarget,
+    pub git: GitTarget,
+    pub s3: S3Target,
+}
+
+impl Deploy {
+    /// Checks that the selected target has everything it needs
+    pub fn validate(&self) -> Result<()> {
+        match self.target {
+            Some(DeployTarget::Rsync) if self.rsync.path.is_empty() => {
+                bail!("`deploy.rsync.path` needs to be set to publish with rsync")
+            }
+            Some(DeployTarget::Git) if self.git.branch.is_empty() => {
+                bail!("`deploy.git.branch` needs to be set to publish with git")
+            }
+            Some(DeployTarget::S3) if self.s3.endpoint.is_empty() || self.s3.bucket.is_empty() => {
+                bail!("`deploy.s3.endpoint` and `deploy.s3.bucket` need to be set to publish to S3")
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Favicon {
+    /// The image to make the favicons and the web manifest from, relative to the site directory.
+    /// Nothing is generated if not set
+    pub source: Option<String>,
+    /// The color put behind the transparent parts of the Apple touch icon and the
+    /// `background_color` of the web manifest, eg `#fff`
+    pub background: Option<String>,
+    /// The `theme_color` of the web manifest, `background` by default
+    pub theme_color: Option<String>,
+}
+
+impl Favicon {
+    pub fn validate(&self) -> Result<()> {
+        for (name, color) in [("background", &self.background), ("theme_color", &self.theme_color)]
+        {
+            if let Some(c) = color {
+                if parse_hex_color(c).is_none() {
+                    bail!(
+                        "favicon.{} must be a hex color like `#fff` or `#1e90ff`, got `{}`",
+                        name,
+                        c
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The RGB value of `background`, if it is set
+    pub fn background_rgb(&self) -> Option<[u8; 3]> {
+        self.background.as_deref().and_then(parse_hex_color)
+    }
+
+    pub fn theme_color(&self) -> Option<&str> {
+        self.theme_color.as_deref().or(self.background.as_deref())
+    }
+}
+
+/// Parses a `#rgb` or `#rrggbb` color
+fn parse_hex_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+    match hex.len() {
+        3 => {
+            let mut rgb = [0; 3];
+            for (i, c) in hex.chars().enumerate() {
+                rgb[i] = channel(&c.to_string().repeat(2))?;
+            }
+            Some(rgb)
+        }
+        6 => Some([channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_hex_colors() {
+        assert_eq!(parse_hex_color("#fff"), Some([255, 255, 255]));
+        assert_eq!(parse_hex_color("#1e90FF"), Some([30, 144, 255]));
+        assert_eq!(parse_hex_color("fff"), None);
+        assert_eq!(parse_hex_color("#ffff"), None);
+        assert_eq!(parse_hex_color("#ggg"), None);
+    }
+
+    #[test]
+    fn theme_color_defaults_to_background() {
+        let favicon = Favicon { background: Some("#fff".to_string()), ..Default::default() };
+        assert_eq!(favicon.theme_color(), Some("#fff"));
+        assert!(favicon.validate().is_ok());
+        let favicon = Favicon { theme_color: Some("red".to_string()), ..Default::default() };
+        assert!(favicon.validate().is_err());
+    }
+}
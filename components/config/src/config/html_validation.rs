@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HtmlValidationLevel {
+    Error,
+    Warn,
+    Off,
+}
+
+/// How severe each kind of problem found by `validate_html` is.
+/// Each of them can be set to "error", "warn" or "off".
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HtmlValidation {
+    /// Elements that are opened but never closed, or closed without being opened
+    pub unclosed_elements: HtmlValidationLevel,
+    /// Several elements of the same page having the same `id`
+    pub duplicate_ids: HtmlValidationLevel,
+    /// `<a>` elements without a `href`
+    pub links_without_href: HtmlValidationLevel,
+    /// `<img>` elements without an `alt`, defaults to "warn"
+    pub images_without_alt: HtmlValidationLevel,
+    /// Block elements like `<div>` inside a `<p>`
+    pub invalid_nesting: HtmlValidationLevel,
+}
+
+impl Default for HtmlValidation {
+    fn default() -> Self {
+        Self {
+            unclosed_elements: HtmlValidationLevel::Error,
+            duplicate_ids: HtmlValidationLevel::Error,
+            links_without_href: HtmlValidationLevel::Error,
+            images_without_alt: HtmlValidationLevel::Warn,
+            invalid_nesting: HtmlValidationLevel::Error,
+        }
+    }
+}
@@ -1,13 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use errors::{bail, Result};
 use libs::unic_langid::LanguageIdentifier;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 
 use crate::config::search;
 use crate::config::taxonomies;
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct LanguageOptions {
     /// Title of the site. Defaults to None
@@ -27,9 +27,18 @@ pub struct LanguageOptions {
     /// A toml crate `Table` with String key representing term and value
     /// another `String` representing its translation.
     /// Use `get_translation()` method for translating key into different languages.
+    #[serde(serialize_with = "serialize_sorted")]
     pub translations: HashMap<String, String>,
 }
 
+/// Serializes the translations sorted by key so `config.languages` is the same on every build
+fn serialize_sorted<S: Serializer>(
+    translations: &HashMap<String, String>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    translations.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
 impl LanguageOptions {
     /// Merges self with another LanguageOptions, erroring if 2 equivalent fields are not None,
     /// empty or the default value.
@@ -18,7 +18,7 @@ impl Default for LinkCheckerLevel {
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct LinkChecker {
     /// Skip link checking for these URL prefixes
@@ -29,12 +29,33 @@ pub struct LinkChecker {
     pub internal_level: LinkCheckerLevel,
     /// Emit either "error" or "warn" for broken external links (including anchor links).
     pub external_level: LinkCheckerLevel,
+    /// Emit either "error" or "warn" for absolute links to the site that don't start with the
+    /// `base_url`, like `http://` ones when it is `https://`. Defaults to "warn"
+    pub self_links_level: LinkCheckerLevel,
+    /// Whether to rewrite these links when rendering the Markdown: to the permalink of the page
+    /// or section they point to if there is one, otherwise to the scheme of the `base_url`
+    pub rewrite_self_links: bool,
     /// A list of file glob patterns to skip link checking on
     pub ignored_files: Vec<String>,
     #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
     pub ignored_files_globset: Option<GlobSet>,
 }
 
+impl Default for LinkChecker {
+    fn default() -> Self {
+        Self {
+            skip_prefixes: Vec::new(),
+            skip_anchor_prefixes: Vec::new(),
+            internal_level: LinkCheckerLevel::Error,
+            external_level: LinkCheckerLevel::Error,
+            self_links_level: LinkCheckerLevel::Warn,
+            rewrite_self_links: false,
+            ignored_files: Vec::new(),
+            ignored_files_globset: None,
+        }
+    }
+}
+
 impl LinkChecker {
     pub fn resolve_globset(&mut self) -> Result<()> {
         let glob_set = build_ignore_glob_set(&self.ignored_files, "files")?;
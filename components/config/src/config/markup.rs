@@ -1,4 +1,6 @@
-use std::{path::Path, sync::Arc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use libs::syntect::{
     highlighting::{Theme, ThemeSet},
@@ -22,6 +24,30 @@ pub struct ThemeCss {
     pub filename: String,
 }
 
+/// How the highlighted code is coloured
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightingMode {
+    /// Colours are set in `style` attributes, the default
+    #[default]
+    Inline,
+    /// Tokens get classes and the colours of the theme are in a `syntax-theme.css` file
+    Css,
+}
+
+/// How the ids of the headings without an explicit `{#id}` are made
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnchorStyle {
+    /// The heading text slugified with the `slugify.anchors` strategy, the default
+    #[default]
+    Slug,
+    /// The heading text with unicode kept, only replacing whitespace
+    Safe,
+    /// A short hash of the heading text
+    Hash,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Markdown {
@@ -30,6 +56,12 @@ pub struct Markdown {
     /// Which themes to use for code highlighting. See Readme for supported themes
     /// Defaults to "base16-ocean-dark"
     pub highlight_theme: String,
+    /// Whether to colour code with inline styles or with classes and a stylesheet.
+    /// Defaults to `inline`
+    pub highlighting_mode: HighlightingMode,
+    /// The theme to use in the stylesheet when the user prefers a dark colour scheme,
+    /// only used with the `css` highlighting mode
+    pub highlight_theme_dark: Option<String>,
     /// Generate CSS files for Themes out of syntect
     pub highlight_themes_css: Vec<ThemeCss>,
     /// Whether to render emoji aliases (e.g.: :smile: => 😄) in the markdown files
@@ -55,13 +87,19 @@ pub struct Markdown {
     pub extra_theme_set: Arc<Option<ThemeSet>>,
     /// Add loading="lazy" decoding="async" to img tags. When turned on, the alt text must be plain text. Defaults to false
     pub lazy_async_image: bool,
+    /// How many levels to shift the headings of the markdown content down by, eg `1` to render
+    /// `# Intro` as a `<h2>` when the templates already use the title as `<h1>`.
+    /// Headings never go past `<h6>`. Defaults to 0
+    pub heading_offset: u8,
+    /// How the ids of the headings are made. Defaults to `slug`
+    pub anchor_style: AnchorStyle,
 }
 
 impl Markdown {
     /// Gets the configured highlight theme from the THEME_SET or the config's extra_theme_set
-    /// Returns None if the configured highlighting theme is set to use css
+    /// Returns None if the code is highlighted with classes
     pub fn get_highlight_theme(&self) -> Option<&Theme> {
-        if self.highlight_theme == "css" {
+        if self.highlighting_mode == HighlightingMode::Css || self.highlight_theme == "css" {
             None
         } else {
             self.get_highlight_theme_by_name(&self.highlight_theme)
@@ -108,6 +146,22 @@ impl Markdown {
         }
     }
 
+    /// The content of the `syntax-theme.css` file written in the `css` highlighting mode.
+    /// With a dark theme, both themes are wrapped in `prefers-color-scheme` media queries.
+    pub fn export_syntax_theme_css(&self) -> Result<String> {
+        let light = self.export_theme_css(&self.highlight_theme)?;
+        match &self.highlight_theme_dark {
+            Some(dark_theme) => {
+                let dark = self.export_theme_css(dark_theme)?;
+                Ok(format!(
+                    "@media (prefers-color-scheme: light) {{\n{}}}\n\n@media (prefers-color-scheme: dark) {{\n{}}}\n",
+                    light, dark
+                ))
+            }
+            None => Ok(light),
+        }
+    }
+
     pub fn init_extra_syntaxes_and_highlight_themes(&mut self, path: &Path) -> Result<()> {
         let (loaded_extra_syntaxes, loaded_extra_highlight_themes) =
             self.load_extra_syntaxes_and_highlight_themes(path)?;
@@ -120,7 +174,16 @@ impl Markdown {
             self.extra_theme_set = Arc::new(Some(extra_theme_set));
         }
 
+        if let Some(dark_theme) = &self.highlight_theme_dark {
+            if self.get_highlight_theme_by_name(dark_theme).is_none() {
+                bail!("Dark highlight theme {} not available", dark_theme)
+            }
+        }
+
         if self.highlight_theme == "css" {
+            if self.highlighting_mode == HighlightingMode::Css {
+                bail!("`highlight_theme` needs to be the name of a theme to generate the stylesheet of the `css` highlighting mode");
+            }
             return Ok(());
         }
 
@@ -199,6 +262,8 @@ impl Default for Markdown {
         Markdown {
             highlight_code: false,
             highlight_theme: DEFAULT_HIGHLIGHT_THEME.to_owned(),
+            highlighting_mode: HighlightingMode::default(),
+            highlight_theme_dark: None,
             highlight_themes_css: Vec::new(),
             render_emoji: false,
             external_links_target_blank: false,
@@ -210,6 +275,86 @@ impl Default for Markdown {
             extra_syntax_set: None,
             extra_theme_set: Arc::new(None),
             lazy_async_image: false,
+            heading_offset: 0,
+            anchor_style: AnchorStyle::default(),
+        }
+    }
+}
+
+/// The content written in other markup languages than Markdown
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Markup {
+    /// The command converting the pages with that extension to HTML, like
+    /// `adoc = "asciidoctor --embedded -o - {file}"`. `{file}` is replaced by the path of a file
+    /// containing the body of the page, which is given on stdin otherwise
+    pub external: HashMap<String, String>,
+}
+
+/// Whether that program can be run, either as a path or by looking it up in the `PATH`
+fn find_program(program: &Path) -> bool {
+    if program.components().count() > 1 {
+        return program.is_file();
+    }
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    let extensions: &[&str] = if cfg!(windows) { &["", ".exe", ".cmd", ".bat"] } else { &[""] };
+    std::env::split_paths(&paths).any(|dir| {
+        extensions.iter().any(|ext| {
+            let mut name = program.as_os_str().to_owned();
+            name.push(ext);
+            dir.join(name).is_file()
+        })
+    })
+}
+
+impl Markup {
+    /// The converter of that content file, if it isn't Markdown
+    pub fn external_command(&self, path: &Path) -> Option<&str> {
+        let extension = path.extension()?.to_str()?;
+        self.external.get(extension).map(|c| c.as_str())
+    }
+
+    /// Whether that file is a page: a Markdown file or one with an external converter
+    pub fn is_content_file(&self, path: &Path) -> bool {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("md") => true,
+            Some(extension) => self.external.contains_key(extension),
+            None => false,
         }
     }
+
+    /// Checks the converters exist, so a missing one fails the build before rendering anything.
+    /// Relative paths to a converter are made absolute from the site directory, as the
+    /// commands are run in the directory of the page they convert
+    pub fn init_external_converters(&mut self, site_dir: &Path) -> Result<()> {
+        for (extension, command) in self.external.iter_mut() {
+            if extension == "md" || extension.is_empty() || extension.contains('.') {
+                bail!(
+                    "`{}` can't be converted by an external command in `[markup.external]`, use an extension like `adoc`",
+                    extension
+                );
+            }
+            let Some(program) = command.split_whitespace().next().map(|p| p.to_string()) else {
+                bail!(
+                    "The command converting `.{}` files in `[markup.external]` is empty",
+                    extension
+                );
+            };
+            let mut program_path = PathBuf::from(&program);
+            if program_path.is_relative() && program_path.components().count() > 1 {
+                program_path = site_dir.join(program_path);
+                *command = command.replacen(&program, &program_path.to_string_lossy(), 1);
+            }
+            if !find_program(&program_path) {
+                bail!(
+                    "`{}`, the command converting `.{}` files in `[markup.external]`, was not found",
+                    program,
+                    extension
+                );
+            }
+        }
+        Ok(())
+    }
 }
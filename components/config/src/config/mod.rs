@@ -1,21 +1,36 @@
+pub mod archives;
+pub mod asset_optimization;
+pub mod build_info;
+pub mod check;
+pub mod deploy;
+pub mod favicon;
+pub mod html_validation;
 pub mod languages;
 pub mod link_checker;
 pub mod markup;
+pub mod output_formats;
+pub mod redirects;
+pub mod related;
 pub mod search;
 pub mod slugify;
 pub mod taxonomies;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
+use libs::chrono::{FixedOffset, NaiveDate, Offset, TimeZone};
+use libs::chrono_tz::Tz;
 use libs::globset::GlobSet;
+use libs::tera::{Context, Map, Value};
+use libs::time::{Month, PrimitiveDateTime, UtcOffset, Weekday};
 use libs::toml::Value as Toml;
+use libs::url::Url;
 use serde::{Deserialize, Serialize};
 
 use crate::theme::Theme;
 use errors::{anyhow, bail, Result};
-use utils::fs::read_file;
-use utils::globs::build_ignore_glob_set;
+use utils::fs::{read_file, CopyMode};
+use utils::globs::{build_glob_set, build_ignore_glob_set};
 use utils::slugs::slugify_paths;
 
 // We want a default base url for tests
@@ -29,11 +44,27 @@ pub enum Mode {
     Check,
 }
 
+/// What `get_page`, `get_section` and `get_pages_by` do when the content asked for doesn't exist
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingContent {
+    /// Fail the build
+    #[default]
+    Error,
+    /// Return nothing and list what was missing at the end of the build
+    Warn,
+    /// Return nothing
+    Ignore,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct Config {
     /// Base URL of the site, the only required config argument
     pub base_url: String,
+    /// The URL the static files, the processed images and the colocated assets are linked from,
+    /// eg a CDN serving the output directory. Defaults to `base_url`
+    pub asset_base_url: Option<String>,
 
     /// Theme to use
     pub theme: Option<String>,
@@ -56,9 +87,36 @@ pub struct Config {
     /// The filenames to use for feeds. Used to find the templates, too.
     /// Defaults to ["atom.xml"], with "rss.xml" also having a template provided out of the box.
     pub feed_filenames: Vec<String>,
+    /// Where the first of the `feed_filenames` is written instead, relative to the root of the
+    /// site and of the sections and taxonomy terms with feeds, eg `feed/index.xml`.
+    /// Defaults to the name of the feed
+    pub feed_filename: Option<String>,
+    /// The sections whose pages, including the ones of their subsections, go in the main feed,
+    /// eg `["blog", "notes"]`. Defaults to all of them
+    pub feed_sections: Vec<String>,
+    /// How many words of the content to use as the summary of the pages without a
+    /// `<!-- more -->`. Defaults to None, leaving them without summary.
+    pub auto_summary_length: Option<usize>,
+    /// How many characters of the text of the content make the `description_resolved` of the
+    /// pages and sections without a description or summary. Defaults to 160
+    pub description_length: usize,
     /// If set, files from static/ will be hardlinked instead of copied to the output dir.
+    /// Same as setting `copy_mode = "hardlink"`
     pub hard_link_static: bool,
+    /// How the files from static/ and the content assets are put in the output dir.
+    /// Defaults to copying them
+    pub copy_mode: CopyMode,
     pub taxonomies: Vec<taxonomies::TaxonomyConfig>,
+    /// The archive pages to generate, listing the pages of a section by year or month
+    pub archives: Vec<archives::ArchiveConfig>,
+    /// The redirect rules files to generate for the hosts doing real redirects, from the aliases
+    /// of the pages and sections and `redirects`
+    pub redirect_output: Vec<redirects::RedirectOutput>,
+    /// Redirects to add to the rules files
+    pub redirects: Vec<redirects::Redirect>,
+    /// Whether to render the HTML pages redirecting the aliases. Can only be disabled when a
+    /// redirect rules file is generated, and they are always rendered by `zola serve`
+    pub alias_html: bool,
     /// The default author for pages.
     pub author: Option<String>,
 
@@ -80,6 +138,18 @@ pub struct Config {
     #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
     pub ignored_static_globset: Option<GlobSet>,
 
+    /// Paths to skip when loading the content, copying the static files and watching for
+    /// changes, in the gitignore syntax and anchored to the root of the site. They are applied
+    /// after the ones of the `.zolaignore` file at the root of the site, if there is one
+    pub ignore_patterns: Vec<String>,
+
+    /// A list of file glob patterns, relative to the static folder, of the files that get a
+    /// content hash inserted in their filename when building the site. Compiled Sass files are
+    /// matched relative to the output directory. Defaults to none.
+    pub hash_static_assets: Vec<String>,
+    #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
+    pub hash_static_assets_globset: Option<GlobSet>,
+
     /// The mode Zola is currently being ran on. Some logging/feature can differ depending on the
     /// command being used.
     #[serde(skip_serializing)]
@@ -88,6 +158,47 @@ pub struct Config {
     pub output_dir: String,
     /// Whether dotfiles inside the output directory are preserved when rebuilding the site
     pub preserve_dotfiles_in_output: bool,
+    /// Whether using a key of `page.extra` or `section.extra` that isn't set in a page/section
+    /// template is an error. If false, they are rendered as empty strings.
+    pub missing_extra_is_error: bool,
+    /// Whether `page.extra` and `section.extra` are their `effective_extra`, the `extra` of the
+    /// config and of the ancestor sections merged with their own
+    pub merge_extra: bool,
+    /// What the functions getting pages and sections do when they don't exist
+    pub missing_content: MissingContent,
+    /// The timezone of the front matter datetimes without an offset, either an offset like
+    /// `+02:00` or an IANA name like `Europe/Paris`. They are in UTC if not set.
+    pub default_timezone: Option<String>,
+    /// Whether to load the last commit of each page from git, as `page.git`
+    pub git_metadata: bool,
+    /// The URL to edit a page, where `{path}` is the path of the file in the repository and
+    /// `{remote}` the URL of the `origin` remote, eg `{remote}/edit/main/{path}`
+    pub git_edit_url: Option<String>,
+    /// Whether pages of a section ending up with the same slug get a `-1`, `-2`... suffix
+    /// instead of being an error. Defaults to false
+    pub deduplicate_slugs: bool,
+    /// How many words per minute are read, to compute the reading time of pages and sections.
+    /// Defaults to 200
+    pub reading_speed: usize,
+    /// Whether the words of code blocks and shortcode bodies are counted in the word count and
+    /// the reading time. Defaults to false
+    pub count_code_blocks: bool,
+    /// Whether pages that are not `index.md` get the sibling files named after them as assets,
+    /// like `post.png` for `post.md`. Defaults to false
+    pub colocate_loose_assets: bool,
+    /// Whether to render the sections made up for the directories of `content` without an
+    /// `_index.md`. Defaults to false
+    pub implicit_sections_render: bool,
+    /// The additional files to render for each page, next to their `index.html`.
+    /// Sections can override it for their pages
+    pub page_output_formats: Vec<output_formats::OutputFormat>,
+    /// Whether to check the HTML generated for pages and sections for structural problems.
+    /// Defaults to false
+    pub validate_html: bool,
+    /// How severe each kind of problem found when `validate_html` is on is
+    pub html_validation: html_validation::HtmlValidation,
+    /// The options of `zola check`, like how severe the problems found by `--a11y` are
+    pub check: check::Check,
 
     pub link_checker: link_checker::LinkChecker,
     /// The setup for which slugification strategies to use for paths, taxonomies and anchors
@@ -96,32 +207,151 @@ pub struct Config {
     pub search: search::Search,
     /// The config for the Markdown rendering: syntax highlighting and everything
     pub markdown: markup::Markdown,
+    /// The content written in other markup languages, converted to HTML by external commands
+    pub markup: markup::Markup,
+    /// Which images to optimize when building the site and how
+    pub asset_optimization: asset_optimization::AssetOptimization,
+    /// Where and how `zola publish` uploads the site
+    pub deploy: deploy::Deploy,
+    /// The favicons and web manifest to generate from a single image
+    pub favicon: favicon::Favicon,
+    /// The passwords the protected pages can refer to by name, never given to the templates
+    #[serde(skip_serializing)]
+    pub passwords: HashMap<String, String>,
+    /// How to find the pages related to each page through their taxonomies
+    pub related_pages: related::RelatedPages,
+    /// A data file, relative to the site directory, or a URL mapping permalinks to arrays of
+    /// mentions like webmentions, given to each page as `page.mentions`
+    pub mentions_source: Option<String>,
     /// All user params set in `[extra]` in the config
     pub extra: HashMap<String, Toml>,
     /// Enables the generation of Sitemap.xml
     pub generate_sitemap: bool,
+    /// Where the sitemap is written, relative to the output directory. The sitemaps it is split
+    /// into when it is too big are next to it, with a number. Defaults to `sitemap.xml`
+    pub sitemap_filename: String,
     /// Enables the generation of robots.txt
     pub generate_robots_txt: bool,
+    /// Whether to write a `zola-manifest.json` listing every generated file with its source,
+    /// permalink and hash at the end of `zola build`
+    pub generate_manifest: bool,
+    /// Whether `zola build` moves the rendered HTML of the pages to disk as soon as their
+    /// Markdown is rendered, reading it back only when a template or the search index uses it.
+    /// Lowers the memory used by very large sites, `zola serve` ignores it
+    pub low_memory: bool,
+    /// Whether `zola build` makes the same output from the same sources, byte for byte: the build
+    /// time defaults to the date of the last commit instead of now when `SOURCE_DATE_EPOCH` is
+    /// not set, and it is given as modification time to every generated file
+    pub reproducible: bool,
+    /// Whether the templates error on undefined variables used as conditions, like
+    /// `{% if page.titel %}`, instead of treating them as falsy
+    pub strict_templates: bool,
+    /// The format used by the `date` filter when no `format` argument is given.
+    /// Defaults to `%Y-%m-%d`
+    pub date_format: String,
+    /// Set when the site is loaded, available as `build_info` in every template and shortcode
+    #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
+    pub build_info: build_info::BuildInfo,
+}
+
+enum Timezone {
+    Offset(UtcOffset),
+    Iana(Tz),
+}
+
+fn parse_timezone(tz: &str) -> Option<Timezone> {
+    match tz.parse::<FixedOffset>() {
+        Ok(offset) => {
+            UtcOffset::from_whole_seconds(offset.local_minus_utc()).ok().map(Timezone::Offset)
+        }
+        Err(_) => tz.parse().ok().map(Timezone::Iana),
+    }
+}
+
+/// Checks that a file written by Zola, like the sitemap, stays in the output directory
+fn validate_output_filename(key: &str, filename: &str) -> Result<()> {
+    if filename.is_empty()
+        || filename.starts_with('/')
+        || filename.ends_with('/')
+        || filename.split(['/', '\\']).any(|c| c.is_empty() || c == "." || c == "..")
+    {
+        bail!(
+            "`{}` should be a file path relative to the output directory, like `feed/index.xml`, got `{}`",
+            key,
+            filename
+        );
+    }
+    Ok(())
+}
+
+/// Whether the path is an absolute url like `https://example.com/`, ie it starts with a scheme
+fn has_scheme(path: &str) -> bool {
+    match path.split_once("://") {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        }
+        None => false,
+    }
+}
+
+/// `asset_base_url` needs to be an absolute URL written like `base_url`, that can't serve the
+/// assets from a path of the site itself
+fn validate_asset_base_url(asset_base_url: &str, base_url: &str) -> Result<()> {
+    let Ok(asset_url) = Url::parse(asset_base_url) else {
+        bail!(
+            "`asset_base_url` should be an absolute URL like `https://cdn.example.com`, got `{}`",
+            asset_base_url
+        );
+    };
+    if asset_url.query().is_some() || asset_url.fragment().is_some() {
+        bail!("`asset_base_url` can't have a query string or a fragment, got `{}`", asset_base_url);
+    }
+    if asset_base_url.ends_with('/') != base_url.ends_with('/') {
+        bail!(
+            "`asset_base_url` and `base_url` should both end with a `/` or neither should, got `{}` and `{}`",
+            asset_base_url,
+            base_url
+        );
+    }
+    if let Ok(site_url) = Url::parse(base_url) {
+        let asset_path = format!("{}/", asset_url.path().trim_end_matches('/'));
+        let site_path = format!("{}/", site_url.path().trim_end_matches('/'));
+        let overlaps = asset_path.starts_with(&site_path) || site_path.starts_with(&asset_path);
+        if asset_url.origin() == site_url.origin() && asset_path != site_path && overlaps {
+            bail!(
+                "`asset_base_url` is on the same host as `base_url` with a path overlapping the pages of the site: `{}` and `{}`",
+                asset_url.path(),
+                site_url.path()
+            );
+        }
+    }
+    Ok(())
 }
 
 #[derive(Serialize)]
 pub struct SerializedConfig<'a> {
     base_url: &'a str,
+    asset_base_url: &'a str,
     mode: Mode,
     title: &'a Option<String>,
     description: &'a Option<String>,
-    languages: HashMap<&'a String, &'a languages::LanguageOptions>,
+    languages: BTreeMap<&'a String, &'a languages::LanguageOptions>,
     default_language: &'a str,
     generate_feed: bool,
     generate_feeds: bool,
     feed_filenames: &'a [String],
+    feed_filename: &'a Option<String>,
+    feed_url: Option<String>,
+    feed_sections: &'a [String],
     taxonomies: &'a [taxonomies::TaxonomyConfig],
     author: &'a Option<String>,
     build_search_index: bool,
-    extra: &'a HashMap<String, Toml>,
+    extra: Map<String, Value>,
     markdown: &'a markup::Markdown,
     search: search::SerializedSearch<'a>,
     generate_sitemap: bool,
+    sitemap_filename: &'a str,
     generate_robots_txt: bool,
 }
 
@@ -144,9 +374,38 @@ impl Config {
             languages::validate_code(code)?;
         }
 
+        if let Some(ref asset_base_url) = config.asset_base_url {
+            validate_asset_base_url(asset_base_url, &config.base_url)?;
+        }
+
         config.add_default_language()?;
         config.slugify_taxonomies();
         config.link_checker.resolve_globset()?;
+        config.asset_optimization.resolve_globsets()?;
+        config.favicon.validate()?;
+        if let Some(ref tz) = config.default_timezone {
+            if parse_timezone(tz).is_none() {
+                bail!(
+                    "`default_timezone` should be an offset like `+02:00` or an IANA timezone name like `Europe/Paris`, got `{}`",
+                    tz
+                );
+            }
+        }
+        config.deploy.validate()?;
+        config.related_pages.validate(&config.languages)?;
+        output_formats::OutputFormat::validate_all(&config.page_output_formats)?;
+        archives::ArchiveConfig::validate_all(&config.archives)?;
+        redirects::Redirect::validate_all(&config.redirects)?;
+        if !config.alias_html && config.redirect_output.is_empty() {
+            bail!("`alias_html = false` needs a `redirect_output` to redirect the aliases");
+        }
+        if config.reading_speed == 0 {
+            bail!("`reading_speed` needs to be greater than 0");
+        }
+        if let Some(ref feed_filename) = config.feed_filename {
+            validate_output_filename("feed_filename", feed_filename)?;
+        }
+        validate_output_filename("sitemap_filename", &config.sitemap_filename)?;
 
         let content_glob_set = build_ignore_glob_set(&config.ignored_content, "content")?;
         config.ignored_content_globset = Some(content_glob_set);
@@ -154,6 +413,13 @@ impl Config {
         let static_glob_set = build_ignore_glob_set(&config.ignored_static, "static")?;
         config.ignored_static_globset = Some(static_glob_set);
 
+        let hash_glob_set = build_glob_set(&config.hash_static_assets, "hash_static_assets")?;
+        config.hash_static_assets_globset = Some(hash_glob_set);
+
+        if config.hard_link_static {
+            config.copy_mode = CopyMode::Hardlink;
+        }
+
         Ok(config)
     }
 
@@ -176,6 +442,7 @@ impl Config {
 
         // this is the step at which missing extra syntax and highlighting themes are raised as errors
         config.markdown.init_extra_syntaxes_and_highlight_themes(config_dir)?;
+        config.markup.init_external_converters(config_dir)?;
 
         Ok(config)
     }
@@ -188,10 +455,57 @@ impl Config {
         }
     }
 
-    /// Makes a url, taking into account that the base url might have a trailing slash
+    /// The offset of `default_timezone` for that local datetime, `None` if it isn't set.
+    /// The offset of an IANA timezone depends on the date, eg for daylight saving time.
+    pub fn default_offset_at(&self, datetime: PrimitiveDateTime) -> Option<UtcOffset> {
+        match parse_timezone(self.default_timezone.as_ref()?)? {
+            Timezone::Offset(offset) => Some(offset),
+            Timezone::Iana(tz) => {
+                let naive = NaiveDate::from_ymd_opt(
+                    datetime.year(),
+                    datetime.month() as u32,
+                    datetime.day() as u32,
+                )?
+                .and_hms_opt(
+                    datetime.hour() as u32,
+                    datetime.minute() as u32,
+                    datetime.second() as u32,
+                )?;
+                // A local time skipped by a change of offset is taken as if it happened in UTC
+                let offset = tz
+                    .offset_from_local_datetime(&naive)
+                    .earliest()
+                    .unwrap_or_else(|| tz.offset_from_utc_datetime(&naive));
+                UtcOffset::from_whole_seconds(offset.fix().local_minus_utc()).ok()
+            }
+        }
+    }
+
+    /// Makes a url, taking into account that the base url might have a trailing slash.
+    /// Absolute urls are returned unchanged and the query string and fragment of the path are kept
+    /// as they are, after the trailing slash if one is added.
     pub fn make_permalink(&self, path: &str) -> String {
+        let (path, suffix) = match path.find(['?', '#']) {
+            Some(idx) => path.split_at(idx),
+            None => (path, ""),
+        };
+        if has_scheme(path) {
+            return format!("{}{}", path, suffix);
+        }
+
+        let mut cleaned = String::with_capacity(path.len());
+        for c in path.chars() {
+            if c == '/' && cleaned.ends_with('/') {
+                continue;
+            }
+            cleaned.push(c);
+        }
+        let path = cleaned.as_str();
+
         let trailing_bit = if path.ends_with('/')
             || self.feed_filenames.iter().any(|feed_filename| path.ends_with(feed_filename))
+            || self.feed_filename.as_ref().is_some_and(|f| path.ends_with(f.as_str()))
+            || path.ends_with(&self.sitemap_filename)
             || path.is_empty()
         {
             ""
@@ -200,7 +514,7 @@ impl Config {
         };
 
         // Index section with a base url that has a trailing slash
-        if self.base_url.ends_with('/') && path == "/" {
+        let permalink = if self.base_url.ends_with('/') && path == "/" {
             self.base_url.clone()
         } else if path == "/" {
             // index section with a base url that doesn't have a trailing slash
@@ -211,6 +525,68 @@ impl Config {
             format!("{}{}{}", self.base_url, path, trailing_bit)
         } else {
             format!("{}/{}{}", self.base_url, path, trailing_bit)
+        };
+        format!("{}{}", permalink, suffix)
+    }
+
+    /// `asset_base_url`, unless in `zola serve` where the assets are served locally
+    pub fn get_asset_base_url(&self) -> Option<&str> {
+        self.asset_base_url.as_deref().filter(|_| !self.is_in_serve_mode())
+    }
+
+    /// Makes the URL of a file of the output directory, like a static file or a processed image,
+    /// from `asset_base_url` if it is set and `base_url` otherwise. No trailing slash is added
+    pub fn make_asset_permalink(&self, path: &str) -> String {
+        if has_scheme(path) {
+            return path.to_string();
+        }
+        let base_url = self.get_asset_base_url().unwrap_or(&self.base_url);
+        format!("{}/{}", base_url.trim_end_matches('/'), path.trim_start_matches('/'))
+    }
+
+    /// The link to an asset from its path from the root of the site: that path, or its URL on
+    /// `asset_base_url` if it is set
+    pub fn make_asset_link(&self, path: &str) -> String {
+        match self.get_asset_base_url() {
+            Some(_) => self.make_asset_permalink(path),
+            None => path.to_string(),
+        }
+    }
+
+    /// Moves a permalink of the site to `asset_base_url` if it is set, for the colocated assets
+    pub fn to_asset_permalink(&self, permalink: &str) -> String {
+        match (self.get_asset_base_url(), permalink.strip_prefix(&self.base_url)) {
+            (Some(_), Some(path)) => self.make_asset_permalink(path),
+            _ => permalink.to_string(),
+        }
+    }
+
+    /// Where a feed of the language is written, relative to the root of the site or of the
+    /// section or taxonomy term it is for: `feed_filename` for the first one if it is set
+    pub fn feed_output_filename<'a>(&'a self, lang: &str, feed_filename: &'a str) -> &'a str {
+        match self.feed_filename {
+            Some(ref output)
+                if self.languages[lang].feed_filenames.first().map(|f| f.as_str())
+                    == Some(feed_filename) =>
+            {
+                output
+            }
+            _ => feed_filename,
+        }
+    }
+
+    /// The URL of the main feed of the language, if it has feeds
+    pub fn feed_url(&self, lang: &str) -> Option<String> {
+        let options = self.languages.get(lang)?;
+        if !options.generate_feeds {
+            return None;
+        }
+        let feed_filename = options.feed_filenames.first()?;
+        let output = self.feed_output_filename(lang, feed_filename);
+        if lang == self.default_language {
+            Some(self.make_permalink(output))
+        } else {
+            Some(self.make_permalink(&format!("{}/{}", lang, output)))
         }
     }
 
@@ -233,8 +609,8 @@ impl Config {
             if base_language_options == languages::LanguageOptions::default() {
                 return Ok(());
             }
-            println!("Warning: config.toml contains both default language specific information at base and under section `[languages.{}]`, \
-                which may cause merge conflicts. Please use only one to specify language specific information", self.default_language);
+            console::warn(&format!("config.toml contains both default language specific information at base and under section `[languages.{}]`, \
+                which may cause merge conflicts. Please use only one to specify language specific information", self.default_language));
             base_language_options.merge(section_language_options)?;
         }
         self.languages.insert(self.default_language.clone(), base_language_options);
@@ -283,10 +659,34 @@ impl Config {
         !self.other_languages().is_empty()
     }
 
+    /// Whether the given path matches one of the `ignored_content` patterns.
+    /// The path should be relative to the `content` directory, or to the page bundle directory
+    /// for assets.
+    pub fn is_ignored_content<P: AsRef<Path>>(&self, relative_path: P) -> bool {
+        match self.ignored_content_globset {
+            Some(ref gs) => gs.is_match(relative_path.as_ref()),
+            None => false,
+        }
+    }
+
     pub fn is_in_check_mode(&self) -> bool {
         self.mode == Mode::Check
     }
 
+    pub fn is_in_serve_mode(&self) -> bool {
+        self.mode == Mode::Serve
+    }
+
+    /// Whether the asset at that path, relative to the static or output directory, should get a
+    /// content hash in its filename. Always false in serve mode to keep live reload simple.
+    pub fn should_hash_asset<P: AsRef<Path>>(&self, relative_path: P) -> bool {
+        !self.is_in_serve_mode()
+            && self
+                .hash_static_assets_globset
+                .as_ref()
+                .is_some_and(|gs| gs.is_match(relative_path.as_ref()))
+    }
+
     pub fn enable_serve_mode(&mut self) {
         self.mode = Mode::Serve;
     }
@@ -311,6 +711,33 @@ impl Config {
         }
     }
 
+    /// Returns the name of the given month in that language if the translations have a key for it
+    /// (`january` to `december`) and the English name otherwise
+    pub fn get_month_name(&self, lang: &str, month: Month) -> String {
+        let name = month.to_string();
+        self.get_translation(lang, &name.to_lowercase()).unwrap_or(name)
+    }
+
+    /// Returns the name of the given day of the week in that language if the translations
+    /// have a key for it (`monday` to `sunday`) and the English name otherwise
+    pub fn get_weekday_name(&self, lang: &str, weekday: Weekday) -> String {
+        let name = weekday.to_string();
+        self.get_translation(lang, &name.to_lowercase()).unwrap_or(name)
+    }
+
+    /// Whether the pages of the section at `components`, eg `["blog", "2024"]`, go in the
+    /// main feed: they need to be in one of the `feed_sections` or one of their subsections
+    pub fn is_in_feed_sections(&self, components: &[String]) -> bool {
+        if self.feed_sections.is_empty() {
+            return true;
+        }
+        self.feed_sections.iter().any(|section| {
+            let section: Vec<_> =
+                section.trim_matches('/').split('/').filter(|c| !c.is_empty()).collect();
+            components.len() >= section.len() && components.iter().zip(section).all(|(a, b)| a == b)
+        })
+    }
+
     pub fn has_taxonomy(&self, name: &str, lang: &str) -> bool {
         if let Some(lang_options) = self.languages.get(lang) {
             lang_options.taxonomies.iter().any(|t| t.name == name)
@@ -319,11 +746,42 @@ impl Config {
         }
     }
 
+    /// The `extra` of the config as it is given to the templates, with the TOML dates as
+    /// strings like in the front-matter of pages and sections. Its keys are sorted, as the
+    /// templates iterate over them in that order
+    pub fn extra_json(&self) -> Map<String, Value> {
+        let extra = self.extra.iter().collect::<BTreeMap<_, _>>();
+        let extra = libs::serde_json::to_value(extra).unwrap_or_default();
+        match extra {
+            Value::Object(m) => match utils::de::fix_toml_dates(m) {
+                Value::Object(m) => m,
+                _ => unreachable!(),
+            },
+            _ => Map::new(),
+        }
+    }
+
+    /// The variables every template and shortcode gets: `config` for that language, the content
+    /// of the `data` directory loaded with the site, `build_info` and `lang`
+    pub fn template_context(&self, lang: &str, data: &Value) -> Context {
+        let mut context = Context::new();
+        // A config that wasn't loaded from a file, like `Config::default()`, doesn't have its
+        // default language yet
+        if self.languages.contains_key(lang) {
+            context.insert("config", &self.serialize(lang));
+        }
+        context.insert("data", data);
+        context.insert("build_info", &self.build_info);
+        context.insert("lang", lang);
+        context
+    }
+
     pub fn serialize(&self, lang: &str) -> SerializedConfig {
         let options = &self.languages[lang];
 
         SerializedConfig {
             base_url: &self.base_url,
+            asset_base_url: self.get_asset_base_url().unwrap_or(&self.base_url),
             mode: self.mode,
             title: &options.title,
             description: &options.description,
@@ -332,13 +790,17 @@ impl Config {
             generate_feed: options.generate_feeds,
             generate_feeds: options.generate_feeds,
             feed_filenames: &options.feed_filenames,
+            feed_filename: &self.feed_filename,
+            feed_url: self.feed_url(lang),
+            feed_sections: &self.feed_sections,
             taxonomies: &options.taxonomies,
             author: &self.author,
             build_search_index: options.build_search_index,
-            extra: &self.extra,
+            extra: self.extra_json(),
             markdown: &self.markdown,
             search: self.search.serialize(),
             generate_sitemap: self.generate_sitemap,
+            sitemap_filename: &self.sitemap_filename,
             generate_robots_txt: self.generate_robots_txt,
         }
     }
@@ -376,6 +838,7 @@ impl Default for Config {
     fn default() -> Config {
         Config {
             base_url: DEFAULT_BASE_URL.to_string(),
+            asset_base_url: None,
             title: None,
             description: None,
             theme: None,
@@ -383,9 +846,18 @@ impl Default for Config {
             languages: HashMap::new(),
             generate_feeds: false,
             feed_limit: None,
+            auto_summary_length: None,
+            description_length: 160,
             feed_filenames: vec!["atom.xml".to_string()],
+            feed_filename: None,
+            feed_sections: Vec::new(),
             hard_link_static: false,
+            copy_mode: CopyMode::Copy,
             taxonomies: Vec::new(),
+            archives: Vec::new(),
+            redirect_output: Vec::new(),
+            redirects: Vec::new(),
+            alias_html: true,
             author: None,
             compile_sass: false,
             minify_html: false,
@@ -395,22 +867,56 @@ impl Default for Config {
             ignored_content_globset: None,
             ignored_static: Vec::new(),
             ignored_static_globset: None,
+            ignore_patterns: Vec::new(),
+            hash_static_assets: Vec::new(),
+            hash_static_assets_globset: None,
             translations: HashMap::new(),
             output_dir: "public".to_string(),
             preserve_dotfiles_in_output: false,
+            missing_extra_is_error: true,
+            merge_extra: false,
+            missing_content: MissingContent::Error,
+            default_timezone: None,
+            git_metadata: false,
+            git_edit_url: None,
+            deduplicate_slugs: false,
+            reading_speed: 200,
+            count_code_blocks: false,
+            colocate_loose_assets: false,
+            implicit_sections_render: false,
+            page_output_formats: Vec::new(),
+            validate_html: false,
+            html_validation: html_validation::HtmlValidation::default(),
+            check: check::Check::default(),
             link_checker: link_checker::LinkChecker::default(),
             slugify: slugify::Slugify::default(),
             search: search::Search::default(),
             markdown: markup::Markdown::default(),
+            markup: markup::Markup::default(),
+            asset_optimization: asset_optimization::AssetOptimization::default(),
+            deploy: deploy::Deploy::default(),
+            favicon: favicon::Favicon::default(),
+            passwords: HashMap::new(),
+            related_pages: related::RelatedPages::default(),
+            mentions_source: None,
             extra: HashMap::new(),
             generate_sitemap: true,
+            sitemap_filename: "sitemap.xml".to_string(),
             generate_robots_txt: true,
+            generate_manifest: false,
+            low_memory: false,
+            reproducible: false,
+            strict_templates: false,
+            date_format: "%Y-%m-%d".to_string(),
+            build_info: build_info::BuildInfo::default(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::html_validation::HtmlValidationLevel;
+    use super::markup::HighlightingMode;
     use super::*;
     use utils::slugs::SlugifyStrategy;
 
@@ -567,6 +1073,32 @@ hello = "world"
         assert_eq!(config.make_permalink("atom.xml"), "http://vincent.is/atom.xml");
     }
 
+    #[test]
+    fn can_make_url_with_queries_fragments_and_absolute_urls() {
+        for (base_url, path, expected) in [
+            ("http://vincent.is", "https://example.com/a//b", "https://example.com/a//b"),
+            ("http://vincent.is", "git+ssh://host/repo.git", "git+ssh://host/repo.git"),
+            ("http://vincent.is", "hello?page=2", "http://vincent.is/hello/?page=2"),
+            ("http://vincent.is/", "/hello/#top", "http://vincent.is/hello/#top"),
+            ("http://vincent.is", "hello?a=1#top", "http://vincent.is/hello/?a=1#top"),
+            ("http://vincent.is", "?page=2", "http://vincent.is/?page=2"),
+            ("http://vincent.is/", "#top", "http://vincent.is/#top"),
+            ("http://vincent.is", "//hello///world", "http://vincent.is/hello/world/"),
+            ("http://vincent.is/", "//", "http://vincent.is/"),
+            ("http://vincent.is", "atom.xml?v=1", "http://vincent.is/atom.xml?v=1"),
+            (
+                "http://vincent.is",
+                "go?to=https://example.com//a",
+                "http://vincent.is/go/?to=https://example.com//a",
+            ),
+            ("http://vincent.is", "a:b//c", "http://vincent.is/a:b/c/"),
+            ("http://127.0.0.1:1111", "/tags//rust#x?y", "http://127.0.0.1:1111/tags/rust/#x?y"),
+        ] {
+            let config = Config { base_url: base_url.to_string(), ..Default::default() };
+            assert_eq!(config.make_permalink(path), expected, "{} + {}", base_url, path);
+        }
+    }
+
     #[test]
     fn can_merge_with_theme_data_and_preserve_config_value() {
         let config_str = r#"
@@ -646,6 +1178,23 @@ title = "A title"
         assert_eq!("Translation key 'absent' for language 'en' is missing", format!("{}", error));
     }
 
+    #[test]
+    fn can_get_translated_date_names() {
+        let config_str = r#"
+base_url = "https://remplace-par-ton-url.fr"
+default_language = "fr"
+
+[translations]
+march = "mars"
+tuesday = "mardi"
+"#;
+        let config = Config::parse(config_str).unwrap();
+        assert_eq!(config.get_month_name("fr", Month::March), "mars");
+        assert_eq!(config.get_month_name("fr", Month::April), "April");
+        assert_eq!(config.get_weekday_name("fr", Weekday::Tuesday), "mardi");
+        assert_eq!(config.get_weekday_name("en", Weekday::Tuesday), "Tuesday");
+    }
+
     #[test]
     fn missing_ignored_content_results_in_empty_vector() {
         let config_str = r#"
@@ -716,6 +1265,165 @@ ignored_files = []
         assert_eq!(config.link_checker.ignored_files.len(), 0);
     }
 
+    #[test]
+    fn can_configure_asset_optimization() {
+        let config_str = r#"
+base_url = "example.com"
+
+[asset_optimization]
+jpeg_quality = 70
+include = ["static/photos/**"]
+exclude = ["static/photos/raw/**"]
+        "#;
+        let config = Config::parse(config_str).unwrap();
+        assert_eq!(config.asset_optimization.png_level, 3);
+        assert_eq!(config.asset_optimization.jpeg_quality, 70);
+        assert!(config.asset_optimization.should_optimize(Path::new("static/photos/a.jpg")));
+        assert!(!config.asset_optimization.should_optimize(Path::new("static/photos/raw/a.jpg")));
+        assert!(!config.asset_optimization.should_optimize(Path::new("static/logo.png")));
+
+        let config_str = r#"
+base_url = "example.com"
+
+[asset_optimization]
+png_level = 7
+        "#;
+        assert!(Config::parse(config_str).is_err());
+    }
+
+    #[test]
+    fn can_configure_deploy_targets() {
+        let config_str = r#"
+base_url = "example.com"
+
+[deploy]
+target = "s3"
+
+[deploy.s3]
+endpoint = "https://s3.eu-west-1.amazonaws.com"
+bucket = "my-site"
+        "#;
+        let config = Config::parse(config_str).unwrap();
+        assert_eq!(config.deploy.target, Some(deploy::DeployTarget::S3));
+        assert_eq!(config.deploy.s3.bucket, "my-site");
+        assert_eq!(config.deploy.s3.region, "us-east-1");
+        assert_eq!(config.deploy.git.branch, "gh-pages");
+        assert_eq!(config.deploy.rsync.flags, vec!["-avz", "--delete"]);
+
+        let config_str = r#"
+base_url = "example.com"
+
+[deploy]
+target = "rsync"
+        "#;
+        assert!(Config::parse(config_str).is_err());
+    }
+
+    #[test]
+    fn can_configure_related_pages() {
+        let config_str = r#"
+base_url = "example.com"
+taxonomies = [{ name = "tags" }, { name = "categories" }]
+
+[related_pages]
+limit = 3
+weights = { categories = 2 }
+        "#;
+        let config = Config::parse(config_str).unwrap();
+        assert!(config.related_pages.enabled);
+        assert_eq!(config.related_pages.limit, 3);
+        assert_eq!(config.related_pages.weight("categories"), 2);
+        assert_eq!(config.related_pages.weight("tags"), 1);
+
+        let config_str = r#"
+base_url = "example.com"
+taxonomies = [{ name = "tags" }]
+
+[related_pages]
+weights = { categories = 2 }
+        "#;
+        assert!(Config::parse(config_str).is_err());
+    }
+
+    #[test]
+    fn can_configure_html_validation() {
+        let config = Config::parse("base_url = \"example.com\"").unwrap();
+        assert!(!config.validate_html);
+        assert_eq!(config.html_validation.images_without_alt, HtmlValidationLevel::Warn);
+        assert_eq!(config.html_validation.duplicate_ids, HtmlValidationLevel::Error);
+
+        let config_str = r#"
+base_url = "example.com"
+validate_html = true
+
+[html_validation]
+images_without_alt = "off"
+invalid_nesting = "warn"
+        "#;
+        let config = Config::parse(config_str).unwrap();
+        assert!(config.validate_html);
+        assert_eq!(config.html_validation.images_without_alt, HtmlValidationLevel::Off);
+        assert_eq!(config.html_validation.invalid_nesting, HtmlValidationLevel::Warn);
+        assert_eq!(config.html_validation.unclosed_elements, HtmlValidationLevel::Error);
+
+        let config_str = r#"
+base_url = "example.com"
+
+[html_validation]
+images_without_alt = "fatal"
+        "#;
+        assert!(Config::parse(config_str).is_err());
+    }
+
+    #[test]
+    fn can_set_copy_mode() {
+        let config = Config::parse("base_url = \"example.com\"").unwrap();
+        assert_eq!(config.copy_mode, CopyMode::Copy);
+
+        let config =
+            Config::parse("base_url = \"example.com\"\ncopy_mode = \"reflink_or_copy\"").unwrap();
+        assert_eq!(config.copy_mode, CopyMode::ReflinkOrCopy);
+
+        let config = Config::parse("base_url = \"example.com\"\nhard_link_static = true").unwrap();
+        assert_eq!(config.copy_mode, CopyMode::Hardlink);
+    }
+
+    #[test]
+    fn can_configure_hashed_static_assets() {
+        let config_str = r#"
+base_url = "example.com"
+hash_static_assets = ["*.css", "js/*.js"]
+        "#;
+        let mut config = Config::parse(config_str).unwrap();
+        assert!(config.should_hash_asset("site.css"));
+        assert!(config.should_hash_asset("css/nested/site.css"));
+        assert!(config.should_hash_asset("js/app.js"));
+        assert!(!config.should_hash_asset("vendor.js"));
+        assert!(!config.should_hash_asset("logo.png"));
+
+        config.enable_serve_mode();
+        assert!(!config.should_hash_asset("site.css"));
+
+        assert!(Config::parse("base_url = \"example.com\"\nhash_static_assets = [\"[\"]").is_err());
+    }
+
+    #[test]
+    fn ignored_content_matches_paths_relative_to_content() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+ignored_content = ["**/drafts/**", "*.foo.md", "temp.*"]
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        assert!(config.is_ignored_content("drafts/hello.md"));
+        assert!(config.is_ignored_content("posts/drafts/nested/hello.md"));
+        assert!(config.is_ignored_content("posts/hello.foo.md"));
+        assert!(config.is_ignored_content("temp.txt"));
+        assert!(!config.is_ignored_content("posts/hello.md"));
+        assert!(!config.is_ignored_content("posts/temp.txt"));
+    }
+
     #[test]
     fn non_empty_ignored_content_results_in_vector_of_patterns_and_configured_globset() {
         let config_str = r#"
@@ -952,6 +1660,59 @@ highlight_themes_css = [
         assert!(config.is_err());
     }
 
+    #[test]
+    fn errors_on_zero_reading_speed() {
+        let config = Config::parse("base_url = \"https://example.com\"\nreading_speed = 0");
+        assert!(config.is_err());
+        let config = Config::parse("base_url = \"https://example.com\"").unwrap();
+        assert_eq!(config.reading_speed, 200);
+        assert!(!config.count_code_blocks);
+    }
+
+    #[test]
+    fn can_select_feed_sections() {
+        let mut config = Config::default();
+        let components = |path: &str| -> Vec<String> {
+            path.split('/').filter(|c| !c.is_empty()).map(|c| c.to_string()).collect()
+        };
+        assert!(config.is_in_feed_sections(&components("projects")));
+
+        config.feed_sections = vec!["blog".to_string(), "/notes/daily/".to_string()];
+        assert!(config.is_in_feed_sections(&components("blog")));
+        assert!(config.is_in_feed_sections(&components("blog/2024/january")));
+        assert!(config.is_in_feed_sections(&components("notes/daily")));
+        assert!(!config.is_in_feed_sections(&components("notes")));
+        assert!(!config.is_in_feed_sections(&components("blogroll")));
+        assert!(!config.is_in_feed_sections(&components("")));
+    }
+
+    #[test]
+    fn can_export_syntax_theme_css_with_dark_variant() {
+        let config = r#"
+base_url = "https://example.com"
+
+[markdown]
+highlight_code = true
+highlighting_mode = "css"
+highlight_theme = "base16-ocean-light"
+highlight_theme_dark = "base16-ocean-dark"
+    "#;
+
+        let mut config = Config::parse(config).unwrap();
+        config.markdown.init_extra_syntaxes_and_highlight_themes(Path::new(".")).unwrap();
+        assert_eq!(config.markdown.highlighting_mode, HighlightingMode::Css);
+        assert!(config.markdown.get_highlight_theme().is_none());
+        let css = config.markdown.export_syntax_theme_css().unwrap();
+        assert!(css.starts_with("@media (prefers-color-scheme: light) {\n"));
+        assert!(css.contains("\n@media (prefers-color-scheme: dark) {\n"));
+
+        config.markdown.highlight_theme_dark = Some("asdf".to_owned());
+        assert!(config.markdown.init_extra_syntaxes_and_highlight_themes(Path::new(".")).is_err());
+        config.markdown.highlight_theme_dark = None;
+        config.markdown.highlight_theme = "css".to_owned();
+        assert!(config.markdown.init_extra_syntaxes_and_highlight_themes(Path::new(".")).is_err());
+    }
+
     // https://github.com/getzola/zola/issues/1687
     #[test]
     fn regression_config_default_lang_data() {
@@ -1003,6 +1764,96 @@ feed_filename = "test.xml"
         Config::parse(config).unwrap();
     }
 
+    #[test]
+    fn can_set_feed_and_sitemap_filenames() {
+        let config = Config::parse(
+            r#"
+base_url = "https://example.com"
+generate_feeds = true
+feed_filename = "feed/index.xml"
+sitemap_filename = "sitemap_index.xml"
+
+[languages.fr]
+generate_feeds = true
+feed_filenames = ["atom.xml", "rss.xml"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.feed_url("en").unwrap(), "https://example.com/feed/index.xml");
+        assert_eq!(config.feed_url("fr").unwrap(), "https://example.com/fr/feed/index.xml");
+        assert_eq!(config.feed_output_filename("fr", "rss.xml"), "rss.xml");
+        assert_eq!(
+            config.make_permalink("sitemap_index.xml"),
+            "https://example.com/sitemap_index.xml"
+        );
+
+        for filename in ["/feed.xml", "feed/", "../feed.xml", "a//b.xml", ""] {
+            let res = Config::parse(&format!(
+                "base_url = \"https://example.com\"\nfeed_filename = \"{}\"",
+                filename
+            ));
+            assert!(res.is_err(), "{}", filename);
+        }
+        assert!(
+            Config::parse("base_url = \"https://example.com\"\nsitemap_filename = \"..\"").is_err()
+        );
+    }
+
+    #[test]
+    fn can_make_asset_permalinks() {
+        let mut config = Config::parse(
+            "base_url = \"https://example.com\"\nasset_base_url = \"https://cdn.example.com\"",
+        )
+        .unwrap();
+        assert_eq!(
+            config.make_asset_permalink("/css/app.css"),
+            "https://cdn.example.com/css/app.css"
+        );
+        assert_eq!(config.make_asset_permalink("img.png"), "https://cdn.example.com/img.png");
+        assert_eq!(
+            config.make_asset_permalink("https://other.com/img.png"),
+            "https://other.com/img.png"
+        );
+        assert_eq!(
+            config.to_asset_permalink("https://example.com/blog/post/img.png"),
+            "https://cdn.example.com/blog/post/img.png"
+        );
+        assert_eq!(config.make_permalink("blog"), "https://example.com/blog/");
+
+        // Everything is served locally by `zola serve`
+        config.enable_serve_mode();
+        assert_eq!(config.make_asset_permalink("/css/app.css"), "https://example.com/css/app.css");
+        assert_eq!(
+            config.to_asset_permalink("https://example.com/blog/post/img.png"),
+            "https://example.com/blog/post/img.png"
+        );
+    }
+
+    #[test]
+    fn errors_on_invalid_asset_base_url() {
+        let parse = |base_url: &str, asset_base_url: &str| {
+            Config::parse(&format!(
+                "base_url = \"{}\"\nasset_base_url = \"{}\"",
+                base_url, asset_base_url
+            ))
+        };
+        assert!(parse("https://example.com", "https://cdn.example.com").is_ok());
+        assert!(parse("https://example.com/", "https://cdn.example.com/assets/").is_ok());
+        assert!(parse("https://example.com/blog", "https://example.com/blog").is_ok());
+        assert!(parse("https://example.com/blog", "https://example.com/assets").is_ok());
+
+        for (base_url, asset_base_url) in [
+            ("https://example.com", "cdn.example.com"),
+            ("https://example.com", "https://cdn.example.com?v=1"),
+            ("https://example.com", "https://cdn.example.com/"),
+            ("https://example.com/", "https://cdn.example.com"),
+            ("https://example.com", "https://example.com/assets"),
+            ("https://example.com/blog", "https://example.com/blog/assets"),
+        ] {
+            assert!(parse(base_url, asset_base_url).is_err(), "{} {}", base_url, asset_base_url);
+        }
+    }
+
     #[test]
     fn parse_generate_sitemap_true() {
         let config = r#"
@@ -1057,6 +1908,18 @@ generate_robots_txt = false
         assert!(!config.generate_robots_txt);
     }
 
+    #[test]
+    fn can_set_date_format() {
+        let config = r#"
+title = "My Site"
+base_url = "example.com"
+date_format = "%B %e, %Y"
+"#;
+        let config = Config::parse(config).unwrap();
+        assert_eq!(config.date_format, "%B %e, %Y");
+        assert_eq!(Config::default().date_format, "%Y-%m-%d");
+    }
+
     #[test]
     fn default_no_robots_true() {
         let config = r#"
@@ -1066,4 +1929,100 @@ base_url = "example.com"
         let config = Config::parse(config).unwrap();
         assert!(config.generate_robots_txt);
     }
+
+    #[test]
+    fn can_get_the_offset_of_the_default_timezone() {
+        use libs::time::{Date, Month, Time};
+        let at = |month: Month| {
+            PrimitiveDateTime::new(
+                Date::from_calendar_date(2018, month, 1).unwrap(),
+                Time::from_hms(9, 0, 0).unwrap(),
+            )
+        };
+        let offset = |hours: i8| UtcOffset::from_hms(hours, 0, 0).unwrap();
+
+        let config = Config::parse("base_url = \"https://example.com\"").unwrap();
+        assert_eq!(config.default_offset_at(at(Month::June)), None);
+
+        let config =
+            Config::parse("base_url = \"https://example.com\"\ndefault_timezone = \"+02:00\"")
+                .unwrap();
+        assert_eq!(config.default_offset_at(at(Month::January)), Some(offset(2)));
+
+        let config = Config::parse(
+            "base_url = \"https://example.com\"\ndefault_timezone = \"Europe/Paris\"",
+        )
+        .unwrap();
+        assert_eq!(config.default_offset_at(at(Month::January)), Some(offset(1)));
+        assert_eq!(config.default_offset_at(at(Month::June)), Some(offset(2)));
+
+        let config =
+            Config::parse("base_url = \"https://example.com\"\ndefault_timezone = \"Paris\"");
+        assert!(config.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn errors_on_missing_external_converters() {
+        let config = r#"
+base_url = "https://example.com"
+
+[markup.external]
+adoc = "sh -c asciidoctor"
+rst = "zola-missing-rst-converter {file}"
+        "#;
+        let mut config = Config::parse(config).unwrap();
+        let site_dir = Path::new("/site");
+        assert_eq!(
+            config.markup.external_command(Path::new("a/b.adoc")),
+            Some("sh -c asciidoctor")
+        );
+        assert!(config.markup.is_content_file(Path::new("b.rst")));
+        assert!(!config.markup.is_content_file(Path::new("b.txt")));
+        let err = config.markup.init_external_converters(site_dir).unwrap_err();
+        assert!(err.to_string().contains("zola-missing-rst-converter"));
+
+        config.markup.external.remove("rst");
+        config.markup.external.insert("txt".to_string(), "./convert.sh".to_string());
+        let err = config.markup.init_external_converters(site_dir).unwrap_err();
+        assert!(err.to_string().contains("./convert.sh"));
+        config.markup.external.remove("txt");
+        assert!(config.markup.init_external_converters(site_dir).is_ok());
+    }
+
+    #[test]
+    fn can_parse_redirects() {
+        let config = r#"
+base_url = "https://example.com"
+redirect_output = ["netlify", "vercel"]
+alias_html = false
+
+[[redirects]]
+from = "/old-blog/"
+to = "@/blog/_index.md"
+
+[[redirects]]
+from = "/chat"
+to = "https://chat.example.com"
+status = 302
+        "#;
+        let config = Config::parse(config).unwrap();
+        assert_eq!(
+            config.redirect_output,
+            vec![redirects::RedirectOutput::Netlify, redirects::RedirectOutput::Vercel]
+        );
+        assert_eq!(config.redirects[0].status, 301);
+        assert_eq!(config.redirects[1].status, 302);
+
+        let base = "base_url = \"https://example.com\"\n";
+        assert!(Config::parse(&format!("{}alias_html = false", base)).is_err());
+        let invalid = [
+            "[[redirects]]\nfrom = \"old\"\nto = \"/new/\"",
+            "[[redirects]]\nfrom = \"/old\"",
+            "[[redirects]]\nfrom = \"/old\"\nto = \"/new/\"\nstatus = 200",
+        ];
+        for redirect in invalid {
+            assert!(Config::parse(&format!("{}{}", base, redirect)).is_err(), "{}", redirect);
+        }
+    }
 }
@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+/// An additional file rendered for each page, next to its `index.html`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputFormat {
+    /// The extension of the file, `json` will write an `index.json`
+    pub extension: String,
+    /// The template rendered with the same context as the page template
+    pub template: String,
+}
+
+impl OutputFormat {
+    /// Checks that the formats can all be written
+    pub fn validate_all(formats: &[OutputFormat]) -> Result<()> {
+        let mut extensions = HashSet::new();
+        for format in formats {
+            let extension = format.extension.as_str();
+            if extension.is_empty() || extension.contains(['/', '\\', '.']) {
+                bail!("`page_output_formats` has an invalid extension `{}`", extension);
+            }
+            if extension == "html" {
+                bail!("`page_output_formats` cannot have a `html` format, it is always rendered");
+            }
+            if !extensions.insert(extension) {
+                bail!(
+                    "`page_output_formats` has several formats with the `{}` extension",
+                    extension
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(extension: &str) -> OutputFormat {
+        OutputFormat { extension: extension.to_string(), template: "page.txt".to_string() }
+    }
+
+    #[test]
+    fn can_validate_output_formats() {
+        assert!(OutputFormat::validate_all(&[format("json"), format("gmi")]).is_ok());
+        assert!(OutputFormat::validate_all(&[format("")]).is_err());
+        assert!(OutputFormat::validate_all(&[format(".json")]).is_err());
+        assert!(OutputFormat::validate_all(&[format("html")]).is_err());
+        assert!(OutputFormat::validate_all(&[format("json"), format("json")]).is_err());
+    }
+}
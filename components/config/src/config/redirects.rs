@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+/// The hosts whose redirect rules file can be generated
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedirectOutput {
+    /// A `_redirects` file, also read by Cloudflare Pages
+    Netlify,
+    /// The `redirects` of a `vercel.json` file
+    Vercel,
+}
+
+/// A redirect given in `[[redirects]]`, on top of the aliases of the pages and sections
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Redirect {
+    /// The path redirected, from the root of the site, eg `/old-blog/`
+    pub from: String,
+    /// Where it redirects to: a path of the site, an internal link like `@/blog/_index.md` or an
+    /// absolute URL
+    pub to: String,
+    /// The HTTP status of the redirect. Defaults to 301
+    pub status: u16,
+}
+
+impl Default for Redirect {
+    fn default() -> Self {
+        Self { from: String::new(), to: String::new(), status: 301 }
+    }
+}
+
+impl Redirect {
+    pub fn validate_all(redirects: &[Redirect]) -> Result<()> {
+        for redirect in redirects {
+            if !redirect.from.starts_with('/') {
+                bail!("The `from` of a redirect needs to start with `/`, got `{}`", redirect.from);
+            }
+            if redirect.to.is_empty() {
+                bail!("The redirect from `{}` needs a `to`", redirect.from);
+            }
+            if ![301, 302, 303, 307, 308].contains(&redirect.status) {
+                bail!(
+                    "The status of the redirect from `{}` should be one of 301, 302, 303, 307 or 308, got {}",
+                    redirect.from,
+                    redirect.status
+                );
+            }
+        }
+        Ok(())
+    }
+}
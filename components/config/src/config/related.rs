@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+use crate::config::languages::LanguageOptions;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RelatedPages {
+    /// Whether to find the related pages of every page, defaults to true
+    pub enabled: bool,
+    /// How many related pages to keep for each page, defaults to 5
+    pub limit: usize,
+    /// How much a shared term counts for each taxonomy, by taxonomy name.
+    /// The taxonomies not in there count for 1.
+    pub weights: HashMap<String, usize>,
+}
+
+impl RelatedPages {
+    pub fn weight(&self, taxonomy: &str) -> usize {
+        self.weights.get(taxonomy).copied().unwrap_or(1)
+    }
+
+    pub fn validate(&self, languages: &HashMap<String, LanguageOptions>) -> Result<()> {
+        for name in self.weights.keys() {
+            if !languages.values().any(|l| l.taxonomies.iter().any(|t| &t.name == name)) {
+                bail!("`related_pages.weights` has a weight for the taxonomy `{}` which is not defined in config.toml", name);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for RelatedPages {
+    fn default() -> Self {
+        Self { enabled: true, limit: 5, weights: HashMap::new() }
+    }
+}
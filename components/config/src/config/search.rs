@@ -26,7 +26,7 @@ impl IndexFormat {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Search {
     /// Include the title of the page in the search index. `true` by default.
@@ -45,6 +45,28 @@ pub struct Search {
     pub include_path: bool,
     /// Foramt of the search index to be produced. 'elasticlunr_javascript' by default.
     pub index_format: IndexFormat,
+    /// The code of the language whose stemmer and stop words are used by the elasticlunr index.
+    /// Defaults to the language of the content.
+    pub language: Option<String>,
+    /// Replaces the stop words of the language, the words left out of the elasticlunr index
+    pub stop_words: Option<Vec<String>>,
+    /// How much more matches in the title count than the other fields. `1.0` by default.
+    pub title_boost: f64,
+    /// How much more matches in the description count than the other fields. `1.0` by default.
+    pub description_boost: f64,
+    /// How much more matches in the path count than the other fields. `1.0` by default.
+    pub path_boost: f64,
+    /// How much more matches in the content count than the other fields. `1.0` by default.
+    pub body_boost: f64,
+    /// Generate an `opensearch.xml` to search the site from the address bar of the browsers.
+    /// `false` by default.
+    pub generate_opensearch: bool,
+    /// The URL of the search page in `opensearch.xml`, where `{searchTerms}` is replaced by the search.
+    /// Relative to the `base_url` unless it is a full URL. `/search/?q={searchTerms}` by default.
+    pub opensearch_url: String,
+    /// Generate a `search_corpus.txt` with a line per page and section: its permalink, title and
+    /// content as plain text, separated by tabs. `false` by default.
+    pub generate_corpus: bool,
 }
 
 impl Default for Search {
@@ -57,11 +79,33 @@ impl Default for Search {
             include_date: false,
             truncate_content_length: None,
             index_format: Default::default(),
+            language: None,
+            stop_words: None,
+            title_boost: 1.0,
+            description_boost: 1.0,
+            path_boost: 1.0,
+            body_boost: 1.0,
+            generate_opensearch: false,
+            opensearch_url: "/search/?q={searchTerms}".to_string(),
+            generate_corpus: false,
         }
     }
 }
 
 impl Search {
+    /// The full URL of the search page of `opensearch.xml`
+    pub fn opensearch_url(&self, base_url: &str) -> String {
+        if self.opensearch_url.contains("://") {
+            self.opensearch_url.clone()
+        } else {
+            format!(
+                "{}/{}",
+                base_url.trim_end_matches('/'),
+                self.opensearch_url.trim_start_matches('/')
+            )
+        }
+    }
+
     pub fn serialize(&self) -> SerializedSearch {
         SerializedSearch { index_format: &self.index_format }
     }
@@ -71,3 +115,19 @@ impl Search {
 pub struct SerializedSearch<'a> {
     pub index_format: &'a IndexFormat,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_get_opensearch_url() {
+        let mut search = Search::default();
+        assert_eq!(
+            search.opensearch_url("https://example.com/"),
+            "https://example.com/search/?q={searchTerms}"
+        );
+        search.opensearch_url = "https://duckduckgo.com/?q={searchTerms}+site:example.com".into();
+        assert_eq!(search.opensearch_url("https://example.com"), search.opensearch_url);
+    }
+}
@@ -0,0 +1,496 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+use libs::once_cell::sync::Lazy;
+use libs::regex::Regex;
+use libs::serde_json::{Map, Value};
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Context, Result};
+use utils::fs::read_file;
+
+pub const EXTRA_SCHEMA_FILENAME: &str = "extra_schema.toml";
+
+static DATETIME_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\d{4}-\d{2}-\d{2}([T ]\d{2}:\d{2}(:\d{2}(\.\d+)?)?(Z|[+-]\d{2}:\d{2})?)?$")
+        .unwrap()
+});
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    Any,
+    String,
+    Integer,
+    /// Integers are accepted as well
+    Float,
+    Boolean,
+    /// A TOML date or datetime, or a string in that format
+    Datetime,
+    Array,
+    Table,
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FieldType::Any => "anything",
+            FieldType::String => "a string",
+            FieldType::Integer => "an integer",
+            FieldType::Float => "a float",
+            FieldType::Boolean => "a boolean",
+            FieldType::Datetime => "a datetime",
+            FieldType::Array => "an array",
+            FieldType::Table => "a table",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+fn describe_value(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(n) if n.is_f64() => "a float",
+        Value::Number(_) => "an integer",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "a table",
+    }
+}
+
+/// What a key of `extra` should look like.
+/// Everything is optional so a schema can only change some settings of the one it is merged into.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldSchema {
+    /// Defaults to `table` if `fields` is set and `any` otherwise
+    #[serde(rename = "type")]
+    pub kind: Option<FieldType>,
+    /// Defaults to whether any of its `fields` is required
+    pub required: Option<bool>,
+    /// The only values allowed
+    #[serde(rename = "enum")]
+    pub allowed: Option<Vec<Value>>,
+    /// The schema of the elements of an array
+    pub items: Option<Box<FieldSchema>>,
+    /// The schema of the keys of a table
+    #[serde(default)]
+    pub fields: BTreeMap<String, FieldSchema>,
+}
+
+impl FieldSchema {
+    fn kind(&self) -> FieldType {
+        match self.kind {
+            Some(kind) => kind,
+            None if !self.fields.is_empty() => FieldType::Table,
+            None => FieldType::Any,
+        }
+    }
+
+    fn is_required(&self) -> bool {
+        self.required.unwrap_or_else(|| self.fields.values().any(|f| f.is_required()))
+    }
+
+    /// Settings of `other` win over the ones of `self`, fields are merged recursively
+    fn merge(&mut self, other: FieldSchema) {
+        if other.kind.is_some() {
+            self.kind = other.kind;
+        }
+        if other.required.is_some() {
+            self.required = other.required;
+        }
+        if other.allowed.is_some() {
+            self.allowed = other.allowed;
+        }
+        match (&mut self.items, other.items) {
+            (Some(items), Some(other_items)) => items.merge(*other_items),
+            (items, other_items @ Some(_)) => *items = other_items,
+            _ => (),
+        }
+        merge_fields(&mut self.fields, other.fields);
+    }
+
+    fn validate(&self, path: &str, value: &Value, errors: &mut Vec<String>) {
+        let kind = self.kind();
+        let is_valid_type = match kind {
+            FieldType::Any => true,
+            FieldType::String => value.is_string(),
+            FieldType::Integer => value.is_i64() || value.is_u64(),
+            FieldType::Float => value.is_number(),
+            FieldType::Boolean => value.is_boolean(),
+            FieldType::Datetime => value.as_str().is_some_and(|s| DATETIME_RE.is_match(s)),
+            FieldType::Array => value.is_array(),
+            FieldType::Table => value.is_object(),
+        };
+        if !is_valid_type {
+            errors.push(format!(
+                "`{}` should be {} but is {}",
+                path,
+                kind,
+                match (kind, value) {
+                    (FieldType::Datetime, Value::String(s)) => format!("\"{}\"", s),
+                    _ => describe_value(value).to_string(),
+                }
+            ));
+            return;
+        }
+
+        if let Some(allowed) = &self.allowed {
+            if !allowed.contains(value) {
+                let allowed: Vec<_> = allowed.iter().map(|v| v.to_string()).collect();
+                errors.push(format!(
+                    "`{}` should be one of {} but is {}",
+                    path,
+                    allowed.join(", "),
+                    value
+                ));
+            }
+        }
+
+        match value {
+            Value::Array(values) => {
+                if let Some(items) = &self.items {
+                    for (i, item) in values.iter().enumerate() {
+                        items.validate(&format!("{}[{}]", path, i), item, errors);
+                    }
+                }
+            }
+            Value::Object(table) => validate_fields(&self.fields, path, table, errors),
+            _ => (),
+        }
+    }
+}
+
+fn validate_fields(
+    fields: &BTreeMap<String, FieldSchema>,
+    path: &str,
+    table: &Map<String, Value>,
+    errors: &mut Vec<String>,
+) {
+    for (key, field) in fields {
+        let field_path = format!("{}.{}", path, key);
+        match table.get(key) {
+            Some(value) => field.validate(&field_path, value, errors),
+            None if field.required == Some(true) => {
+                errors.push(format!("`{}` is required", field_path))
+            }
+            // Only required because some of its fields are, report those
+            None if field.is_required() => {
+                validate_fields(&field.fields, &field_path, &Map::new(), errors)
+            }
+            None => (),
+        }
+    }
+}
+
+fn merge_fields(fields: &mut BTreeMap<String, FieldSchema>, other: BTreeMap<String, FieldSchema>) {
+    for (key, field) in other {
+        match fields.get_mut(&key) {
+            Some(existing) => existing.merge(field),
+            None => {
+                fields.insert(key, field);
+            }
+        }
+    }
+}
+
+/// Turns the dotted keys, eg `"author.name" = {type = "string"}`, into nested fields
+fn expand_dotted_keys(fields: BTreeMap<String, FieldSchema>) -> BTreeMap<String, FieldSchema> {
+    fn expand_field(field: &mut FieldSchema) {
+        field.fields = expand_dotted_keys(std::mem::take(&mut field.fields));
+        if let Some(items) = field.items.as_mut() {
+            expand_field(items);
+        }
+    }
+
+    let mut expanded = BTreeMap::new();
+    for (key, mut field) in fields {
+        expand_field(&mut field);
+        let mut parts = key.rsplit('.');
+        let mut field = (parts.next().unwrap().to_string(), field);
+        for parent in parts {
+            let parent_field =
+                FieldSchema { fields: BTreeMap::from([field]), ..Default::default() };
+            field = (parent.to_string(), parent_field);
+        }
+        merge_fields(&mut expanded, BTreeMap::from([field]));
+    }
+    expanded
+}
+
+/// Holds the data from an `extra_schema.toml` file: the expected shape of `config.extra`,
+/// `page.extra` and `section.extra`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExtraSchema {
+    /// The keys of `extra` in the config
+    pub config: BTreeMap<String, FieldSchema>,
+    /// The keys of `extra` in the front matter of all pages
+    pub page: BTreeMap<String, FieldSchema>,
+    /// The keys of `extra` in the front matter of all sections
+    pub section: BTreeMap<String, FieldSchema>,
+    /// Added to `page` for the pages in a section directory, eg `blog`, and its subsections
+    pub pages: HashMap<String, BTreeMap<String, FieldSchema>>,
+    /// Added to `section` for a section directory, eg `blog`, and its subsections
+    pub sections: HashMap<String, BTreeMap<String, FieldSchema>>,
+}
+
+impl ExtraSchema {
+    /// Parses a TOML string to our ExtraSchema struct
+    pub fn parse(content: &str) -> Result<ExtraSchema> {
+        let schema: ExtraSchema = libs::toml::from_str(content)?;
+        let expand_all = |all: HashMap<String, BTreeMap<String, FieldSchema>>| {
+            all.into_iter()
+                .map(|(dir, fields)| {
+                    (dir.trim_matches('/').to_string(), expand_dotted_keys(fields))
+                })
+                .collect()
+        };
+        let schema = ExtraSchema {
+            config: expand_dotted_keys(schema.config),
+            page: expand_dotted_keys(schema.page),
+            section: expand_dotted_keys(schema.section),
+            pages: expand_all(schema.pages),
+            sections: expand_all(schema.sections),
+        };
+        check_enums(&schema)?;
+        Ok(schema)
+    }
+
+    /// Parses an `extra_schema.toml` file from the given path
+    pub fn from_file(path: &Path) -> Result<ExtraSchema> {
+        let content = read_file(path)?;
+        ExtraSchema::parse(&content)
+            .with_context(|| format!("Failed to parse the extra schema {}", path.display()))
+    }
+
+    /// Loads the schema of the theme if there is one, then the one of the site on top of it.
+    /// Returns `None` if neither exists.
+    pub fn load(base_path: &Path, theme: Option<&str>) -> Result<Option<ExtraSchema>> {
+        let mut schema = None;
+        let mut paths = Vec::new();
+        if let Some(theme) = theme {
+            paths.push(base_path.join("themes").join(theme).join(EXTRA_SCHEMA_FILENAME));
+        }
+        paths.push(base_path.join(EXTRA_SCHEMA_FILENAME));
+
+        for path in paths.into_iter().filter(|p| p.exists()) {
+            let other = ExtraSchema::from_file(&path)?;
+            match schema.as_mut() {
+                None => schema = Some(other),
+                Some(s) => s.merge(other),
+            }
+        }
+        Ok(schema)
+    }
+
+    /// Adds the fields of `other` to this schema, the fields of both being merged recursively.
+    /// The settings of `other` win when both set them.
+    pub fn merge(&mut self, other: ExtraSchema) {
+        merge_fields(&mut self.config, other.config);
+        merge_fields(&mut self.page, other.page);
+        merge_fields(&mut self.section, other.section);
+        for (dir, fields) in other.pages {
+            merge_fields(self.pages.entry(dir).or_default(), fields);
+        }
+        for (dir, fields) in other.sections {
+            merge_fields(self.sections.entry(dir).or_default(), fields);
+        }
+    }
+
+    /// The fields of `base` with the ones for the sections containing the `components`
+    /// directories, the most specific ones winning
+    fn fields_for(
+        base: &BTreeMap<String, FieldSchema>,
+        by_section: &HashMap<String, BTreeMap<String, FieldSchema>>,
+        components: &[String],
+    ) -> BTreeMap<String, FieldSchema> {
+        let mut fields = base.clone();
+        for depth in 0..=components.len() {
+            if let Some(section_fields) = by_section.get(&components[..depth].join("/")) {
+                merge_fields(&mut fields, section_fields.clone());
+            }
+        }
+        fields
+    }
+
+    /// Returns a message for every problem found in `config.extra`
+    pub fn validate_config(&self, extra: &Map<String, Value>) -> Vec<String> {
+        let mut errors = Vec::new();
+        validate_fields(&self.config, "extra", extra, &mut errors);
+        errors
+    }
+
+    /// Returns a message for every problem found in the `extra` of a page in the section
+    /// at `components`, eg `["blog", "2024"]` for the pages in `content/blog/2024`
+    pub fn validate_page(&self, components: &[String], extra: &Map<String, Value>) -> Vec<String> {
+        let mut errors = Vec::new();
+        let fields = ExtraSchema::fields_for(&self.page, &self.pages, components);
+        validate_fields(&fields, "extra", extra, &mut errors);
+        errors
+    }
+
+    /// Returns a message for every problem found in the `extra` of the section at `components`
+    pub fn validate_section(
+        &self,
+        components: &[String],
+        extra: &Map<String, Value>,
+    ) -> Vec<String> {
+        let mut errors = Vec::new();
+        let fields = ExtraSchema::fields_for(&self.section, &self.sections, components);
+        validate_fields(&fields, "extra", extra, &mut errors);
+        errors
+    }
+}
+
+/// Checks that the `enum` values of a schema have the right type, to catch typos early
+fn check_enums(schema: &ExtraSchema) -> Result<()> {
+    fn check_field(path: &str, field: &FieldSchema) -> Result<()> {
+        if let Some(allowed) = &field.allowed {
+            let mut errors = Vec::new();
+            let without_enum = FieldSchema { allowed: None, ..field.clone() };
+            for value in allowed {
+                without_enum.validate(path, value, &mut errors);
+            }
+            if let Some(error) = errors.first() {
+                bail!("Invalid `enum` in the extra schema: {}", error);
+            }
+        }
+        for (key, child) in &field.fields {
+            check_field(&format!("{}.{}", path, key), child)?;
+        }
+        if let Some(items) = &field.items {
+            check_field(&format!("{}[]", path), items)?;
+        }
+        Ok(())
+    }
+
+    let all_fields = [&schema.config, &schema.page, &schema.section]
+        .into_iter()
+        .chain(schema.pages.values())
+        .chain(schema.sections.values());
+    for fields in all_fields {
+        for (key, field) in fields {
+            check_field(&format!("extra.{}", key), field)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libs::serde_json::json;
+
+    fn as_map(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(m) => m,
+            _ => unreachable!(),
+        }
+    }
+
+    const SCHEMA: &str = r#"
+[config]
+"author.name" = { type = "string", required = true }
+"author.email" = { type = "string" }
+theme_color = { type = "string", enum = ["light", "dark"] }
+social = { type = "array", items = { type = "table", fields = { name = { type = "string", required = true }, url = { type = "string", required = true } } } }
+
+[page]
+image = { type = "string" }
+
+[pages.blog]
+published = { type = "datetime", required = true }
+"#;
+
+    #[test]
+    fn can_validate_config_extra() {
+        let schema = ExtraSchema::parse(SCHEMA).unwrap();
+        let valid = json!({
+            "author": {"name": "Jane"},
+            "theme_color": "dark",
+            "social": [{"name": "Mastodon", "url": "https://example.com/@jane"}],
+            "other": 1,
+        });
+        assert!(schema.validate_config(&as_map(valid)).is_empty());
+
+        let invalid = json!({
+            "theme_color": "blue",
+            "social": [{"name": "Mastodon"}, {"name": 1, "url": "a"}],
+        });
+        assert_eq!(
+            schema.validate_config(&as_map(invalid)),
+            vec![
+                "`extra.author.name` is required",
+                "`extra.social[0].url` is required",
+                "`extra.social[1].name` should be a string but is an integer",
+                "`extra.theme_color` should be one of \"light\", \"dark\" but is \"blue\"",
+            ]
+        );
+
+        let invalid = json!({"author": "Jane"});
+        assert_eq!(
+            schema.validate_config(&as_map(invalid)),
+            vec!["`extra.author` should be a table but is a string"]
+        );
+    }
+
+    #[test]
+    fn can_validate_page_extra_per_section() {
+        let schema = ExtraSchema::parse(SCHEMA).unwrap();
+        let blog = vec!["blog".to_string()];
+        let nested = vec!["blog".to_string(), "2024".to_string()];
+
+        assert!(schema.validate_page(&[], &as_map(json!({"image": "a.png"}))).is_empty());
+        assert_eq!(
+            schema.validate_page(&[], &as_map(json!({"image": 1}))),
+            vec!["`extra.image` should be a string but is an integer"]
+        );
+        assert_eq!(schema.validate_page(&blog, &Map::new()), vec!["`extra.published` is required"]);
+        assert_eq!(
+            schema.validate_page(&nested, &as_map(json!({"published": "yesterday"}))),
+            vec!["`extra.published` should be a datetime but is \"yesterday\""]
+        );
+        assert!(schema
+            .validate_page(&nested, &as_map(json!({"published": "2024-01-01T10:00:00Z"})))
+            .is_empty());
+        assert!(schema.validate_section(&blog, &Map::new()).is_empty());
+    }
+
+    #[test]
+    fn site_schema_is_merged_over_theme_one() {
+        let mut schema = ExtraSchema::parse(SCHEMA).unwrap();
+        let site_schema = ExtraSchema::parse(
+            r#"
+[config]
+"author.website" = { type = "string", required = true }
+theme_color = { enum = ["light", "dark", "blue"] }
+"#,
+        )
+        .unwrap();
+        schema.merge(site_schema);
+
+        let author = &schema.config["author"];
+        assert_eq!(author.fields.len(), 3);
+        assert_eq!(author.fields["name"].required, Some(true));
+        assert_eq!(author.fields["website"].required, Some(true));
+        let theme_color = &schema.config["theme_color"];
+        assert_eq!(theme_color.kind, Some(FieldType::String));
+        assert_eq!(theme_color.allowed.as_ref().unwrap().len(), 3);
+
+        let extra = json!({"author": {"name": "Jane"}, "theme_color": "blue"});
+        assert_eq!(
+            schema.validate_config(&as_map(extra)),
+            vec!["`extra.author.website` is required"]
+        );
+    }
+
+    #[test]
+    fn errors_on_invalid_schema() {
+        assert!(ExtraSchema::parse("[config]\na = { type = \"str\" }").is_err());
+        assert!(ExtraSchema::parse("[config]\na = { type = \"string\", requried = true }").is_err());
+        assert!(ExtraSchema::parse("[config]\na = { type = \"integer\", enum = [\"a\"] }").is_err());
+    }
+}
@@ -1,17 +1,29 @@
 mod config;
+pub mod extra_schema;
 pub mod highlighting;
 mod theme;
 
 use std::path::Path;
 
 pub use crate::config::{
+    archives::{ArchiveConfig, ArchiveGrouping},
+    asset_optimization::AssetOptimization,
+    build_info::BuildInfo,
+    check::{A11yCheck, Check},
+    deploy::{Deploy, DeployTarget, GitTarget, RsyncTarget, S3Target},
+    favicon::Favicon,
+    html_validation::{HtmlValidation, HtmlValidationLevel},
     languages::LanguageOptions,
     link_checker::LinkChecker,
     link_checker::LinkCheckerLevel,
+    markup::{AnchorStyle, HighlightingMode},
+    output_formats::OutputFormat,
+    redirects::{Redirect, RedirectOutput},
+    related::RelatedPages,
     search::{IndexFormat, Search},
     slugify::Slugify,
     taxonomies::TaxonomyConfig,
-    Config,
+    Config, MissingContent,
 };
 use errors::Result;
 
@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+
+use errors::{Error, FileErrors, LocatedError};
+use libs::once_cell::sync::Lazy;
+use libs::regex::Regex;
+use libs::serde_json::json;
+use libs::tera;
+
+static TEMPLATE_NAME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:Failed to render|Failed to parse) '([^']+)'").unwrap());
+static TEMPLATE_POSITION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"--> (\d+):(\d+)").unwrap());
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A warning or an error in the machine readable output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: Option<PathBuf>,
+    /// 1-based line and column
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+    /// Where it comes from, eg `front_matter`, `template` or `link`
+    pub kind: String,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, kind: &str, message: &str) -> Self {
+        Diagnostic {
+            severity,
+            file: None,
+            position: None,
+            message: message.to_string(),
+            kind: kind.to_string(),
+        }
+    }
+
+    pub fn in_file(mut self, file: PathBuf) -> Self {
+        self.file = Some(file);
+        self
+    }
+
+    /// Converts an error to diagnostics, using the file/position information we can find in its
+    /// chain. A `FileErrors` results in one diagnostic per file error.
+    pub fn from_error(severity: Severity, error: &Error) -> Vec<Diagnostic> {
+        if let Some(file_errors) = error.chain().find_map(|e| e.downcast_ref::<FileErrors>()) {
+            return file_errors
+                .iter()
+                .flat_map(|(path, e)| {
+                    Diagnostic::from_error(severity, e).into_iter().map(move |mut d| {
+                        d.file.get_or_insert_with(|| path.clone());
+                        if d.kind == "general" {
+                            d.kind = "content".to_string();
+                        }
+                        d
+                    })
+                })
+                .collect();
+        }
+
+        let mut diagnostic = Diagnostic::new(severity, "general", &format!("{:#}", error));
+        if let Some(located) = error.chain().find_map(|e| e.downcast_ref::<LocatedError>()) {
+            diagnostic.kind = located.kind.to_string();
+            diagnostic.file = Some(located.path.clone());
+            diagnostic.position = located.position;
+        } else if error.chain().any(|e| e.downcast_ref::<tera::Error>().is_some()) {
+            diagnostic.kind = "template".to_string();
+            for e in error.chain() {
+                let message = e.to_string();
+                if let Some(caps) = TEMPLATE_NAME_RE.captures(&message) {
+                    diagnostic
+                        .file
+                        .get_or_insert_with(|| PathBuf::from("templates").join(&caps[1]));
+                }
+                if let Some(caps) = TEMPLATE_POSITION_RE.captures(&message) {
+                    diagnostic
+                        .position
+                        .get_or_insert((caps[1].parse().unwrap(), caps[2].parse().unwrap()));
+                }
+            }
+        }
+
+        vec![diagnostic]
+    }
+
+    pub fn to_json(&self) -> String {
+        json!({
+            "severity": self.severity.as_str(),
+            "file": self.file.as_ref().map(|f| f.to_string_lossy()),
+            "line": self.position.map(|p| p.0),
+            "column": self.position.map(|p| p.1),
+            "message": self.message,
+            "kind": self.kind,
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use errors::anyhow;
+
+    #[test]
+    fn can_convert_located_errors() {
+        let err = Error::new(LocatedError {
+            kind: "front_matter",
+            path: PathBuf::from("content/a.md"),
+            position: Some((3, 7)),
+            message: "invalid type".to_string(),
+        })
+        .context("Error when parsing front matter of page `content/a.md`");
+        let diagnostics = Diagnostic::from_error(Severity::Error, &err);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].to_json(),
+            r#"{"severity":"error","file":"content/a.md","line":3,"column":7,"message":"Error when parsing front matter of page `content/a.md`: invalid type","kind":"front_matter"}"#
+        );
+    }
+
+    #[test]
+    fn can_convert_file_errors() {
+        let mut errors = FileErrors::default();
+        errors.push("content/b.md", anyhow!("Dead link"));
+        errors.push("content/a.md", anyhow!("oops"));
+        let err = errors.into_result().unwrap_err();
+        let diagnostics = Diagnostic::from_error(Severity::Warning, &err);
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::new(Severity::Warning, "content", "oops")
+                    .in_file(PathBuf::from("content/a.md")),
+                Diagnostic::new(Severity::Warning, "content", "Dead link")
+                    .in_file(PathBuf::from("content/b.md")),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_convert_template_errors() {
+        let err = Error::new(tera::Error::chain(
+            "Failed to render 'page.html'",
+            tera::Error::msg(
+                "Variable `page.extra.cover` not found in context while rendering 'page.html'",
+            ),
+        ));
+        let diagnostics = Diagnostic::from_error(Severity::Error, &err);
+        assert_eq!(diagnostics[0].kind, "template");
+        assert_eq!(diagnostics[0].file, Some(PathBuf::from("templates/page.html")));
+    }
+}
@@ -1,21 +1,66 @@
+mod diagnostics;
+
 use std::env;
 use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use libs::atty;
 use libs::once_cell::sync::Lazy;
 use libs::termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
+pub use diagnostics::{Diagnostic, Severity};
+
 /// Termcolor color choice.
 /// We do not rely on ColorChoice::Auto behavior
 /// as the check is already performed by has_color.
 static COLOR_CHOICE: Lazy<ColorChoice> =
     Lazy::new(|| if has_color() { ColorChoice::Always } else { ColorChoice::Never });
 
+/// Whether warnings and errors are printed as JSON diagnostics rather than for humans
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Switches the output to one JSON object per diagnostic on stdout, for editors and other tools.
+/// The other messages are then printed on stderr so they don't get in the way.
+pub fn enable_json_output() {
+    JSON_OUTPUT.store(true, Ordering::Relaxed);
+}
+
+pub fn is_json_output() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// Whether the debug messages are printed
+static DEBUG_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Prints the messages of `debug`, eg with `zola serve --verbose`
+pub fn enable_debug_output() {
+    DEBUG_OUTPUT.store(true, Ordering::Relaxed);
+}
+
+/// Prints a dimmed message, only if the debug messages are enabled
+pub fn debug(message: &str) {
+    if DEBUG_OUTPUT.load(Ordering::Relaxed) {
+        colorize(message, ColorSpec::new().set_dimmed(true), progress_stream());
+    }
+}
+
+/// Prints a message as is
+pub fn plain(message: &str) {
+    if is_json_output() {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
 pub fn info(message: &str) {
-    colorize(message, ColorSpec::new().set_bold(true), StandardStream::stdout(*COLOR_CHOICE));
+    colorize(message, ColorSpec::new().set_bold(true), progress_stream());
 }
 
 pub fn warn(message: &str) {
+    if is_json_output() {
+        return emit(&Diagnostic::new(Severity::Warning, "general", message));
+    }
     colorize(
         &format!("{}{}", "Warning: ", message),
         ColorSpec::new().set_bold(true).set_fg(Some(Color::Yellow)),
@@ -27,11 +72,14 @@ pub fn success(message: &str) {
     colorize(
         message,
         ColorSpec::new().set_bold(true).set_fg(Some(Color::Green)),
-        StandardStream::stdout(*COLOR_CHOICE),
+        progress_stream(),
     );
 }
 
 pub fn error(message: &str) {
+    if is_json_output() {
+        return emit(&Diagnostic::new(Severity::Error, "general", message));
+    }
     colorize(
         &format!("{}{}", "Error: ", message),
         ColorSpec::new().set_bold(true).set_fg(Some(Color::Red)),
@@ -39,6 +87,25 @@ pub fn error(message: &str) {
     );
 }
 
+/// Prints a diagnostic as a line of JSON on stdout
+pub fn emit(diagnostic: &Diagnostic) {
+    emit_json(&diagnostic.to_json());
+}
+
+/// Prints a line of JSON on stdout, for the machine readable messages that are not diagnostics
+pub fn emit_json(json: &str) {
+    println!("{}", json);
+}
+
+/// The stream for the messages that are not diagnostics
+fn progress_stream() -> StandardStream {
+    if is_json_output() {
+        StandardStream::stderr(*COLOR_CHOICE)
+    } else {
+        StandardStream::stdout(*COLOR_CHOICE)
+    }
+}
+
 /// Print a colorized message to stdout
 fn colorize(message: &str, color: &ColorSpec, mut stream: StandardStream) {
     stream.set_color(color).unwrap();
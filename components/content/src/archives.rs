@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use config::{ArchiveConfig, ArchiveGrouping, Config};
+use errors::{Context as ErrorContext, Result};
+use libs::tera::{Context, Tera, Value};
+use utils::templates::render_template;
+
+use crate::library::Library;
+use crate::ser::SerializingPage;
+
+/// The pages of a year or month of an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveGroup {
+    pub year: i32,
+    /// Only set when the archive is grouped by month
+    pub month: Option<u8>,
+    pub path: String,
+    pub permalink: String,
+    /// The newest first
+    pub pages: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SerializedArchiveGroup<'a> {
+    year: i32,
+    month: Option<u8>,
+    path: &'a str,
+    permalink: &'a str,
+    pages: Vec<SerializingPage<'a>>,
+    page_count: usize,
+}
+
+impl<'a> SerializedArchiveGroup<'a> {
+    pub fn from_group(group: &'a ArchiveGroup, library: &'a Library, include_pages: bool) -> Self {
+        let pages = if include_pages {
+            group
+                .pages
+                .iter()
+                .map(|p| SerializingPage::for_listing(&library.pages[p], library, false))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        SerializedArchiveGroup {
+            year: group.year,
+            month: group.month,
+            path: &group.path,
+            permalink: &group.permalink,
+            pages,
+            page_count: group.pages.len(),
+        }
+    }
+}
+
+/// The archive pages of a section in a language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Archive {
+    pub kind: ArchiveConfig,
+    pub lang: String,
+    pub path: String,
+    pub permalink: String,
+    /// The newest first
+    pub groups: Vec<ArchiveGroup>,
+}
+
+impl Archive {
+    /// Groups the dated pages of the sections of the `archives` config, in every language where
+    /// the section exists. Also returns the pages left out because they have no date
+    pub fn find_all(library: &Library, config: &Config) -> (Vec<Archive>, Vec<PathBuf>) {
+        let mut archives = Vec::new();
+        let mut undated = Vec::new();
+
+        for kind in &config.archives {
+            let components = kind.section_components();
+            let mut languages: Vec<_> = library
+                .sections
+                .values()
+                .filter(|s| s.file.components == components)
+                .map(|s| s.lang.as_str())
+                .collect();
+            languages.sort_unstable();
+
+            for lang in languages {
+                let mut groups: BTreeMap<(i32, Option<u8>), Vec<_>> = BTreeMap::new();
+                let pages = library.pages.values().filter(|p| {
+                    p.lang == lang
+                        && !p.meta.unlisted
+                        && p.file.components.len() >= components.len()
+                        && p.file.components.iter().zip(&components).all(|(a, b)| a == b)
+                });
+                for page in pages {
+                    let Some(datetime) = page.meta.datetime else {
+                        undated.push(page.file.path.clone());
+                        continue;
+                    };
+                    let month = match kind.by {
+                        ArchiveGrouping::Year => None,
+                        ArchiveGrouping::Month => Some(u8::from(datetime.month())),
+                    };
+                    groups.entry((datetime.year(), month)).or_default().push(page);
+                }
+
+                let path = if lang != config.default_language {
+                    format!("/{}/{}/", lang, kind.path.trim_matches('/'))
+                } else {
+                    format!("/{}/", kind.path.trim_matches('/'))
+                };
+                let groups = groups
+                    .into_iter()
+                    .rev()
+                    .map(|((year, month), mut pages)| {
+                        pages.sort_by(|a, b| {
+                            b.meta
+                                .datetime
+                                .cmp(&a.meta.datetime)
+                                .then_with(|| crate::tie_break(a, b))
+                        });
+                        let path = match month {
+                            Some(month) => format!("{}{}/{:02}/", path, year, month),
+                            None => format!("{}{}/", path, year),
+                        };
+                        ArchiveGroup {
+                            year,
+                            month,
+                            permalink: config.make_permalink(&path),
+                            path,
+                            pages: pages.into_iter().map(|p| p.file.path.clone()).collect(),
+                        }
+                    })
+                    .collect();
+
+                archives.push(Archive {
+                    kind: kind.clone(),
+                    lang: lang.to_string(),
+                    permalink: config.make_permalink(&path),
+                    path,
+                    groups,
+                });
+            }
+        }
+        undated.sort();
+        undated.dedup();
+
+        (archives, undated)
+    }
+
+    /// The components of the path of the archive, from the root of the output directory
+    pub fn components(&self) -> Vec<&str> {
+        self.path.split('/').filter(|c| !c.is_empty()).collect()
+    }
+
+    fn base_context(&self, config: &Config, data: &Value, library: &Library) -> Context {
+        let mut context = config.template_context(&self.lang, data);
+        context.insert("site", &library.site_index);
+        context.insert("archive_path", &self.path);
+        context.insert("archive_permalink", &self.permalink);
+        context
+    }
+
+    /// Renders the page of a year or month, with its `year`, `month` and `pages`
+    pub fn render_group(
+        &self,
+        group: &ArchiveGroup,
+        tera: &Tera,
+        config: &Config,
+        data: &Value,
+        library: &Library,
+    ) -> Result<String> {
+        let serialized = SerializedArchiveGroup::from_group(group, library, true);
+        let mut context = self.base_context(config, data, library);
+        context.insert("year", &group.year);
+        context.insert("month", &group.month);
+        context.insert("pages", &serialized.pages);
+        context.insert("current_url", &group.permalink);
+        context.insert("current_path", &group.path);
+
+        render_template(&self.kind.template, tera, context, &config.theme)
+            .with_context(|| format!("Failed to render the archive page {}", group.path))
+    }
+
+    /// Renders the page listing the years or months of the archive, as `groups`
+    pub fn render_index(
+        &self,
+        tera: &Tera,
+        config: &Config,
+        data: &Value,
+        library: &Library,
+    ) -> Result<String> {
+        let groups: Vec<_> = self
+            .groups
+            .iter()
+            .map(|g| SerializedArchiveGroup::from_group(g, library, false))
+            .collect();
+        let mut context = self.base_context(config, data, library);
+        context.insert("groups", &groups);
+        context.insert("current_url", &self.permalink);
+        context.insert("current_path", &self.path);
+
+        render_template(&self.kind.index_template, tera, context, &config.theme)
+            .with_context(|| format!("Failed to render the archive index {}", self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Page, Section};
+    use time::macros::datetime;
+    use time::OffsetDateTime;
+
+    fn page(path: &str, components: &[&str], date: Option<OffsetDateTime>) -> Page {
+        let mut page = Page::default();
+        page.file.path = PathBuf::from(path);
+        page.file.components = components.iter().map(|c| c.to_string()).collect();
+        page.lang = "en".to_string();
+        page.meta.datetime = date;
+        page
+    }
+
+    #[test]
+    fn groups_dated_pages_by_year_and_month() {
+        let mut config = Config::default_for_test();
+        config.archives =
+            vec![ArchiveConfig { section: "blog".to_string(), ..ArchiveConfig::default() }];
+        let mut library = Library::new(&config);
+        let mut section = Section::default();
+        section.file.path = PathBuf::from("content/blog/_index.md");
+        section.file.components = vec!["blog".to_string()];
+        section.lang = "en".to_string();
+        library.insert_section(section);
+        for p in [
+            page("content/blog/a.md", &["blog"], Some(datetime!(2018-03-01 0:00 UTC))),
+            page("content/blog/old/b.md", &["blog", "old"], Some(datetime!(2017-12-01 0:00 UTC))),
+            page("content/blog/c.md", &["blog"], Some(datetime!(2018-07-01 0:00 UTC))),
+            page("content/blog/d.md", &["blog"], None),
+            page("content/notes/e.md", &["notes"], Some(datetime!(2018-07-01 0:00 UTC))),
+        ] {
+            library.insert_page(p);
+        }
+
+        let (archives, undated) = Archive::find_all(&library, &config);
+        assert_eq!(undated, vec![PathBuf::from("content/blog/d.md")]);
+        assert_eq!(archives.len(), 1);
+        assert_eq!(archives[0].path, "/archive/");
+        let groups: Vec<_> =
+            archives[0].groups.iter().map(|g| (g.path.as_str(), g.pages.len())).collect();
+        assert_eq!(groups, vec![("/archive/2018/", 2), ("/archive/2017/", 1)]);
+        assert_eq!(archives[0].groups[0].pages[0], PathBuf::from("content/blog/c.md"));
+
+        config.archives[0].by = ArchiveGrouping::Month;
+        let (archives, _) = Archive::find_all(&library, &config);
+        let paths: Vec<_> = archives[0].groups.iter().map(|g| g.path.as_str()).collect();
+        assert_eq!(paths, vec!["/archive/2018/07/", "/archive/2018/03/", "/archive/2017/12/"]);
+        assert_eq!(archives[0].groups[0].month, Some(7));
+    }
+}
@@ -58,13 +58,15 @@ impl FileInfo {
         let file_path = path.to_path_buf();
         let mut parent = file_path.parent().expect("Get parent of page").to_path_buf();
         let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        // Pages converted by `[markup.external]` keep their own extension
+        let extension = path.extension().map_or("md".into(), |e| e.to_string_lossy());
         let canonical = parent.join(&name);
         let mut components =
             find_content_components(file_path.strip_prefix(base_path).unwrap_or(&file_path));
         let relative = if !components.is_empty() {
-            format!("{}/{}.md", components.join("/"), name)
+            format!("{}/{}.{}", components.join("/"), name, extension)
         } else {
-            format!("{}.md", name)
+            format!("{}.{}", name, extension)
         };
         let mut colocated_path = None;
 
@@ -2,6 +2,6 @@ mod page;
 mod section;
 mod split;
 
-pub use page::PageFrontMatter;
+pub use page::{parse_datetime, PageFrontMatter, Protected};
 pub use section::SectionFrontMatter;
-pub use split::{split_page_content, split_section_content};
+pub use split::{split_page_content, split_section_content, strip_front_matter};
@@ -4,10 +4,11 @@ use libs::tera::{Map, Value};
 use serde::Deserialize;
 use time::format_description::well_known::Rfc3339;
 use time::macros::{format_description, time};
-use time::{Date, OffsetDateTime, PrimitiveDateTime};
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
 
+use config::Config;
 use errors::{bail, Result};
-use utils::de::{fix_toml_dates, from_unknown_datetime};
+use utils::de::{fix_toml_dates, from_unknown_datetime, from_unknown_time};
 
 use crate::front_matter::split::RawFrontMatter;
 
@@ -31,6 +32,9 @@ pub struct PageFrontMatter {
     /// Date if we want to order pages (ie blog post)
     #[serde(default, deserialize_with = "from_unknown_datetime")]
     pub date: Option<String>,
+    /// The time of the day of `date`, when it is a date only, eg `08:30` or `08:30:00`
+    #[serde(default, deserialize_with = "from_unknown_time")]
+    pub time: Option<String>,
     /// Datetime content was created
     #[serde(default, skip_deserializing)]
     pub datetime: Option<OffsetDateTime>,
@@ -66,17 +70,59 @@ pub struct PageFrontMatter {
     /// Defaults to `true` but is only used if search if explicitly enabled in the config.
     #[serde(skip_serializing)]
     pub in_search_index: bool,
+    /// Whether the page is meant to not be linked from anywhere, in which case
+    /// it is not reported as an orphan page
+    #[serde(skip_serializing)]
+    pub orphan: bool,
+    /// Whether the page is only reachable by its permalink: it is rendered but left out of
+    /// the sections, taxonomies, feeds, sitemap and search index
+    #[serde(skip_serializing)]
+    pub unlisted: bool,
+    /// The reading time of the page in minutes, instead of the one computed from its word count
+    #[serde(skip_serializing)]
+    pub reading_time: Option<usize>,
+    /// The extensions of the `page_output_formats` not to render for that page
+    #[serde(skip_serializing)]
+    pub skip_output_formats: Vec<String>,
+    /// Overrides `markdown.heading_offset` of the config for that page
+    #[serde(skip_serializing)]
+    pub heading_offset: Option<u8>,
+    /// Overrides `markdown.highlight_code` of the config for that page
+    #[serde(skip_serializing)]
+    pub highlight_code: Option<bool>,
+    /// Old heading ids that should keep working, pointing to the current ids: hidden anchors with
+    /// the old ids are added before the headings
+    #[serde(skip_serializing)]
+    pub anchor_aliases: HashMap<String, String>,
+    /// Makes the page only readable with a password: its content is encrypted at build time
+    #[serde(skip_serializing)]
+    pub protected: Option<Protected>,
+    /// Where the original of the page is, like for a syndicated post: an absolute URL or an
+    /// internal link resolved when rendering the markdown
+    pub canonical_url: Option<String>,
+    /// The `<meta name="robots">` directives of the page, like `noindex, nofollow`.
+    /// The `noindex` pages are left out of the sitemap and search index
+    pub robots: Option<String>,
     /// Any extra parameter present in the front matter
     pub extra: Map<String, Value>,
 }
 
+/// The password of a protected page, either given directly or by its name in the `passwords`
+/// of the config
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Protected {
+    pub password: Option<String>,
+    pub name: Option<String>,
+}
+
 /// Parse a string for a datetime coming from one of the supported TOML format
 /// There are three alternatives:
 /// 1. an offset datetime (plain RFC3339)
 /// 2. a local datetime (RFC3339 with timezone omitted)
 /// 3. a local date (YYYY-MM-DD).
 /// This tries each in order.
-fn parse_datetime(d: &str) -> Option<OffsetDateTime> {
+pub fn parse_datetime(d: &str) -> Option<OffsetDateTime> {
     OffsetDateTime::parse(d, &Rfc3339)
         .or_else(|_| OffsetDateTime::parse(format!("{}Z", d).as_ref(), &Rfc3339))
         .or_else(|_| match Date::parse(d, &format_description!("[year]-[month]-[day]")) {
@@ -86,6 +132,16 @@ fn parse_datetime(d: &str) -> Option<OffsetDateTime> {
         .ok()
 }
 
+/// The datetime if it has a time but no offset, like a TOML local datetime
+fn local_datetime(d: &str) -> Option<PrimitiveDateTime> {
+    if OffsetDateTime::parse(d, &Rfc3339).is_ok() {
+        return None;
+    }
+    OffsetDateTime::parse(&format!("{}Z", d), &Rfc3339)
+        .ok()
+        .map(|dt| PrimitiveDateTime::new(dt.date(), dt.time()))
+}
+
 impl PageFrontMatter {
     pub fn parse(raw: &RawFrontMatter) -> Result<PageFrontMatter> {
         let mut f: PageFrontMatter = raw.deserialize()?;
@@ -102,11 +158,36 @@ impl PageFrontMatter {
             }
         }
 
+        if let Some(ref protected) = f.protected {
+            match (&protected.password, &protected.name) {
+                (Some(password), None) if password.is_empty() => {
+                    bail!("`protected.password` can't be empty if present")
+                }
+                (Some(_), None) | (None, Some(_)) => (),
+                _ => bail!("`protected` needs either a `password` or a `name`, but not both"),
+            }
+            // The whole point is to not have the content readable
+            f.in_search_index = false;
+        }
+
+        if let Some(ref url) = f.canonical_url {
+            if !url.starts_with("@/") && !url.contains("://") {
+                bail!(
+                    "`canonical_url` should be an absolute URL or an internal link starting with `@/`, got `{}`",
+                    url
+                );
+            }
+        }
+        if f.is_noindex() {
+            f.in_search_index = false;
+        }
+
         f.extra = match fix_toml_dates(f.extra) {
             Value::Object(o) => o,
             _ => unreachable!("Got something other than a table in page extra"),
         };
 
+        f.merge_date_and_time()?;
         f.date_to_datetime();
 
         for terms in f.taxonomies.values() {
@@ -126,6 +207,53 @@ impl PageFrontMatter {
         Ok(f)
     }
 
+    /// Puts the `time` in the `date` when it is a date only, as a local datetime like a TOML one
+    fn merge_date_and_time(&mut self) -> Result<()> {
+        let time = match self.time.take() {
+            Some(t) => t,
+            None => return Ok(()),
+        };
+        let date = match self.date {
+            Some(ref d) => d,
+            None => bail!("`time` is set to `{}` but there is no `date` to go with it", time),
+        };
+        if Date::parse(date, &format_description!("[year]-[month]-[day]")).is_err() {
+            bail!("`time` is set to `{}` but `date` ({}) isn't a date only", time, date);
+        }
+        let parsed = Time::parse(&time, &format_description!("[hour]:[minute]:[second]"))
+            .or_else(|_| Time::parse(&time, &format_description!("[hour]:[minute]")));
+        match parsed {
+            Ok(t) => {
+                let t = t.format(&format_description!("[hour]:[minute]:[second]")).unwrap();
+                self.date = Some(format!("{}T{}", date, t));
+                Ok(())
+            }
+            Err(_) => bail!(
+                "`time` ({}) of `date` {} could not be parsed, it should be like `08:30` or `08:30:00`",
+                time,
+                date
+            ),
+        }
+    }
+
+    /// Gives the `date` and `updated` that have a time but no offset the one of the
+    /// `default_timezone` of the config, instead of UTC
+    pub fn apply_default_timezone(&mut self, config: &Config) {
+        for date in [&mut self.date, &mut self.updated].into_iter().flatten() {
+            if let Some(local) = local_datetime(date) {
+                if let Some(offset) = config.default_offset_at(local) {
+                    *date = local.assume_offset(offset).format(&Rfc3339).unwrap();
+                }
+            }
+        }
+        self.date_to_datetime();
+    }
+
+    /// Whether `date` or `updated` have a time but no offset, in which case they are in UTC
+    pub fn has_datetime_without_offset(&self) -> bool {
+        [&self.date, &self.updated].iter().any(|d| d.as_deref().and_then(local_datetime).is_some())
+    }
+
     /// Converts the TOML datetime to a time::OffsetDateTime
     /// Also grabs the year/month/day tuple that will be used in serialization
     pub fn date_to_datetime(&mut self) {
@@ -137,6 +265,13 @@ impl PageFrontMatter {
             self.updated_datetime.map(|dt| (dt.year(), dt.month().into(), dt.day()));
     }
 
+    /// Whether the `robots` directives ask search engines not to index the page
+    pub fn is_noindex(&self) -> bool {
+        self.robots.as_deref().is_some_and(|robots| {
+            robots.split(',').any(|d| d.trim().eq_ignore_ascii_case("noindex"))
+        })
+    }
+
     pub fn weight(&self) -> usize {
         self.weight.unwrap()
     }
@@ -152,6 +287,7 @@ impl Default for PageFrontMatter {
             updated_datetime: None,
             updated_datetime_tuple: None,
             date: None,
+            time: None,
             datetime: None,
             datetime_tuple: None,
             draft: false,
@@ -163,6 +299,16 @@ impl Default for PageFrontMatter {
             authors: Vec::new(),
             aliases: Vec::new(),
             template: None,
+            orphan: false,
+            unlisted: false,
+            reading_time: None,
+            skip_output_formats: Vec::new(),
+            heading_offset: None,
+            highlight_code: None,
+            anchor_aliases: HashMap::new(),
+            protected: None,
+            canonical_url: None,
+            robots: None,
             extra: Map::new(),
         }
     }
@@ -170,11 +316,13 @@ impl Default for PageFrontMatter {
 
 #[cfg(test)]
 mod tests {
-    use crate::front_matter::page::PageFrontMatter;
+    use crate::front_matter::page::{parse_datetime, PageFrontMatter};
     use crate::front_matter::split::RawFrontMatter;
+    use config::Config;
     use libs::tera::to_value;
     use test_case::test_case;
     use time::macros::datetime;
+    use time::OffsetDateTime;
 
     #[test_case(&RawFrontMatter::Toml(r#"  "#); "toml")]
     #[test_case(&RawFrontMatter::Toml(r#"  "#); "yaml")]
@@ -563,4 +711,126 @@ authors:
             res2.authors
         );
     }
+
+    #[test_case(&RawFrontMatter::Toml(r#"
+protected = { password = "hunter2" }
+"#); "toml")]
+    #[test_case(&RawFrontMatter::Yaml(r#"
+protected:
+    password: hunter2
+"#); "yaml")]
+    fn can_parse_protected(content: &RawFrontMatter) {
+        let res = PageFrontMatter::parse(content).unwrap();
+        assert_eq!(res.protected.unwrap().password.as_deref(), Some("hunter2"));
+        assert!(!res.in_search_index);
+    }
+
+    #[test_case(&RawFrontMatter::Toml(r#"
+protected = { password = "hunter2", name = "members" }
+"#); "both")]
+    #[test_case(&RawFrontMatter::Toml(r#"
+protected = {}
+"#); "none")]
+    #[test_case(&RawFrontMatter::Toml(r#"
+protected = { password = "" }
+"#); "empty")]
+    fn errors_on_invalid_protected(content: &RawFrontMatter) {
+        assert!(PageFrontMatter::parse(content).is_err());
+    }
+
+    #[test_case(&RawFrontMatter::Toml(r#"
+date = 1525163400
+"#); "toml timestamp")]
+    #[test_case(&RawFrontMatter::Yaml(r#"
+date: 1525163400
+"#); "yaml timestamp")]
+    #[test_case(&RawFrontMatter::Toml(r#"
+date = 2018-05-01
+time = "08:30"
+"#); "toml date and time")]
+    #[test_case(&RawFrontMatter::Toml(r#"
+date = 2018-05-01
+time = 08:30:00
+"#); "toml date and local time")]
+    #[test_case(&RawFrontMatter::Yaml(r#"
+date: 2018-05-01
+time: "08:30:00"
+"#); "yaml date and time")]
+    fn can_parse_date_as_timestamp_or_with_time(content: &RawFrontMatter) {
+        let res = PageFrontMatter::parse(content).unwrap();
+        assert_eq!(res.datetime.unwrap(), datetime!(2018 - 05 - 01 08:30:00 UTC));
+        assert_eq!(res.datetime_tuple, Some((2018, 5, 1)));
+        assert_eq!(res.time, None);
+    }
+
+    #[test_case(&RawFrontMatter::Toml(r#"
+time = "08:30"
+"#), "no `date`"; "time without date")]
+    #[test_case(&RawFrontMatter::Toml(r#"
+date = 2018-05-01
+time = "8h30"
+"#), "could not be parsed"; "malformed time")]
+    #[test_case(&RawFrontMatter::Toml(r#"
+date = 2018-05-01T10:00:00
+time = "08:30"
+"#), "isn't a date only"; "date with a time")]
+    fn errors_on_invalid_date_and_time(content: &RawFrontMatter, expected: &str) {
+        let err = PageFrontMatter::parse(content).unwrap_err().to_string();
+        assert!(err.contains("`time`"), "{}", err);
+        assert!(err.contains(expected), "{}", err);
+    }
+
+    #[test_case(&RawFrontMatter::Toml(r#"
+date = 2018-06-01T09:00:00
+"#), datetime!(2018 - 06 - 01 09:00:00 +02:00); "toml local datetime")]
+    #[test_case(&RawFrontMatter::Toml(r#"
+date = 2018-01-01T09:00:00
+"#), datetime!(2018 - 01 - 01 09:00:00 +01:00); "toml local datetime in winter")]
+    #[test_case(&RawFrontMatter::Toml(r#"
+date = 2018-06-01T09:00:00Z
+"#), datetime!(2018 - 06 - 01 09:00:00 UTC); "toml offset datetime")]
+    #[test_case(&RawFrontMatter::Toml(r#"
+date = 2018-06-01
+"#), datetime!(2018 - 06 - 01 0:00 UTC); "toml date")]
+    fn can_apply_default_timezone(content: &RawFrontMatter, expected: OffsetDateTime) {
+        let mut config = Config::default();
+        config.default_timezone = Some("Europe/Paris".to_string());
+        let mut res = PageFrontMatter::parse(content).unwrap();
+        res.apply_default_timezone(&config);
+        assert_eq!(res.datetime.unwrap(), expected);
+        assert_eq!(res.datetime.unwrap().offset(), expected.offset());
+        assert_eq!(parse_datetime(res.date.as_ref().unwrap()), Some(expected));
+        assert!(!res.has_datetime_without_offset());
+    }
+
+    #[test]
+    fn can_tell_datetimes_without_offset() {
+        let local = PageFrontMatter::parse(&RawFrontMatter::Toml("date = 2018-06-01T09:00:00"));
+        let mut local = local.unwrap();
+        assert!(local.has_datetime_without_offset());
+        local.apply_default_timezone(&Config::default());
+        assert!(local.has_datetime_without_offset());
+        assert_eq!(local.datetime.unwrap(), datetime!(2018 - 06 - 01 09:00:00 UTC));
+
+        let date = PageFrontMatter::parse(&RawFrontMatter::Toml("date = 2018-06-01")).unwrap();
+        assert!(!date.has_datetime_without_offset());
+    }
+
+    #[test]
+    fn can_parse_canonical_url_and_robots() {
+        let res = PageFrontMatter::parse(&RawFrontMatter::Toml(
+            "canonical_url = \"@/blog/original.md\"\nrobots = \"NoIndex, nofollow\"",
+        ))
+        .unwrap();
+        assert_eq!(res.canonical_url.as_deref(), Some("@/blog/original.md"));
+        assert!(res.is_noindex());
+        assert!(!res.in_search_index);
+
+        let res = PageFrontMatter::parse(&RawFrontMatter::Toml("robots = \"nofollow\"")).unwrap();
+        assert!(!res.is_noindex());
+        assert!(res.in_search_index);
+
+        let res = PageFrontMatter::parse(&RawFrontMatter::Toml("canonical_url = \"/blog/\""));
+        assert!(res.is_err());
+    }
 }
@@ -1,6 +1,9 @@
+use std::collections::HashMap;
+
 use libs::tera::{Map, Value};
 use serde::{Deserialize, Serialize};
 
+use config::OutputFormat;
 use errors::Result;
 use utils::de::fix_toml_dates;
 use utils::types::InsertAnchor;
@@ -11,7 +14,7 @@ use crate::SortBy;
 static DEFAULT_PAGINATE_PATH: &str = "page";
 
 /// The front matter of every section
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct SectionFrontMatter {
     /// <title> of the page
@@ -39,6 +42,13 @@ pub struct SectionFrontMatter {
     /// Path to be used by pagination: the page number will be appended after it. Defaults to `page`.
     #[serde(skip_serializing)]
     pub paginate_path: String,
+    /// How many pages to give to the template in `section.pages`, after skipping `page_offset` of them.
+    /// Ignored if the section is paginated.
+    #[serde(skip_serializing)]
+    pub page_limit: Option<usize>,
+    /// How many pages to skip at the start of `section.pages`. Ignored if the section is paginated.
+    #[serde(skip_serializing)]
+    pub page_offset: usize,
     /// Whether to insert a link for each header like the ones you can see in this site if you hover one
     /// The default template can be overridden by creating a `anchor-link.html` in the `templates` directory
     pub insert_anchor_links: InsertAnchor,
@@ -56,13 +66,25 @@ pub struct SectionFrontMatter {
     /// Defaults to `true` but is only used if search if explicitly enabled in the config.
     #[serde(skip_serializing)]
     pub in_search_index: bool,
+    /// How much more the section content and its pages/subsections count in the search results.
+    /// Multiplied by the boosts of the parent sections. Defaults to `1.0`.
+    #[serde(skip_serializing)]
+    pub search_boost: f64,
     /// Whether the section should pass its pages on to the parent section. Defaults to `false`.
     /// Useful when the section shouldn't split up the parent section, like
     /// sections for each year under a posts section.
     pub transparent: bool,
+    /// Whether the pages dated in the future are included even if the site doesn't include them.
+    /// Defaults to `false`. Useful for sections where the future is the point, like events.
+    #[serde(skip_serializing)]
+    pub include_future: bool,
     /// Optional template for all pages in this section (including the pages of children section)
     #[serde(skip_serializing)]
     pub page_template: Option<String>,
+    /// Overrides `page_output_formats` of the config for all pages in this section
+    /// (including the pages of children section)
+    #[serde(skip_serializing)]
+    pub page_output_formats: Option<Vec<OutputFormat>>,
     /// All aliases for that page. Zola will create HTML templates that will
     /// redirect to this
     #[serde(skip_serializing)]
@@ -70,6 +92,16 @@ pub struct SectionFrontMatter {
     /// Whether to generate a feed for the current section
     #[serde(skip_serializing)]
     pub generate_feeds: bool,
+    /// Overrides `markdown.heading_offset` of the config for that section
+    #[serde(skip_serializing)]
+    pub heading_offset: Option<u8>,
+    /// Overrides `markdown.highlight_code` of the config for that section
+    #[serde(skip_serializing)]
+    pub highlight_code: Option<bool>,
+    /// Old heading ids that should keep working, pointing to the current ids: hidden anchors with
+    /// the old ids are added before the headings
+    #[serde(skip_serializing)]
+    pub anchor_aliases: HashMap<String, String>,
     /// Any extra parameter present in the front matter
     pub extra: Map<String, Value>,
 }
@@ -77,6 +109,9 @@ pub struct SectionFrontMatter {
 impl SectionFrontMatter {
     pub fn parse(raw: &RawFrontMatter) -> Result<SectionFrontMatter> {
         let mut f: SectionFrontMatter = raw.deserialize()?;
+        if let Some(ref formats) = f.page_output_formats {
+            OutputFormat::validate_all(formats)?;
+        }
 
         f.extra = match fix_toml_dates(f.extra) {
             Value::Object(o) => o,
@@ -93,6 +128,11 @@ impl SectionFrontMatter {
             None => false,
         }
     }
+
+    /// Whether `section.pages` should only have some of the pages of the section
+    pub fn limits_pages(&self) -> bool {
+        self.page_limit.is_some() || self.page_offset > 0
+    }
 }
 
 impl Default for SectionFrontMatter {
@@ -106,14 +146,22 @@ impl Default for SectionFrontMatter {
             paginate_by: None,
             paginate_reversed: false,
             paginate_path: DEFAULT_PAGINATE_PATH.to_string(),
+            page_limit: None,
+            page_offset: 0,
             render: true,
             redirect_to: None,
             insert_anchor_links: InsertAnchor::None,
             in_search_index: true,
+            search_boost: 1.0,
             transparent: false,
+            include_future: false,
             page_template: None,
+            page_output_formats: None,
             aliases: Vec::new(),
             generate_feeds: false,
+            heading_offset: None,
+            highlight_code: None,
+            anchor_aliases: HashMap::new(),
             extra: Map::new(),
             draft: false,
         }
@@ -1,6 +1,6 @@
 use std::path::Path;
 
-use errors::{bail, Context, Result};
+use errors::{bail, Context, Error, LocatedError, Result};
 use libs::once_cell::sync::Lazy;
 use libs::regex::Regex;
 use libs::{serde_yaml, toml};
@@ -41,9 +41,13 @@ impl RawFrontMatter<'_> {
     }
 }
 
-/// Split a file between the front matter and its content
+/// Split a file between the front matter and its content, also returning the byte offset
+/// of the front matter in the file.
 /// Will return an error if the front matter wasn't found
-fn split_content<'c>(file_path: &Path, content: &'c str) -> Result<(RawFrontMatter<'c>, &'c str)> {
+fn split_content<'c>(
+    file_path: &Path,
+    content: &'c str,
+) -> Result<(RawFrontMatter<'c>, &'c str, usize)> {
     let (re, is_toml) = if TOML_RE.is_match(content) {
         (&TOML_RE as &Regex, true)
     } else if YAML_RE.is_match(content) {
@@ -60,13 +64,99 @@ fn split_content<'c>(file_path: &Path, content: &'c str) -> Result<(RawFrontMatt
     // caps[0] is the full match
     // caps[1] => front matter
     // caps[2] => content
-    let front_matter = caps.get(1).unwrap().as_str();
+    let front_matter = caps.get(1).unwrap();
+    let offset = front_matter.start();
+    let front_matter = front_matter.as_str();
     let content = caps.get(2).map_or("", |m| m.as_str());
 
     if is_toml {
-        Ok((RawFrontMatter::Toml(front_matter), content))
+        Ok((RawFrontMatter::Toml(front_matter), content, offset))
     } else {
-        Ok((RawFrontMatter::Yaml(front_matter), content))
+        Ok((RawFrontMatter::Yaml(front_matter), content, offset))
+    }
+}
+
+/// The content of a file without its front matter, which isn't parsed.
+/// Files without front matter are returned as is.
+pub fn strip_front_matter(content: &str) -> &str {
+    for re in [&TOML_RE, &YAML_RE] {
+        if let Some(caps) = re.captures(content) {
+            return caps.get(2).map_or("", |m| m.as_str());
+        }
+    }
+    content
+}
+
+/// A hint for the most common front matter mistakes
+fn toml_error_hint(message: &str, line: &str) -> Option<&'static str> {
+    let key = line.split('=').next().unwrap_or_default().trim();
+    if message.contains("unknown field") {
+        Some("custom variables need to be set in the `[extra]` table")
+    } else if message.contains("expected a sequence") {
+        Some("taxonomies terms and aliases are arrays, eg `tags = [\"rust\"]`")
+    } else if key == "date" || key == "updated" {
+        Some("dates are written as `2024-01-31`, `2024-01-31T10:00:00` or `2024-01-31T10:00:00+02:00`, with or without quotes")
+    } else {
+        None
+    }
+}
+
+/// Turns a TOML error into a message pointing to the line of the file where the error is,
+/// with the previous line for context and a caret under the offending token.
+fn describe_toml_error(
+    content: &str,
+    offset: usize,
+    error: &toml::de::Error,
+) -> Option<((usize, usize), String)> {
+    let span = error.span()?;
+    let start = offset + span.start;
+    let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_number = content[..start].matches('\n').count() + 1;
+    let column = content[line_start..start].chars().count() + 1;
+    let line = content[line_start..].lines().next().unwrap_or_default();
+    let message = error.message().trim();
+
+    let gutter = line_number.to_string().len();
+    let mut out = format!("{} (line {}, column {})\n", message, line_number, column);
+    if line_number > 1 {
+        let previous_line = content[..line_start.saturating_sub(1)].rsplit('\n').next().unwrap();
+        out.push_str(&format!("{:>gutter$} | {}\n", line_number - 1, previous_line));
+    }
+    out.push_str(&format!("{} | {}\n", line_number, line));
+    let underline_len = underline_len(content, start, offset + span.end);
+    out.push_str(&format!(
+        "{:gutter$} | {}{}",
+        "",
+        " ".repeat(column - 1),
+        "^".repeat(underline_len)
+    ));
+    if let Some(hint) = toml_error_hint(message, line) {
+        out.push_str(&format!("\nhint: {}", hint));
+    }
+    Some(((line_number, column), out))
+}
+
+/// How many characters to underline for an error spanning from `start` to `end`, on the line of
+/// `start` only. An empty span still underlines the character it points to.
+fn underline_len(content: &str, start: usize, end: usize) -> usize {
+    let next_char_end = start + content[start..].chars().next().map_or(0, char::len_utf8);
+    content[start..end.max(next_char_end)].lines().next().map_or(1, |s| s.chars().count().max(1))
+}
+
+/// Adds the location of TOML errors in the file, the spans given by `toml` being relative
+/// to the front matter
+fn locate_front_matter_error(err: Error, file_path: &Path, content: &str, offset: usize) -> Error {
+    match err
+        .downcast_ref::<toml::de::Error>()
+        .and_then(|e| describe_toml_error(content, offset, e))
+    {
+        Some((position, message)) => Error::new(LocatedError {
+            kind: "front_matter",
+            path: file_path.to_path_buf(),
+            position: Some(position),
+            message,
+        }),
+        None => err,
     }
 }
 
@@ -76,12 +166,14 @@ pub fn split_section_content<'c>(
     file_path: &Path,
     content: &'c str,
 ) -> Result<(SectionFrontMatter, &'c str)> {
-    let (front_matter, content) = split_content(file_path, content)?;
-    let meta = SectionFrontMatter::parse(&front_matter).with_context(|| {
-        format!("Error when parsing front matter of section `{}`", file_path.to_string_lossy())
-    })?;
+    let (front_matter, body, offset) = split_content(file_path, content)?;
+    let meta = SectionFrontMatter::parse(&front_matter)
+        .map_err(|e| locate_front_matter_error(e, file_path, content, offset))
+        .with_context(|| {
+            format!("Error when parsing front matter of section `{}`", file_path.to_string_lossy())
+        })?;
 
-    Ok((meta, content))
+    Ok((meta, body))
 }
 
 /// Split a file between the front matter and its content
@@ -90,11 +182,13 @@ pub fn split_page_content<'c>(
     file_path: &Path,
     content: &'c str,
 ) -> Result<(PageFrontMatter, &'c str)> {
-    let (front_matter, content) = split_content(file_path, content)?;
-    let meta = PageFrontMatter::parse(&front_matter).with_context(|| {
-        format!("Error when parsing front matter of section `{}`", file_path.to_string_lossy())
-    })?;
-    Ok((meta, content))
+    let (front_matter, body, offset) = split_content(file_path, content)?;
+    let meta = PageFrontMatter::parse(&front_matter)
+        .map_err(|e| locate_front_matter_error(e, file_path, content, offset))
+        .with_context(|| {
+            format!("Error when parsing front matter of page `{}`", file_path.to_string_lossy())
+        })?;
+    Ok((meta, body))
 }
 
 #[cfg(test)]
@@ -102,7 +196,7 @@ mod tests {
     use std::path::Path;
     use test_case::test_case;
 
-    use super::{split_page_content, split_section_content};
+    use super::{split_page_content, split_section_content, strip_front_matter, underline_len};
 
     #[test_case(r#"
 +++
@@ -144,6 +238,13 @@ Hello
         assert!(front_matter.is_paginated());
     }
 
+    #[test_case("+++\nrender = false\n+++\nHello\n", "Hello\n"; "toml")]
+    #[test_case("---\nrender: false\n---\nHello\n", "Hello\n"; "yaml")]
+    #[test_case("Hello\n\n---\n\nWorld\n", "Hello\n\n---\n\nWorld\n"; "no front matter")]
+    fn can_strip_front_matter(content: &str, expected: &str) {
+        assert_eq!(strip_front_matter(content), expected);
+    }
+
     #[test_case(r#"
 +++
 title = "Title"
@@ -248,4 +349,37 @@ date: 2002-10-12
         let res = split_page_content(Path::new(""), content);
         assert!(res.is_err());
     }
+
+    #[test]
+    fn toml_errors_point_to_the_line_in_the_file() {
+        let content = "\n+++\ntitle = \"Title\"\n[taxonomies]\ntags = \"rust\"\n+++\nHello";
+        let err = split_page_content(Path::new("post.md"), content).unwrap_err();
+        assert_eq!(err.to_string(), "Error when parsing front matter of page `post.md`");
+        assert_eq!(
+            err.root_cause().to_string(),
+            "invalid type: string \"rust\", expected a sequence (line 5, column 8)\n\
+             4 | [taxonomies]\n\
+             5 | tags = \"rust\"\n  \
+               |        ^^^^^^\n\
+             hint: taxonomies terms and aliases are arrays, eg `tags = [\"rust\"]`"
+        );
+    }
+
+    #[test]
+    fn can_underline_multibyte_characters() {
+        let content = "title = \"Café\"\nnext";
+        let start = content.find('é').unwrap();
+        assert_eq!(underline_len(content, start, start), 1);
+        assert_eq!(underline_len(content, start, content.len()), 2);
+        assert_eq!(underline_len(content, content.len(), content.len()), 1);
+    }
+
+    #[test]
+    fn toml_errors_give_hints_for_unknown_section_keys() {
+        let content = "+++\nsort = \"date\"\n+++\n";
+        let err = split_section_content(Path::new("_index.md"), content).unwrap_err();
+        let message = err.root_cause().to_string();
+        assert!(message.contains("(line 2, column 1)"));
+        assert!(message.contains("hint: custom variables need to be set in the `[extra]` table"));
+    }
 }
@@ -1,21 +1,28 @@
 mod front_matter;
 
+mod archives;
 mod file_info;
 mod library;
 mod page;
 mod pagination;
 mod section;
 mod ser;
+mod site_index;
 mod sorting;
 mod taxonomies;
 mod types;
 mod utils;
 
+pub use archives::{Archive, ArchiveGroup, SerializedArchiveGroup};
 pub use file_info::FileInfo;
-pub use front_matter::{PageFrontMatter, SectionFrontMatter};
+pub use front_matter::{
+    parse_datetime, strip_front_matter, PageFrontMatter, Protected, SectionFrontMatter,
+};
 pub use library::Library;
 pub use page::Page;
 pub use pagination::Paginator;
-pub use section::Section;
+pub use section::{Section, SectionStats};
+pub use site_index::{Ancestor, ContentStats, SiteIndex, SiteIndexSection};
+pub use sorting::{sort_pages, tie_break};
 pub use taxonomies::{Taxonomy, TaxonomyTerm};
 pub use types::*;
@@ -1,12 +1,26 @@
+use std::cmp::Reverse;
 use std::path::{Path, PathBuf};
 
-use config::Config;
+use config::{Config, RelatedPages};
 use libs::ahash::{AHashMap, AHashSet};
+use libs::rayon::prelude::*;
+use libs::tera::{to_value, Map, Value};
+
+use errors::{bail, Result};
 
 use crate::ser::TranslatedContent;
 use crate::sorting::sort_pages;
 use crate::taxonomies::{Taxonomy, TaxonomyFound};
-use crate::{Page, Section, SortBy};
+use crate::utils::merge_extra;
+use crate::{Page, Section, SectionStats, SiteIndex, SortBy};
+
+/// Makes the permalinks of the pages and the URLs of the mentions comparable: the scheme and
+/// trailing slash are left out, so `http://example.com/blog` matches `https://example.com/blog/`
+pub fn normalize_mention_url(url: &str) -> String {
+    let url = url.trim();
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    url.trim_end_matches('/').to_string()
+}
 
 macro_rules! set {
     ($($key:expr,)+) => (set!($($key),+));
@@ -30,12 +44,56 @@ pub struct Library {
     pub reverse_aliases: AHashMap<String, AHashSet<PathBuf>>,
     pub translations: AHashMap<PathBuf, AHashSet<PathBuf>>,
     pub backlinks: AHashMap<String, AHashSet<PathBuf>>,
+    /// The mentions of the pages, like webmentions, by permalink normalized with
+    /// `normalize_mention_url`
+    pub mentions: AHashMap<String, Vec<Value>>,
     // A mapping of {lang -> <slug, {term -> vec<paths>}>>}
     taxonomies_def: AHashMap<String, AHashMap<String, AHashMap<String, Vec<PathBuf>>>>,
     // All the taxonomies from config.toml in their slugifiedv ersion
     // So we don't need to pass the Config when adding a page to know how to slugify and we only
     // slugify once
     taxo_name_to_slug: AHashMap<String, String>,
+    /// The lightweight index of the sections, rebuilt when populating them
+    pub site_index: SiteIndex,
+    /// The fingerprint of every page and section by path, to find out what changed between two
+    /// loads of the site. Only filled in `zola serve`, see `fill_fingerprints`
+    pub fingerprints: AHashMap<PathBuf, u64>,
+    /// Whether `extra` is serialized as the `effective_extra` of the pages and sections
+    pub(crate) merge_extra: bool,
+}
+
+/// The pages with an index each and the pages of every taxonomy term as indices, to score
+/// the related pages of all the pages without hashing their paths
+struct RelatedIndex<'a> {
+    pages: Vec<&'a Page>,
+    /// The dates of the pages, in nanoseconds since the epoch
+    dates: Vec<Option<i128>>,
+    /// (lang, taxonomy slug, term) -> the pages that can be related to the pages with that term
+    terms: AHashMap<(&'a str, &'a str, &'a str), Vec<usize>>,
+}
+
+impl<'a> RelatedIndex<'a> {
+    fn new(library: &'a Library) -> Self {
+        let pages: Vec<_> = library.pages.values().collect();
+        let indices: AHashMap<_, _> =
+            pages.iter().enumerate().map(|(i, p)| (&p.file.path, i)).collect();
+        let mut terms = AHashMap::new();
+        for (lang, taxonomies) in &library.taxonomies_def {
+            for (slug, terms_pages) in taxonomies {
+                for (term, paths) in terms_pages {
+                    let related = paths
+                        .iter()
+                        .filter_map(|p| indices.get(p).copied())
+                        .filter(|i| pages[*i].meta.render && !pages[*i].meta.draft)
+                        .collect();
+                    terms.insert((lang.as_str(), slug.as_str(), term.as_str()), related);
+                }
+            }
+        }
+        let dates =
+            pages.iter().map(|p| p.meta.datetime.map(|d| d.unix_timestamp_nanos())).collect();
+        Self { pages, dates, terms }
+    }
 }
 
 impl Library {
@@ -80,15 +138,37 @@ impl Library {
             .collect()
     }
 
+    /// Finds the pages and sections whose path or one of their aliases is one of the given files
+    /// generated by Zola, like the sitemap, as both would be written at the same place.
+    /// Returns Vec<(file colliding, [list of files causing that collision])>
+    pub fn find_generated_file_collisions(&self, files: &[String]) -> Vec<(String, Vec<PathBuf>)> {
+        let mut collisions = Vec::new();
+        for file in files {
+            let mut paths: Vec<_> = self
+                .reverse_aliases
+                .iter()
+                .filter(|(alias, _)| alias.trim_matches('/') == file.trim_matches('/'))
+                .flat_map(|(_, paths)| paths.iter().cloned())
+                .collect();
+            if !paths.is_empty() {
+                paths.sort();
+                collisions.push((file.clone(), paths));
+            }
+        }
+        collisions
+    }
+
     pub fn insert_page(&mut self, page: Page) {
         let file_path = page.file.path.clone();
         if page.meta.render {
-            let mut entries = vec![page.path.clone()];
+            let mut entries = vec![page.path.to_string()];
             entries.extend(page.meta.aliases.to_vec());
             self.insert_reverse_aliases(&file_path, entries);
         }
 
-        for (taxa_name, terms) in &page.meta.taxonomies {
+        // Unlisted pages are not added to the taxonomies
+        let taxonomies = if page.meta.unlisted { None } else { Some(&page.meta.taxonomies) };
+        for (taxa_name, terms) in taxonomies.into_iter().flatten() {
             for term in terms {
                 // Safe unwraps as we create all lang/taxa and we validated that they are correct
                 // before getting there
@@ -112,13 +192,36 @@ impl Library {
     pub fn insert_section(&mut self, section: Section) {
         let file_path = section.file.path.clone();
         if section.meta.render {
-            let mut entries = vec![section.path.clone()];
+            let mut entries = vec![section.path.to_string()];
             entries.extend(section.meta.aliases.to_vec());
             self.insert_reverse_aliases(&file_path, entries);
         }
         self.sections.insert(file_path, section);
     }
 
+    /// Computes the fingerprint of every page and section, once they are rendered and populated
+    pub fn fill_fingerprints(&mut self) {
+        let pages = self.pages.par_iter().map(|(path, page)| (path.clone(), page.fingerprint()));
+        let sections =
+            self.sections.par_iter().map(|(path, section)| (path.clone(), section.fingerprint()));
+        self.fingerprints = pages.chain(sections).collect::<Vec<_>>().into_iter().collect();
+    }
+
+    /// Updates the fingerprints of these pages and sections after they were rendered again
+    pub fn update_fingerprints<'a>(&mut self, paths: impl IntoIterator<Item = &'a PathBuf>) {
+        for path in paths {
+            let fingerprint = match (self.pages.get(path), self.sections.get(path)) {
+                (Some(page), _) => page.fingerprint(),
+                (None, Some(section)) => section.fingerprint(),
+                (None, None) => {
+                    self.fingerprints.remove(path);
+                    continue;
+                }
+            };
+            self.fingerprints.insert(path.clone(), fingerprint);
+        }
+    }
+
     /// Fills a map of target -> {content mentioning it}
     /// This can only be called _after_ rendering markdown as we need to have accumulated all
     /// the links first
@@ -146,6 +249,33 @@ impl Library {
         }
     }
 
+    /// Sets the mentions of the pages from the data mapping permalinks to arrays of mentions.
+    /// The entries of the permalinks normalizing to the same URL are merged.
+    pub fn set_mentions(&mut self, data: &Map<String, Value>) -> Result<()> {
+        self.mentions.clear();
+        for (url, entries) in data {
+            let Value::Array(entries) = entries else {
+                bail!("The mentions of `{}` should be an array", url);
+            };
+            self.mentions.entry(normalize_mention_url(url)).or_default().extend(entries.clone());
+        }
+        Ok(())
+    }
+
+    /// The pages and sections using each shortcode, by shortcode name.
+    /// This can only be called _after_ rendering markdown as that's when the shortcodes are found
+    pub fn shortcode_usage(&self) -> AHashMap<&str, AHashSet<&Path>> {
+        let mut usage: AHashMap<&str, AHashSet<&Path>> = AHashMap::new();
+        let pages = self.pages.values().map(|p| (&p.shortcodes, p.file.path.as_path()));
+        let sections = self.sections.values().map(|s| (&s.shortcodes, s.file.path.as_path()));
+        for (shortcodes, path) in pages.chain(sections) {
+            for name in shortcodes {
+                usage.entry(name.as_str()).or_default().insert(path);
+            }
+        }
+        usage
+    }
+
     /// This is called _before_ rendering the markdown the pages/sections
     pub fn find_taxonomies(&self, config: &Config) -> Vec<Taxonomy> {
         let mut taxonomies = Vec::new();
@@ -167,27 +297,126 @@ impl Library {
                 taxonomies.push(Taxonomy::new(taxo_found, config));
             }
         }
+        // In the order of the config rather than the one of the hashmaps
+        taxonomies.sort_by_cached_key(|t| {
+            let position =
+                config.languages[&t.lang].taxonomies.iter().position(|c| c.slug == t.slug);
+            (t.lang.clone(), position)
+        });
 
         taxonomies
     }
 
+    /// Fills `page.related` with the pages sharing the most taxonomy terms with each page.
+    /// It is quadratic in the worst case, hence done in parallel and possible to disable.
+    pub fn fill_related_pages(&mut self, config: &Config) {
+        let settings = &config.related_pages;
+        let related: Vec<(PathBuf, Vec<PathBuf>)> = if settings.enabled && settings.limit > 0 {
+            let index = RelatedIndex::new(self);
+            index
+                .pages
+                .par_iter()
+                .enumerate()
+                .filter(|(_, page)| !page.meta.taxonomies.is_empty())
+                .map_init(
+                    || vec![None; index.pages.len()],
+                    |scores, (i, page)| {
+                        (
+                            page.file.path.clone(),
+                            self.find_related_pages(&index, i, scores, settings),
+                        )
+                    },
+                )
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        for page in self.pages.values_mut() {
+            page.related.clear();
+        }
+        for (path, related) in related {
+            self.pages.get_mut(&path).unwrap().related = related;
+        }
+    }
+
+    /// Scores the other pages by the weights of the terms they share with the page at `page` in
+    /// the index, the ones with the closest date first on ties.
+    /// `scores` has an entry per page of the index, all `None`, and is left that way
+    fn find_related_pages(
+        &self,
+        index: &RelatedIndex,
+        page: usize,
+        scores: &mut [Option<usize>],
+        settings: &RelatedPages,
+    ) -> Vec<PathBuf> {
+        let mut scored = Vec::new();
+        let current = index.pages[page];
+        for (taxa_name, terms) in &current.meta.taxonomies {
+            let weight = settings.weight(taxa_name);
+            let slug = self.taxo_name_to_slug[taxa_name].as_str();
+            let terms_pages = terms
+                .iter()
+                .filter_map(|t| index.terms.get(&(current.lang.as_str(), slug, t.as_str())));
+            for &other in terms_pages.flatten() {
+                if other == page {
+                    continue;
+                }
+                let score = &mut scores[other];
+                if score.is_none() {
+                    scored.push(other);
+                }
+                *score = Some(score.unwrap_or(0) + weight);
+            }
+        }
+
+        // Only the pages scoring at least as much as the `limit`-th best one can make it
+        if scored.len() > settings.limit {
+            let mut best: Vec<_> = scored.iter().map(|&other| scores[other].unwrap()).collect();
+            let threshold = *best.select_nth_unstable_by(settings.limit - 1, |a, b| b.cmp(a)).1;
+            scored.retain(|&other| {
+                let keep = scores[other] >= Some(threshold);
+                if !keep {
+                    scores[other] = None;
+                }
+                keep
+            });
+        }
+
+        let mut related: Vec<_> = scored
+            .into_iter()
+            .map(|other| {
+                let score = scores[other].take().unwrap();
+                let distance = match (index.dates[page], index.dates[other]) {
+                    (Some(a), Some(b)) => Some((a - b).abs()),
+                    _ => None,
+                };
+                (Reverse(score), distance.is_none(), distance, &index.pages[other].file.path)
+            })
+            .collect();
+        if related.len() > settings.limit {
+            related.select_nth_unstable(settings.limit);
+            related.truncate(settings.limit);
+        }
+        related.sort_unstable();
+        related.into_iter().map(|(_, _, _, path)| path.clone()).collect()
+    }
+
     /// Sort all sections pages according to sorting method given
     /// Pages that cannot be sorted are set to the section.ignored_pages instead
     pub fn sort_section_pages(&mut self) {
         let mut updates = AHashMap::new();
         for (path, section) in &self.sections {
             let pages: Vec<_> = section.pages.iter().map(|p| &self.pages[p]).collect();
-            let (sorted_pages, cannot_be_sorted_pages) = match section.meta.sort_by {
-                SortBy::None => continue,
-                _ => sort_pages(&pages, section.meta.sort_by),
-            };
+            // Even unsorted sections get their pages in a stable order
+            let (sorted_pages, cannot_be_sorted_pages) = sort_pages(&pages, section.meta.sort_by);
 
             updates
                 .insert(path.clone(), (sorted_pages, cannot_be_sorted_pages, section.meta.sort_by));
         }
 
-        for (path, (sorted, unsortable, _)) in updates {
-            if !self.sections[&path].meta.transparent {
+        for (path, (sorted, unsortable, sort_by)) in updates {
+            if sort_by != SortBy::None && !self.sections[&path].meta.transparent {
                 // Fill siblings
                 for (i, page_path) in sorted.iter().enumerate() {
                     let p = self.pages.get_mut(page_path).unwrap();
@@ -226,12 +455,14 @@ impl Library {
 
         let mut ancestors = AHashMap::new();
         let mut subsections = AHashMap::new();
-        let mut sections_weight = AHashMap::new();
+        let mut sections_order = AHashMap::new();
 
         // We iterate over the sections twice
         // The first time to build up the list of ancestors for each section
         for (path, section) in &self.sections {
-            sections_weight.insert(path.clone(), section.meta.weight);
+            // Sections are ordered by weight, then by title with the untitled ones last
+            let title = section.meta.title.as_deref();
+            sections_order.insert(path.clone(), (section.meta.weight, title.is_none(), title));
             if let Some(ref grand_parent) = section.file.grand_parent {
                 subsections
                     // Using the original filename to work for multi-lingual sections
@@ -266,6 +497,17 @@ impl Library {
             ancestors.insert(section.file.path.clone(), parents);
         }
 
+        let mut siblings = AHashMap::new();
+        for children in subsections.values_mut() {
+            children.sort_by(|a: &PathBuf, b| {
+                sections_order[a].cmp(&sections_order[b]).then_with(|| a.cmp(b))
+            });
+            for (i, child) in children.iter().enumerate() {
+                let prev = if i > 0 { children.get(i - 1).cloned() } else { None };
+                siblings.insert(child.clone(), (prev, children.get(i + 1).cloned()));
+            }
+        }
+
         // The second time we actually assign ancestors and order subsections based on their weights
         for (path, section) in self.sections.iter_mut() {
             section.subsections.clear();
@@ -274,10 +516,10 @@ impl Library {
             section.ancestors.clear();
 
             if let Some(children) = subsections.get(path) {
-                let mut children: Vec<_> = children.clone();
-                children.sort_by(|a, b| sections_weight[a].cmp(&sections_weight[b]));
-                section.subsections = children;
+                section.subsections = children.clone();
             }
+            (section.prev_sibling, section.next_sibling) =
+                siblings.remove(path).unwrap_or_default();
             if let Some(parents) = ancestors.get(path) {
                 section.ancestors = parents.clone();
             }
@@ -304,7 +546,9 @@ impl Library {
 
             while let Some(parent_section) = self.sections.get_mut(&parent_section_path) {
                 let is_transparent = parent_section.meta.transparent;
-                parent_section.pages.push(path.clone());
+                if !page.meta.unlisted {
+                    parent_section.pages.push(path.clone());
+                }
                 page.ancestors = ancestors.get(&parent_section_path).cloned().unwrap_or_default();
                 // Don't forget to push the actual parent
                 page.ancestors.push(parent_section.file.relative.clone());
@@ -334,8 +578,81 @@ impl Library {
             }
         }
 
+        self.cascade_extra(config, content_path);
         // And once we have all the pages assigned to their section, we sort them
         self.sort_section_pages();
+        self.fill_section_stats();
+        self.site_index = SiteIndex::new(self, &config.default_language);
+    }
+
+    /// Sums the word counts and reading times of the pages of each section, leaving the drafts
+    /// out. Needs the pages and subsections of the sections to be set.
+    fn fill_section_stats(&mut self) {
+        fn collect_pages<'a>(lib: &'a Library, path: &Path, pages: &mut AHashSet<&'a PathBuf>) {
+            let section = &lib.sections[path];
+            pages.extend(section.pages.iter().filter(|p| !lib.pages[*p].meta.draft));
+            for subsection in &section.subsections {
+                collect_pages(lib, subsection, pages);
+            }
+        }
+
+        let mut stats = AHashMap::with_capacity(self.sections.len());
+        for (path, section) in &self.sections {
+            let pages: Vec<_> =
+                section.pages.iter().map(|p| &self.pages[p]).filter(|p| !p.meta.draft).collect();
+            let mut recursive_pages = AHashSet::new();
+            collect_pages(self, path, &mut recursive_pages);
+            stats.insert(
+                path.clone(),
+                SectionStats {
+                    pages: pages.len(),
+                    recursive_pages: recursive_pages.len(),
+                    word_count: pages.iter().filter_map(|p| p.word_count).sum(),
+                    reading_time: pages.iter().filter_map(|p| p.reading_time).sum(),
+                },
+            );
+        }
+
+        for (path, stats) in stats {
+            self.sections.get_mut(&path).unwrap().stats = stats;
+        }
+    }
+
+    /// Sets the `effective_extra` of every section and page: the `extra` of the config, which
+    /// already has the defaults of the theme, then of each ancestor section, outermost first,
+    /// and finally their own. Needs the ancestors to be set.
+    fn cascade_extra(&mut self, config: &Config, content_path: &Path) {
+        self.merge_extra = config.merge_extra;
+        let config_extra = match to_value(&config.extra) {
+            Ok(Value::Object(extra)) => extra,
+            _ => Map::new(),
+        };
+        let merged = |sections: &AHashMap<PathBuf, Section>, ancestors: &[String]| {
+            let mut extra = config_extra.clone();
+            for ancestor in ancestors {
+                if let Some(s) = sections.get(&content_path.join(ancestor)) {
+                    merge_extra(&mut extra, &s.meta.extra);
+                }
+            }
+            extra
+        };
+
+        let mut section_extras: AHashMap<_, _> = self
+            .sections
+            .iter()
+            .map(|(path, section)| {
+                let mut extra = merged(&self.sections, &section.ancestors);
+                merge_extra(&mut extra, &section.meta.extra);
+                (path.clone(), extra)
+            })
+            .collect();
+        for page in self.pages.values_mut() {
+            page.effective_extra = merged(&self.sections, &page.ancestors);
+            merge_extra(&mut page.effective_extra, &page.meta.extra);
+        }
+        for (path, section) in self.sections.iter_mut() {
+            section.effective_extra = section_extras.remove(path).unwrap_or_default();
+        }
     }
 
     /// Find all the orphan pages: pages that are in a folder without an `_index.md`
@@ -378,18 +695,20 @@ impl Library {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ser::SerializingPage;
     use crate::FileInfo;
     use config::{LanguageOptions, TaxonomyConfig};
+    use libs::time::OffsetDateTime;
     use std::collections::HashMap;
     use utils::slugs::SlugifyStrategy;
 
     #[test]
     fn can_find_collisions_with_paths() {
         let mut library = Library::default();
-        let mut section = Section { path: "hello".to_owned(), ..Default::default() };
+        let mut section = Section { path: "hello".into(), ..Default::default() };
         section.file.path = PathBuf::from("hello.md");
         library.insert_section(section.clone());
-        let mut section2 = Section { path: "hello".to_owned(), ..Default::default() };
+        let mut section2 = Section { path: "hello".into(), ..Default::default() };
         section2.file.path = PathBuf::from("bonjour.md");
         library.insert_section(section2.clone());
 
@@ -403,16 +722,16 @@ mod tests {
     #[test]
     fn can_find_collisions_with_aliases() {
         let mut library = Library::default();
-        let mut section = Section { path: "hello".to_owned(), ..Default::default() };
+        let mut section = Section { path: "hello".into(), ..Default::default() };
         section.file.path = PathBuf::from("hello.md");
         library.insert_section(section.clone());
-        let mut section2 = Section { path: "world".to_owned(), ..Default::default() };
+        let mut section2 = Section { path: "world".into(), ..Default::default() };
         section2.file.path = PathBuf::from("bonjour.md");
         section2.meta.aliases = vec!["hello".to_owned(), "hola".to_owned()];
         library.insert_section(section2.clone());
         // Sections with render=false do not collide with anything
         // https://github.com/getzola/zola/issues/1656
-        let mut section3 = Section { path: "world2".to_owned(), ..Default::default() };
+        let mut section3 = Section { path: "world2".into(), ..Default::default() };
         section3.meta.render = false;
         section3.file.path = PathBuf::from("bonjour2.md");
         section3.meta.aliases = vec!["hola".to_owned()];
@@ -425,6 +744,21 @@ mod tests {
         assert!(collisions[0].1.contains(&section2.file.path));
     }
 
+    #[test]
+    fn can_find_collisions_with_generated_files() {
+        let mut library = Library::default();
+        let mut section = Section { path: "/blog/".into(), ..Default::default() };
+        section.file.path = PathBuf::from("blog/_index.md");
+        section.meta.aliases = vec!["/feed/index.xml".to_owned()];
+        library.insert_section(section.clone());
+
+        let collisions = library.find_generated_file_collisions(&[
+            "feed/index.xml".to_string(),
+            "sitemap.xml".to_string(),
+        ]);
+        assert_eq!(collisions, vec![("feed/index.xml".to_string(), vec![section.file.path])]);
+    }
+
     #[derive(Debug, Clone)]
     enum PageSort {
         None,
@@ -472,6 +806,76 @@ mod tests {
         section
     }
 
+    #[test]
+    fn can_cascade_extra() {
+        let mut config = Config::default_for_test();
+        config.extra = libs::toml::from_str(
+            r#"
+show_author = true
+level = "config"
+tags = ["a", "b"]
+[social]
+twitter = "config"
+github = "config"
+"#,
+        )
+        .unwrap();
+        let extra =
+            |json: &str| -> Map<String, Value> { libs::serde_json::from_str(json).unwrap() };
+        let mut library = Library::default();
+        let sections = [
+            ("content/_index.md", r#"{"level": "root", "social": {"twitter": "root"}}"#),
+            ("content/blog/_index.md", r#"{"level": "blog", "tags": ["c"], "social": "none"}"#),
+            ("content/blog/rust/_index.md", r#"{"level": 3, "social": {"mastodon": "rust"}}"#),
+        ];
+        for (path, json) in sections {
+            let mut section = create_section(path, "en", 0, false, SortBy::None);
+            section.meta.extra = extra(json);
+            library.insert_section(section);
+        }
+        let mut page = create_page("content/blog/rust/intro.md", "en", PageSort::None);
+        page.meta.extra = extra(r#"{"show_author": false, "social": {"github": "page"}}"#);
+        library.insert_page(page);
+        library.populate_sections(&config, Path::new("content"));
+
+        let blog = &library.sections[&PathBuf::from("content/blog/_index.md")];
+        assert_eq!(
+            Value::Object(blog.effective_extra.clone()),
+            libs::serde_json::json!({
+                "show_author": true, "level": "blog", "tags": ["c"], "social": "none"
+            })
+        );
+        let rust = &library.sections[&PathBuf::from("content/blog/rust/_index.md")];
+        assert_eq!(
+            Value::Object(rust.effective_extra.clone()),
+            libs::serde_json::json!({
+                "show_author": true, "level": 3, "tags": ["c"], "social": {"mastodon": "rust"}
+            })
+        );
+        let page = &library.pages[&PathBuf::from("content/blog/rust/intro.md")];
+        assert_eq!(
+            Value::Object(page.effective_extra.clone()),
+            libs::serde_json::json!({
+                "show_author": false,
+                "level": 3,
+                "tags": ["c"],
+                "social": {"mastodon": "rust", "github": "page"}
+            })
+        );
+        // The original `extra` is left as is
+        assert_eq!(page.meta.extra.len(), 2);
+
+        let serialized = |library: &Library| {
+            let page = &library.pages[&PathBuf::from("content/blog/rust/intro.md")];
+            to_value(SerializingPage::new(page, Some(library), false)).unwrap()
+        };
+        assert_eq!(serialized(&library)["extra"]["level"], Value::Null);
+        assert_eq!(serialized(&library)["effective_extra"]["level"], to_value(3).unwrap());
+        config.merge_extra = true;
+        library.populate_sections(&config, Path::new("content"));
+        assert_eq!(serialized(&library)["extra"]["level"], to_value(3).unwrap());
+    }
+
     #[test]
     fn can_populate_sections() {
         let mut config = Config::default_for_test();
@@ -613,6 +1017,148 @@ mod tests {
         assert!(translations[1].title.is_some());
     }
 
+    #[test]
+    fn can_fill_section_stats() {
+        let config = Config::default_for_test();
+        let mut library = Library::default();
+        for (path, transparent) in [
+            ("content/_index.md", false),
+            ("content/course/_index.md", false),
+            ("content/course/intro/_index.md", true),
+            ("content/course/chapter-1/_index.md", false),
+            ("content/course/chapter-1/extras/_index.md", false),
+        ] {
+            library.insert_section(create_section(path, "en", 0, transparent, SortBy::None));
+        }
+        for (path, word_count, reading_time, draft, render) in [
+            ("content/course/welcome.md", 200, 1, false, true),
+            ("content/course/intro/setup.md", 400, 2, false, true),
+            ("content/course/intro/todo.md", 1000, 5, true, true),
+            ("content/course/chapter-1/lesson-1.md", 1200, 6, false, true),
+            ("content/course/chapter-1/lesson-2.md", 800, 4, false, true),
+            ("content/course/chapter-1/hidden.md", 800, 4, false, false),
+            ("content/course/chapter-1/extras/quiz.md", 100, 1, false, true),
+        ] {
+            let mut page = create_page(path, "en", PageSort::None);
+            page.word_count = Some(word_count);
+            page.reading_time = Some(reading_time);
+            page.meta.draft = draft;
+            page.meta.render = render;
+            library.insert_page(page);
+        }
+        library.populate_sections(&config, Path::new("content"));
+
+        let stats = |path: &str| library.sections[&PathBuf::from(path)].stats.clone();
+        // The pages of the transparent `intro` are counted with the ones of `course`
+        assert_eq!(
+            stats("content/course/_index.md"),
+            SectionStats { pages: 2, recursive_pages: 5, word_count: 600, reading_time: 3 }
+        );
+        assert_eq!(
+            stats("content/course/intro/_index.md"),
+            SectionStats { pages: 1, recursive_pages: 1, word_count: 400, reading_time: 2 }
+        );
+        assert_eq!(
+            stats("content/course/chapter-1/_index.md"),
+            SectionStats { pages: 2, recursive_pages: 3, word_count: 2000, reading_time: 10 }
+        );
+        assert_eq!(
+            stats("content/_index.md"),
+            SectionStats { pages: 0, recursive_pages: 5, word_count: 0, reading_time: 0 }
+        );
+    }
+
+    #[test]
+    fn can_order_subsections_and_find_siblings() {
+        let config = Config::default_for_test();
+        let mut library = Library::default();
+        let sections = vec![
+            ("content/docs/_index.md", None, 0),
+            ("content/docs/install/_index.md", Some("Install"), 1),
+            ("content/docs/usage/_index.md", Some("Usage"), 2),
+            ("content/docs/config/_index.md", Some("Configuration"), 2),
+            ("content/docs/misc/_index.md", None, 2),
+        ];
+        for (p, title, weight) in sections {
+            let mut section = create_section(p, "en", weight, false, SortBy::None);
+            section.meta.title = title.map(|t| t.to_owned());
+            library.insert_section(section);
+        }
+        library.populate_sections(&config, Path::new("content"));
+
+        let docs = &library.sections[&PathBuf::from("content/docs/_index.md")];
+        // By weight, then title with the untitled ones last
+        assert_eq!(
+            docs.subsections,
+            vec![
+                PathBuf::from("content/docs/install/_index.md"),
+                PathBuf::from("content/docs/config/_index.md"),
+                PathBuf::from("content/docs/usage/_index.md"),
+                PathBuf::from("content/docs/misc/_index.md"),
+            ]
+        );
+        let config_section = &library.sections[&PathBuf::from("content/docs/config/_index.md")];
+        assert_eq!(
+            config_section.prev_sibling,
+            Some(PathBuf::from("content/docs/install/_index.md"))
+        );
+        assert_eq!(
+            config_section.next_sibling,
+            Some(PathBuf::from("content/docs/usage/_index.md"))
+        );
+        let install = &library.sections[&PathBuf::from("content/docs/install/_index.md")];
+        assert_eq!(install.prev_sibling, None);
+        assert!(docs.prev_sibling.is_none() && docs.next_sibling.is_none());
+
+        let value = libs::tera::to_value(config_section.serialize(&library)).unwrap();
+        assert_eq!(value["weight"], 2);
+        assert_eq!(value["prev_sibling"]["title"], "Install");
+        assert_eq!(value["prev_sibling"]["path"], "docs/install/_index.md");
+        assert_eq!(value["next_sibling"]["title"], "Usage");
+    }
+
+    #[test]
+    fn can_limit_pages_of_serialized_section() {
+        let config = Config::default_for_test();
+        let mut library = Library::default();
+        let mut section = create_section("content/blog/_index.md", "en", 0, false, SortBy::Date);
+        section.meta.page_offset = 1;
+        section.meta.page_limit = Some(2);
+        library.insert_section(section);
+        for (p, date) in [
+            ("content/blog/a.md", "2022-01-01"),
+            ("content/blog/b.md", "2022-02-02"),
+            ("content/blog/c.md", "2022-03-03"),
+            ("content/blog/d.md", "2022-04-04"),
+        ] {
+            library.insert_page(create_page(p, "en", PageSort::Date(date)));
+        }
+        library.populate_sections(&config, Path::new("content"));
+
+        let section_path = PathBuf::from("content/blog/_index.md");
+        let serialized_paths = |library: &Library| -> (Vec<String>, usize) {
+            let value =
+                libs::tera::to_value(library.sections[&section_path].serialize(library)).unwrap();
+            let paths = value["pages"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["relative_path"].as_str().unwrap().to_owned())
+                .collect();
+            (paths, value["total_pages"].as_u64().unwrap() as usize)
+        };
+        // The section keeps all its pages, only the templates get less of them
+        assert_eq!(library.sections[&section_path].pages.len(), 4);
+        assert_eq!(
+            serialized_paths(&library),
+            (vec!["blog/c.md".to_owned(), "blog/b.md".to_owned()], 4)
+        );
+
+        // Paginated sections give all their pages to the paginator
+        library.sections.get_mut(&section_path).unwrap().meta.paginate_by = Some(2);
+        assert_eq!(serialized_paths(&library).0.len(), 4);
+    }
+
     macro_rules! taxonomies {
         ($config:expr, [$($page:expr),+]) => {{
             let mut library = Library::new(&$config);
@@ -685,6 +1231,124 @@ mod tests {
         assert_eq!(authors.items[0].permalink, "http://a-website.com/authors/vincent-prouillet/");
     }
 
+    #[test]
+    fn can_fill_related_pages() {
+        let mut config = Config::default_for_test();
+        config.languages.get_mut("en").unwrap().taxonomies = vec![
+            TaxonomyConfig { name: "categories".to_string(), ..TaxonomyConfig::default() },
+            TaxonomyConfig { name: "tags".to_string(), ..TaxonomyConfig::default() },
+        ];
+        config.slugify_taxonomies();
+        config.related_pages.limit = 3;
+        config.related_pages.weights.insert("categories".to_string(), 2);
+
+        let date = |days: i64| Some(OffsetDateTime::from_unix_timestamp(days * 86400).unwrap());
+        let mut page1 = create_page_w_taxa(
+            "a.md",
+            "en",
+            vec![("tags", vec!["rust", "db"]), ("categories", vec!["tutorials"])],
+        );
+        page1.meta.datetime = date(100);
+        let page2 = create_page_w_taxa(
+            "b.md",
+            "en",
+            vec![("tags", vec!["js"]), ("categories", vec!["tutorials"])],
+        );
+        let page3 = create_page_w_taxa("c.md", "en", vec![("tags", vec!["rust", "db"])]);
+        let mut page4 = create_page_w_taxa("d.md", "en", vec![("tags", vec!["rust", "db"])]);
+        page4.meta.draft = true;
+        let mut page5 = create_page_w_taxa("e.md", "en", vec![("tags", vec!["rust"])]);
+        page5.meta.datetime = date(10);
+        let mut page6 = create_page_w_taxa("f.md", "en", vec![("tags", vec!["rust"])]);
+        page6.meta.datetime = date(90);
+        let mut library = Library::new(&config);
+        for page in [page1, page2, page3, page4, page5, page6] {
+            library.insert_page(page);
+        }
+
+        library.fill_related_pages(&config);
+        let related = &library.pages[&PathBuf::from("a.md")].related;
+        assert_eq!(related, &[PathBuf::from("b.md"), PathBuf::from("c.md"), PathBuf::from("f.md")]);
+        assert!(library.pages[&PathBuf::from("b.md")].related == [PathBuf::from("a.md")]);
+
+        config.related_pages.enabled = false;
+        library.fill_related_pages(&config);
+        assert!(library.pages.values().all(|p| p.related.is_empty()));
+    }
+
+    #[test]
+    fn related_pages_are_the_same_as_scoring_every_page() {
+        let mut config = Config::default_for_test();
+        config.languages.get_mut("en").unwrap().taxonomies = vec![
+            TaxonomyConfig { name: "categories".to_string(), ..TaxonomyConfig::default() },
+            TaxonomyConfig { name: "tags".to_string(), ..TaxonomyConfig::default() },
+        ];
+        config.slugify_taxonomies();
+        config.related_pages.limit = 5;
+        config.related_pages.weights.insert("categories".to_string(), 3);
+
+        let tags = ["a", "b", "c", "d", "e", "f", "g"];
+        let categories = ["c1", "c2", "c3"];
+        // A small LCG so the pages are the same on every run
+        let mut seed = 42u64;
+        let mut next = |n: usize| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % n
+        };
+        let mut library = Library::new(&config);
+        for i in 0..300 {
+            let mut page = create_page_w_taxa(
+                &format!("page-{}.md", i),
+                "en",
+                vec![
+                    ("tags", vec![tags[next(7)], tags[next(7)]]),
+                    ("categories", vec![categories[next(3)]]),
+                ],
+            );
+            if next(4) > 0 {
+                page.meta.datetime =
+                    Some(OffsetDateTime::from_unix_timestamp(next(50) as i64 * 86400).unwrap());
+            }
+            page.meta.draft = next(20) == 0;
+            page.meta.render = next(20) > 0;
+            library.insert_page(page);
+        }
+        library.fill_related_pages(&config);
+
+        // Scores all the pages sharing a term, then sorts all of them
+        let expected = |page: &Page| -> Vec<PathBuf> {
+            let settings = &config.related_pages;
+            let mut scores: AHashMap<&PathBuf, usize> = AHashMap::new();
+            let taxonomies = &library.taxonomies_def[&page.lang];
+            for (taxa_name, terms) in &page.meta.taxonomies {
+                let weight = settings.weight(taxa_name);
+                let terms_pages = &taxonomies[&library.taxo_name_to_slug[taxa_name]];
+                for other in terms.iter().filter_map(|t| terms_pages.get(t)).flatten() {
+                    if other != &page.file.path {
+                        *scores.entry(other).or_default() += weight;
+                    }
+                }
+            }
+            let mut related: Vec<_> = scores
+                .into_iter()
+                .filter(|(path, _)| {
+                    library.pages.get(*path).is_some_and(|p| !p.meta.draft && p.meta.render)
+                })
+                .collect();
+            related.sort_by_cached_key(|(path, score)| {
+                let distance = match (page.meta.datetime, library.pages[*path].meta.datetime) {
+                    (Some(a), Some(b)) => Some((a - b).abs()),
+                    _ => None,
+                };
+                (Reverse(*score), distance.is_none(), distance, *path)
+            });
+            related.into_iter().take(settings.limit).map(|(path, _)| path.clone()).collect()
+        };
+        for page in library.pages.values() {
+            assert_eq!(page.related, expected(page), "{}", page.file.path.display());
+        }
+    }
+
     #[test]
     fn can_make_multiple_language_taxonomies() {
         let mut config = Config::default_for_test();
@@ -740,7 +1404,8 @@ mod tests {
         assert_eq!(term1.name, "Ecole");
         assert_eq!(term1.slug, "ecole");
         assert_eq!(term1.permalink, "http://a-website.com/test-taxonomy/ecole/");
-        assert_eq!(term1.pages.len(), 4);
+        // The pages of the merged terms are sorted together
+        assert_eq!(term1.pages, ["a.md", "b.md", "c.md", "d.md"].map(PathBuf::from));
     }
 
     #[test]
@@ -784,4 +1449,50 @@ mod tests {
         );
         assert_eq!(library.backlinks["_index.md"], set! {PathBuf::from("page2.md")});
     }
+
+    #[test]
+    fn can_set_mentions() {
+        let mut page1 = create_page("page1.md", "en", PageSort::None);
+        page1.permalink = "https://example.com/page1/".to_owned().into();
+        let mut page2 = create_page("page2.md", "en", PageSort::None);
+        page2.permalink = "https://example.com/page2/".to_owned().into();
+        let mut library = Library::default();
+        library.insert_page(page1);
+        library.insert_page(page2);
+        let data = libs::serde_json::json!({
+            "http://example.com/page1": [{"author": "Bob"}],
+            "https://example.com/page1/": [{"author": "Alice"}],
+        });
+        library.set_mentions(data.as_object().unwrap()).unwrap();
+
+        let page1 = &library.pages[Path::new("page1.md")];
+        let serialized = to_value(SerializingPage::new(page1, Some(&library), false)).unwrap();
+        assert_eq!(serialized["mentions"]["count"], to_value(2).unwrap());
+        let page2 = &library.pages[Path::new("page2.md")];
+        let serialized = to_value(SerializingPage::new(page2, Some(&library), false)).unwrap();
+        assert_eq!(serialized["mentions"]["count"], to_value(0).unwrap());
+        assert_eq!(serialized["mentions"]["items"], to_value(Vec::<Value>::new()).unwrap());
+
+        let data = libs::serde_json::json!({"https://example.com/page1/": 3});
+        assert!(library.set_mentions(data.as_object().unwrap()).is_err());
+    }
+
+    #[test]
+    fn can_get_shortcode_usage() {
+        let mut page1 = create_page("page1.md", "en", PageSort::None);
+        page1.shortcodes = vec!["note".to_owned(), "youtube".to_owned()];
+        let mut page2 = create_page("page2.md", "en", PageSort::None);
+        page2.shortcodes = vec!["note".to_owned()];
+        let mut section1 = create_section("_index.md", "en", 10, false, SortBy::None);
+        section1.shortcodes = vec!["youtube".to_owned()];
+        let mut library = Library::default();
+        library.insert_page(page1);
+        library.insert_page(page2);
+        library.insert_section(section1);
+
+        let usage = library.shortcode_usage();
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage["note"], set! {Path::new("page1.md"), Path::new("page2.md")});
+        assert_eq!(usage["youtube"], set! {Path::new("page1.md"), Path::new("_index.md")});
+    }
 }
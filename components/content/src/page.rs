@@ -1,27 +1,35 @@
 /// A page, can be a blog post or a basic page
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use libs::once_cell::sync::Lazy;
 use libs::regex::Regex;
-use libs::tera::{Context as TeraContext, Tera};
+use libs::tera::{Context as TeraContext, Map, Tera, Value};
 
 use config::Config;
-use errors::{Context, Result};
-use markdown::{render_content, RenderContext};
+use errors::{bail, Context, Result};
+use markdown::{auto_summary, render_content, render_external, RenderContext};
 use utils::slugs::slugify_paths;
 use utils::table_of_contents::Heading;
-use utils::templates::{render_template, ShortcodeDefinition};
+use utils::templates::{render_template_with_options, ShortcodeDefinition};
 use utils::types::InsertAnchor;
 
 use crate::file_info::FileInfo;
 use crate::front_matter::{split_page_content, PageFrontMatter};
 use crate::library::Library;
 use crate::ser::SerializingPage;
-use crate::utils::get_reading_analytics;
-use crate::utils::{find_related_assets, has_anchor};
+use crate::utils::{find_loose_assets, find_related_assets, has_anchor};
+use crate::utils::{get_plain_excerpt, get_reading_analytics, hash_source};
+use crate::GitMetadata;
 use utils::anchors::has_anchor_id;
-use utils::fs::read_file;
+use utils::data::{load_data_file, PAGE_DATA_FILENAMES};
+use utils::encryption::encrypt;
+use utils::fs::{create_file, read_content_file, read_file};
+use utils::site::resolve_internal_link;
 
 // Based on https://regex101.com/r/H2n38Z/1/tests
 // A regex parsing RFC3339 date followed by {_,-} and some characters
@@ -31,6 +39,10 @@ static RFC3339_DATE: Lazy<Regex> = Lazy::new(|| {
     ).unwrap()
 });
 
+// A number followed by {_,-} and some characters, like `03_setup`
+static WEIGHT_PREFIX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?P<weight>\d+)(_|-)(?P<slug>.+$)").unwrap());
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Page {
     /// All info about the actual file
@@ -41,25 +53,33 @@ pub struct Page {
     pub ancestors: Vec<String>,
     /// The actual content of the page, in markdown
     pub raw_content: String,
+    /// A hash of the whole file of the page, front matter included
+    pub source_hash: u64,
     /// All the non-md files we found next to the .md file
     pub assets: Vec<PathBuf>,
     /// All the non-md files we found next to the .md file
     pub serialized_assets: Vec<String>,
-    /// The HTML rendered of the page
+    /// The HTML rendered of the page. Empty once moved to disk by `spill_content`, use
+    /// `content()` to read it
     pub content: String,
+    /// Where the rendered HTML was written by `spill_content` in `low_memory` builds
+    pub spilled_content: Option<PathBuf>,
     /// The slug of that page.
     /// First tries to find the slug in the meta and defaults to filename otherwise
     pub slug: String,
     /// The URL path of the page, always starting with a slash
-    pub path: String,
+    pub path: Arc<str>,
     /// The components of the path of the page
-    pub components: Vec<String>,
+    pub components: Arc<[String]>,
     /// The full URL for that page
-    pub permalink: String,
+    pub permalink: Arc<str>,
     /// The summary for the article, defaults to None
     /// When <!-- more --> is found in the text, will take the content up to that part
     /// as summary
     pub summary: Option<String>,
+    /// Whether the summary is only part of the content, either because of the <!-- more -->
+    /// or because it was made with `auto_summary_length` words of it
+    pub summary_truncated: bool,
     /// The previous page when sorting: earlier/earlier_updated/lighter/prev
     pub lower: Option<PathBuf>,
     /// The next page when sorting: later/later_updated/heavier/next
@@ -71,17 +91,46 @@ pub struct Page {
     /// How long would it take to read the raw content.
     /// See `get_reading_analytics` on how it is calculated
     pub reading_time: Option<usize>,
+    /// The summary without its HTML, or the start of the text of the content if there is no
+    /// summary. Empty for protected pages
+    pub excerpt_plain: String,
+    /// The `description` of the front matter, falling back to `excerpt_plain`
+    pub description_resolved: String,
     /// The language of that page. Equal to the default lang if the user doesn't setup `languages` in config.
     /// Corresponds to the lang in the {slug}.{lang}.md file scheme
     pub lang: String,
     /// Contains all the translated version of that page
     pub translations: Vec<PathBuf>,
+    /// The pages sharing the most taxonomy terms with that one, the most related first
+    pub related: Vec<PathBuf>,
     /// The list of all internal links (as path to markdown file), with optional anchor fragments.
     /// We can only check the anchor after all pages have been built and their ToC compiled.
     /// The page itself should exist otherwise it would have errored before getting there.
     pub internal_links: Vec<(String, Option<String>)>,
     /// The list of all links to external webpages. They can be validated by the `link_checker`.
     pub external_links: Vec<String>,
+    /// The absolute links to the site that don't use its `base_url`, with what they should be
+    /// replaced with. Reported with the `self_links_level` of the `link_checker`.
+    pub mixed_self_links: Vec<(String, String)>,
+    /// The names of the shortcodes used in the content, to render it again when one of them changes
+    pub shortcodes: Vec<String>,
+    /// The name of the month of the page date, translated in the page language if possible
+    pub month_name: Option<String>,
+    /// The name of the day of the week of the page date, translated in the page language if possible
+    pub weekday: Option<String>,
+    /// The content of the `data.{toml,yaml,json}` file found next to the `index.md` file, if any
+    pub data: Option<Value>,
+    /// The last commit of that file, if `git_metadata` is enabled
+    pub git: Option<GitMetadata>,
+    /// The `extra` of the config and of the ancestor sections, outermost first, merged with the
+    /// `extra` of the page
+    pub effective_extra: Map<String, Value>,
+    /// The password the content is encrypted with if the page is `protected`
+    pub password: Option<String>,
+    /// Whether the date of the page is after the time it was loaded
+    pub future: bool,
+    /// The `canonical_url` of the front matter, with the internal link resolved
+    pub canonical_url: Option<String>,
 }
 
 impl Page {
@@ -100,28 +149,37 @@ impl Page {
         config: &Config,
         base_path: &Path,
     ) -> Result<Page> {
+        let source_hash = hash_source(content);
         let (meta, content) = split_page_content(file_path, content)?;
         let mut page = Page::new(file_path, meta, base_path);
+        page.source_hash = source_hash;
 
         page.lang =
             page.file.find_language(&config.default_language, &config.other_languages_codes())?;
 
+        if let Some(ref protected) = page.meta.protected {
+            page.password = match (&protected.password, &protected.name) {
+                (Some(password), _) => Some(password.clone()),
+                (None, Some(name)) => match config.passwords.get(name) {
+                    Some(password) => Some(password.clone()),
+                    None => bail!(
+                        "Page `{}` is protected with the password `{}` but it isn't in the `passwords` of the config",
+                        file_path.display(),
+                        name
+                    ),
+                },
+                (None, None) => None,
+            };
+        }
+
         page.raw_content = content.to_string();
-        let (word_count, reading_time) = get_reading_analytics(&page.raw_content);
+        let (word_count, reading_time) = get_reading_analytics(&page.raw_content, config);
         page.word_count = Some(word_count);
-        page.reading_time = Some(reading_time);
+        page.reading_time = Some(page.meta.reading_time.unwrap_or(reading_time));
 
         let mut slug_from_dated_filename = None;
 
-        let file_path_for_slug = if page.file.name == "index" {
-            if let Some(parent) = page.file.path.parent() {
-                parent.file_name().unwrap().to_str().unwrap().to_string()
-            } else {
-                page.file.name.to_string()
-            }
-        } else {
-            page.file.name.to_string()
-        };
+        let file_path_for_slug = page.name_for_slug();
 
         if let Some(ref caps) = RFC3339_DATE.captures(&file_path_for_slug) {
             if !config.slugify.paths_keep_dates {
@@ -133,7 +191,15 @@ impl Page {
             }
         }
 
-        page.slug = {
+        page.meta.apply_default_timezone(config);
+        page.future = page.meta.datetime.is_some_and(|d| d > config.build_info.time());
+
+        if let Some(dt) = page.meta.datetime {
+            page.month_name = Some(config.get_month_name(&page.lang, dt.month()));
+            page.weekday = Some(config.get_weekday_name(&page.lang, dt.weekday()));
+        }
+
+        let slug = {
             if let Some(ref slug) = page.meta.slug {
                 slugify_paths(slug, config.slugify.paths)
             } else if let Some(slug) = slug_from_dated_filename {
@@ -142,8 +208,53 @@ impl Page {
                 slugify_paths(&file_path_for_slug, config.slugify.paths)
             }
         };
+        page.set_slug(slug, config);
+
+        Ok(page)
+    }
+
+    pub fn find_language(&mut self) {}
+
+    /// The name the slug is made from: the filename, or the directory name for an `index.md`
+    fn name_for_slug(&self) -> String {
+        if self.file.name == "index" {
+            if let Some(parent) = self.file.path.parent() {
+                parent.file_name().unwrap().to_str().unwrap().to_string()
+            } else {
+                self.file.name.to_string()
+            }
+        } else {
+            self.file.name.to_string()
+        }
+    }
+
+    /// Takes the weight of a page without one from the number its filename starts with, like
+    /// `03_setup.md`, which is removed from its slug unless it is set in the front matter.
+    /// Returns whether the page got a weight that way.
+    pub fn set_weight_from_filename(&mut self, config: &Config) -> bool {
+        let name = self.name_for_slug();
+        if self.meta.weight.is_some() || RFC3339_DATE.is_match(&name) {
+            return false;
+        }
+        let (weight, slug) = match WEIGHT_PREFIX.captures(&name) {
+            Some(caps) => match caps["weight"].parse() {
+                Ok(weight) => (weight, caps["slug"].to_string()),
+                Err(_) => return false,
+            },
+            None => return false,
+        };
+        self.meta.weight = Some(weight);
+        if self.meta.slug.is_none() {
+            self.set_slug(slugify_paths(&slug, config.slugify.paths), config);
+        }
+        true
+    }
 
-        page.path = if let Some(ref p) = page.meta.path {
+    /// Sets the slug of the page and updates its path and permalink with it
+    pub fn set_slug(&mut self, slug: String, config: &Config) {
+        self.slug = slug;
+
+        let mut path = if let Some(ref p) = self.meta.path {
             let path = p.trim();
 
             if path.starts_with('/') {
@@ -152,50 +263,62 @@ impl Page {
                 format!("/{}", path)
             }
         } else {
-            let mut path = if page.file.components.is_empty() {
-                if page.file.name == "index" && page.file.colocated_path.is_none() {
+            let mut path = if self.file.components.is_empty() {
+                if self.file.name == "index" && self.file.colocated_path.is_none() {
                     String::new()
                 } else {
-                    page.slug.clone()
+                    self.slug.clone()
                 }
             } else {
-                format!("{}/{}", page.file.components.join("/"), page.slug)
+                format!("{}/{}", self.file.components.join("/"), self.slug)
             };
 
-            if page.lang != config.default_language {
-                path = format!("{}/{}", page.lang, path);
+            if self.lang != config.default_language {
+                path = format!("{}/{}", self.lang, path);
             }
 
             format!("/{}", path)
         };
 
-        if !page.path.ends_with('/') {
-            page.path = format!("{}/", page.path);
+        if !path.ends_with('/') {
+            path.push('/');
         }
 
-        page.components = page
-            .path
-            .split('/')
-            .map(|p| p.to_string())
-            .filter(|p| !p.is_empty())
-            .collect::<Vec<_>>();
-        page.permalink = config.make_permalink(&page.path);
-
-        Ok(page)
+        self.components =
+            path.split('/').filter(|p| !p.is_empty()).map(|p| p.to_string()).collect();
+        self.permalink = config.make_permalink(&path).into();
+        self.path = path.into();
     }
 
-    pub fn find_language(&mut self) {}
-
     /// Read and parse a .md file into a Page struct
     pub fn from_file<P: AsRef<Path>>(path: P, config: &Config, base_path: &Path) -> Result<Page> {
         let path = path.as_ref();
-        let content = read_file(path)?;
+        let content = read_content_file(path)?;
         let mut page = Page::parse(path, &content, config, base_path)?;
 
         if page.file.name == "index" {
             let parent_dir = path.parent().unwrap();
             page.assets = find_related_assets(parent_dir, config, true);
-            page.serialized_assets = page.serialize_assets(base_path);
+            let data_files: Vec<_> = PAGE_DATA_FILENAMES
+                .iter()
+                .map(|f| parent_dir.join(f))
+                .filter(|p| p.is_file())
+                .collect();
+            if data_files.len() > 1 {
+                bail!(
+                    "Page {} has several data files, only one is allowed: {:?}",
+                    path.display(),
+                    data_files
+                );
+            }
+            if let Some(data_file) = data_files.first() {
+                page.data = Some(load_data_file(data_file)?);
+                page.assets.retain(|a| a != data_file);
+            }
+            page.serialized_assets = page.serialize_assets(base_path, config);
+        } else if config.colocate_loose_assets {
+            page.assets = find_loose_assets(path, &page.file.name, config);
+            page.serialized_assets = page.serialize_assets(base_path, config);
         } else {
             page.assets = vec![];
         }
@@ -207,15 +330,17 @@ impl Page {
     /// so that can't happen at the same time as parsing
     pub fn render_markdown(
         &mut self,
-        permalinks: &HashMap<String, String>,
+        permalinks: &HashMap<String, Arc<str>>,
         tera: &Tera,
         config: &Config,
+        data: &Value,
         anchor_insert: InsertAnchor,
         shortcode_definitions: &HashMap<String, ShortcodeDefinition>,
     ) -> Result<()> {
         let mut context = RenderContext::new(
             tera,
             config,
+            data,
             &self.lang,
             &self.permalink,
             permalinks,
@@ -223,40 +348,151 @@ impl Page {
         );
         context.set_shortcode_definitions(shortcode_definitions);
         context.set_current_page_path(&self.file.relative);
+        if let Some(heading_offset) = self.meta.heading_offset {
+            context.set_heading_offset(heading_offset);
+        }
+        if let Some(highlight_code) = self.meta.highlight_code {
+            context.set_highlight_code(highlight_code);
+        }
+        context.set_anchor_aliases(&self.meta.anchor_aliases);
+        self.canonical_url = match self.meta.canonical_url {
+            Some(ref url) if url.starts_with("@/") => {
+                let resolved = resolve_internal_link(url, permalinks).with_context(|| {
+                    format!("The `canonical_url` of {} is invalid", self.file.path.display())
+                })?;
+                Some(resolved.permalink)
+            }
+            ref url => url.clone(),
+        };
         context.tera_context.insert("page", &SerializingPage::new(self, None, false));
 
-        let res = render_content(&self.raw_content, &context)
-            .with_context(|| format!("Failed to render content of {}", self.file.path.display()))?;
+        let res = match config.markup.external_command(&self.file.path) {
+            Some(command) => render_external(&self.raw_content, command, &self.file.path),
+            None => render_content(&self.raw_content, &context),
+        }
+        .with_context(|| format!("Failed to render content of {}", self.file.path.display()))?;
 
+        self.summary_truncated = res.summary.is_some();
         self.summary = res.summary;
+        if let (None, Some(words)) = (&self.summary, config.auto_summary_length) {
+            let (summary, truncated) = auto_summary(&res.body, words);
+            self.summary = Some(summary);
+            self.summary_truncated = truncated;
+        }
+        if self.password.is_some() {
+            self.summary = None;
+            self.summary_truncated = false;
+        }
         self.content = res.body;
+        self.excerpt_plain = if self.password.is_some() {
+            String::new()
+        } else {
+            get_plain_excerpt(self.summary.as_deref(), &self.content, config.description_length)
+        };
+        self.description_resolved =
+            self.meta.description.clone().unwrap_or_else(|| self.excerpt_plain.clone());
         self.toc = res.toc;
         self.external_links = res.external_links;
+        self.mixed_self_links = res.mixed_self_links;
         self.internal_links = res.internal_links;
+        self.shortcodes = res.shortcodes;
 
         Ok(())
     }
 
-    /// Renders the page using the default layout, unless specified in front-matter
-    pub fn render_html(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
+    /// Renders the page using the default layout, unless specified in front-matter.
+    /// The HTML of a protected page is encrypted and given to the `protected.html` template,
+    /// which decrypts it in the browser once the password is entered.
+    pub fn render_html(
+        &self,
+        tera: &Tera,
+        config: &Config,
+        data: &Value,
+        library: &Library,
+    ) -> Result<String> {
         let tpl_name = match self.meta.template {
             Some(ref l) => l,
             None => "page.html",
         };
+        let password = match self.password {
+            Some(ref p) => p,
+            None => return self.render_template(tpl_name, tera, config, data, library),
+        };
+
+        let mut page = self.serialize(library);
+        page.reveal_content(self);
+        let html = self.render_with_context(tpl_name, tera, config, data, library, page, |c| c)?;
+        // The path keeps 2 pages with the same content and password from being encrypted the same way
+        let seed = config.build_info.reproducible.then_some(self.file.relative.as_str());
+        let encrypted = encrypt(&html, password, seed)
+            .with_context(|| format!("Failed to encrypt page '{}'", self.file.path.display()))?;
+        self.render_with_context(
+            "protected.html",
+            tera,
+            config,
+            data,
+            library,
+            self.serialize(library),
+            |mut c| {
+                c.insert("protected", &encrypted);
+                c
+            },
+        )
+    }
+
+    /// Renders the given template with the context of the page, used for its HTML and for
+    /// its `page_output_formats`
+    pub fn render_template(
+        &self,
+        tpl_name: &str,
+        tera: &Tera,
+        config: &Config,
+        data: &Value,
+        library: &Library,
+    ) -> Result<String> {
+        let page = self.serialize(library);
+        self.render_with_context(tpl_name, tera, config, data, library, page, |c| c)
+    }
 
-        let mut context = TeraContext::new();
-        context.insert("config", &config.serialize(&self.lang));
+    #[allow(clippy::too_many_arguments)]
+    fn render_with_context(
+        &self,
+        tpl_name: &str,
+        tera: &Tera,
+        config: &Config,
+        data: &Value,
+        library: &Library,
+        page: SerializingPage,
+        additional_context_fn: impl Fn(TeraContext) -> TeraContext,
+    ) -> Result<String> {
+        let mut context = config.template_context(&self.lang, data);
         context.insert("current_url", &self.permalink);
         context.insert("current_path", &self.path);
-        context.insert("page", &self.serialize(library));
-        context.insert("lang", &self.lang);
+        context.insert("page", &page);
+        context.insert("site", &library.site_index);
 
-        render_template(tpl_name, tera, context, &config.theme)
-            .with_context(|| format!("Failed to render page '{}'", self.file.path.display()))
+        render_template_with_options(
+            tpl_name,
+            tera,
+            additional_context_fn(context),
+            &config.theme,
+            config.missing_extra_is_error,
+        )
+        .with_context(|| format!("Failed to render page '{}'", self.file.path.display()))
     }
 
     /// Creates a vectors of asset URLs.
-    fn serialize_assets(&self, base_path: &Path) -> Vec<String> {
+    fn serialize_assets(&self, base_path: &Path, config: &Config) -> Vec<String> {
+        // The assets of a page that isn't an `index.md` are copied next to its `index.html`
+        if self.file.colocated_path.is_none() {
+            return self
+                .assets
+                .iter()
+                .filter_map(|asset| asset.file_name().and_then(|f| f.to_str()))
+                .map(|filename| config.make_asset_link(&format!("{}{}", self.path, filename)))
+                .collect();
+        }
+
         self.assets
             .iter()
             .filter_map(|asset| asset.strip_prefix(self.file.path.parent().unwrap()).ok())
@@ -273,7 +509,7 @@ impl Page {
                     .to_path_buf();
                 path
             })
-            .map(|path| format!("/{}", path.display()))
+            .map(|path| config.make_asset_link(&format!("/{}", path.display())))
             .collect()
     }
 
@@ -281,8 +517,50 @@ impl Page {
         has_anchor(&self.toc, anchor)
     }
 
-    pub fn has_anchor_id(&self, id: &str) -> bool {
-        has_anchor_id(&self.content, id)
+    pub fn has_anchor_id(&self, id: &str) -> Result<bool> {
+        Ok(has_anchor_id(&self.content()?, id))
+    }
+
+    /// The HTML rendered of the page, read back from disk if it was spilled there
+    pub fn content(&self) -> Result<Cow<'_, str>> {
+        match self.spilled_content {
+            Some(ref path) => read_file(path).map(Cow::Owned).with_context(|| {
+                format!("Failed to read back the content of {}", self.file.path.display())
+            }),
+            None => Ok(Cow::Borrowed(&self.content)),
+        }
+    }
+
+    /// Writes the rendered HTML to a file of the given directory and frees it from memory,
+    /// for the `low_memory` builds. `content()` reads it back when needed
+    pub fn spill_content(&mut self, dir: &Path) -> Result<()> {
+        let mut hasher = DefaultHasher::new();
+        self.file.path.hash(&mut hasher);
+        let path = dir.join(format!("{:016x}.html", hasher.finish()));
+        create_file(&path, &self.content)?;
+        self.content = String::new();
+        self.spilled_content = Some(path);
+        Ok(())
+    }
+
+    /// A hash of what the templates can see of the page, to find out whether it changed between
+    /// two loads of the site
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.source_hash.hash(&mut hasher);
+        self.permalink.hash(&mut hasher);
+        self.content.hash(&mut hasher);
+        self.summary.hash(&mut hasher);
+        self.ancestors.hash(&mut hasher);
+        self.translations.hash(&mut hasher);
+        self.lower.hash(&mut hasher);
+        self.higher.hash(&mut hasher);
+        self.related.hash(&mut hasher);
+        self.serialized_assets.hash(&mut hasher);
+        self.git.hash(&mut hasher);
+        self.data.as_ref().map(|d| d.to_string()).hash(&mut hasher);
+        libs::serde_json::to_string(&self.effective_extra).unwrap_or_default().hash(&mut hasher);
+        hasher.finish()
     }
 
     pub fn serialize<'a>(&'a self, library: &'a Library) -> SerializingPage<'a> {
@@ -297,19 +575,35 @@ impl Page {
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
-    use std::fs::{create_dir, File};
+    use std::fs::{create_dir, create_dir_all, File};
     use std::io::Write;
     use std::path::{Path, PathBuf};
 
     use libs::globset::{Glob, GlobSetBuilder};
-    use libs::tera::Tera;
+    use libs::tera::{to_value, Tera, Value};
     use tempfile::tempdir;
 
-    use crate::Page;
+    use crate::ser::SerializingPage;
+    use crate::{Library, Page};
     use config::{Config, LanguageOptions};
     use utils::slugs::SlugifyStrategy;
+    use utils::templates::get_shortcodes;
     use utils::types::InsertAnchor;
 
+    #[test]
+    fn can_override_reading_time() {
+        let mut config = Config::default_for_test();
+        config.reading_speed = 1;
+        let content = "+++\n+++\nHello world";
+        let page = Page::parse(Path::new("post.md"), content, &config, &PathBuf::new()).unwrap();
+        assert_eq!(page.reading_time, Some(2));
+
+        let content = "+++\nreading_time = 12\n+++\nHello world";
+        let page = Page::parse(Path::new("post.md"), content, &config, &PathBuf::new()).unwrap();
+        assert_eq!(page.word_count, Some(2));
+        assert_eq!(page.reading_time, Some(12));
+    }
+
     #[test]
     fn can_parse_a_valid_page() {
         let config = Config::default_for_test();
@@ -327,6 +621,7 @@ Hello world"#;
             &HashMap::default(),
             &Tera::default(),
             &config,
+            &Value::Null,
             InsertAnchor::None,
             &HashMap::new(),
         )
@@ -338,6 +633,80 @@ Hello world"#;
         assert_eq!(page.content, "<p>Hello world</p>\n".to_string());
     }
 
+    #[test]
+    fn can_spill_content_to_disk() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let mut page = Page { content: "<p>Hello world</p>\n".to_string(), ..Page::default() };
+        page.spill_content(tmp_dir.path()).unwrap();
+
+        assert!(page.content.is_empty());
+        assert!(page.spilled_content.as_ref().unwrap().starts_with(tmp_dir.path()));
+        assert_eq!(page.content().unwrap(), "<p>Hello world</p>\n");
+
+        // Only read back for the page itself, not when it is listed
+        let library = Library::default();
+        let serialized = to_value(page.serialize(&library)).unwrap();
+        assert_eq!(serialized["content"], "<p>Hello world</p>\n");
+        let listed = to_value(SerializingPage::for_listing(&page, &library, false)).unwrap();
+        assert_eq!(listed["content"], "");
+    }
+
+    #[test]
+    fn can_override_heading_offset_in_front_matter() {
+        let mut config = Config::default_for_test();
+        config.markdown.heading_offset = 2;
+        let content = r#"
++++
+title = "Hello"
+heading_offset = 1
++++
+# Intro"#;
+        let mut page =
+            Page::parse(Path::new("post.md"), content, &config, &PathBuf::new()).unwrap();
+        page.render_markdown(
+            &HashMap::default(),
+            &Tera::default(),
+            &config,
+            &Value::Null,
+            InsertAnchor::None,
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(page.content, "<h2 id=\"intro\">Intro</h2>\n");
+        assert_eq!(page.toc[0].level, 2);
+    }
+
+    #[test]
+    fn can_disable_highlighting_in_front_matter() {
+        let mut config = Config::default_for_test();
+        config.markdown.highlight_code = true;
+        let content = r#"
++++
+title = "Hello"
+highlight_code = false
++++
+```rust
+let a = 1;
+```"#;
+        let mut page =
+            Page::parse(Path::new("post.md"), content, &config, &PathBuf::new()).unwrap();
+        page.render_markdown(
+            &HashMap::default(),
+            &Tera::default(),
+            &config,
+            &Value::Null,
+            InsertAnchor::None,
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            page.content,
+            "<pre data-lang=\"rust\" class=\"language-rust \"><code class=\"language-rust\" data-lang=\"rust\">let a = 1;\n</code></pre>\n"
+        );
+    }
+
     #[test]
     fn can_parse_author() {
         let config = Config::default_for_test();
@@ -355,6 +724,7 @@ Hello world"#;
             &HashMap::default(),
             &Tera::default(),
             &config,
+            &Value::Null,
             InsertAnchor::None,
             &HashMap::new(),
         )
@@ -377,9 +747,9 @@ Hello world"#;
             Page::parse(Path::new("content/posts/intro/start.md"), content, &conf, &PathBuf::new());
         assert!(res.is_ok());
         let page = res.unwrap();
-        assert_eq!(page.path, "/posts/intro/hello-world/");
-        assert_eq!(page.components, vec!["posts", "intro", "hello-world"]);
-        assert_eq!(page.permalink, "http://hello.com/posts/intro/hello-world/");
+        assert_eq!(&*page.path, "/posts/intro/hello-world/");
+        assert_eq!(*page.components, ["posts", "intro", "hello-world"]);
+        assert_eq!(&*page.permalink, "http://hello.com/posts/intro/hello-world/");
     }
 
     #[test]
@@ -393,9 +763,9 @@ Hello world"#;
         let res = Page::parse(Path::new("start.md"), content, &config, &PathBuf::new());
         assert!(res.is_ok());
         let page = res.unwrap();
-        assert_eq!(page.path, "/hello-world/");
-        assert_eq!(page.components, vec!["hello-world"]);
-        assert_eq!(page.permalink, config.make_permalink("hello-world"));
+        assert_eq!(&*page.path, "/hello-world/");
+        assert_eq!(*page.components, ["hello-world"]);
+        assert_eq!(&*page.permalink, config.make_permalink("hello-world"));
     }
 
     #[test]
@@ -410,9 +780,9 @@ Hello world"#;
         let res = Page::parse(Path::new("start.md"), content, &config, &PathBuf::new());
         assert!(res.is_ok());
         let page = res.unwrap();
-        assert_eq!(page.path, "/hello-world/");
-        assert_eq!(page.components, vec!["hello-world"]);
-        assert_eq!(page.permalink, config.make_permalink("hello-world"));
+        assert_eq!(&*page.path, "/hello-world/");
+        assert_eq!(*page.components, ["hello-world"]);
+        assert_eq!(&*page.permalink, config.make_permalink("hello-world"));
     }
 
     #[test]
@@ -427,9 +797,9 @@ Hello world"#;
         let res = Page::parse(Path::new("start.md"), content, &config, &PathBuf::new());
         assert!(res.is_ok());
         let page = res.unwrap();
-        assert_eq!(page.path, "/日本/");
-        assert_eq!(page.components, vec!["日本"]);
-        assert_eq!(page.permalink, config.make_permalink("日本"));
+        assert_eq!(&*page.path, "/日本/");
+        assert_eq!(*page.components, ["日本"]);
+        assert_eq!(&*page.permalink, config.make_permalink("日本"));
     }
 
     #[test]
@@ -448,9 +818,9 @@ Hello world"#;
         );
         assert!(res.is_ok());
         let page = res.unwrap();
-        assert_eq!(page.path, "/hello-world/");
-        assert_eq!(page.components, vec!["hello-world"]);
-        assert_eq!(page.permalink, config.make_permalink("hello-world"));
+        assert_eq!(&*page.path, "/hello-world/");
+        assert_eq!(*page.components, ["hello-world"]);
+        assert_eq!(&*page.permalink, config.make_permalink("hello-world"));
     }
 
     #[test]
@@ -469,8 +839,8 @@ Hello world"#;
         );
         assert!(res.is_ok());
         let page = res.unwrap();
-        assert_eq!(page.path, "/hello-world/");
-        assert_eq!(page.permalink, config.make_permalink("hello-world"));
+        assert_eq!(&*page.path, "/hello-world/");
+        assert_eq!(&*page.permalink, config.make_permalink("hello-world"));
     }
 
     #[test]
@@ -495,7 +865,7 @@ Hello world"#;
         assert!(res.is_ok());
         let page = res.unwrap();
         assert_eq!(page.slug, "file-with-space");
-        assert_eq!(page.permalink, config.make_permalink(&page.slug));
+        assert_eq!(&*page.permalink, config.make_permalink(&page.slug));
     }
 
     #[test]
@@ -506,7 +876,7 @@ Hello world"#;
         assert!(res.is_ok());
         let page = res.unwrap();
         assert_eq!(page.slug, "日本");
-        assert_eq!(page.permalink, config.make_permalink(&page.slug));
+        assert_eq!(&*page.permalink, config.make_permalink(&page.slug));
     }
 
     #[test]
@@ -525,6 +895,7 @@ Hello world
             &HashMap::default(),
             &Tera::default(),
             &config,
+            &Value::Null,
             InsertAnchor::None,
             &HashMap::new(),
         )
@@ -532,6 +903,77 @@ Hello world
         assert_eq!(page.summary, Some("<p>Hello world</p>\n".to_string()));
     }
 
+    #[test]
+    fn can_make_summary_from_the_first_words() {
+        let mut config = Config::default_for_test();
+        config.auto_summary_length = Some(5);
+        let render = |content: &str| {
+            let mut page =
+                Page::parse(Path::new("hello.md"), content, &config, &PathBuf::new()).unwrap();
+            page.render_markdown(
+                &HashMap::default(),
+                &Tera::default(),
+                &config,
+                &Value::Null,
+                InsertAnchor::None,
+                &HashMap::new(),
+            )
+            .unwrap();
+            (page.summary.unwrap(), page.summary_truncated)
+        };
+
+        assert_eq!(
+            render("+++\n+++\nHello world[^1].\n\nSecond *paragraph* is long.\n\n[^1]: Note"),
+            ("<p>Hello world.</p>".to_string(), true)
+        );
+        assert_eq!(render("+++\n+++\nHello world"), ("<p>Hello world</p>\n".to_string(), false));
+        // The <!-- more --> takes precedence
+        assert_eq!(
+            render("+++\n+++\nHello\n<!-- more -->\nworld"),
+            ("<p>Hello</p>\n".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn can_resolve_description() {
+        let mut config = Config::default_for_test();
+        config.description_length = 20;
+        let mut tera = Tera::default();
+        tera.add_raw_template("shortcodes/note.html", "<aside><b>Note</b>: {{ text }}</aside>")
+            .unwrap();
+        let shortcodes = get_shortcodes(&tera);
+        let render = |content: &str| {
+            let mut page =
+                Page::parse(Path::new("hello.md"), content, &config, &PathBuf::new()).unwrap();
+            page.render_markdown(
+                &HashMap::default(),
+                &tera,
+                &config,
+                &Value::Null,
+                InsertAnchor::None,
+                &shortcodes,
+            )
+            .unwrap();
+            (page.excerpt_plain, page.description_resolved)
+        };
+
+        assert_eq!(
+            render("+++\ndescription = \"Mine\"\n+++\nSome *text*"),
+            ("Some text".to_string(), "Mine".to_string())
+        );
+        // The summary is used in full
+        let (excerpt, description) =
+            render("+++\n+++\nTom &amp; <em>Jerry</em> are <b>friends</b>\n<!-- more -->\nRest");
+        assert_eq!(excerpt, "Tom & Jerry are friends");
+        assert_eq!(description, excerpt);
+        // Otherwise the start of the text, shortcodes included
+        let (excerpt, description) =
+            render("+++\n+++\n{{ note(text=\"a <tag>\") }}\n\nThen the rest of the page");
+        assert_eq!(excerpt, "Note: a <tag> Then…");
+        assert_eq!(description, excerpt);
+        assert_eq!(render("+++\n+++\n"), (String::new(), String::new()));
+    }
+
     #[test]
     fn strips_footnotes_in_summary() {
         let config = Config::default_for_test();
@@ -559,6 +1001,7 @@ And here's another. [^3]
             &HashMap::default(),
             &Tera::default(),
             &config,
+            &Value::Null,
             InsertAnchor::None,
             &HashMap::new(),
         )
@@ -590,7 +1033,7 @@ And here's another. [^3]
         assert_eq!(page.slug, "with-assets");
         assert_eq!(page.assets.len(), 3);
         assert!(page.serialized_assets[0].starts_with('/'));
-        assert_eq!(page.permalink, "http://a-website.com/posts/with-assets/");
+        assert_eq!(&*page.permalink, "http://a-website.com/posts/with-assets/");
     }
 
     #[test]
@@ -613,7 +1056,7 @@ And here's another. [^3]
         assert_eq!(page.file.parent, path.join("content").join("posts"));
         assert_eq!(page.slug, "hey");
         assert_eq!(page.assets.len(), 3);
-        assert_eq!(page.permalink, "http://a-website.com/posts/hey/");
+        assert_eq!(&*page.permalink, "http://a-website.com/posts/hey/");
     }
 
     // https://github.com/getzola/zola/issues/674
@@ -639,7 +1082,26 @@ And here's another. [^3]
         assert_eq!(page.serialized_assets.len(), 3);
         // We should not get with-assets since that's the slugified version
         assert!(page.serialized_assets[0].contains("with_assets"));
-        assert_eq!(page.permalink, "http://a-website.com/posts/with-assets/");
+        assert_eq!(&*page.permalink, "http://a-website.com/posts/with-assets/");
+    }
+
+    #[test]
+    fn page_assets_are_linked_from_the_asset_base_url() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let path = tmp_dir.path();
+        let nested_path = path.join("content").join("posts").join("with-assets");
+        create_dir_all(&nested_path).expect("create nested temp dir");
+        let mut f = File::create(nested_path.join("index.md")).unwrap();
+        f.write_all(b"+++\n+++\n").unwrap();
+        File::create(nested_path.join("graph.jpg")).unwrap();
+        let mut config = Config::default();
+        config.asset_base_url = Some("https://cdn.example.com".to_string());
+
+        let page = Page::from_file(nested_path.join("index.md").as_path(), &config, path).unwrap();
+        assert_eq!(
+            page.serialized_assets,
+            vec!["https://cdn.example.com/posts/with-assets/graph.jpg".to_string()]
+        );
     }
 
     // https://github.com/getzola/zola/issues/607
@@ -664,7 +1126,7 @@ And here's another. [^3]
         assert_eq!(page.slug, "with-assets");
         assert_eq!(page.meta.date, Some("2013-06-02".to_string()));
         assert_eq!(page.assets.len(), 3);
-        assert_eq!(page.permalink, "http://a-website.com/posts/with-assets/");
+        assert_eq!(&*page.permalink, "http://a-website.com/posts/with-assets/");
     }
 
     #[test]
@@ -694,6 +1156,46 @@ And here's another. [^3]
         assert_eq!(page.assets[0].file_name().unwrap().to_str(), Some("graph.jpg"));
     }
 
+    #[test]
+    fn page_with_data_file_gets_data_and_not_asset() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let path = tmp_dir.path();
+        create_dir(path.join("content")).expect("create content temp dir");
+        let nested_path = path.join("content").join("product");
+        create_dir(&nested_path).expect("create nested temp dir");
+        let mut f = File::create(nested_path.join("index.md")).unwrap();
+        f.write_all(b"+++\n+++\n").unwrap();
+        let mut f = File::create(nested_path.join("data.toml")).unwrap();
+        f.write_all(b"[specs]\nweight = 10\nreleased = 2024-01-01\n").unwrap();
+        File::create(nested_path.join("graph.jpg")).unwrap();
+
+        let res = Page::from_file(nested_path.join("index.md").as_path(), &Config::default(), path);
+        assert!(res.is_ok());
+        let page = res.unwrap();
+        assert_eq!(page.assets.len(), 1);
+        assert_eq!(page.assets[0].file_name().unwrap().to_str(), Some("graph.jpg"));
+        let data = page.data.unwrap();
+        assert_eq!(data["specs"]["weight"], 10);
+        assert_eq!(data["specs"]["released"], "2024-01-01");
+    }
+
+    #[test]
+    fn page_with_invalid_data_file_errors_with_filename() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let path = tmp_dir.path();
+        create_dir(path.join("content")).expect("create content temp dir");
+        let nested_path = path.join("content").join("product");
+        create_dir(&nested_path).expect("create nested temp dir");
+        let mut f = File::create(nested_path.join("index.md")).unwrap();
+        f.write_all(b"+++\n+++\n").unwrap();
+        let mut f = File::create(nested_path.join("data.yaml")).unwrap();
+        f.write_all(b"specs: [").unwrap();
+
+        let res = Page::from_file(nested_path.join("index.md").as_path(), &Config::default(), path);
+        assert!(res.is_err());
+        assert!(format!("{:?}", res.unwrap_err()).contains("data.yaml"));
+    }
+
     // https://github.com/getzola/zola/issues/1566
     #[test]
     fn colocated_page_with_slug_and_date_in_path() {
@@ -712,7 +1214,7 @@ And here's another. [^3]
         let res = Page::from_file(&file_path, &config, path);
         assert!(res.is_ok());
         let page = res.unwrap();
-        assert_eq!(page.path, "/articles/hey/");
+        assert_eq!(&*page.path, "/articles/hey/");
 
         // then a colocated one, it should still work
         let dir_path = articles_path.join("2021-07-29-sample-article-2.md");
@@ -722,7 +1224,7 @@ And here's another. [^3]
         let res = Page::from_file(&dir_path.join("index.md"), &config, path);
         assert!(res.is_ok());
         let page = res.unwrap();
-        assert_eq!(page.path, "/articles/ho/");
+        assert_eq!(&*page.path, "/articles/ho/");
     }
 
     #[test]
@@ -742,6 +1244,27 @@ Hello world
         assert_eq!(page.slug, "hello");
     }
 
+    #[test]
+    fn can_get_translated_date_names() {
+        let config = Config::parse(
+            r#"
+base_url = "https://example.com"
+default_language = "fr"
+
+[translations]
+october = "octobre"
+"#,
+        )
+        .unwrap();
+        let res =
+            Page::parse(Path::new("2018-10-08_hello.md"), "+++\n+++\n", &config, &PathBuf::new());
+        assert!(res.is_ok());
+        let page = res.unwrap();
+
+        assert_eq!(page.month_name, Some("octobre".to_string()));
+        assert_eq!(page.weekday, Some("Monday".to_string()));
+    }
+
     // https://github.com/getzola/zola/pull/1323#issuecomment-779401063
     #[test]
     fn can_get_date_from_short_date_in_filename_respects_slugification_strategy() {
@@ -780,6 +1303,38 @@ Hello world
         assert_eq!(page.slug, "hello");
     }
 
+    #[test]
+    fn can_get_weight_from_filename() {
+        let config = Config::default();
+        let parse = |path: &str, front_matter: &str| {
+            let content = format!("+++\n{}\n+++\nHello", front_matter);
+            Page::parse(Path::new(path), &content, &config, &PathBuf::new()).unwrap()
+        };
+
+        let mut page = parse("content/book/03_setup.md", "");
+        assert!(page.set_weight_from_filename(&config));
+        assert_eq!(page.meta.weight, Some(3));
+        assert_eq!(page.slug, "setup");
+        assert_eq!(&*page.path, "/book/setup/");
+
+        let mut page = parse("content/book/12-first-steps/index.md", "slug = \"start\"");
+        assert!(page.set_weight_from_filename(&config));
+        assert_eq!(page.meta.weight, Some(12));
+        assert_eq!(page.slug, "start");
+
+        // An explicit weight always wins
+        let mut page = parse("content/book/03_setup.md", "weight = 30");
+        assert!(!page.set_weight_from_filename(&config));
+        assert_eq!(page.meta.weight, Some(30));
+        assert_eq!(page.slug, "03-setup");
+
+        for path in ["content/book/setup.md", "content/book/2018-10-08_setup.md"] {
+            let mut page = parse(path, "");
+            assert!(!page.set_weight_from_filename(&config));
+            assert_eq!(page.meta.weight, None);
+        }
+    }
+
     #[test]
     fn can_get_date_from_filename_with_spaces_respects_slugification() {
         let mut config = Config::default();
@@ -877,7 +1432,7 @@ Bonjour le monde"#
         let page = res.unwrap();
         assert_eq!(page.lang, "fr".to_string());
         assert_eq!(page.slug, "hello");
-        assert_eq!(page.permalink, "http://a-website.com/fr/hello/");
+        assert_eq!(&*page.permalink, "http://a-website.com/fr/hello/");
     }
 
     #[test]
@@ -896,7 +1451,7 @@ Bonjour le monde"#
         assert_eq!(page.meta.date, Some("2018-10-08".to_string()));
         assert_eq!(page.lang, "fr".to_string());
         assert_eq!(page.slug, "hello");
-        assert_eq!(page.permalink, "http://a-website.com/fr/hello/");
+        assert_eq!(&*page.permalink, "http://a-website.com/fr/hello/");
     }
 
     #[test]
@@ -914,6 +1469,6 @@ Bonjour le monde"#
         let page = res.unwrap();
         assert_eq!(page.lang, "fr".to_string());
         assert_eq!(page.slug, "hello");
-        assert_eq!(page.permalink, "http://a-website.com/bonjour/");
+        assert_eq!(&*page.permalink, "http://a-website.com/bonjour/");
     }
 }
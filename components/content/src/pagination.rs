@@ -5,15 +5,15 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use errors::{Context as ErrorContext, Result};
-use libs::tera::{to_value, Context, Tera, Value};
-use utils::templates::{check_template_fallbacks, render_template};
+use libs::tera::{to_value, Tera, Value};
+use utils::templates::{check_template_fallbacks, render_template_with_options};
 
 use crate::library::Library;
 use crate::ser::{SectionSerMode, SerializingPage, SerializingSection};
 use crate::taxonomies::{Taxonomy, TaxonomyTerm};
 use crate::Section;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 enum PaginationRoot<'a> {
     Section(&'a Section),
     Taxonomy(&'a Taxonomy, &'a TaxonomyTerm),
@@ -43,7 +43,7 @@ impl<'a> Pager<'a> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Paginator<'a> {
     /// All pages in the section/taxonomy
     all_pages: Cow<'a, [PathBuf]>,
@@ -75,8 +75,8 @@ impl<'a> Paginator<'a> {
             paginate_by,
             paginate_reversed: section.meta.paginate_reversed,
             root: PaginationRoot::Section(section),
-            permalink: section.permalink.clone(),
-            path: section.path.clone(),
+            permalink: section.permalink.to_string(),
+            path: section.path.to_string(),
             paginate_path: section.meta.paginate_path.clone(),
             is_index: section.is_index(),
             template: section.get_template_name().to_string(),
@@ -86,6 +86,14 @@ impl<'a> Paginator<'a> {
         paginator
     }
 
+    /// The `_index.md` of the section being paginated, None for taxonomies
+    pub fn source_file(&self) -> Option<&PathBuf> {
+        match self.root {
+            PaginationRoot::Section(section) => Some(&section.file.path),
+            PaginationRoot::Taxonomy(..) => None,
+        }
+    }
+
     /// Create a new paginator from a taxonomy
     /// It will always at least create one pager (the first) even if there are not enough pages to paginate
     pub fn from_taxonomy(
@@ -133,7 +141,7 @@ impl<'a> Paginator<'a> {
             if !page.meta.render {
                 continue;
             }
-            current_page.push(SerializingPage::new(page, Some(library), false));
+            current_page.push(SerializingPage::for_listing(page, library, false));
 
             if current_page.len() == self.paginate_by {
                 pages.push(current_page);
@@ -222,32 +230,39 @@ impl<'a> Paginator<'a> {
         &self,
         pager: &Pager,
         config: &Config,
+        data: &Value,
         tera: &Tera,
         library: &Library,
     ) -> Result<String> {
-        let mut context = Context::new();
-        match self.root {
+        let mut context = match self.root {
             PaginationRoot::Section(s) => {
+                let mut context = config.template_context(&s.lang, data);
                 context.insert(
                     "section",
                     &SerializingSection::new(s, SectionSerMode::MetadataOnly(library)),
                 );
-                context.insert("lang", &s.lang);
-                context.insert("config", &config.serialize(&s.lang));
+                context
             }
             PaginationRoot::Taxonomy(t, item) => {
+                let mut context = config.template_context(&t.lang, data);
                 context.insert("taxonomy", &t.kind);
                 context.insert("term", &item.serialize(library));
-                context.insert("lang", &t.lang);
-                context.insert("config", &config.serialize(&t.lang));
+                context
             }
         };
         context.insert("current_url", &pager.permalink);
         context.insert("current_path", &pager.path);
         context.insert("paginator", &self.build_paginator_context(pager));
-
-        render_template(&self.template, tera, context, &config.theme)
-            .with_context(|| format!("Failed to render pager {}", pager.index))
+        context.insert("site", &library.site_index);
+
+        render_template_with_options(
+            &self.template,
+            tera,
+            context,
+            &config.theme,
+            config.missing_extra_is_error,
+        )
+        .with_context(|| format!("Failed to render pager {}", pager.index))
     }
 }
 
@@ -267,14 +282,14 @@ mod tests {
 
         let mut s = Section::new("content/_index.md", f, &PathBuf::new());
         if !is_index {
-            s.path = "/posts/".to_string();
-            s.permalink = "https://vincent.is/posts/".to_string();
+            s.path = "/posts/".into();
+            s.permalink = "https://vincent.is/posts/".to_string().into();
             s.file.path = PathBuf::from("posts/_index.md");
             s.file.components = vec!["posts".to_string()];
         } else {
             s.path = "/".into();
             s.file.path = PathBuf::from("_index.md");
-            s.permalink = "https://vincent.is/".to_string();
+            s.permalink = "https://vincent.is/".to_string().into();
         }
         s
     }
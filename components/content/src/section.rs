@@ -1,37 +1,60 @@
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use libs::tera::{Context as TeraContext, Tera};
+use libs::tera::{Map, Tera, Value};
+use serde::Serialize;
 
 use config::Config;
 use errors::{Context, Result};
 use markdown::{render_content, RenderContext};
-use utils::fs::read_file;
+use utils::anchors::has_anchor_id;
+use utils::fs::read_content_file;
 use utils::net::is_external_link;
 use utils::table_of_contents::Heading;
-use utils::templates::{render_template, ShortcodeDefinition};
+use utils::templates::{render_template_with_options, ShortcodeDefinition};
 
 use crate::file_info::FileInfo;
 use crate::front_matter::{split_section_content, SectionFrontMatter};
 use crate::library::Library;
 use crate::ser::{SectionSerMode, SerializingSection};
-use crate::utils::{find_related_assets, get_reading_analytics, has_anchor};
+use crate::utils::{
+    find_related_assets, get_plain_excerpt, get_reading_analytics, has_anchor, hash_source,
+    remove_loose_assets,
+};
+
+/// The reading statistics of the pages of a section, without the drafts
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct SectionStats {
+    /// How many pages the section has, including the ones of its transparent subsections
+    pub pages: usize,
+    /// How many pages the section and all its subsections have
+    pub recursive_pages: usize,
+    /// The sum of the word counts of the pages of the section
+    pub word_count: usize,
+    /// The sum of the reading times of the pages of the section, in minutes
+    pub reading_time: usize,
+}
 
 // Default is used to create a default index section if there is no _index.md in the root content directory
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Section {
     /// All info about the actual file
     pub file: FileInfo,
     /// The front matter meta-data
     pub meta: SectionFrontMatter,
     /// The URL path of the page, always starting with a slash
-    pub path: String,
+    pub path: Arc<str>,
     /// The components for the path of that page
-    pub components: Vec<String>,
+    pub components: Arc<[String]>,
     /// The full URL for that page
-    pub permalink: String,
+    pub permalink: Arc<str>,
     /// The actual content of the page, in markdown
     pub raw_content: String,
+    /// A hash of the whole file of the section, front matter included
+    pub source_hash: u64,
     /// The HTML rendered of the page
     pub content: String,
     /// All the non-md files we found next to the .md file
@@ -46,6 +69,9 @@ pub struct Section {
     pub ancestors: Vec<String>,
     /// All direct subsections
     pub subsections: Vec<PathBuf>,
+    /// The previous and next subsections of the parent section, in the order of `subsections`
+    pub prev_sibling: Option<PathBuf>,
+    pub next_sibling: Option<PathBuf>,
     /// Toc made from the headings of the markdown file
     pub toc: Vec<Heading>,
     /// How many words in the raw content
@@ -53,6 +79,12 @@ pub struct Section {
     /// How long would it take to read the raw content.
     /// See `get_reading_analytics` on how it is calculated
     pub reading_time: Option<usize>,
+    /// The start of the text of the content
+    pub excerpt_plain: String,
+    /// The `description` of the front matter, falling back to `excerpt_plain`
+    pub description_resolved: String,
+    /// The reading statistics of its pages, filled by `Library::populate_sections`
+    pub stats: SectionStats,
     /// The language of that section. Equal to the default lang if the user doesn't setup `languages` in config.
     /// Corresponds to the lang in the _index.{lang}.md file scheme
     pub lang: String,
@@ -62,6 +94,16 @@ pub struct Section {
     pub internal_links: Vec<(String, Option<String>)>,
     /// The list of all links to external webpages. They can be validated by the `link_checker`.
     pub external_links: Vec<String>,
+    /// The absolute links to the site that don't use its `base_url`, with what they should be
+    /// replaced with. Reported with the `self_links_level` of the `link_checker`.
+    pub mixed_self_links: Vec<(String, String)>,
+    /// The names of the shortcodes used in the content, to render it again when one of them changes
+    pub shortcodes: Vec<String>,
+    /// Whether the section was made up for a directory without an `_index.md`
+    pub implicit: bool,
+    /// The `extra` of the config and of the ancestor sections, outermost first, merged with the
+    /// `extra` of the section
+    pub effective_extra: Map<String, Value>,
 }
 
 impl Section {
@@ -81,36 +123,62 @@ impl Section {
         config: &Config,
         base_path: &Path,
     ) -> Result<Section> {
+        let source_hash = hash_source(content);
         let (meta, content) = split_section_content(file_path, content)?;
         let mut section = Section::new(file_path, meta, base_path);
+        section.source_hash = source_hash;
         section.lang = section
             .file
             .find_language(&config.default_language, &config.other_languages_codes())?;
         section.raw_content = content.to_string();
-        let (word_count, reading_time) = get_reading_analytics(&section.raw_content);
+        let (word_count, reading_time) = get_reading_analytics(&section.raw_content, config);
         section.word_count = Some(word_count);
         section.reading_time = Some(reading_time);
+        section.set_path(config);
+        Ok(section)
+    }
+
+    /// Makes up the section of a directory of the `content` directory without an `_index.md`
+    /// for that language. It is titled after the directory and only rendered if
+    /// `implicit_sections_render` is set in the config
+    pub fn implicit(dir: &Path, lang: &str, config: &Config, base_path: &Path) -> Result<Section> {
+        let filename = if lang == config.default_language {
+            "_index.md".to_string()
+        } else {
+            format!("_index.{}.md", lang)
+        };
+        let meta = SectionFrontMatter {
+            title: dir.file_name().map(|name| name.to_string_lossy().to_string()),
+            render: config.implicit_sections_render,
+            ..SectionFrontMatter::default()
+        };
+        let mut section = Section::new(dir.join(filename), meta, base_path);
+        section.lang = section
+            .file
+            .find_language(&config.default_language, &config.other_languages_codes())?;
+        section.implicit = true;
+        section.set_path(config);
+        Ok(section)
+    }
 
-        let path = section.file.components.join("/");
-        let lang_path = if section.lang != config.default_language {
-            format!("/{}", section.lang)
+    /// Sets the URL path, its components and the permalink from the file and the language
+    fn set_path(&mut self, config: &Config) {
+        let path = self.file.components.join("/");
+        let lang_path = if self.lang != config.default_language {
+            format!("/{}", self.lang)
         } else {
             "".into()
         };
-        section.path = if path.is_empty() {
+        let path = if path.is_empty() {
             format!("{}/", lang_path)
         } else {
             format!("{}/{}/", lang_path, path)
         };
 
-        section.components = section
-            .path
-            .split('/')
-            .map(|p| p.to_string())
-            .filter(|p| !p.is_empty())
-            .collect::<Vec<_>>();
-        section.permalink = config.make_permalink(&section.path);
-        Ok(section)
+        self.components =
+            path.split('/').filter(|p| !p.is_empty()).map(|p| p.to_string()).collect();
+        self.permalink = config.make_permalink(&path).into();
+        self.path = path.into();
     }
 
     /// Read and parse a .md file into a Section struct
@@ -120,12 +188,15 @@ impl Section {
         base_path: &Path,
     ) -> Result<Section> {
         let path = path.as_ref();
-        let content = read_file(path)?;
+        let content = read_content_file(path)?;
         let mut section = Section::parse(path, &content, config, base_path)?;
 
         let parent_dir = path.parent().unwrap();
         section.assets = find_related_assets(parent_dir, config, false);
-        section.serialized_assets = section.serialize_assets();
+        if config.colocate_loose_assets {
+            remove_loose_assets(path, &mut section.assets, config);
+        }
+        section.serialized_assets = section.serialize_assets(config);
 
         Ok(section)
     }
@@ -146,14 +217,16 @@ impl Section {
     /// so that can't happen at the same time as parsing
     pub fn render_markdown(
         &mut self,
-        permalinks: &HashMap<String, String>,
+        permalinks: &HashMap<String, Arc<str>>,
         tera: &Tera,
         config: &Config,
+        data: &Value,
         shortcode_definitions: &HashMap<String, ShortcodeDefinition>,
     ) -> Result<()> {
         let mut context = RenderContext::new(
             tera,
             config,
+            data,
             &self.lang,
             &self.permalink,
             permalinks,
@@ -161,6 +234,13 @@ impl Section {
         );
         context.set_shortcode_definitions(shortcode_definitions);
         context.set_current_page_path(&self.file.relative);
+        if let Some(heading_offset) = self.meta.heading_offset {
+            context.set_heading_offset(heading_offset);
+        }
+        if let Some(highlight_code) = self.meta.highlight_code {
+            context.set_highlight_code(highlight_code);
+        }
+        context.set_anchor_aliases(&self.meta.anchor_aliases);
         context
             .tera_context
             .insert("section", &SerializingSection::new(self, SectionSerMode::ForMarkdown));
@@ -168,9 +248,13 @@ impl Section {
         let res = render_content(&self.raw_content, &context)
             .with_context(|| format!("Failed to render content of {}", self.file.path.display()))?;
         self.content = res.body;
+        self.excerpt_plain = get_plain_excerpt(None, &self.content, config.description_length);
+        self.description_resolved =
+            self.meta.description.clone().unwrap_or_else(|| self.excerpt_plain.clone());
         self.toc = res.toc;
 
         self.external_links = res.external_links;
+        self.mixed_self_links = res.mixed_self_links;
         if let Some(ref redirect_to) = self.meta.redirect_to {
             if is_external_link(redirect_to) {
                 self.external_links.push(redirect_to.to_owned());
@@ -178,23 +262,35 @@ impl Section {
         }
 
         self.internal_links = res.internal_links;
+        self.shortcodes = res.shortcodes;
 
         Ok(())
     }
 
     /// Renders the page using the default layout, unless specified in front-matter
-    pub fn render_html(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
+    pub fn render_html(
+        &self,
+        tera: &Tera,
+        config: &Config,
+        data: &Value,
+        library: &Library,
+    ) -> Result<String> {
         let tpl_name = self.get_template_name();
 
-        let mut context = TeraContext::new();
-        context.insert("config", &config.serialize(&self.lang));
+        let mut context = config.template_context(&self.lang, data);
         context.insert("current_url", &self.permalink);
         context.insert("current_path", &self.path);
         context.insert("section", &SerializingSection::new(self, SectionSerMode::Full(library)));
-        context.insert("lang", &self.lang);
+        context.insert("site", &library.site_index);
 
-        render_template(tpl_name, tera, context, &config.theme)
-            .with_context(|| format!("Failed to render section '{}'", self.file.path.display()))
+        render_template_with_options(
+            tpl_name,
+            tera,
+            context,
+            &config.theme,
+            config.missing_extra_is_error,
+        )
+        .with_context(|| format!("Failed to render section '{}'", self.file.path.display()))
     }
 
     /// Is this the index section?
@@ -203,12 +299,12 @@ impl Section {
     }
 
     /// Creates a vectors of asset URLs.
-    fn serialize_assets(&self) -> Vec<String> {
+    fn serialize_assets(&self, config: &Config) -> Vec<String> {
         self.assets
             .iter()
             .filter_map(|asset| asset.strip_prefix(self.file.path.parent().unwrap()).ok())
             .filter_map(|filename| filename.to_str())
-            .map(|filename| format!("{}{}", self.path, filename))
+            .map(|filename| config.make_asset_link(&format!("{}{}", self.path, filename)))
             .collect()
     }
 
@@ -216,6 +312,10 @@ impl Section {
         has_anchor(&self.toc, anchor)
     }
 
+    pub fn has_anchor_id(&self, id: &str) -> bool {
+        has_anchor_id(&self.content, id)
+    }
+
     pub fn paginate_by(&self) -> Option<usize> {
         match self.meta.paginate_by {
             None => None,
@@ -226,6 +326,22 @@ impl Section {
         }
     }
 
+    /// A hash of what the templates can see of the section, to find out whether it changed
+    /// between two loads of the site
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.source_hash.hash(&mut hasher);
+        self.permalink.hash(&mut hasher);
+        self.content.hash(&mut hasher);
+        self.ancestors.hash(&mut hasher);
+        self.pages.hash(&mut hasher);
+        self.subsections.hash(&mut hasher);
+        self.serialized_assets.hash(&mut hasher);
+        self.stats.hash(&mut hasher);
+        libs::serde_json::to_string(&self.effective_extra).unwrap_or_default().hash(&mut hasher);
+        hasher.finish()
+    }
+
     pub fn serialize<'a>(&'a self, library: &'a Library) -> SerializingSection<'a> {
         SerializingSection::new(self, SectionSerMode::Full(library))
     }
@@ -270,7 +386,7 @@ mod tests {
         let section = res.unwrap();
         assert_eq!(section.assets.len(), 3);
         assert!(section.serialized_assets[0].starts_with('/'));
-        assert_eq!(section.permalink, "http://a-website.com/posts/with-assets/");
+        assert_eq!(&*section.permalink, "http://a-website.com/posts/with-assets/");
     }
 
     #[test]
@@ -323,7 +439,23 @@ Bonjour le monde"#
         assert!(res.is_ok());
         let section = res.unwrap();
         assert_eq!(section.lang, "fr".to_string());
-        assert_eq!(section.permalink, "http://a-website.com/fr/hello/nested/");
+        assert_eq!(&*section.permalink, "http://a-website.com/fr/hello/nested/");
+    }
+
+    #[test]
+    fn can_make_implicit_section() {
+        let mut config = Config::default();
+        config.languages.insert("fr".to_owned(), LanguageOptions::default());
+        let section =
+            Section::implicit(Path::new("content/hello/nested"), "fr", &config, &PathBuf::new())
+                .unwrap();
+        assert!(section.implicit);
+        assert!(!section.meta.render);
+        assert_eq!(section.meta.title.as_deref(), Some("nested"));
+        assert_eq!(section.file.path, Path::new("content/hello/nested/_index.fr.md"));
+        assert_eq!(section.file.name, "_index");
+        assert_eq!(section.lang, "fr");
+        assert_eq!(&*section.permalink, "http://a-website.com/fr/hello/nested/");
     }
 
     // https://zola.discourse.group/t/rfc-i18n/13/17?u=keats
@@ -341,7 +473,7 @@ Bonjour le monde"#
         assert!(res.is_ok());
         let section = res.unwrap();
         assert_eq!(section.lang, "fr".to_string());
-        assert_eq!(section.permalink, "http://a-website.com/fr/");
+        assert_eq!(&*section.permalink, "http://a-website.com/fr/");
     }
 
     #[test]
@@ -362,7 +494,7 @@ Bonjour le monde"#
         assert!(res.is_ok());
         let section = res.unwrap();
         assert_eq!(section.lang, "fr".to_string());
-        assert_eq!(section.permalink, "http://a-website.com/fr/subcontent/");
+        assert_eq!(&*section.permalink, "http://a-website.com/fr/subcontent/");
     }
 
     #[test]
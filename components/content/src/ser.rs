@@ -1,17 +1,38 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-use serde::Serialize;
+use serde::{Serialize, Serializer};
 
-use crate::library::Library;
-use crate::{Page, Section};
+use crate::library::{normalize_mention_url, Library};
+use crate::{GitMetadata, Page, Section, SectionStats};
 use libs::tera::{Map, Value};
+use utils::fs::read_file;
 use utils::table_of_contents::Heading;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct BackLink<'a> {
     pub permalink: &'a str,
     pub title: &'a Option<String>,
+    /// The path of the markdown file linking, relative to the content directory
+    pub path: &'a str,
+}
+
+/// The mentions of a page found in the `mentions_source` of the config
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Mentions<'a> {
+    pub count: usize,
+    pub items: &'a [Value],
+}
+
+impl<'a> Mentions<'a> {
+    fn find(permalink: &str, library: &'a Library) -> Self {
+        let items = library
+            .mentions
+            .get(&normalize_mention_url(permalink))
+            .map(|m| m.as_slice())
+            .unwrap_or_default();
+        Mentions { count: items.len(), items }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize)]
@@ -23,53 +44,134 @@ pub struct TranslatedContent<'a> {
     pub path: &'a Path,
 }
 
+/// The previous or next subsection of the same parent section
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SiblingSection<'a> {
+    pub permalink: &'a str,
+    pub title: &'a Option<String>,
+    /// The path to the `_index.md` file, relative to the content directory
+    pub path: &'a str,
+}
+
+impl<'a> SiblingSection<'a> {
+    fn find(path: &Option<PathBuf>, library: &'a Library) -> Option<Self> {
+        path.as_ref().and_then(|p| library.sections.get(p)).map(|s| SiblingSection {
+            permalink: &s.permalink,
+            title: &s.meta.title,
+            path: &s.file.relative,
+        })
+    }
+}
+
 fn find_backlinks<'a>(relative_path: &str, library: &'a Library) -> Vec<BackLink<'a>> {
     let mut backlinks = Vec::new();
     if let Some(b) = library.backlinks.get(relative_path) {
         for backlink in b {
             if let Some(p) = library.pages.get(backlink) {
-                backlinks.push(BackLink { permalink: &p.permalink, title: &p.meta.title });
+                backlinks.push(BackLink {
+                    permalink: &p.permalink,
+                    title: &p.meta.title,
+                    path: &p.file.relative,
+                });
             }
             if let Some(s) = library.sections.get(backlink) {
-                backlinks.push(BackLink { permalink: &s.permalink, title: &s.meta.title });
+                backlinks.push(BackLink {
+                    permalink: &s.permalink,
+                    title: &s.meta.title,
+                    path: &s.file.relative,
+                });
             }
         }
-        backlinks.sort_by_key(|b| b.permalink);
+        // By title, the untitled ones last
+        backlinks.sort_by_key(|b| (b.title.is_none(), b.title, b.permalink));
     }
     backlinks
 }
 
+/// The rendered HTML of a page as given to the templates. When a `low_memory` build spilled it to
+/// disk, it is only read back when serialized and the render fails if it can't be. The listings
+/// leave it out, see `SerializingPage::for_listing`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PageContent<'a> {
+    Html(&'a str),
+    Spilled(&'a Path),
+}
+
+impl<'a> PageContent<'a> {
+    fn of(page: &'a Page) -> Self {
+        match page.spilled_content {
+            Some(ref path) => PageContent::Spilled(path),
+            None => PageContent::Html(&page.content),
+        }
+    }
+}
+
+impl Serialize for PageContent<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            PageContent::Html(html) => serializer.serialize_str(html),
+            PageContent::Spilled(path) => {
+                let html = read_file(path).map_err(|e| {
+                    serde::ser::Error::custom(format!(
+                        "Failed to read back the content of the page: {:#}",
+                        e
+                    ))
+                })?;
+                serializer.serialize_str(&html)
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct SerializingPage<'a> {
     relative_path: &'a str,
     colocated_path: &'a Option<String>,
-    content: &'a str,
+    content: PageContent<'a>,
     permalink: &'a str,
     slug: &'a str,
     ancestors: &'a [String],
     pub(crate) title: &'a Option<String>,
     description: &'a Option<String>,
+    description_resolved: &'a str,
+    excerpt_plain: &'a str,
     updated: &'a Option<String>,
     date: &'a Option<String>,
     year: Option<i32>,
     month: Option<u8>,
     day: Option<u8>,
-    taxonomies: &'a HashMap<String, Vec<String>>,
+    month_name: &'a Option<String>,
+    weekday: &'a Option<String>,
+    week: Option<u8>,
+    timestamp: Option<i64>,
+    /// Sorted by taxonomy name, as the templates iterate over it in that order
+    taxonomies: BTreeMap<&'a str, &'a [String]>,
     authors: &'a [String],
     extra: &'a Map<String, Value>,
+    effective_extra: &'a Map<String, Value>,
+    data: &'a Option<Value>,
     path: &'a str,
     components: &'a [String],
     summary: &'a Option<String>,
+    summary_truncated: bool,
     toc: &'a [Heading],
     word_count: Option<usize>,
     reading_time: Option<usize>,
     assets: &'a [String],
     draft: bool,
+    unlisted: bool,
+    protected: bool,
+    future: bool,
     lang: &'a str,
     lower: Option<Box<SerializingPage<'a>>>,
     higher: Option<Box<SerializingPage<'a>>>,
+    related: Vec<SerializingPage<'a>>,
     translations: Vec<TranslatedContent<'a>>,
     backlinks: Vec<BackLink<'a>>,
+    mentions: Mentions<'a>,
+    canonical_url: &'a Option<String>,
+    robots: &'a Option<String>,
+    git: &'a Option<GitMetadata>,
 }
 
 impl<'a> SerializingPage<'a> {
@@ -84,21 +186,29 @@ impl<'a> SerializingPage<'a> {
         }
         let mut lower = None;
         let mut higher = None;
+        let mut related = vec![];
         let mut translations = vec![];
         let mut backlinks = vec![];
+        let mut mentions = Mentions { count: 0, items: &[] };
 
         if let Some(lib) = library {
             translations = lib.find_translations(&page.file.canonical);
+            mentions = Mentions::find(&page.permalink, lib);
 
             if include_siblings {
                 lower = page
                     .lower
                     .as_ref()
-                    .map(|p| Box::new(Self::new(&lib.pages[p], Some(lib), false)));
+                    .map(|p| Box::new(Self::for_listing(&lib.pages[p], lib, false)));
                 higher = page
                     .higher
                     .as_ref()
-                    .map(|p| Box::new(Self::new(&lib.pages[p], Some(lib), false)));
+                    .map(|p| Box::new(Self::for_listing(&lib.pages[p], lib, false)));
+                related = page
+                    .related
+                    .iter()
+                    .map(|p| Self::for_listing(&lib.pages[p], lib, false))
+                    .collect();
             }
 
             backlinks = find_backlinks(&page.file.relative, lib);
@@ -108,33 +218,81 @@ impl<'a> SerializingPage<'a> {
             relative_path: &page.file.relative,
             colocated_path: &page.file.colocated_path,
             ancestors: &page.ancestors,
-            content: &page.content,
+            // The content of protected pages is only given to their own encrypted render
+            content: if page.password.is_some() {
+                PageContent::Html("")
+            } else {
+                PageContent::of(page)
+            },
             permalink: &page.permalink,
             slug: &page.slug,
             title: &page.meta.title,
             description: &page.meta.description,
-            extra: &page.meta.extra,
+            description_resolved: &page.description_resolved,
+            excerpt_plain: &page.excerpt_plain,
+            extra: if library.map(|l| l.merge_extra).unwrap_or(false) {
+                &page.effective_extra
+            } else {
+                &page.meta.extra
+            },
+            effective_extra: &page.effective_extra,
+            data: &page.data,
             updated: &page.meta.updated,
             date: &page.meta.date,
             year,
             month,
             day,
-            taxonomies: &page.meta.taxonomies,
+            month_name: &page.month_name,
+            weekday: &page.weekday,
+            week: page.meta.datetime.map(|d| d.iso_week()),
+            timestamp: page.meta.datetime.map(|d| d.unix_timestamp()),
+            taxonomies: page
+                .meta
+                .taxonomies
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_slice()))
+                .collect(),
             authors: &page.meta.authors,
             path: &page.path,
             components: &page.components,
             summary: &page.summary,
-            toc: &page.toc,
+            summary_truncated: page.summary_truncated,
+            toc: if page.password.is_some() { &[] } else { &page.toc },
             word_count: page.word_count,
             reading_time: page.reading_time,
             assets: &page.serialized_assets,
             draft: page.meta.draft,
+            unlisted: page.meta.unlisted,
+            protected: page.password.is_some(),
+            future: page.future,
             lang: &page.lang,
             lower,
             higher,
+            related,
             translations,
             backlinks,
+            mentions,
+            canonical_url: &page.canonical_url,
+            robots: &page.meta.robots,
+            git: &page.git,
+        }
+    }
+
+    /// The page as listed by a section, a taxonomy term, an archive, a paginator or as a sibling.
+    /// A content spilled to disk by a `low_memory` build is left out instead of being read back
+    /// for every listing the page is in: the page itself and `get_page` have it.
+    pub fn for_listing(page: &'a Page, library: &'a Library, include_siblings: bool) -> Self {
+        let mut serialized = Self::new(page, Some(library), include_siblings);
+        if let PageContent::Spilled(_) = serialized.content {
+            serialized.content = PageContent::Html("");
         }
+        serialized
+    }
+
+    /// Gives back the content of a protected page, for the render that gets encrypted
+    pub(crate) fn reveal_content(&mut self, page: &'a Page) {
+        self.content = PageContent::of(page);
+        self.toc = &page.toc;
     }
 }
 
@@ -148,7 +306,10 @@ pub struct SerializingSection<'a> {
     ancestors: &'a [String],
     title: &'a Option<String>,
     description: &'a Option<String>,
+    description_resolved: &'a str,
+    excerpt_plain: &'a str,
     extra: &'a Map<String, Value>,
+    effective_extra: &'a Map<String, Value>,
     path: &'a str,
     components: &'a [String],
     toc: &'a [Heading],
@@ -156,12 +317,18 @@ pub struct SerializingSection<'a> {
     reading_time: Option<usize>,
     lang: &'a str,
     assets: &'a [String],
+    weight: usize,
     pages: Vec<SerializingPage<'a>>,
+    total_pages: usize,
+    stats: &'a SectionStats,
     subsections: Vec<&'a str>,
+    prev_sibling: Option<SiblingSection<'a>>,
+    next_sibling: Option<SiblingSection<'a>>,
     translations: Vec<TranslatedContent<'a>>,
     backlinks: Vec<BackLink<'a>>,
     generate_feeds: bool,
     transparent: bool,
+    implicit: bool,
 }
 
 #[derive(Debug)]
@@ -181,6 +348,9 @@ impl<'a> SerializingSection<'a> {
         let mut subsections = Vec::with_capacity(section.subsections.len());
         let mut translations = Vec::new();
         let mut backlinks = Vec::new();
+        let mut prev_sibling = None;
+        let mut next_sibling = None;
+        let mut extra = &section.meta.extra;
 
         match mode {
             SectionSerMode::ForMarkdown => {}
@@ -191,11 +361,22 @@ impl<'a> SerializingSection<'a> {
                     .iter()
                     .map(|p| lib.sections[p].file.relative.as_str())
                     .collect();
+                if lib.merge_extra {
+                    extra = &section.effective_extra;
+                }
+                prev_sibling = SiblingSection::find(&section.prev_sibling, lib);
+                next_sibling = SiblingSection::find(&section.next_sibling, lib);
 
                 // Fetching pages on top
                 if let SectionSerMode::Full(_) = mode {
-                    for p in &section.pages {
-                        pages.push(SerializingPage::new(&lib.pages[p], Some(lib), true));
+                    // The paginator has all the pages of a paginated section
+                    let (offset, limit) = if section.meta.is_paginated() {
+                        (0, None)
+                    } else {
+                        (section.meta.page_offset, section.meta.page_limit)
+                    };
+                    for p in section.pages.iter().skip(offset).take(limit.unwrap_or(usize::MAX)) {
+                        pages.push(SerializingPage::for_listing(&lib.pages[p], lib, true));
                     }
                 }
 
@@ -212,7 +393,10 @@ impl<'a> SerializingSection<'a> {
             permalink: &section.permalink,
             title: &section.meta.title,
             description: &section.meta.description,
-            extra: &section.meta.extra,
+            description_resolved: &section.description_resolved,
+            excerpt_plain: &section.excerpt_plain,
+            extra,
+            effective_extra: &section.effective_extra,
             path: &section.path,
             components: &section.components,
             toc: &section.toc,
@@ -222,8 +406,14 @@ impl<'a> SerializingSection<'a> {
             lang: &section.lang,
             generate_feeds: section.meta.generate_feeds,
             transparent: section.meta.transparent,
+            implicit: section.implicit,
+            weight: section.meta.weight,
             pages,
+            total_pages: section.pages.len(),
+            stats: &section.stats,
             subsections,
+            prev_sibling,
+            next_sibling,
             translations,
             backlinks,
         }
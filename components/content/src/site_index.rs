@@ -0,0 +1,213 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use libs::ahash::{AHashMap, AHashSet};
+use libs::tera::{to_value, Map, Value};
+use serde::Serialize;
+
+use crate::{Library, Page};
+
+/// A section in the index of the site, with only what is needed to list or link to it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SiteIndexSection {
+    pub relative_path: String,
+    pub path: Arc<str>,
+    pub permalink: Arc<str>,
+    pub title: Option<String>,
+    pub lang: String,
+    pub page_count: usize,
+    /// The `relative_path` of the parent section, `None` for the index sections
+    pub parent: Option<String>,
+}
+
+/// A page or section in the list returned by `get_ancestors`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Ancestor {
+    pub title: Option<String>,
+    pub permalink: Arc<str>,
+    pub path: Arc<str>,
+}
+
+/// An inventory of the pages of the site, available as `site.stats` in the templates and shown
+/// after `zola build` and `zola check`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ContentStats {
+    /// The number of pages, in all languages
+    pub pages: usize,
+    /// The pages with `draft = true`, only there when building with `--drafts`
+    pub drafts: usize,
+    /// The pages dated in the future, only there when they are included in the build
+    pub future: usize,
+    /// The pages with `unlisted = true`
+    pub unlisted: usize,
+    /// The pages without a `description` in their front matter
+    pub missing_description: usize,
+    /// The sum of the word counts of the pages
+    pub word_count: usize,
+}
+
+impl ContentStats {
+    pub fn new<'a>(pages: impl IntoIterator<Item = &'a Page>) -> Self {
+        let mut stats = Self::default();
+        for page in pages {
+            stats.pages += 1;
+            stats.drafts += usize::from(page.meta.draft);
+            stats.future += usize::from(page.future);
+            stats.unlisted += usize::from(page.meta.unlisted);
+            stats.missing_description += usize::from(page.meta.description.is_none());
+            stats.word_count += page.word_count.unwrap_or(0);
+        }
+        stats
+    }
+}
+
+/// A lightweight index of the whole site, available as `site` in the templates and through
+/// `get_site_index` in the shortcodes.
+/// The sections are listed depth-first, each of them followed by its subsections in their order.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SiteIndex {
+    pub sections: Vec<SiteIndexSection>,
+    pub pages_count: usize,
+    pub stats: ContentStats,
+    /// Every page and section with the relative paths of its ancestors, by relative path
+    #[serde(skip)]
+    ancestry: AHashMap<String, (Ancestor, Vec<String>)>,
+}
+
+impl SiteIndex {
+    /// Needs to be called after the sections have been populated
+    pub fn new(library: &Library, default_language: &str) -> Self {
+        let mut roots: Vec<_> = library.sections.values().filter(|s| s.is_index()).collect();
+        roots.sort_by(|a, b| {
+            (a.lang != default_language, &a.lang).cmp(&(b.lang != default_language, &b.lang))
+        });
+
+        let mut sections = Vec::with_capacity(library.sections.len());
+        let mut visited = AHashSet::with_capacity(library.sections.len());
+        let mut stack: Vec<(&PathBuf, Option<String>)> =
+            roots.iter().rev().map(|s| (&s.file.path, None)).collect();
+        while let Some((path, parent)) = stack.pop() {
+            if !visited.insert(path) {
+                continue;
+            }
+            let section = &library.sections[path];
+            stack.extend(
+                section.subsections.iter().rev().map(|p| (p, Some(section.file.relative.clone()))),
+            );
+            sections.push(SiteIndexSection {
+                relative_path: section.file.relative.clone(),
+                path: section.path.clone(),
+                permalink: section.permalink.clone(),
+                title: section.meta.title.clone(),
+                lang: section.lang.clone(),
+                page_count: section.pages.len(),
+                parent,
+            });
+        }
+
+        let sections_ancestry = library.sections.values().map(|s| {
+            let item = Ancestor {
+                title: s.meta.title.clone(),
+                permalink: s.permalink.clone(),
+                path: s.path.clone(),
+            };
+            (s.file.relative.clone(), (item, s.ancestors.clone()))
+        });
+        let pages_ancestry = library.pages.values().map(|p| {
+            let item = Ancestor {
+                title: p.meta.title.clone(),
+                permalink: p.permalink.clone(),
+                path: p.path.clone(),
+            };
+            (p.file.relative.clone(), (item, p.ancestors.clone()))
+        });
+        let ancestry = sections_ancestry.chain(pages_ancestry).collect();
+
+        Self {
+            sections,
+            pages_count: library.pages.len(),
+            stats: ContentStats::new(library.pages.values()),
+            ancestry,
+        }
+    }
+
+    /// The sections a page or section is in, outermost first, followed by the page or section
+    /// itself. `None` if there is nothing at that path relative to the `content` directory.
+    pub fn ancestors(&self, relative_path: &str) -> Option<Vec<&Ancestor>> {
+        let (item, ancestors) = self.ancestry.get(relative_path)?;
+        let mut res: Vec<_> =
+            ancestors.iter().filter_map(|a| self.ancestry.get(a).map(|(s, _)| s)).collect();
+        res.push(item);
+        Some(res)
+    }
+
+    /// The sections nested in the `subsections` of their parent, starting from the index sections
+    pub fn tree(&self) -> Value {
+        let mut children: AHashMap<&str, Vec<&SiteIndexSection>> = AHashMap::new();
+        for section in &self.sections {
+            if let Some(ref parent) = section.parent {
+                children.entry(parent.as_str()).or_default().push(section);
+            }
+        }
+
+        fn node(
+            section: &SiteIndexSection,
+            children: &AHashMap<&str, Vec<&SiteIndexSection>>,
+        ) -> Value {
+            let subsections = children
+                .get(section.relative_path.as_str())
+                .map(|c| c.iter().map(|s| node(s, children)).collect())
+                .unwrap_or_default();
+            let mut value = to_value(section).unwrap();
+            value
+                .as_object_mut()
+                .unwrap()
+                .insert("subsections".to_string(), Value::Array(subsections));
+            value
+        }
+
+        let roots = self.sections.iter().filter(|s| s.parent.is_none()).map(|s| node(s, &children));
+        let mut tree = Map::new();
+        tree.insert("sections".to_string(), Value::Array(roots.collect()));
+        tree.insert("pages_count".to_string(), to_value(self.pages_count).unwrap());
+        tree.insert("stats".to_string(), to_value(self.stats).unwrap());
+        Value::Object(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::front_matter::PageFrontMatter;
+
+    #[test]
+    fn can_count_content_stats() {
+        let draft = Page {
+            meta: PageFrontMatter {
+                draft: true,
+                description: Some("A draft".to_string()),
+                ..PageFrontMatter::default()
+            },
+            word_count: Some(120),
+            ..Page::default()
+        };
+        let future = Page {
+            meta: PageFrontMatter { unlisted: true, ..PageFrontMatter::default() },
+            future: true,
+            word_count: Some(30),
+            ..Page::default()
+        };
+
+        assert_eq!(
+            ContentStats::new([&draft, &future, &Page::default()]),
+            ContentStats {
+                pages: 3,
+                drafts: 1,
+                future: 1,
+                unlisted: 1,
+                missing_description: 2,
+                word_count: 150,
+            }
+        );
+    }
+}
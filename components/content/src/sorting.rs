@@ -5,10 +5,18 @@ use crate::{Page, SortBy};
 use libs::lexical_sort::natural_lexical_cmp;
 use libs::rayon::prelude::*;
 
-/// Sort by the field picked by the function.
-/// The pages permalinks are used to break the ties
+/// The order of pages that are equal on what they are sorted by: by slug, then by the path of
+/// their file. Every sort of pages ends with it so the same content always ends up in the same
+/// order, whatever order the files were found in.
+pub fn tie_break(a: &Page, b: &Page) -> Ordering {
+    a.slug.cmp(&b.slug).then_with(|| a.file.path.cmp(&b.file.path))
+}
+
+/// Sort by the field picked by the function, with `tie_break` for the pages that are equal.
+/// The pages that cannot be sorted, for example without a date when sorting by date, are
+/// returned separately, ordered with `tie_break` only.
 pub fn sort_pages(pages: &[&Page], sort_by: SortBy) -> (Vec<PathBuf>, Vec<PathBuf>) {
-    let (mut can_be_sorted, cannot_be_sorted): (Vec<&Page>, Vec<_>) =
+    let (mut can_be_sorted, mut cannot_be_sorted): (Vec<&Page>, Vec<&Page>) =
         pages.par_iter().partition(|page| match sort_by {
             SortBy::Date => page.meta.datetime.is_some(),
             SortBy::UpdateDate => {
@@ -16,8 +24,7 @@ pub fn sort_pages(pages: &[&Page], sort_by: SortBy) -> (Vec<PathBuf>, Vec<PathBu
             }
             SortBy::Title | SortBy::TitleBytes => page.meta.title.is_some(),
             SortBy::Weight => page.meta.weight.is_some(),
-            SortBy::Slug => true,
-            SortBy::None => unreachable!(),
+            SortBy::Slug | SortBy::None => true,
         });
 
     can_be_sorted.par_sort_unstable_by(|a, b| {
@@ -34,15 +41,12 @@ pub fn sort_pages(pages: &[&Page], sort_by: SortBy) -> (Vec<PathBuf>, Vec<PathBu
             }
             SortBy::Weight => a.meta.weight.unwrap().cmp(&b.meta.weight.unwrap()),
             SortBy::Slug => natural_lexical_cmp(&a.slug, &b.slug),
-            SortBy::None => unreachable!(),
+            SortBy::None => Ordering::Equal,
         };
 
-        if ord == Ordering::Equal {
-            a.permalink.cmp(&b.permalink)
-        } else {
-            ord
-        }
+        ord.then_with(|| tie_break(a, b))
     });
+    cannot_be_sorted.par_sort_unstable_by(|a, b| tie_break(a, b));
 
     (
         can_be_sorted.iter().map(|p| p.file.path.clone()).collect(),
@@ -228,4 +232,74 @@ mod tests {
         assert_eq!(ignored_pages.len(), 1);
         assert_eq!(ignored_pages[0], page2.file.path);
     }
+
+    #[test]
+    fn sorting_does_not_depend_on_insertion_order() {
+        let mut pages = Vec::new();
+        for (i, slug) in ["b", "a", "d", "c", "a"].into_iter().enumerate() {
+            let front_matter = PageFrontMatter {
+                title: Some("Same".to_string()),
+                weight: Some(1),
+                date: Some("2020-01-01".to_string()),
+                ..Default::default()
+            };
+            let mut page =
+                Page::new(format!("content/{}/{}.md", i, slug), front_matter, &PathBuf::new());
+            page.meta.date_to_datetime();
+            page.slug = slug.to_string();
+            pages.push(page);
+        }
+        // A page that can't be sorted by anything but the slug
+        let mut undated = create_page_with_slug("e");
+        undated.file.path = PathBuf::from("content/undated.md");
+        pages.push(undated);
+
+        let sort_bys = [
+            SortBy::Date,
+            SortBy::UpdateDate,
+            SortBy::Title,
+            SortBy::TitleBytes,
+            SortBy::Weight,
+            SortBy::Slug,
+            SortBy::None,
+        ];
+        let mut refs: Vec<&Page> = pages.iter().collect();
+        let expected: Vec<_> = sort_bys.iter().map(|s| sort_pages(&refs, *s)).collect();
+        // Equal pages are ordered by slug, then by file path
+        let (sorted, _) = &expected[0];
+        assert_eq!(
+            sorted,
+            &[
+                "content/1/a.md",
+                "content/4/a.md",
+                "content/0/b.md",
+                "content/3/c.md",
+                "content/2/d.md"
+            ]
+            .map(PathBuf::from)
+        );
+
+        // Shuffle the order the pages are given in: every rotation of them and of their
+        // reverse, each with every pair of pages swapped
+        for reverse in [false, true] {
+            if reverse {
+                refs.reverse();
+            }
+            for _ in 0..refs.len() {
+                refs.rotate_left(1);
+                for (sort_by, expected) in sort_bys.iter().zip(&expected) {
+                    assert_eq!(&sort_pages(&refs, *sort_by), expected, "{:?}", sort_by);
+                }
+                for i in 0..refs.len() {
+                    for j in i + 1..refs.len() {
+                        let mut swapped = refs.clone();
+                        swapped.swap(i, j);
+                        for (sort_by, expected) in sort_bys.iter().zip(&expected) {
+                            assert_eq!(&sort_pages(&swapped, *sort_by), expected, "{:?}", sort_by);
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use serde::Serialize;
@@ -6,7 +6,7 @@ use serde::Serialize;
 use config::{Config, TaxonomyConfig};
 use errors::{Context as ErrorContext, Result};
 use libs::ahash::AHashMap;
-use libs::tera::{Context, Tera};
+use libs::tera::{Context, Tera, Value};
 use utils::slugs::slugify_paths;
 use utils::templates::{check_template_fallbacks, render_template};
 
@@ -32,7 +32,7 @@ impl<'a> SerializedTaxonomyTerm<'a> {
 
         if include_pages {
             for p in &item.pages {
-                pages.push(SerializingPage::new(&library.pages[p], Some(library), false));
+                pages.push(SerializingPage::for_listing(&library.pages[p], library, false));
             }
         }
 
@@ -143,28 +143,21 @@ pub struct Taxonomy {
 
 impl Taxonomy {
     pub(crate) fn new(tax_found: TaxonomyFound, config: &Config) -> Self {
-        let mut sorted_items = vec![];
         let slug = tax_found.slug;
+        // Terms with different names can end up with the same slug, eg `Rust` and `rust`: they
+        // are the same term, named after the first name, with the pages of all of them sorted
+        // together
+        let mut terms: BTreeMap<String, (&str, Vec<&Page>)> = BTreeMap::new();
         for (name, pages) in tax_found.terms {
-            sorted_items.push(TaxonomyTerm::new(name, tax_found.lang, &slug, &pages, config));
+            let term_slug = slugify_paths(name, config.slugify.taxonomies);
+            let term = terms.entry(term_slug).or_insert_with(|| (name, Vec::new()));
+            term.0 = term.0.min(name);
+            term.1.extend(pages);
         }
-
-        sorted_items.sort_by(|a, b| match a.slug.cmp(&b.slug) {
-            Ordering::Less => Ordering::Less,
-            Ordering::Greater => Ordering::Greater,
-            Ordering::Equal => a.name.cmp(&b.name),
-        });
-        sorted_items.dedup_by(|a, b| {
-            // custom Eq impl checks for equal permalinks
-            // here we make sure all pages from a get copied to b
-            // before dedup gets rid of it
-            if a == b {
-                b.merge(a.to_owned());
-                true
-            } else {
-                false
-            }
-        });
+        let sorted_items: Vec<_> = terms
+            .into_values()
+            .map(|(name, pages)| TaxonomyTerm::new(name, tax_found.lang, &slug, &pages, config))
+            .collect();
         let path = if tax_found.lang != config.default_language {
             format!("/{}/{}/", tax_found.lang, slug)
         } else {
@@ -187,9 +180,10 @@ impl Taxonomy {
         item: &TaxonomyTerm,
         tera: &Tera,
         config: &Config,
+        data: &Value,
         library: &Library,
     ) -> Result<String> {
-        let context = self.build_term_context(item, config, library);
+        let context = self.build_term_context(item, config, data, library);
 
         // Check for taxon-specific template, or use generic as fallback.
         let specific_template = format!("{}/single.html", self.kind.name);
@@ -204,15 +198,15 @@ impl Taxonomy {
         &self,
         item: &TaxonomyTerm,
         config: &Config,
+        data: &Value,
         library: &Library,
     ) -> Context {
-        let mut context = Context::new();
-        context.insert("config", &config.serialize(&self.lang));
-        context.insert("lang", &self.lang);
+        let mut context = config.template_context(&self.lang, data);
         context.insert("term", &SerializedTaxonomyTerm::from_item(item, library, true));
         context.insert("taxonomy", &self.kind);
         context.insert("current_url", &item.permalink);
         context.insert("current_path", &item.path);
+        context.insert("site", &library.site_index);
         context
     }
 
@@ -220,17 +214,17 @@ impl Taxonomy {
         &self,
         tera: &Tera,
         config: &Config,
+        data: &Value,
         library: &Library,
     ) -> Result<String> {
-        let mut context = Context::new();
-        context.insert("config", &config.serialize(&self.lang));
+        let mut context = config.template_context(&self.lang, data);
         let terms: Vec<SerializedTaxonomyTerm> = self
             .items
             .iter()
             .map(|i| SerializedTaxonomyTerm::from_item(i, library, true))
             .collect();
         context.insert("terms", &terms);
-        context.insert("lang", &self.lang);
+        context.insert("site", &library.site_index);
         context.insert("taxonomy", &self.kind);
         context.insert("current_url", &self.permalink);
         context.insert("current_path", &self.path);
@@ -275,6 +269,7 @@ impl<'a> TaxonomyFound<'a> {
 #[cfg(test)]
 mod tests {
     use config::{Config, TaxonomyConfig};
+    use libs::tera::Value;
 
     use crate::{Library, Taxonomy, TaxonomyTerm};
 
@@ -290,7 +285,7 @@ mod tests {
         let term = TaxonomyTerm::new("rust", &conf.default_language, "tags", pages, &conf);
         let lib = Library::default();
 
-        let ctx = tax.build_term_context(&term, &conf, &lib);
+        let ctx = tax.build_term_context(&term, &conf, &Value::Null, &lib);
 
         assert_eq!(ctx.get("current_path").and_then(|x| x.as_str()), Some("/tags/rust/"));
 
@@ -20,3 +20,17 @@ pub enum SortBy {
     /// No sorting
     None,
 }
+
+/// The information about the last commit that changed a content file, available when
+/// `git_metadata` is enabled. All the fields are `None` for untracked files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct GitMetadata {
+    /// Hash of the last commit
+    pub hash: Option<String>,
+    /// Name of the author of the last commit
+    pub author: Option<String>,
+    /// Date of the last commit, in ISO 8601
+    pub date: Option<String>,
+    /// URL to edit the file, from `git_edit_url`
+    pub edit_url: Option<String>,
+}
@@ -1,12 +1,38 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
 
+use libs::once_cell::sync::Lazy;
+use libs::regex::Regex;
+use libs::tera::{Map, Value};
 use libs::unicode_segmentation::UnicodeSegmentation;
 use libs::walkdir::WalkDir;
 
 use config::Config;
-use utils::fs::is_temp_file;
+use utils::fs::{is_temp_file, walk_dir_following_links};
+use utils::html::strip_html;
 use utils::table_of_contents::Heading;
 
+/// Hashes the content of a file, to find out whether it changed between two loads of the site
+pub fn hash_source(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Deep-merges the `extra` of some content into `into`: tables are merged key by key and
+/// any other value, including arrays, replaces the one in `into`
+pub fn merge_extra(into: &mut Map<String, Value>, from: &Map<String, Value>) {
+    for (key, value) in from {
+        match (into.get_mut(key), value) {
+            (Some(Value::Object(existing)), Value::Object(table)) => merge_extra(existing, table),
+            _ => {
+                into.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
 pub fn has_anchor(headings: &[Heading], anchor: &str) -> bool {
     for heading in headings {
         if heading.id == anchor {
@@ -29,27 +55,23 @@ pub fn has_anchor(headings: &[Heading], anchor: &str) -> bool {
 pub fn find_related_assets(path: &Path, config: &Config, recursive: bool) -> Vec<PathBuf> {
     let mut assets = vec![];
 
-    let mut builder = WalkDir::new(path).follow_links(true);
+    let mut builder = WalkDir::new(path);
     if !recursive {
         builder = builder.max_depth(1);
     }
-    for entry in builder.into_iter().filter_map(std::result::Result::ok) {
+    for entry in walk_dir_following_links(builder).filter_map(std::result::Result::ok) {
         let entry_path = entry.path();
 
         if entry_path.is_file() && !is_temp_file(entry_path) {
-            match entry_path.extension() {
-                Some(e) => match e.to_str() {
-                    Some("md") => continue,
-                    _ => assets.push(entry_path.to_path_buf()),
-                },
-                None => assets.push(entry_path.to_path_buf()),
+            if config.markup.is_content_file(entry_path) {
+                continue;
             }
+            assets.push(entry_path.to_path_buf());
         }
     }
 
-    if let Some(ref globset) = config.ignored_content_globset {
-        assets.retain(|p| !globset.is_match(p));
-    }
+    // `ignored_content` patterns are matched against the path relative to the bundle directory
+    assets.retain(|p| !config.is_ignored_content(p.strip_prefix(path).unwrap_or(p)));
 
     assets.sort_by(|a, b| {
         a.to_str().unwrap().to_ascii_lowercase().cmp(&b.to_str().unwrap().to_ascii_lowercase())
@@ -58,16 +80,82 @@ pub fn find_related_assets(path: &Path, config: &Config, recursive: bool) -> Vec
     assets
 }
 
-/// Get word count and estimated reading time
-pub fn get_reading_analytics(content: &str) -> (usize, usize) {
-    // code fences "toggle" the state from non-code to code and back, so anything inbetween the
-    // first fence and the next can be ignored
-    let split = content.split("```");
-    let word_count = split.step_by(2).map(|section| section.unicode_words().count()).sum();
+/// Whether the asset is named after the page: `post.png` or `post.diagram.svg` for `post.md`
+fn is_loose_asset_of(asset: &Path, page_name: &str) -> bool {
+    asset
+        .file_name()
+        .and_then(|f| f.to_str())
+        .and_then(|f| f.strip_prefix(page_name))
+        .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Finds the assets of a page that is not an `index.md`: the files next to it that are named
+/// after it. `page_name` is the filename of the page without the extension and the language.
+pub fn find_loose_assets(path: &Path, page_name: &str, config: &Config) -> Vec<PathBuf> {
+    let parent = path.parent().unwrap();
+    let mut assets = find_related_assets(parent, config, false);
+    assets.retain(|a| is_loose_asset_of(a, page_name));
+    assets
+}
+
+/// Removes the assets belonging to the pages next to a section `_index.md` from its assets
+pub fn remove_loose_assets(section_path: &Path, assets: &mut Vec<PathBuf>, config: &Config) {
+    let parent = section_path.parent().unwrap();
+    let languages = config.other_languages_codes();
+    let page_names: Vec<String> = WalkDir::new(parent)
+        .max_depth(1)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+        .map(|e| e.path().to_path_buf())
+        .filter(|p| p.is_file() && config.markup.is_content_file(p))
+        .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(|s| s.to_string()))
+        .filter(|stem| !stem.starts_with("_index") && !stem.starts_with("index"))
+        .map(|stem| match stem.rsplit_once('.') {
+            Some((name, lang)) if languages.contains(&lang) => name.to_string(),
+            _ => stem,
+        })
+        .collect();
+    assets.retain(|a| !page_names.iter().any(|name| is_loose_asset_of(a, name)));
+}
+
+// The body of a shortcode, from its opening tag to its `{% end %}`
+static SHORTCODE_BODY_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)\{%\s*\w+\s*\(.*?\)\s*%\}.*?\{%\s*end\s*%\}").unwrap());
+
+/// Get word count and estimated reading time, at `config.reading_speed` words per minute.
+/// Code blocks and shortcode bodies are left out unless `config.count_code_blocks` is set.
+pub fn get_reading_analytics(content: &str, config: &Config) -> (usize, usize) {
+    let word_count = if config.count_code_blocks {
+        content.unicode_words().count()
+    } else {
+        let content = SHORTCODE_BODY_RE.replace_all(content, " ");
+        // code fences "toggle" the state from non-code to code and back, so anything inbetween the
+        // first fence and the next can be ignored
+        let split = content.split("```");
+        split.step_by(2).map(|section| section.unicode_words().count()).sum()
+    };
 
     // https://help.medium.com/hc/en-us/articles/214991667-Read-time
-    // 275 seems a bit too high though
-    (word_count, ((word_count + 199) / 200))
+    (word_count, word_count.div_ceil(config.reading_speed))
+}
+
+/// The plain text excerpt of some rendered content: its summary without the HTML if it has one,
+/// otherwise the first `length` characters of its text, without cutting a word in half
+pub fn get_plain_excerpt(summary: Option<&str>, content: &str, length: usize) -> String {
+    if let Some(summary) = summary {
+        return strip_html(summary);
+    }
+    let text = strip_html(content);
+    match text.grapheme_indices(true).nth(length) {
+        Some((cut, _)) => {
+            let kept = match text[..cut].rfind(' ') {
+                Some(space) if !text[cut..].starts_with(' ') => &text[..space],
+                _ => &text[..cut],
+            };
+            format!("{}…", kept.trim_end())
+        }
+        None => text,
+    }
 }
 
 #[cfg(test)]
@@ -76,6 +164,7 @@ mod tests {
     use std::fs::{create_dir, File};
 
     use config::Config;
+    use libs::globset::{Glob, GlobSetBuilder};
     use tempfile::tempdir;
 
     #[test]
@@ -149,6 +238,34 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn can_ignore_related_assets_relative_to_bundle() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let path = tmp_dir.path();
+        File::create(path.join("index.md")).unwrap();
+        File::create(path.join("example.js")).unwrap();
+        File::create(path.join("graph.jpg")).unwrap();
+        create_dir(path.join("subdir")).expect("create subdir temp dir");
+        File::create(path.join("subdir").join("example.js")).unwrap();
+        File::create(path.join("subdir").join("graph.jpg")).unwrap();
+        create_dir(path.join("drafts")).expect("create drafts temp dir");
+        File::create(path.join("drafts").join("graph.jpg")).unwrap();
+
+        let mut gsb = GlobSetBuilder::new();
+        gsb.add(Glob::new("subdir/*.js").unwrap());
+        gsb.add(Glob::new("**/drafts/**").unwrap());
+        let mut config = Config::default();
+        config.ignored_content_globset = Some(gsb.build().unwrap());
+
+        let assets = find_related_assets(path, &config, true);
+        let assets: Vec<_> = assets.iter().map(|p| p.strip_prefix(path).unwrap()).collect();
+        assert_eq!(
+            assets,
+            vec![Path::new("example.js"), Path::new("graph.jpg"), Path::new("subdir/graph.jpg")]
+        );
+    }
+
     #[test]
     fn can_find_anchor_at_root() {
         let input = vec![
@@ -222,14 +339,14 @@ mod tests {
 
     #[test]
     fn reading_analytics_empty_text() {
-        let (word_count, reading_time) = get_reading_analytics("  ");
+        let (word_count, reading_time) = get_reading_analytics("  ", &Config::default());
         assert_eq!(word_count, 0);
         assert_eq!(reading_time, 0);
     }
 
     #[test]
     fn reading_analytics_short_text() {
-        let (word_count, reading_time) = get_reading_analytics("Hello World");
+        let (word_count, reading_time) = get_reading_analytics("Hello World", &Config::default());
         assert_eq!(word_count, 2);
         assert_eq!(reading_time, 1);
     }
@@ -240,22 +357,47 @@ mod tests {
         for _ in 0..1000 {
             content.push_str(" Hello world");
         }
-        let (word_count, reading_time) = get_reading_analytics(&content);
+        let (word_count, reading_time) = get_reading_analytics(&content, &Config::default());
         assert_eq!(word_count, 2000);
         assert_eq!(reading_time, 10);
     }
 
     #[test]
     fn reading_analytics_no_code() {
-        let (word_count, reading_time) =
-            get_reading_analytics("hello world ``` code goes here ``` goodbye world");
+        let (word_count, reading_time) = get_reading_analytics(
+            "hello world ``` code goes here ``` goodbye world",
+            &Config::default(),
+        );
         assert_eq!(word_count, 4);
         assert_eq!(reading_time, 1);
 
         let (word_count, reading_time) = get_reading_analytics(
             "hello world ``` code goes here ``` goodbye world ``` dangling fence",
+            &Config::default(),
         );
         assert_eq!(word_count, 4);
         assert_eq!(reading_time, 1);
     }
+
+    #[test]
+    fn reading_analytics_no_shortcode_body() {
+        let content = "hello {% quote(author=\"Vincent\") %}\nsome quoted words\n{% end %} world";
+        let (word_count, _) = get_reading_analytics(content, &Config::default());
+        assert_eq!(word_count, 2);
+    }
+
+    #[test]
+    fn reading_analytics_with_config() {
+        let mut config = Config::default();
+        config.reading_speed = 100;
+        let mut content = String::from("``` let a = 1; ```");
+        for _ in 0..150 {
+            content.push_str(" Hello");
+        }
+        assert_eq!(get_reading_analytics(&content, &config), (150, 2));
+
+        config.count_code_blocks = true;
+        let (word_count, _) = get_reading_analytics(&content, &config);
+        assert_eq!(word_count, 153);
+    }
 }
@@ -1 +1,119 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
 pub use anyhow::*;
+
+/// An error that we can tie to a specific file and possibly a position in it.
+/// `kind` is a short identifier of where the error comes from, eg `front_matter` or `link`,
+/// which is used in the machine readable output.
+#[derive(Debug)]
+pub struct LocatedError {
+    pub kind: &'static str,
+    pub path: PathBuf,
+    /// 1-based line and column
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl fmt::Display for LocatedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for LocatedError {}
+
+/// Errors that happened while processing several files, keyed by the file they happened in.
+/// Used to report all the broken files at once rather than stopping at the first one.
+#[derive(Debug, Default)]
+pub struct FileErrors {
+    errors: Vec<(PathBuf, Error)>,
+    /// Replaces the default listing of the errors when displayed
+    summary: Option<String>,
+}
+
+impl FileErrors {
+    /// Uses that message as the human readable version of these errors
+    pub fn with_summary(mut self, summary: String) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    pub fn push<P: AsRef<Path>>(&mut self, path: P, error: Error) {
+        self.errors.push((path.as_ref().to_path_buf(), error));
+    }
+
+    pub fn extend(&mut self, other: FileErrors) {
+        self.errors.extend(other.errors);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(PathBuf, Error)> {
+        self.errors.iter()
+    }
+
+    /// `Ok` if there were no errors. A single error without summary is returned as is,
+    /// otherwise they are returned as a `FileErrors`, sorted by path.
+    pub fn into_result(mut self) -> Result<()> {
+        match self.errors.len() {
+            0 => Ok(()),
+            1 if self.summary.is_none() => Err(self.errors.pop().unwrap().1),
+            _ => {
+                self.errors.sort_by(|a, b| a.0.cmp(&b.0));
+                Err(Error::new(self))
+            }
+        }
+    }
+}
+
+impl FromIterator<(PathBuf, Error)> for FileErrors {
+    fn from_iter<I: IntoIterator<Item = (PathBuf, Error)>>(iter: I) -> Self {
+        FileErrors { errors: iter.into_iter().collect(), summary: None }
+    }
+}
+
+impl fmt::Display for FileErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref summary) = self.summary {
+            return write!(f, "{}", summary);
+        }
+        write!(f, "Found {} errors:", self.errors.len())?;
+        for (path, error) in &self.errors {
+            write!(f, "\n- {}\n  {:#}", path.display(), error)?;
+        }
+        std::result::Result::Ok(())
+    }
+}
+
+impl std::error::Error for FileErrors {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_error_is_returned_as_is() {
+        let mut errors = FileErrors::default();
+        assert!(errors.is_empty());
+        errors.push("a.md", anyhow!("oops"));
+        let err = errors.into_result().unwrap_err();
+        assert_eq!(err.to_string(), "oops");
+    }
+
+    #[test]
+    fn can_report_all_errors_sorted_by_path() {
+        let mut errors: FileErrors =
+            vec![(PathBuf::from("b.md"), anyhow!("inner").context("outer"))].into_iter().collect();
+        errors.push("a.md", anyhow!("oops"));
+        assert_eq!(errors.len(), 2);
+        let err = errors.into_result().unwrap_err();
+        assert_eq!(err.to_string(), "Found 2 errors:\n- a.md\n  oops\n- b.md\n  outer: inner");
+    }
+}
@@ -0,0 +1,224 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use config::Config;
+use errors::{Context, Result};
+use libs::image::codecs::ico::{IcoEncoder, IcoFrame};
+use libs::image::imageops::{self, FilterType};
+use libs::image::{self, ExtendedColorType, ImageFormat, Rgba, RgbaImage};
+use libs::serde_json::json;
+use libs::sha2::{Digest, Sha256};
+use utils::fs as ufs;
+
+use crate::processor::RESIZED_SUBDIR;
+
+/// Where the favicons are generated, in the processed images directory
+pub static FAVICON_SUBDIR: &str = "favicon";
+pub static WEBMANIFEST_FILENAME: &str = "site.webmanifest";
+/// The hash of everything the favicons are made from, to only generate them again on changes
+static HASH_FILENAME: &str = ".source-hash";
+
+/// The PNG favicons, with their filename and size
+const PNG_ICONS: &[(&str, u32)] = &[
+    ("favicon-16x16.png", 16),
+    ("favicon-32x32.png", 32),
+    ("apple-touch-icon.png", 180),
+    ("android-chrome-192x192.png", 192),
+    ("android-chrome-512x512.png", 512),
+];
+/// The sizes of the images in `favicon.ico`
+const ICO_SIZES: &[u32] = &[16, 32, 48];
+
+fn favicon_dir(base_path: &Path) -> PathBuf {
+    base_path.join("static").join(RESIZED_SUBDIR).join(FAVICON_SUBDIR)
+}
+
+/// The permalink of a file generated from the favicon source
+fn favicon_permalink(config: &Config, filename: &str) -> String {
+    format!(
+        "{}{}",
+        config.make_permalink(&format!("{}/{}", RESIZED_SUBDIR, FAVICON_SUBDIR)),
+        filename
+    )
+}
+
+/// Fits the image in a square of `size` pixels, centered, on a transparent or `background`
+/// canvas
+fn square_icon(img: &image::DynamicImage, size: u32, background: Option<[u8; 3]>) -> RgbaImage {
+    let resized = img.resize(size, size, FilterType::Lanczos3).to_rgba8();
+    let fill = background.map(|[r, g, b]| Rgba([r, g, b, 255])).unwrap_or(Rgba([0, 0, 0, 0]));
+    let mut canvas = RgbaImage::from_pixel(size, size, fill);
+    let x = (size - resized.width()) / 2;
+    let y = (size - resized.height()) / 2;
+    imageops::overlay(&mut canvas, &resized, x as i64, y as i64);
+    canvas
+}
+
+/// Generates the PNG favicons, `favicon.ico` and the web manifest from `favicon.source`, in
+/// the processed images directory of the static folder so they are copied with it.
+/// Nothing is done if the source image and the config they depend on didn't change since the
+/// last time. Returns whether they were generated.
+pub fn generate_favicons(base_path: &Path, config: &Config) -> Result<bool> {
+    let source = match config.favicon.source {
+        Some(ref s) => base_path.join(s),
+        None => return Ok(false),
+    };
+    let original = fs::read(&source)
+        .with_context(|| format!("Failed to read the favicon source {}", source.display()))?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(&original);
+    for part in [&config.favicon.background, &config.favicon.theme_color, &config.title] {
+        hasher.update(part.as_deref().unwrap_or_default().as_bytes());
+        hasher.update([0]);
+    }
+    let hash = format!("{:x}", hasher.finalize());
+    let dir = favicon_dir(base_path);
+    if fs::read_to_string(dir.join(HASH_FILENAME)).is_ok_and(|h| h == hash) {
+        return Ok(false);
+    }
+
+    let img = image::load_from_memory(&original)
+        .with_context(|| format!("Failed to decode the favicon source {}", source.display()))?;
+    ufs::create_directory(&dir)?;
+    let background = config.favicon.background_rgb();
+    for (filename, size) in PNG_ICONS {
+        // Apple devices show the transparent parts in black
+        let bg = if *filename == "apple-touch-icon.png" { background } else { None };
+        square_icon(&img, *size, bg)
+            .save_with_format(dir.join(filename), ImageFormat::Png)
+            .with_context(|| format!("Failed to write the favicon {}", filename))?;
+    }
+
+    let icons = ICO_SIZES.iter().map(|size| square_icon(&img, *size, None)).collect::<Vec<_>>();
+    let frames = icons
+        .iter()
+        .map(|icon| {
+            IcoFrame::as_png(icon.as_raw(), icon.width(), icon.height(), ExtendedColorType::Rgba8)
+        })
+        .collect::<image::ImageResult<Vec<_>>>()?;
+    let mut ico = Vec::new();
+    IcoEncoder::new(&mut ico).encode_images(&frames)?;
+    fs::write(dir.join("favicon.ico"), ico).context("Failed to write favicon.ico")?;
+
+    let title = config.title.as_deref().unwrap_or_default();
+    let manifest_icons: Vec<_> = [192, 512]
+        .iter()
+        .map(|size| {
+            json!({
+                "src": format!("android-chrome-{0}x{0}.png", size),
+                "sizes": format!("{0}x{0}", size),
+                "type": "image/png",
+            })
+        })
+        .collect();
+    let mut manifest = json!({
+        "name": title,
+        "short_name": title,
+        "icons": manifest_icons,
+        "display": "standalone",
+    });
+    if let Some(color) = config.favicon.theme_color() {
+        manifest["theme_color"] = json!(color);
+    }
+    if let Some(ref color) = config.favicon.background {
+        manifest["background_color"] = json!(color);
+    }
+    ufs::create_file(
+        &dir.join(WEBMANIFEST_FILENAME),
+        libs::serde_json::to_string_pretty(&manifest).unwrap(),
+    )?;
+    ufs::create_file(&dir.join(HASH_FILENAME), &hash)?;
+    Ok(true)
+}
+
+/// The `<link>` and `<meta>` tags for the favicons and web manifest, empty if there is no
+/// `favicon.source`
+pub fn favicon_tags(config: &Config) -> String {
+    if config.favicon.source.is_none() {
+        return String::new();
+    }
+    let mut tags = vec![format!(
+        r#"<link rel="icon" href="{}" sizes="48x48">"#,
+        favicon_permalink(config, "favicon.ico")
+    )];
+    for (filename, size) in &PNG_ICONS[..2] {
+        tags.push(format!(
+            r#"<link rel="icon" type="image/png" sizes="{0}x{0}" href="{1}">"#,
+            size,
+            favicon_permalink(config, filename)
+        ));
+    }
+    tags.push(format!(
+        r#"<link rel="apple-touch-icon" sizes="180x180" href="{}">"#,
+        favicon_permalink(config, "apple-touch-icon.png")
+    ));
+    tags.push(format!(
+        r#"<link rel="manifest" href="{}">"#,
+        favicon_permalink(config, WEBMANIFEST_FILENAME)
+    ));
+    if let Some(color) = config.favicon.theme_color() {
+        tags.push(format!(r#"<meta name="theme-color" content="{}">"#, color));
+    }
+    tags.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_generate_favicons_once() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let base_path = tmp_dir.path();
+        // A wide image, to check it is centered
+        let mut source = RgbaImage::from_pixel(64, 32, Rgba([255, 0, 0, 255]));
+        source.put_pixel(0, 0, Rgba([0, 0, 255, 255]));
+        source.save(base_path.join("icon.png")).unwrap();
+
+        let mut config = Config::default_for_test();
+        config.title = Some("My site".to_string());
+        assert!(!generate_favicons(base_path, &config).unwrap());
+        assert_eq!(favicon_tags(&config), "");
+
+        config.favicon.source = Some("icon.png".to_string());
+        config.favicon.background = Some("#fff".to_string());
+        assert!(generate_favicons(base_path, &config).unwrap());
+        let dir = favicon_dir(base_path);
+        for (filename, size) in PNG_ICONS {
+            let icon = image::open(dir.join(filename)).unwrap().to_rgba8();
+            assert_eq!(icon.dimensions(), (*size, *size));
+            let corner = icon.get_pixel(0, 0);
+            if *filename == "apple-touch-icon.png" {
+                assert_eq!(corner, &Rgba([255, 255, 255, 255]));
+            } else {
+                assert_eq!(corner[3], 0);
+            }
+        }
+        let ico = image::load_from_memory_with_format(
+            &fs::read(dir.join("favicon.ico")).unwrap(),
+            ImageFormat::Ico,
+        )
+        .unwrap();
+        assert_eq!(ico.width(), 48);
+        let manifest: libs::serde_json::Value = libs::serde_json::from_str(
+            &fs::read_to_string(dir.join(WEBMANIFEST_FILENAME)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(manifest["name"], "My site");
+        assert_eq!(manifest["theme_color"], "#fff");
+        assert_eq!(manifest["icons"][1]["src"], "android-chrome-512x512.png");
+
+        // Nothing changed
+        assert!(!generate_favicons(base_path, &config).unwrap());
+        // The config the files depend on changed
+        config.favicon.theme_color = Some("#000".to_string());
+        assert!(generate_favicons(base_path, &config).unwrap());
+
+        let tags = favicon_tags(&config);
+        assert!(tags.contains(
+            r#"<link rel="apple-touch-icon" sizes="180x180" href="http://a-website.com/processed_images/favicon/apple-touch-icon.png">"#
+        ));
+        assert!(tags.contains(r##"<meta name="theme-color" content="#000">"##));
+    }
+}
@@ -1,4 +1,5 @@
 use errors::{anyhow, Result};
+use libs::image::ImageFormat;
 use std::hash::{Hash, Hasher};
 
 const DEFAULT_Q_JPG: u8 = 75;
@@ -12,6 +13,8 @@ pub enum Format {
     Png,
     /// WebP, The `u8` argument is WebP quality (in percent), None meaning lossless.
     WebP(Option<u8>),
+    /// AVIF, only available if Zola was built with the `avif` feature
+    Avif,
 }
 
 impl Format {
@@ -32,6 +35,8 @@ impl Format {
             "jpeg" | "jpg" => Ok(Jpeg(jpg_quality)),
             "png" => Ok(Png),
             "webp" => Ok(WebP(quality)),
+            "avif" if ImageFormat::Avif.writing_enabled() => Ok(Avif),
+            "avif" => Err(anyhow!("AVIF images require Zola to be built with the `avif` feature")),
             _ => Err(anyhow!("Invalid image format: {}", format)),
         }
     }
@@ -44,6 +49,18 @@ impl Format {
             Png => "png",
             Jpeg(_) => "jpg",
             WebP(_) => "webp",
+            Avif => "avif",
+        }
+    }
+
+    pub fn mime(&self) -> &str {
+        use Format::*;
+
+        match *self {
+            Png => "image/png",
+            Jpeg(_) => "image/jpeg",
+            WebP(_) => "image/webp",
+            Avif => "image/avif",
         }
     }
 }
@@ -58,6 +75,7 @@ impl Hash for Format {
             Jpeg(q) => 1001 + q as u16,
             WebP(None) => 2000,
             WebP(Some(q)) => 2001 + q as u16,
+            Avif => 3000,
         };
 
         hasher.write_u16(q);
@@ -1,22 +1,36 @@
 use std::borrow::Cow;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use std::io::Cursor;
 use std::path::Path;
 
 use crate::format::Format;
 use crate::ResizeOperation;
+use errors::{anyhow, Result};
+use exif::experimental::Writer as ExifWriter;
+use exif::{Exif, In, Tag};
 use libs::image::DynamicImage;
 
+fn read_exif(path: &Path) -> Option<Exif> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut buf_reader = std::io::BufReader::new(&file);
+    exif::Reader::new().read_from_container(&mut buf_reader).ok()
+}
+
+/// The EXIF orientation of the image, if it has one
+pub fn get_orientation(path: &Path) -> Option<u32> {
+    read_exif(path)?.get_field(Tag::Orientation, In::PRIMARY)?.value.get_uint(0)
+}
+
+/// Whether applying that EXIF orientation swaps the width and height of the image
+pub fn orientation_swaps_dimensions(orientation: u32) -> bool {
+    (5..=8).contains(&orientation)
+}
+
 /// Apply image rotation based on EXIF data
 /// Returns `None` if no transformation is needed
 pub fn fix_orientation(img: &DynamicImage, path: &Path) -> Option<DynamicImage> {
-    let file = std::fs::File::open(path).ok()?;
-    let mut buf_reader = std::io::BufReader::new(&file);
-    let exif_reader = exif::Reader::new();
-    let exif = exif_reader.read_from_container(&mut buf_reader).ok()?;
-    let orientation =
-        exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?.value.get_uint(0)?;
-    match orientation {
+    match get_orientation(path)? {
         // Values are taken from the page 30 of
         // https://www.cipa.jp/std/documents/e/DC-008-2012_E.pdf
         // For more details check http://sylvana.net/jpegcrop/exif_orientation.html
@@ -32,6 +46,41 @@ pub fn fix_orientation(img: &DynamicImage, path: &Path) -> Option<DynamicImage>
     }
 }
 
+/// The EXIF metadata of the image to copy in its resized versions, as a TIFF structure.
+/// The orientation is left out since it has been applied to the pixels, and so are the dimensions
+/// and the thumbnail of the original image.
+/// Returns `None` if the image has no EXIF metadata.
+pub fn get_exif_to_keep(path: &Path) -> Option<Vec<u8>> {
+    let exif = read_exif(path)?;
+    let mut writer = ExifWriter::new();
+    for field in exif.fields() {
+        if field.ifd_num == In::PRIMARY
+            && !matches!(field.tag, Tag::Orientation | Tag::PixelXDimension | Tag::PixelYDimension)
+        {
+            writer.push_field(field);
+        }
+    }
+    let mut buf = Cursor::new(Vec::new());
+    writer.write(&mut buf, exif.little_endian()).ok()?;
+    Some(buf.into_inner())
+}
+
+/// Insert the EXIF metadata in an APP1 segment, right after the start of image marker of the JPEG
+pub fn insert_jpeg_exif(jpeg: &mut Vec<u8>, exif: &[u8]) -> Result<()> {
+    const EXIF_HEADER: &[u8] = b"Exif\0\0";
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        return Err(anyhow!("Not a JPEG image"));
+    }
+    let length = u16::try_from(2 + EXIF_HEADER.len() + exif.len())
+        .map_err(|_| anyhow!("The EXIF metadata is too large to fit in a JPEG image"))?;
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&length.to_be_bytes());
+    segment.extend_from_slice(EXIF_HEADER);
+    segment.extend_from_slice(exif);
+    jpeg.splice(2..2, segment);
+    Ok(())
+}
+
 /// We only use the input_path to get the file stem.
 /// Hashing the resolved `input_path` would include the absolute path to the image
 /// with all filesystem components.
@@ -40,11 +89,16 @@ pub fn get_processed_filename(
     input_src: &str,
     op: &ResizeOperation,
     format: &Format,
+    keep_metadata: bool,
 ) -> String {
     let mut hasher = DefaultHasher::new();
     hasher.write(input_src.as_ref());
     op.hash(&mut hasher);
     format.hash(&mut hasher);
+    // Only hashed when set so the filenames of the images without metadata don't change
+    if keep_metadata {
+        keep_metadata.hash(&mut hasher);
+    }
     let hash = hasher.finish();
     let filename = input_path
         .file_stem()
@@ -1,10 +1,16 @@
+mod favicon;
 mod format;
 mod helpers;
 mod meta;
 mod ops;
+mod optimize;
+mod placeholder;
 mod processor;
 
+pub use favicon::{favicon_tags, generate_favicons, FAVICON_SUBDIR};
 pub use helpers::fix_orientation;
 pub use meta::{read_image_metadata, ImageMeta, ImageMetaResponse};
 pub use ops::{ResizeInstructions, ResizeOperation};
-pub use processor::{EnqueueResponse, Processor, RESIZED_SUBDIR};
+pub use optimize::{is_optimizable, optimize_asset};
+pub use placeholder::{get_placeholder, Placeholder, PLACEHOLDER_SUBDIR};
+pub use processor::{EnqueueResponse, FormatResponse, Processor, RESIZED_SUBDIR};
@@ -6,10 +6,12 @@ use serde::Serialize;
 use std::ffi::OsStr;
 use std::path::Path;
 
+use crate::helpers::{get_orientation, orientation_swaps_dimensions};
+
 /// Size and format read cheaply with `image`'s `Reader`.
 #[derive(Debug)]
 pub struct ImageMeta {
-    /// (w, h)
+    /// (w, h), once the EXIF orientation is applied
     pub size: (u32, u32),
     pub format: Option<ImageFormat>,
 }
@@ -18,7 +20,12 @@ impl ImageMeta {
     pub fn read(path: &Path) -> ImageResult<Self> {
         let reader = ImgReader::open(path).and_then(ImgReader::with_guessed_format)?;
         let format = reader.format();
-        let size = reader.into_dimensions()?;
+        let (width, height) = reader.into_dimensions()?;
+        let size = if get_orientation(path).is_some_and(orientation_swaps_dimensions) {
+            (height, width)
+        } else {
+            (width, height)
+        };
 
         Ok(Self { size, format })
     }
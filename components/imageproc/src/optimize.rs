@@ -0,0 +1,65 @@
+use std::fs;
+use std::path::Path;
+
+use config::AssetOptimization;
+use errors::{Context, Result};
+use libs::image::codecs::jpeg::JpegEncoder;
+use libs::image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use libs::image::{self, ImageFormat};
+
+use crate::helpers::fix_orientation;
+
+/// Whether `optimize_asset` knows how to optimize the file at that path
+pub fn is_optimizable(path: &Path) -> bool {
+    matches!(ImageFormat::from_path(path), Ok(ImageFormat::Png) | Ok(ImageFormat::Jpeg))
+}
+
+/// Optimizes the PNG or JPEG image at `src` and writes the result to `dest`.
+/// JPEG images are re-encoded at the configured quality, which strips their EXIF metadata:
+/// the EXIF orientation is applied to the pixels first so the image is still displayed the same way.
+/// If the optimized image is not smaller than the original one, the original is copied as is.
+/// Returns whether the optimized image was written.
+pub fn optimize_asset(src: &Path, dest: &Path, options: &AssetOptimization) -> Result<bool> {
+    let original =
+        fs::read(src).with_context(|| format!("Failed to read image {}", src.display()))?;
+    let format = ImageFormat::from_path(src)?;
+    let img = image::load_from_memory_with_format(&original, format)
+        .with_context(|| format!("Failed to decode image {}", src.display()))?;
+
+    let mut optimized = Vec::new();
+    match format {
+        ImageFormat::Png => {
+            let compression = match options.png_level {
+                0 | 1 => CompressionType::Fast,
+                2 | 3 => CompressionType::Default,
+                _ => CompressionType::Best,
+            };
+            img.write_with_encoder(PngEncoder::new_with_quality(
+                &mut optimized,
+                compression,
+                FilterType::Adaptive,
+            ))?;
+        }
+        ImageFormat::Jpeg => {
+            let img = fix_orientation(&img, src).unwrap_or(img);
+            img.write_with_encoder(JpegEncoder::new_with_quality(
+                &mut optimized,
+                options.jpeg_quality,
+            ))?;
+        }
+        _ => optimized.clone_from(&original),
+    }
+
+    let use_optimized = optimized.len() < original.len();
+    // `dest` can be a hard link to `src` when the static files are hard linked: writing to it
+    // would overwrite the original image
+    match fs::remove_file(dest) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(e).with_context(|| format!("Failed to remove {}", dest.display()));
+        }
+        _ => (),
+    }
+    fs::write(dest, if use_optimized { &optimized } else { &original })
+        .with_context(|| format!("Failed to write image {}", dest.display()))?;
+    Ok(use_optimized)
+}
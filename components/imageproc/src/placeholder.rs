@@ -0,0 +1,166 @@
+use std::fs;
+use std::path::Path;
+
+use errors::{anyhow, Context, Result};
+use libs::base64::engine::{general_purpose::STANDARD as standard_b64, Engine};
+use libs::image::codecs::jpeg::JpegEncoder;
+use libs::image::imageops::FilterType;
+use libs::image::{self, DynamicImage, GenericImageView};
+use libs::sha2::{Digest, Sha256};
+use utils::fs as ufs;
+
+use crate::helpers::fix_orientation;
+use crate::processor::RESIZED_SUBDIR;
+
+/// Where the placeholders are cached, in the processed images directory
+pub static PLACEHOLDER_SUBDIR: &str = "placeholders";
+/// The width of the low quality image placeholders
+const LQIP_WIDTH: u32 = 20;
+const LQIP_QUALITY: u8 = 60;
+
+/// What to show while an image is loading
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placeholder {
+    /// The dominant color of the image, as an hex color
+    Color,
+    /// A tiny blurred version of the image, as a JPEG data URI
+    Lqip,
+}
+
+impl Placeholder {
+    pub fn from_arg(arg: &str) -> Result<Self> {
+        match arg {
+            "color" => Ok(Placeholder::Color),
+            "lqip" => Ok(Placeholder::Lqip),
+            _ => Err(anyhow!("Invalid placeholder `{}`, it must be `color` or `lqip`", arg)),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Placeholder::Color => "color",
+            Placeholder::Lqip => "lqip",
+        }
+    }
+}
+
+/// The most common color of the image, averaged over the pixels of that color once quantized.
+/// The transparent pixels are not counted, unless there are only transparent pixels.
+fn dominant_color(img: &DynamicImage) -> String {
+    let (width, height) = img.dimensions();
+    let thumbnail =
+        if width > 64 || height > 64 { img.thumbnail(64, 64) } else { img.clone() }.to_rgba8();
+    // 4 bits per channel, so 4096 buckets of (count, sum of r, g and b)
+    let fill = |only_opaque: bool| {
+        let mut buckets = vec![(0u32, [0u32; 3]); 4096];
+        for pixel in thumbnail.pixels() {
+            let [r, g, b, a] = pixel.0;
+            if only_opaque && a < 128 {
+                continue;
+            }
+            let index = ((r as usize >> 4) << 8) | ((g as usize >> 4) << 4) | (b as usize >> 4);
+            let bucket = &mut buckets[index];
+            bucket.0 += 1;
+            for (sum, value) in bucket.1.iter_mut().zip([r, g, b]) {
+                *sum += value as u32;
+            }
+        }
+        buckets
+    };
+    let mut buckets = fill(true);
+    if buckets.iter().all(|(count, _)| *count == 0) {
+        buckets = fill(false);
+    }
+
+    let (count, sums) = buckets.iter().max_by_key(|(count, _)| *count).unwrap();
+    let [r, g, b] = sums.map(|sum| sum / (*count).max(1));
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// A `LQIP_WIDTH` pixels wide blurred JPEG of the image, as a data URI
+fn lqip(img: &DynamicImage) -> Result<String> {
+    let (width, height) = img.dimensions();
+    let lqip_height = ((height as u64 * LQIP_WIDTH as u64) / width.max(1) as u64).max(1) as u32;
+    let small = img.resize_exact(LQIP_WIDTH, lqip_height, FilterType::Triangle).blur(1.0);
+    let mut jpeg = Vec::new();
+    small.to_rgb8().write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, LQIP_QUALITY))?;
+    Ok(format!("data:image/jpeg;base64,{}", standard_b64.encode(jpeg)))
+}
+
+/// The placeholder of the image at `path`, cached in the processed images directory by hash of
+/// the image so it is only computed again when the image changes
+pub fn get_placeholder(base_path: &Path, path: &Path, placeholder: Placeholder) -> Result<String> {
+    let original =
+        fs::read(path).with_context(|| format!("Failed to read image: {}", path.display()))?;
+    let hash = format!("{:x}", Sha256::digest(&original));
+    let cache_path = base_path
+        .join("static")
+        .join(RESIZED_SUBDIR)
+        .join(PLACEHOLDER_SUBDIR)
+        .join(format!("{}.{}", &hash[..16], placeholder.name()));
+    if let Ok(cached) = fs::read_to_string(&cache_path) {
+        return Ok(cached);
+    }
+
+    let img = image::load_from_memory(&original)
+        .with_context(|| format!("Failed to decode image: {}", path.display()))?;
+    let value = match placeholder {
+        Placeholder::Color => dominant_color(&img),
+        Placeholder::Lqip => lqip(&fix_orientation(&img, path).unwrap_or(img))?,
+    };
+    ufs::create_directory(cache_path.parent().unwrap())?;
+    ufs::create_file(&cache_path, &value)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libs::image::{Rgba, RgbaImage};
+
+    #[test]
+    fn can_get_dominant_color() {
+        // Mostly red with a bit of blue and a transparent green part that doesn't count
+        let mut img = RgbaImage::from_pixel(10, 10, Rgba([250, 10, 10, 255]));
+        for x in 0..3 {
+            for y in 0..10 {
+                img.put_pixel(x, y, Rgba([0, 0, 255, 255]));
+            }
+        }
+        for x in 3..8 {
+            for y in 0..10 {
+                img.put_pixel(x, y, Rgba([0, 255, 0, 0]));
+            }
+        }
+        assert_eq!(dominant_color(&DynamicImage::ImageRgba8(img)), "#0000ff");
+
+        let transparent = RgbaImage::from_pixel(4, 4, Rgba([0, 255, 0, 0]));
+        assert_eq!(dominant_color(&DynamicImage::ImageRgba8(transparent)), "#00ff00");
+    }
+
+    #[test]
+    fn can_get_cached_placeholders() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let base_path = tmp_dir.path();
+        let path = base_path.join("red.png");
+        RgbaImage::from_pixel(100, 50, Rgba([255, 0, 0, 255])).save(&path).unwrap();
+
+        assert_eq!(get_placeholder(base_path, &path, Placeholder::Color).unwrap(), "#ff0000");
+        let lqip = get_placeholder(base_path, &path, Placeholder::Lqip).unwrap();
+        let jpeg = standard_b64.decode(lqip.trim_start_matches("data:image/jpeg;base64,")).unwrap();
+        assert_eq!(image::load_from_memory(&jpeg).unwrap().dimensions(), (20, 10));
+
+        let cache_dir = base_path.join("static").join(RESIZED_SUBDIR).join(PLACEHOLDER_SUBDIR);
+        assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 2);
+        // Read from the cache, as long as the image doesn't change
+        for entry in fs::read_dir(&cache_dir).unwrap() {
+            fs::write(entry.unwrap().path(), "cached").unwrap();
+        }
+        assert_eq!(get_placeholder(base_path, &path, Placeholder::Color).unwrap(), "cached");
+        RgbaImage::from_pixel(100, 50, Rgba([0, 0, 255, 255])).save(&path).unwrap();
+        assert_eq!(get_placeholder(base_path, &path, Placeholder::Color).unwrap(), "#0000ff");
+
+        fs::write(&path, "not an image").unwrap();
+        assert!(get_placeholder(base_path, &path, Placeholder::Lqip).is_err());
+    }
+}
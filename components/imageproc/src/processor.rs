@@ -15,7 +15,7 @@ use serde::{Deserialize, Serialize};
 use utils::fs as ufs;
 
 use crate::format::Format;
-use crate::helpers::get_processed_filename;
+use crate::helpers::{get_exif_to_keep, get_processed_filename, insert_jpeg_exif};
 use crate::{fix_orientation, ImageMeta, ResizeInstructions, ResizeOperation};
 
 pub static RESIZED_SUBDIR: &str = "processed_images";
@@ -27,6 +27,8 @@ pub struct ImageOp {
     output_path: PathBuf,
     instr: ResizeInstructions,
     format: Format,
+    /// Whether to copy the EXIF metadata of the original image, only possible for JPEG outputs
+    keep_metadata: bool,
     /// Whether we actually want to perform that op.
     /// In practice we set it to true if the output file already
     /// exists and is not stale. We do need to keep the ImageOp around for pruning though.
@@ -59,8 +61,14 @@ impl ImageOp {
                 img.write_to(&mut buffered_f, ImageFormat::Png)?;
             }
             Format::Jpeg(q) => {
-                let mut encoder = JpegEncoder::new_with_quality(&mut buffered_f, q);
-                encoder.encode_image(&img)?;
+                let mut jpeg = Vec::new();
+                JpegEncoder::new_with_quality(&mut jpeg, q).encode_image(&img)?;
+                if self.keep_metadata {
+                    if let Some(exif) = get_exif_to_keep(&self.input_path) {
+                        insert_jpeg_exif(&mut jpeg, &exif)?;
+                    }
+                }
+                buffered_f.write_all(&jpeg)?;
             }
             Format::WebP(q) => {
                 let encoder = webp::Encoder::from_image(&img)
@@ -71,6 +79,9 @@ impl ImageOp {
                 };
                 buffered_f.write_all(memory.as_bytes())?;
             }
+            Format::Avif => {
+                img.write_to(&mut buffered_f, ImageFormat::Avif)?;
+            }
         }
 
         Ok(())
@@ -91,6 +102,18 @@ pub struct EnqueueResponse {
     pub orig_width: u32,
     /// Original image height
     pub orig_height: u32,
+    /// The same image in the other formats asked for
+    pub formats: Vec<FormatResponse>,
+}
+
+/// The resized image in another format, to offer it with `<picture>`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FormatResponse {
+    /// The file extension of the format, eg `webp`
+    pub format: String,
+    pub mime: String,
+    pub url: String,
+    pub static_path: String,
 }
 
 impl EnqueueResponse {
@@ -104,7 +127,7 @@ impl EnqueueResponse {
         let (width, height) = instr.resize_instruction.unwrap_or(meta.size);
         let (orig_width, orig_height) = meta.size;
 
-        Self { url, static_path, width, height, orig_width, orig_height }
+        Self { url, static_path, width, height, orig_width, orig_height, formats: Vec::new() }
     }
 }
 
@@ -124,14 +147,14 @@ impl Processor {
     pub fn new(base_path: PathBuf, config: &Config) -> Processor {
         Processor {
             output_dir: base_path.join("static").join(RESIZED_SUBDIR),
-            base_url: config.make_permalink(RESIZED_SUBDIR),
+            base_url: format!("{}/", config.make_asset_permalink(RESIZED_SUBDIR)),
             img_ops: HashSet::default(),
             meta_cache: HashMap::default(),
         }
     }
 
     pub fn set_base_url(&mut self, config: &Config) {
-        self.base_url = config.make_permalink(RESIZED_SUBDIR);
+        self.base_url = format!("{}/", config.make_asset_permalink(RESIZED_SUBDIR));
     }
 
     pub fn num_img_ops(&self) -> usize {
@@ -145,6 +168,7 @@ impl Processor {
         input_path: PathBuf,
         format: &str,
         quality: Option<u8>,
+        keep_metadata: bool,
     ) -> Result<EnqueueResponse> {
         // First we load metadata from the cache if possible, otherwise from the file itself
         if !self.meta_cache.contains_key(&input_path) {
@@ -157,7 +181,7 @@ impl Processor {
         // We get the output format
         let format = Format::from_args(meta.is_lossy(), format, quality)?;
         // Now we have all the data we need to generate the output filename and the response
-        let filename = get_processed_filename(&input_path, &input_src, &op, &format);
+        let filename = get_processed_filename(&input_path, &input_src, &op, &format, keep_metadata);
         let url = format!("{}{}", self.base_url, filename);
         let static_path = Path::new("static").join(RESIZED_SUBDIR).join(&filename);
         let output_path = self.output_dir.join(&filename);
@@ -169,12 +193,54 @@ impl Processor {
             output_path,
             instr,
             format,
+            keep_metadata,
         };
         self.img_ops.insert(img_op);
 
         Ok(enqueue_response)
     }
 
+    /// Like `enqueue`, with the same operation also enqueued for each of the `formats`, which are
+    /// listed in the `formats` of the response
+    #[allow(clippy::too_many_arguments)]
+    pub fn enqueue_with_formats(
+        &mut self,
+        op: ResizeOperation,
+        input_src: String,
+        input_path: PathBuf,
+        format: &str,
+        formats: &[String],
+        quality: Option<u8>,
+        keep_metadata: bool,
+    ) -> Result<EnqueueResponse> {
+        let mut response = self.enqueue(
+            op,
+            input_src.clone(),
+            input_path.clone(),
+            format,
+            quality,
+            keep_metadata,
+        )?;
+        for other in formats {
+            let other_response = self.enqueue(
+                op,
+                input_src.clone(),
+                input_path.clone(),
+                other,
+                quality,
+                keep_metadata,
+            )?;
+            let other = Format::from_args(self.meta_cache[&input_path].is_lossy(), other, quality)?;
+            response.formats.push(FormatResponse {
+                format: other.extension().to_string(),
+                mime: other.mime().to_string(),
+                url: other_response.url,
+                static_path: other_response.static_path,
+            });
+        }
+        Ok(response)
+    }
+
     /// Run the enqueued image operations
     pub fn do_process(&mut self) -> Result<()> {
         if !self.img_ops.is_empty() {
@@ -0,0 +1,72 @@
+use std::fs;
+use std::path::PathBuf;
+
+use config::AssetOptimization;
+use imageproc::{is_optimizable, optimize_asset};
+use libs::image::{self, GenericImageView};
+use libs::once_cell::sync::Lazy;
+use tempfile::tempdir;
+
+static TEST_IMGS: Lazy<PathBuf> =
+    Lazy::new(|| [env!("CARGO_MANIFEST_DIR"), "tests", "test_imgs"].iter().collect());
+
+#[test]
+fn only_png_and_jpeg_are_optimizable() {
+    assert!(is_optimizable(&TEST_IMGS.join("png.png")));
+    assert!(is_optimizable(&TEST_IMGS.join("jpg.jpg")));
+    assert!(!is_optimizable(&TEST_IMGS.join("webp.webp")));
+    assert!(!is_optimizable(&TEST_IMGS.join("svg.svg")));
+}
+
+#[test]
+fn can_optimize_jpeg() {
+    let tmp_dir = tempdir().unwrap();
+    let src = TEST_IMGS.join("jpg.jpg");
+    let dest = tmp_dir.path().join("jpg.jpg");
+    let options = AssetOptimization { jpeg_quality: 50, ..Default::default() };
+
+    assert!(optimize_asset(&src, &dest, &options).unwrap());
+    assert!(fs::metadata(&dest).unwrap().len() < fs::metadata(&src).unwrap().len());
+    let (original, optimized) = (image::open(&src).unwrap(), image::open(&dest).unwrap());
+    assert_eq!(original.dimensions(), optimized.dimensions());
+}
+
+#[test]
+fn optimized_png_is_never_larger() {
+    let tmp_dir = tempdir().unwrap();
+    let src = TEST_IMGS.join("png.png");
+    let dest = tmp_dir.path().join("png.png");
+    let options = AssetOptimization { png_level: 6, ..Default::default() };
+
+    let optimized = optimize_asset(&src, &dest, &options).unwrap();
+    assert!(fs::metadata(&dest).unwrap().len() <= fs::metadata(&src).unwrap().len());
+    if !optimized {
+        assert_eq!(fs::read(&dest).unwrap(), fs::read(&src).unwrap());
+    }
+}
+
+#[test]
+fn keeps_original_if_optimized_version_is_larger() {
+    let tmp_dir = tempdir().unwrap();
+    // This image is tiny and already as small as it gets
+    let src = TEST_IMGS.join("exif_0.jpg");
+    let dest = tmp_dir.path().join("exif_0.jpg");
+    let options = AssetOptimization { jpeg_quality: 100, ..Default::default() };
+
+    assert!(!optimize_asset(&src, &dest, &options).unwrap());
+    assert_eq!(fs::read(&dest).unwrap(), fs::read(&src).unwrap());
+}
+
+#[test]
+fn does_not_overwrite_the_source_through_a_hard_link() {
+    let tmp_dir = tempdir().unwrap();
+    let src = tmp_dir.path().join("src.jpg");
+    let dest = tmp_dir.path().join("dest.jpg");
+    fs::copy(TEST_IMGS.join("jpg.jpg"), &src).unwrap();
+    fs::hard_link(&src, &dest).unwrap();
+    let options = AssetOptimization { jpeg_quality: 50, ..Default::default() };
+
+    assert!(optimize_asset(&src, &dest, &options).unwrap());
+    assert_eq!(fs::read(&src).unwrap(), fs::read(TEST_IMGS.join("jpg.jpg")).unwrap());
+    assert!(fs::metadata(&dest).unwrap().len() < fs::metadata(&src).unwrap().len());
+}
@@ -1,7 +1,10 @@
 use std::env;
-use std::path::{PathBuf, MAIN_SEPARATOR as SLASH};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR as SLASH};
 
 use config::Config;
+use exif::{In, Tag};
 use imageproc::{fix_orientation, ImageMetaResponse, Processor, ResizeOperation};
 use libs::image::{self, DynamicImage, GenericImageView, Pixel};
 use libs::once_cell::sync::Lazy;
@@ -50,7 +53,8 @@ fn image_op_test(
     let mut proc = Processor::new(tmpdir.clone(), &config);
     let resize_op = ResizeOperation::from_args(op, width, height).unwrap();
 
-    let resp = proc.enqueue(resize_op, source_img.into(), source_path, format, None).unwrap();
+    let resp =
+        proc.enqueue(resize_op, source_img.into(), source_path, format, None, false).unwrap();
     assert_processed_path_matches(&resp.url, "https://example.com/processed_images/", expect_ext);
     assert_processed_path_matches(&resp.static_path, PROCESSED_PREFIX.as_str(), expect_ext);
     assert_eq!(resp.width, expect_width);
@@ -229,7 +233,7 @@ fn resize_and_check(source_img: &str) -> bool {
     let mut proc = Processor::new(tmpdir.clone(), &config);
     let resize_op = ResizeOperation::from_args("scale", Some(16), Some(16)).unwrap();
 
-    let resp = proc.enqueue(resize_op, source_img.into(), source_path, "jpg", None).unwrap();
+    let resp = proc.enqueue(resize_op, source_img.into(), source_path, "jpg", None, false).unwrap();
 
     proc.do_process().unwrap();
     let processed_path = PathBuf::from(&resp.static_path);
@@ -270,3 +274,132 @@ fn asymmetric_resize_with_exif_orientations() {
     // 8: Rotate 270 CW
     image_op_test("exif_8.jpg", "scale", Some(16), Some(32), "auto", "jpg", 16, 32, 16, 16);
 }
+
+fn read_exif(path: &Path) -> Option<exif::Exif> {
+    let mut reader = BufReader::new(File::open(path).unwrap());
+    exif::Reader::new().read_from_container(&mut reader).ok()
+}
+
+#[test]
+fn read_image_metadata_applies_exif_orientation() {
+    // Portrait photos stored as landscape ones with orientations 5 to 8
+    for orientation in 1..=8 {
+        let path = TEST_IMGS.join(format!("exif_portrait_{}.jpg", orientation));
+        let meta = imageproc::read_image_metadata(&path).unwrap();
+        assert_eq!((meta.width, meta.height), (16, 32), "orientation {}", orientation);
+    }
+}
+
+#[test]
+fn resize_image_with_portrait_exif_orientations() {
+    for orientation in 1..=8 {
+        let source_img = format!("exif_portrait_{}.jpg", orientation);
+        let source_path = TEST_IMGS.join(&source_img);
+        assert!(read_exif(&source_path)
+            .unwrap()
+            .get_field(Tag::GPSLatitude, In::PRIMARY)
+            .is_some());
+        let tmpdir = tempfile::tempdir().unwrap().into_path();
+        let config = Config::parse(CONFIG).unwrap();
+        let mut proc = Processor::new(tmpdir.clone(), &config);
+        let resize_op = ResizeOperation::from_args("fit_width", Some(8), None).unwrap();
+
+        let stripped = proc
+            .enqueue(resize_op, source_img.clone(), source_path.clone(), "jpg", None, false)
+            .unwrap();
+        let kept = proc.enqueue(resize_op, source_img, source_path, "jpg", None, true).unwrap();
+        assert_ne!(stripped.url, kept.url);
+        assert_eq!((stripped.width, stripped.height), (8, 16));
+        assert_eq!((stripped.orig_width, stripped.orig_height), (16, 32));
+        proc.do_process().unwrap();
+
+        // The metadata is stripped by default
+        let stripped_path = tmpdir.join(&stripped.static_path);
+        assert!(read_exif(&stripped_path).is_none());
+        let img = image::open(&stripped_path).unwrap();
+        assert_eq!(img.dimensions(), (8, 16));
+        // top left is red, top right is green, bottom left is blue, bottom right is white
+        assert!(img.get_pixel(0, 0)[0] > 200 && img.get_pixel(0, 0)[1] < 50);
+        assert!(img.get_pixel(7, 0)[1] > 200 && img.get_pixel(7, 0)[0] < 50);
+        assert!(img.get_pixel(0, 15)[2] > 200 && img.get_pixel(0, 15)[0] < 50);
+        assert!(img.get_pixel(7, 15).channels().iter().all(|c| *c > 200));
+
+        // And kept on demand, minus the orientation that has been applied
+        let kept_path = tmpdir.join(&kept.static_path);
+        let exif = read_exif(&kept_path).unwrap();
+        assert!(exif.get_field(Tag::Orientation, In::PRIMARY).is_none());
+        assert!(exif.get_field(Tag::GPSLatitude, In::PRIMARY).is_some());
+        assert_eq!(
+            exif.get_field(Tag::Make, In::PRIMARY).unwrap().display_value().to_string(),
+            "\"Zola\""
+        );
+        assert_eq!(imageproc::read_image_metadata(&kept_path).unwrap().width, 8);
+    }
+}
+
+#[test]
+fn resize_image_in_other_formats() {
+    let source_path = TEST_IMGS.join("jpg.jpg");
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let config = Config::parse(CONFIG).unwrap();
+    let mut proc = Processor::new(tmpdir.clone(), &config);
+    let resize_op = ResizeOperation::from_args("fit_width", Some(150), None).unwrap();
+
+    let formats = vec!["webp".to_string()];
+    let resp = proc
+        .enqueue_with_formats(
+            resize_op,
+            "jpg.jpg".into(),
+            source_path.clone(),
+            "auto",
+            &formats,
+            None,
+            false,
+        )
+        .unwrap();
+    assert_processed_path_matches(&resp.url, "https://example.com/processed_images/", "jpg");
+    assert_eq!(resp.formats.len(), 1);
+    let webp = &resp.formats[0];
+    assert_eq!((webp.format.as_str(), webp.mime.as_str()), ("webp", "image/webp"));
+    assert_processed_path_matches(&webp.url, "https://example.com/processed_images/", "webp");
+    assert_processed_path_matches(&webp.static_path, PROCESSED_PREFIX.as_str(), "webp");
+
+    proc.do_process().unwrap();
+    for path in [&resp.static_path, &webp.static_path] {
+        let meta = imageproc::read_image_metadata(tmpdir.join(path)).unwrap();
+        assert_eq!((meta.width, meta.height), (150, 190));
+    }
+    let webp_meta = imageproc::read_image_metadata(tmpdir.join(&webp.static_path)).unwrap();
+    assert_eq!(webp_meta.format, Some("webp"));
+
+    // AVIF needs the encoder, which is behind a feature
+    let formats = vec!["avif".to_string()];
+    let res = proc.enqueue_with_formats(
+        resize_op,
+        "jpg.jpg".into(),
+        source_path,
+        "auto",
+        &formats,
+        None,
+        false,
+    );
+    if image::ImageFormat::Avif.writing_enabled() {
+        assert_eq!(res.unwrap().formats[0].mime, "image/avif");
+        proc.do_process().unwrap();
+    } else {
+        assert!(format!("{}", res.unwrap_err()).contains("`avif` feature"));
+    }
+}
+
+#[test]
+fn resized_images_are_linked_from_the_asset_base_url() {
+    let source_path = TEST_IMGS.join("jpg.jpg");
+    let tmpdir = tempfile::tempdir().unwrap().into_path();
+    let mut config = Config::parse(CONFIG).unwrap();
+    config.asset_base_url = Some("https://cdn.example.com".to_string());
+    let mut proc = Processor::new(tmpdir, &config);
+    let resize_op = ResizeOperation::from_args("scale", Some(150), Some(150)).unwrap();
+
+    let resp = proc.enqueue(resize_op, "jpg.jpg".into(), source_path, "auto", None, false).unwrap();
+    assert_processed_path_matches(&resp.url, "https://cdn.example.com/processed_images/", "jpg");
+}
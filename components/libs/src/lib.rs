@@ -8,6 +8,8 @@ pub use ahash;
 pub use ammonia;
 pub use atty;
 pub use base64;
+pub use chrono;
+pub use chrono_tz;
 pub use csv;
 pub use elasticlunr;
 pub use filetime;
@@ -15,8 +17,11 @@ pub use gh_emoji;
 pub use glob;
 pub use globset;
 pub use grass;
+pub use html5ever;
+pub use ignore;
 pub use image;
 pub use lexical_sort;
+pub use libc;
 pub use minify_html;
 pub use nom_bibtex;
 pub use num_format;
@@ -29,6 +34,7 @@ pub use rayon;
 pub use regex;
 pub use relative_path;
 pub use reqwest;
+pub use ring;
 pub use serde_json;
 pub use serde_yaml;
 pub use sha2;
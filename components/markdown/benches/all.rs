@@ -4,7 +4,7 @@ extern crate test;
 use std::collections::HashMap;
 
 use config::Config;
-use libs::tera::Tera;
+use libs::tera::{Tera, Value};
 use markdown::{render_content, RenderContext};
 use utils::types::InsertAnchor;
 
@@ -91,6 +91,7 @@ fn bench_render_content_with_highlighting(b: &mut test::Bencher) {
     let mut context = RenderContext::new(
         &tera,
         &config,
+        &Value::Null,
         &config.default_language,
         current_page_permalink,
         &permalinks_ctx,
@@ -112,6 +113,7 @@ fn bench_render_content_without_highlighting(b: &mut test::Bencher) {
     let mut context = RenderContext::new(
         &tera,
         &config,
+        &Value::Null,
         &config.default_language,
         current_page_permalink,
         &permalinks_ctx,
@@ -133,6 +135,7 @@ fn bench_render_content_no_shortcode(b: &mut test::Bencher) {
     let context = RenderContext::new(
         &tera,
         &config,
+        &Value::Null,
         &config.default_language,
         current_page_permalink,
         &permalinks_ctx,
@@ -154,6 +157,7 @@ fn bench_render_content_with_emoji(b: &mut test::Bencher) {
     let context = RenderContext::new(
         &tera,
         &config,
+        &Value::Null,
         &config.default_language,
         current_page_permalink,
         &permalinks_ctx,
@@ -24,6 +24,10 @@ pub struct FenceSettings<'a> {
     pub line_number_start: usize,
     pub highlight_lines: Vec<RangeInclusive<usize>>,
     pub hide_lines: Vec<RangeInclusive<usize>>,
+    /// The name of the file shown above the block, eg `src/main.rs`
+    pub name: Option<&'a str>,
+    /// Set by `nohighlight` to not highlight that block, even with a language
+    pub no_highlight: bool,
 }
 
 impl<'a> FenceSettings<'a> {
@@ -34,6 +38,8 @@ impl<'a> FenceSettings<'a> {
             line_number_start: 1,
             highlight_lines: Vec::new(),
             hide_lines: Vec::new(),
+            name: None,
+            no_highlight: false,
         };
 
         for token in FenceIter::new(fence_info) {
@@ -43,6 +49,8 @@ impl<'a> FenceSettings<'a> {
                 FenceToken::InitialLineNumber(l) => me.line_number_start = l,
                 FenceToken::HighlightLines(lines) => me.highlight_lines.extend(lines),
                 FenceToken::HideLines(lines) => me.hide_lines.extend(lines),
+                FenceToken::Name(name) => me.name = Some(name),
+                FenceToken::NoHighlight => me.no_highlight = true,
             }
         }
 
@@ -57,6 +65,8 @@ enum FenceToken<'a> {
     InitialLineNumber(usize),
     HighlightLines(Vec<RangeInclusive<usize>>),
     HideLines(Vec<RangeInclusive<usize>>),
+    Name(&'a str),
+    NoHighlight,
 }
 
 struct FenceIter<'a> {
@@ -95,6 +105,7 @@ impl<'a> Iterator for FenceIter<'a> {
                     }
                 }
                 "linenos" => return Some(FenceToken::EnableLineNumbers),
+                "nohighlight" => return Some(FenceToken::NoHighlight),
                 "hl_lines" => {
                     let ranges = Self::parse_ranges(tok_split.next());
                     return Some(FenceToken::HighlightLines(ranges));
@@ -103,6 +114,15 @@ impl<'a> Iterator for FenceIter<'a> {
                     let ranges = Self::parse_ranges(tok_split.next());
                     return Some(FenceToken::HideLines(ranges));
                 }
+                "name" => {
+                    // The name can contain `=` so we take everything after the first one
+                    if let Some((_, name)) = tok.split_once('=') {
+                        let name = name.trim();
+                        if !name.is_empty() {
+                            return Some(FenceToken::Name(name));
+                        }
+                    }
+                }
                 lang => {
                     return Some(FenceToken::Language(lang));
                 }
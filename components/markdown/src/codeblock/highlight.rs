@@ -133,15 +133,11 @@ pub(crate) enum SyntaxHighlighter<'config> {
 }
 
 impl<'config> SyntaxHighlighter<'config> {
-    pub fn new(highlight_code: bool, s: SyntaxAndTheme<'config>) -> Self {
-        if highlight_code {
-            if let Some(theme) = s.theme {
-                SyntaxHighlighter::Inlined(InlineHighlighter::new(s.syntax, s.syntax_set, theme))
-            } else {
-                SyntaxHighlighter::Classed(ClassHighlighter::new(s.syntax, s.syntax_set))
-            }
+    pub fn new(s: SyntaxAndTheme<'config>) -> Self {
+        if let Some(theme) = s.theme {
+            SyntaxHighlighter::Inlined(InlineHighlighter::new(s.syntax, s.syntax_set, theme))
         } else {
-            SyntaxHighlighter::NoHighlight
+            SyntaxHighlighter::Classed(ClassHighlighter::new(s.syntax, s.syntax_set))
         }
     }
 
@@ -254,11 +250,8 @@ mod tests {
 
     #[test]
     fn no_highlight_escapes_html() {
-        let mut config = Config::default();
-        config.markdown.highlight_code = false;
         let code = "<script>alert('hello')</script>";
-        let syntax_and_theme = resolve_syntax_and_theme(Some("py"), &config);
-        let mut highlighter = SyntaxHighlighter::new(false, syntax_and_theme);
+        let mut highlighter = SyntaxHighlighter::NoHighlight;
         let mut out = String::new();
         for line in LinesWithEndings::from(code) {
             out.push_str(&highlighter.highlight_line(line));
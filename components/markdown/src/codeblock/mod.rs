@@ -73,19 +73,27 @@ impl<'config> CodeBlock<'config> {
     pub fn new<'fence_info>(
         fence: FenceSettings<'fence_info>,
         config: &'config Config,
+        // whether the page or section highlights its code, defaulting to the config
+        highlight_code: bool,
         // path to the current file if there is one, to point where the error is
         path: Option<&'config str>,
     ) -> (Self, String) {
-        let syntax_and_theme = resolve_syntax_and_theme(fence.language, config);
-        if syntax_and_theme.source == HighlightSource::NotFound && config.markdown.highlight_code {
-            let lang = fence.language.unwrap();
-            if let Some(p) = path {
-                eprintln!("Warning: Highlight language {} not found in {}", lang, p);
-            } else {
-                eprintln!("Warning: Highlight language {} not found", lang);
+        // Resolving the syntax loads the syntax set, which takes a while, so we only do it when
+        // the block is actually highlighted
+        let highlighter = if highlight_code && !fence.no_highlight {
+            let syntax_and_theme = resolve_syntax_and_theme(fence.language, config);
+            if syntax_and_theme.source == HighlightSource::NotFound {
+                let lang = fence.language.unwrap();
+                if let Some(p) = path {
+                    console::warn(&format!("Highlight language {} not found in {}", lang, p));
+                } else {
+                    console::warn(&format!("Highlight language {} not found", lang));
+                }
             }
-        }
-        let highlighter = SyntaxHighlighter::new(config.markdown.highlight_code, syntax_and_theme);
+            SyntaxHighlighter::new(syntax_and_theme)
+        } else {
+            SyntaxHighlighter::NoHighlight
+        };
 
         let html_start = opening_html(
             fence.language,
@@ -1,8 +1,9 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use config::Config;
-use libs::tera::{Context, Tera};
+use libs::tera::{Context, Tera, Value};
 use utils::templates::ShortcodeDefinition;
 use utils::types::InsertAnchor;
 
@@ -14,8 +15,11 @@ pub struct RenderContext<'a> {
     pub tera_context: Context,
     pub current_page_path: Option<&'a str>,
     pub current_page_permalink: &'a str,
-    pub permalinks: Cow<'a, HashMap<String, String>>,
+    pub permalinks: Cow<'a, HashMap<String, Arc<str>>>,
     pub insert_anchor: InsertAnchor,
+    pub heading_offset: u8,
+    pub highlight_code: bool,
+    pub anchor_aliases: Cow<'a, HashMap<String, String>>,
     pub lang: &'a str,
     pub shortcode_definitions: Cow<'a, HashMap<String, ShortcodeDefinition>>,
 }
@@ -24,14 +28,13 @@ impl<'a> RenderContext<'a> {
     pub fn new(
         tera: &'a Tera,
         config: &'a Config,
+        data: &Value,
         lang: &'a str,
         current_page_permalink: &'a str,
-        permalinks: &'a HashMap<String, String>,
+        permalinks: &'a HashMap<String, Arc<str>>,
         insert_anchor: InsertAnchor,
     ) -> RenderContext<'a> {
-        let mut tera_context = Context::new();
-        tera_context.insert("config", &config.serialize(lang));
-        tera_context.insert("lang", lang);
+        let tera_context = config.template_context(lang, data);
 
         Self {
             tera: Cow::Borrowed(tera),
@@ -40,6 +43,9 @@ impl<'a> RenderContext<'a> {
             current_page_permalink,
             permalinks: Cow::Borrowed(permalinks),
             insert_anchor,
+            heading_offset: config.markdown.heading_offset,
+            highlight_code: config.markdown.highlight_code,
+            anchor_aliases: Cow::Owned(HashMap::new()),
             config,
             lang,
             shortcode_definitions: Cow::Owned(HashMap::new()),
@@ -57,17 +63,40 @@ impl<'a> RenderContext<'a> {
         self.current_page_path = Some(path);
     }
 
+    /// Same as above, for the pages and sections overriding the offset of the config
+    pub fn set_heading_offset(&mut self, heading_offset: u8) {
+        self.heading_offset = heading_offset;
+    }
+
+    /// Same as above, for the pages and sections turning the highlighting of the config on or off
+    pub fn set_highlight_code(&mut self, highlight_code: bool) {
+        self.highlight_code = highlight_code;
+    }
+
+    /// Same as above
+    pub fn set_anchor_aliases(&mut self, aliases: &'a HashMap<String, String>) {
+        self.anchor_aliases = Cow::Borrowed(aliases);
+    }
+
     // In use in the markdown filter
     // NOTE: This RenderContext is not i18n-aware, see MarkdownFilter::filter for details
     // If this function is ever used outside of MarkdownFilter, take this into consideration
-    pub fn from_config(config: &'a Config) -> RenderContext<'a> {
+    pub fn from_config(config: &'a Config, data: &Value) -> RenderContext<'a> {
+        // Same context as `new`, for the shortcodes used in the markdown
+        let mut tera_context = config.template_context(&config.default_language, data);
+        // `lang` is left out so the shortcodes can be given the current language as an argument
+        tera_context.remove("lang");
+
         Self {
             tera: Cow::Owned(Tera::default()),
-            tera_context: Context::new(),
+            tera_context,
             current_page_path: None,
             current_page_permalink: "",
             permalinks: Cow::Owned(HashMap::new()),
             insert_anchor: InsertAnchor::None,
+            heading_offset: config.markdown.heading_offset,
+            highlight_code: config.markdown.highlight_code,
+            anchor_aliases: Cow::Owned(HashMap::new()),
             config,
             lang: &config.default_language,
             shortcode_definitions: Cow::Owned(HashMap::new()),
@@ -0,0 +1,151 @@
+//! The pages written in other markup languages, like AsciiDoc, converted to HTML by the commands
+//! of `[markup.external]`.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use libs::once_cell::sync::Lazy;
+use libs::sha2::{Digest, Sha256};
+
+use errors::{bail, Context, Result};
+
+use crate::markdown::Rendered;
+
+/// What the commands output, by hash of the command and content they were given, so `zola serve`
+/// only runs them again for the pages that changed
+static CONVERTED: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Makes the names of the temporary files unique when several pages have the same body
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// The temporary file given as `{file}` to a converter, removed once it is done
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(content: &str, extension: &str) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "zola-markup-{}-{}.{}",
+            std::process::id(),
+            TEMP_FILES.fetch_add(1, Ordering::Relaxed),
+            extension
+        ));
+        // The temporary directory is shared: never write through a file or symlink that was
+        // put there under that name by someone else
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        let temp_file = TempFile(path);
+        file.write_all(content.as_bytes())
+            .with_context(|| format!("Failed to write {}", temp_file.0.display()))?;
+        Ok(temp_file)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn run_converter(content: &str, command: &str, path: &Path) -> Result<String> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let temp_file =
+        if command.contains("{file}") { Some(TempFile::new(content, extension)?) } else { None };
+    let mut args = command.split_whitespace().map(|arg| match &temp_file {
+        Some(file) => arg.replace("{file}", &file.0.to_string_lossy()),
+        None => arg.to_string(),
+    });
+    let program = args.next().unwrap_or_default();
+
+    let mut child = Command::new(&program)
+        .args(args)
+        .current_dir(path.parent().unwrap_or(Path::new(".")))
+        .stdin(if temp_file.is_some() { Stdio::null() } else { Stdio::piped() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run `{}` to convert {}", program, path.display()))?;
+    // Written from another thread so a converter filling its stdout before reading everything
+    // doesn't block
+    let writer = child.stdin.take().map(|mut stdin| {
+        let content = content.to_string();
+        std::thread::spawn(move || stdin.write_all(content.as_bytes()))
+    });
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to run `{}` to convert {}", program, path.display()))?;
+    if let Some(writer) = writer {
+        // The converter not reading its stdin is only a problem if it also failed
+        let _ = writer.join();
+    }
+
+    if !output.status.success() {
+        bail!(
+            "`{}` failed to convert {} ({}):\n{}",
+            command,
+            path.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Converts the body of the page at that path to HTML with that command.
+/// Shortcodes and internal links are left as they are, and there is no table of contents.
+pub fn render_external(content: &str, command: &str, path: &Path) -> Result<Rendered> {
+    let mut hasher = Sha256::new();
+    hasher.update(command.as_bytes());
+    hasher.update([0]);
+    hasher.update(content.as_bytes());
+    let key = format!("{:x}", hasher.finalize());
+
+    let cached = CONVERTED.lock().unwrap().get(&key).cloned();
+    let body = match cached {
+        Some(body) => body,
+        None => {
+            let body = run_converter(content, command, path)?;
+            CONVERTED.lock().unwrap().insert(key, body.clone());
+            body
+        }
+    };
+
+    Ok(Rendered {
+        body,
+        summary: None,
+        toc: Vec::new(),
+        internal_links: Vec::new(),
+        external_links: Vec::new(),
+        mixed_self_links: Vec::new(),
+        shortcodes: Vec::new(),
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_convert_with_stdin_and_file() {
+        let path = std::env::temp_dir().join("page.txt");
+        let rendered = render_external("Hello\n", "tr a-z A-Z", &path).unwrap();
+        assert_eq!(rendered.body, "HELLO\n");
+        let rendered = render_external("Hello file\n", "cat {file}", &path).unwrap();
+        assert_eq!(rendered.body, "Hello file\n");
+    }
+
+    #[test]
+    fn errors_include_stderr_and_path() {
+        let path = std::env::temp_dir().join("broken.txt");
+        let err = render_external("Hello", "ls /zola-does-not-exist", &path).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("broken.txt"), "{}", message);
+        assert!(message.contains("zola-does-not-exist"), "{}", message);
+    }
+}
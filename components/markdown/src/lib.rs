@@ -1,5 +1,6 @@
 mod codeblock;
 mod context;
+mod external;
 mod markdown;
 mod shortcode;
 
@@ -8,8 +9,9 @@ use shortcode::{extract_shortcodes, insert_md_shortcodes};
 use errors::Result;
 
 use crate::markdown::markdown_to_html;
-pub use crate::markdown::Rendered;
+pub use crate::markdown::{auto_summary, Rendered};
 pub use context::RenderContext;
+pub use external::render_external;
 
 pub fn render_content(content: &str, context: &RenderContext) -> Result<markdown::Rendered> {
     // avoid parsing the content if needed
@@ -20,13 +22,17 @@ pub fn render_content(content: &str, context: &RenderContext) -> Result<markdown
     let definitions = context.shortcode_definitions.as_ref();
     // Extract all the defined shortcodes
     let (content, shortcodes) = extract_shortcodes(content, definitions)?;
+    let mut names: Vec<_> = shortcodes.iter().map(|s| s.name.clone()).collect();
+    names.sort_unstable();
+    names.dedup();
 
     // Step 1: we render the MD shortcodes before rendering the markdown so they can get processed
     let (content, html_shortcodes) =
         insert_md_shortcodes(content, shortcodes, &context.tera_context, &context.tera)?;
 
     // Step 2: we render the markdown and the HTML markdown at the same time
-    let html_context = markdown_to_html(&content, context, html_shortcodes)?;
+    let mut html_context = markdown_to_html(&content, context, html_shortcodes)?;
+    html_context.shortcodes = names;
 
     // TODO: Here issue #1418 could be implemented
     // if do_warn_about_unprocessed_md {
@@ -11,20 +11,24 @@ use libs::tera;
 use utils::net::is_external_link;
 
 use crate::context::RenderContext;
+use config::AnchorStyle;
 use errors::{Context, Error, Result};
 use libs::pulldown_cmark_escape::escape_html;
 use libs::regex::{Regex, RegexBuilder};
-use utils::site::resolve_internal_link;
-use utils::slugs::slugify_anchors;
+use libs::sha2::{Digest, Sha256};
+use utils::site::{find_mixed_self_link, is_internal_link, resolve_internal_link};
+use utils::slugs::{slugify_anchors, SlugifyStrategy};
 use utils::table_of_contents::{make_table_of_contents, Heading};
 use utils::types::InsertAnchor;
 
-use self::cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+use self::cmark::{Event, HeadingLevel, LinkType, Options, Parser, Tag, TagEnd};
 use crate::codeblock::{CodeBlock, FenceSettings};
 use crate::shortcode::{Shortcode, SHORTCODE_PLACEHOLDER};
 
 const CONTINUE_READING: &str = "<span id=\"continue-reading\"></span>";
 const ANCHOR_LINK_TEMPLATE: &str = "anchor-link.html";
+const TOC_TEMPLATE: &str = "toc.html";
+const CODE_BLOCK_TEMPLATE: &str = "code-block.html";
 static EMOJI_REPLACER: Lazy<EmojiReplacer> = Lazy::new(EmojiReplacer::new);
 
 /// Set as a regex to help match some extra cases. This way, spaces and case don't matter.
@@ -49,6 +53,9 @@ static STARTS_WITH_SCHEMA_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[0-9A-Za-
 
 /// Matches a <a>..</a> tag, getting the opening tag in a capture group.
 /// Used only with AnchorInsert::Heading to grab it from the template
+/// Left by the built-in `toc` shortcode, with the depth to render (0 for all of it)
+static TOC_MARKER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<!-- zola-toc:(\d+) -->").unwrap());
+
 static A_HTML_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(<\s*a[^>]*>).*?<\s*/\s*a>").unwrap());
 
 /// Efficiently insert multiple element in their specified index.
@@ -88,6 +95,11 @@ pub struct Rendered {
     pub internal_links: Vec<(String, Option<String>)>,
     /// Outgoing links to external webpages (i.e. HTTP(S) targets).
     pub external_links: Vec<String>,
+    /// Absolute links to the site that don't start with its `base_url` and were not rewritten,
+    /// with what they should be replaced with.
+    pub mixed_self_links: Vec<(String, String)>,
+    /// The names of the shortcodes used in the content, sorted
+    pub shortcodes: Vec<String>,
 }
 
 /// Tracks a heading in a slice of pulldown-cmark events
@@ -156,16 +168,17 @@ fn fix_link(
     context: &RenderContext,
     internal_links: &mut Vec<(String, Option<String>)>,
     external_links: &mut Vec<String>,
+    mixed_self_links: &mut Vec<(String, String)>,
 ) -> Result<String> {
     if link_type == LinkType::Email {
         return Ok(link.to_string());
     }
 
     // A few situations here:
-    // - it could be a relative link (starting with `@/`)
+    // - it could be a relative link (starting with `@/`, or the deprecated `./`)
     // - it could be a link to a co-located asset
     // - it could be a normal link
-    let result = if link.starts_with("@/") {
+    let result = if is_internal_link(link) {
         match resolve_internal_link(link, &context.permalinks) {
             Ok(resolved) => {
                 internal_links.push((resolved.md_path, resolved.anchor));
@@ -189,8 +202,30 @@ fn fix_link(
     } else if is_colocated_asset_link(link) {
         format!("{}{}", context.current_page_permalink, link)
     } else if is_external_link(link) {
-        external_links.push(link.to_owned());
-        link.to_owned()
+        let self_link = find_mixed_self_link(link, &context.config.base_url, &context.permalinks);
+        match self_link {
+            Some(self_link) if context.config.link_checker.rewrite_self_links => {
+                match self_link.internal {
+                    Some(resolved) => {
+                        internal_links.push((resolved.md_path, resolved.anchor));
+                        resolved.permalink
+                    }
+                    None => {
+                        external_links.push(self_link.normalized.clone());
+                        self_link.normalized
+                    }
+                }
+            }
+            Some(self_link) => {
+                mixed_self_links.push((link.to_owned(), self_link.suggestion()));
+                external_links.push(link.to_owned());
+                link.to_owned()
+            }
+            None => {
+                external_links.push(link.to_owned());
+                link.to_owned()
+            }
+        }
     } else if link == "#" {
         link.to_string()
     } else if let Some(stripped_link) = link.strip_prefix('#') {
@@ -222,7 +257,8 @@ fn get_text(parser_slice: &[Event]) -> String {
     title
 }
 
-fn get_heading_refs(events: &[Event]) -> Vec<HeadingRef> {
+/// Finds the headings, with their level shifted down by `offset` and capped at 6
+fn get_heading_refs(events: &[Event], offset: u8) -> Vec<HeadingRef> {
     let mut heading_refs = vec![];
 
     for (i, event) in events.iter().enumerate() {
@@ -230,7 +266,7 @@ fn get_heading_refs(events: &[Event]) -> Vec<HeadingRef> {
             Event::Start(Tag::Heading { level, id, classes, .. }) => {
                 heading_refs.push(HeadingRef::new(
                     i,
-                    *level as u32,
+                    (*level as u32 + offset as u32).min(6),
                     id.clone().map(|a| a.to_string()),
                     &classes.iter().map(|x| x.to_string()).collect::<Vec<_>>(),
                 ));
@@ -414,6 +450,8 @@ pub fn markdown_to_html(
     let mut error = None;
 
     let mut code_block: Option<CodeBlock> = None;
+    // Whether the current code block is inside a `<div class="code-block">` to close
+    let mut code_block_wrapped = false;
     // Indicates whether we're in the middle of parsing a text node which will be placed in an HTML
     // attribute, and which hence has to be escaped using escape_html rather than push_html's
     // default HTML body escaping for text nodes.
@@ -422,6 +460,7 @@ pub fn markdown_to_html(
     let mut headings: Vec<Heading> = vec![];
     let mut internal_links = Vec::new();
     let mut external_links = Vec::new();
+    let mut mixed_self_links = Vec::new();
 
     let mut stop_next_end_p = false;
 
@@ -559,7 +598,17 @@ pub fn markdown_to_html(
                         cmark::CodeBlockKind::Fenced(fence_info) => FenceSettings::new(fence_info),
                         _ => FenceSettings::new(""),
                     };
-                    let (block, begin) = CodeBlock::new(fence, context.config, path);
+                    if let Some(name) = fence.name {
+                        match render_code_block_header(name, fence.language, context) {
+                            Ok(header) => {
+                                events.push(Event::Html(header.into()));
+                                code_block_wrapped = true;
+                            }
+                            Err(e) => error = Some(e),
+                        }
+                    }
+                    let (block, begin) =
+                        CodeBlock::new(fence, context.config, context.highlight_code, path);
                     code_block = Some(block);
                     events.push(Event::Html(begin.into()));
                 }
@@ -573,11 +622,18 @@ pub fn markdown_to_html(
                     // reset highlight and close the code block
                     code_block = None;
                     events.push(Event::Html("</code></pre>\n".into()));
+                    if code_block_wrapped {
+                        events.push(Event::Html("</div>\n".into()));
+                        code_block_wrapped = false;
+                    }
                 }
                 Event::Start(Tag::Image { link_type, dest_url, title, id }) => {
+                    // The images are served from `asset_base_url` when it is set
                     let link = if is_colocated_asset_link(&dest_url) {
                         let link = format!("{}{}", context.current_page_permalink, &*dest_url);
-                        link.into()
+                        context.config.to_asset_permalink(&link).into()
+                    } else if dest_url.starts_with('/') && !dest_url.starts_with("//") {
+                        context.config.make_asset_link(&dest_url).into()
                     } else {
                         dest_url
                     };
@@ -624,6 +680,7 @@ pub fn markdown_to_html(
                         context,
                         &mut internal_links,
                         &mut external_links,
+                        &mut mixed_self_links,
                     ) {
                         Ok(fixed_link) => fixed_link,
                         Err(err) => {
@@ -703,7 +760,7 @@ pub fn markdown_to_html(
             _ => true,
         });
 
-        let heading_refs = get_heading_refs(&events);
+        let heading_refs = get_heading_refs(&events, context.heading_offset);
 
         let mut anchors_to_insert = vec![];
         let mut inserted_anchors = vec![];
@@ -720,11 +777,8 @@ pub fn markdown_to_html(
             let title = get_text(&events[start_idx + 1..end_idx]);
 
             if heading_ref.id.is_none() {
-                heading_ref.id = Some(find_anchor(
-                    &inserted_anchors,
-                    slugify_anchors(&title, context.config.slugify.anchors),
-                    0,
-                ));
+                heading_ref.id =
+                    Some(find_anchor(&inserted_anchors, make_anchor(&title, context), 0));
             }
 
             inserted_anchors.push(heading_ref.id.clone().unwrap());
@@ -732,6 +786,19 @@ pub fn markdown_to_html(
 
             let html = heading_ref.to_html(id);
             events[start_idx] = Event::Html(html.into());
+            let mut aliases: Vec<_> =
+                context.anchor_aliases.iter().filter(|(_, new)| *new == id).collect();
+            aliases.sort();
+            for (old, _) in aliases {
+                let mut alias = String::from("<span id=\"");
+                escape_html(&mut alias, old).unwrap();
+                alias.push_str("\" aria-hidden=\"true\"></span>");
+                anchors_to_insert.push((start_idx, Event::Html(alias.into())));
+            }
+            if context.heading_offset > 0 {
+                let level = HeadingLevel::try_from(heading_ref.level as usize).unwrap();
+                events[end_idx] = Event::End(TagEnd::Heading(level));
+            }
 
             // generate anchors and places to insert them
             if context.insert_anchor != InsertAnchor::None {
@@ -774,7 +841,23 @@ pub fn markdown_to_html(
             headings.push(h);
         }
 
-        if context.insert_anchor != InsertAnchor::None {
+        for (old, new) in context.anchor_aliases.iter() {
+            if !inserted_anchors.contains(new) {
+                bail!(
+                    "`anchor_aliases` points `{}` to `{}` but there is no heading with that id",
+                    old,
+                    new
+                );
+            }
+            if inserted_anchors.contains(old) {
+                bail!(
+                    "`anchor_aliases` has an alias `{}` which is already the id of a heading",
+                    old
+                );
+            }
+        }
+
+        if !anchors_to_insert.is_empty() {
             insert_many(&mut events, anchors_to_insert);
         }
 
@@ -803,16 +886,108 @@ pub fn markdown_to_html(
     }
 
     if let Some(e) = error {
-        Err(e)
-    } else {
-        Ok(Rendered {
-            summary,
-            body: html,
-            toc: make_table_of_contents(headings),
-            internal_links,
-            external_links,
+        return Err(e);
+    }
+
+    let toc = make_table_of_contents(headings);
+    let summary = match summary {
+        Some(s) => Some(insert_toc(s, &toc, context)?),
+        None => None,
+    };
+    let body = insert_toc(html, &toc, context)?;
+    Ok(Rendered {
+        summary,
+        body,
+        toc,
+        internal_links,
+        external_links,
+        mixed_self_links,
+        shortcodes: Vec::new(),
+    })
+}
+
+/// Makes the id of a heading without an explicit one from its text
+fn make_anchor(title: &str, context: &RenderContext) -> String {
+    match context.config.markdown.anchor_style {
+        AnchorStyle::Slug => slugify_anchors(title, context.config.slugify.anchors),
+        AnchorStyle::Safe => slugify_anchors(title, SlugifyStrategy::Safe),
+        AnchorStyle::Hash => {
+            let hash = Sha256::digest(title.as_bytes());
+            let hex: String = hash.iter().take(4).map(|b| format!("{:02x}", b)).collect();
+            format!("h-{}", hex)
+        }
+    }
+}
+
+/// Makes a summary of the first `words` words of some rendered content, for the pages without
+/// a `<!-- more -->`. Returns whether the content was truncated.
+pub fn auto_summary(html: &str, words: usize) -> (String, bool) {
+    utils::html::truncate_html(&FOOTNOTES_RE.replace_all(html, ""), words)
+}
+
+/// Opens the `<div>` wrapping a code block with a `name` annotation and renders the
+/// `code-block.html` template inside it, before the `<pre>`
+fn render_code_block_header(
+    name: &str,
+    lang: Option<&str>,
+    context: &RenderContext,
+) -> Result<String> {
+    let mut html = String::from("<div class=\"code-block\"");
+    if let Some(lang) = lang {
+        html.push_str(" data-lang=\"");
+        escape_html(&mut html, lang).unwrap();
+        html.push('"');
+    }
+    html.push_str(" data-name=\"");
+    escape_html(&mut html, name).unwrap();
+    html.push_str("\">");
+
+    let mut c = tera::Context::new();
+    c.insert("name", name);
+    c.insert("lang", &lang);
+    let header = utils::templates::render_template(CODE_BLOCK_TEMPLATE, &context.tera, c, &None)
+        .context("Failed to render the code block header template")?;
+    html.push_str(&header);
+    Ok(html)
+}
+
+/// Keeps only the first `depth` levels of the table of contents
+fn limit_toc_depth(toc: &[Heading], depth: usize) -> Vec<Heading> {
+    toc.iter()
+        .map(|h| Heading {
+            children: if depth > 1 { limit_toc_depth(&h.children, depth - 1) } else { Vec::new() },
+            ..h.clone()
         })
+        .collect()
+}
+
+/// Replaces the markers left by the `toc` shortcode with the `toc.html` template, which can only
+/// be rendered once all the headings are known
+fn insert_toc(html: String, toc: &[Heading], context: &RenderContext) -> Result<String> {
+    if !TOC_MARKER_RE.is_match(&html) {
+        return Ok(html);
     }
+
+    let mut res = String::with_capacity(html.len());
+    let mut last_end = 0;
+    for caps in TOC_MARKER_RE.captures_iter(&html) {
+        let marker = caps.get(0).unwrap();
+        let depth: usize = caps[1].parse().unwrap_or(0);
+        let mut c = context.tera_context.clone();
+        if depth == 0 {
+            c.insert("toc", toc);
+        } else {
+            c.insert("toc", &limit_toc_depth(toc, depth));
+        }
+        let rendered = utils::templates::render_template(TOC_TEMPLATE, &context.tera, c, &None)
+            .context("Failed to render the table of contents template")?;
+        res.push_str(&html[last_end..marker.start()]);
+        res.push_str(&rendered);
+        last_end = marker.end();
+    }
+    res.push_str(&html[last_end..]);
+
+    Ok(res)
 }
 
 #[cfg(test)]
@@ -875,7 +1050,7 @@ mod tests {
         let mores =
             ["<!-- more -->", "<!--more-->", "<!-- MORE -->", "<!--MORE-->", "<!--\t MoRe \t-->"];
         let config = Config::default();
-        let context = RenderContext::from_config(&config);
+        let context = RenderContext::from_config(&config, &tera::Value::Null);
         for more in mores {
             let content = format!("{top}\n\n{more}\n\n{bottom}");
             let rendered = markdown_to_html(&content, &context, vec![]).unwrap();
@@ -1,4 +1,10 @@
-use config::Config;
+use std::collections::HashMap;
+
+use config::{Config, HighlightingMode};
+use libs::tera::{Tera, Value};
+use markdown::{render_content, RenderContext};
+use templates::ZOLA_TERA;
+use utils::types::InsertAnchor;
 
 mod common;
 
@@ -6,6 +12,8 @@ enum HighlightMode {
     None,
     Inlined,
     Classed,
+    /// Classed through the `css` highlighting mode, keeping the theme for the stylesheet
+    CssMode,
 }
 
 fn render_codeblock(content: &str, highlight_mode: HighlightMode) -> String {
@@ -19,6 +27,10 @@ fn render_codeblock(content: &str, highlight_mode: HighlightMode) -> String {
             config.markdown.highlight_code = true;
             config.markdown.highlight_theme = "css".to_owned();
         }
+        HighlightMode::CssMode => {
+            config.markdown.highlight_code = true;
+            config.markdown.highlighting_mode = HighlightingMode::Css;
+        }
     }
     common::render_with_config(content, config).unwrap().body
 }
@@ -257,6 +269,19 @@ fn can_highlight_with_classes() {
     insta::assert_snapshot!(body);
 }
 
+#[test]
+fn can_highlight_with_css_highlighting_mode() {
+    let body = render_codeblock(
+        r#"
+```rust
+let x = 1;
+```
+    "#,
+        HighlightMode::CssMode,
+    );
+    insta::assert_snapshot!(body);
+}
+
 #[test]
 fn can_add_line_numbers() {
     let body = render_codeblock(
@@ -390,3 +415,96 @@ bar
     );
     insta::assert_snapshot!(body);
 }
+
+#[test]
+fn can_add_name_header() {
+    let body = render_codeblock(
+        r#"
+```rust,linenos,name=src/main.rs,hl_lines=2
+fn main() {
+    println!("Hello");
+}
+```
+    "#,
+        HighlightMode::Classed,
+    );
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn can_add_name_header_without_language() {
+    let body = render_codeblock(
+        r#"
+```name=notes.txt
+foo
+```
+    "#,
+        HighlightMode::None,
+    );
+    assert_eq!(
+        body,
+        "<div class=\"code-block\" data-name=\"notes.txt\"><div class=\"code-block-header\"><span class=\"code-block-name\">notes.txt</span></div>\n<pre><code>foo\n</code></pre>\n</div>\n"
+    );
+}
+
+#[test]
+fn can_customise_code_block_header() {
+    let mut tera = Tera::default();
+    tera.extend(&ZOLA_TERA).unwrap();
+    tera.add_raw_template("code-block.html", "<p>{{ name }} ({{ lang }})</p>").unwrap();
+    let permalinks_ctx = HashMap::new();
+    let config = Config::default_for_test();
+    let context = RenderContext::new(
+        &tera,
+        &config,
+        &Value::Null,
+        &config.default_language,
+        "",
+        &permalinks_ctx,
+        InsertAnchor::None,
+    );
+    let body = render_content("```py,name=a.py\nx\n```", &context).unwrap().body;
+    assert_eq!(
+        body,
+        "<div class=\"code-block\" data-lang=\"py\" data-name=\"a.py\"><p>a.py (py)</p><pre data-lang=\"py\" class=\"language-py \"><code class=\"language-py\" data-lang=\"py\">x\n</code></pre>\n</div>\n"
+    );
+}
+
+#[test]
+fn can_disable_highlighting_of_a_block() {
+    let body = render_codeblock(
+        r#"
+```rust,nohighlight,linenos
+let a = "<b>";
+```
+    "#,
+        HighlightMode::Inlined,
+    );
+    assert_eq!(
+        body,
+        "<pre data-linenos data-lang=\"rust\" class=\"language-rust \"><code class=\"language-rust\" data-lang=\"rust\"><table><tbody><tr><td>1</td><td>let a = &quot;&lt;b&gt;&quot;;\n</td></tr></tbody></table></code></pre>\n"
+    );
+}
+
+#[test]
+fn can_override_highlighting_in_render_context() {
+    let tera = Tera::default();
+    let permalinks_ctx = HashMap::new();
+    let mut config = Config::default_for_test();
+    config.markdown.highlight_code = true;
+    let mut context = RenderContext::new(
+        &tera,
+        &config,
+        &Value::Null,
+        &config.default_language,
+        "",
+        &permalinks_ctx,
+        InsertAnchor::None,
+    );
+    context.set_highlight_code(false);
+    let body = render_content("```py\nx = 1\n```", &context).unwrap().body;
+    assert_eq!(
+        body,
+        "<pre data-lang=\"py\" class=\"language-py \"><code class=\"language-py\" data-lang=\"py\">x = 1\n</code></pre>\n"
+    );
+}
@@ -1,8 +1,9 @@
 #![allow(dead_code)]
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use libs::tera::Tera;
+use libs::tera::{Tera, Value};
 
 use config::Config;
 use errors::Result;
@@ -51,17 +52,29 @@ fn configurable_render(
     )
     .unwrap();
     tera.add_raw_template("shortcodes/md_passthrough.md", "{{body}}").unwrap();
+    tera.add_raw_template(
+        "shortcodes/analytics.html",
+        "{{ config.title }} on {{ config.extra.plausible_domain }} since {{ config.extra.launched }}",
+    )
+    .unwrap();
 
     let mut permalinks = HashMap::new();
-    permalinks.insert("pages/about.md".to_owned(), "https://getzola.org/about/".to_owned());
+    permalinks.insert("pages/about.md".to_owned(), "https://getzola.org/about/".into());
+    let permalinks = Arc::new(permalinks);
 
     tera.register_filter(
         "markdown",
-        templates::filters::MarkdownFilter::new(config.clone(), permalinks.clone(), tera.clone()),
+        templates::filters::MarkdownFilter::new(
+            config.clone(),
+            Arc::default(),
+            permalinks.clone(),
+            tera.clone(),
+        ),
     );
     let mut context = RenderContext::new(
         &tera,
         &config,
+        &Value::Null,
         &config.default_language,
         "https://www.getzola.org/test/",
         &permalinks,
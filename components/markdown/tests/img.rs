@@ -31,3 +31,23 @@ fn can_add_lazy_loading_and_async_decoding() {
     let body = common::render_with_config(&cases.join("\n"), config).unwrap().body;
     insta::assert_snapshot!(body);
 }
+
+#[test]
+fn can_link_images_from_the_asset_base_url() {
+    let cases = vec![
+        "![colocated](graph.png)",
+        "![static](/images/logo.png)",
+        "![external](https://example.com/abc.jpg)",
+        "![protocol relative](//example.com/abc.jpg)",
+    ];
+
+    let mut config = Config::default_for_test();
+    config.base_url = "https://www.getzola.org".to_string();
+    config.asset_base_url = Some("https://cdn.getzola.org".to_string());
+
+    let body = common::render_with_config(&cases.join("\n"), config).unwrap().body;
+    assert!(body.contains(r#"<img src="https://cdn.getzola.org/test/graph.png""#), "{}", body);
+    assert!(body.contains(r#"<img src="https://cdn.getzola.org/images/logo.png""#), "{}", body);
+    assert!(body.contains(r#"<img src="https://example.com/abc.jpg""#), "{}", body);
+    assert!(body.contains(r#"<img src="//example.com/abc.jpg""#), "{}", body);
+}
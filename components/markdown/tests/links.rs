@@ -1,5 +1,7 @@
 mod common;
 
+use config::Config;
+
 #[test]
 fn can_detect_links() {
     // no links
@@ -44,6 +46,12 @@ Email: <foo@bar.baz>",
         &["http://google.com".to_owned(), "http://google.fr".to_owned()]
     );
 
+    // internal without extension, or with the deprecated `./` prefix
+    let rendered = common::render("[abc](@/pages/about#hello)").unwrap();
+    assert_eq!(rendered.internal_links[0], ("pages/about.md".to_owned(), Some("hello".to_owned())));
+    let rendered = common::render("[abc](./pages/about.md)").unwrap();
+    assert_eq!(rendered.internal_links, vec![("pages/about.md".to_owned(), None)]);
+
     // Not pointing to anything known so that's an error
     let res = common::render("[abc](@/def/123.md)");
     assert!(res.is_err());
@@ -53,3 +61,31 @@ Email: <foo@bar.baz>",
     assert!(res.is_err());
     assert_eq!(res.unwrap_err().to_string(), "There is a link that is missing a URL");
 }
+
+#[test]
+fn can_detect_and_rewrite_mixed_self_links() {
+    let mut config = Config::default_for_test();
+    config.base_url = "https://getzola.org".to_string();
+    let content = "[about](http://getzola.org/about/#team) [logo](http://getzola.org/logo.png) \
+                   [ok](https://getzola.org/about/)";
+
+    let rendered = common::render_with_config(content, config.clone()).unwrap();
+    assert_eq!(
+        rendered.mixed_self_links,
+        vec![
+            ("http://getzola.org/about/#team".to_owned(), "@/pages/about.md#team".to_owned()),
+            ("http://getzola.org/logo.png".to_owned(), "https://getzola.org/logo.png".to_owned()),
+        ]
+    );
+    assert!(rendered.body.contains("href=\"http://getzola.org/about/#team\""));
+
+    config.link_checker.rewrite_self_links = true;
+    let rendered = common::render_with_config(content, config).unwrap();
+    assert!(rendered.mixed_self_links.is_empty());
+    assert_eq!(
+        rendered.internal_links,
+        vec![("pages/about.md".to_owned(), Some("team".to_owned()))]
+    );
+    assert!(rendered.body.contains("href=\"https://getzola.org/about/#team\""));
+    assert!(rendered.body.contains("href=\"https://getzola.org/logo.png\""));
+}
@@ -1,8 +1,8 @@
 use std::collections::HashMap;
 
-use libs::tera::Tera;
+use libs::tera::{Tera, Value};
 
-use config::Config;
+use config::{AnchorStyle, Config};
 use markdown::{render_content, RenderContext};
 use templates::ZOLA_TERA;
 use utils::slugs::SlugifyStrategy;
@@ -84,6 +84,53 @@ fn can_handle_heading_ids() {
     insta::assert_snapshot!(body);
 }
 
+#[test]
+fn can_choose_anchor_style() {
+    let mut config = Config::default_for_test();
+    let content = "# L'écologie et vous\n# L'écologie et vous\n# Pinned {#pinned}";
+
+    config.markdown.anchor_style = AnchorStyle::Safe;
+    let res = common::render_with_config(content, config.clone()).unwrap();
+    assert_eq!(res.toc[0].id, "L'écologie_et_vous");
+
+    config.markdown.anchor_style = AnchorStyle::Hash;
+    let res = common::render_with_config(content, config).unwrap();
+    let ids: Vec<_> = res.toc.iter().map(|h| h.id.as_str()).collect();
+    assert_eq!(ids, ["h-eed49bab", "h-eed49bab-1", "pinned"]);
+    assert!(res.body.starts_with("<h1 id=\"h-eed49bab\">"));
+}
+
+#[test]
+fn can_add_anchor_aliases() {
+    let mut tera = Tera::default();
+    tera.extend(&ZOLA_TERA).unwrap();
+    let permalinks_ctx = HashMap::new();
+    let config = Config::default_for_test();
+    let mut context = RenderContext::new(
+        &tera,
+        &config,
+        &Value::Null,
+        &config.default_language,
+        "",
+        &permalinks_ctx,
+        InsertAnchor::Left,
+    );
+    let mut aliases = HashMap::new();
+    aliases.insert("old-setup".to_string(), "setup".to_string());
+    aliases.insert("older-setup".to_string(), "setup".to_string());
+    context.set_anchor_aliases(&aliases);
+    let body = render_content("# Intro\n## Setup", &context).unwrap().body;
+    assert!(body.contains(
+        "<span id=\"old-setup\" aria-hidden=\"true\"></span>\
+         <span id=\"older-setup\" aria-hidden=\"true\"></span><h2 id=\"setup\"><a class=\"zola-anchor\""
+    ));
+
+    let mut aliases = HashMap::new();
+    aliases.insert("old-setup".to_string(), "typo".to_string());
+    context.set_anchor_aliases(&aliases);
+    assert!(render_content("## Setup", &context).is_err());
+}
+
 #[test]
 fn can_insert_anchors() {
     let cases = vec![
@@ -116,6 +163,7 @@ fn can_customise_anchor_template() {
     let context = RenderContext::new(
         &tera,
         &config,
+        &Value::Null,
         &config.default_language,
         "",
         &permalinks_ctx,
@@ -125,6 +173,43 @@ fn can_customise_anchor_template() {
     insta::assert_snapshot!(body);
 }
 
+#[test]
+fn can_shift_heading_levels() {
+    let mut tera = Tera::default();
+    tera.extend(&ZOLA_TERA).unwrap();
+    tera.add_raw_template("anchor-link.html", " (h{{ level }})").unwrap();
+    let permalinks_ctx = HashMap::new();
+    let mut config = Config::default_for_test();
+    config.markdown.heading_offset = 1;
+    let mut context = RenderContext::new(
+        &tera,
+        &config,
+        &Value::Null,
+        &config.default_language,
+        "",
+        &permalinks_ctx,
+        InsertAnchor::Right,
+    );
+    let content = "# Intro\n## Details\n##### Deep\n###### Deepest";
+    let res = render_content(content, &context).unwrap();
+    assert_eq!(
+        res.body,
+        "<h2 id=\"intro\">Intro (h2)</h2>\n<h3 id=\"details\">Details (h3)</h3>\n\
+         <h6 id=\"deep\">Deep (h6)</h6>\n<h6 id=\"deepest\">Deepest (h6)</h6>\n"
+    );
+    assert_eq!(res.toc.len(), 1);
+    assert_eq!(res.toc[0].level, 2);
+    assert_eq!(res.toc[0].children[0].level, 3);
+    assert_eq!(res.toc[0].children[0].children.len(), 2);
+    assert!(res.toc[0].children[0].children.iter().all(|h| h.level == 6));
+
+    // The pages and sections can override the offset of the config
+    context.set_heading_offset(0);
+    let body = render_content(content, &context).unwrap().body;
+    assert!(body.starts_with("<h1 id=\"intro\">Intro (h1)</h1>"));
+    assert!(body.contains("<h5 id=\"deep\">Deep (h5)</h5>"));
+}
+
 #[test]
 fn can_use_smart_punctuation() {
     let mut config = Config::default_for_test();
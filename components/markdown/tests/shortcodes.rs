@@ -46,6 +46,23 @@ fn can_grab_lang_in_md_shortcodes() {
     insta::assert_snapshot!(body);
 }
 
+#[test]
+fn can_read_config_in_shortcodes() {
+    let config = Config::parse(
+        r#"
+base_url = "https://example.com"
+title = "My site"
+
+[extra]
+plausible_domain = "example.com"
+launched = 2020-01-01
+    "#,
+    )
+    .unwrap();
+    let body = common::render_with_config("{{ analytics() }}", config).unwrap().body;
+    assert_eq!(body, "My site on example.com since 2020-01-01");
+}
+
 #[test]
 fn can_render_body_shortcode_and_paragraph_after() {
     let body = common::render(
@@ -1,5 +1,13 @@
 mod common;
 
+use std::collections::HashMap;
+
+use config::Config;
+use libs::tera::{Tera, Value};
+use markdown::{render_content, RenderContext};
+use templates::ZOLA_TERA;
+use utils::types::InsertAnchor;
+
 #[test]
 fn can_make_simple_toc() {
     let res = common::render(
@@ -75,3 +83,52 @@ fn can_make_toc_all_levels() {
     assert_eq!(toc[0].children[1].children[0].children.len(), 1);
     assert_eq!(toc[0].children[1].children[0].children[0].children.len(), 1);
 }
+
+#[test]
+fn can_insert_toc_in_content() {
+    let res = common::render(
+        r#"
+Intro
+
+{{ toc() }}
+
+# A
+
+## B <em>bold</em>
+
+### C
+"#,
+    )
+    .unwrap();
+    assert!(res.body.starts_with("<p>Intro</p>\n<nav class=\"toc\">"));
+    assert!(res.body.contains(r#"<a href="https://www.getzola.org/test/#c">C</a>"#));
+    assert!(!res.body.contains("zola-toc"));
+
+    let res = common::render("{{ toc(depth=2) }}\n# A\n## B\n### C").unwrap();
+    assert!(res.body.contains(r#"<a href="https://www.getzola.org/test/#b">B</a>"#));
+    assert!(!res.body.contains(r##"#c""##));
+    // The depth only applies to the rendered shortcode
+    assert_eq!(res.toc[0].children[0].children.len(), 1);
+}
+
+#[test]
+fn can_customise_toc_template() {
+    let mut tera = Tera::default();
+    tera.extend(&ZOLA_TERA).unwrap();
+    tera.add_raw_template("toc.html", "{% for h in toc %}[{{ h.title }}]{% endfor %}").unwrap();
+    let permalinks_ctx = HashMap::new();
+    let config = Config::default_for_test();
+    let mut context = RenderContext::new(
+        &tera,
+        &config,
+        &Value::Null,
+        &config.default_language,
+        "",
+        &permalinks_ctx,
+        InsertAnchor::None,
+    );
+    let shortcode_def = utils::templates::get_shortcodes(&tera);
+    context.set_shortcode_definitions(&shortcode_def);
+    let body = render_content("# A\n\n{{ toc() }}\n\n# B", &context).unwrap().body;
+    assert_eq!(body, "<h1 id=\"a\">A</h1>\n[A][B]<h1 id=\"b\">B</h1>\n");
+}
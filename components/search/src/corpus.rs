@@ -0,0 +1,74 @@
+use config::Search;
+use content::Library;
+use errors::Result;
+use utils::html::strip_html;
+
+/// Builds the plain text corpus of the content in that language, for tools that can't read the
+/// search index. Each page and section included in the search index is a line with its
+/// permalink, title and content as plain text separated by tabs, sorted by permalink.
+pub fn build_corpus(lang: &str, library: &Library, config: &Search) -> Result<String> {
+    let mut lines = Vec::new();
+    let mut add_line = |permalink: &str, title: &Option<String>, content: &str| {
+        let title = strip_html(title.as_deref().unwrap_or_default());
+        let mut body = strip_html(content);
+        if let Some(new_len) = config.truncate_content_length {
+            body.truncate(body.char_indices().nth(new_len).map(|(i, _)| i).unwrap_or(body.len()));
+        }
+        lines.push(format!("{}\t{}\t{}\n", permalink, title, body));
+    };
+
+    for section in library.sections.values() {
+        if section.lang != lang || !section.meta.in_search_index {
+            continue;
+        }
+        if section.meta.redirect_to.is_none() {
+            add_line(&section.permalink, &section.meta.title, &section.content);
+        }
+        for key in &section.pages {
+            let page = &library.pages[key];
+            if page.meta.in_search_index {
+                add_line(&page.permalink, &page.meta.title, &page.content()?);
+            }
+        }
+    }
+
+    lines.sort_unstable();
+    Ok(lines.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::Config;
+    use content::{Page, Section};
+
+    #[test]
+    fn can_build_corpus() {
+        let config = Config::default();
+        let mut library = Library::new(&config);
+        let mut section = Section { lang: "en".to_string(), ..Section::default() };
+        section.permalink = "https://example.com/".to_string().into();
+        section.meta.title = Some("Home".to_string());
+        section.content = "<p>Welcome\n<em>home</em></p>".to_string();
+
+        let mut page = Page { lang: "en".to_string(), ..Page::default() };
+        page.file.path = "content/hello.md".into();
+        page.permalink = "https://example.com/hello/".to_string().into();
+        page.meta.title = Some("Hello\tworld".to_string());
+        page.content = "<p>Fish &amp; chips</p><script>alert(1)</script>\n<p>Done</p>".to_string();
+        section.pages.push(page.file.path.clone());
+        library.pages.insert(page.file.path.clone(), page);
+
+        let mut hidden = Section { lang: "en".to_string(), ..Section::default() };
+        hidden.permalink = "https://example.com/hidden/".to_string().into();
+        hidden.meta.in_search_index = false;
+        library.sections.insert("content/_index.md".into(), section);
+        library.sections.insert("content/hidden/_index.md".into(), hidden);
+
+        assert_eq!(
+            build_corpus("en", &library, &config.search).unwrap(),
+            "https://example.com/\tHome\tWelcome home\nhttps://example.com/hello/\tHello world\tFish & chips Done\n"
+        );
+        assert_eq!(build_corpus("fr", &library, &config.search).unwrap(), "");
+    }
+}
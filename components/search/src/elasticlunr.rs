@@ -1,14 +1,87 @@
 use config::{Config, Search};
 use content::{Library, Section};
 use errors::{bail, Result};
-use libs::elasticlunr::{lang, Index, IndexBuilder};
+use libs::elasticlunr::lang::{self, common::StopWordFilter, Language};
+use libs::elasticlunr::{Index, IndexBuilder, Pipeline};
+use libs::serde_json::{self, json, Map, Value};
 use libs::time::format_description::well_known::Rfc3339;
 use libs::time::OffsetDateTime;
 
-use crate::clean_and_truncate_body;
+use crate::{clean_and_truncate_body, section_boost};
 
 pub const ELASTICLUNR_JS: &str = include_str!("elasticlunr.min.js");
 
+/// An elasticlunr language with the stop words of the config instead of its own
+struct WithStopWords {
+    language: Box<dyn Language>,
+    stop_words: Vec<String>,
+}
+
+impl Language for WithStopWords {
+    fn name(&self) -> String {
+        self.language.name()
+    }
+
+    fn code(&self) -> String {
+        self.language.code()
+    }
+
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        self.language.tokenize(text)
+    }
+
+    fn make_pipeline(&self) -> Pipeline {
+        let stop_words: Vec<&str> = self.stop_words.iter().map(String::as_str).collect();
+        let mut pipeline = self.language.make_pipeline();
+        for function in pipeline.queue.iter_mut() {
+            // Keeping the name so the pipeline is still loaded with the right function in JS
+            let name = function.name();
+            if name.starts_with("stopWordFilter") {
+                *function = Box::new(StopWordFilter::new(&name, &stop_words));
+            }
+        }
+        pipeline
+    }
+}
+
+/// The elasticlunr language of the index, with the stop words of the config if there are some.
+/// Errors if the language is not available in Elasticlunr
+fn get_language(lang: &str, search_config: &Search) -> Result<Box<dyn Language>> {
+    let code = search_config.language.as_deref().unwrap_or(lang);
+    let language = match lang::from_code(code) {
+        Some(l) => l,
+        None => {
+            let supported: Vec<_> = lang::languages().iter().map(|l| l.code()).collect();
+            bail!(
+                "Tried to build search index for language {} which is not supported, the supported languages are: {}",
+                code,
+                supported.join(", ")
+            );
+        }
+    };
+
+    Ok(match &search_config.stop_words {
+        Some(stop_words) => Box::new(WithStopWords { language, stop_words: stop_words.clone() }),
+        None => language,
+    })
+}
+
+/// The boost of each field of the index, in the format of the `fields` option of elasticlunr's
+/// `search`
+fn field_boosts(search_config: &Search) -> Map<String, Value> {
+    let mut boosts = Map::new();
+    let mut add = |field: &str, included: bool, boost: f64| {
+        if included {
+            boosts.insert(field.to_string(), json!({ "boost": boost }));
+        }
+    };
+    add("title", search_config.include_title, search_config.title_boost);
+    add("description", search_config.include_description, search_config.description_boost);
+    add("path", search_config.include_path, search_config.path_boost);
+    add("body", search_config.include_content, search_config.body_boost);
+    boosts
+}
+
 fn build_fields(search_config: &Search, mut index: IndexBuilder) -> IndexBuilder {
     if search_config.include_title {
         index = index.add_field("title");
@@ -77,42 +150,61 @@ fn fill_index(
 }
 
 /// Returns the generated JSON index with all the documents of the site added using
-/// the language given
+/// the language given, or the one of the search config.
+/// The boosts of the fields are added as `fieldBoosts` and the documents with a `search_boost`
+/// get a `boost` in the document store.
 /// Errors if the language given is not available in Elasticlunr
 /// TODO: is making `in_search_index` apply to subsections of a `false` section useful?
 pub fn build_index(lang: &str, library: &Library, config: &Config) -> Result<String> {
-    let language = match lang::from_code(lang) {
-        Some(l) => l,
-        None => {
-            bail!("Tried to build search index for language {} which is not supported", lang);
-        }
-    };
     let language_options = &config.languages[lang];
+    let language = get_language(lang, &language_options.search)?;
     let mut index = IndexBuilder::with_language(language);
     index = build_fields(&language_options.search, index);
     let mut index = index.build();
 
-    for (_, section) in &library.sections {
+    let mut boosts = Vec::new();
+    let mut sections: Vec<_> = library.sections.values().collect();
+    // The sections are in a hashmap: sort them so the index is the same on every build
+    sections.sort_by(|a, b| a.file.path.cmp(&b.file.path));
+    for section in sections {
         if section.lang == lang {
-            add_section_to_index(&mut index, section, library, &language_options.search);
+            add_section_to_index(
+                &mut index,
+                &mut boosts,
+                section,
+                library,
+                &language_options.search,
+            )?;
         }
     }
 
-    Ok(index.to_json())
+    let mut json = serde_json::to_value(&index)?;
+    json["fieldBoosts"] = Value::Object(field_boosts(&language_options.search));
+    for (permalink, boost) in boosts {
+        if let Some(doc) = json["documentStore"]["docs"].get_mut(permalink) {
+            doc["boost"] = json!(boost);
+        }
+    }
+    Ok(serde_json::to_string(&json)?)
 }
 
-fn add_section_to_index(
+fn add_section_to_index<'a>(
     index: &mut Index,
-    section: &Section,
-    library: &Library,
+    boosts: &mut Vec<(&'a str, f64)>,
+    section: &'a Section,
+    library: &'a Library,
     search_config: &Search,
-) {
+) -> Result<()> {
     if !section.meta.in_search_index {
-        return;
+        return Ok(());
     }
 
+    let boost = section_boost(section, library);
     // Don't index redirecting sections
     if section.meta.redirect_to.is_none() {
+        if boost != 1.0 {
+            boosts.push((&section.permalink, boost));
+        }
         index.add_doc(
             &section.permalink,
             &fill_index(
@@ -131,6 +223,9 @@ fn add_section_to_index(
         if !page.meta.in_search_index {
             continue;
         }
+        if boost != 1.0 {
+            boosts.push((&page.permalink, boost));
+        }
 
         index.add_doc(
             &page.permalink,
@@ -140,10 +235,11 @@ fn add_section_to_index(
                 &page.meta.description,
                 &page.meta.datetime,
                 &page.path,
-                &page.content,
+                &page.content()?,
             ),
         );
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -233,4 +329,45 @@ mod tests {
         assert_eq!(res[1], "2023-01-31T00:00:00Z");
         assert_eq!(res[2], content);
     }
+
+    #[test]
+    fn can_get_language() {
+        let mut config = Config::default();
+        assert_eq!(get_language("fr", &config.search).unwrap().code(), "fr");
+        config.search.language = Some("de".to_string());
+        assert_eq!(get_language("fr", &config.search).unwrap().code(), "de");
+
+        config.search.language = Some("xx".to_string());
+        let err = get_language("en", &config.search).err().unwrap().to_string();
+        assert!(err.contains("language xx which is not supported"));
+        assert!(err.contains("the supported languages are: en, "));
+    }
+
+    #[test]
+    fn can_replace_stop_words() {
+        let mut config = Config::default();
+        let tokens = || vec!["the".to_string(), "zola".to_string(), "site".to_string()];
+        let pipeline = get_language("en", &config.search).unwrap().make_pipeline();
+        assert_eq!(pipeline.run(tokens()), vec!["zola", "site"]);
+
+        config.search.stop_words = Some(vec!["zola".to_string()]);
+        let pipeline = get_language("en", &config.search).unwrap().make_pipeline();
+        assert_eq!(pipeline.run(tokens()), vec!["the", "site"]);
+        // The JS side needs the same function names
+        assert_eq!(
+            serde_json::to_value(&pipeline).unwrap(),
+            json!(["trimmer", "stopWordFilter", "stemmer"])
+        );
+    }
+
+    #[test]
+    fn can_get_field_boosts() {
+        let mut config = Config::default();
+        config.search.title_boost = 3.0;
+        config.search.path_boost = 2.0;
+        assert_eq!(
+            Value::Object(field_boosts(&config.search)),
+            json!({"title": {"boost": 3.0}, "body": {"boost": 1.0}})
+        );
+    }
 }
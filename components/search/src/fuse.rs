@@ -3,7 +3,7 @@ use content::Library;
 use errors::Result;
 use libs::serde_json;
 
-use crate::clean_and_truncate_body;
+use crate::{clean_and_truncate_body, section_boost};
 
 /// build index in Fuse.js format.
 pub fn build_index(lang: &str, library: &Library, config: &Search) -> Result<String> {
@@ -14,13 +14,20 @@ pub fn build_index(lang: &str, library: &Library, config: &Search) -> Result<Str
         description: Option<&'a str>,
         body: Option<String>, // AMMONIA.clean has to allocate anyway
         path: Option<&'a str>,
+        /// The `search_boost` of the section, only set if there is one
+        #[serde(skip_serializing_if = "Option::is_none")]
+        boost: Option<f64>,
     }
     let mut items: Vec<Item> = Vec::new();
-    for (_, section) in &library.sections {
+    let mut sections: Vec<_> = library.sections.values().collect();
+    // The sections are in a hashmap: sort them so the index is the same on every build
+    sections.sort_by(|a, b| a.file.path.cmp(&b.file.path));
+    for section in sections {
         if section.lang == lang
             && section.meta.redirect_to.is_none()
             && section.meta.in_search_index
         {
+            let boost = Some(section_boost(section, library)).filter(|b| *b != 1.0);
             items.push(Item {
                 url: &section.permalink,
                 title: match config.include_title {
@@ -42,6 +49,7 @@ pub fn build_index(lang: &str, library: &Library, config: &Search) -> Result<Str
                     true => Some(&section.path),
                     false => None,
                 },
+                boost,
             });
             for page in &section.pages {
                 let page = &library.pages[page];
@@ -59,7 +67,7 @@ pub fn build_index(lang: &str, library: &Library, config: &Search) -> Result<Str
                         body: match config.include_content {
                             true => Some(super::clean_and_truncate_body(
                                 config.truncate_content_length,
-                                &page.content,
+                                &page.content()?,
                             )),
                             false => None,
                         },
@@ -67,6 +75,7 @@ pub fn build_index(lang: &str, library: &Library, config: &Search) -> Result<Str
                             true => Some(&page.path),
                             false => None,
                         },
+                        boost,
                     })
                 }
             }
@@ -1,10 +1,13 @@
+mod corpus;
 mod elasticlunr;
 mod fuse;
 
+use content::{Library, Section};
 use libs::ammonia;
 use libs::once_cell::sync::Lazy;
 use std::collections::{HashMap, HashSet};
 
+pub use corpus::build_corpus;
 pub use elasticlunr::{build_index as build_elasticlunr, ELASTICLUNR_JS};
 pub use fuse::build_index as build_fuse;
 
@@ -33,6 +36,16 @@ pub fn clean_and_truncate_body(truncate_content_length: Option<usize>, body: &st
     clean
 }
 
+/// The `search_boost` of the section multiplied by the ones of its ancestors, which applies
+/// to the section and its pages
+fn section_boost(section: &Section, library: &Library) -> f64 {
+    library
+        .sections
+        .values()
+        .filter(|s| section.ancestors.contains(&s.file.relative))
+        .fold(section.meta.search_boost, |boost, s| boost * s.meta.search_boost)
+}
+
 #[cfg(test)]
 #[test]
 fn clean_and_truncate_body_test() {
@@ -120,6 +120,11 @@ title = "My site"
 base_url = "https://replace-this-with-your-url.com"
 theme = "sample"
 
+taxonomies = [
+ {name = "tags"},
+ {name = "categories"}
+]
+
 [extra.author]
 name = "Vincent Prouillet"
 """)
@@ -128,6 +133,8 @@ name = "Vincent Prouillet"
     shutil.copytree("../../../test_site/templates", os.path.join(name, "templates"))
     shutil.copytree("../../../test_site/themes", os.path.join(name, "themes"))
     shutil.copytree("../../../test_site/static", os.path.join(name, "static"))
+    # It lists the posts of test_site, which the generated sites don't have
+    os.remove(os.path.join(name, "themes", "sample", "templates", "50x.html"))
 
 
 def gen_section(path, num_pages, is_blog):
@@ -148,7 +155,7 @@ template = "section_paginated.html"
         with open(os.path.join(path, "page-{}.md".format(i)), "w") as f:
             f.write(
                 PAGE
-                .replace("REPLACE_DATE", str(day + datetime.timedelta(days=1)))
+                .replace("REPLACE_DATE", str(day - datetime.timedelta(days=i)))
                 .replace("REPLACE_CATEGORY", random.choice(CATEGORIES))
                 .replace("REPLACE_TAG", str([random.choice(TAGS), random.choice(TAGS)]))
             )
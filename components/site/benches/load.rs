@@ -52,28 +52,28 @@ fn bench_loading_small_blog_with_syntax_highlighting(b: &mut test::Bencher) {
 //    b.iter(|| site.load().unwrap());
 //}
 //
-//#[bench]
-//fn bench_loading_big_blog(b: &mut test::Bencher) {
-//    let mut path = env::current_dir().unwrap().to_path_buf();
-//    path.push("benches");
-//    path.push("big-blog");
-//    let config_file = path.join("config.toml");
-//    let mut site = Site::new(&path, &config_file).unwrap();
-//
-//    b.iter(|| site.load().unwrap());
-//}
-//
-//#[bench]
-//fn bench_loading_big_blog_with_syntax_highlighting(b: &mut test::Bencher) {
-//    let mut path = env::current_dir().unwrap().to_path_buf();
-//    path.push("benches");
-//    path.push("big-blog");
-//    let config_file = path.join("config.toml");
-//    let mut site = Site::new(&path, &config_file).unwrap();
-//    site.config.highlight_code = true;
-//
-//    b.iter(|| site.load().unwrap());
-//}
+#[bench]
+fn bench_loading_big_blog(b: &mut test::Bencher) {
+    let mut path = env::current_dir().unwrap();
+    path.push("benches");
+    path.push("big-blog");
+    let config_file = path.join("config.toml");
+    let mut site = Site::new(&path, &config_file).unwrap();
+
+    b.iter(|| site.load().unwrap());
+}
+
+#[bench]
+fn bench_loading_big_blog_with_syntax_highlighting(b: &mut test::Bencher) {
+    let mut path = env::current_dir().unwrap();
+    path.push("benches");
+    path.push("big-blog");
+    let config_file = path.join("config.toml");
+    let mut site = Site::new(&path, &config_file).unwrap();
+    site.config.markdown.highlight_code = true;
+
+    b.iter(|| site.load().unwrap());
+}
 
 //#[bench]
 //fn bench_loading_huge_blog(b: &mut test::Bencher) {
@@ -113,3 +113,16 @@ fn bench_render_markdown_small_kb(b: &mut test::Bencher) {
 
     b.iter(|| site.render_markdown());
 }
+
+#[bench]
+fn bench_reload_templates_medium_blog(b: &mut test::Bencher) {
+    let mut site = setup_site("medium-blog");
+    // Templates are only reloaded by `zola serve`
+    site.enable_serve_mode();
+    let tmp_dir = tempdir().expect("create temp dir");
+    let public = &tmp_dir.path().join("public");
+    site.set_output_path(&public);
+    let template = site.templates_path.join("page.html");
+
+    b.iter(|| site.reload_templates(&[&template]).unwrap());
+}
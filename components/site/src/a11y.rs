@@ -0,0 +1,252 @@
+//! Finds accessibility problems in the HTML generated for pages and sections, for
+//! `zola check --a11y`.
+//!
+//! Like `validate_html`, the HTML is read with the html5ever tokenizer.
+use std::path::PathBuf;
+
+use libs::html5ever::tendril::StrTendril;
+use libs::html5ever::tokenizer::{
+    BufferQueue, Tag, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use libs::rayon::prelude::*;
+
+use config::{A11yCheck, HtmlValidationLevel};
+use errors::Result;
+
+use crate::html_validation::{after_start_tag, render_site_html};
+use crate::Site;
+
+/// The texts that don't tell where a link goes, once lowercased and without punctuation
+const VAGUE_LINK_TEXTS: &[&str] = &["here", "click here"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum A11yRule {
+    HeadingSkip,
+    ImageWithoutAlt,
+    VagueLinkText,
+    MissingMain,
+}
+
+impl A11yRule {
+    pub fn level(&self, config: &A11yCheck) -> HtmlValidationLevel {
+        match self {
+            A11yRule::HeadingSkip => config.heading_skips,
+            A11yRule::ImageWithoutAlt => config.images_without_alt,
+            A11yRule::VagueLinkText => config.vague_link_text,
+            A11yRule::MissingMain => config.missing_main,
+        }
+    }
+
+    /// What the problems of that rule are, to group them in the report
+    pub fn description(&self) -> &'static str {
+        match self {
+            A11yRule::HeadingSkip => "Heading levels skipped",
+            A11yRule::ImageWithoutAlt => "Images without alt text",
+            A11yRule::VagueLinkText => "Links without a descriptive text",
+            A11yRule::MissingMain => "Documents without a main landmark",
+        }
+    }
+}
+
+/// An accessibility problem found in a generated HTML file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct A11yProblem {
+    pub rule: A11yRule,
+    /// The line of the generated HTML, not of the content file
+    pub line: u64,
+    pub message: String,
+}
+
+/// A link being read, to know its text once it is closed
+struct OpenLink {
+    line: u64,
+    text: String,
+    /// Links with an `aria-label` or `aria-labelledby` are described by it rather than their text
+    labelled: bool,
+}
+
+#[derive(Default)]
+struct Auditor {
+    /// The level and line of the last heading
+    last_heading: Option<(u8, u64)>,
+    link: Option<OpenLink>,
+    /// Whether the HTML is a whole document rather than a fragment
+    is_document: bool,
+    has_main: bool,
+    problems: Vec<A11yProblem>,
+}
+
+impl Auditor {
+    fn report(&mut self, rule: A11yRule, line: u64, message: String) {
+        self.problems.push(A11yProblem { rule, line, message });
+    }
+
+    fn start_tag(&mut self, tag: &Tag, line: u64) {
+        let name = tag.name.as_ref();
+        let attr = |attr_name: &str| {
+            tag.attrs.iter().find(|a| a.name.local.as_ref() == attr_name).map(|a| &a.value)
+        };
+
+        if attr("role").is_some_and(|r| r.as_ref() == "main") {
+            self.has_main = true;
+        }
+        match name {
+            "html" | "body" => self.is_document = true,
+            "main" => self.has_main = true,
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name.as_bytes()[1] - b'0';
+                if let Some((previous, previous_line)) = self.last_heading {
+                    if level > previous + 1 {
+                        let message = format!(
+                            "<{}> on line {} follows the <h{}> on line {}",
+                            name, line, previous, previous_line
+                        );
+                        self.report(A11yRule::HeadingSkip, line, message);
+                    }
+                }
+                self.last_heading = Some((level, line));
+            }
+            "img" if attr("alt").is_none() => {
+                let message = format!("<img> on line {} has no `alt` attribute", line);
+                self.report(A11yRule::ImageWithoutAlt, line, message);
+            }
+            "a" if attr("href").is_some() => {
+                let labelled = attr("aria-label").is_some() || attr("aria-labelledby").is_some();
+                self.link = Some(OpenLink { line, text: String::new(), labelled });
+            }
+            _ => (),
+        }
+    }
+
+    fn end_tag(&mut self, tag: &Tag) {
+        if tag.name.as_ref() != "a" {
+            return;
+        }
+        let Some(link) = self.link.take() else {
+            return;
+        };
+        let text = link
+            .text
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim_matches(|c: char| c.is_ascii_punctuation())
+            .to_lowercase();
+        if !link.labelled && VAGUE_LINK_TEXTS.contains(&text.as_str()) {
+            let message = format!(
+                "the link on line {} only says \"{}\", which doesn't tell where it goes",
+                link.line, text
+            );
+            self.report(A11yRule::VagueLinkText, link.line, message);
+        }
+    }
+}
+
+impl TokenSink for Auditor {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, line_number: u64) -> TokenSinkResult<()> {
+        match token {
+            Token::TagToken(tag) if tag.kind == TagKind::StartTag => {
+                self.start_tag(&tag, line_number);
+                after_start_tag(&tag)
+            }
+            Token::TagToken(tag) => {
+                self.end_tag(&tag);
+                TokenSinkResult::Continue
+            }
+            Token::CharacterTokens(text) => {
+                if let Some(link) = &mut self.link {
+                    link.text.push_str(&text);
+                }
+                TokenSinkResult::Continue
+            }
+            Token::EOFToken => {
+                if self.is_document && !self.has_main {
+                    let message =
+                        "the document has no <main> element nor `role=\"main\"`".to_string();
+                    self.report(A11yRule::MissingMain, 1, message);
+                }
+                TokenSinkResult::Continue
+            }
+            _ => TokenSinkResult::Continue,
+        }
+    }
+}
+
+/// Returns the accessibility problems of the given HTML, in the order they appear
+pub fn audit(html: &str) -> Vec<A11yProblem> {
+    let mut input = BufferQueue::default();
+    input.push_back(StrTendril::from_slice(html));
+    let mut tokenizer = Tokenizer::new(Auditor::default(), TokenizerOpts::default());
+    let _ = tokenizer.feed(&mut input);
+    tokenizer.end();
+    let mut problems = tokenizer.sink.problems;
+    problems.sort_by_key(|p| p.line);
+    problems
+}
+
+/// Renders every page and section in memory and looks for accessibility problems in their HTML.
+/// Returns the content file of each problem.
+pub fn check_site_accessibility(site: &Site) -> Result<Vec<(PathBuf, A11yProblem)>> {
+    let problems = render_site_html(site)?
+        .into_par_iter()
+        .flat_map_iter(|(path, html)| audit(&html).into_iter().map(move |p| (path.clone(), p)))
+        .collect();
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(html: &str) -> Vec<A11yRule> {
+        audit(html).into_iter().map(|p| p.rule).collect()
+    }
+
+    #[test]
+    fn accepts_accessible_html() {
+        let html = r#"<!DOCTYPE html>
+<html>
+<body>
+<main>
+<h1>Title</h1><h2>Part</h2><h3>Sub part</h3><h2>Other part</h2>
+<img src="a.png" alt=""><a href="/docs/">Read the docs</a>
+<a href="/" aria-label="Home page">here</a>
+<script>document.write("<h6>")</script>
+</main>
+</body>
+</html>"#;
+        assert_eq!(audit(html), vec![]);
+    }
+
+    #[test]
+    fn finds_skipped_heading_levels() {
+        let problems = audit("<h1>A</h1>\n<h2>B</h2>\n<h4>C</h4>\n<h3>D</h3>");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].rule, A11yRule::HeadingSkip);
+        assert_eq!(problems[0].message, "<h4> on line 3 follows the <h2> on line 2");
+        assert_eq!(rules("<h3>Starting lower is fine</h3>"), vec![]);
+    }
+
+    #[test]
+    fn finds_images_without_alt_and_vague_links() {
+        assert_eq!(
+            rules("<img src=\"a.png\">\n<a href=\"/a\"> Click   HERE! </a><a href=\"/b\">here is why</a>"),
+            vec![A11yRule::ImageWithoutAlt, A11yRule::VagueLinkText]
+        );
+        let problems = audit("<p>\n<a href=\"/a\">here</a></p>");
+        assert_eq!(
+            problems[0].message,
+            "the link on line 2 only says \"here\", which doesn't tell where it goes"
+        );
+    }
+
+    #[test]
+    fn finds_documents_without_main() {
+        assert_eq!(rules("<html><body><p>Hello</p></body></html>"), vec![A11yRule::MissingMain]);
+        assert_eq!(rules("<body><div role=\"main\">Hello</div></body>"), vec![]);
+        // A fragment, like the output of a shortcode, has no landmarks
+        assert_eq!(rules("<p>Hello</p>"), vec![]);
+    }
+}
@@ -0,0 +1,176 @@
+//! The fingerprints of the files made out of the whole site: the sitemap, the feeds of the site
+//! and the search index. In `zola serve`, each of them is only regenerated when its fingerprint
+//! changed since it was last rendered: when the content it is made of or its templates changed.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use libs::tera::ast::Node;
+use libs::tera::Tera;
+
+use content::Library;
+use utils::templates::check_template_fallbacks;
+
+/// The fingerprints of the aggregates as they were last rendered, by name
+#[derive(Debug, Default)]
+pub(crate) struct AggregateFingerprints(Mutex<HashMap<String, u64>>);
+
+impl AggregateFingerprints {
+    /// Whether the aggregate was already rendered with that fingerprint
+    pub(crate) fn is_unchanged(&self, name: &str, fingerprint: u64) -> bool {
+        self.0.lock().unwrap().get(name) == Some(&fingerprint)
+    }
+
+    /// Records the fingerprint of an aggregate once it is rendered
+    pub(crate) fn record(&self, name: &str, fingerprint: u64) {
+        self.0.lock().unwrap().insert(name.to_string(), fingerprint);
+    }
+
+    /// Forgets all the fingerprints so all the aggregates get rendered again, for the changes
+    /// that could affect any of them like the `data` directory
+    pub(crate) fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+/// Adds the names of the templates used by a template to `names`: the ones it extends, includes
+/// and imports macros from, recursively
+fn add_template_dependencies(tera: &Tera, name: &str, names: &mut BTreeSet<String>) {
+    if !names.insert(name.to_string()) {
+        return;
+    }
+    let Some(template) = tera.templates.get(name) else {
+        return;
+    };
+    let mut dependencies: Vec<&str> = template.parents.iter().map(|p| p.as_str()).collect();
+    dependencies.extend(template.imported_macro_files.iter().map(|(file, _)| file.as_str()));
+    find_includes(&template.ast, &mut dependencies);
+    for definition in template.macros.values() {
+        find_includes(&definition.body, &mut dependencies);
+    }
+    for dependency in dependencies {
+        add_template_dependencies(tera, dependency, names);
+    }
+}
+
+fn find_includes<'a>(nodes: &'a [Node], includes: &mut Vec<&'a str>) {
+    for node in nodes {
+        match node {
+            Node::Include(_, names, _) => includes.extend(names.iter().map(|n| n.as_str())),
+            Node::MacroDefinition(_, definition, _) => find_includes(&definition.body, includes),
+            Node::FilterSection(_, section, _) => find_includes(&section.body, includes),
+            Node::Block(_, block, _) => find_includes(&block.body, includes),
+            Node::Forloop(_, forloop, _) => {
+                find_includes(&forloop.body, includes);
+                if let Some(ref body) = forloop.empty_body {
+                    find_includes(body, includes);
+                }
+            }
+            Node::If(if_node, _) => {
+                for (_, _, body) in &if_node.conditions {
+                    find_includes(body, includes);
+                }
+                if let Some((_, ref body)) = if_node.otherwise {
+                    find_includes(body, includes);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// The names of the templates used to render these templates, themselves included, once the
+/// fallbacks on the theme and the built-in templates are resolved
+pub(crate) fn used_templates(
+    tera: &Tera,
+    theme: &Option<String>,
+    names: &[&str],
+) -> BTreeSet<String> {
+    let mut used = BTreeSet::new();
+    for name in names {
+        if let Some(name) = check_template_fallbacks(name, tera, theme) {
+            add_template_dependencies(tera, name, &mut used);
+        }
+    }
+    used
+}
+
+/// Hashes the templates an aggregate is rendered with, along with all the templates they use.
+/// The files are read again rather than hashing the parsed templates, whose maps aren't in the
+/// same order after a reload. The built-in templates have no file and only their name is hashed.
+pub(crate) fn hash_templates(
+    tera: &Tera,
+    theme: &Option<String>,
+    names: &[&str],
+    hasher: &mut DefaultHasher,
+) {
+    for name in used_templates(tera, theme, names) {
+        name.hash(hasher);
+        let path = tera.templates.get(&name).and_then(|t| t.path.as_ref());
+        if let Some(path) = path {
+            std::fs::read(path).unwrap_or_default().hash(hasher);
+        }
+    }
+}
+
+/// Hashes the fingerprints of these pages and sections, in the order of their paths
+pub(crate) fn hash_content<'a>(
+    library: &Library,
+    paths: impl Iterator<Item = &'a PathBuf>,
+    hasher: &mut DefaultHasher,
+) {
+    let mut paths: Vec<_> = paths.collect();
+    paths.sort_unstable();
+    for path in paths {
+        path.hash(hasher);
+        library.fingerprints.get(path).hash(hasher);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::hash::Hasher;
+
+    use tempfile::tempdir;
+
+    fn fingerprint(tera: &Tera, name: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_templates(tera, &None, &[name], &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn template_fingerprints_follow_the_templates_used() {
+        let dir = tempdir().unwrap();
+        let write = |name: &str, content: &str| std::fs::write(dir.path().join(name), content);
+        write("base.xml", "{% block content %}{% endblock %}").unwrap();
+        write("macros.xml", "{% macro title() %}Title{% endmacro %}").unwrap();
+        write("header.xml", "Header").unwrap();
+        write("other.xml", "Other").unwrap();
+        write(
+            "feed.xml",
+            r#"{% extends "base.xml" %}{% import "macros.xml" as macros %}
+{% block content %}{% if true %}{% include "header.xml" %}{% endif %}{{ macros::title() }}{% endblock %}"#,
+        )
+        .unwrap();
+        let mut tera = Tera::new(&format!("{}/*.xml", dir.path().display())).unwrap();
+        let before = fingerprint(&tera, "feed.xml");
+
+        write("other.xml", "Other edited").unwrap();
+        tera.full_reload().unwrap();
+        assert_eq!(fingerprint(&tera, "feed.xml"), before);
+
+        for name in ["base.xml", "macros.xml", "header.xml"] {
+            let before = fingerprint(&tera, "feed.xml");
+            let content = std::fs::read_to_string(dir.path().join(name)).unwrap();
+            write(name, &format!("{} ", content)).unwrap();
+            tera.full_reload().unwrap();
+            assert_ne!(fingerprint(&tera, "feed.xml"), before, "{}", name);
+        }
+    }
+}
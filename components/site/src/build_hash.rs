@@ -0,0 +1,111 @@
+//! A single hash of everything `zola build` outputs, for the hosts and CI pipelines that only
+//! need to deploy when something changed.
+
+use std::fs;
+use std::path::Path;
+
+use libs::sha2::{Digest, Sha256};
+use libs::walkdir::WalkDir;
+
+use config::Config;
+use errors::{Context, Result};
+
+use crate::manifest::MANIFEST_FILENAME;
+
+/// The name of the file the build hash is written to, at the root of the output directory
+pub const BUILD_HASH_FILENAME: &str = ".zola-build-hash";
+
+/// The SHA-256 of that content, in hexadecimal
+pub fn hash_content(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// The files of the output directory with the hash of their content, as a path relative to the
+/// output directory with `/` separators.
+/// The manifest and the build hash are left out since they are made out of the other files,
+/// as are the dotfiles kept by `preserve_dotfiles_in_output` since Zola didn't write them.
+pub fn hash_output_files(output_path: &Path, config: &Config) -> Result<Vec<(String, String)>> {
+    let mut files = Vec::new();
+    if !output_path.exists() {
+        return Ok(files);
+    }
+
+    for entry in WalkDir::new(output_path).follow_links(true) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(output_path).unwrap();
+        let path = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if path == MANIFEST_FILENAME
+            || path == BUILD_HASH_FILENAME
+            || (config.preserve_dotfiles_in_output && path.starts_with('.'))
+        {
+            continue;
+        }
+
+        let content = fs::read(entry.path())
+            .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+        files.push((path, hash_content(&content)));
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Combines the hashes of the files into a single one, Merkle-style: the hash of the list of the
+/// paths and hashes of the files, sorted by path. Any file added, removed, renamed or changed
+/// changes it, and it doesn't depend on the order the files were written in.
+pub fn combine_hashes<'a>(files: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut files: Vec<_> = files.into_iter().collect();
+    files.sort_unstable();
+    let mut hasher = Sha256::new();
+    for (path, hash) in files {
+        hasher.update(path.as_bytes());
+        hasher.update([0]);
+        hasher.update(hash.as_bytes());
+        hasher.update([b'\n']);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_hash_depends_on_paths_and_content_only() {
+        let hash = combine_hashes([("index.html", "aa"), ("site.css", "bb")]);
+        assert_eq!(hash, combine_hashes([("site.css", "bb"), ("index.html", "aa")]));
+        assert_ne!(hash, combine_hashes([("index.html", "aa"), ("site.css", "bc")]));
+        assert_ne!(hash, combine_hashes([("index.html", "aa"), ("style.css", "bb")]));
+        assert_ne!(hash, combine_hashes([("index.html", "aa")]));
+    }
+
+    #[test]
+    fn can_hash_output_files() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let output = tmp_dir.path();
+        fs::create_dir_all(output.join("blog")).unwrap();
+        fs::write(output.join("blog/index.html"), "hello").unwrap();
+        fs::write(output.join(".htaccess"), "kept").unwrap();
+        fs::write(output.join(BUILD_HASH_FILENAME), "previous").unwrap();
+        fs::write(output.join(MANIFEST_FILENAME), "[]").unwrap();
+
+        let mut config = Config::default();
+        let files = hash_output_files(output, &config).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                (".htaccess".to_string(), hash_content(b"kept")),
+                ("blog/index.html".to_string(), hash_content(b"hello")),
+            ]
+        );
+        config.preserve_dotfiles_in_output = true;
+        assert_eq!(hash_output_files(output, &config).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,120 @@
+//! What the last build or re-render of a site did, shown by `zola serve` after each change.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// The upper bounds of the buckets of `BuildSummary::timing_histogram`, in milliseconds
+const HISTOGRAM_BUCKETS_MS: [u128; 6] = [1, 5, 10, 50, 100, 500];
+
+/// How long rendering a page took, recorded when the timings are enabled with
+/// `Site::enable_timings`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageTiming {
+    /// Rendering its Markdown, shortcodes included
+    pub markdown: Duration,
+    /// Rendering its templates, the ones of its output formats included
+    pub template: Duration,
+}
+
+impl PageTiming {
+    pub fn total(&self) -> Duration {
+        self.markdown + self.template
+    }
+}
+
+/// The content rendered and the files regenerated since the site was last loaded or since the
+/// last partial re-render, with how long each step took
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildSummary {
+    /// The full paths of the content files of the pages rendered, sorted
+    pub pages: Vec<PathBuf>,
+    /// The full paths of the content files of the sections rendered, sorted
+    pub sections: Vec<PathBuf>,
+    /// The files made out of the whole site that were regenerated, eg `sitemap` or `feeds`
+    pub aggregates: Vec<&'static str>,
+    /// The files made out of the whole site that `zola serve` didn't regenerate as nothing they
+    /// are made of changed
+    pub unchanged_aggregates: Vec<&'static str>,
+    /// The steps of the load/build and how long they took, in order
+    pub phases: Vec<(String, Duration)>,
+    /// How long each page took to render, by full path of its content file. Empty unless the
+    /// timings are enabled
+    pub page_timings: HashMap<PathBuf, PageTiming>,
+}
+
+impl BuildSummary {
+    pub(crate) fn add_page(&mut self, path: PathBuf) {
+        self.pages.push(path);
+    }
+
+    pub(crate) fn add_section(&mut self, path: PathBuf) {
+        self.sections.push(path);
+    }
+
+    pub(crate) fn add_aggregate(&mut self, name: &'static str) {
+        if !self.aggregates.contains(&name) {
+            self.aggregates.push(name);
+        }
+    }
+
+    pub(crate) fn add_unchanged_aggregate(&mut self, name: &'static str) {
+        if !self.unchanged_aggregates.contains(&name) {
+            self.unchanged_aggregates.push(name);
+        }
+    }
+
+    pub(crate) fn add_phase(&mut self, name: &str, duration: Duration) {
+        self.phases.push((name.to_string(), duration));
+    }
+
+    pub(crate) fn add_markdown_time(&mut self, path: PathBuf, duration: Duration) {
+        self.page_timings.entry(path).or_default().markdown += duration;
+    }
+
+    pub(crate) fn add_template_time(&mut self, path: PathBuf, duration: Duration) {
+        self.page_timings.entry(path).or_default().template += duration;
+    }
+
+    /// The `count` pages that took the longest to render, the slowest first
+    pub fn slowest_pages(&self, count: usize) -> Vec<(&PathBuf, &PageTiming)> {
+        let mut pages: Vec<_> = self.page_timings.iter().collect();
+        pages.sort_unstable_by(|a, b| b.1.total().cmp(&a.1.total()).then(a.0.cmp(b.0)));
+        pages.truncate(count);
+        pages
+    }
+
+    /// How many pages took how long to render in total, as the label of the bucket and its
+    /// count, from the fastest bucket to the slowest. Empty unless the timings are enabled
+    pub fn timing_histogram(&self) -> Vec<(String, usize)> {
+        if self.page_timings.is_empty() {
+            return Vec::new();
+        }
+        let mut counts = [0; HISTOGRAM_BUCKETS_MS.len() + 1];
+        for timing in self.page_timings.values() {
+            let ms = timing.total().as_millis();
+            let bucket = HISTOGRAM_BUCKETS_MS
+                .iter()
+                .position(|max| ms < *max)
+                .unwrap_or(HISTOGRAM_BUCKETS_MS.len());
+            counts[bucket] += 1;
+        }
+        let mut labels = vec![format!("< {}ms", HISTOGRAM_BUCKETS_MS[0])];
+        labels.extend(HISTOGRAM_BUCKETS_MS.windows(2).map(|w| format!("{}-{}ms", w[0], w[1])));
+        labels.push(format!(">= {}ms", HISTOGRAM_BUCKETS_MS[HISTOGRAM_BUCKETS_MS.len() - 1]));
+        labels.into_iter().zip(counts).collect()
+    }
+
+    /// The content is rendered in parallel so the pages and sections are sorted to be readable.
+    /// They are also deduplicated as a page can be rendered twice by a partial re-render.
+    /// The feeds regenerated in one language and not in another are listed as regenerated
+    pub(crate) fn sorted(mut self) -> Self {
+        let aggregates = &self.aggregates;
+        self.unchanged_aggregates.retain(|name| !aggregates.contains(name));
+        self.pages.sort_unstable();
+        self.pages.dedup();
+        self.sections.sort_unstable();
+        self.sections.dedup();
+        self
+    }
+}
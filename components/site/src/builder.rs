@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use libs::tera::{Filter, Function};
+
+use errors::Result;
+
+use crate::tpls::CustomTeraFns;
+use crate::Site;
+
+/// Sets up a [`Site`] for programs embedding Zola.
+///
+/// ```no_run
+/// use site::SiteBuilder;
+///
+/// let mut site = SiteBuilder::new("my-site")
+///     .base_url("https://example.com")
+///     .output_dir("/tmp/my-site")
+///     .include_drafts(true)
+///     .create()?;
+/// site.load()?;
+/// let stats = site.build()?;
+/// println!("Built {} pages", stats.pages);
+/// # Ok::<(), errors::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct SiteBuilder {
+    root: PathBuf,
+    config_file: PathBuf,
+    base_url: Option<String>,
+    output_dir: Option<PathBuf>,
+    include_drafts: bool,
+    include_future: bool,
+    relative_urls: bool,
+    tera_fns: CustomTeraFns,
+}
+
+impl SiteBuilder {
+    /// Starts the setup of the site in the `root` directory, using its `config.toml`
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        SiteBuilder {
+            root: root.as_ref().to_path_buf(),
+            config_file: PathBuf::from("config.toml"),
+            base_url: None,
+            output_dir: None,
+            include_drafts: false,
+            include_future: false,
+            relative_urls: false,
+            tera_fns: CustomTeraFns::default(),
+        }
+    }
+
+    /// The config file to use, relative to the root directory if it's a relative path
+    pub fn config_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.config_file = path.as_ref().to_path_buf();
+        self
+    }
+
+    /// Overrides the `base_url` of the config
+    pub fn base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Where to write the site instead of the `output_dir` of the config
+    pub fn output_dir<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.output_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Whether to load the draft pages
+    pub fn include_drafts(mut self, include_drafts: bool) -> Self {
+        self.include_drafts = include_drafts;
+        self
+    }
+
+    /// Whether to load the pages dated in the future in every section
+    pub fn include_future(mut self, include_future: bool) -> Self {
+        self.include_future = include_future;
+        self
+    }
+
+    /// Whether to make the internal links relative, like `zola build --offline`
+    pub fn relative_urls(mut self, relative_urls: bool) -> Self {
+        self.relative_urls = relative_urls;
+        self
+    }
+
+    /// Adds a Tera function to the templates and shortcodes, see [`Site::register_function`]
+    pub fn function<F: Function + 'static>(mut self, name: &str, function: F) -> Self {
+        self.tera_fns.functions.push((name.to_string(), Arc::new(function)));
+        self
+    }
+
+    /// Adds a Tera filter to the templates and shortcodes, see [`Site::register_filter`]
+    pub fn filter<F: Filter + 'static>(mut self, name: &str, filter: F) -> Self {
+        self.tera_fns.filters.push((name.to_string(), Arc::new(filter)));
+        self
+    }
+
+    /// Parses the config and the templates of the site.
+    /// The content is not read until [`Site::load`] is called.
+    pub fn create(self) -> Result<Site> {
+        let mut site = Site::new(&self.root, &self.config_file)?;
+        if let Some(base_url) = self.base_url {
+            site.set_base_url(base_url);
+        }
+        if let Some(output_dir) = self.output_dir {
+            site.set_output_path(output_dir);
+        }
+        if self.include_drafts {
+            site.include_drafts();
+        }
+        if self.include_future {
+            site.include_future();
+        }
+        if self.relative_urls {
+            site.enable_relative_urls();
+        }
+        self.tera_fns.register(&mut site.tera);
+        site.tera_fns = self.tera_fns;
+        Ok(site)
+    }
+}
@@ -0,0 +1,35 @@
+//! The scratch directory of the `low_memory` builds. The rendered HTML of every page is written
+//! there as soon as its Markdown is rendered instead of staying in memory for the whole build,
+//! and read back by `Page::content` whenever a template or the search index needs it.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use errors::Result;
+use utils::fs::create_directory;
+
+/// A directory in the temporary directory of the system, removed with everything in it when
+/// dropped, ie when the site is
+#[derive(Debug)]
+pub(crate) struct ContentSpill(PathBuf);
+
+impl ContentSpill {
+    pub(crate) fn new() -> Result<Self> {
+        static SPILLS: AtomicUsize = AtomicUsize::new(0);
+        let id = SPILLS.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("zola-content-{}-{}", std::process::id(), id));
+        create_directory(&path)?;
+        Ok(ContentSpill(path))
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ContentSpill {
+    fn drop(&mut self) {
+        // Nothing left to read it once the site is gone
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
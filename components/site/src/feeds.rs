@@ -1,4 +1,3 @@
-use std::cmp::Ordering;
 use std::path::PathBuf;
 
 use libs::rayon::prelude::*;
@@ -6,7 +5,8 @@ use libs::tera::Context;
 use serde::Serialize;
 
 use crate::Site;
-use content::{Page, TaxonomyTerm};
+use config::Config;
+use content::{tie_break, Library, Page, TaxonomyTerm};
 use errors::Result;
 use utils::templates::render_template;
 
@@ -27,6 +27,36 @@ impl<'a> SerializedFeedTaxonomyItem<'a> {
     }
 }
 
+/// The pages of `all_pages` that end up in a feed, most recent first and limited to `feed_limit`
+pub fn select_feed_pages<'a>(config: &Config, all_pages: Vec<&'a Page>) -> Vec<&'a Page> {
+    // The protected pages have their content encrypted, it can't be in the feeds
+    let mut pages = all_pages
+        .into_iter()
+        .filter(|p| p.meta.date.is_some() && p.password.is_none())
+        .collect::<Vec<_>>();
+
+    pages.par_sort_unstable_by(|a, b| {
+        b.meta.datetime.unwrap().cmp(&a.meta.datetime.unwrap()).then_with(|| tie_break(a, b))
+    });
+    // limit to the last n elements if the limit is set; otherwise use all.
+    pages.truncate(config.feed_limit.unwrap_or(pages.len()));
+    pages
+}
+
+/// The pages of the main feeds of the given language, empty if it doesn't generate feeds
+pub fn site_feed_pages<'a>(config: &Config, library: &'a Library, lang: &str) -> Vec<&'a Page> {
+    if !config.languages[lang].generate_feeds {
+        return Vec::new();
+    }
+    let pages = library
+        .pages
+        .values()
+        .filter(|p| p.lang == lang && !p.meta.unlisted)
+        .filter(|p| config.is_in_feed_sections(&p.file.components))
+        .collect();
+    select_feed_pages(config, pages)
+}
+
 pub fn render_feeds(
     site: &Site,
     all_pages: Vec<&Page>,
@@ -34,23 +64,14 @@ pub fn render_feeds(
     base_path: Option<&PathBuf>,
     additional_context_fn: impl Fn(Context) -> Context,
 ) -> Result<Option<Vec<String>>> {
-    let mut pages = all_pages.into_iter().filter(|p| p.meta.date.is_some()).collect::<Vec<_>>();
+    let pages = select_feed_pages(&site.config, all_pages);
 
     // Don't generate a feed if none of the pages has a date
     if pages.is_empty() {
         return Ok(None);
     }
 
-    pages.par_sort_unstable_by(|a, b| {
-        let ord = b.meta.datetime.unwrap().cmp(&a.meta.datetime.unwrap());
-        if ord == Ordering::Equal {
-            a.permalink.cmp(&b.permalink)
-        } else {
-            ord
-        }
-    });
-
-    let mut context = Context::new();
+    let mut context = site.config.template_context(lang, &site.data);
     context.insert(
         "last_updated",
         pages
@@ -61,27 +82,20 @@ pub fn render_feeds(
             .unwrap(), // Guaranteed because of pages[0].meta.date
     );
     let library = site.library.read().unwrap();
-    // limit to the last n elements if the limit is set; otherwise use all.
-    let num_entries = site.config.feed_limit.unwrap_or(pages.len());
-    let p = pages
-        .iter()
-        .take(num_entries)
-        .map(|x| x.serialize_without_siblings(&library))
-        .collect::<Vec<_>>();
+    let p = pages.iter().map(|x| x.serialize_without_siblings(&library)).collect::<Vec<_>>();
 
     context.insert("pages", &p);
-    context.insert("config", &site.config.serialize(lang));
-    context.insert("lang", lang);
 
     let mut feeds = Vec::new();
     for feed_filename in &site.config.languages[lang].feed_filenames {
         let mut context = context.clone();
 
+        let output_filename = site.config.feed_output_filename(lang, feed_filename);
         let feed_url = if let Some(base) = base_path {
             site.config
-                .make_permalink(&base.join(feed_filename).to_string_lossy().replace('\\', "/"))
+                .make_permalink(&base.join(output_filename).to_string_lossy().replace('\\', "/"))
         } else {
-            site.config.make_permalink(feed_filename)
+            site.config.make_permalink(output_filename)
         };
 
         context.insert("feed_url", &feed_url);
@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use libs::once_cell::sync::Lazy;
+use libs::regex::{Captures, Regex};
+use libs::sha2::{Digest, Sha256};
+use libs::walkdir::WalkDir;
+
+use config::Config;
+use errors::{bail, Context, Result};
+use imageproc::RESIZED_SUBDIR;
+use utils::fs::{create_file, walk_dir_following_links};
+use utils::globs::IgnoreRules;
+
+/// Original path -> content-hashed path, both relative to the output directory
+pub type AssetManifest = BTreeMap<String, String>;
+
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
+static CSS_URL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"url\(\s*(['"]?)([^'")]+)(['"]?)\s*\)"#).unwrap());
+
+/// Inserts a hash of the contents right before the extension: `css/app.css` -> `css/app.3f9ab2c4d1.css`
+pub fn hashed_path(path: &str, contents: &[u8]) -> String {
+    let hash = format!("{:x}", Sha256::digest(contents));
+    let hash = &hash[..10];
+    let (dir, filename) = match path.rfind('/') {
+        Some(idx) => path.split_at(idx + 1),
+        None => ("", path),
+    };
+    match filename.rfind('.') {
+        Some(idx) if idx > 0 => format!("{}{}.{}{}", dir, &filename[..idx], hash, &filename[idx..]),
+        _ => format!("{}.{}", path, hash),
+    }
+}
+
+fn to_manifest_key(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => Some(s.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The assets matching `hash_static_assets` that will end up in the output directory:
+/// the compiled Sass files and the files of the static directories.
+/// Processed images are left alone, their filenames already contain a hash.
+pub fn find_assets_to_hash(
+    config: &Config,
+    static_dirs: &[(PathBuf, bool)],
+    output_path: &Path,
+    compiled_sass: &[PathBuf],
+    ignore_rules: &IgnoreRules,
+) -> BTreeSet<String> {
+    let mut assets = BTreeSet::new();
+
+    for path in compiled_sass {
+        let relative_path = path.strip_prefix(output_path).unwrap_or(path);
+        if config.should_hash_asset(relative_path) {
+            assets.insert(to_manifest_key(relative_path));
+        }
+    }
+
+    for (dir, respect_ignored_static) in static_dirs {
+        if !dir.exists() {
+            continue;
+        }
+        for entry in walk_dir_following_links(WalkDir::new(dir)).filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let relative_path = path.strip_prefix(dir).unwrap();
+            if !path.is_file()
+                || relative_path.starts_with(RESIZED_SUBDIR)
+                || !config.should_hash_asset(relative_path)
+                || ignore_rules.is_ignored(path, false)
+            {
+                continue;
+            }
+            if *respect_ignored_static
+                && config
+                    .ignored_static_globset
+                    .as_ref()
+                    .is_some_and(|gs| gs.is_match(relative_path))
+            {
+                continue;
+            }
+            assets.insert(to_manifest_key(relative_path));
+        }
+    }
+
+    assets
+}
+
+/// Resolves a `url(...)` reference found in the CSS file at `css_key` to a manifest key
+fn resolve_css_reference(css_key: &str, reference: &str) -> Option<String> {
+    if reference.starts_with('#') || reference.starts_with("//") || reference.contains(':') {
+        return None;
+    }
+
+    let mut parts: Vec<&str> =
+        if reference.starts_with('/') { Vec::new() } else { css_key.split('/').collect() };
+    // The CSS filename itself
+    parts.pop();
+
+    for segment in reference.split('/') {
+        match segment {
+            "" | "." => (),
+            ".." => {
+                parts.pop()?;
+            }
+            s => parts.push(s),
+        }
+    }
+
+    Some(parts.join("/"))
+}
+
+/// Rewrites the `url(...)` of the given CSS that point to hashed assets
+fn rewrite_css_urls(css_key: &str, css: &str, manifest: &AssetManifest) -> String {
+    CSS_URL_RE
+        .replace_all(css, |caps: &Captures| {
+            let reference = &caps[2];
+            // Keep any query string or fragment as is
+            let end = reference.find(['?', '#']).unwrap_or(reference.len());
+            let (path, suffix) = reference.split_at(end);
+
+            match resolve_css_reference(css_key, path).and_then(|key| manifest.get(&key)) {
+                Some(hashed) => {
+                    let hashed_filename = hashed.rsplit('/').next().unwrap();
+                    let new_path = match path.rfind('/') {
+                        Some(idx) => format!("{}{}", &path[..=idx], hashed_filename),
+                        None => hashed_filename.to_string(),
+                    };
+                    format!("url({}{}{}{})", &caps[1], new_path, suffix, &caps[3])
+                }
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// The manifest keys of the assets the given CSS file points to
+fn css_references<'a>(css_key: &'a str, css: &'a str) -> impl Iterator<Item = String> + 'a {
+    CSS_URL_RE.captures_iter(css).filter_map(move |caps| {
+        let reference = caps.get(2).unwrap().as_str();
+        let end = reference.find(['?', '#']).unwrap_or(reference.len());
+        resolve_css_reference(css_key, &reference[..end])
+    })
+}
+
+/// Renames the given assets, already copied to the output directory, to a filename containing
+/// the hash of their final contents and writes the manifest at the root of the output directory.
+/// The CSS files are renamed last, once their `url(...)` have been pointed to the hashed assets,
+/// so their hash changes when one of the assets they use changes. A CSS file using another
+/// hashed CSS file is renamed after it.
+pub fn hash_assets(output_path: &Path, assets: &BTreeSet<String>) -> Result<AssetManifest> {
+    let manifest_path = output_path.join(MANIFEST_FILENAME);
+    if manifest_path.exists() {
+        bail!(
+            "The output directory already has a `{}`, coming from the static files, but that \
+             filename is used for the manifest of the hashed assets when `hash_static_assets` is set",
+            MANIFEST_FILENAME
+        );
+    }
+
+    let mut manifest = AssetManifest::new();
+    let mut pending_css = BTreeMap::new();
+    for key in assets {
+        let path = output_path.join(key);
+        if !path.exists() {
+            continue;
+        }
+        let contents =
+            fs::read(&path).with_context(|| format!("Failed to read asset {}", path.display()))?;
+        if key.ends_with(".css") {
+            pending_css.insert(key.as_str(), String::from_utf8_lossy(&contents).into_owned());
+            continue;
+        }
+        let hashed = hashed_path(key, &contents);
+        fs::rename(&path, output_path.join(&hashed)).with_context(|| {
+            format!("Failed to rename {} to its hashed filename {}", path.display(), hashed)
+        })?;
+        manifest.insert(key.clone(), hashed);
+    }
+
+    while !pending_css.is_empty() {
+        let mut ready: Vec<&str> = pending_css
+            .iter()
+            .filter(|(key, css)| {
+                css_references(key, css)
+                    .all(|r| r == **key || !pending_css.contains_key(r.as_str()))
+            })
+            .map(|(key, _)| *key)
+            .collect();
+        // Files using each other: there is no right order, the first one keeps pointing to
+        // the original filenames of the others
+        if ready.is_empty() {
+            ready = pending_css.keys().take(1).copied().collect();
+        }
+
+        for key in ready {
+            let css = pending_css.remove(key).unwrap();
+            let rewritten = rewrite_css_urls(key, &css, &manifest);
+            let hashed = hashed_path(key, rewritten.as_bytes());
+            // The file might be hard linked to the one in `static`, we do not want to modify it
+            fs::remove_file(output_path.join(key))?;
+            create_file(&output_path.join(&hashed), rewritten)?;
+            manifest.insert(key.to_string(), hashed);
+        }
+    }
+
+    let manifest_json = libs::serde_json::to_string_pretty(&manifest)?;
+    create_file(&manifest_path, manifest_json)?;
+    Ok(manifest)
+}
+
+/// Points the CSS files of the output directory that are not hashed themselves, eg the ones
+/// rendered from templates, to the hashed assets
+pub fn rewrite_css_files(output_path: &Path, manifest: &AssetManifest) -> Result<()> {
+    let hashed_files: BTreeSet<&String> = manifest.values().collect();
+    for entry in WalkDir::new(output_path).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !path.is_file() || path.extension() != Some("css".as_ref()) {
+            continue;
+        }
+        let key = to_manifest_key(path.strip_prefix(output_path).unwrap());
+        if hashed_files.contains(&key) {
+            continue;
+        }
+        let css = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read CSS file {}", path.display()))?;
+        let rewritten = rewrite_css_urls(&key, &css, manifest);
+        if rewritten != css {
+            // The file might be hard linked to the one in `static`, we do not want to modify it
+            fs::remove_file(path)?;
+            create_file(path, rewritten)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_insert_hash_before_extension() {
+        let hashed = hashed_path("css/app.min.css", b"body {}");
+        assert!(hashed.starts_with("css/app.min."));
+        assert!(hashed.ends_with(".css"));
+        assert_eq!(hashed.len(), "css/app.min.css".len() + 11);
+        assert_eq!(hashed, hashed_path("css/app.min.css", b"body {}"));
+        assert_ne!(hashed, hashed_path("css/app.min.css", b"body { color: red; }"));
+
+        assert!(hashed_path("LICENSE", b"MIT").starts_with("LICENSE."));
+        assert!(hashed_path(".well-known/.hidden", b"").starts_with(".well-known/.hidden."));
+    }
+
+    #[test]
+    fn can_rewrite_css_urls() {
+        let manifest = AssetManifest::from([
+            ("img/logo.png".to_string(), "img/logo.abc.png".to_string()),
+            ("fonts/a.woff2".to_string(), "fonts/a.def.woff2".to_string()),
+        ]);
+        let css = r#"a{background:url("../img/logo.png")}@font-face{src:url(/fonts/a.woff2?v=1#x)}b{background:url('../img/other.png')}c{background:url(data:image/png;base64,AA)}"#;
+        let expected = r#"a{background:url("../img/logo.abc.png")}@font-face{src:url(/fonts/a.def.woff2?v=1#x)}b{background:url('../img/other.png')}c{background:url(data:image/png;base64,AA)}"#;
+        assert_eq!(rewrite_css_urls("css/app.css", css, &manifest), expected);
+    }
+
+    #[test]
+    fn hashes_css_files_after_the_assets_they_use() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let output = tmp_dir.path();
+        let build = |logo: &str| {
+            let _ = fs::remove_dir_all(output);
+            create_file(&output.join("img/logo.png"), logo).unwrap();
+            create_file(&output.join("css/fonts.css"), "a{background:url(../img/logo.png)}")
+                .unwrap();
+            create_file(&output.join("css/app.css"), "@import url(fonts.css);").unwrap();
+            let assets = ["img/logo.png", "css/fonts.css", "css/app.css"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            hash_assets(output, &assets).unwrap()
+        };
+
+        let before = build("logo");
+        let fonts = fs::read_to_string(output.join(&before["css/fonts.css"])).unwrap();
+        assert!(fonts.contains(before["img/logo.png"].trim_start_matches("img/")));
+        let app = fs::read_to_string(output.join(&before["css/app.css"])).unwrap();
+        assert!(app.contains(before["css/fonts.css"].trim_start_matches("css/")));
+        assert!(!output.join("css/app.css").exists());
+        assert!(output.join(MANIFEST_FILENAME).exists());
+
+        let after = build("new logo");
+        for key in ["img/logo.png", "css/fonts.css", "css/app.css"] {
+            assert_ne!(before[key], after[key]);
+        }
+    }
+}
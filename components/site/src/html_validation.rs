@@ -0,0 +1,373 @@
+//! Finds structural problems in the HTML generated for pages and sections, for `validate_html`.
+//!
+//! The HTML is read with the html5ever tokenizer rather than parsed into a tree: a tree builder
+//! would silently fix most of the problems we are looking for.
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use libs::html5ever::tendril::StrTendril;
+use libs::html5ever::tokenizer::states::RawKind;
+use libs::html5ever::tokenizer::{
+    BufferQueue, Tag, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use libs::rayon::prelude::*;
+
+use config::{HtmlValidation, HtmlValidationLevel};
+use content::Paginator;
+use errors::Result;
+
+use crate::Site;
+
+/// Elements that never have content nor closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose closing tag can be omitted
+const OPTIONAL_END_TAG_ELEMENTS: &[&str] = &[
+    "body", "caption", "colgroup", "dd", "dt", "head", "html", "li", "optgroup", "option", "p",
+    "rp", "rt", "tbody", "td", "tfoot", "th", "thead", "tr",
+];
+
+/// Elements that can't be inside a `<p>`: they close it instead
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HtmlProblemKind {
+    UnclosedElement,
+    DuplicateId,
+    LinkWithoutHref,
+    ImageWithoutAlt,
+    InvalidNesting,
+}
+
+impl HtmlProblemKind {
+    pub fn level(&self, config: &HtmlValidation) -> HtmlValidationLevel {
+        match self {
+            HtmlProblemKind::UnclosedElement => config.unclosed_elements,
+            HtmlProblemKind::DuplicateId => config.duplicate_ids,
+            HtmlProblemKind::LinkWithoutHref => config.links_without_href,
+            HtmlProblemKind::ImageWithoutAlt => config.images_without_alt,
+            HtmlProblemKind::InvalidNesting => config.invalid_nesting,
+        }
+    }
+}
+
+/// A problem found in a generated HTML file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlProblem {
+    pub kind: HtmlProblemKind,
+    /// The line of the generated HTML, not of the content file
+    pub line: u64,
+    pub message: String,
+}
+
+struct OpenElement {
+    name: String,
+    line: u64,
+    /// For a `<p>`, the first block element opened inside it with its line.
+    /// Browsers close the `<p>` there, which is only a mistake if it is closed explicitly later
+    block_inside: Option<(String, u64)>,
+}
+
+#[derive(Default)]
+struct Validator {
+    open_elements: Vec<OpenElement>,
+    ids: HashMap<String, u64>,
+    problems: Vec<HtmlProblem>,
+}
+
+impl Validator {
+    fn report(&mut self, kind: HtmlProblemKind, line: u64, message: String) {
+        self.problems.push(HtmlProblem { kind, line, message });
+    }
+
+    /// Closes the open element at that index, and the ones opened after it
+    fn close_until(&mut self, index: usize, line: u64) {
+        for element in self.open_elements.split_off(index).into_iter().skip(1).rev() {
+            if !OPTIONAL_END_TAG_ELEMENTS.contains(&element.name.as_str()) {
+                let message = format!(
+                    "<{}> opened on line {} is not closed before line {}",
+                    element.name, element.line, line
+                );
+                self.report(HtmlProblemKind::UnclosedElement, element.line, message);
+            }
+        }
+    }
+
+    fn start_tag(&mut self, tag: &Tag, line: u64) {
+        let name = tag.name.as_ref();
+        let attr = |attr_name: &str| {
+            tag.attrs.iter().find(|a| a.name.local.as_ref() == attr_name).map(|a| &a.value)
+        };
+
+        if let Some(id) = attr("id") {
+            if let Some(first_line) = self.ids.get(id.as_ref()) {
+                let message = format!(
+                    "the id `{}` on line {} is already used on line {}",
+                    id, line, first_line
+                );
+                self.report(HtmlProblemKind::DuplicateId, line, message);
+            } else {
+                self.ids.insert(id.to_string(), line);
+            }
+        }
+        if name == "a" && attr("href").is_none() {
+            let message = format!("<a> on line {} has no `href` attribute", line);
+            self.report(HtmlProblemKind::LinkWithoutHref, line, message);
+        }
+        if name == "img" && attr("alt").is_none() {
+            let message = format!("<img> on line {} has no `alt` attribute", line);
+            self.report(HtmlProblemKind::ImageWithoutAlt, line, message);
+        }
+
+        if BLOCK_ELEMENTS.contains(&name) {
+            if let Some(index) = self.open_elements.iter().rposition(|e| e.name == "p") {
+                if name == "p" {
+                    // A `<p>` implicitly closes the previous one
+                    self.close_until(index, line);
+                } else if self.open_elements[index].block_inside.is_none() {
+                    self.open_elements[index].block_inside = Some((name.to_string(), line));
+                }
+            }
+        }
+
+        if !tag.self_closing && !VOID_ELEMENTS.contains(&name) {
+            self.open_elements.push(OpenElement {
+                name: name.to_string(),
+                line,
+                block_inside: None,
+            });
+        }
+    }
+
+    fn end_tag(&mut self, tag: &Tag, line: u64) {
+        let name = tag.name.as_ref();
+        if VOID_ELEMENTS.contains(&name) {
+            return;
+        }
+        match self.open_elements.iter().rposition(|e| e.name == name) {
+            Some(index) => {
+                if let Some((block, block_line)) = self.open_elements[index].block_inside.take() {
+                    let message = format!(
+                        "<{}> on line {} is inside the <p> opened on line {}",
+                        block, block_line, self.open_elements[index].line
+                    );
+                    self.report(HtmlProblemKind::InvalidNesting, block_line, message);
+                }
+                self.close_until(index, line);
+            }
+            None => {
+                let message =
+                    format!("</{}> on line {} closes an element that is not open", name, line);
+                self.report(HtmlProblemKind::UnclosedElement, line, message);
+            }
+        }
+    }
+}
+
+/// What the tokenizer reads after that start tag: it needs to be told where the content is not
+/// HTML, like in a `<script>`
+pub(crate) fn after_start_tag(tag: &Tag) -> TokenSinkResult<()> {
+    match tag.name.as_ref() {
+        _ if tag.self_closing => TokenSinkResult::Continue,
+        "script" => TokenSinkResult::RawData(RawKind::ScriptData),
+        "style" | "xmp" | "iframe" | "noembed" | "noframes" => {
+            TokenSinkResult::RawData(RawKind::Rawtext)
+        }
+        "title" | "textarea" => TokenSinkResult::RawData(RawKind::Rcdata),
+        "plaintext" => TokenSinkResult::Plaintext,
+        _ => TokenSinkResult::Continue,
+    }
+}
+
+impl TokenSink for Validator {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, line_number: u64) -> TokenSinkResult<()> {
+        match token {
+            Token::TagToken(tag) if tag.kind == TagKind::StartTag => {
+                self.start_tag(&tag, line_number);
+                after_start_tag(&tag)
+            }
+            Token::TagToken(tag) => {
+                self.end_tag(&tag, line_number);
+                TokenSinkResult::Continue
+            }
+            Token::EOFToken => {
+                for element in std::mem::take(&mut self.open_elements) {
+                    if !OPTIONAL_END_TAG_ELEMENTS.contains(&element.name.as_str()) {
+                        let message = format!(
+                            "<{}> opened on line {} is never closed",
+                            element.name, element.line
+                        );
+                        self.report(HtmlProblemKind::UnclosedElement, element.line, message);
+                    }
+                }
+                TokenSinkResult::Continue
+            }
+            _ => TokenSinkResult::Continue,
+        }
+    }
+}
+
+/// Returns the structural problems of the given HTML document, in the order they appear
+pub fn validate(html: &str) -> Vec<HtmlProblem> {
+    let mut input = BufferQueue::default();
+    input.push_back(StrTendril::from_slice(html));
+    let mut tokenizer = Tokenizer::new(Validator::default(), TokenizerOpts::default());
+    let _ = tokenizer.feed(&mut input);
+    tokenizer.end();
+    let mut problems = tokenizer.sink.problems;
+    problems.sort_by_key(|p| p.line);
+    problems
+}
+
+/// Renders every page and section in memory, for the checks of `zola check` over the generated
+/// HTML. Returns the HTML with its content file.
+pub fn render_site_html(site: &Site) -> Result<Vec<(PathBuf, String)>> {
+    let library = site.library.read().unwrap();
+    let mut rendered: Vec<(PathBuf, String)> = library
+        .pages
+        .par_iter()
+        .filter(|(_, page)| page.meta.render)
+        .map(|(path, page)| {
+            Ok((path.clone(), page.render_html(&site.tera, &site.config, &site.data, &library)?))
+        })
+        .collect::<Result<_>>()?;
+
+    let sections = library
+        .sections
+        .par_iter()
+        .filter(|(_, section)| section.meta.render && section.meta.redirect_to.is_none())
+        .map(|(path, section)| {
+            let html = if section.meta.is_paginated() {
+                // All the pagers use the same template, the first one is enough
+                let paginator = Paginator::from_section(section, &library);
+                paginator.render_pager(
+                    &paginator.pagers[0],
+                    &site.config,
+                    &site.data,
+                    &site.tera,
+                    &library,
+                )?
+            } else {
+                section.render_html(&site.tera, &site.config, &site.data, &library)?
+            };
+            Ok((path.clone(), html))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    rendered.extend(sections);
+    Ok(rendered)
+}
+
+/// Renders every page and section in memory and validates their HTML, for `zola check`.
+/// Returns the content file of each problem.
+pub fn check_site_html(site: &Site) -> Result<Vec<(PathBuf, HtmlProblem)>> {
+    let problems = render_site_html(site)?
+        .into_par_iter()
+        .flat_map_iter(|(path, html)| validate(&html).into_iter().map(move |p| (path.clone(), p)))
+        .collect();
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(html: &str) -> Vec<HtmlProblemKind> {
+        validate(html).into_iter().map(|p| p.kind).collect()
+    }
+
+    #[test]
+    fn accepts_valid_html() {
+        let html = r#"<!DOCTYPE html>
+<html>
+<head><title>A <title></title><style>p > a { color: red; }</style></head>
+<body>
+<p>Hello <a href="/">world</a><br><img src="a.png" alt="A">
+<p>Implicitly closed paragraphs
+<ul><li>One<li>Two</ul>
+<svg><path d="M0 0"/></svg>
+<script>if (1 < 2) { document.write("<div>"); }</script>
+</body>
+</html>"#;
+        assert_eq!(validate(html), vec![]);
+    }
+
+    #[test]
+    fn finds_unclosed_elements() {
+        let problems = validate("<div>\n<span>Hello\n</div>\n<section>");
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].kind, HtmlProblemKind::UnclosedElement);
+        assert_eq!(problems[0].line, 2);
+        assert_eq!(problems[0].message, "<span> opened on line 2 is not closed before line 3");
+        assert_eq!(problems[1].message, "<section> opened on line 4 is never closed");
+
+        assert_eq!(kinds("<div></span></div>"), vec![HtmlProblemKind::UnclosedElement]);
+    }
+
+    #[test]
+    fn finds_duplicate_ids() {
+        let problems =
+            validate("<h1 id=\"intro\">A</h1>\n<h2 id=\"intro\">B</h2><h2 id=\"other\">C</h2>");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].kind, HtmlProblemKind::DuplicateId);
+        assert_eq!(problems[0].message, "the id `intro` on line 2 is already used on line 1");
+    }
+
+    #[test]
+    fn finds_links_without_href_and_images_without_alt() {
+        assert_eq!(
+            kinds("<a>Nowhere</a><a href=\"#\">Here</a><img src=\"a.png\"><img src=\"b.png\" alt=\"\">"),
+            vec![HtmlProblemKind::LinkWithoutHref, HtmlProblemKind::ImageWithoutAlt]
+        );
+    }
+
+    #[test]
+    fn finds_block_elements_inside_paragraphs() {
+        let problems = validate("<p>Some text\n<div>A div</div></p>");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].kind, HtmlProblemKind::InvalidNesting);
+        assert_eq!(problems[0].message, "<div> on line 2 is inside the <p> opened on line 1");
+        assert_eq!(
+            kinds("<p><span><ul><li>A</li></ul></span></p>"),
+            vec![HtmlProblemKind::InvalidNesting]
+        );
+        // Without closing tag, the browsers close the `<p>` at the block element as intended
+        assert_eq!(validate("<p>Some text<div>A div</div>"), vec![]);
+    }
+}
@@ -1,33 +1,82 @@
+//! Loading and rendering of a Zola site.
+//!
+//! [`SiteBuilder`] is the entry point for programs embedding Zola: it creates a [`Site`] which
+//! can then be loaded with [`Site::load`], written with [`Site::build`] and updated after a
+//! change in its directory with [`Site::rebuild`]. The loaded content is available through
+//! [`Site::content`].
+
+pub mod a11y;
+mod aggregates;
+pub mod build_hash;
+mod build_summary;
+mod builder;
+mod content_spill;
 pub mod feeds;
+mod git;
+pub mod hashed_assets;
+pub mod html_validation;
 pub mod link_checking;
+pub mod manifest;
 mod minify;
+pub mod orphans;
+pub mod redirects;
+pub mod relative_urls;
 pub mod sass;
 pub mod sitemap;
 pub mod tpls;
 
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use libs::once_cell::sync::Lazy;
 use libs::rayon::prelude::*;
-use libs::tera::{Context, Tera};
+use libs::tera::{Context, Filter, Function, Map, Tera, Value};
 use libs::walkdir::{DirEntry, WalkDir};
 
-use config::{get_config, Config, IndexFormat};
-use content::{Library, Page, Paginator, Section, Taxonomy};
-use errors::{anyhow, bail, Result};
+use config::extra_schema::ExtraSchema;
+use config::{
+    get_config, BuildInfo, Config, HighlightingMode, HtmlValidationLevel, IndexFormat,
+    LinkCheckerLevel, OutputFormat, RedirectOutput,
+};
+use console::{Diagnostic, Severity};
+use content::{
+    Archive, ContentStats, Library, Page, Paginator, Section, SiteIndex, SortBy, Taxonomy,
+};
+use errors::{anyhow, bail, Context as ErrorContext, Error, FileErrors, LocatedError, Result};
+use hashed_assets::AssetManifest;
+use html_validation::HtmlProblem;
 use libs::relative_path::RelativePathBuf;
 use std::time::Instant;
-use templates::{load_tera, render_redirect_template};
+use templates::global_fns::{self, ContentMiss, InvalidDate};
+use templates::{
+    load_extra_templates, load_tera, make_templates_strict, reload_template,
+    render_redirect_template,
+};
+use utils::data::load_data_dir;
 use utils::fs::{
-    clean_site_output_folder, copy_directory, copy_file_if_needed, create_directory, create_file,
+    clean_site_output_folder, copy_directory, copy_file, copy_file_if_needed, create_directory,
+    create_file, is_path_in_directory, read_file, set_modification_times, walk_dir_following_links,
+    CopyMode, CopySummary,
 };
+use utils::globs::IgnoreRules;
 use utils::net::{get_available_port, is_external_link};
-use utils::templates::{render_template, ShortcodeDefinition};
+use utils::templates::{check_template_fallbacks, render_template, ShortcodeDefinition};
 use utils::types::InsertAnchor;
 
+use crate::aggregates::AggregateFingerprints;
+pub use crate::build_summary::{BuildSummary, PageTiming};
+pub use crate::builder::SiteBuilder;
+use crate::content_spill::ContentSpill;
+pub use crate::manifest::ManifestEntry;
+use crate::redirects::RedirectRule;
+use crate::tpls::CustomTeraFns;
+
 pub static SITE_CONTENT: Lazy<Arc<RwLock<HashMap<RelativePathBuf, String>>>> =
     Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
 
@@ -38,6 +87,80 @@ pub enum BuildMode {
     Disk,
     /// In memory for the content -> `zola serve`
     Memory,
+    /// In memory for the content and in a scratch directory removed after the build for the
+    /// rest -> `zola build --if-changed`, to know the build hash without writing the output
+    DryRun,
+}
+
+/// The size of the site after loading or building it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiteStats {
+    /// The number of pages, in all languages
+    pub pages: usize,
+    /// How many of those pages are orphans: not part of any section
+    pub orphan_pages: usize,
+    /// The number of sections, not counting the index sections
+    pub sections: usize,
+    /// The drafts, future and unlisted pages, the pages without description and the word count
+    pub content: ContentStats,
+    /// How long the loading or building took
+    pub duration: Duration,
+    /// The files written by the last build with their source, when `generate_manifest` is on
+    pub manifest: Vec<ManifestEntry>,
+    /// The hash of everything the last build output, see [`build_hash`]. `None` in `zola serve`
+    pub build_hash: Option<String>,
+}
+
+/// A file or directory of the site that changed, for [`Site::rebuild`].
+/// The paths are absolute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A file in the `content` directory was added, modified or removed
+    Content(PathBuf),
+    /// A template, including shortcodes
+    Template(PathBuf),
+    /// A file in the `sass` directory
+    Sass(PathBuf),
+    /// A file or directory in the `static` directory
+    Static(PathBuf),
+    /// A file in the `data` directory
+    Data,
+    /// Anything in the theme
+    Theme,
+    /// The config file
+    Config,
+}
+
+/// A read-only view of the pages and sections of a loaded site, see [`Site::content`].
+/// The site can't be rebuilt while it is alive.
+pub struct SiteContent<'a> {
+    library: RwLockReadGuard<'a, Library>,
+}
+
+impl<'a> SiteContent<'a> {
+    /// All the pages, in all languages, sorted by file path
+    pub fn pages(&self) -> impl Iterator<Item = &Page> {
+        let mut pages: Vec<_> = self.library.pages.values().collect();
+        pages.sort_unstable_by(|a, b| a.file.path.cmp(&b.file.path));
+        pages.into_iter()
+    }
+
+    /// All the sections, in all languages, sorted by file path
+    pub fn sections(&self) -> impl Iterator<Item = &Section> {
+        let mut sections: Vec<_> = self.library.sections.values().collect();
+        sections.sort_unstable_by(|a, b| a.file.path.cmp(&b.file.path));
+        sections.into_iter()
+    }
+
+    /// The page of that Markdown file, if there is one
+    pub fn page(&self, path: &Path) -> Option<&Page> {
+        self.library.pages.get(path)
+    }
+
+    /// The section of that `_index.md` file, if there is one
+    pub fn section(&self, path: &Path) -> Option<&Section> {
+        self.library.sections.get(path)
+    }
 }
 
 #[derive(Debug)]
@@ -56,15 +179,72 @@ pub struct Site {
     pub static_path: PathBuf,
     pub templates_path: PathBuf,
     pub taxonomies: Vec<Taxonomy>,
+    /// The archive pages of the `archives` config, filled when loading the site
+    pub archives: Vec<Archive>,
+    /// The redirects of the rules files of `redirect_output`, filled when loading the site
+    pub redirects: Vec<RedirectRule>,
     /// A map of all .md files (section and pages) and their permalink
     /// We need that if there are relative links in the content that need to be resolved
-    pub permalinks: HashMap<String, String>,
+    pub permalinks: Arc<HashMap<String, Arc<str>>>,
     /// Contains all pages and sections of the site
     pub library: Arc<RwLock<Library>>,
+    /// The content of the `data` directory, loaded with the site and available as `data` in
+    /// every template and shortcode
+    pub data: Arc<Value>,
     /// Whether to load draft pages
     include_drafts: bool,
+    /// Whether to load the pages dated in the future in every section
+    include_future: bool,
+    /// Whether to make the internal URLs relative so the site can be browsed from the filesystem
+    relative_urls: bool,
     build_mode: BuildMode,
     shortcode_definitions: HashMap<String, ShortcodeDefinition>,
+    /// The content-hashed paths of the assets matching `hash_static_assets`, filled by `build`
+    pub asset_manifest: Arc<RwLock<AssetManifest>>,
+    /// The paths of the `.zolaignore` file and of `ignore_patterns`, skipped when loading the
+    /// content and copying the static files
+    pub ignore_rules: IgnoreRules,
+    /// The full path of the config file, to reload it on change
+    config_file: PathBuf,
+    /// The base URL given to `set_base_url`, kept when reloading the config
+    base_url_override: Option<String>,
+    /// The version of Zola given to `set_version`, for `build_info.version`
+    version: String,
+    /// The Tera functions and filters added with `register_function` and `register_filter`
+    tera_fns: CustomTeraFns,
+    /// The expected shape of the `extra` of the config, pages and sections, if there is one
+    extra_schema: Option<ExtraSchema>,
+    /// The only part of the `content` directory to load, set by `zola serve --only`
+    only_content: Option<PathBuf>,
+    /// The problems found in the rendered HTML when `validate_html` is on, with their content
+    /// file. Reported and emptied after rendering
+    html_problems: Mutex<Vec<(PathBuf, HtmlProblem)>>,
+    /// What was rendered since the site was loaded or since the last partial re-render
+    build_summary: Mutex<BuildSummary>,
+    /// A copy of the index of the library for `get_site_index`, which can be called from
+    /// shortcodes while the library is locked
+    site_index: Arc<RwLock<SiteIndex>>,
+    /// The pages and sections the templates asked for that don't exist, when `missing_content`
+    /// is `warn`. Reported and emptied at the end of the build
+    content_misses: Arc<Mutex<Vec<ContentMiss>>>,
+    /// The front matter values `group_pages_by_date` couldn't read as dates. Reported and
+    /// emptied at the end of the build and of `zola check`
+    invalid_dates: Arc<Mutex<BTreeSet<InvalidDate>>>,
+    /// The files written by the last build, when `generate_manifest` is on
+    manifest: Mutex<Vec<ManifestEntry>>,
+    /// What a dry run rendered, by path relative to the output directory
+    dry_run_content: Mutex<BTreeMap<String, String>>,
+    /// The hash of the output of the last build
+    build_hash: Mutex<Option<String>>,
+    /// The fingerprints of the sitemap, feeds and search index last rendered in `zola serve`
+    aggregate_fingerprints: AggregateFingerprints,
+    /// Where the rendered HTML of the pages is moved to in `low_memory` builds
+    content_spill: Option<ContentSpill>,
+    /// Whether to record how long each page takes to render in the build summary
+    timings: bool,
+    /// The seed of the randomness of the templates, like `get_random_page`. It is the same for
+    /// every render of a build so the pages picked are the ones linked to everywhere
+    pub build_seed: u64,
 }
 
 impl Site {
@@ -81,6 +261,8 @@ impl Site {
         }
 
         let tera = load_tera(path, &config)?;
+        let ignore_rules = IgnoreRules::new(path, &config.ignore_patterns)?;
+        let extra_schema = ExtraSchema::load(path, config.theme.as_deref())?;
         let shortcode_definitions = utils::templates::get_shortcodes(&tera);
 
         let content_path = path.join("content");
@@ -91,6 +273,28 @@ impl Site {
         let output_path = path.join(config.output_dir.clone());
 
         let site = Site {
+            config_file: path.join(config_file),
+            ignore_rules,
+            base_url_override: None,
+            version: String::new(),
+            tera_fns: CustomTeraFns::default(),
+            extra_schema,
+            only_content: None,
+            html_problems: Mutex::new(Vec::new()),
+            build_summary: Mutex::new(BuildSummary::default()),
+            site_index: Arc::new(RwLock::new(SiteIndex::default())),
+            content_misses: Arc::new(Mutex::new(Vec::new())),
+            invalid_dates: Arc::new(Mutex::new(BTreeSet::new())),
+            manifest: Mutex::new(Vec::new()),
+            dry_run_content: Mutex::new(BTreeMap::new()),
+            build_hash: Mutex::new(None),
+            aggregate_fingerprints: AggregateFingerprints::default(),
+            content_spill: None,
+            timings: false,
+            build_seed: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default(),
             base_path: path.to_path_buf(),
             config,
             tera,
@@ -102,17 +306,52 @@ impl Site {
             static_path,
             templates_path,
             taxonomies: Vec::new(),
-            permalinks: HashMap::new(),
+            archives: Vec::new(),
+            redirects: Vec::new(),
+            permalinks: Arc::new(HashMap::new()),
             include_drafts: false,
+            include_future: false,
+            relative_urls: false,
             // We will allocate it properly later on
             library: Arc::new(RwLock::new(Library::default())),
+            data: Arc::new(Value::Object(Map::new())),
             build_mode: BuildMode::Disk,
             shortcode_definitions,
+            asset_manifest: Arc::new(RwLock::new(AssetManifest::new())),
         };
 
         Ok(site)
     }
 
+    /// The paths of the sitemap and of the feeds of the site and of the sections, relative to
+    /// the output directory, for the pages and sections not to be written there too
+    fn generated_files(&self, library: &Library) -> Vec<String> {
+        let mut files = Vec::new();
+        if self.config.generate_sitemap {
+            files.push(self.config.sitemap_filename.clone());
+        }
+        for (lang, options) in &self.config.languages {
+            if !options.generate_feeds {
+                continue;
+            }
+            for feed_filename in &options.feed_filenames {
+                let output = self.config.feed_output_filename(lang, feed_filename);
+                if lang == &self.config.default_language {
+                    files.push(output.to_string());
+                } else {
+                    files.push(format!("{}/{}", lang, output));
+                }
+            }
+        }
+        for section in library.sections.values().filter(|s| s.meta.generate_feeds) {
+            for feed_filename in &self.config.languages[&section.lang].feed_filenames {
+                let output = self.config.feed_output_filename(&section.lang, feed_filename);
+                files.push(format!("{}{}", section.path.trim_start_matches('/'), output));
+            }
+        }
+        files
+    }
+
     /// Enable some `zola serve` related options
     pub fn enable_serve_mode(&mut self) {
         SITE_CONTENT.write().unwrap().clear();
@@ -120,12 +359,123 @@ impl Site {
         self.build_mode = BuildMode::Memory;
     }
 
+    /// Renders the site without writing it to the output directory, only to get its build hash.
+    /// The files Zola doesn't render itself, like the static ones, go to a scratch directory
+    /// removed by `write_dry_run` or `discard_dry_run`.
+    /// Needs to be called before loading it
+    pub fn enable_dry_run(&mut self) {
+        static DRY_RUNS: AtomicUsize = AtomicUsize::new(0);
+        let id = DRY_RUNS.fetch_add(1, Ordering::Relaxed);
+        self.output_path =
+            std::env::temp_dir().join(format!("zola-dry-run-{}-{}", std::process::id(), id));
+        self.build_mode = BuildMode::DryRun;
+    }
+
+    /// Set the site to use relative URLs in the HTML files so it can be browsed offline
+    /// from the filesystem.
+    pub fn enable_relative_urls(&mut self) {
+        self.relative_urls = true;
+        // The assets need to be in the output directory too
+        self.config.asset_base_url = None;
+        let mut imageproc =
+            self.imageproc.lock().expect("Couldn't lock imageproc (enable_relative_urls)");
+        imageproc.set_base_url(&self.config);
+    }
+
+    /// Records how long the Markdown and the templates of each page take to render in the
+    /// build summary, for `zola build --timings`. Only a couple of `Instant::now` per page
+    pub fn enable_timings(&mut self) {
+        self.timings = true;
+    }
+
+    /// Set the version of Zola shown to the templates in `build_info.version`.
+    /// Needs to be called before loading the site
+    pub fn set_version(&mut self, version: &str) {
+        self.version = version.to_string();
+    }
+
     /// Set the site to load the drafts.
     /// Needs to be called before loading it
     pub fn include_drafts(&mut self) {
         self.include_drafts = true;
     }
 
+    /// Set the site to load the pages dated in the future, which are otherwise only loaded in the
+    /// sections with `include_future = true`.
+    /// Needs to be called before loading it
+    pub fn include_future(&mut self) {
+        self.include_future = true;
+    }
+
+    /// Whether the page is left out of the site: the drafts and the pages dated in the future,
+    /// unless they are included
+    fn is_excluded(&self, page: &Page) -> bool {
+        if page.meta.draft && !self.include_drafts {
+            return true;
+        }
+        if !page.future || self.include_future {
+            return false;
+        }
+        let section = self.parent_section_file(&page.file.parent, &page.lang);
+        !self.library.read().unwrap().sections.get(&section).is_some_and(|s| s.meta.include_future)
+    }
+
+    /// Gives the pages without a weight in a section sorted by weight the one in their filename,
+    /// if any, see `Page::set_weight_from_filename`
+    fn set_weight_from_filename(&self, page: &mut Page) {
+        let section = self.parent_section_file(&page.file.parent, &page.lang);
+        let sorted_by_weight = self
+            .library
+            .read()
+            .unwrap()
+            .sections
+            .get(&section)
+            .is_some_and(|s| s.meta.sort_by == SortBy::Weight);
+        if sorted_by_weight {
+            page.set_weight_from_filename(&self.config);
+        }
+    }
+
+    /// Only loads the content under `path`, eg `docs` or `content/docs`, and the sections above
+    /// it. As the rest of the content is missing, links to it only give a warning.
+    /// Needs to be called before loading the site
+    pub fn load_only(&mut self, path: &Path) -> Result<()> {
+        let path = path.strip_prefix("content").unwrap_or(path);
+        if !self.content_path.join(path).exists() {
+            bail!(
+                "Can't only load `{}`: it doesn't exist in the content directory",
+                path.display()
+            );
+        }
+        self.only_content = Some(path.to_path_buf());
+        self.config.link_checker.internal_level = LinkCheckerLevel::Warn;
+        Ok(())
+    }
+
+    /// Whether the content file or directory at `path` is loaded, which is always the case
+    /// unless `load_only` was used: then it needs to be in the part to load or to be one of the
+    /// sections above it
+    pub fn is_content_loaded(&self, path: &Path) -> bool {
+        let only = match &self.only_content {
+            Some(only) => only,
+            None => return true,
+        };
+        let relative = path.strip_prefix(&self.content_path).unwrap_or(path);
+        if relative.starts_with(only) {
+            return true;
+        }
+
+        let is_section_file = relative
+            .file_name()
+            .map(|f| f.to_string_lossy().starts_with("_index."))
+            .unwrap_or(false);
+        if is_section_file {
+            relative.parent().map(|p| only.starts_with(p)).unwrap_or(false)
+        } else {
+            path.is_dir() && only.starts_with(relative)
+        }
+    }
+
     /// The index sections are ALWAYS at those paths
     /// There are one index section for the default language + 1 per language
     fn index_section_paths(&self) -> Vec<(PathBuf, Option<&str>)> {
@@ -148,14 +498,234 @@ impl Site {
         self.live_reload = Some(live_reload_port);
     }
 
-    /// Reloads the templates and rebuild the site without re-markdown the Markdown.
-    pub fn reload_templates(&mut self) -> Result<()> {
+    /// Reloads the given templates after they were modified, created or deleted and renders
+    /// again what uses them, without rendering the Markdown, compiling the Sass or copying the
+    /// static files. Only these templates are parsed again unless one of them is a directory,
+    /// and the Tera functions and filters stay registered.
+    /// Outside of `zola serve`, or when all the templates had to be loaded again, the whole site
+    /// is rendered again.
+    pub fn reload_templates(&mut self, paths: &[&Path]) -> Result<SiteStats> {
+        let start = Instant::now();
+        *self.build_summary.lock().unwrap() = BuildSummary::default();
+        let mut reloaded = true;
+        for path in paths {
+            if !reload_template(&mut self.tera, &self.templates_path, &self.config.theme, path)? {
+                reloaded = false;
+                break;
+            }
+        }
+        if !reloaded {
+            self.tera.full_reload()?;
+        }
+        self.finish_loading_templates()?;
+        if !reloaded || self.build_mode != BuildMode::Memory {
+            return self.build();
+        }
+
+        let changed: BTreeSet<String> = paths
+            .iter()
+            .filter_map(|p| p.strip_prefix(&self.templates_path).ok())
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .collect();
+        let uses_changed = |names: &[&str]| {
+            !aggregates::used_templates(&self.tera, &self.config.theme, names).is_disjoint(&changed)
+        };
+        let feed_templates = |lang: &str| -> Vec<&str> {
+            self.config.languages[lang].feed_filenames.iter().map(|f| f.as_str()).collect()
+        };
+
+        self.html_problems.lock().unwrap().clear();
+        {
+            let library = self.library.read().unwrap();
+            let pages: Vec<&Page> = library
+                .pages
+                .values()
+                .filter(|page| {
+                    let mut names = vec![page.meta.template.as_deref().unwrap_or("page.html")];
+                    if page.password.is_some() {
+                        names.push("protected.html");
+                    }
+                    let formats = self.page_output_formats(page, &library);
+                    names.extend(formats.iter().map(|f| f.template.as_str()));
+                    uses_changed(&names)
+                })
+                .collect();
+            let sections: Vec<&Section> = library
+                .sections
+                .values()
+                .filter(|section| {
+                    let mut names = vec![section.get_template_name()];
+                    if section.meta.generate_feeds {
+                        names.extend(feed_templates(&section.lang));
+                    }
+                    uses_changed(&names)
+                })
+                .collect();
+            pages.par_iter().map(|page| self.render_page(page)).collect::<Result<()>>()?;
+            sections
+                .par_iter()
+                .map(|section| self.render_section(section, false))
+                .collect::<Result<()>>()?;
+        }
+        self.report_html_problems()?;
+        // They only render again if their templates changed
+        self.render_aggregates()?;
+
+        if uses_changed(&["404.html"]) {
+            self.render_404()?;
+        }
+        if check_template_fallbacks("50x.html", &self.tera, &self.config.theme).is_some()
+            && uses_changed(&["50x.html"])
+        {
+            self.render_50x()?;
+        }
+        if self.config.generate_robots_txt && uses_changed(&["robots.txt"]) {
+            self.render_robots()?;
+            self.build_summary.lock().unwrap().add_aggregate("robots.txt");
+        }
+        for taxonomy in self.taxonomies.iter().filter(|t| t.kind.render) {
+            let single = format!("{}/single.html", taxonomy.kind.name);
+            let list = format!("{}/list.html", taxonomy.kind.name);
+            let mut names =
+                vec![single.as_str(), "taxonomy_single.html", &list, "taxonomy_list.html"];
+            if taxonomy.kind.feed {
+                names.extend(feed_templates(&taxonomy.lang));
+            }
+            if uses_changed(&names) {
+                self.render_taxonomy(taxonomy)?;
+                self.build_summary.lock().unwrap().add_aggregate("taxonomies");
+            }
+        }
+        let library = self.library.read().unwrap();
+        for archive in &self.archives {
+            if uses_changed(&[&archive.kind.template, &archive.kind.index_template]) {
+                self.render_archive(archive, &library, |_| true)?;
+                self.build_summary.lock().unwrap().add_aggregate("archives");
+            }
+        }
+        drop(library);
+        self.log_time(start, "Rendered what uses the templates");
+        Ok(self.stats(start))
+    }
+
+    /// Reloads the templates after some shortcodes changed and renders again the markdown and
+    /// the HTML of the pages and sections using them.
+    /// The whole site is loaded again if shortcodes were added or removed, as content that
+    /// didn't use them before might now and the other way around.
+    pub fn reload_shortcodes(&mut self, paths: &[&Path]) -> Result<SiteStats> {
+        let start = Instant::now();
+        *self.build_summary.lock().unwrap() = BuildSummary::default();
         self.tera.full_reload()?;
-        // TODO: be smarter than that, no need to recompile sass for example
-        self.build()
+        self.finish_loading_templates()?;
+        let definitions = utils::templates::get_shortcodes(&self.tera);
+        let same_shortcodes = definitions.len() == self.shortcode_definitions.len()
+            && definitions.iter().all(|(name, def)| {
+                self.shortcode_definitions.get(name).is_some_and(|d| d.tera_name == def.tera_name)
+            });
+        if !same_shortcodes {
+            self.reload_config()?;
+            self.load()?;
+            return self.build();
+        }
+        // The `markdown` filter has its own copy of the templates for the shortcodes
+        tpls::register_early_global_fns(self)?;
+
+        let shortcodes_path = self.templates_path.join("shortcodes");
+        let names: Vec<String> = paths
+            .iter()
+            .filter_map(|p| p.strip_prefix(&shortcodes_path).ok())
+            .map(|p| {
+                let name = p.with_extension("");
+                name.components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .collect();
+        let (pages, sections): (Vec<PathBuf>, Vec<PathBuf>) = {
+            let library = self.library.read().unwrap();
+            let usage = library.shortcode_usage();
+            let mut paths: Vec<PathBuf> = names
+                .iter()
+                .filter_map(|name| usage.get(name.as_str()))
+                .flatten()
+                .map(|p| p.to_path_buf())
+                .collect();
+            paths.sort();
+            paths.dedup();
+            paths.into_iter().partition(|p| library.pages.contains_key(p))
+        };
+
+        let insert_anchors: HashMap<PathBuf, InsertAnchor> = {
+            let library = self.library.read().unwrap();
+            pages
+                .iter()
+                .map(|path| {
+                    let page = &library.pages[path];
+                    let anchor =
+                        self.find_parent_section_insert_anchor(&page.file.parent, &page.lang);
+                    (path.clone(), anchor)
+                })
+                .collect()
+        };
+        {
+            let mut library = self.library.write().expect("Get lock for reload_shortcodes");
+            for path in &pages {
+                library.pages.get_mut(path).unwrap().render_markdown(
+                    &self.permalinks,
+                    &self.tera,
+                    &self.config,
+                    &self.data,
+                    insert_anchors[path],
+                    &self.shortcode_definitions,
+                )?;
+            }
+            for path in &sections {
+                library.sections.get_mut(path).unwrap().render_markdown(
+                    &self.permalinks,
+                    &self.tera,
+                    &self.config,
+                    &self.data,
+                    &self.shortcode_definitions,
+                )?;
+            }
+        }
+
+        if self.build_mode == BuildMode::Memory {
+            self.library.write().unwrap().update_fingerprints(pages.iter().chain(&sections));
+        }
+
+        let library = self.library.read().unwrap();
+        for path in &pages {
+            self.render_page(&library.pages[path])?;
+        }
+        for path in &sections {
+            self.render_section(&library.sections[path], false)?;
+        }
+        drop(library);
+        self.report_html_problems()?;
+        self.log_time(start, "Rendered content using the shortcodes");
+        Ok(self.stats(start))
+    }
+
+    /// Adds a function to the templates and shortcodes, overriding the built-in one if there is
+    /// one with the same name.
+    /// Needs to be called before loading the site to be available in shortcodes.
+    pub fn register_function<F: Function + 'static>(&mut self, name: &str, function: F) {
+        self.tera_fns.functions.push((name.to_string(), Arc::new(function)));
+        self.tera_fns.register(&mut self.tera);
+    }
+
+    /// Adds a filter to the templates and shortcodes, overriding the built-in one if there is
+    /// one with the same name.
+    /// Needs to be called before loading the site to be available in shortcodes.
+    pub fn register_filter<F: Filter + 'static>(&mut self, name: &str, filter: F) {
+        self.tera_fns.filters.push((name.to_string(), Arc::new(filter)));
+        self.tera_fns.register(&mut self.tera);
     }
 
     pub fn set_base_url(&mut self, base_url: String) {
+        self.base_url_override = Some(base_url.clone());
         self.config.base_url = base_url;
         let mut imageproc = self.imageproc.lock().expect("Couldn't lock imageproc (set_base_url)");
         imageproc.set_base_url(&self.config);
@@ -165,17 +735,123 @@ impl Site {
         self.output_path = path.as_ref().to_path_buf();
     }
 
+    /// The pages and sections of the site, once loaded
+    pub fn content(&self) -> SiteContent<'_> {
+        SiteContent { library: self.library.read().unwrap() }
+    }
+
+    fn stats(&self, start: Instant) -> SiteStats {
+        let library = self.library.read().unwrap();
+        SiteStats {
+            pages: library.pages.len(),
+            orphan_pages: library.get_all_orphan_pages().len(),
+            sections: library.sections.values().filter(|s| !s.is_index()).count(),
+            content: library.site_index.stats,
+            duration: start.elapsed(),
+            manifest: self.manifest.lock().unwrap().clone(),
+            build_hash: self.build_hash.lock().unwrap().clone(),
+        }
+    }
+
+    /// What was rendered since the site was loaded or since the last partial re-render
+    /// (`rebuild`, `reload_templates`, `add_and_render_page` and `add_and_render_section`)
+    pub fn build_summary(&self) -> BuildSummary {
+        self.build_summary.lock().unwrap().clone().sorted()
+    }
+
+    /// Records how long a step took since `start` in the build summary, printing it if the
+    /// `ZOLA_PERF_LOG` env variable is set
+    fn log_time(&self, start: Instant, message: &str) -> Instant {
+        let now = Instant::now();
+        let duration = now.duration_since(start);
+        if std::env::var("ZOLA_PERF_LOG").is_ok() {
+            console::plain(&format!("{} took {}ms", message, duration.as_millis()));
+        }
+        self.build_summary.lock().unwrap().add_phase(message, duration);
+        now
+    }
+
+    /// The fingerprint of an aggregate made out of what `hash` hashes. Only computed in
+    /// `zola serve`, where the output is kept between builds so unchanged aggregates are skipped
+    fn aggregate_fingerprint(&self, hash: impl FnOnce(&mut DefaultHasher)) -> Option<u64> {
+        if self.build_mode != BuildMode::Memory {
+            return None;
+        }
+        let mut hasher = DefaultHasher::new();
+        hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    /// Whether the aggregate stored as `key` was already rendered with that fingerprint, in which
+    /// case it is listed as unchanged under `name` in the build summary
+    fn is_aggregate_unchanged(
+        &self,
+        key: &str,
+        name: &'static str,
+        fingerprint: Option<u64>,
+    ) -> bool {
+        let unchanged =
+            fingerprint.is_some_and(|f| self.aggregate_fingerprints.is_unchanged(key, f));
+        if unchanged {
+            self.build_summary.lock().unwrap().add_unchanged_aggregate(name);
+        }
+        unchanged
+    }
+
+    fn record_aggregate(&self, key: &str, fingerprint: Option<u64>) {
+        if let Some(fingerprint) = fingerprint {
+            self.aggregate_fingerprints.record(key, fingerprint);
+        }
+    }
+
     /// Reads all .md files in the `content` directory and create pages/sections
     /// out of them
-    pub fn load(&mut self) -> Result<()> {
+    pub fn load(&mut self) -> Result<SiteStats> {
+        let load_start = Instant::now();
+        *self.build_summary.lock().unwrap() = BuildSummary::default();
+        self.manifest.lock().unwrap().clear();
+        self.data = Arc::new(load_data_dir(&self.base_path.join("data"))?);
+        let source_date_epoch = std::env::var("SOURCE_DATE_EPOCH").ok();
+        let build_timestamp = match source_date_epoch {
+            None if self.config.reproducible => {
+                match git::get_last_commit_timestamp(&self.base_path) {
+                    Some(timestamp) => timestamp,
+                    None => {
+                        console::warn(
+                            "`reproducible` is set but `SOURCE_DATE_EPOCH` is not and the site is not in a git repository: the build time is now, the output will differ between builds.",
+                        );
+                        get_build_timestamp(None)?
+                    }
+                }
+            }
+            _ => get_build_timestamp(source_date_epoch.as_deref())?,
+        };
+        let reproducible = self.config.reproducible || source_date_epoch.is_some();
+        // A fixed build time means the same random picks as well
+        if reproducible {
+            self.build_seed = build_timestamp as u64;
+        }
+        self.config.build_info =
+            BuildInfo::new(&self.version, build_timestamp, git::get_short_hash(&self.base_path));
+        self.config.build_info.reproducible = reproducible;
         self.library = Arc::new(RwLock::new(Library::new(&self.config)));
         let mut pages_insert_anchors = HashMap::new();
 
         // not the most elegant loop, but this is necessary to use skip_current_dir
         // which we can only decide to use after we've deserialised the section
         // so it's kinda necessecary
-        let mut dir_walker =
-            WalkDir::new(self.base_path.join("content")).follow_links(true).into_iter();
+        let mut dir_walker = walk_dir_following_links(WalkDir::new(&self.content_path));
+        // Binary files that happen to end in `.md` are skipped rather than failing the build
+        let skip_binary_file = |path: &Path| {
+            let is_binary = utils::fs::is_binary_file(path);
+            if is_binary {
+                console::warn(&format!(
+                    "Skipping {}: it looks like a binary file rather than markdown",
+                    path.display()
+                ));
+            }
+            is_binary
+        };
         let mut allowed_index_filenames: Vec<_> = self
             .config
             .other_languages()
@@ -189,6 +865,8 @@ impl Site {
         // when there is both a _index.md and index.md in the same folder
         let mut pages = Vec::new();
         let mut sections = HashSet::new();
+        // We keep going after a broken file so all of them can be reported at once
+        let mut errors = FileErrors::default();
 
         loop {
             let entry: DirEntry = match dir_walker.next() {
@@ -202,15 +880,30 @@ impl Site {
                 Some(name) => name.to_str().unwrap(),
             };
 
-            // ignore excluded content
-            match &self.config.ignored_content_globset {
-                Some(gs) => {
-                    if gs.is_match(path) {
-                        continue;
-                    }
+            // ignore excluded content, matching on the path relative to the content directory
+            if entry.depth() > 0
+                && self
+                    .config
+                    .is_ignored_content(path.strip_prefix(&self.content_path).unwrap_or(path))
+            {
+                if path.is_dir() {
+                    dir_walker.skip_current_dir();
+                }
+                continue;
+            }
+
+            if entry.depth() > 0 && self.ignore_rules.is_ignored(path, path.is_dir()) {
+                if path.is_dir() {
+                    dir_walker.skip_current_dir();
                 }
+                continue;
+            }
 
-                None => (),
+            if entry.depth() > 0 && !self.is_content_loaded(path) {
+                if path.is_dir() {
+                    dir_walker.skip_current_dir();
+                }
+                continue;
             }
 
             // we process a section when we encounter the dir
@@ -220,8 +913,10 @@ impl Site {
                 continue;
             }
 
-            // skip hidden files and non md files
-            if !path.is_dir() && (!file_name.ends_with(".md") || file_name.starts_with('.')) {
+            // skip hidden files and the files that aren't Markdown or converted by `[markup.external]`
+            if !path.is_dir()
+                && (!self.config.markup.is_content_file(path) || file_name.starts_with('.'))
+            {
                 continue;
             }
 
@@ -241,6 +936,11 @@ impl Site {
                             // https://github.com/getzola/zola/issues/1244
                             if f.path().is_file()
                                 && allowed_index_filenames.iter().any(|s| s == path_str)
+                                && !self.config.is_ignored_content(
+                                    f.path().strip_prefix(&self.content_path).unwrap_or(f.path()),
+                                )
+                                && !self.ignore_rules.is_ignored(f.path(), false)
+                                && !skip_binary_file(f.path())
                             {
                                 Some(f)
                             } else {
@@ -251,8 +951,17 @@ impl Site {
                     .collect::<Vec<DirEntry>>();
 
                 for index_file in index_files {
-                    let section =
-                        Section::from_file(index_file.path(), &self.config, &self.base_path)?;
+                    let section = match Section::from_file(
+                        index_file.path(),
+                        &self.config,
+                        &self.base_path,
+                    ) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            errors.push(index_file.path(), e);
+                            continue;
+                        }
+                    };
                     sections.insert(section.components.join("/"));
 
                     // if the section is drafted we can skip the entire dir
@@ -261,24 +970,33 @@ impl Site {
                         continue;
                     }
 
-                    self.add_section(section, false)?;
+                    let section_path = section.file.path.clone();
+                    if let Err(e) = self.add_section(section, false) {
+                        errors.push(section_path, e);
+                    }
+                }
+            } else if !skip_binary_file(path) {
+                match Page::from_file(path, &self.config, &self.base_path) {
+                    Ok(page) => pages.push(page),
+                    Err(e) => errors.push(path, e),
                 }
-            } else {
-                let page = Page::from_file(path, &self.config, &self.base_path)?;
-                pages.push(page);
             }
         }
         self.create_default_index_sections()?;
-
-        for page in pages {
-            // should we skip drafts?
-            if page.meta.draft && !self.include_drafts {
+        self.create_implicit_sections(&pages)?;
+
+        // Sorted so the suffixes added by `deduplicate_slugs` don't depend on the filesystem
+        pages.sort_by(|a, b| a.file.path.cmp(&b.file.path));
+        let mut pages_by_path = HashMap::new();
+        for mut page in pages {
+            // should we skip drafts and future pages?
+            if self.is_excluded(&page) {
                 continue;
             }
 
             // We are only checking it on load and not in add_page since we have access to
             // all the components there.
-            if page.file.filename == "index.md" {
+            if Path::new(&page.file.filename).file_stem().is_some_and(|s| s == "index") {
                 let is_invalid = match page.components.last() {
                     Some(_) => sections.contains(&page.components.join("/")),
                     // content/index.md is always invalid, but content/colocated/index.md is ok
@@ -286,26 +1004,52 @@ impl Site {
                 };
 
                 if is_invalid {
-                    bail!("We can't have a page called `index.md` in the same folder as an index section in {:?}", page.file.parent);
+                    errors.push(&page.file.path, anyhow!("We can't have a page called `{}` in the same folder as an index section in {:?}", page.file.filename, page.file.parent));
+                    continue;
                 }
             }
 
+            self.set_weight_from_filename(&mut page);
+            if let Err(e) = self.check_slug_collision(&mut page, &mut pages_by_path) {
+                errors.push(&page.file.path, e);
+                continue;
+            }
+
             pages_insert_anchors.insert(
                 page.file.path.clone(),
                 self.find_parent_section_insert_anchor(&page.file.parent.clone(), &page.lang),
             );
-            self.add_page(page, false)?;
+            let page_path = page.file.path.clone();
+            if let Err(e) = self.add_page(page, false) {
+                errors.push(page_path, e);
+            }
+        }
+        errors.extend(self.validate_extra());
+        errors.into_result()?;
+
+        if self.config.git_metadata {
+            let paths = self.library.read().unwrap().pages.keys().cloned().collect();
+            self.load_git_metadata(paths);
         }
 
         {
             let library = self.library.read().unwrap();
             let collisions = library.find_path_collisions();
-            if !collisions.is_empty() {
+            let generated_collisions =
+                library.find_generated_file_collisions(&self.generated_files(&library));
+            if !collisions.is_empty() || !generated_collisions.is_empty() {
                 let mut msg = String::from("Found path collisions:\n");
                 for (path, filepaths) in collisions {
                     let row = format!("- `{}` from files {:?}\n", path, filepaths);
                     msg.push_str(&row);
                 }
+                for (path, filepaths) in generated_collisions {
+                    let row = format!(
+                        "- `{}` from files {:?} and the feed or sitemap generated there\n",
+                        path, filepaths
+                    );
+                    msg.push_str(&row);
+                }
                 return Err(anyhow!(msg));
             }
         }
@@ -313,58 +1057,344 @@ impl Site {
         // taxonomy Tera fns are loaded in `register_early_global_fns`
         // so we do need to populate it first.
         self.populate_taxonomies()?;
+        self.finish_loading_templates()?;
         tpls::register_early_global_fns(self)?;
         self.populate_sections();
+        for path in self.populate_archives() {
+            console::warn(&format!(
+                "{} is not in the archive of its section as it has no date",
+                path.strip_prefix(&self.base_path).unwrap_or(&path).display()
+            ));
+        }
+        self.populate_redirects()?;
         self.render_markdown()?;
         {
             let mut lib = self.library.write().unwrap();
             lib.fill_backlinks();
         }
+        self.load_mentions()?;
         tpls::register_tera_global_fns(self);
 
         // Needs to be done after rendering markdown as we only get the anchors at that point
         let internal_link_messages = link_checking::check_internal_links_with_anchors(self);
+        self.report_broken_links(
+            "internal anchor",
+            internal_link_messages,
+            &self.config.link_checker.internal_level,
+        )?;
+
+        let self_link_messages = link_checking::check_self_links(self);
+        self.report_broken_links(
+            "self",
+            self_link_messages,
+            &self.config.link_checker.self_links_level,
+        )?;
+
+        // check external links, log the results, and error out if needed
+        if self.config.is_in_check_mode() {
+            let external_link_messages = link_checking::check_external_links(self)?;
+            self.report_broken_links(
+                "external",
+                external_link_messages,
+                &self.config.link_checker.external_level,
+            )?;
+        }
+
+        if self.config.is_in_check_mode() && self.config.validate_html {
+            let problems = html_validation::check_site_html(self)?;
+            self.html_problems.lock().unwrap().extend(problems);
+            self.report_html_problems()?;
+        }
+
+        if self.build_mode == BuildMode::Memory {
+            self.library.write().unwrap().fill_fingerprints();
+        }
+
+        self.log_time(load_start, "Loaded content");
+        Ok(self.stats(load_start))
+    }
+
+    /// Checks the `extra` of the config and of every page and section against the extra schema
+    fn validate_extra(&self) -> FileErrors {
+        let mut errors = FileErrors::default();
+        let schema = match &self.extra_schema {
+            Some(s) => s,
+            None => return errors,
+        };
+        let mut push = |path: &Path, problems: Vec<String>| {
+            if problems.is_empty() {
+                return;
+            }
+            let relative_path = path.strip_prefix(&self.base_path).unwrap_or(path);
+            let mut message = format!(
+                "The `extra` of {} doesn't match the extra schema:",
+                relative_path.display()
+            );
+            for problem in problems {
+                message.push_str(&format!("\n- {}", problem));
+            }
+            let error =
+                LocatedError { kind: "extra", path: path.to_path_buf(), position: None, message };
+            errors.push(path, Error::new(error));
+        };
+
+        let config_extra = self.config.extra_json();
+        push(&self.config_file, schema.validate_config(&config_extra));
+
+        let library = self.library.read().unwrap();
+        for page in library.pages.values() {
+            push(&page.file.path, schema.validate_page(&page.file.components, &page.meta.extra));
+        }
+        for section in library.sections.values() {
+            // The index sections created when there is no `_index.md` have no front matter
+            if !section.file.path.exists() {
+                continue;
+            }
+            push(
+                &section.file.path,
+                schema.validate_section(&section.file.components, &section.meta.extra),
+            );
+        }
+        errors
+    }
+
+    /// Logs the broken links found by the link checker and errors out if needed
+    fn report_broken_links(
+        &self,
+        link_type: &str,
+        broken_links: Vec<(PathBuf, String)>,
+        level: &config::LinkCheckerLevel,
+    ) -> Result<()> {
+        if broken_links.is_empty() {
+            return Ok(());
+        }
+
+        let messages: Vec<String> = broken_links
+            .iter()
+            .enumerate()
+            .map(|(i, (_, msg))| format!("  {}. {}", i + 1, msg))
+            .collect();
+        let msg = format!(
+            "Found {} broken {} link(s)\n{}",
+            messages.len(),
+            link_type,
+            messages.join("\n")
+        );
+        let errors = broken_links
+            .into_iter()
+            .map(|(path, message)| {
+                let error =
+                    LocatedError { kind: "link", path: path.clone(), position: None, message };
+                (path, Error::new(error))
+            })
+            .collect::<FileErrors>()
+            .with_summary(msg.clone());
+
+        match level {
+            config::LinkCheckerLevel::Warn if console::is_json_output() => {
+                for diagnostic in Diagnostic::from_error(Severity::Warning, &Error::new(errors)) {
+                    console::emit(&diagnostic);
+                }
+                Ok(())
+            }
+            config::LinkCheckerLevel::Warn => {
+                console::warn(&msg);
+                Ok(())
+            }
+            config::LinkCheckerLevel::Error => errors.into_result(),
+        }
+    }
 
-        // log any broken internal links and error out if needed
-        if !internal_link_messages.is_empty() {
-            let messages: Vec<String> = internal_link_messages
+    /// Validates the HTML rendered for that content file if `validate_html` is on,
+    /// the problems are reported by `report_html_problems`
+    fn validate_html(&self, path: &Path, html: &str) {
+        if !self.config.validate_html {
+            return;
+        }
+        let problems = html_validation::validate(html);
+        if !problems.is_empty() {
+            let mut html_problems = self.html_problems.lock().unwrap();
+            html_problems.extend(problems.into_iter().map(|p| (path.to_path_buf(), p)));
+        }
+    }
+
+    /// Reports the HTML problems found since the last call, erroring if some of them are at
+    /// the error level of `html_validation`
+    fn report_html_problems(&self) -> Result<()> {
+        let mut problems = std::mem::take(&mut *self.html_problems.lock().unwrap());
+        if problems.is_empty() {
+            return Ok(());
+        }
+        problems.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.line.cmp(&b.1.line)));
+
+        let mut warnings = Vec::new();
+        let mut errors = Vec::new();
+        for (path, problem) in problems {
+            match problem.kind.level(&self.config.html_validation) {
+                HtmlValidationLevel::Error => errors.push((path, problem)),
+                HtmlValidationLevel::Warn => warnings.push((path, problem)),
+                HtmlValidationLevel::Off => (),
+            }
+        }
+
+        let to_file_errors = |problems: Vec<(PathBuf, HtmlProblem)>| {
+            let lines: Vec<String> = problems
                 .iter()
-                .enumerate()
-                .map(|(i, msg)| format!("  {}. {}", i + 1, msg))
+                .map(|(path, problem)| {
+                    let relative_path = path.strip_prefix(&self.base_path).unwrap_or(path);
+                    format!("- {}: {}", relative_path.display(), problem.message)
+                })
                 .collect();
-            let msg = format!(
-                "Found {} broken internal anchor link(s)\n{}",
-                messages.len(),
-                messages.join("\n")
+            let summary = format!(
+                "Found {} problem(s) in the generated HTML:\n{}",
+                lines.len(),
+                lines.join("\n")
             );
-            match self.config.link_checker.internal_level {
-                config::LinkCheckerLevel::Warn => console::warn(&msg),
-                config::LinkCheckerLevel::Error => return Err(anyhow!(msg)),
+            let errors = problems
+                .into_iter()
+                .map(|(path, problem)| {
+                    let error = LocatedError {
+                        kind: "html",
+                        path: path.clone(),
+                        position: None,
+                        message: format!("{} in the generated HTML", problem.message),
+                    };
+                    (path, Error::new(error))
+                })
+                .collect::<FileErrors>();
+            (summary, errors)
+        };
+
+        if !warnings.is_empty() {
+            let (summary, warnings) = to_file_errors(warnings);
+            if console::is_json_output() {
+                for diagnostic in Diagnostic::from_error(Severity::Warning, &Error::new(warnings)) {
+                    console::emit(&diagnostic);
+                }
+            } else {
+                console::warn(&summary);
             }
         }
+        if errors.is_empty() {
+            return Ok(());
+        }
+        let (summary, errors) = to_file_errors(errors);
+        errors.with_summary(summary).into_result()
+    }
 
-        // check external links, log the results, and error out if needed
-        if self.config.is_in_check_mode() {
-            let external_link_messages = link_checking::check_external_links(self);
-            if !external_link_messages.is_empty() {
-                let messages: Vec<String> = external_link_messages
-                    .iter()
-                    .enumerate()
-                    .map(|(i, msg)| format!("  {}. {}", i + 1, msg))
-                    .collect();
-                let msg = format!(
-                    "Found {} broken external link(s)\n{}",
-                    messages.len(),
-                    messages.join("\n")
-                );
-                match self.config.link_checker.external_level {
-                    config::LinkCheckerLevel::Warn => console::warn(&msg),
-                    config::LinkCheckerLevel::Error => return Err(anyhow!(msg)),
+    /// Looks for accessibility problems in the HTML of every page and section, for
+    /// `zola check --a11y`. The problems are reported grouped by rule, erroring if some of
+    /// them are at the error level of `[check.a11y]`
+    pub fn check_accessibility(&self) -> Result<()> {
+        let mut problems = a11y::check_site_accessibility(self)?;
+        problems.sort_by(|a, b| (a.1.rule, &a.0, a.1.line).cmp(&(b.1.rule, &b.0, b.1.line)));
+
+        let mut warnings = Vec::new();
+        let mut errors = Vec::new();
+        for (path, problem) in problems {
+            match problem.rule.level(&self.config.check.a11y) {
+                HtmlValidationLevel::Error => errors.push((path, problem)),
+                HtmlValidationLevel::Warn => warnings.push((path, problem)),
+                HtmlValidationLevel::Off => (),
+            }
+        }
+
+        let to_file_errors = |problems: Vec<(PathBuf, a11y::A11yProblem)>| {
+            let mut summary =
+                format!("Found {} accessibility problem(s) in the generated HTML:", problems.len());
+            let mut current_rule = None;
+            for (i, (path, problem)) in problems.iter().enumerate() {
+                if current_rule != Some(problem.rule) {
+                    let count =
+                        problems[i..].iter().take_while(|p| p.1.rule == problem.rule).count();
+                    summary.push_str(&format!("\n{} ({}):", problem.rule.description(), count));
+                    current_rule = Some(problem.rule);
                 }
+                let relative_path = path.strip_prefix(&self.base_path).unwrap_or(path);
+                summary.push_str(&format!("\n- {}: {}", relative_path.display(), problem.message));
+            }
+            let errors = problems
+                .into_iter()
+                .map(|(path, problem)| {
+                    let error = LocatedError {
+                        kind: "a11y",
+                        path: path.clone(),
+                        position: None,
+                        message: format!("{} in the generated HTML", problem.message),
+                    };
+                    (path, Error::new(error))
+                })
+                .collect::<FileErrors>();
+            (summary, errors)
+        };
+
+        if !warnings.is_empty() {
+            let (summary, warnings) = to_file_errors(warnings);
+            if console::is_json_output() {
+                for diagnostic in Diagnostic::from_error(Severity::Warning, &Error::new(warnings)) {
+                    console::emit(&diagnostic);
+                }
+            } else {
+                console::warn(&summary);
             }
         }
+        if errors.is_empty() {
+            return Ok(());
+        }
+        let (summary, errors) = to_file_errors(errors);
+        errors.with_summary(summary).into_result()
+    }
 
-        Ok(())
+    /// Warns about the pages and sections that the templates asked for and don't exist,
+    /// grouped by template
+    fn report_missing_content(&self) {
+        let misses = std::mem::take(&mut *self.content_misses.lock().unwrap());
+        if misses.is_empty() {
+            return;
+        }
+        // The same template can miss the same content for every page it renders
+        let mut counts: BTreeMap<&ContentMiss, usize> = BTreeMap::new();
+        for miss in &misses {
+            *counts.entry(miss).or_default() += 1;
+        }
+
+        let mut lines = Vec::new();
+        let mut current_template = None;
+        for (miss, count) in &counts {
+            if current_template != Some(&miss.template) {
+                current_template = Some(&miss.template);
+                lines.push(format!("- {}", miss.template.as_deref().unwrap_or("unknown template")));
+            }
+            match count {
+                1 => lines.push(format!("  - {}", miss.call)),
+                _ => lines.push(format!("  - {} ({} times)", miss.call, count)),
+            }
+        }
+        console::warn(&format!(
+            "{} call(s) to get pages or sections that don't exist in the templates:\n{}",
+            counts.len(),
+            lines.join("\n")
+        ));
+    }
+
+    /// Warns about the front matter values `group_pages_by_date` couldn't read as dates
+    fn report_invalid_dates(&self) {
+        let invalid = std::mem::take(&mut *self.invalid_dates.lock().unwrap());
+        if invalid.is_empty() {
+            return;
+        }
+        let lines: Vec<_> = invalid
+            .iter()
+            .map(|i| {
+                let path = i.path.strip_prefix(&self.base_path).unwrap_or(&i.path);
+                format!("- {}: `{}` is {:?}", path.display(), i.field, i.value)
+            })
+            .collect();
+        console::warn(&format!(
+            "{} front matter value(s) given to `group_pages_by_date` are not dates:\n{}",
+            invalid.len(),
+            lines.join("\n")
+        ));
     }
 
     /// Insert a default index section for each language if necessary so we don't need to create
@@ -389,19 +1419,19 @@ impl Site {
                     index_path.file_name().unwrap().to_string_lossy().to_string();
                 if let Some(ref l) = lang {
                     index_section.file.name = format!("_index.{}", l);
-                    index_section.path = format!("{}/", l);
-                    index_section.permalink = self.config.make_permalink(l);
+                    index_section.path = format!("{}/", l).into();
+                    index_section.permalink = self.config.make_permalink(l).into();
                     let filename = format!("_index.{}.md", l);
                     index_section.file.path = self.content_path.join(&filename);
                     index_section.file.relative = filename;
                     index_section.file.canonical = self.content_path.join(format!("_index.{}", l));
                 } else {
                     index_section.file.name = "_index".to_string();
-                    index_section.permalink = self.config.make_permalink("");
+                    index_section.permalink = self.config.make_permalink("").into();
                     index_section.file.path = self.content_path.join("_index.md");
                     index_section.file.relative = "_index.md".to_string();
                     index_section.file.canonical = self.content_path.join("_index");
-                    index_section.path = "/".to_string();
+                    index_section.path = "/".into();
                 }
                 index_section.lang = index_section.file.find_language(
                     &self.config.default_language,
@@ -414,6 +1444,53 @@ impl Site {
         Ok(())
     }
 
+    /// Makes up a section for each directory of the `content` directory containing pages or
+    /// sections of a language but no `_index.md` for it, so every page has all its ancestors.
+    /// The directories of colocated pages are left alone
+    fn create_implicit_sections(&mut self, pages: &[Page]) -> Result<()> {
+        let page_dirs: HashSet<&Path> = pages
+            .iter()
+            .filter(|p| p.file.colocated_path.is_some())
+            .filter_map(|p| p.file.path.parent())
+            .collect();
+        let mut dirs: Vec<(PathBuf, String)> = pages
+            .iter()
+            .filter(|p| !self.is_excluded(p))
+            .map(|p| (p.file.parent.clone(), p.lang.clone()))
+            .collect();
+        dirs.extend(
+            self.library.read().unwrap().sections.values().filter_map(|s| {
+                s.file.grand_parent.as_ref().map(|dir| (dir.clone(), s.lang.clone()))
+            }),
+        );
+
+        let mut seen = HashSet::new();
+        let mut implicit_sections = Vec::new();
+        for (dir, lang) in &dirs {
+            let ancestors = dir
+                .ancestors()
+                .take_while(|a| a.starts_with(&self.content_path) && *a != self.content_path);
+            for ancestor in ancestors {
+                // Its own ancestors have been handled as well
+                if !seen.insert((ancestor, lang)) {
+                    break;
+                }
+                if page_dirs.contains(ancestor) {
+                    continue;
+                }
+                let section = Section::implicit(ancestor, lang, &self.config, &self.base_path)?;
+                if !self.library.read().unwrap().sections.contains_key(&section.file.path) {
+                    implicit_sections.push(section);
+                }
+            }
+        }
+
+        for section in implicit_sections {
+            self.add_section(section, false)?;
+        }
+        Ok(())
+    }
+
     /// Render the markdown of all pages/sections
     /// Used in a build and in `serve` if a shortcode has changed
     pub fn render_markdown(&mut self) -> Result<()> {
@@ -422,6 +1499,7 @@ impl Site {
         let permalinks = &self.permalinks;
         let tera = &self.tera;
         let config = &self.config;
+        let data = &self.data;
 
         // This is needed in the first place because of silly borrow checker
         let mut pages_insert_anchors = HashMap::new();
@@ -432,34 +1510,170 @@ impl Site {
             );
         }
 
+        // `zola serve` keeps everything in memory to render the changes quickly
+        if self.config.low_memory
+            && self.build_mode != BuildMode::Memory
+            && self.content_spill.is_none()
+        {
+            self.content_spill = Some(ContentSpill::new()?);
+        }
+        let spill_dir = self.content_spill.as_ref().map(|s| s.path());
+
         let mut library = self.library.write().expect("Get lock for render_markdown");
-        library
+        let mut errors: FileErrors = library
             .pages
             .values_mut()
             .collect::<Vec<_>>()
             .par_iter_mut()
-            .map(|page| {
+            .filter_map(|page| {
                 let insert_anchor = pages_insert_anchors[&page.file.path];
-                page.render_markdown(
+                let start = self.timings.then(Instant::now);
+                let res = page.render_markdown(
                     permalinks,
                     tera,
                     config,
+                    data,
                     insert_anchor,
                     &self.shortcode_definitions,
-                )
+                );
+                if let Some(start) = start {
+                    self.build_summary
+                        .lock()
+                        .unwrap()
+                        .add_markdown_time(page.file.path.clone(), start.elapsed());
+                }
+                res.and_then(|_| match spill_dir {
+                    Some(dir) => page.spill_content(dir),
+                    None => Ok(()),
+                })
+                .err()
+                .map(|e| (page.file.path.clone(), e))
             })
-            .collect::<Result<()>>()?;
-
-        library
-            .sections
-            .values_mut()
             .collect::<Vec<_>>()
-            .par_iter_mut()
-            .map(|section| {
-                section.render_markdown(permalinks, tera, config, &self.shortcode_definitions)
-            })
-            .collect::<Result<()>>()?;
+            .into_iter()
+            .collect();
+
+        errors.extend(
+            library
+                .sections
+                .values_mut()
+                .collect::<Vec<_>>()
+                .par_iter_mut()
+                .filter_map(|section| {
+                    section
+                        .render_markdown(
+                            permalinks,
+                            tera,
+                            config,
+                            data,
+                            &self.shortcode_definitions,
+                        )
+                        .err()
+                        .map(|e| (section.file.path.clone(), e))
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .collect(),
+        );
+
+        errors.into_result()
+    }
+
+    /// Renders the Markdown and the templates of every page and section in memory with strict
+    /// templates, whatever `strict_templates` is set to. Used by `zola check` to report all the
+    /// undefined variables at once rather than stopping at the first one: they are errors if
+    /// `strict_templates` is on and warnings otherwise, as the site builds fine without it.
+    pub fn check_templates(&self) -> Result<()> {
+        let mut tera = self.tera.clone();
+        make_templates_strict(&mut tera);
+        let library = self.library.read().unwrap();
+
+        let check_page = |page: &Page| -> Result<()> {
+            let mut page = page.clone();
+            let insert_anchor =
+                self.find_parent_section_insert_anchor(&page.file.parent, &page.lang);
+            page.render_markdown(
+                &self.permalinks,
+                &tera,
+                &self.config,
+                &self.data,
+                insert_anchor,
+                &self.shortcode_definitions,
+            )?;
+            if !page.meta.render {
+                return Ok(());
+            }
+            page.render_html(&tera, &self.config, &self.data, &library)?;
+            for format in self.page_output_formats(&page, &library) {
+                if !page.meta.skip_output_formats.contains(&format.extension) {
+                    page.render_template(
+                        &format.template,
+                        &tera,
+                        &self.config,
+                        &self.data,
+                        &library,
+                    )?;
+                }
+            }
+            Ok(())
+        };
+        let check_section = |section: &Section| -> Result<()> {
+            let mut section = section.clone();
+            section.render_markdown(
+                &self.permalinks,
+                &tera,
+                &self.config,
+                &self.data,
+                &self.shortcode_definitions,
+            )?;
+            if !section.meta.render || section.meta.redirect_to.is_some() {
+                return Ok(());
+            }
+            if section.meta.is_paginated() {
+                let paginator = Paginator::from_section(&section, &library);
+                if let Some(pager) = paginator.pagers.first() {
+                    paginator.render_pager(pager, &self.config, &self.data, &tera, &library)?;
+                }
+            } else {
+                section.render_html(&tera, &self.config, &self.data, &library)?;
+            }
+            Ok(())
+        };
 
+        let mut errors: FileErrors = library
+            .pages
+            .par_iter()
+            .filter_map(|(path, page)| check_page(page).err().map(|e| (path.clone(), e)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect();
+        errors.extend(
+            library
+                .sections
+                .par_iter()
+                .filter_map(|(path, section)| {
+                    check_section(section).err().map(|e| (path.clone(), e))
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .collect(),
+        );
+        self.report_invalid_dates();
+
+        if errors.is_empty() || self.config.strict_templates {
+            return errors.into_result();
+        }
+        let error = errors.into_result().unwrap_err();
+        if console::is_json_output() {
+            for diagnostic in Diagnostic::from_error(Severity::Warning, &error) {
+                console::emit(&diagnostic);
+            }
+        } else {
+            console::warn(&format!(
+                "The templates would not render with `strict_templates = true`: {:#}",
+                error
+            ));
+        }
         Ok(())
     }
 
@@ -476,17 +1690,30 @@ impl Site {
             }
         }
 
-        self.permalinks.insert(page.file.relative.clone(), page.permalink.clone());
+        // The map is shared with the template functions so it gets copied if they still have it
+        let previous = Arc::make_mut(&mut self.permalinks)
+            .insert(page.file.relative.clone(), page.permalink.clone());
         if render_md {
+            if previous.as_ref() != Some(&page.permalink) {
+                tpls::register_get_url(self);
+            }
             let insert_anchor =
                 self.find_parent_section_insert_anchor(&page.file.parent, &page.lang);
+            let start = self.timings.then(Instant::now);
             page.render_markdown(
                 &self.permalinks,
                 &self.tera,
                 &self.config,
+                &self.data,
                 insert_anchor,
                 &self.shortcode_definitions,
             )?;
+            if let Some(start) = start {
+                self.build_summary
+                    .lock()
+                    .unwrap()
+                    .add_markdown_time(page.file.path.clone(), start.elapsed());
+            }
         }
 
         let mut library = self.library.write().expect("Get lock for add_page");
@@ -496,27 +1723,190 @@ impl Site {
         Ok(())
     }
 
-    /// Adds a page to the site and render it
+    /// Checks that `page` doesn't end up at the same path as another page of its section, which
+    /// happens with `intro.md` and `Intro.md` or with two pages setting the same `slug`.
+    /// `pages_by_path` maps the lowercased paths of the pages already added to their file and
+    /// gets the path of `page`.
+    /// If `deduplicate_slugs` is set, the slug of `page` gets a `-1`, `-2`... suffix instead
+    /// of erroring.
+    fn check_slug_collision(
+        &self,
+        page: &mut Page,
+        pages_by_path: &mut HashMap<String, PathBuf>,
+    ) -> Result<()> {
+        if page.meta.path.is_some() {
+            return Ok(());
+        }
+
+        if let Some(other) = pages_by_path.get(&page.path.to_lowercase()) {
+            if !self.config.deduplicate_slugs {
+                bail!(
+                    "Pages {} and {} have the same slug `{}` in the same section. \
+                    Change the slug of one of them or set `deduplicate_slugs = true` in config.toml",
+                    other.display(),
+                    page.file.path.display(),
+                    page.slug
+                );
+            }
+            let other = other.clone();
+            let slug = page.slug.clone();
+            let mut suffix = 1;
+            loop {
+                page.set_slug(format!("{}-{}", slug, suffix), &self.config);
+                if !pages_by_path.contains_key(&page.path.to_lowercase()) {
+                    break;
+                }
+                suffix += 1;
+            }
+            console::warn(&format!(
+                "Page {} has the same slug `{}` as {}, using `{}` instead",
+                page.file.path.display(),
+                slug,
+                other.display(),
+                page.slug
+            ));
+        }
+
+        pages_by_path.insert(page.path.to_lowercase(), page.file.path.clone());
+        Ok(())
+    }
+
+    /// Adds a page to the site and render it, along with the content it links or used to link to
+    /// so their backlinks are up to date
     /// Only used in `zola serve --fast`
     pub fn add_and_render_page(&mut self, path: &Path) -> Result<()> {
-        let page = Page::from_file(path, &self.config, &self.base_path)?;
+        let start = Instant::now();
+        *self.build_summary.lock().unwrap() = BuildSummary::default();
+        let mut page = Page::from_file(path, &self.config, &self.base_path)?;
+        self.set_weight_from_filename(&mut page);
+        let mut pages_by_path = self
+            .library
+            .read()
+            .unwrap()
+            .pages
+            .values()
+            .filter(|p| p.file.path != path && p.meta.path.is_none())
+            .map(|p| (p.path.to_lowercase(), p.file.path.clone()))
+            .collect();
+        self.check_slug_collision(&mut page, &mut pages_by_path)?;
+        let mut linked: Vec<String> = match self.library.read().unwrap().pages.get(path) {
+            Some(p) => p.internal_links.iter().map(|(link, _)| link.clone()).collect(),
+            None => Vec::new(),
+        };
+        // The archive pages the page was in, to update them if it moved to another one
+        let previous_groups: HashSet<String> = self
+            .archives
+            .iter()
+            .flat_map(|a| &a.groups)
+            .filter(|g| g.pages.iter().any(|p| p == path))
+            .map(|g| g.path.clone())
+            .collect();
+        // The sections showing stats that change with the page need to be rendered again
+        let previous_stats: HashMap<PathBuf, content::SectionStats> = self
+            .library
+            .read()
+            .unwrap()
+            .sections
+            .iter()
+            .map(|(p, s)| (p.clone(), s.stats.clone()))
+            .collect();
         self.add_page(page, true)?;
+        if self.config.git_metadata {
+            self.load_git_metadata(vec![path.to_path_buf()]);
+        }
         self.populate_sections();
         self.populate_taxonomies()?;
+        self.populate_archives();
+        {
+            let mut library = self.library.write().unwrap();
+            library.fill_backlinks();
+            if self.build_mode == BuildMode::Memory {
+                let sections: Vec<_> = library.sections.keys().cloned().collect();
+                library.update_fingerprints(sections.iter().chain([&path.to_path_buf()]));
+            }
+        }
         let library = self.library.read().unwrap();
         let page = library.pages.get(path).unwrap();
-        self.render_page(page)
+        self.render_page(page)?;
+        self.report_html_problems()?;
+        for archive in &self.archives {
+            let is_affected = |g: &content::ArchiveGroup| {
+                previous_groups.contains(&g.path) || g.pages.iter().any(|p| p == path)
+            };
+            if archive.groups.iter().any(is_affected)
+                || previous_groups.iter().any(|p| p.starts_with(&archive.path))
+            {
+                self.render_archive(archive, &library, is_affected)?;
+            }
+        }
+
+        for (section_path, section) in &library.sections {
+            if previous_stats.get(section_path) != Some(&section.stats) {
+                self.render_section(section, false)?;
+            }
+        }
+
+        linked.extend(page.internal_links.iter().map(|(link, _)| link.clone()));
+        linked.sort();
+        linked.dedup();
+        for link in linked {
+            let linked_path = self.content_path.join(&link);
+            if linked_path == path {
+                continue;
+            }
+            if let Some(p) = library.pages.get(&linked_path) {
+                self.render_page(p)?;
+            } else if let Some(s) = library.sections.get(&linked_path) {
+                self.render_section(s, false)?;
+            }
+        }
+        self.log_time(start, "Rendered page and linked content");
+        Ok(())
+    }
+
+    /// Sets `page.git` for the given pages, with the metadata of their last commit
+    fn load_git_metadata(&self, paths: Vec<PathBuf>) {
+        let metadata = match git::get_git_metadata(
+            &self.base_path,
+            &self.content_path,
+            &paths,
+            self.config.git_edit_url.as_deref(),
+        ) {
+            Ok(m) => m,
+            Err(e) => {
+                console::warn(&format!("Could not load the git metadata of the pages: {}", e));
+                HashMap::new()
+            }
+        };
+
+        let mut library = self.library.write().expect("Get lock for load_git_metadata");
+        for path in paths {
+            if let Some(page) = library.pages.get_mut(&path) {
+                page.git = Some(metadata.get(&path).cloned().unwrap_or_default());
+            }
+        }
     }
 
     /// Add a section to the site
     /// The `render` parameter is used in the serve command with --fast, when rebuilding a page.
     pub fn add_section(&mut self, mut section: Section, render_md: bool) -> Result<()> {
-        self.permalinks.insert(section.file.relative.clone(), section.permalink.clone());
+        if section.meta.is_paginated() && section.meta.limits_pages() {
+            console::warn(&format!(
+                "Section `{}` is paginated so its `page_limit` and `page_offset` are ignored",
+                section.file.path.display()
+            ));
+        }
+        let previous = Arc::make_mut(&mut self.permalinks)
+            .insert(section.file.relative.clone(), section.permalink.clone());
         if render_md {
+            if previous.as_ref() != Some(&section.permalink) {
+                tpls::register_get_url(self);
+            }
             section.render_markdown(
                 &self.permalinks,
                 &self.tera,
                 &self.config,
+                &self.data,
                 &self.shortcode_definitions,
             )?;
         }
@@ -527,15 +1917,45 @@ impl Site {
         Ok(())
     }
 
-    /// Adds a section to the site and render it
+    /// Adds a section to the site and render it, along with its parent and siblings if
+    /// its position among them might have changed
     /// Only used in `zola serve --fast`
     pub fn add_and_render_section(&mut self, path: &Path) -> Result<()> {
+        let start = Instant::now();
+        *self.build_summary.lock().unwrap() = BuildSummary::default();
         let section = Section::from_file(path, &self.config, &self.base_path)?;
+        let previous_order = self
+            .library
+            .read()
+            .unwrap()
+            .sections
+            .get(path)
+            .map(|s| (s.meta.weight, s.meta.title.clone()));
+        let order_changed =
+            previous_order != Some((section.meta.weight, section.meta.title.clone()));
         self.add_section(section, true)?;
         self.populate_sections();
+        if self.build_mode == BuildMode::Memory {
+            self.library.write().unwrap().update_fingerprints([&path.to_path_buf()]);
+        }
         let library = self.library.read().unwrap();
         let section = library.sections.get(path).unwrap();
-        self.render_section(section, true)
+        self.render_section(section, true)?;
+
+        if order_changed {
+            let parent =
+                library.sections.values().find(|s| s.subsections.iter().any(|p| p == path));
+            if let Some(parent) = parent {
+                self.render_section(parent, false)?;
+                for sibling in &parent.subsections {
+                    if sibling != path {
+                        self.render_section(&library.sections[sibling], false)?;
+                    }
+                }
+            }
+        }
+        self.log_time(start, "Rendered section and its neighbours");
+        self.report_html_problems()
     }
 
     /// Finds the insert_anchor for the parent section of the directory at `path`.
@@ -545,76 +1965,268 @@ impl Site {
         parent_path: &Path,
         lang: &str,
     ) -> InsertAnchor {
-        let parent = if lang != self.config.default_language {
-            parent_path.join(format!("_index.{}.md", lang))
-        } else {
-            parent_path.join("_index.md")
-        };
+        let parent = self.parent_section_file(parent_path, lang);
         match self.library.read().unwrap().sections.get(&parent) {
             Some(s) => s.meta.insert_anchor_links,
             None => InsertAnchor::None,
         }
     }
 
+    /// The `_index.md` of the section in that directory in that language
+    fn parent_section_file(&self, parent_path: &Path, lang: &str) -> PathBuf {
+        if lang != self.config.default_language {
+            parent_path.join(format!("_index.{}.md", lang))
+        } else {
+            parent_path.join("_index.md")
+        }
+    }
+
     /// Find out the direct subsections of each subsection if there are some
     /// as well as the pages for each section
     pub fn populate_sections(&mut self) {
         let mut library = self.library.write().expect("Get lock for populate_sections");
         library.populate_sections(&self.config, &self.content_path);
+        *self.site_index.write().unwrap() = library.site_index.clone();
     }
 
-    /// Find all the tags and categories if it's asked in the config
+    /// Find all the tags and categories if it's asked in the config, and the pages related
+    /// through them
     pub fn populate_taxonomies(&mut self) -> Result<()> {
-        self.taxonomies = self.library.read().unwrap().find_taxonomies(&self.config);
+        let mut library = self.library.write().expect("Get lock for populate_taxonomies");
+        self.taxonomies = library.find_taxonomies(&self.config);
+        library.fill_related_pages(&self.config);
+        Ok(())
+    }
+
+    /// Groups the pages of the sections of the `archives` config by year or month, returning
+    /// the pages left out of their archive because they have no date
+    pub fn populate_archives(&mut self) -> Vec<PathBuf> {
+        let (archives, undated) = Archive::find_all(&self.library.read().unwrap(), &self.config);
+        self.archives = archives;
+        undated
+    }
+
+    /// Loads the mentions of the pages from `mentions_source`, with the same machinery as
+    /// `load_data`. A missing file or URL leaves the pages without mentions.
+    fn load_mentions(&mut self) -> Result<()> {
+        let Some(source) = self.config.mentions_source.clone() else {
+            return Ok(());
+        };
+        let mut args = HashMap::from([("required".to_string(), Value::Bool(false))]);
+        if is_external_link(&source) {
+            args.insert("url".to_string(), Value::String(source.clone()));
+            args.insert("format".to_string(), Value::String("json".to_string()));
+        } else {
+            args.insert("path".to_string(), Value::String(source.clone()));
+        }
+        let load_data = global_fns::LoadData::new(
+            self.base_path.clone(),
+            self.config.theme.clone(),
+            self.output_path.clone(),
+        );
+        let data = load_data
+            .call(&args)
+            .map_err(|e| anyhow!("Failed to load the mentions from `{}`: {}", source, e))?;
+
+        let mut library = self.library.write().unwrap();
+        match data {
+            Value::Null => library.set_mentions(&Map::new()),
+            Value::Object(mentions) => library.set_mentions(&mentions),
+            _ => bail!("The mentions of `{}` should map permalinks to arrays", source),
+        }
+        .with_context(|| format!("Failed to load the mentions from `{}`", source))
+    }
+
+    /// Gathers the redirects of the rules files, if there are some to generate
+    pub fn populate_redirects(&mut self) -> Result<()> {
+        if self.config.redirect_output.is_empty() {
+            return Ok(());
+        }
+        let mut static_dirs = vec![self.static_path.clone()];
+        if let Some(ref theme) = self.config.theme {
+            static_dirs.push(self.base_path.join("themes").join(theme).join("static"));
+        }
+        self.redirects = redirects::find_redirects(
+            &self.config,
+            &self.library.read().unwrap(),
+            &self.taxonomies,
+            &self.archives,
+            &self.permalinks,
+            &static_dirs,
+        )?;
+        Ok(())
+    }
+
+    /// Inject live reload script tag if in live reload mode, and a banner telling only some of
+    /// the content is loaded with `zola serve --only`
+    fn inject_livereload(&self, mut html: String) -> String {
+        if let Some(only) = &self.only_content {
+            let banner = format!(
+                r#"<div style="position:fixed;bottom:0;left:0;right:0;z-index:99999;padding:4px 8px;background:#fef3c7;color:#000;font:13px sans-serif;text-align:center">Partial build: only <code>content/{}</code> is loaded</div>"#,
+                only.display()
+            );
+            insert_before_body_end(&mut html, &banner);
+        }
+        // The port is in the livereload.js served by `zola serve`
+        if self.live_reload.is_some() && !html.contains(LIVE_RELOAD_SCRIPT) {
+            insert_before_body_end(&mut html, LIVE_RELOAD_SCRIPT);
+        }
+
+        html
+    }
+
+    /// Copy the main `static` folder and the theme `static` folder if a theme is used
+    pub fn copy_static_directories(&self) -> Result<CopySummary> {
+        let mut summary = CopySummary::default();
+        // The user files will overwrite the theme files
+        if let Some(ref theme) = self.config.theme {
+            summary.merge(copy_directory(
+                &self.base_path.join("themes").join(theme).join("static"),
+                &self.output_path,
+                CopyMode::Copy,
+                None,
+                Some(&self.ignore_rules),
+            )?);
+        }
+        // We're fine with missing static folders
+        if self.static_path.exists() {
+            summary.merge(copy_directory(
+                &self.static_path,
+                &self.output_path,
+                self.config.copy_mode,
+                self.config.ignored_static_globset.as_ref(),
+                Some(&self.ignore_rules),
+            )?);
+            self.optimize_static_assets()?;
+        }
+
+        Ok(summary)
+    }
+
+    /// Whether that file or directory of `static` is skipped by `ignored_static` or the ignore rules
+    fn is_ignored_static(&self, path: &Path) -> bool {
+        self.config
+            .ignored_static_globset
+            .as_ref()
+            .is_some_and(|gs| path.strip_prefix(&self.static_path).is_ok_and(|p| gs.is_match(p)))
+            || self.ignore_rules.is_ignored(path, path.is_dir())
+    }
+
+    /// Copies a directory created in, or moved to, `static` without copying all of `static` again
+    pub fn copy_static_directory(&self, path: &Path) -> Result<()> {
+        let mut walker = walk_dir_following_links(WalkDir::new(path));
+        while let Some(entry) = walker.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            if self.is_ignored_static(entry.path()) {
+                if entry.file_type().is_dir() {
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+            if entry.file_type().is_file() {
+                copy_file(
+                    entry.path(),
+                    &self.output_path,
+                    &self.static_path,
+                    self.config.copy_mode,
+                )?;
+            }
+        }
         Ok(())
     }
 
-    /// Inject live reload script tag if in live reload mode
-    fn inject_livereload(&self, mut html: String) -> String {
-        if let Some(port) = self.live_reload {
-            let script =
-                format!(r#"<script src="/livereload.js?port={}&amp;mindelay=10"></script>"#, port,);
-            if let Some(index) = html.rfind("</body>") {
-                html.insert_str(index, &script);
-            } else {
-                html.push_str(&script);
+    /// Removes what a file deleted from `static`, or moved out of it, was copied to, along with
+    /// the directories of the output it leaves empty. If it was overriding a file of the theme,
+    /// the one of the theme is copied again.
+    /// The output directory of a deleted directory can also hold rendered pages, eg `static/blog`
+    /// and the `blog` section, and we don't know which files came from `static`: the whole site
+    /// is built again instead.
+    pub fn remove_static_file(&self, path: &Path) -> Result<()> {
+        let relative = match path.strip_prefix(&self.static_path) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative,
+            _ => return Ok(()),
+        };
+        let target = self.output_path.join(relative);
+        if target.is_dir() {
+            self.clean()?;
+            return self.build().map(|_| ());
+        } else if target.exists() {
+            std::fs::remove_file(&target)
+                .with_context(|| format!("Failed to remove {}", target.display()))?;
+        }
+
+        let theme_static = self
+            .config
+            .theme
+            .as_ref()
+            .map(|t| self.base_path.join("themes").join(t).join("static"));
+        // The directories that are now empty, unless they are empty in `static` as well
+        let mut dir = target.parent();
+        while let Some(current) = dir {
+            let Ok(relative_dir) = current.strip_prefix(&self.output_path) else { break };
+            if relative_dir.as_os_str().is_empty()
+                || self.static_path.join(relative_dir).exists()
+                || theme_static.as_ref().is_some_and(|t| t.join(relative_dir).exists())
+                || std::fs::read_dir(current).map_or(true, |mut d| d.next().is_some())
+            {
+                break;
             }
+            std::fs::remove_dir(current)
+                .with_context(|| format!("Failed to remove {}", current.display()))?;
+            dir = current.parent();
         }
 
-        html
+        if let Some(theme_static) = theme_static {
+            let theme_path = theme_static.join(relative);
+            if theme_path.is_file() {
+                copy_file(&theme_path, &self.output_path, &theme_static, CopyMode::Copy)?;
+            }
+        }
+        Ok(())
     }
 
-    /// Copy the main `static` folder and the theme `static` folder if a theme is used
-    pub fn copy_static_directories(&self) -> Result<()> {
-        // The user files will overwrite the theme files
-        if let Some(ref theme) = self.config.theme {
-            copy_directory(
-                &self.base_path.join("themes").join(theme).join("static"),
-                &self.output_path,
-                false,
-                None,
-            )?;
-        }
-        // We're fine with missing static folders
-        if self.static_path.exists() {
-            if let Some(gs) = &self.config.ignored_static_globset {
-                copy_directory(
-                    &self.static_path,
-                    &self.output_path,
-                    self.config.hard_link_static,
-                    Some(gs),
-                )?;
-            } else {
-                copy_directory(
-                    &self.static_path,
-                    &self.output_path,
-                    self.config.hard_link_static,
-                    None,
-                )?;
-            }
+    /// Whether the asset at that path should go through `imageproc::optimize_asset` rather than
+    /// being copied. Never the case in serve mode since it would slow down rebuilds.
+    fn should_optimize_asset(&self, path: &Path) -> bool {
+        !self.config.is_in_serve_mode()
+            && imageproc::is_optimizable(path)
+            && self
+                .config
+                .asset_optimization
+                .should_optimize(path.strip_prefix(&self.base_path).unwrap_or(path))
+    }
+
+    /// Replaces the copied static images matching `asset_optimization` by their optimized version
+    fn optimize_static_assets(&self) -> Result<()> {
+        if self.config.asset_optimization.include.is_empty() || self.config.is_in_serve_mode() {
+            return Ok(());
         }
 
-        Ok(())
+        let images: Vec<_> = walk_dir_following_links(WalkDir::new(&self.static_path))
+            .filter_map(|e| e.ok())
+            .map(|e| e.into_path())
+            .filter(|p| p.is_file() && self.should_optimize_asset(p))
+            .filter(|p| !self.ignore_rules.is_ignored(p, false))
+            .filter(|p| {
+                let relative_path = p.strip_prefix(&self.static_path).unwrap();
+                !self
+                    .config
+                    .ignored_static_globset
+                    .as_ref()
+                    .is_some_and(|gs| gs.is_match(relative_path))
+            })
+            .collect();
+
+        images
+            .par_iter()
+            .map(|src| {
+                let dest = self.output_path.join(src.strip_prefix(&self.static_path).unwrap());
+                imageproc::optimize_asset(src, &dest, &self.config.asset_optimization).map(|_| ())
+            })
+            .collect::<Result<()>>()
     }
 
     pub fn num_img_ops(&self) -> usize {
@@ -670,6 +2282,10 @@ impl Site {
 
                 SITE_CONTENT.write().unwrap().insert(site_path, final_content);
             }
+            BuildMode::DryRun => {
+                let path = site_path.join(filename).to_string();
+                self.dry_run_content.lock().unwrap().insert(path, final_content);
+            }
         }
 
         Ok(current_path)
@@ -678,13 +2294,15 @@ impl Site {
     fn copy_assets(&self, parent: &Path, assets: &[impl AsRef<Path>], dest: &Path) -> Result<()> {
         for asset in assets {
             let asset_path = asset.as_ref();
-            copy_file_if_needed(
-                asset_path,
-                &dest.join(
-                    asset_path.strip_prefix(parent).expect("Couldn't get filename from page asset"),
-                ),
-                self.config.hard_link_static,
-            )?;
+            let dest_path = dest.join(
+                asset_path.strip_prefix(parent).expect("Couldn't get filename from page asset"),
+            );
+            if self.should_optimize_asset(asset_path) {
+                create_directory(dest_path.parent().unwrap())?;
+                imageproc::optimize_asset(asset_path, &dest_path, &self.config.asset_optimization)?;
+            } else {
+                copy_file_if_needed(asset_path, &dest_path, self.config.copy_mode)?;
+            }
         }
 
         Ok(())
@@ -695,96 +2313,410 @@ impl Site {
         if !page.meta.render {
             return Ok(());
         }
+        self.build_summary.lock().unwrap().add_page(page.file.path.clone());
 
-        let output = page.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
+        let library = self.library.read().unwrap();
+        let mut template_time = Duration::ZERO;
+        let start = self.timings.then(Instant::now);
+        let output = page.render_html(&self.tera, &self.config, &self.data, &library)?;
+        if let Some(start) = start {
+            template_time += start.elapsed();
+        }
+        self.validate_html(&page.file.path, &output);
         let content = self.inject_livereload(output);
         let components: Vec<&str> = page.path.split('/').collect();
         let current_path = self.write_content(&components, "index.html", content)?;
 
+        for format in self.page_output_formats(page, &library) {
+            if page.meta.skip_output_formats.contains(&format.extension) {
+                continue;
+            }
+            let start = self.timings.then(Instant::now);
+            let output = page.render_template(
+                &format.template,
+                &self.tera,
+                &self.config,
+                &self.data,
+                &library,
+            )?;
+            if let Some(start) = start {
+                template_time += start.elapsed();
+            }
+            self.write_content(&components, &format!("index.{}", format.extension), output)?;
+        }
+        if self.timings {
+            self.build_summary
+                .lock()
+                .unwrap()
+                .add_template_time(page.file.path.clone(), template_time);
+        }
+
         // Copy any asset we found previously into the same directory as the index.html
         self.copy_assets(page.file.path.parent().unwrap(), &page.assets, &current_path)?;
 
         Ok(())
     }
 
+    /// Loads the templates of the `page_output_formats` of the config and of the sections and
+    /// makes all the templates strict if `strict_templates` is on
+    fn finish_loading_templates(&mut self) -> Result<()> {
+        let library = self.library.read().unwrap();
+        let mut names: Vec<&str> =
+            self.config.page_output_formats.iter().map(|f| f.template.as_str()).collect();
+        for section in library.sections.values() {
+            if let Some(ref formats) = section.meta.page_output_formats {
+                names.extend(formats.iter().map(|f| f.template.as_str()));
+            }
+        }
+        names.sort_unstable();
+        names.dedup();
+        load_extra_templates(&mut self.tera, &self.base_path, &self.config.theme, &names)?;
+        if self.config.strict_templates {
+            make_templates_strict(&mut self.tera);
+        }
+        Ok(())
+    }
+
+    /// The output formats of the closest ancestor section setting them, or of the config
+    fn page_output_formats<'a>(&'a self, page: &Page, library: &'a Library) -> &'a [OutputFormat] {
+        page.ancestors
+            .iter()
+            .rev()
+            .filter_map(|a| library.sections.get(&self.content_path.join(a)))
+            .find_map(|s| s.meta.page_output_formats.as_deref())
+            .unwrap_or(&self.config.page_output_formats)
+    }
+
     /// Deletes the `public` directory (only for `zola build`) and builds the site
-    pub fn build(&self) -> Result<()> {
-        let mut start = Instant::now();
+    /// Renders the loaded site to the output directory, or to memory in `zola serve`
+    pub fn build(&self) -> Result<SiteStats> {
+        let build_start = Instant::now();
+        let mut start = build_start;
+        self.manifest.lock().unwrap().clear();
+        self.dry_run_content.lock().unwrap().clear();
+        *self.build_hash.lock().unwrap() = None;
         // Do not clean on `zola serve` otherwise we end up copying assets all the time
-        if self.build_mode == BuildMode::Disk {
+        if self.build_mode != BuildMode::Memory {
             self.clean()?;
         }
-        start = log_time(start, "Cleaned folder");
+        start = self.log_time(start, "Cleaned folder");
 
         // Generate/move all assets before markdown any content
+        let mut compiled_sass = Vec::new();
         if let Some(ref theme) = self.config.theme {
             let theme_path = self.base_path.join("themes").join(theme);
             if theme_path.join("sass").exists() {
-                sass::compile_sass(&theme_path, &self.output_path)?;
-                start = log_time(start, "Compiled theme Sass");
+                compiled_sass.extend(sass::compile_sass(&theme_path, &self.output_path)?);
+                start = self.log_time(start, "Compiled theme Sass");
             }
         }
 
         if self.config.compile_sass {
-            sass::compile_sass(&self.base_path, &self.output_path)?;
-            start = log_time(start, "Compiled own Sass");
+            compiled_sass.extend(sass::compile_sass(&self.base_path, &self.output_path)?);
+            start = self.log_time(start, "Compiled own Sass");
         }
 
-        if self.config.build_search_index {
-            self.build_search_index()?;
-            start = log_time(start, "Built search index");
+        // The hashed paths need to be known before rendering anything using `get_url` so the
+        // static files are copied first, to hash their final version
+        let hash_assets =
+            !self.config.hash_static_assets.is_empty() && !self.config.is_in_serve_mode();
+        if hash_assets {
+            let summary = self.copy_static_directories()?;
+            start = self.log_time(start, &format!("Copied static dir ({})", summary));
+            let mut static_dirs = Vec::new();
+            if let Some(ref theme) = self.config.theme {
+                static_dirs.push((self.base_path.join("themes").join(theme).join("static"), false));
+            }
+            static_dirs.push((self.static_path.clone(), true));
+            let assets = hashed_assets::find_assets_to_hash(
+                &self.config,
+                &static_dirs,
+                &self.output_path,
+                &compiled_sass,
+                &self.ignore_rules,
+            );
+            *self.asset_manifest.write().unwrap() =
+                hashed_assets::hash_assets(&self.output_path, &assets)?;
+            start = self.log_time(start, "Hashed static assets");
+        } else {
+            self.asset_manifest.write().unwrap().clear();
         }
 
+        // Problems left by the previous renders have already been reported
+        self.html_problems.lock().unwrap().clear();
         // Render aliases first to allow overwriting
         self.render_aliases()?;
-        start = log_time(start, "Rendered aliases");
+        start = self.log_time(start, "Rendered aliases");
         self.render_sections()?;
-        start = log_time(start, "Rendered sections");
+        start = self.log_time(start, "Rendered sections");
         self.render_orphan_pages()?;
-        start = log_time(start, "Rendered orphan pages");
-        if self.config.generate_sitemap {
-            self.render_sitemap()?;
-            start = log_time(start, "Rendered sitemap");
-        }
+        self.log_time(start, "Rendered orphan pages");
+        self.report_html_problems()?;
+        self.render_aggregates()?;
+        start = Instant::now();
 
         let library = self.library.read().unwrap();
-        if self.config.generate_feeds {
-            let is_multilingual = self.config.is_multilingual();
-            let pages: Vec<_> = if is_multilingual {
-                library.pages.values().filter(|p| p.lang == self.config.default_language).collect()
-            } else {
-                library.pages.values().collect()
-            };
-            self.render_feeds(pages, None, &self.config.default_language, |c| c)?;
-            start = log_time(start, "Generated feed in default language");
-        }
-
-        for (code, language) in &self.config.other_languages() {
-            if !language.generate_feeds {
-                continue;
-            }
-            let pages: Vec<_> = library.pages.values().filter(|p| &p.lang == code).collect();
-            self.render_feeds(pages, Some(&PathBuf::from(code)), code, |c| c)?;
-            start = log_time(start, "Generated feed in other language");
-        }
         self.render_themes_css()?;
-        start = log_time(start, "Rendered themes css");
+        start = self.log_time(start, "Rendered themes css");
+        if self.config.markdown.highlight_code
+            && self.config.markdown.highlighting_mode == HighlightingMode::Css
+        {
+            self.render_syntax_theme_css()?;
+            start = self.log_time(start, "Rendered syntax-theme.css");
+        }
         self.render_404()?;
-        start = log_time(start, "Rendered 404");
+        start = self.log_time(start, "Rendered 404");
+        if check_template_fallbacks("50x.html", &self.tera, &self.config.theme).is_some() {
+            self.render_50x()?;
+            start = self.log_time(start, "Rendered 50x");
+        }
         if self.config.generate_robots_txt {
             self.render_robots()?;
-            start = log_time(start, "Rendered robots.txt");
+            self.build_summary.lock().unwrap().add_aggregate("robots.txt");
+            start = self.log_time(start, "Rendered robots.txt");
         }
         self.render_taxonomies()?;
-        start = log_time(start, "Rendered taxonomies");
+        if !self.taxonomies.is_empty() {
+            self.build_summary.lock().unwrap().add_aggregate("taxonomies");
+        }
+        start = self.log_time(start, "Rendered taxonomies");
+        if !self.archives.is_empty() {
+            self.render_archives()?;
+            self.build_summary.lock().unwrap().add_aggregate("archives");
+            start = self.log_time(start, "Rendered archives");
+        }
+        self.report_missing_content();
+        self.report_invalid_dates();
         // We process images at the end as we might have picked up images to process from markdown
         // or from templates
         self.process_images()?;
-        start = log_time(start, "Processed images");
+        start = self.log_time(start, "Processed images");
+        if imageproc::generate_favicons(&self.base_path, &self.config)? {
+            start = self.log_time(start, "Generated favicons");
+        }
         // Processed images will be in static so the last step is to copy it
-        self.copy_static_directories()?;
-        log_time(start, "Copied static dir");
+        if hash_assets {
+            let processed_images = self.static_path.join(imageproc::RESIZED_SUBDIR);
+            if processed_images.exists() {
+                self.copy_static_directory(&processed_images)?;
+                start = self.log_time(start, "Copied processed images");
+            }
+            hashed_assets::rewrite_css_files(
+                &self.output_path,
+                &self.asset_manifest.read().unwrap(),
+            )?;
+            start = self.log_time(start, "Pointed CSS files to hashed static assets");
+        } else {
+            let summary = self.copy_static_directories()?;
+            start = self.log_time(start, &format!("Copied static dir ({})", summary));
+        }
+
+        if !self.config.redirect_output.is_empty() && self.build_mode != BuildMode::Memory {
+            self.render_redirects()?;
+            self.build_summary.lock().unwrap().add_aggregate("redirects");
+            start = self.log_time(start, "Rendered redirect rules");
+        }
+
+        if self.relative_urls && self.build_mode != BuildMode::Memory {
+            relative_urls::make_urls_relative(&self.output_path, &self.config.base_url)?;
+            if self.build_mode == BuildMode::DryRun {
+                for (path, content) in self.dry_run_content.lock().unwrap().iter_mut() {
+                    if path.ends_with(".html") {
+                        let depth = path.matches('/').count();
+                        *content =
+                            relative_urls::rewrite_html(content, &self.config.base_url, depth);
+                    }
+                }
+            }
+            start = self.log_time(start, "Made URLs relative");
+        }
+
+        // Last so it lists the files as they end up in the output directory
+        if self.config.generate_manifest && self.build_mode == BuildMode::Disk {
+            let entries = manifest::build_manifest(
+                &self.output_path,
+                &self.config,
+                &library,
+                &self.taxonomies,
+                &self.archives,
+            )?;
+            manifest::write_manifest(&self.output_path, &entries)?;
+            *self.manifest.lock().unwrap() = entries;
+            start = self.log_time(start, "Wrote manifest");
+        }
+
+        if self.build_mode != BuildMode::Memory {
+            self.compute_build_hash()?;
+            start = self.log_time(start, "Computed build hash");
+        }
+
+        // Hard linked static files share their times with the sources, which we leave alone
+        if self.config.reproducible
+            && self.config.copy_mode != CopyMode::Hardlink
+            && self.build_mode == BuildMode::Disk
+        {
+            set_modification_times(&self.output_path, self.config.build_info.unix_timestamp)?;
+            self.log_time(start, "Set modification times");
+        }
+
+        Ok(self.stats(build_start))
+    }
+
+    /// Writes what a dry run rendered to that output directory, as a build would have, without
+    /// rendering it again, and removes the scratch directory of the dry run.
+    pub fn write_dry_run(&self, output_path: &Path) -> Result<()> {
+        clean_site_output_folder(output_path, self.config.preserve_dotfiles_in_output)?;
+        if self.output_path.exists() {
+            copy_directory(&self.output_path, output_path, CopyMode::Copy, None, None)?;
+        }
+        self.discard_dry_run()?;
+        for (path, content) in self.dry_run_content.lock().unwrap().iter() {
+            let path = output_path.join(path);
+            create_directory(path.parent().unwrap())?;
+            create_file(&path, content)?;
+        }
+
+        if self.config.generate_manifest {
+            let library = self.library.read().unwrap();
+            let entries = manifest::build_manifest(
+                output_path,
+                &self.config,
+                &library,
+                &self.taxonomies,
+                &self.archives,
+            )?;
+            manifest::write_manifest(output_path, &entries)?;
+            *self.manifest.lock().unwrap() = entries;
+        }
+        if let Some(ref hash) = *self.build_hash.lock().unwrap() {
+            create_file(&output_path.join(build_hash::BUILD_HASH_FILENAME), hash)?;
+        }
+        if self.config.reproducible {
+            set_modification_times(output_path, self.config.build_info.unix_timestamp)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the scratch directory of a dry run, once its output isn't needed anymore
+    pub fn discard_dry_run(&self) -> Result<()> {
+        if self.build_mode == BuildMode::DryRun && self.output_path.exists() {
+            std::fs::remove_dir_all(&self.output_path)
+                .with_context(|| format!("Failed to remove {}", self.output_path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Hashes the output of the build, writing the hash to the output directory on disk
+    fn compute_build_hash(&self) -> Result<()> {
+        let manifest = self.manifest.lock().unwrap();
+        let files = if manifest.is_empty() {
+            build_hash::hash_output_files(&self.output_path, &self.config)?
+        } else {
+            manifest.iter().map(|e| (e.path.clone(), e.hash.clone())).collect()
+        };
+
+        let hash = if self.build_mode == BuildMode::DryRun {
+            let mut hashes: BTreeMap<_, _> = self
+                .dry_run_content
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(path, content)| (path.clone(), build_hash::hash_content(content.as_bytes())))
+                .collect();
+            hashes.extend(files);
+            build_hash::combine_hashes(hashes.iter().map(|(p, h)| (p.as_str(), h.as_str())))
+        } else {
+            let hash =
+                build_hash::combine_hashes(files.iter().map(|(p, h)| (p.as_str(), h.as_str())));
+            create_file(&self.output_path.join(build_hash::BUILD_HASH_FILENAME), &hash)?;
+            hash
+        };
+        *self.build_hash.lock().unwrap() = Some(hash);
+        Ok(())
+    }
+
+    /// Updates the site after a change in its directory, re-rendering only what is needed.
+    /// Changes to the content, data, theme and config reload the whole site.
+    pub fn rebuild(&mut self, change: &Change) -> Result<SiteStats> {
+        let start = Instant::now();
+        *self.build_summary.lock().unwrap() = BuildSummary::default();
+        match change {
+            Change::Content(_) => {
+                self.load()?;
+                self.build()?;
+            }
+            Change::Data => {
+                // The data is given to the templates of the aggregates too
+                self.aggregate_fingerprints.clear();
+                self.load()?;
+                self.build()?;
+            }
+            Change::Template(path) => {
+                if path.starts_with(self.templates_path.join("shortcodes")) {
+                    self.reload_shortcodes(&[path])?;
+                } else {
+                    self.reload_templates(&[path])?;
+                }
+            }
+            Change::Sass(_) => {
+                if self.config.compile_sass && self.asset_manifest.read().unwrap().is_empty() {
+                    sass::compile_sass(&self.base_path, &self.output_path)?;
+                } else {
+                    self.build()?;
+                }
+            }
+            Change::Static(path) => {
+                let from_static = path.starts_with(&self.static_path)
+                    && self.asset_manifest.read().unwrap().is_empty();
+                if self.is_ignored_static(path) {
+                    // Nothing to do
+                } else if from_static && !path.exists() {
+                    self.remove_static_file(path)?;
+                } else if from_static && path.is_dir() {
+                    self.copy_static_directory(path)?;
+                } else if path.is_file()
+                    && path.starts_with(&self.static_path)
+                    && self.asset_manifest.read().unwrap().is_empty()
+                {
+                    copy_file(path, &self.output_path, &self.static_path, self.config.copy_mode)?;
+                } else {
+                    self.build()?;
+                }
+            }
+            Change::Theme | Change::Config => {
+                self.reload_config()?;
+                self.load()?;
+                self.build()?;
+            }
+        }
+        Ok(self.stats(start))
+    }
 
+    /// Re-reads the config and the templates, keeping the options set on the site
+    fn reload_config(&mut self) -> Result<()> {
+        let mut site = Site::new(&self.base_path, &self.config_file)?;
+        if let Some(base_url) = self.base_url_override.clone() {
+            site.set_base_url(base_url);
+        }
+        if self.output_path != self.base_path.join(&self.config.output_dir) {
+            site.set_output_path(&self.output_path);
+        }
+        if self.build_mode == BuildMode::Memory {
+            site.enable_serve_mode();
+        }
+        if let Some(only) = &self.only_content {
+            site.load_only(only)?;
+        }
+        site.version = self.version.clone();
+        site.include_drafts = self.include_drafts;
+        site.include_future = self.include_future;
+        if self.relative_urls {
+            site.enable_relative_urls();
+        }
+        site.live_reload = self.live_reload;
+        site.tera_fns = self.tera_fns.clone();
+        site.tera_fns.register(&mut site.tera);
+        *self = site;
         Ok(())
     }
 
@@ -806,6 +2738,13 @@ impl Site {
         Ok(())
     }
 
+    /// Writes the stylesheet of the highlight theme(s) used in the `css` highlighting mode
+    pub fn render_syntax_theme_css(&self) -> Result<()> {
+        let content = self.config.markdown.export_syntax_theme_css()?;
+        self.write_content(&[], "syntax-theme.css", content)?;
+        Ok(())
+    }
+
     fn index_for_lang(&self, lang: &str) -> Result<()> {
         let path = &self.output_path.join(self.config.search.index_format.filename(lang));
         let library = self.library.read().unwrap();
@@ -829,19 +2768,59 @@ impl Site {
         )
     }
 
+    fn corpus_for_lang(&self, lang: &str, filename: &str) -> Result<()> {
+        let library = self.library.read().unwrap();
+        let content = search::build_corpus(lang, &library, &self.config.languages[lang].search)?;
+        drop(library);
+        self.write_content(&[], filename, content)?;
+        Ok(())
+    }
+
+    /// Renders the OpenSearch description of the site, pointing at its search page
+    pub fn render_opensearch(&self) -> Result<()> {
+        let mut context = Context::new();
+        context.insert("config", &self.config.serialize(&self.config.default_language));
+        context.insert("build_info", &self.config.build_info);
+        context.insert("search_url", &self.config.search.opensearch_url(&self.config.base_url));
+        let content = render_template("opensearch.xml", &self.tera, context, &self.config.theme)?;
+        self.write_content(&[], "opensearch.xml", content)?;
+        Ok(())
+    }
+
     pub fn build_search_index(&self) -> Result<()> {
+        let fingerprint = self.aggregate_fingerprint(|hasher| {
+            let library = self.library.read().unwrap();
+            aggregates::hash_content(&library, library.fingerprints.keys(), hasher);
+            if self.config.search.generate_opensearch {
+                let templates = ["opensearch.xml"];
+                aggregates::hash_templates(&self.tera, &self.config.theme, &templates, hasher);
+            }
+        });
+        if self.is_aggregate_unchanged("search index", "search index", fingerprint) {
+            return Ok(());
+        }
         create_directory(&self.output_path)?;
         // TODO: add those to the SITE_CONTENT map
 
         // index first
         self.index_for_lang(&self.config.default_language)?;
+        if self.config.search.generate_corpus {
+            self.corpus_for_lang(&self.config.default_language, "search_corpus.txt")?;
+        }
 
         for (code, language) in &self.config.other_languages() {
             if code != &self.config.default_language && language.build_search_index {
                 self.index_for_lang(code)?;
+                if self.config.search.generate_corpus {
+                    self.corpus_for_lang(code, &format!("search_corpus.{}.txt", code))?;
+                }
             }
         }
 
+        if self.config.search.generate_opensearch {
+            self.render_opensearch()?;
+        }
+
         match self.config.search.index_format {
             IndexFormat::ElasticlunrJavascript | IndexFormat::ElasticlunrJson => {
                 // then elasticlunr.min.js
@@ -850,6 +2829,8 @@ impl Site {
             _ => {}
         }
 
+        self.build_summary.lock().unwrap().add_aggregate("search index");
+        self.record_aggregate("search index", fingerprint);
         Ok(())
     }
 
@@ -866,6 +2847,15 @@ impl Site {
             }
             None => "index.html",
         };
+        if self.build_mode != BuildMode::Memory {
+            create_directory(&self.output_path)?;
+            let mut target = self.output_path.clone();
+            target.extend(&split);
+            target.push(page_name);
+            if !is_path_in_directory(&self.output_path, &target)?.is_inside() {
+                bail!("Alias `{}` would be written outside of the output directory", alias);
+            }
+        }
         let content = render_redirect_template(permalink, &self.tera)?;
         self.write_content(&split, page_name, content)?;
         Ok(())
@@ -874,6 +2864,10 @@ impl Site {
     /// Renders all the aliases for each page/section: a magic HTML template that redirects to
     /// the canonical one
     pub fn render_aliases(&self) -> Result<()> {
+        // The dev server doesn't read the redirect rules files
+        if !self.config.alias_html && !self.config.is_in_serve_mode() {
+            return Ok(());
+        }
         let library = self.library.read().unwrap();
         for (_, page) in &library.pages {
             for alias in &page.meta.aliases {
@@ -888,21 +2882,51 @@ impl Site {
         Ok(())
     }
 
+    /// Writes the redirect rules file of each host of `redirect_output`, keeping the rules of
+    /// the one copied from `static` if there is one
+    pub fn render_redirects(&self) -> Result<()> {
+        for output in &self.config.redirect_output {
+            let filename = redirects::rules_filename(*output);
+            let existing_path = self.output_path.join(filename);
+            let existing =
+                if existing_path.exists() { Some(read_file(&existing_path)?) } else { None };
+            let content = match output {
+                RedirectOutput::Netlify => {
+                    redirects::render_netlify(&self.redirects, existing.as_deref())
+                }
+                RedirectOutput::Vercel => {
+                    redirects::render_vercel(&self.redirects, existing.as_deref())?
+                }
+            };
+            self.write_content(&[], filename, content)?;
+        }
+        Ok(())
+    }
+
     /// Renders 404.html
     pub fn render_404(&self) -> Result<()> {
-        let mut context = Context::new();
-        context.insert("config", &self.config.serialize(&self.config.default_language));
-        context.insert("lang", &self.config.default_language);
-        let output = render_template("404.html", &self.tera, context, &self.config.theme)?;
+        self.render_error_page("404.html")
+    }
+
+    /// Renders 50x.html, only called if there is a template for it as there is no default one
+    pub fn render_50x(&self) -> Result<()> {
+        self.render_error_page("50x.html")
+    }
+
+    /// Error pages are not part of the library, they only get the config, the site index and
+    /// the global functions to list sections and taxonomies
+    fn render_error_page(&self, name: &str) -> Result<()> {
+        let mut context = self.config.template_context(&self.config.default_language, &self.data);
+        context.insert("site", &self.library.read().unwrap().site_index);
+        let output = render_template(name, &self.tera, context, &self.config.theme)?;
         let content = self.inject_livereload(output);
-        self.write_content(&[], "404.html", content)?;
+        self.write_content(&[], name, content)?;
         Ok(())
     }
 
     /// Renders robots.txt
     pub fn render_robots(&self) -> Result<()> {
-        let mut context = Context::new();
-        context.insert("config", &self.config.serialize(&self.config.default_language));
+        let context = self.config.template_context(&self.config.default_language, &self.data);
         let content = render_template("robots.txt", &self.tera, context, &self.config.theme)?;
         self.write_content(&[], "robots.txt", content)?;
         Ok(())
@@ -932,8 +2956,12 @@ impl Site {
 
         components.push(taxonomy.slug.as_ref());
 
-        let list_output =
-            taxonomy.render_all_terms(&self.tera, &self.config, &self.library.read().unwrap())?;
+        let list_output = taxonomy.render_all_terms(
+            &self.tera,
+            &self.config,
+            &self.data,
+            &self.library.read().unwrap(),
+        )?;
         let content = self.inject_livereload(list_output);
         self.write_content(&components, "index.html", content)?;
 
@@ -957,8 +2985,13 @@ impl Site {
                         ),
                     )?;
                 } else {
-                    let single_output =
-                        taxonomy.render_term(item, &self.tera, &self.config, &library)?;
+                    let single_output = taxonomy.render_term(
+                        item,
+                        &self.tera,
+                        &self.config,
+                        &self.data,
+                        &library,
+                    )?;
                     let content = self.inject_livereload(single_output);
                     self.write_content(&comp, "index.html", content)?;
                 }
@@ -989,11 +3022,67 @@ impl Site {
             .collect::<Result<()>>()
     }
 
+    /// Renders the archive pages and their index
+    pub fn render_archives(&self) -> Result<()> {
+        let library = self.library.read().unwrap();
+        for archive in &self.archives {
+            self.render_archive(archive, &library, |_| true)?;
+        }
+        Ok(())
+    }
+
+    /// Renders the index of that archive and the pages of its groups matching `filter`
+    fn render_archive(
+        &self,
+        archive: &Archive,
+        library: &Library,
+        filter: impl Fn(&content::ArchiveGroup) -> bool + Sync,
+    ) -> Result<()> {
+        let components = archive.components();
+        let index = archive.render_index(&self.tera, &self.config, &self.data, library)?;
+        self.write_content(&components, "index.html", self.inject_livereload(index))?;
+
+        archive
+            .groups
+            .par_iter()
+            .filter(|group| filter(group))
+            .map(|group| {
+                let mut components = components.clone();
+                let year = group.year.to_string();
+                let month = group.month.map(|m| format!("{:02}", m));
+                components.push(&year);
+                if let Some(ref month) = month {
+                    components.push(month);
+                }
+                let output =
+                    archive.render_group(group, &self.tera, &self.config, &self.data, library)?;
+                self.write_content(&components, "index.html", self.inject_livereload(output))?;
+                Ok(())
+            })
+            .collect::<Result<()>>()
+    }
+
     /// What it says on the tin
     pub fn render_sitemap(&self) -> Result<()> {
         let library = self.library.read().unwrap();
         let all_sitemap_entries =
-            { sitemap::find_entries(&library, &self.taxonomies[..], &self.config) };
+            { sitemap::find_entries(&library, &self.taxonomies, &self.archives, &self.config) };
+        let fingerprint = self.aggregate_fingerprint(|hasher| {
+            libs::serde_json::to_string(&all_sitemap_entries).unwrap_or_default().hash(hasher);
+            let templates = ["sitemap.xml", "split_sitemap_index.xml"];
+            aggregates::hash_templates(&self.tera, &self.config.theme, &templates, hasher);
+        });
+        if self.is_aggregate_unchanged("sitemap", "sitemap", fingerprint) {
+            return Ok(());
+        }
+        self.write_sitemaps(&all_sitemap_entries)?;
+        self.build_summary.lock().unwrap().add_aggregate("sitemap");
+        self.record_aggregate("sitemap", fingerprint);
+        Ok(())
+    }
+
+    /// Writes the sitemap, split in several files with an index if there are too many entries
+    fn write_sitemaps(&self, all_sitemap_entries: &[sitemap::SitemapEntry]) -> Result<()> {
         let sitemap_limit = 30000;
 
         if all_sitemap_entries.len() < sitemap_limit {
@@ -1001,19 +3090,25 @@ impl Site {
             let mut context = Context::new();
             context.insert("entries", &all_sitemap_entries);
             let sitemap = render_template("sitemap.xml", &self.tera, context, &self.config.theme)?;
-            self.write_content(&[], "sitemap.xml", sitemap)?;
+            self.write_content(&[], &self.config.sitemap_filename, sitemap)?;
             return Ok(());
         }
 
+        // The numbered sitemaps are next to the main one, eg `maps/sitemap1.xml`
+        let (stem, extension) = match self.config.sitemap_filename.rsplit_once('.') {
+            Some((stem, extension)) if !extension.contains('/') => {
+                (stem, format!(".{}", extension))
+            }
+            _ => (self.config.sitemap_filename.as_str(), String::new()),
+        };
+
         // Create multiple sitemaps (max 30000 urls each)
         let mut sitemap_index = Vec::new();
-        for (i, chunk) in
-            all_sitemap_entries.iter().collect::<Vec<_>>().chunks(sitemap_limit).enumerate()
-        {
+        for (i, chunk) in all_sitemap_entries.chunks(sitemap_limit).enumerate() {
             let mut context = Context::new();
             context.insert("entries", &chunk);
             let sitemap = render_template("sitemap.xml", &self.tera, context, &self.config.theme)?;
-            let file_name = format!("sitemap{}.xml", i + 1);
+            let file_name = format!("{}{}{}", stem, i + 1, extension);
             self.write_content(&[], &file_name, sitemap)?;
             let mut sitemap_url = self.config.make_permalink(&file_name);
             sitemap_url.pop(); // Remove trailing slash
@@ -1029,8 +3124,65 @@ impl Site {
             main_context,
             &self.config.theme,
         )?;
-        self.write_content(&[], "sitemap.xml", sitemap)?;
+        self.write_content(&[], &self.config.sitemap_filename, sitemap)?;
+
+        Ok(())
+    }
+
+    /// Renders the search index, the sitemap and the feeds of the site in parallel
+    fn render_aggregates(&self) -> Result<()> {
+        let search_index = || {
+            if !self.config.build_search_index {
+                return Ok(());
+            }
+            let start = Instant::now();
+            self.build_search_index()?;
+            self.log_time(start, "Built search index");
+            Ok(())
+        };
+        let sitemap = || {
+            if !self.config.generate_sitemap {
+                return Ok(());
+            }
+            let start = Instant::now();
+            self.render_sitemap()?;
+            self.log_time(start, "Rendered sitemap");
+            Ok(())
+        };
+        let feeds = || {
+            let start = Instant::now();
+            self.render_site_feeds()?;
+            self.log_time(start, "Generated feeds");
+            Ok(())
+        };
+        let (search_index, (sitemap, feeds)): (Result<()>, (Result<()>, Result<()>)) =
+            libs::rayon::join(search_index, || libs::rayon::join(sitemap, feeds));
+        search_index.and(sitemap).and(feeds)
+    }
 
+    /// Renders the feeds of the whole site, for each language generating them
+    fn render_site_feeds(&self) -> Result<()> {
+        let library = self.library.read().unwrap();
+        for (code, language) in &self.config.languages {
+            if !language.generate_feeds {
+                continue;
+            }
+            let is_default = code == &self.config.default_language;
+            let pages = feeds::site_feed_pages(&self.config, &library, code);
+            let fingerprint = self.aggregate_fingerprint(|hasher| {
+                aggregates::hash_content(&library, pages.iter().map(|p| &p.file.path), hasher);
+                let templates: Vec<_> =
+                    language.feed_filenames.iter().map(|f| f.as_str()).collect();
+                aggregates::hash_templates(&self.tera, &self.config.theme, &templates, hasher);
+            });
+            let key = format!("feeds.{}", code);
+            if self.is_aggregate_unchanged(&key, "feeds", fingerprint) {
+                continue;
+            }
+            let base_path = if is_default { None } else { Some(PathBuf::from(code)) };
+            self.render_feeds(pages, base_path.as_ref(), code, |c| c)?;
+            self.record_aggregate(&key, fingerprint);
+        }
         Ok(())
     }
 
@@ -1049,10 +3201,12 @@ impl Site {
                 Some(v) => v,
                 None => return Ok(()),
             };
+        self.build_summary.lock().unwrap().add_aggregate("feeds");
 
         for (feed, feed_filename) in
             feeds.into_iter().zip(self.config.languages[lang].feed_filenames.iter())
         {
+            let feed_filename = self.config.feed_output_filename(lang, feed_filename);
             if let Some(base) = base_path {
                 let mut components = Vec::new();
                 for component in base.components() {
@@ -1114,6 +3268,7 @@ impl Site {
         if !section.meta.render {
             return Ok(());
         }
+        self.build_summary.lock().unwrap().add_section(section.file.path.clone());
 
         if let Some(ref redirect_to) = section.meta.redirect_to {
             let permalink: Cow<String> = if is_external_link(redirect_to) {
@@ -1136,8 +3291,13 @@ impl Site {
                 &Paginator::from_section(section, &self.library.read().unwrap()),
             )?;
         } else {
-            let output =
-                section.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
+            let output = section.render_html(
+                &self.tera,
+                &self.config,
+                &self.data,
+                &self.library.read().unwrap(),
+            )?;
+            self.validate_html(&section.file.path, &output);
             let content = self.inject_livereload(output);
             self.write_content(&components, "index.html", content)?;
         }
@@ -1156,12 +3316,16 @@ impl Site {
             .collect::<Result<()>>()
     }
 
-    /// Renders all pages that do not belong to any sections
+    /// Renders all pages that do not belong to any sections, as well as the unlisted pages
+    /// since sections don't render them
     pub fn render_orphan_pages(&self) -> Result<()> {
         let library = self.library.read().unwrap();
         for page in library.get_all_orphan_pages() {
             self.render_page(page)?;
         }
+        for page in library.pages.values().filter(|p| p.meta.unlisted && !p.ancestors.is_empty()) {
+            self.render_page(page)?;
+        }
 
         Ok(())
     }
@@ -1185,9 +3349,14 @@ impl Site {
                 let output = paginator.render_pager(
                     pager,
                     &self.config,
+                    &self.data,
                     &self.tera,
                     &self.library.read().unwrap(),
                 )?;
+                // All the pagers use the same template, the first one is enough
+                if let (1, Some(path)) = (pager.index, paginator.source_file()) {
+                    self.validate_html(path, &output);
+                }
                 let content = self.inject_livereload(output);
 
                 if pager.index > 1 {
@@ -1207,11 +3376,58 @@ impl Site {
     }
 }
 
-fn log_time(start: Instant, message: &str) -> Instant {
-    let do_print = std::env::var("ZOLA_PERF_LOG").is_ok();
-    let now = Instant::now();
-    if do_print {
-        println!("{} took {}ms", message, now.duration_since(start).as_millis());
+/// The script tag injected in the HTML of `zola serve`
+pub const LIVE_RELOAD_SCRIPT: &str = r#"<script src="/livereload.js?mindelay=10"></script>"#;
+
+/// When the site is built, in seconds since the Unix epoch: the `SOURCE_DATE_EPOCH` env variable
+/// if it is set, for reproducible builds, or now otherwise
+fn get_build_timestamp(source_date_epoch: Option<&str>) -> Result<i64> {
+    match source_date_epoch {
+        Some(epoch) => epoch.trim().parse().map_err(|_| {
+            anyhow!("`SOURCE_DATE_EPOCH` should be a number of seconds, got `{}`", epoch)
+        }),
+        None => Ok(SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default()),
+    }
+}
+
+/// Inserts that snippet right before the closing `</body>` of the document, or before `</html>`
+/// or at the end if there is none, as minified HTML can omit them.
+fn insert_before_body_end(html: &mut String, snippet: &str) {
+    let lowercase = html.to_ascii_lowercase();
+    match lowercase.rfind("</body").or_else(|| lowercase.rfind("</html")) {
+        Some(index) => html.insert_str(index, snippet),
+        None => html.push_str(snippet),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_get_build_timestamp() {
+        assert_eq!(get_build_timestamp(Some("1714564800")).unwrap(), 1714564800);
+        assert_eq!(get_build_timestamp(Some(" 0\n")).unwrap(), 0);
+        assert!(get_build_timestamp(Some("2024-05-01")).is_err());
+        assert!(get_build_timestamp(None).unwrap() > 1714564800);
+    }
+
+    #[test]
+    fn can_insert_before_body_end() {
+        let mut html = "<html><body><p>Hi</body></html>".to_string();
+        insert_before_body_end(&mut html, "<script></script>");
+        assert_eq!(html, "<html><body><p>Hi<script></script></body></html>");
+
+        let mut html = "<HTML><BODY><p>Hi</BODY></HTML>".to_string();
+        insert_before_body_end(&mut html, "<script></script>");
+        assert_eq!(html, "<HTML><BODY><p>Hi<script></script></BODY></HTML>");
+
+        // Minified HTML can omit the closing tags
+        let mut html = "<!doctype html><title>Hi</title><p>Hi".to_string();
+        insert_before_body_end(&mut html, "<script></script>");
+        assert_eq!(html, "<!doctype html><title>Hi</title><p>Hi<script></script>");
     }
-    now
 }
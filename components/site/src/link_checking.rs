@@ -18,8 +18,9 @@ use utils::anchors::is_special_anchor;
 /// is always performed (while external ones only conditionally in `zola check`).  If broken links
 /// are encountered, the `internal_level` setting in config.toml will determine whether they are
 /// treated as warnings or errors.
-pub fn check_internal_links_with_anchors(site: &Site) -> Vec<String> {
-    println!("Checking all internal links with anchors.");
+/// Returns the file containing each broken link along with the error message.
+pub fn check_internal_links_with_anchors(site: &Site) -> Vec<(PathBuf, String)> {
+    console::plain("Checking all internal links with anchors.");
     let library = site.library.write().expect("Get lock for check_internal_links_with_anchors");
 
     // Chain all internal links, from both sections and pages.
@@ -65,7 +66,7 @@ pub fn check_internal_links_with_anchors(site: &Site) -> Vec<String> {
                     page.strip_prefix(&site.base_path).unwrap()
                 )
             });
-            !section.has_anchor(anchor)
+            !(section.has_anchor(anchor) || section.has_anchor_id(anchor))
         } else {
             let page = library.pages.get(&full_path).unwrap_or_else(|| {
                 panic!(
@@ -75,35 +76,70 @@ pub fn check_internal_links_with_anchors(site: &Site) -> Vec<String> {
                 )
             });
 
-            !(page.has_anchor(anchor) || page.has_anchor_id(anchor))
+            // Reported as missing if the content can't be read back
+            !(page.has_anchor(anchor) || page.has_anchor_id(anchor).unwrap_or(false))
         }
     });
 
     // Format faulty entries into error messages, and collect them.
     let messages = missing_targets
         .map(|(page_path, md_path, anchor)| {
-            format!(
+            let message = format!(
                 "The anchor in the link `@/{}#{}` in {} does not exist.",
                 md_path,
                 anchor,
                 page_path.to_string_lossy(),
-            )
+            );
+            (page_path, message)
         })
         .collect::<Vec<_>>();
 
     // Finally emit a summary, and return overall anchors-checking result.
     if messages.is_empty() {
-        println!("> Successfully checked {} internal link(s) with anchors.", anchors_total);
+        console::plain(&format!(
+            "> Successfully checked {} internal link(s) with anchors.",
+            anchors_total
+        ));
     } else {
-        println!(
+        console::plain(&format!(
             "> Checked {} internal link(s) with anchors: {} target(s) missing.",
             anchors_total,
             messages.len(),
-        );
+        ));
     }
     messages
 }
 
+/// Lists the absolute links to the site that don't start with its `base_url`, such as `http://`
+/// links once the site is on `https://`, with what they should be replaced with.
+/// The ones rewritten with `rewrite_self_links` are not there anymore.
+pub fn check_self_links(site: &Site) -> Vec<(PathBuf, String)> {
+    let library = site.library.read().unwrap();
+    let page_links = library
+        .pages
+        .values()
+        .flat_map(|p| p.mixed_self_links.iter().map(move |l| (&p.file.path, l)));
+    let section_links = library
+        .sections
+        .values()
+        .flat_map(|s| s.mixed_self_links.iter().map(move |l| (&s.file.path, l)));
+
+    let mut messages: Vec<_> = page_links
+        .chain(section_links)
+        .map(|(path, (link, suggestion))| {
+            let message = format!(
+                "The link `{}` in {} points to the site without using its base_url, use `{}` instead.",
+                link,
+                path.strip_prefix(&site.base_path).unwrap_or(path).display(),
+                suggestion,
+            );
+            (path.clone(), message)
+        })
+        .collect();
+    messages.sort();
+    messages
+}
+
 fn should_skip_by_prefix(link: &str, skip_prefixes: &[String]) -> bool {
     skip_prefixes.iter().any(|prefix| link.starts_with(prefix))
 }
@@ -122,9 +158,10 @@ fn get_link_domain(link: &str) -> Result<String> {
     };
 }
 
-/// Checks all external links and returns all the errors that were encountered.
+/// Checks all external links and returns all the errors that were encountered, along with the
+/// file containing the link.
 /// Empty vec == all good
-pub fn check_external_links(site: &Site) -> Vec<String> {
+pub fn check_external_links(site: &Site) -> Result<Vec<(PathBuf, String)>> {
     let library = site.library.write().expect("Get lock for check_external_links");
 
     struct LinkDef {
@@ -143,7 +180,7 @@ pub fn check_external_links(site: &Site) -> Vec<String> {
         }
     }
 
-    let mut messages: Vec<String> = vec![];
+    let mut messages: Vec<(PathBuf, String)> = vec![];
     let mut external_links = Vec::new();
     for p in library.pages.values() {
         external_links.push((&p.file.path, &p.external_links));
@@ -173,7 +210,7 @@ pub fn check_external_links(site: &Site) -> Vec<String> {
                         // We could use the messages.len() to keep track of them for below
                         // but it's more explicit this way
                         invalid_url_links += 1;
-                        messages.push(err.to_string());
+                        messages.push((file_path.clone(), err.to_string()));
                     }
                 }
             }
@@ -186,7 +223,7 @@ pub fn check_external_links(site: &Site) -> Vec<String> {
     )
     .len();
 
-    println!(
+    console::plain(&format!(
         "Checking {} external link(s). Skipping {} external link(s).{}",
         unique_links_count,
         skipped_link_count,
@@ -195,15 +232,15 @@ pub fn check_external_links(site: &Site) -> Vec<String> {
         } else {
             format!(" {} link(s) had unparseable URLs.", invalid_url_links)
         }
-    );
+    ));
 
     if checked_links.is_empty() {
-        return Vec::new();
+        return Ok(Vec::new());
     }
 
     // error out if we're in error mode and any external URLs couldn't be parsed
     if site.config.link_checker.external_level == LinkCheckerLevel::Error && !messages.is_empty() {
-        return messages;
+        return Ok(messages);
     }
 
     let mut links_by_domain: HashMap<&str, Vec<&LinkDef>> = HashMap::new();
@@ -274,23 +311,25 @@ pub fn check_external_links(site: &Site) -> Vec<String> {
                     .collect::<Vec<_>>()
             });
 
-            println!(
+            console::plain(&format!(
                 "> Checked {} external link(s): {} error(s) found.",
                 unique_links_count,
                 errors.len()
-            );
+            ));
 
             for (page_path, link, check_res) in errors {
-                messages.push(format!(
+                let message = format!(
                     "Broken link in {} to {}: {}",
                     page_path.to_string_lossy(),
                     link,
                     link_checker::message(&check_res)
-                ));
+                );
+                messages.push((page_path.clone(), message));
             }
         }
-        Err(pool_err) => messages.push(pool_err.to_string()),
+        // Reported like a broken link so that it follows `external_level`
+        Err(pool_err) => messages.push((site.base_path.clone(), pool_err.to_string())),
     }
 
-    messages
+    Ok(messages)
 }
@@ -0,0 +1,269 @@
+//! The manifest of the files written by `zola build`, for the tools post-processing the output
+//! directory that need to know where each file comes from.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use libs::serde_json;
+use serde::Serialize;
+
+use config::Config;
+use content::{Archive, Library, Taxonomy};
+use errors::Result;
+
+use crate::build_hash::hash_output_files;
+
+/// The name of the manifest file, at the root of the output directory
+pub const MANIFEST_FILENAME: &str = "zola-manifest.json";
+
+/// A file of the output directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ManifestEntry {
+    /// The path of the file, relative to the output directory and with `/` separators
+    pub path: String,
+    /// Where the file comes from: the `content/...` path of a page or section, or one of
+    /// `sitemap`, `feed`, `taxonomy`, `archive`, `alias`, `redirects`, `search`, `robots`,
+    /// `error_page` and `static`
+    pub source: String,
+    pub permalink: String,
+    /// The SHA-256 of the content of the file, in hexadecimal
+    pub hash: String,
+}
+
+/// Turns a permalink path like `/blog/hello/` into an output directory like `blog/hello/`
+fn output_dir(path: &str) -> String {
+    let dir = path.trim_start_matches('/');
+    if dir.is_empty() || dir.ends_with('/') {
+        dir.to_string()
+    } else {
+        format!("{}/", dir)
+    }
+}
+
+/// Knows which page, section or generated aggregate each output file comes from
+struct Sources<'a> {
+    feed_filenames: HashSet<&'a str>,
+    /// The `feed_filename` of the config, which can be in a subdirectory
+    feed_output: Option<&'a str>,
+    /// The `sitemap_filename` of the config, without and with its extension
+    sitemap: (&'a str, &'a str),
+    /// The redirect rules files of `redirect_output`
+    redirect_files: HashSet<&'static str>,
+    /// The output directory of each page and section, with its source and its assets
+    content_dirs: HashMap<String, (String, HashSet<String>)>,
+    /// Where the pagers of each paginated section are, with the source of the section
+    pager_dirs: Vec<(String, String)>,
+    taxonomy_dirs: Vec<String>,
+    archive_dirs: Vec<String>,
+    aliases: HashSet<String>,
+}
+
+impl<'a> Sources<'a> {
+    fn new(
+        config: &'a Config,
+        library: &Library,
+        taxonomies: &[Taxonomy],
+        archives: &[Archive],
+    ) -> Self {
+        let mut feed_filenames: HashSet<_> =
+            config.feed_filenames.iter().map(|f| f.as_str()).collect();
+        for language in config.languages.values() {
+            feed_filenames.extend(language.feed_filenames.iter().map(|f| f.as_str()));
+        }
+
+        let redirect_files =
+            config.redirect_output.iter().map(|o| crate::redirects::rules_filename(*o)).collect();
+
+        let mut content_dirs = HashMap::new();
+        let mut pager_dirs = Vec::new();
+        let mut aliases = HashSet::new();
+        let mut add_aliases = |page_aliases: &[String]| {
+            for alias in page_aliases {
+                let alias = alias.trim_start_matches('/');
+                if alias.ends_with(".html") {
+                    aliases.insert(alias.to_string());
+                } else {
+                    aliases.insert(format!("{}index.html", output_dir(alias)));
+                }
+            }
+        };
+        let asset_names = |assets: &[std::path::PathBuf]| -> HashSet<String> {
+            assets
+                .iter()
+                .filter_map(|a| a.file_name())
+                .map(|n| n.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        for section in library.sections.values() {
+            let source = format!("content/{}", section.file.relative);
+            let dir = output_dir(&section.path);
+            if section.meta.paginate_by.is_some() {
+                pager_dirs
+                    .push((format!("{}{}/", dir, section.meta.paginate_path), source.clone()));
+            }
+            add_aliases(&section.meta.aliases);
+            content_dirs.insert(dir, (source, asset_names(&section.assets)));
+        }
+        for page in library.pages.values() {
+            let source = format!("content/{}", page.file.relative);
+            add_aliases(&page.meta.aliases);
+            content_dirs.insert(output_dir(&page.path), (source, asset_names(&page.assets)));
+        }
+
+        let taxonomy_dirs = taxonomies.iter().map(|t| output_dir(&t.path)).collect();
+        let archive_dirs = archives.iter().map(|a| output_dir(&a.path)).collect();
+
+        let sitemap_filename = config.sitemap_filename.as_str();
+        let sitemap_stem = match sitemap_filename.rsplit_once('.') {
+            Some((stem, extension)) if !extension.contains('/') => stem,
+            _ => sitemap_filename,
+        };
+
+        Sources {
+            feed_filenames,
+            feed_output: config.feed_filename.as_deref(),
+            sitemap: (sitemap_stem, sitemap_filename),
+            redirect_files,
+            content_dirs,
+            pager_dirs,
+            taxonomy_dirs,
+            archive_dirs,
+            aliases,
+        }
+    }
+
+    fn source_of(&self, path: &str) -> String {
+        let (dir, filename) = match path.rfind('/') {
+            Some(i) => (&path[..=i], &path[i + 1..]),
+            None => ("", path),
+        };
+
+        if self.feed_filenames.contains(filename)
+            || self.feed_output.is_some_and(|f| path == f || path.ends_with(&format!("/{}", f)))
+        {
+            return "feed".to_string();
+        }
+        // The sitemap and the numbered ones it is split into
+        let (sitemap_stem, sitemap_filename) = self.sitemap;
+        let sitemap_number = path
+            .strip_prefix(sitemap_stem)
+            .and_then(|rest| rest.strip_suffix(&sitemap_filename[sitemap_stem.len()..]));
+        if path == sitemap_filename
+            || sitemap_number
+                .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        {
+            return "sitemap".to_string();
+        }
+        if dir.is_empty() {
+            let generated = match filename {
+                "robots.txt" => Some("robots"),
+                "404.html" | "50x.html" => Some("error_page"),
+                "elasticlunr.min.js" | "opensearch.xml" => Some("search"),
+                _ if filename.starts_with("search_index.")
+                    || filename.starts_with("search_corpus") =>
+                {
+                    Some("search")
+                }
+                _ => None,
+            };
+            if self.redirect_files.contains(filename) {
+                return "redirects".to_string();
+            }
+            if let Some(source) = generated {
+                return source.to_string();
+            }
+        }
+        if self.aliases.contains(path) {
+            return "alias".to_string();
+        }
+        if let Some((source, assets)) = self.content_dirs.get(dir) {
+            if filename.starts_with("index.") || assets.contains(filename) {
+                return source.clone();
+            }
+        }
+        for (pager_dir, source) in &self.pager_dirs {
+            if let Some(rest) = path.strip_prefix(pager_dir.as_str()) {
+                if rest.split('/').next().is_some_and(|n| n.parse::<usize>().is_ok()) {
+                    return source.clone();
+                }
+            }
+        }
+        if self.taxonomy_dirs.iter().any(|d| path.starts_with(d.as_str())) {
+            return "taxonomy".to_string();
+        }
+        if self.archive_dirs.iter().any(|d| path.starts_with(d.as_str())) {
+            return "archive".to_string();
+        }
+
+        "static".to_string()
+    }
+}
+
+/// Lists every file of the output directory, sorted by path, except the manifest itself
+pub fn build_manifest(
+    output_path: &Path,
+    config: &Config,
+    library: &Library,
+    taxonomies: &[Taxonomy],
+    archives: &[Archive],
+) -> Result<Vec<ManifestEntry>> {
+    let sources = Sources::new(config, library, taxonomies, archives);
+    let base_url = config.base_url.trim_end_matches('/');
+
+    let entries = hash_output_files(output_path, config)?
+        .into_iter()
+        .map(|(path, hash)| ManifestEntry {
+            source: sources.source_of(&path),
+            permalink: format!("{}/{}", base_url, path.trim_end_matches("index.html")),
+            hash,
+            path,
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// Writes the manifest as JSON at the root of the output directory
+pub fn write_manifest(output_path: &Path, entries: &[ManifestEntry]) -> Result<()> {
+    let json = serde_json::to_string_pretty(entries)?;
+    utils::fs::create_file(&output_path.join(MANIFEST_FILENAME), &json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_get_output_dirs() {
+        assert_eq!(output_dir("/"), "");
+        assert_eq!(output_dir("/blog/hello/"), "blog/hello/");
+        assert_eq!(output_dir("/tags"), "tags/");
+    }
+
+    #[test]
+    fn can_find_source_of_generated_files() {
+        let config = Config::default();
+        let library = Library::default();
+        let sources = Sources::new(&config, &library, &[], &[]);
+        assert_eq!(sources.source_of("atom.xml"), "feed");
+        assert_eq!(sources.source_of("fr/atom.xml"), "feed");
+        assert_eq!(sources.source_of("sitemap.xml"), "sitemap");
+        assert_eq!(sources.source_of("sitemap2.xml"), "sitemap");
+        assert_eq!(sources.source_of("search_index.en.js"), "search");
+        assert_eq!(sources.source_of("robots.txt"), "robots");
+        assert_eq!(sources.source_of("404.html"), "error_page");
+        assert_eq!(sources.source_of("css/sitemap.xml"), "static");
+        assert_eq!(sources.source_of("style.css"), "static");
+
+        let mut config = Config::default();
+        config.feed_filename = Some("feed/index.xml".to_string());
+        config.sitemap_filename = "maps/sitemap_index.xml".to_string();
+        let sources = Sources::new(&config, &library, &[], &[]);
+        assert_eq!(sources.source_of("feed/index.xml"), "feed");
+        assert_eq!(sources.source_of("blog/feed/index.xml"), "feed");
+        assert_eq!(sources.source_of("maps/sitemap_index.xml"), "sitemap");
+        assert_eq!(sources.source_of("maps/sitemap_index12.xml"), "sitemap");
+        assert_eq!(sources.source_of("sitemap.xml"), "static");
+    }
+}
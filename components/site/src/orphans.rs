@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use utils::data::PAGE_DATA_FILENAMES;
+
+use crate::{feeds, Site};
+
+/// Finds the pages that cannot be reached from anything Zola renders: they are not listed in
+/// any rendered section or taxonomy term, not linked from any page or section and not part of
+/// the main feeds.
+/// Pages with `orphan = true` or `unlisted = true` in their front matter are skipped.
+pub fn find_orphan_pages(site: &Site) -> Vec<PathBuf> {
+    let library = site.library.read().unwrap();
+    let mut reachable: HashSet<&Path> = HashSet::new();
+
+    for section in library.sections.values().filter(|s| s.meta.render) {
+        reachable.extend(section.pages.iter().map(|p| p.as_path()));
+    }
+
+    for taxonomy in site.taxonomies.iter().filter(|t| t.kind.render) {
+        for term in &taxonomy.items {
+            reachable.extend(term.pages.iter().map(|p| p.as_path()));
+        }
+    }
+
+    for lang in site.config.languages.keys() {
+        let pages = feeds::site_feed_pages(&site.config, &library, lang);
+        reachable.extend(pages.into_iter().map(|p| p.file.path.as_path()));
+    }
+
+    let mut orphans: Vec<_> = library
+        .pages
+        .values()
+        .filter(|p| p.meta.render && !p.meta.orphan && !p.meta.unlisted)
+        .filter(|p| !reachable.contains(p.file.path.as_path()))
+        .filter(|p| !library.backlinks.contains_key(&p.file.relative))
+        .map(|p| p.file.path.clone())
+        .collect();
+    orphans.sort();
+    orphans
+}
+
+/// Finds the colocated assets of the pages that are not referenced by their page, data files aside.
+/// An asset is referenced if its filename appears in the page content, either in the Markdown
+/// or in the rendered HTML. When `check_output` is set, the HTML file written in the output
+/// directory is searched as well, to catch assets used by templates.
+/// Returns the assets grouped by page.
+pub fn find_unreferenced_assets(site: &Site, check_output: bool) -> Vec<(PathBuf, Vec<PathBuf>)> {
+    let library = site.library.read().unwrap();
+    let mut unreferenced = Vec::new();
+
+    for page in library.pages.values() {
+        if page.assets.is_empty() {
+            continue;
+        }
+        let rendered = if check_output && page.meta.render {
+            let output = site.output_path.join(page.path.trim_matches('/')).join("index.html");
+            fs::read_to_string(output).unwrap_or_default()
+        } else {
+            String::new()
+        };
+        // Its assets can't be told unreferenced without it
+        let Ok(content) = page.content() else { continue };
+
+        let mut assets: Vec<_> = page
+            .assets
+            .iter()
+            .filter(|asset| {
+                let filename = match asset.file_name() {
+                    Some(f) => f.to_string_lossy(),
+                    None => return false,
+                };
+                // Data files are loaded as `page.data`
+                if PAGE_DATA_FILENAMES.contains(&filename.as_ref()) {
+                    return false;
+                }
+                ![page.raw_content.as_str(), &*content, rendered.as_str()]
+                    .iter()
+                    .any(|text| text.contains(filename.as_ref()))
+            })
+            .cloned()
+            .collect();
+
+        if !assets.is_empty() {
+            assets.sort();
+            unreferenced.push((page.file.path.clone(), assets));
+        }
+    }
+
+    unreferenced.sort();
+    unreferenced
+}
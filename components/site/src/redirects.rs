@@ -0,0 +1,273 @@
+//! The redirect rules files read by hosts like Netlify, Vercel or Cloudflare Pages to do real
+//! redirects for the aliases of the pages and sections and the `redirects` of the config.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use libs::serde_json::{self, json, Value};
+
+use config::{Config, RedirectOutput};
+use content::{Archive, Library, Taxonomy};
+use errors::{bail, Context, Result};
+use utils::site::resolve_internal_link;
+
+/// A redirect written to the rules files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectRule {
+    /// The path redirected, starting with `/`
+    pub from: String,
+    /// A path of the site or an absolute URL
+    pub to: String,
+    pub status: u16,
+    /// Where the redirect comes from: the `content/...` path of a page or section, or `config.toml`
+    pub origin: String,
+}
+
+/// The paths of everything a redirect of the config can point to, with a trailing `/`
+fn known_paths(
+    library: &Library,
+    taxonomies: &[Taxonomy],
+    archives: &[Archive],
+) -> HashSet<String> {
+    let mut paths: HashSet<&str> = HashSet::new();
+    paths.extend(library.pages.values().map(|p| &*p.path));
+    paths.extend(library.sections.values().map(|s| &*s.path));
+    for taxonomy in taxonomies {
+        paths.insert(&taxonomy.path);
+        paths.extend(taxonomy.items.iter().map(|t| t.path.as_str()));
+    }
+    for archive in archives {
+        paths.insert(&archive.path);
+        paths.extend(archive.groups.iter().map(|g| g.path.as_str()));
+    }
+    paths.into_iter().map(|p| format!("{}/", p.trim_end_matches('/'))).collect()
+}
+
+/// Turns the `to` of a redirect of the config into a path of the site or an absolute URL,
+/// erroring if it doesn't point to a page, section, taxonomy, archive or static file
+fn resolve_target(
+    to: &str,
+    config: &Config,
+    permalinks: &HashMap<String, Arc<str>>,
+    known_paths: &HashSet<String>,
+    static_dirs: &[PathBuf],
+) -> Result<String> {
+    if to.contains("://") {
+        return Ok(to.to_string());
+    }
+    if to.starts_with("@/") {
+        let permalink = resolve_internal_link(to, permalinks)?.permalink;
+        let base_url = config.base_url.trim_end_matches('/');
+        return Ok(permalink.strip_prefix(base_url).unwrap_or(&permalink).to_string());
+    }
+    if !to.starts_with('/') {
+        bail!("it should be a path starting with `/`, an internal link or an absolute URL");
+    }
+
+    let path = to.split(['?', '#']).next().unwrap_or_default();
+    let is_known = known_paths.contains(&format!("{}/", path.trim_end_matches('/')))
+        || static_dirs.iter().any(|dir| dir.join(path.trim_start_matches('/')).is_file());
+    if !is_known {
+        bail!("no page, section, taxonomy, archive or static file is at `{}`", path);
+    }
+    Ok(to.to_string())
+}
+
+/// Gathers the aliases of the pages and sections and the `redirects` of the config, sorted by
+/// the path they redirect. Two redirects of the same path to different targets are an error.
+pub fn find_redirects(
+    config: &Config,
+    library: &Library,
+    taxonomies: &[Taxonomy],
+    archives: &[Archive],
+    permalinks: &HashMap<String, Arc<str>>,
+    static_dirs: &[PathBuf],
+) -> Result<Vec<RedirectRule>> {
+    let mut rules = Vec::new();
+    let aliases = library
+        .pages
+        .values()
+        .map(|p| (&p.meta.aliases, &p.path, &p.file.relative))
+        .chain(library.sections.values().map(|s| (&s.meta.aliases, &s.path, &s.file.relative)));
+    for (aliases, path, relative) in aliases {
+        for alias in aliases {
+            rules.push(RedirectRule {
+                from: format!("/{}", alias.trim_start_matches('/')),
+                to: path.to_string(),
+                status: 301,
+                origin: format!("content/{}", relative),
+            });
+        }
+    }
+
+    let known_paths = known_paths(library, taxonomies, archives);
+    for redirect in &config.redirects {
+        let to = resolve_target(&redirect.to, config, permalinks, &known_paths, static_dirs)
+            .with_context(|| {
+                format!("The redirect from `{}` to `{}` is invalid", redirect.from, redirect.to)
+            })?;
+        rules.push(RedirectRule {
+            from: redirect.from.clone(),
+            to,
+            status: redirect.status,
+            origin: "config.toml".to_string(),
+        });
+    }
+
+    // `/old` and `/old/` are the same for the hosts
+    let mut by_source: BTreeMap<String, RedirectRule> = BTreeMap::new();
+    let mut conflicts = Vec::new();
+    for rule in rules {
+        let key = match rule.from.trim_end_matches('/') {
+            "" => "/".to_string(),
+            k => k.to_string(),
+        };
+        match by_source.get(&key) {
+            Some(existing) if existing.to != rule.to || existing.status != rule.status => {
+                conflicts.push(format!(
+                    "- `{}` redirects to `{}` in {} and to `{}` in {}\n",
+                    key, existing.to, existing.origin, rule.to, rule.origin
+                ));
+            }
+            Some(_) => (),
+            None => {
+                by_source.insert(key, rule);
+            }
+        }
+    }
+    if !conflicts.is_empty() {
+        conflicts.sort();
+        bail!("Found conflicting redirects:\n{}", conflicts.concat());
+    }
+
+    Ok(by_source.into_values().collect())
+}
+
+/// The content of the `_redirects` file, after the rules of the one of `static` if there is one
+pub fn render_netlify(rules: &[RedirectRule], existing: Option<&str>) -> String {
+    let mut content = String::new();
+    if let Some(existing) = existing {
+        content.push_str(existing.trim_end());
+        content.push('\n');
+    }
+    for rule in rules {
+        content.push_str(&format!("{} {} {}\n", rule.from, rule.to, rule.status));
+    }
+    content
+}
+
+/// The content of the `vercel.json` file, adding the rules to the `redirects` of the one of
+/// `static` if there is one
+pub fn render_vercel(rules: &[RedirectRule], existing: Option<&str>) -> Result<String> {
+    let mut vercel: Value = match existing {
+        Some(existing) => serde_json::from_str(existing).context("Failed to parse vercel.json")?,
+        None => json!({}),
+    };
+    let Some(object) = vercel.as_object_mut() else {
+        bail!("vercel.json should contain an object");
+    };
+    let redirects = object.entry("redirects").or_insert_with(|| json!([]));
+    let Some(redirects) = redirects.as_array_mut() else {
+        bail!("The `redirects` of vercel.json should be an array");
+    };
+    redirects.extend(rules.iter().map(
+        |rule| json!({"source": rule.from, "destination": rule.to, "statusCode": rule.status}),
+    ));
+
+    Ok(serde_json::to_string_pretty(&vercel)?)
+}
+
+/// The name of the file each host reads its rules from
+pub fn rules_filename(output: RedirectOutput) -> &'static str {
+    match output {
+        RedirectOutput::Netlify => "_redirects",
+        RedirectOutput::Vercel => "vercel.json",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::Redirect;
+    use content::{Page, Section};
+
+    fn library() -> Library {
+        let mut library = Library::default();
+        let mut section = Section::default();
+        section.file.relative = "blog/_index.md".to_string();
+        section.path = "/blog/".into();
+        section.meta.aliases = vec!["posts/".to_string()];
+        library.sections.insert(PathBuf::from("content/blog/_index.md"), section);
+        let mut page = Page::default();
+        page.file.relative = "blog/hello.md".to_string();
+        page.path = "/blog/hello/".into();
+        page.meta.aliases = vec!["/hello/".to_string(), "old/hello.html".to_string()];
+        library.pages.insert(PathBuf::from("content/blog/hello.md"), page);
+        library
+    }
+
+    fn redirect(from: &str, to: &str) -> Redirect {
+        Redirect { from: from.to_string(), to: to.to_string(), ..Redirect::default() }
+    }
+
+    #[test]
+    fn can_find_redirects() {
+        let mut config = Config::default_for_test();
+        config.redirects = vec![
+            redirect("/chat/", "https://chat.example.com"),
+            redirect("/archive", "@/blog/_index.md"),
+            redirect("/hello", "/blog/hello/"),
+        ];
+        let permalink = format!("{}/blog/", config.base_url);
+        let permalinks = HashMap::from([("blog/_index.md".to_string(), permalink.into())]);
+        let rules = find_redirects(&config, &library(), &[], &[], &permalinks, &[]).unwrap();
+        let rules: Vec<_> =
+            rules.iter().map(|r| (r.from.as_str(), r.to.as_str(), r.origin.as_str())).collect();
+        assert_eq!(
+            rules,
+            vec![
+                ("/archive", "/blog/", "config.toml"),
+                ("/chat/", "https://chat.example.com", "config.toml"),
+                ("/hello/", "/blog/hello/", "content/blog/hello.md"),
+                ("/old/hello.html", "/blog/hello/", "content/blog/hello.md"),
+                ("/posts/", "/blog/", "content/blog/_index.md"),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_on_unresolved_and_conflicting_redirects() {
+        let mut config = Config::default_for_test();
+        config.redirects = vec![redirect("/docs/", "/documentation/")];
+        let err = find_redirects(&config, &library(), &[], &[], &HashMap::new(), &[]).unwrap_err();
+        assert!(format!("{:#}", err).contains("/documentation/"));
+
+        config.redirects = vec![redirect("/hello", "/blog/")];
+        let err = find_redirects(&config, &library(), &[], &[], &HashMap::new(), &[]).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("content/blog/hello.md"), "{}", message);
+        assert!(message.contains("config.toml"), "{}", message);
+    }
+
+    #[test]
+    fn can_render_rules_files() {
+        let rules = vec![RedirectRule {
+            from: "/old/".to_string(),
+            to: "/new/".to_string(),
+            status: 301,
+            origin: "config.toml".to_string(),
+        }];
+        assert_eq!(render_netlify(&rules, None), "/old/ /new/ 301\n");
+        assert_eq!(render_netlify(&rules, Some("/a /b 302\n\n")), "/a /b 302\n/old/ /new/ 301\n");
+
+        let vercel = render_vercel(&rules, Some(r#"{"cleanUrls": true}"#)).unwrap();
+        let vercel: Value = serde_json::from_str(&vercel).unwrap();
+        assert_eq!(vercel["cleanUrls"], json!(true));
+        assert_eq!(
+            vercel["redirects"],
+            json!([{"source": "/old/", "destination": "/new/", "statusCode": 301}])
+        );
+        assert!(render_vercel(&rules, Some("[]")).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use std::fs;
+use std::path::Path;
+
+use libs::once_cell::sync::Lazy;
+use libs::rayon::prelude::*;
+use libs::regex::{Captures, Regex};
+use libs::url::Url;
+use libs::walkdir::WalkDir;
+
+use errors::{Context, Result};
+use utils::fs::create_file;
+use utils::html::{decode_entities, find_ignore_ascii_case};
+
+static URL_ATTRIBUTE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)(\s(?:href|src|srcset|action|poster|content)\s*=\s*)(?:"([^"]*)"|'([^']*)'|([^\s"'=<>`]+))"#,
+    )
+    .unwrap()
+});
+static META_REFRESH_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^(\s*\d+\s*;\s*url=)(.*)$").unwrap());
+
+/// Turns the internal absolute URLs of the HTML files in the output directory into relative ones
+/// and points directory URLs to their `index.html`, so the site can be browsed from the
+/// filesystem with `file://` URLs.
+/// Feeds and sitemaps are not HTML and keep their absolute URLs.
+pub fn make_urls_relative(output_path: &Path, base_url: &str) -> Result<()> {
+    let html_files: Vec<_> = WalkDir::new(output_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension() == Some("html".as_ref()))
+        .map(|e| e.into_path())
+        .collect();
+
+    html_files.par_iter().try_for_each(|path| {
+        let relative_path = path.strip_prefix(output_path).unwrap();
+        let depth = relative_path.components().count() - 1;
+        let html = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read HTML file {}", path.display()))?;
+        let rewritten = rewrite_html(&html, base_url, depth);
+        if rewritten != html {
+            create_file(path, &rewritten)?;
+        }
+        Ok(())
+    })
+}
+
+/// Rewrites the URL-holding attributes of the tags in that HTML, leaving comments and the
+/// contents of `<script>` and `<style>` alone.
+/// `depth` is the number of directories between the output root and the HTML file.
+pub fn rewrite_html(html: &str, base_url: &str, depth: usize) -> String {
+    let rewriter = UrlRewriter::new(base_url, depth);
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with("<!--") {
+            let end = rest.find("-->").map(|i| i + 3).unwrap_or(rest.len());
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        let is_tag = rest[1..].starts_with(|c: char| c.is_ascii_alphabetic());
+        let end = if is_tag { find_tag_end(rest) } else { None };
+        let end = match end {
+            Some(end) => end,
+            None => {
+                out.push('<');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+
+        let tag = &rest[..end];
+        out.push_str(&rewriter.rewrite_tag(tag));
+        rest = &rest[end..];
+
+        // Skip raw text elements, their content is not HTML
+        let tag_name: String = tag[1..].chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+        for raw in ["script", "style"] {
+            if tag_name.eq_ignore_ascii_case(raw) {
+                let closing = format!("</{}", raw);
+                let content_end = find_ignore_ascii_case(rest, &closing).unwrap_or(rest.len());
+                out.push_str(&rest[..content_end]);
+                rest = &rest[content_end..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Finds the end of the tag starting at the beginning of `html`, skipping quoted attribute values
+fn find_tag_end(html: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => (),
+        }
+    }
+    None
+}
+
+fn encode_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;").replace('\'', "&#x27;")
+}
+
+struct UrlRewriter<'a> {
+    base_url: &'a str,
+    /// The path part of the base URL, eg `/docs/` for `https://example.com/docs`
+    base_path: String,
+    prefix: String,
+}
+
+impl<'a> UrlRewriter<'a> {
+    fn new(base_url: &'a str, depth: usize) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        let base_path = match Url::parse(base_url) {
+            Ok(url) => format!("{}/", url.path().trim_end_matches('/')),
+            Err(_) => "/".to_string(),
+        };
+        UrlRewriter { base_url, base_path, prefix: "../".repeat(depth) }
+    }
+
+    fn rewrite_tag(&self, tag: &str) -> String {
+        URL_ATTRIBUTE_RE
+            .replace_all(tag, |caps: &Captures| {
+                let attribute = caps[1].trim_start().to_ascii_lowercase();
+                let (value, quote) = match (caps.get(2), caps.get(3), caps.get(4)) {
+                    (Some(v), _, _) => (v.as_str(), "\""),
+                    (_, Some(v), _) => (v.as_str(), "'"),
+                    (_, _, Some(v)) => (v.as_str(), ""),
+                    _ => unreachable!(),
+                };
+
+                // Templates often escape the URLs, eg `/` as `&#x2F;`
+                let value = decode_entities(value);
+                let rewritten = if attribute.starts_with("srcset") {
+                    Some(self.rewrite_srcset(&value))
+                } else if attribute.starts_with("content") {
+                    // Only the `<meta http-equiv="refresh">` of aliases holds a URL
+                    META_REFRESH_RE.captures(&value).and_then(|c| {
+                        self.rewrite_url(&c[2]).map(|url| format!("{}{}", &c[1], url))
+                    })
+                } else {
+                    self.rewrite_url(&value)
+                };
+
+                match rewritten {
+                    Some(v) => format!("{}{}{}{}", &caps[1], quote, encode_attribute(&v), quote),
+                    None => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+
+    fn rewrite_srcset(&self, srcset: &str) -> String {
+        srcset
+            .split(',')
+            .map(|candidate| {
+                let candidate = candidate.trim();
+                let (url, descriptor) = match candidate.find(char::is_whitespace) {
+                    Some(idx) => candidate.split_at(idx),
+                    None => (candidate, ""),
+                };
+                let url = self.rewrite_url(url).unwrap_or_else(|| url.to_string());
+                format!("{}{}", url, descriptor)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Returns the relative version of the URL if it points to the site
+    fn rewrite_url(&self, url: &str) -> Option<String> {
+        let path = if let Some(rest) = url.strip_prefix(self.base_url) {
+            if !(rest.is_empty() || rest.starts_with(['/', '?', '#'])) {
+                return None;
+            }
+            rest.trim_start_matches('/')
+        } else if url.starts_with('/') && !url.starts_with("//") {
+            url.strip_prefix(self.base_path.as_str())
+                .or_else(|| (url == self.base_path.trim_end_matches('/')).then_some(""))
+                .unwrap_or(&url[1..])
+        } else {
+            return None;
+        };
+
+        let end = path.find(['?', '#']).unwrap_or(path.len());
+        let (path, suffix) = path.split_at(end);
+        let index = if path.is_empty() || path.ends_with('/') { "index.html" } else { "" };
+        Some(format!("{}{}{}{}", self.prefix, path, index, suffix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_URL: &str = "https://example.com";
+
+    #[test]
+    fn can_rewrite_links_of_nested_pages() {
+        let html = r#"<a href="https://example.com/">Home</a><a href="https://example.com/blog/other/#intro">Other</a><a href='/tags/rust/'>Rust</a>"#;
+        assert_eq!(
+            rewrite_html(html, BASE_URL, 2),
+            r#"<a href="../../index.html">Home</a><a href="../../blog/other/index.html#intro">Other</a><a href='../../tags/rust/index.html'>Rust</a>"#
+        );
+        assert_eq!(
+            rewrite_html(r#"<a href="https://example.com">Home</a>"#, BASE_URL, 0),
+            r#"<a href="index.html">Home</a>"#
+        );
+    }
+
+    #[test]
+    fn can_rewrite_assets() {
+        let html = r#"<link rel="stylesheet" href="https://example.com/site.css?h=123"><img src=/blog/post/cat.jpg srcset="https://example.com/cat-1x.jpg 1x, /cat-2x.jpg 2x"><script src="https://example.com/app.js"></script>"#;
+        assert_eq!(
+            rewrite_html(html, BASE_URL, 1),
+            r#"<link rel="stylesheet" href="../site.css?h=123"><img src=../blog/post/cat.jpg srcset="../cat-1x.jpg 1x, ../cat-2x.jpg 2x"><script src="../app.js"></script>"#
+        );
+    }
+
+    #[test]
+    fn leaves_other_urls_alone() {
+        let html = r##"<a href="#top">Top</a><a href="https://example.community/">Other</a><a href="//cdn.com/a.js">CDN</a><a href="mailto:a@b.c">Mail</a><meta name="description" content="/not-a-link/">"##;
+        assert_eq!(rewrite_html(html, BASE_URL, 1), html);
+    }
+
+    #[test]
+    fn skips_comments_scripts_and_text() {
+        let html = r#"<p>See href="/a/" and 1 < 2</p><!-- <a href="/b/"> --><script>var a = '<a href="/c/">';</script><style>a { background: url(/d.png) }</style><SCRIPT>var b = '<a href="/e/">';</Script>"#;
+        assert_eq!(rewrite_html(html, BASE_URL, 1), html);
+    }
+
+    #[test]
+    fn can_rewrite_escaped_urls() {
+        let html =
+            r#"<a href="https:&#x2F;&#x2F;example.com&#x2F;tags&#x2F;c&#x2F;?a=1&amp;b=2">C</a>"#;
+        assert_eq!(
+            rewrite_html(html, BASE_URL, 1),
+            r#"<a href="../tags/c/index.html?a=1&amp;b=2">C</a>"#
+        );
+    }
+
+    #[test]
+    fn can_rewrite_alias_redirects() {
+        let html = r#"<meta http-equiv="refresh" content="0; url=https://example.com/blog/new/"><link rel="canonical" href="https://example.com/blog/new/">"#;
+        assert_eq!(
+            rewrite_html(html, BASE_URL, 2),
+            r#"<meta http-equiv="refresh" content="0; url=../../blog/new/index.html"><link rel="canonical" href="../../blog/new/index.html">"#
+        );
+    }
+
+    #[test]
+    fn handles_base_url_with_a_path() {
+        let html =
+            r#"<a href="https://example.com/docs/intro/">Intro</a><a href="/docs/">Docs</a>"#;
+        assert_eq!(
+            rewrite_html(html, "https://example.com/docs/", 1),
+            r#"<a href="../intro/index.html">Intro</a><a href="../index.html">Docs</a>"#
+        );
+    }
+}
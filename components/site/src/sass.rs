@@ -9,7 +9,8 @@ use crate::anyhow;
 use errors::{bail, Result};
 use utils::fs::{create_directory, create_file};
 
-pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
+/// Compiles the non-partial Sass files of `base_path/sass` and returns the paths of the CSS files written
+pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<Vec<PathBuf>> {
     create_directory(output_path)?;
 
     let sass_path = {
@@ -49,7 +50,7 @@ pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
         }
     }
 
-    Ok(())
+    Ok(compiled_paths.into_iter().map(|(_, css_path)| css_path).collect())
 }
 
 fn is_partial_scss(entry: &DirEntry) -> bool {
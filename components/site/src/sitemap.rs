@@ -5,7 +5,7 @@ use std::hash::{Hash, Hasher};
 use serde::Serialize;
 
 use config::Config;
-use content::{Library, Taxonomy};
+use content::{Archive, Library, Taxonomy};
 use libs::tera::{Map, Value};
 use std::cmp::Ordering;
 
@@ -59,12 +59,13 @@ impl<'a> Ord for SitemapEntry<'a> {
 pub fn find_entries<'a>(
     library: &'a Library,
     taxonomies: &'a [Taxonomy],
+    archives: &'a [Archive],
     config: &'a Config,
 ) -> Vec<SitemapEntry<'a>> {
     let mut entries = HashSet::new();
 
     for p in library.pages.values() {
-        if !p.meta.render {
+        if !p.meta.render || p.meta.unlisted || p.meta.is_noindex() {
             continue;
         }
         let mut entry = SitemapEntry::new(
@@ -91,6 +92,13 @@ pub fn find_entries<'a>(
         }
     }
 
+    for archive in archives {
+        entries.insert(SitemapEntry::new(Cow::Borrowed(&archive.permalink), &None));
+        for group in &archive.groups {
+            entries.insert(SitemapEntry::new(Cow::Borrowed(&group.permalink), &None));
+        }
+    }
+
     for taxonomy in taxonomies {
         if !taxonomy.kind.render {
             continue;
@@ -1,8 +1,62 @@
 use crate::Site;
-use libs::tera::Result as TeraResult;
+use libs::tera::{Filter, Function, Result as TeraResult, Tera, Value};
+use std::collections::HashMap;
+use std::fmt;
 use std::sync::Arc;
 use templates::{filters, global_fns};
 
+/// The Tera functions and filters added by a program embedding Zola.
+/// They are registered after the built-in ones and can override them.
+#[derive(Clone, Default)]
+pub struct CustomTeraFns {
+    pub functions: Vec<(String, Arc<dyn Function>)>,
+    pub filters: Vec<(String, Arc<dyn Filter>)>,
+}
+
+impl CustomTeraFns {
+    pub fn register(&self, tera: &mut Tera) {
+        for (name, function) in &self.functions {
+            tera.register_function(name, SharedFunction(function.clone()));
+        }
+        for (name, filter) in &self.filters {
+            tera.register_filter(name, SharedFilter(filter.clone()));
+        }
+    }
+}
+
+impl fmt::Debug for CustomTeraFns {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomTeraFns")
+            .field("functions", &self.functions.iter().map(|(n, _)| n).collect::<Vec<_>>())
+            .field("filters", &self.filters.iter().map(|(n, _)| n).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+struct SharedFunction(Arc<dyn Function>);
+
+impl Function for SharedFunction {
+    fn call(&self, args: &HashMap<String, Value>) -> TeraResult<Value> {
+        self.0.call(args)
+    }
+
+    fn is_safe(&self) -> bool {
+        self.0.is_safe()
+    }
+}
+
+struct SharedFilter(Arc<dyn Filter>);
+
+impl Filter for SharedFilter {
+    fn filter(&self, value: &Value, args: &HashMap<String, Value>) -> TeraResult<Value> {
+        self.0.filter(value, args)
+    }
+
+    fn is_safe(&self) -> bool {
+        self.0.is_safe()
+    }
+}
+
 /// Adds global fns that are to be available to shortcodes while rendering markdown
 pub fn register_early_global_fns(site: &mut Site) -> TeraResult<()> {
     site.tera.register_filter(
@@ -10,15 +64,9 @@ pub fn register_early_global_fns(site: &mut Site) -> TeraResult<()> {
         filters::NumFormatFilter::new(&site.config.default_language),
     );
 
-    site.tera.register_function(
-        "get_url",
-        global_fns::GetUrl::new(
-            site.base_path.clone(),
-            site.config.clone(),
-            site.permalinks.clone(),
-            site.output_path.clone(),
-        ),
-    );
+    site.tera.register_filter("date", filters::DateFilter::new(site.config.clone()));
+
+    register_get_url(site);
     site.tera.register_function(
         "resize_image",
         global_fns::ResizeImage::new(
@@ -44,7 +92,25 @@ pub fn register_early_global_fns(site: &mut Site) -> TeraResult<()> {
             site.output_path.clone(),
         ),
     );
+    site.tera.register_function("now", global_fns::Now::new(site.config.build_info.unix_timestamp));
     site.tera.register_function("trans", global_fns::Trans::new(site.config.clone()));
+    site.tera.register_function("favicon_tags", global_fns::FaviconTags::new(&site.config));
+    site.tera.register_function(
+        "get_site_index",
+        global_fns::GetSiteIndex::new(site.site_index.clone()),
+    );
+    site.tera.register_function(
+        "get_ancestors",
+        global_fns::GetAncestors::new(
+            &site.config.default_language,
+            Arc::new(site.config.languages.keys().map(|s| s.to_string()).collect()),
+            site.site_index.clone(),
+            global_fns::MissingContentHandler::new(
+                site.config.missing_content,
+                site.content_misses.clone(),
+            ),
+        ),
+    );
     site.tera.register_function(
         "get_taxonomy_url",
         global_fns::GetTaxonomyUrl::new(
@@ -61,22 +127,52 @@ pub fn register_early_global_fns(site: &mut Site) -> TeraResult<()> {
             site.output_path.clone(),
         ),
     );
+    // Before the `markdown` filter as it gets a copy of Tera for shortcodes
+    site.tera_fns.register(&mut site.tera);
     site.tera.register_filter(
         "markdown",
         filters::MarkdownFilter::new(
             site.config.clone(),
+            site.data.clone(),
             site.permalinks.clone(),
             site.tera.clone(),
         ),
     );
+    let render_content_file = global_fns::RenderContentFile::new(
+        site.content_path.clone(),
+        site.config.clone(),
+        site.data.clone(),
+        site.permalinks.clone(),
+        &site.tera,
+    );
+    site.tera.register_function("render_content_file", render_content_file);
 
     Ok(())
 }
 
+/// `get_url` resolves internal links so it needs to be registered again when a page or section
+/// is added, for the shortcodes of its content to be able to link to it
+pub fn register_get_url(site: &mut Site) {
+    site.tera.register_function(
+        "get_url",
+        global_fns::GetUrl::new(
+            site.base_path.clone(),
+            site.config.clone(),
+            site.permalinks.clone(),
+            site.output_path.clone(),
+            site.asset_manifest.clone(),
+        ),
+    );
+}
+
 /// Functions filled once we have parsed all the pages/sections only, so not available in shortcodes
 pub fn register_tera_global_fns(site: &mut Site) {
     let language_list: Arc<Vec<String>> =
         Arc::new(site.config.languages.keys().map(|s| s.to_string()).collect());
+    let missing_content = global_fns::MissingContentHandler::new(
+        site.config.missing_content,
+        site.content_misses.clone(),
+    );
     site.tera.register_function(
         "get_page",
         global_fns::GetPage::new(
@@ -84,6 +180,7 @@ pub fn register_tera_global_fns(site: &mut Site) {
             &site.config.default_language,
             Arc::clone(&language_list),
             site.library.clone(),
+            missing_content.clone(),
         ),
     );
     site.tera.register_function(
@@ -93,6 +190,46 @@ pub fn register_tera_global_fns(site: &mut Site) {
             &site.config.default_language,
             Arc::clone(&language_list),
             site.library.clone(),
+            missing_content.clone(),
+        ),
+    );
+    let taxonomy_names: HashMap<String, Vec<String>> = site
+        .config
+        .languages
+        .iter()
+        .map(|(lang, options)| {
+            (lang.clone(), options.taxonomies.iter().map(|t| t.name.clone()).collect())
+        })
+        .collect();
+    site.tera.register_function(
+        "get_pages_by",
+        global_fns::GetPagesBy::new(
+            &site.config.default_language,
+            taxonomy_names.clone(),
+            site.config.slugify.taxonomies,
+            site.library.clone(),
+            missing_content.clone(),
+        ),
+    );
+    site.tera.register_function(
+        "get_random_page",
+        global_fns::GetRandomPage::new(
+            &site.config.default_language,
+            taxonomy_names,
+            site.config.slugify.taxonomies,
+            site.library.clone(),
+            missing_content.clone(),
+            site.build_seed,
+        ),
+    );
+    site.tera.register_function(
+        "group_pages_by_date",
+        global_fns::GroupPagesByDate::new(
+            &site.config.default_language,
+            site.library.clone(),
+            missing_content,
+            site.invalid_dates.clone(),
+            site.config.build_info.time(),
         ),
     );
     site.tera.register_function(
@@ -111,4 +248,5 @@ pub fn register_tera_global_fns(site: &mut Site) {
             site.library.clone(),
         ),
     );
+    site.tera_fns.register(&mut site.tera);
 }
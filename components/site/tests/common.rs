@@ -7,6 +7,7 @@ use path_slash::PathExt;
 use site::Site;
 use std::ffi::OsStr;
 use tempfile::{tempdir, TempDir};
+use utils::fs::{copy_directory, CopyMode};
 
 // 2 helper macros to make all the build testing more bearable
 #[macro_export]
@@ -71,6 +72,34 @@ where
     (site, tmp_dir, public.clone())
 }
 
+/// Copies a test site to a temporary directory, for the tests changing its files
+pub fn copy_site(name: &str) -> (TempDir, PathBuf) {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push(name);
+    let tmp_dir = tempdir().expect("create temp dir");
+    let root = tmp_dir.path().join(name);
+    copy_directory(&path, &root, CopyMode::Copy, None, None).expect("copy test site");
+    (tmp_dir, root)
+}
+
+/// Builds a site copied with `copy_site`, once the test changed its files, with the same hook
+/// as `build_site_with_setup` to setup some config options.
+/// The output is written to the `public` directory of `tmp_dir`
+pub fn build_copied_site<F>(tmp_dir: &TempDir, root: &Path, mut setup_cb: F) -> (Site, PathBuf)
+where
+    F: FnMut(Site) -> (Site, bool),
+{
+    let site = Site::new(root, "config.toml").unwrap();
+    let (mut site, needs_loading) = setup_cb(site);
+    if needs_loading {
+        site.load().unwrap();
+    }
+    let public = tmp_dir.path().join("public");
+    site.set_output_path(&public);
+    site.build().expect("Couldn't build the site");
+    (site, public)
+}
+
 /// Finds the unified path (eg. _index.fr.md -> _index.md) and
 /// potential language (if not default) associated with a path
 /// When the path is not a markdown file (.md), None is returned
@@ -216,14 +245,14 @@ impl Translations {
                     let section = &library.sections[key];
                     Translation {
                         lang: section.lang.clone(),
-                        permalink: section.permalink.clone(),
+                        permalink: section.permalink.to_string(),
                         path: section.file.path.to_str().unwrap().to_string(),
                     }
                 } else {
                     let page = &library.pages[key];
                     Translation {
                         lang: page.lang.clone(),
-                        permalink: page.permalink.clone(),
+                        permalink: page.permalink.to_string(),
                         path: page.file.path.to_str().unwrap().to_string(),
                     }
                     //library.get_page_by_key(*key).file.relative.to_string()
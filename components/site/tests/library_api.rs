@@ -0,0 +1,67 @@
+mod common;
+
+use std::collections::HashMap;
+use std::fs;
+
+use common::copy_site;
+use libs::tera::{to_value, Result as TeraResult, Value};
+use site::{Change, SiteBuilder};
+
+#[test]
+fn can_build_site_through_library_api() {
+    let (tmp_dir, root) = copy_site("test_site_i18n");
+    let public = tmp_dir.path().join("public");
+
+    let mut site = SiteBuilder::new(&root)
+        .base_url("https://example.org")
+        .output_dir(&public)
+        .function("exporter", |_: &HashMap<String, Value>| -> TeraResult<Value> {
+            Ok(to_value("my-cms").unwrap())
+        })
+        .filter("shout", |value: &Value, _: &HashMap<String, Value>| -> TeraResult<Value> {
+            Ok(to_value(value.as_str().unwrap_or_default().to_uppercase()).unwrap())
+        })
+        .create()
+        .unwrap();
+
+    let stats = site.load().unwrap();
+    assert_eq!(stats.pages, site.content().pages().count());
+    assert_eq!(stats.sections, 3);
+    let paths: Vec<_> = site.content().pages().map(|p| p.file.path.clone()).collect();
+    let mut sorted_paths = paths.clone();
+    sorted_paths.sort();
+    assert_eq!(paths, sorted_paths);
+    let something = root.join("content").join("blog").join("something.md");
+    assert_eq!(site.content().page(&something).unwrap().meta.title.as_deref(), Some("Something"));
+    assert!(site.content().section(&root.join("content").join("_index.md")).is_some());
+
+    let build_stats = site.build().unwrap();
+    assert_eq!(build_stats.pages, stats.pages);
+    assert!(file_contains!(public, "sitemap.xml", "https://example.org/blog/something/"));
+
+    // New content shows up after a rebuild
+    fs::write(
+        root.join("content").join("blog").join("new-post.md"),
+        "+++\ntitle = \"New post\"\ndate = 2024-01-01\n+++\nHello",
+    )
+    .unwrap();
+    let rebuild_stats =
+        site.rebuild(&Change::Content(root.join("content/blog/new-post.md"))).unwrap();
+    assert_eq!(rebuild_stats.pages, stats.pages + 1);
+    assert!(file_exists!(public, "blog/new-post/index.html"));
+
+    // And so do the injected Tera function and filter, after a template change
+    let index_template = root.join("templates").join("index.html");
+    let mut template = fs::read_to_string(&index_template).unwrap();
+    template.push_str("Exported by {{ exporter() }} {{ \"loudly\" | shout }}");
+    fs::write(&index_template, template).unwrap();
+    site.rebuild(&Change::Template(index_template)).unwrap();
+    assert!(file_contains!(public, "index.html", "Exported by my-cms LOUDLY"));
+
+    // The options of the builder survive a config change
+    site.rebuild(&Change::Config).unwrap();
+    assert!(file_contains!(public, "index.html", "Exported by my-cms LOUDLY"));
+    assert!(file_contains!(public, "sitemap.xml", "https://example.org/blog/new-post/"));
+    // Nothing was written to the `output_dir` of the config
+    assert!(!root.join("public").exists());
+}
@@ -4,12 +4,15 @@ use std::collections::HashMap;
 use std::env;
 use std::path::{Path, PathBuf};
 
-use common::{build_site, build_site_with_setup};
-use config::TaxonomyConfig;
-use content::Page;
+use common::{build_copied_site, build_site, build_site_with_setup, copy_site};
+use config::{
+    HighlightingMode, HtmlValidationLevel, IndexFormat, LinkCheckerLevel, OutputFormat,
+    TaxonomyConfig,
+};
+use content::{ContentStats, Page};
 use libs::ahash::AHashMap;
 use site::sitemap;
-use site::Site;
+use site::{Change, Site};
 
 #[test]
 fn can_parse_site() {
@@ -26,15 +29,15 @@ fn can_parse_site() {
 
     // Make sure the page with a url doesn't have any sections
     let url_post = library.pages.get(&posts_path.join("fixed-url.md")).unwrap();
-    assert_eq!(url_post.path, "/a-fixed-url/");
+    assert_eq!(&*url_post.path, "/a-fixed-url/");
 
     // Make sure the article in a folder with only asset doesn't get counted as a section
     let asset_folder_post =
         library.pages.get(&posts_path.join("with-assets").join("index.md")).unwrap();
     assert_eq!(asset_folder_post.file.components, vec!["posts".to_string()]);
 
-    // That we have the right number of sections
-    assert_eq!(library.sections.len(), 13);
+    // That we have the right number of sections, including the implicit one of `posts/no-section`
+    assert_eq!(library.sections.len(), 14);
 
     // And that the sections are correct
     let index_section = library.sections.get(&path.join("content").join("_index.md")).unwrap();
@@ -43,10 +46,24 @@ fn can_parse_site() {
     assert!(index_section.ancestors.is_empty());
 
     let posts_section = library.sections.get(&posts_path.join("_index.md")).unwrap();
-    assert_eq!(posts_section.subsections.len(), 2);
+    assert_eq!(posts_section.subsections.len(), 3);
     assert_eq!(posts_section.pages.len(), 10); // 11 with 1 draft == 10
     assert_eq!(posts_section.ancestors, vec![index_section.file.relative.clone()]);
 
+    // A directory without an `_index.md` gets a section that is not rendered
+    let no_section = library.sections.get(&posts_path.join("no-section/_index.md")).unwrap();
+    assert!(no_section.implicit);
+    assert!(!no_section.meta.render);
+    assert_eq!(no_section.meta.title.as_deref(), Some("no-section"));
+    assert_eq!(&*no_section.path, "/posts/no-section/");
+    assert_eq!(no_section.pages, vec![posts_path.join("no-section/simple.md")]);
+    assert_eq!(
+        no_section.ancestors,
+        vec![index_section.file.relative.clone(), posts_section.file.relative.clone()]
+    );
+    let no_section_page = library.pages.get(&posts_path.join("no-section/simple.md")).unwrap();
+    assert_eq!(no_section_page.ancestors.last(), Some(&no_section.file.relative));
+
     // Make sure we remove all the pwd + content from the sections
     let basic = library.pages.get(&posts_path.join("simple.md")).unwrap();
     assert_eq!(basic.file.components, vec!["posts".to_string()]);
@@ -92,7 +109,8 @@ fn can_parse_site() {
         prog_section.meta.extra.get("we_have_extra").and_then(|s| s.as_str()),
         Some("variables")
     );
-    let sitemap_entries = sitemap::find_entries(&library, &site.taxonomies[..], &site.config);
+    let sitemap_entries =
+        sitemap::find_entries(&library, &site.taxonomies, &site.archives, &site.config);
     let sitemap_entry = sitemap_entries
         .iter()
         .find(|e| e.permalink.ends_with("tutorials/programming/"))
@@ -115,6 +133,29 @@ fn errors_on_unknown_taxonomies() {
     );
 }
 
+#[test]
+fn reports_all_content_errors_at_once() {
+    let tmp_dir = tempfile::tempdir().expect("create temp dir");
+    let path = tmp_dir.path();
+    std::fs::create_dir_all(path.join("content").join("blog")).unwrap();
+    std::fs::create_dir_all(path.join("templates")).unwrap();
+    std::fs::write(path.join("config.toml"), "base_url = \"https://example.com\"").unwrap();
+    std::fs::write(path.join("content").join("blog").join("_index.md"), "+++\nsort_by = 1\n+++\n")
+        .unwrap();
+    std::fs::write(path.join("content").join("a.md"), "+++\ntitle = \"A\"\n+++\nFine").unwrap();
+    std::fs::write(path.join("content").join("b.md"), "+++\ntitle = 1\n+++\n").unwrap();
+    std::fs::write(path.join("content").join("c.md"), "no front matter").unwrap();
+
+    let mut site = Site::new(path, "config.toml").unwrap();
+    let err = site.load().unwrap_err().to_string();
+    assert!(err.starts_with("Found 3 errors:"));
+    assert!(err.contains("_index.md"));
+    assert!(err.contains("b.md"));
+    assert!(err.contains("line 2"));
+    assert!(err.contains("Couldn't find front matter"));
+    assert!(!err.contains("a.md"));
+}
+
 #[test]
 fn can_build_site_without_live_reload() {
     let (_, _tmp_dir, public) = build_site("test_site");
@@ -126,12 +167,20 @@ fn can_build_site_without_live_reload() {
     assert!(file_exists!(public, "a-fixed-url/index.html"));
     // the config.title is there
     assert!(file_contains!(public, "index.html", "My Integration Testing site"));
+    // the data directory is loaded
+    assert!(file_contains!(public, "index.html", "<p class=\"author\">Vincent Prouillet</p>"));
 
     assert!(file_exists!(public, "posts/python/index.html"));
     // Shortcodes work
     assert!(file_contains!(public, "posts/python/index.html", "Basic shortcode"));
     assert!(file_contains!(public, "posts/python/index.html", "Arrrh Bob"));
     assert!(file_contains!(public, "posts/python/index.html", "Arrrh Bob_Sponge"));
+    // Shortcodes can link to content
+    assert!(file_contains!(
+        public,
+        "posts/simple/index.html",
+        r#"<a class="content-link" href="https://replace-this-with-your-url.com/posts/python/">"#
+    ));
     assert!(file_exists!(public, "posts/tutorials/devops/nix/index.html"));
     assert!(file_exists!(public, "posts/with-assets/index.html"));
     assert!(file_exists!(public, "posts/no-section/simple/index.html"));
@@ -282,7 +331,7 @@ fn can_build_site_with_live_reload_and_drafts() {
 
     // drafted sections are included
     let library = site.library.read().unwrap();
-    assert_eq!(library.sections.len(), 15);
+    assert_eq!(library.sections.len(), 16);
 
     assert!(file_exists!(public, "secret_section/index.html"));
     assert!(file_exists!(public, "secret_section/draft-page/index.html"));
@@ -702,6 +751,432 @@ fn can_build_search_index() {
     assert!(file_exists!(public, "search_index.en.js"));
 }
 
+#[test]
+fn can_build_search_index_with_boosts() {
+    let (tmp_dir, root) = copy_site("test_site");
+    for (section, boost) in [("posts", "2.0"), ("posts/tutorials", "1.5")] {
+        let path = root.join("content").join(section).join("_index.md");
+        let content = std::fs::read_to_string(&path).unwrap();
+        let content = content.replacen("+++\n", &format!("+++\nsearch_boost = {}\n", boost), 1);
+        std::fs::write(&path, content).unwrap();
+    }
+    let (_, public) = build_copied_site(&tmp_dir, &root, |mut site| {
+        site.config.build_search_index = true;
+        site.config.search.index_format = IndexFormat::ElasticlunrJson;
+        site.config.languages.get_mut("en").unwrap().search.title_boost = 3.0;
+        (site, true)
+    });
+
+    let index = std::fs::read_to_string(public.join("search_index.en.json")).unwrap();
+    let index: libs::serde_json::Value = libs::serde_json::from_str(&index).unwrap();
+    assert_eq!(index["fieldBoosts"]["title"]["boost"], 3.0);
+    assert_eq!(index["fieldBoosts"]["body"]["boost"], 1.0);
+    let docs = &index["documentStore"]["docs"];
+    let base_url = "https://replace-this-with-your-url.com";
+    assert_eq!(docs[format!("{}/posts/", base_url)]["boost"], 2.0);
+    assert_eq!(docs[format!("{}/posts/simple/", base_url)]["boost"], 2.0);
+    // Multiplied by the boosts of the parent sections
+    assert_eq!(docs[format!("{}/posts/tutorials/", base_url)]["boost"], 3.0);
+    assert_eq!(docs[format!("{}/posts/tutorials/programming/rust/", base_url)]["boost"], 3.0);
+    assert!(docs[format!("{}/", base_url)].get("boost").is_none());
+    assert!(docs[format!("{}/posts/simple/", base_url)].get("title").is_some());
+}
+
+#[test]
+fn can_build_opensearch_and_corpus_with_search_index() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.build_search_index = true;
+        site.config.search.generate_opensearch = true;
+        site.config.search.generate_corpus = true;
+        (site, true)
+    });
+
+    assert!(file_contains!(public, "opensearch.xml", "<ShortName>My Integration T</ShortName>"));
+    assert!(file_contains!(
+        public,
+        "opensearch.xml",
+        r#"template="https://replace-this-with-your-url.com/search/?q={searchTerms}""#
+    ));
+    assert!(file_contains!(
+        public,
+        "search_corpus.txt",
+        "https://replace-this-with-your-url.com/posts/simple/\tSimple article with shortcodes\t"
+    ));
+    let corpus = std::fs::read_to_string(public.join("search_corpus.txt")).unwrap();
+    assert!(corpus.lines().all(|line| line.split('\t').count() == 3));
+    assert!(!file_contains!(public, "sitemap.xml", "opensearch.xml"));
+    assert!(!file_contains!(public, "sitemap.xml", "search_corpus.txt"));
+}
+
+#[test]
+fn can_write_manifest_of_generated_files() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.generate_manifest = true;
+        (site, true)
+    });
+
+    let manifest: Vec<libs::serde_json::Value> = libs::serde_json::from_str(
+        &std::fs::read_to_string(public.join("zola-manifest.json")).unwrap(),
+    )
+    .unwrap();
+    let entry = |path: &str| manifest.iter().find(|e| e["path"] == path).unwrap();
+    let source_of = |path: &str| entry(path)["source"].as_str().unwrap();
+    assert_eq!(source_of("posts/simple/index.html"), "content/posts/simple.md");
+    assert_eq!(source_of("posts/with-assets/zola.png"), "content/posts/with-assets/index.md");
+    assert_eq!(source_of("posts/index.html"), "content/posts/_index.md");
+    assert_eq!(source_of("posts/page/2/index.html"), "content/posts/_index.md");
+    assert_eq!(source_of("another-old-url/index.html"), "alias");
+    assert_eq!(source_of("top-level.html"), "alias");
+    assert_eq!(source_of("sitemap.xml"), "sitemap");
+    assert_eq!(source_of("atom.xml"), "feed");
+    assert_eq!(source_of("categories/a-category/index.html"), "taxonomy");
+    assert_eq!(source_of("site.css"), "static");
+    assert_eq!(source_of("404.html"), "error_page");
+
+    assert_eq!(
+        entry("posts/simple/index.html")["permalink"],
+        "https://replace-this-with-your-url.com/posts/simple/"
+    );
+    assert!(manifest.iter().all(|e| e["hash"].as_str().unwrap().len() == 64));
+    assert!(manifest.iter().all(|e| e["path"] != "zola-manifest.json"));
+}
+
+#[test]
+fn can_record_the_render_time_of_pages() {
+    let (site, _tmp_dir, _public) = build_site_with_setup("test_site", |mut site| {
+        site.enable_timings();
+        (site, true)
+    });
+    let summary = site.build_summary();
+    let simple = site.base_path.join("content").join("posts").join("simple.md");
+    assert!(summary.page_timings.contains_key(&simple));
+    assert_eq!(summary.slowest_pages(3).len(), 3);
+    let histogram = summary.timing_histogram();
+    assert_eq!(histogram.iter().map(|(_, count)| count).sum::<usize>(), summary.page_timings.len());
+
+    // Nothing is recorded by default
+    let (site, _tmp_dir, _public) = build_site("test_site");
+    assert!(site.build_summary().page_timings.is_empty());
+}
+
+#[test]
+fn can_build_site_with_low_memory() {
+    let build = |low_memory: bool| {
+        build_site_with_setup("test_site", |mut site| {
+            site.config.low_memory = low_memory;
+            site.config.build_search_index = true;
+            (site, true)
+        })
+    };
+    let (_, _tmp_dir, public) = build(false);
+    let (site, _low_tmp_dir, low_public) = build(true);
+
+    let library = site.library.read().unwrap();
+    let page = library.pages.values().find(|p| p.file.relative == "posts/simple.md").unwrap();
+    assert!(page.content.is_empty());
+    assert!(page.spilled_content.is_some());
+    assert!(page.content().unwrap().contains("<p>"));
+
+    for file in ["posts/simple/index.html", "posts/index.html", "atom.xml"] {
+        assert_eq!(
+            std::fs::read_to_string(public.join(file)).unwrap(),
+            std::fs::read_to_string(low_public.join(file)).unwrap(),
+            "{} differs",
+            file
+        );
+    }
+    assert!(file_contains!(low_public, "search_index.en.js", "A simple page"));
+}
+
+#[test]
+fn can_build_the_same_output_twice() {
+    let (tmp_dir, root) = copy_site("test_site");
+    // The salt and IV of the protected pages are usually random
+    std::fs::write(
+        root.join("content/posts/secret.md"),
+        "+++\ntitle = \"Secret\"\ndate = 2020-01-01\nprotected = { password = \"hunter2\" }\n+++\nThe treasure",
+    )
+    .unwrap();
+    let build = |output: &str| {
+        let public = tmp_dir.path().join(output);
+        let mut site = Site::new(&root, "config.toml").unwrap();
+        site.config.reproducible = true;
+        site.load().unwrap();
+        site.set_output_path(&public);
+        site.build().unwrap();
+        (site.config.build_info.unix_timestamp, public)
+    };
+    let (timestamp, public) = build("public");
+    let (other_timestamp, other_public) = build("other_public");
+    assert!(file_contains!(public, "posts/secret/index.html", "zola-protected"));
+
+    let files = |root: &Path| -> Vec<PathBuf> {
+        let mut files: Vec<_> = libs::walkdir::WalkDir::new(root)
+            .into_iter()
+            .map(|e| e.unwrap().path().strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        files.sort();
+        files
+    };
+    let paths = files(&public);
+    assert_eq!(paths, files(&other_public));
+
+    let modified = |path: &Path| {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).unwrap();
+        libs::filetime::FileTime::from_system_time(modified).unix_seconds()
+    };
+    for path in paths {
+        let (file, other_file) = (public.join(&path), other_public.join(&path));
+        if file.is_file() {
+            assert_eq!(
+                std::fs::read(&file).unwrap(),
+                std::fs::read(&other_file).unwrap(),
+                "{} differs",
+                path.display()
+            );
+        }
+        assert_eq!(modified(&file), timestamp, "{} has another modification time", file.display());
+        assert_eq!(modified(&other_file), other_timestamp);
+    }
+}
+
+#[test]
+fn can_hash_the_whole_output() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let public = tmp_dir.path().join("public");
+    let build = |dry_run: bool, relative_urls: bool| {
+        let mut site = Site::new(&root, "config.toml").unwrap();
+        site.set_output_path(&public);
+        if dry_run {
+            site.enable_dry_run();
+        }
+        if relative_urls {
+            site.enable_relative_urls();
+        }
+        site.load().unwrap();
+        let hash = site.build().unwrap().build_hash.unwrap();
+        site.discard_dry_run().unwrap();
+        hash
+    };
+
+    let hash = build(false, false);
+    assert_eq!(hash.len(), 64);
+    assert!(file_contains!(public, ".zola-build-hash", &hash));
+    // A dry run gets the same hash without touching the output directory
+    std::fs::remove_dir_all(&public).unwrap();
+    assert_eq!(build(true, false), hash);
+    assert!(!public.exists());
+
+    let relative_hash = build(false, true);
+    assert_ne!(relative_hash, hash);
+    assert_eq!(build(true, true), relative_hash);
+
+    let page = root.join("content/posts/simple.md");
+    let content = std::fs::read_to_string(&page).unwrap();
+    std::fs::write(&page, format!("{}\nMore content.", content)).unwrap();
+    assert_ne!(build(true, false), hash);
+}
+
+#[test]
+fn can_write_the_output_of_a_dry_run() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let build = |dry_run: bool| {
+        let public = tmp_dir.path().join(if dry_run { "dry_run" } else { "public" });
+        let mut site = Site::new(&root, "config.toml").unwrap();
+        site.set_output_path(&public);
+        if dry_run {
+            site.enable_dry_run();
+        }
+        site.load().unwrap();
+        site.build().unwrap();
+        if dry_run {
+            let scratch = site.output_path.clone();
+            site.write_dry_run(&public).unwrap();
+            assert!(!scratch.exists());
+        }
+        public
+    };
+    let public = build(false);
+    let dry_run = build(true);
+
+    let files = |root: &Path| -> Vec<(PathBuf, Vec<u8>)> {
+        let mut files: Vec<_> = libs::walkdir::WalkDir::new(root)
+            .into_iter()
+            .map(|e| e.unwrap().into_path())
+            .filter(|p| p.is_file())
+            .map(|p| (p.strip_prefix(root).unwrap().to_path_buf(), std::fs::read(&p).unwrap()))
+            .collect();
+        files.sort();
+        files
+    };
+    assert!(file_exists!(dry_run, "posts/simple/index.html"));
+    assert!(file_exists!(dry_run, ".zola-build-hash"));
+    assert_eq!(files(&public), files(&dry_run));
+}
+
+#[test]
+fn can_use_the_builtin_macros() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let template_path = root.join("templates/section_paginated.html");
+    let template = std::fs::read_to_string(&template_path)
+        .unwrap()
+        .replacen(
+            "{% extends \"index.html\" %}",
+            "{% extends \"index.html\" %}\n{% import \"zola/macros.html\" as zm %}",
+            1,
+        )
+        .replace(
+            "{% endblock content %}",
+            "{{ zm::pagination(paginator=paginator) }}\n{% endblock content %}",
+        );
+    std::fs::write(&template_path, template).unwrap();
+    let build = || build_copied_site(&tmp_dir, &root, |site| (site, true)).1;
+    let public = build();
+
+    let base_url = "https://replace-this-with-your-url.com";
+    assert!(file_contains!(
+        public,
+        "posts/page/2/index.html",
+        &format!(r#"<a href="{}/posts/page/2/" aria-current="page">2</a>"#, base_url)
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/page/2/index.html",
+        &format!(r#"<a class="pagination-previous" href="{}/posts/" rel="prev">"#, base_url)
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/page/2/index.html",
+        &format!(r#"<a class="pagination-next" href="{}/posts/page/3/" rel="next">"#, base_url)
+    ));
+
+    // The macros of the site take precedence over the built-in ones
+    std::fs::create_dir_all(root.join("templates/zola")).unwrap();
+    std::fs::write(
+        root.join("templates/zola/macros.html"),
+        "{% macro pagination(paginator) %}Custom pager {{ paginator.current_index }}{% endmacro pagination %}",
+    )
+    .unwrap();
+    build();
+    assert!(file_contains!(public, "posts/page/2/index.html", "Custom pager 2"));
+}
+
+#[test]
+fn unlisted_pages_are_rendered_but_not_listed() {
+    let (tmp_dir, root) = copy_site("test_site");
+    std::fs::write(
+        root.join("content/posts/tutorials/programming/secret.md"),
+        "+++\ntitle = \"Secret tutorial\"\nweight = 3\ndate = 2030-01-01\nunlisted = true\n\n[taxonomies]\ncategories = [\"a-category\"]\n+++\nShh",
+    )
+    .unwrap();
+    let (site, public) = build_copied_site(&tmp_dir, &root, |mut site| {
+        site.config.build_search_index = true;
+        // Dated in the future to be first in the feeds if it was in them
+        site.include_future();
+        (site, true)
+    });
+
+    assert!(file_contains!(public, "posts/tutorials/programming/secret/index.html", "Shh"));
+    for listing in [
+        "posts/tutorials/programming/index.html",
+        "posts/tutorials/programming/atom.xml",
+        "categories/a-category/index.html",
+        "categories/a-category/atom.xml",
+        "atom.xml",
+        "sitemap.xml",
+        "search_index.en.js",
+    ] {
+        assert!(!file_contains!(public, listing, "Secret"), "{} lists the page", listing);
+        assert!(
+            !file_contains!(public, listing, "programming/secret"),
+            "{} lists the page",
+            listing
+        );
+    }
+    // Nothing points to it but it's not an orphan page
+    let secret = root.join("content/posts/tutorials/programming/secret.md");
+    assert!(!site::orphans::find_orphan_pages(&site).contains(&secret));
+}
+
+#[test]
+fn can_colocate_assets_of_loose_pages() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let programming = root.join("content/posts/tutorials/programming");
+    for asset in ["python.png", "rust.diagram.svg", "rust.png", "section.png"] {
+        std::fs::write(programming.join(asset), asset).unwrap();
+    }
+    let (site, public) = build_copied_site(&tmp_dir, &root, |mut site| {
+        site.config.colocate_loose_assets = true;
+        (site, true)
+    });
+
+    let library = site.library.read().unwrap();
+    let python = &library.pages[&programming.join("python.md")];
+    assert_eq!(python.assets, vec![programming.join("python.png")]);
+    assert_eq!(python.serialized_assets, vec!["/posts/tutorials/programming/python/python.png"]);
+    let rust = &library.pages[&programming.join("rust.md")];
+    assert_eq!(
+        rust.assets,
+        vec![programming.join("rust.diagram.svg"), programming.join("rust.png")]
+    );
+    let section = &library.sections[&programming.join("_index.md")];
+    assert_eq!(section.assets, vec![programming.join("section.png")]);
+
+    assert!(file_exists!(public, "posts/tutorials/programming/python/python.png"));
+    assert!(file_exists!(public, "posts/tutorials/programming/rust/rust.diagram.svg"));
+    assert!(file_exists!(public, "posts/tutorials/programming/rust/rust.png"));
+    assert!(!file_exists!(public, "posts/tutorials/programming/python/rust.png"));
+    assert!(file_exists!(public, "posts/tutorials/programming/section.png"));
+    assert!(!file_exists!(public, "posts/tutorials/programming/python.png"));
+}
+
+#[test]
+fn can_render_page_output_formats() {
+    let (tmp_dir, root) = copy_site("test_site");
+    std::fs::write(
+        root.join("templates/page.json"),
+        "{\"title\": {{ page.title | json_encode() | safe }}}",
+    )
+    .unwrap();
+    std::fs::write(root.join("templates/page.txt"), "{{ page.title }}").unwrap();
+    // The programming section only renders the txt format
+    let programming = root.join("content/posts/tutorials/programming/_index.md");
+    let content = std::fs::read_to_string(&programming).unwrap();
+    let content = content.replacen(
+        "weight = 1\n",
+        "weight = 1\npage_output_formats = [{extension = \"txt\", template = \"page.txt\"}]\n",
+        1,
+    );
+    std::fs::write(&programming, content).unwrap();
+    // And that page skips the json one
+    let python = root.join("content/posts/python.md");
+    let content = std::fs::read_to_string(&python).unwrap();
+    std::fs::write(
+        &python,
+        content.replacen("+++\n", "+++\nskip_output_formats = [\"json\"]\n", 1),
+    )
+    .unwrap();
+
+    let (_, public) = build_copied_site(&tmp_dir, &root, |mut site| {
+        site.config.page_output_formats =
+            vec![OutputFormat { extension: "json".to_string(), template: "page.json".to_string() }];
+        (site, true)
+    });
+
+    assert!(file_contains!(
+        public,
+        "posts/simple/index.json",
+        r#"{"title": "Simple article with shortcodes"}"#
+    ));
+    assert!(file_exists!(public, "posts/simple/index.html"));
+    assert!(!file_exists!(public, "posts/python/index.json"));
+    assert!(file_contains!(public, "posts/tutorials/programming/rust/index.txt", "Rust"));
+    assert!(!file_exists!(public, "posts/tutorials/programming/rust/index.json"));
+    // Sections don't have output formats
+    assert!(!file_exists!(public, "posts/index.json"));
+    assert!(!file_contains!(public, "sitemap.xml", "index.json"));
+}
+
 #[test]
 fn can_build_with_extra_syntaxes() {
     let (_, _tmp_dir, public) = build_site("test_site");
@@ -764,6 +1239,15 @@ fn can_build_site_custom_builtins_from_theme() {
     // 404.html is a theme template.
     assert!(file_exists!(public, "404.html"));
     assert!(file_contains!(public, "404.html", "Oops"));
+    // So is 50x.html, which can use the global functions
+    assert!(file_contains!(
+        public,
+        "50x.html",
+        r#"<a href="https://replace-this-with-your-url.com/posts/">"#
+    ));
+    // Error pages are not in the sitemap
+    assert!(!file_contains!(public, "sitemap.xml", "404"));
+    assert!(!file_contains!(public, "sitemap.xml", "50x"));
 }
 
 #[test]
@@ -782,10 +1266,314 @@ fn can_build_site_with_html_minified() {
     ));
 }
 
+#[test]
+fn can_highlight_code_with_classes_and_a_stylesheet() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.markdown.highlighting_mode = HighlightingMode::Css;
+        site.config.markdown.highlight_theme_dark = Some("base16-ocean-dark".to_owned());
+        (site, true)
+    });
+
+    assert!(file_contains!(public, "posts/extra-syntax/index.html", "<span class=\"z-"));
+    assert!(!file_contains!(public, "posts/extra-syntax/index.html", "style=\"color:"));
+    assert!(file_contains!(public, "syntax-theme.css", "@media (prefers-color-scheme: dark) {"));
+    assert!(file_contains!(public, "syntax-theme.css", ".z-code {"));
+}
+
 #[test]
 fn can_ignore_markdown_content() {
-    let (_, _tmp_dir, public) = build_site("test_site");
+    let (site, _tmp_dir, public) = build_site("test_site");
     assert!(!file_exists!(public, "posts/ignored/index.html"));
+    assert!(!site.permalinks.contains_key("posts/ignored.md"));
+    // Ignoring a directory ignores everything in it
+    assert!(!file_exists!(public, "posts/ignored-folder/hidden/index.html"));
+    assert!(!site.permalinks.contains_key("posts/ignored-folder/hidden.md"));
+}
+
+#[test]
+fn can_ignore_paths_with_zolaignore_and_ignore_patterns() {
+    let (tmp_dir, root) = copy_site("test_site");
+    std::fs::write(root.join(".zolaignore"), "node_modules/\n/content/posts/extra_syntax.md\n")
+        .unwrap();
+    let config = std::fs::read_to_string(root.join("config.toml")).unwrap();
+    std::fs::write(
+        root.join("config.toml"),
+        format!(
+            "ignore_patterns = [\"/static/scripts/*\", \"!/static/scripts/hello.js\"]\n{}",
+            config
+        ),
+    )
+    .unwrap();
+    std::fs::create_dir_all(root.join("static/node_modules/pkg")).unwrap();
+    std::fs::write(root.join("static/node_modules/pkg/index.js"), "").unwrap();
+    std::fs::write(root.join("static/scripts/debug.js"), "").unwrap();
+
+    let (site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+
+    assert!(!site.permalinks.contains_key("posts/extra_syntax.md"));
+    assert!(!file_exists!(public, "posts/extra-syntax/index.html"));
+    assert!(file_exists!(public, "posts/simple/index.html"));
+    assert!(!file_exists!(public, "node_modules/pkg/index.js"));
+    assert!(!file_exists!(public, "scripts/debug.js"));
+    // Re-included by the negation
+    assert!(file_exists!(public, "scripts/hello.js"));
+    assert!(file_exists!(public, "site.css"));
+}
+
+#[test]
+fn can_load_content_in_other_encodings_and_skip_binary_files() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let page = "+++\ntitle = \"Vendor notes\"\ndate = 2020-01-01\n+++\nNotes from the café";
+    let utf16: Vec<u8> = std::iter::once(0xFEFF)
+        .chain(page.encode_utf16())
+        .flat_map(|u: u16| u.to_le_bytes())
+        .collect();
+    std::fs::write(root.join("content/posts/notes.md"), utf16).unwrap();
+    std::fs::write(root.join("content/posts/image.md"), [0x89, b'P', b'N', b'G', 0, 0, 0, 0x0D])
+        .unwrap();
+
+    let (site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+
+    assert!(file_contains!(public, "posts/notes/index.html", "Notes from the café"));
+    assert!(!site.permalinks.contains_key("posts/image.md"));
+}
+
+#[cfg(unix)]
+#[test]
+fn can_convert_pages_with_external_commands() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let mut config = std::fs::read_to_string(root.join("config.toml")).unwrap();
+    config.push_str("\n[markup.external]\nadoc = \"tr a-z A-Z\"\nrst = \"cat {file}\"\n");
+    std::fs::write(root.join("config.toml"), config).unwrap();
+    std::fs::write(
+        root.join("content/posts/legacy.adoc"),
+        "+++\ntitle = \"Legacy\"\ndate = 2020-01-01\n+++\nconverted {{ youtube(id=1) }} [link](@/posts/simple.md)",
+    )
+    .unwrap();
+    std::fs::create_dir_all(root.join("content/posts/manual")).unwrap();
+    std::fs::write(
+        root.join("content/posts/manual/index.rst"),
+        "+++\ntitle = \"Manual\"\ndate = 2020-01-01\n+++\n<p>Read me</p>",
+    )
+    .unwrap();
+    std::fs::write(root.join("content/posts/manual/diagram.png"), "").unwrap();
+
+    let (site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+
+    // Shortcodes and internal links are left to the converter
+    assert!(file_contains!(
+        public,
+        "posts/legacy/index.html",
+        "CONVERTED {{ YOUTUBE(ID=1) }} [LINK](@/POSTS/SIMPLE.MD)"
+    ));
+    assert!(site.permalinks.contains_key("posts/legacy.adoc"));
+    assert!(file_contains!(public, "posts/manual/index.html", "<p>Read me</p>"));
+    assert!(file_exists!(public, "posts/manual/diagram.png"));
+    assert!(!file_exists!(public, "posts/manual/index.rst"));
+}
+
+#[cfg(unix)]
+#[test]
+fn errors_when_an_external_converter_fails() {
+    let (_tmp_dir, root) = copy_site("test_site");
+    let mut config = std::fs::read_to_string(root.join("config.toml")).unwrap();
+    config.push_str("\n[markup.external]\nadoc = \"ls /zola-missing-file\"\n");
+    std::fs::write(root.join("config.toml"), config).unwrap();
+    std::fs::write(root.join("content/posts/legacy.adoc"), "+++\ntitle = \"Legacy\"\n+++\nHi")
+        .unwrap();
+
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    let err = format!("{:#}", site.load().unwrap_err());
+    assert!(err.contains("legacy.adoc"), "{}", err);
+    assert!(err.contains("zola-missing-file"), "{}", err);
+}
+
+#[test]
+fn can_give_their_mentions_to_pages() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let config = std::fs::read_to_string(root.join("config.toml")).unwrap();
+    let config = format!("mentions_source = \"data/mentions/webmentions.json\"\n{}", config);
+    std::fs::write(root.join("config.toml"), config).unwrap();
+    let template = std::fs::read_to_string(root.join("templates/page.html")).unwrap().replace(
+        "{% endblock content %}",
+        "Mentions: {{ page.mentions.count }}{% for m in page.mentions.items %} by {{ m.author }}{% endfor %}\n{% endblock content %}",
+    );
+    std::fs::write(root.join("templates/page.html"), template).unwrap();
+
+    // Not having mentions yet is fine
+    let (_, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+    assert!(file_contains!(public, "posts/simple/index.html", "Mentions: 0\n"));
+
+    std::fs::create_dir_all(root.join("data/mentions")).unwrap();
+    std::fs::write(
+        root.join("data/mentions/webmentions.json"),
+        r#"{"http://replace-this-with-your-url.com/posts/simple": [{"author": "Alice"}, {"author": "Bob"}]}"#,
+    )
+    .unwrap();
+    build_copied_site(&tmp_dir, &root, |site| (site, true));
+    assert!(file_contains!(public, "posts/simple/index.html", "Mentions: 2 by Alice by Bob\n"));
+    assert!(file_contains!(public, "posts/python/index.html", "Mentions: 0\n"));
+}
+
+#[test]
+fn can_generate_redirect_rules_files() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let config = std::fs::read_to_string(root.join("config.toml")).unwrap();
+    let config = format!(
+        "redirect_output = [\"netlify\", \"vercel\"]\nalias_html = false\n{}\n[[redirects]]\nfrom = \"/old-posts/\"\nto = \"@/posts/_index.md\"\nstatus = 302\n",
+        config
+    );
+    std::fs::write(root.join("config.toml"), config).unwrap();
+    std::fs::write(root.join("static/_redirects"), "/chat https://chat.example.com 302\n").unwrap();
+
+    let (_, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+
+    assert!(file_contains!(public, "_redirects", "/chat https://chat.example.com 302\n"));
+    assert!(file_contains!(
+        public,
+        "_redirects",
+        "/an-old-url/old-page /posts/something-else/ 301\n"
+    ));
+    assert!(file_contains!(public, "_redirects", "/old-posts/ /posts/ 302\n"));
+    assert!(file_contains!(public, "vercel.json", r#""source": "/top-level.html""#));
+    assert!(!file_exists!(public, "an-old-url/old-page/index.html"));
+    assert!(!file_exists!(public, "top-level.html"));
+
+    // The same path can't redirect to two places
+    let mut config = std::fs::read_to_string(root.join("config.toml")).unwrap();
+    config.push_str("\n[[redirects]]\nfrom = \"/top-level.html\"\nto = \"/posts/\"\n");
+    std::fs::write(root.join("config.toml"), config).unwrap();
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    let err = site.load().unwrap_err().to_string();
+    assert!(err.contains("content/posts/top-level-alias.md"), "{}", err);
+    assert!(err.contains("config.toml"), "{}", err);
+}
+
+#[test]
+fn can_generate_archive_pages() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let mut config = std::fs::read_to_string(root.join("config.toml")).unwrap();
+    config.push_str("\n[[archives]]\nsection = \"posts\"\n");
+    std::fs::write(root.join("config.toml"), config).unwrap();
+    std::fs::write(
+        root.join("templates/archive.html"),
+        "{{ year }}{% for page in pages %}|{{ page.title }}{% endfor %}",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("templates/archive_index.html"),
+        "{% for group in groups %}{{ group.year }}:{{ group.page_count }};{% endfor %}",
+    )
+    .unwrap();
+
+    let (mut site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+
+    assert!(file_contains!(public, "archive/index.html", "2018:2;2017:11;2016:1;2015:1;"));
+    assert!(file_contains!(public, "archive/2017/index.html", "|Simple article with shortcodes"));
+    assert!(file_contains!(public, "archive/2017/index.html", "|Python in posts"));
+    assert!(file_contains!(
+        public,
+        "sitemap.xml",
+        "<loc>https://replace-this-with-your-url.com/archive/2017/</loc>"
+    ));
+    assert!(!file_contains!(public, "atom.xml", "/archive/"));
+
+    // Changing the date of a page moves it to another archive page
+    let page = root.join("content/posts/simple.md");
+    let content = std::fs::read_to_string(&page).unwrap().replace("2017-04-01", "2016-05-01");
+    std::fs::write(&page, content).unwrap();
+    site.add_and_render_page(&page).unwrap();
+    assert!(file_contains!(public, "archive/2016/index.html", "|Simple article with shortcodes"));
+    assert!(!file_contains!(public, "archive/2017/index.html", "|Simple article with shortcodes"));
+    assert!(file_contains!(public, "archive/2017/index.html", "|Python in posts"));
+}
+
+#[test]
+fn can_remove_static_files_from_the_output() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let static_path = root.join("static");
+    std::fs::create_dir_all(static_path.join("css/old")).unwrap();
+    std::fs::write(static_path.join("css/old/old.css"), "").unwrap();
+    std::fs::create_dir_all(static_path.join("downloads")).unwrap();
+    std::fs::write(static_path.join("downloads/notes.txt"), "").unwrap();
+    // Overrides the one of the theme
+    std::fs::write(static_path.join("some.js"), "// site").unwrap();
+
+    let (mut site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+    assert!(file_exists!(public, "css/old/old.css"));
+    assert!(file_contains!(public, "some.js", "// site"));
+
+    // The directories left empty are removed as well
+    std::fs::remove_file(static_path.join("css/old/old.css")).unwrap();
+    std::fs::remove_dir_all(static_path.join("css")).unwrap();
+    site.remove_static_file(&static_path.join("css/old/old.css")).unwrap();
+    assert!(!public.join("css").exists());
+    assert!(file_exists!(public, "site.css"));
+
+    // The file of the theme is back
+    std::fs::remove_file(static_path.join("some.js")).unwrap();
+    site.rebuild(&Change::Static(static_path.join("some.js"))).unwrap();
+    assert!(file_exists!(public, "some.js"));
+    assert!(!file_contains!(public, "some.js", "// site"));
+
+    // A renamed directory is removed and copied to its new place
+    std::fs::rename(static_path.join("downloads"), static_path.join("files")).unwrap();
+    site.rebuild(&Change::Static(static_path.join("downloads"))).unwrap();
+    site.rebuild(&Change::Static(static_path.join("files"))).unwrap();
+    assert!(!public.join("downloads").exists());
+    assert!(file_exists!(public, "files/notes.txt"));
+}
+
+#[test]
+fn removing_a_static_directory_keeps_the_section_of_the_same_name() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let static_path = root.join("static");
+    std::fs::create_dir_all(static_path.join("posts/images")).unwrap();
+    std::fs::write(static_path.join("posts/images/cover.txt"), "cover").unwrap();
+
+    let (mut site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+    assert!(file_exists!(public, "posts/images/cover.txt"));
+    assert!(file_exists!(public, "posts/index.html"));
+
+    std::fs::remove_dir_all(static_path.join("posts")).unwrap();
+    site.rebuild(&Change::Static(static_path.join("posts"))).unwrap();
+    assert!(!public.join("posts/images").exists());
+    assert!(file_exists!(public, "posts/index.html"));
+    assert!(file_exists!(public, "posts/simple/index.html"));
+}
+
+#[test]
+fn excluded_assets_are_not_optimized() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.asset_optimization.include = vec!["content/**".to_string()];
+        site.config.asset_optimization.exclude = vec!["**/zola.png".to_string()];
+        site.config.asset_optimization.resolve_globsets().unwrap();
+        (site, true)
+    });
+
+    let original =
+        std::fs::read(site.base_path.join("content/posts/with-assets/zola.png")).unwrap();
+    let copied = std::fs::read(public.join("posts/with-assets/zola.png")).unwrap();
+    assert_eq!(original, copied);
+}
+
+#[test]
+fn errors_on_alias_outside_of_output_directory() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+    site.load().unwrap();
+    {
+        let mut library = site.library.write().unwrap();
+        let page = library.pages.values_mut().next().unwrap();
+        page.meta.aliases = vec!["../../escaped/".to_string()];
+    }
+    let tmp_dir = tempfile::tempdir().expect("create temp dir");
+    site.set_output_path(tmp_dir.path().join("public"));
+
+    let err = site.build().unwrap_err();
+    assert!(err.to_string().contains("outside of the output directory"));
+    assert!(!tmp_dir.path().join("escaped").exists());
 }
 
 #[test]
@@ -795,6 +1583,89 @@ fn can_cachebust_static_files() {
         "<link href=\"https://replace-this-with-your-url.com/site.css?h=83bd983e8899946ee33d\" rel=\"stylesheet\">"));
 }
 
+#[test]
+fn can_hash_static_assets_filenames() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.hash_static_assets = vec!["*.css".to_string()];
+        site.config.hash_static_assets_globset =
+            Some(utils::globs::build_glob_set(&site.config.hash_static_assets, "").unwrap());
+        (site, true)
+    });
+
+    let manifest = site.asset_manifest.read().unwrap();
+    let hashed_css = &manifest["site.css"];
+    assert!(hashed_css.starts_with("site.") && hashed_css != "site.css");
+    assert!(manifest["nested_sass/sass.css"].starts_with("nested_sass/sass."));
+    assert!(!manifest.contains_key("scripts/hello.js"));
+
+    assert!(!public.join("site.css").exists());
+    assert!(public.join(hashed_css).exists());
+    assert!(!public.join("nested_sass/sass.css").exists());
+    assert!(public.join("scripts/hello.js").exists());
+    assert!(file_contains!(public, "manifest.json", &format!("\"site.css\": \"{}\"", hashed_css)));
+    // No need for the query string cachebusting anymore
+    assert!(file_contains!(
+        public,
+        "index.html",
+        &format!(
+            "<link href=\"https://replace-this-with-your-url.com/{}\" rel=\"stylesheet\">",
+            hashed_css
+        )
+    ));
+}
+
+#[test]
+fn hashes_static_assets_from_their_optimized_version() {
+    let (tmp_dir, root) = copy_site("test_site");
+    std::fs::create_dir_all(root.join("static/img")).unwrap();
+    let image = env::current_dir().unwrap().join("../imageproc/tests/test_imgs/jpg.jpg");
+    std::fs::copy(image, root.join("static/img/photo.jpg")).unwrap();
+    std::fs::write(root.join("static/img/bg.css"), "body{background:url(photo.jpg)}").unwrap();
+    let build = |jpeg_quality: u8| {
+        let public = tmp_dir.path().join(format!("public-{}", jpeg_quality));
+        let mut site = Site::new(&root, "config.toml").unwrap();
+        site.config.hash_static_assets = vec!["img/*".to_string()];
+        site.config.hash_static_assets_globset =
+            Some(utils::globs::build_glob_set(&site.config.hash_static_assets, "").unwrap());
+        site.config.asset_optimization.include = vec!["static/**".to_string()];
+        site.config.asset_optimization.jpeg_quality = jpeg_quality;
+        site.config.asset_optimization.resolve_globsets().unwrap();
+        site.load().unwrap();
+        site.set_output_path(&public);
+        site.build().unwrap();
+        let manifest = site.asset_manifest.read().unwrap().clone();
+        (manifest, public)
+    };
+
+    let (high, public) = build(60);
+    let (low, _) = build(20);
+    assert_ne!(high["img/photo.jpg"], low["img/photo.jpg"]);
+    // The CSS using the image points to its new name so it gets a new name as well
+    assert_ne!(high["img/bg.css"], low["img/bg.css"]);
+    let hashed_photo = high["img/photo.jpg"].trim_start_matches("img/");
+    assert!(file_contains!(public, &high["img/bg.css"], hashed_photo));
+    let optimized = std::fs::read(public.join(&high["img/photo.jpg"])).unwrap();
+    assert_eq!(
+        site::hashed_assets::hashed_path("img/photo.jpg", &optimized),
+        high["img/photo.jpg"]
+    );
+}
+
+#[test]
+fn errors_when_static_has_a_file_named_like_the_hashed_assets_manifest() {
+    let (tmp_dir, root) = copy_site("test_site");
+    std::fs::write(root.join("static/manifest.json"), "{}").unwrap();
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    site.config.hash_static_assets = vec!["*.css".to_string()];
+    site.config.hash_static_assets_globset =
+        Some(utils::globs::build_glob_set(&site.config.hash_static_assets, "").unwrap());
+    site.load().unwrap();
+    site.set_output_path(tmp_dir.path().join("public"));
+
+    let err = site.build().unwrap_err();
+    assert!(err.to_string().contains("manifest.json"));
+}
+
 #[test]
 fn can_get_hash_for_static_files() {
     let (_, _tmp_dir, public) = build_site("test_site");
@@ -880,3 +1751,1021 @@ fn can_find_site_and_page_authors() {
 fn current_path(path: &str) -> String {
     format!("[current_path]({})", path)
 }
+
+#[test]
+fn can_find_orphan_pages_and_unreferenced_assets() {
+    let (site, _tmp_dir, _public) = build_site("test_site");
+    let orphan_page = site.base_path.join("content/posts/no-section/simple.md");
+    assert_eq!(site::orphans::find_orphan_pages(&site), vec![orphan_page.clone()]);
+    assert_eq!(
+        site::orphans::find_unreferenced_assets(&site, true),
+        vec![(
+            site.base_path.join("content/posts/with-assets/index.md"),
+            vec![site.base_path.join("content/posts/with-assets/zola.png")]
+        )]
+    );
+
+    site.library.write().unwrap().pages.get_mut(&orphan_page).unwrap().meta.orphan = true;
+    assert!(site::orphans::find_orphan_pages(&site).is_empty());
+}
+
+#[test]
+fn finds_orphan_pages_only_reachable_from_feeds_they_are_not_in() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let dated = root.join("content/posts/no-section/dated.md");
+    std::fs::write(&dated, "+++\ntitle = \"Dated\"\ndate = 2020-01-01\n+++\nIn the feed").unwrap();
+    let find_orphans = |feed_sections: &[&str]| {
+        let (site, _) = build_copied_site(&tmp_dir, &root, |mut site| {
+            site.config.feed_sections = feed_sections.iter().map(|s| s.to_string()).collect();
+            (site, true)
+        });
+        site::orphans::find_orphan_pages(&site)
+    };
+
+    // Reachable from the main feed
+    assert!(!find_orphans(&[]).contains(&dated));
+    assert!(find_orphans(&["posts/tutorials"]).contains(&dated));
+}
+
+#[test]
+fn can_build_site_with_relative_urls() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.enable_relative_urls();
+        (site, true)
+    });
+
+    assert!(file_contains!(public, "index.html", "<script src=\"scripts/hello.js\""));
+    assert!(file_contains!(public, "index.html", "<a href=\"2018/index.html\">"));
+    assert!(file_contains!(public, "index.html", "href=\"site.css?h="));
+    assert!(file_contains!(
+        public,
+        "top-level.html",
+        "content=\"0; url=posts/top-level-alias/index.html\""
+    ));
+    assert!(file_contains!(public, "posts/index.html", "href=\"../site.css?h="));
+    // Feeds and the sitemap keep absolute URLs
+    assert!(file_contains!(
+        public,
+        "sitemap.xml",
+        "<loc>https://replace-this-with-your-url.com/posts/simple/</loc>"
+    ));
+}
+
+#[test]
+fn can_validate_extra_against_schema() {
+    let (_tmp_dir, root) = copy_site("test_site_i18n");
+    std::fs::write(
+        root.join("extra_schema.toml"),
+        r#"
+[config]
+"author.name" = { type = "string", required = true }
+
+[pages.blog]
+reviewed = { type = "boolean", required = true }
+"#,
+    )
+    .unwrap();
+
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    let err = site.load().expect_err("Site should not load with an invalid extra");
+    let message = format!("{:#}", err);
+    assert!(message.contains(
+        "The `extra` of config.toml doesn't match the extra schema:\n- `extra.author.name` is required"
+    ));
+    assert!(message.contains(
+        "The `extra` of content/blog/something.md doesn't match the extra schema:\n- `extra.reviewed` is required"
+    ));
+    // Only the pages of the blog section need it
+    assert!(!message.contains("content/base.md"));
+
+    // Optional keys can be missing
+    std::fs::write(
+        root.join("extra_schema.toml"),
+        "[config]\n\"author.name\" = { type = \"string\", required = false }\n",
+    )
+    .unwrap();
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    assert!(site.load().is_ok());
+}
+
+#[test]
+fn can_list_and_update_backlinks() {
+    let (tmp_dir, root) = copy_site("test_site_i18n");
+    let blog = root.join("content").join("blog");
+    let page_template = root.join("templates").join("page.html");
+    let mut template = std::fs::read_to_string(&page_template).unwrap();
+    template
+        .push_str("{% for b in page.backlinks %}[{{ b.title }} {{ b.path | safe }}]{% endfor %}");
+    std::fs::write(&page_template, template).unwrap();
+    std::fs::write(blog.join("zebra.md"), "+++\ntitle = \"Zebra\"\n+++\n[a](@/blog/something.md)")
+        .unwrap();
+    std::fs::write(blog.join("apple.md"), "+++\ntitle = \"Apple\"\n+++\n[a](@/blog/something.md)")
+        .unwrap();
+
+    let (mut site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+    assert!(file_contains!(
+        public,
+        "blog/something/index.html",
+        "[Apple blog/apple.md][Zebra blog/zebra.md]"
+    ));
+    let summary = site.build_summary();
+    assert!(summary.pages.contains(&blog.join("something.md")));
+    assert!(summary.sections.contains(&blog.join("_index.md")));
+    assert!(summary.aggregates.contains(&"sitemap"));
+    assert_eq!(summary.phases[0].0, "Loaded content");
+
+    // Removing a link re-renders the page it used to point to
+    std::fs::write(blog.join("zebra.md"), "+++\ntitle = \"Zebra\"\n+++\nNo link").unwrap();
+    site.add_and_render_page(&blog.join("zebra.md")).unwrap();
+    assert!(file_contains!(public, "blog/something/index.html", "[Apple blog/apple.md]"));
+    assert!(!file_contains!(public, "blog/something/index.html", "Zebra"));
+    let summary = site.build_summary();
+    assert_eq!(summary.pages, vec![blog.join("something.md"), blog.join("zebra.md")]);
+    assert!(summary.sections.is_empty());
+    assert!(summary.aggregates.is_empty());
+}
+
+#[test]
+fn can_rerender_the_content_using_a_changed_shortcode() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let (mut site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+    assert!(file_contains!(public, "posts/python/index.html", "<h4>Basic shortcode</h4>"));
+
+    let shortcode = root.join("templates").join("shortcodes").join("basic.html");
+    std::fs::write(&shortcode, "<h4>Edited shortcode</h4>").unwrap();
+    site.rebuild(&Change::Template(shortcode)).unwrap();
+    assert!(file_contains!(public, "posts/python/index.html", "<h4>Edited shortcode</h4>"));
+    // Only the content using it was rendered again
+    let summary = site.build_summary();
+    assert_eq!(summary.pages, vec![root.join("content").join("posts").join("python.md")]);
+    assert!(summary.sections.is_empty());
+    assert!(summary.aggregates.is_empty());
+
+    // A new shortcode reloads everything, as content might have been using it already
+    let shortcode = root.join("templates").join("shortcodes").join("new.html");
+    std::fs::write(&shortcode, "New").unwrap();
+    site.rebuild(&Change::Template(shortcode)).unwrap();
+    assert!(site.build_summary().aggregates.contains(&"sitemap"));
+    assert!(file_contains!(public, "posts/python/index.html", "<h4>Edited shortcode</h4>"));
+}
+
+#[test]
+fn only_regenerates_the_aggregates_that_changed_when_serving() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let config = std::fs::read_to_string(root.join("config.toml")).unwrap();
+    let config = config
+        .replacen("generate_feeds = true", "generate_feeds = true\nbuild_search_index = true", 1)
+        .replace("feed = true", "feed = false");
+    std::fs::write(root.join("config.toml"), config).unwrap();
+    // Only the feeds of the whole site, the ones of the sections are rendered with them
+    for section in ["_index.md", "tutorials/programming/_index.md"] {
+        let path = root.join("content").join("posts").join(section);
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, content.replace("generate_feeds = true", "")).unwrap();
+    }
+    let (mut site, _) = build_copied_site(&tmp_dir, &root, |mut site| {
+        site.enable_serve_mode();
+        (site, true)
+    });
+    let aggregates = ["feeds", "search index", "sitemap"];
+    let summary = site.build_summary();
+    assert!(aggregates.iter().all(|a| summary.aggregates.contains(a)));
+    assert!(summary.unchanged_aggregates.is_empty());
+
+    // None of them use the template of the pages
+    let page_template = root.join("templates").join("page.html");
+    let template = std::fs::read_to_string(&page_template).unwrap();
+    std::fs::write(&page_template, format!("{}<!-- edited -->", template)).unwrap();
+    site.rebuild(&Change::Template(page_template)).unwrap();
+    let mut summary = site.build_summary();
+    summary.unchanged_aggregates.sort_unstable();
+    assert_eq!(summary.unchanged_aggregates, aggregates);
+    assert!(!aggregates.iter().any(|a| summary.aggregates.contains(a)));
+
+    // The content of a dated page is in the feed and the search index, not in the sitemap
+    let page = root.join("content").join("posts").join("python.md");
+    let content = std::fs::read_to_string(&page).unwrap();
+    std::fs::write(&page, content.replace("Same filename", "Another filename")).unwrap();
+    site.rebuild(&Change::Content(page)).unwrap();
+    let summary = site.build_summary();
+    assert!(summary.aggregates.contains(&"feeds"));
+    assert!(summary.aggregates.contains(&"search index"));
+    assert_eq!(summary.unchanged_aggregates, vec!["sitemap"]);
+
+    let sitemap_template = root.join("templates").join("sitemap.xml");
+    std::fs::write(&sitemap_template, "{{ entries | length }} entries").unwrap();
+    site.rebuild(&Change::Template(sitemap_template)).unwrap();
+    let mut summary = site.build_summary();
+    assert!(summary.aggregates.contains(&"sitemap"));
+    summary.unchanged_aggregates.sort_unstable();
+    assert_eq!(summary.unchanged_aggregates, vec!["feeds", "search index"]);
+}
+
+#[test]
+fn can_reload_created_and_deleted_templates() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let (mut site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+
+    let partial = root.join("templates").join("partials").join("signature.html");
+    std::fs::create_dir(root.join("templates").join("partials")).unwrap();
+    std::fs::write(&partial, "Signed by the partial").unwrap();
+    site.rebuild(&Change::Template(partial.clone())).unwrap();
+    let page_template = root.join("templates").join("page.html");
+    let template = std::fs::read_to_string(&page_template).unwrap();
+    std::fs::write(
+        &page_template,
+        template.replace(
+            "{% endblock content %}",
+            "{% include \"partials/signature.html\" %}{% endblock content %}",
+        ),
+    )
+    .unwrap();
+    site.rebuild(&Change::Template(page_template.clone())).unwrap();
+    assert!(file_contains!(public, "posts/simple/index.html", "Signed by the partial"));
+
+    std::fs::write(&page_template, &template).unwrap();
+    site.rebuild(&Change::Template(page_template)).unwrap();
+    std::fs::remove_file(&partial).unwrap();
+    site.rebuild(&Change::Template(partial)).unwrap();
+    assert!(site.tera.get_template("partials/signature.html").is_err());
+    assert!(!file_contains!(public, "posts/simple/index.html", "Signed by the partial"));
+
+    // The templates extending a deleted one now extend the one of the theme
+    let index_template = root.join("templates").join("index.html");
+    assert!(file_contains!(public, "index.html", "list-posts"));
+    std::fs::remove_file(&index_template).unwrap();
+    site.rebuild(&Change::Template(index_template)).unwrap();
+    assert!(!file_contains!(public, "index.html", "list-posts"));
+    assert!(file_contains!(public, "posts/simple/index.html", "fonts.googleapis.com"));
+}
+
+#[test]
+fn only_renders_again_what_uses_a_reloaded_template() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let (mut site, _) = build_copied_site(&tmp_dir, &root, |mut site| {
+        site.enable_serve_mode();
+        (site, true)
+    });
+
+    let page_template = root.join("templates").join("page.html");
+    let template = std::fs::read_to_string(&page_template).unwrap();
+    std::fs::write(&page_template, format!("{}<!-- edited -->", template)).unwrap();
+    site.rebuild(&Change::Template(page_template)).unwrap();
+    let summary = site.build_summary();
+    assert!(summary.pages.contains(&root.join("content").join("posts").join("simple.md")));
+    assert!(summary.sections.is_empty());
+
+    let section_template = root.join("templates").join("section.html");
+    let template = std::fs::read_to_string(&section_template).unwrap();
+    std::fs::write(&section_template, format!("{}<!-- edited -->", template)).unwrap();
+    site.rebuild(&Change::Template(section_template)).unwrap();
+    let summary = site.build_summary();
+    assert!(summary
+        .sections
+        .contains(&root.join("content").join("posts").join("tutorials").join("_index.md")));
+    assert!(!summary.sections.contains(&root.join("content").join("_index.md")));
+    assert!(!summary.pages.contains(&root.join("content").join("posts").join("simple.md")));
+
+    let unused = root.join("templates").join("unused.html");
+    std::fs::write(&unused, "Not used").unwrap();
+    site.rebuild(&Change::Template(unused)).unwrap();
+    let summary = site.build_summary();
+    assert!(summary.pages.is_empty());
+    assert!(summary.sections.is_empty());
+}
+
+#[test]
+fn can_use_the_builtin_image_shortcode() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let page = root.join("content").join("posts").join("with-assets").join("index.md");
+    let mut content = std::fs::read_to_string(&page).unwrap();
+    // The image is 256px wide so there is no 400px version
+    content.push_str(
+        "\n\n{{ image(path=\"zola.png\", widths=[200, 100, 400], alt=\"Zola\", caption=\"The logo\") }}",
+    );
+    std::fs::write(&page, content).unwrap();
+
+    let (mut site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+
+    let html = std::fs::read_to_string(public.join("posts/with-assets/index.html")).unwrap();
+    let srcset = html.split("srcset=\"").nth(1).unwrap().split('"').next().unwrap();
+    let urls: Vec<_> = srcset.split(", ").collect();
+    assert_eq!(urls.len(), 2);
+    assert!(urls[0].ends_with(" 100w"));
+    assert!(urls[1].ends_with(" 200w"));
+    for url in urls {
+        let url = url.split(' ').next().unwrap();
+        let filename = url.rsplit('/').next().unwrap();
+        assert!(url.starts_with("https://replace-this-with-your-url.com/processed_images/"));
+        assert!(root.join("static").join("processed_images").join(filename).exists());
+        assert!(file_exists!(public, &format!("processed_images/{}", filename)));
+    }
+    assert!(html.contains(r#"width="200" height="200" alt="Zola" loading="lazy">"#));
+    assert!(html.contains("<figure><img src="));
+    assert!(html.contains("<figcaption>The logo</figcaption></figure>"));
+
+    // With the dominant color of the image as placeholder
+    std::fs::write(
+        &page,
+        "+++\ntitle = \"With assets\"\ndate = 2015-03-01\n+++\n{{ image(path=\"zola.png\", placeholder=\"color\") }}",
+    )
+    .unwrap();
+    site.rebuild(&Change::Content(page.clone())).unwrap();
+    let html = std::fs::read_to_string(public.join("posts/with-assets/index.html")).unwrap();
+    assert!(html.contains(r#" style="background-color: #"#));
+
+    // With WebP versions in a `<picture>`
+    std::fs::write(
+        &page,
+        "+++\ntitle = \"With assets\"\ndate = 2015-03-01\n+++\n{{ image(path=\"zola.png\", widths=[100, 200], modern_formats=true) }}",
+    )
+    .unwrap();
+    site.rebuild(&Change::Content(page)).unwrap();
+    let html = std::fs::read_to_string(public.join("posts/with-assets/index.html")).unwrap();
+    assert!(html.contains(r#"<picture><source type="image/webp" srcset="https://"#));
+    assert!(html.contains(r#"loading="lazy"></picture>"#));
+    let webp_srcset = html.split("srcset=\"").nth(1).unwrap().split('"').next().unwrap();
+    for url in webp_srcset.split(", ") {
+        assert!(url.contains(".webp "));
+        let filename = url.split(' ').next().unwrap().rsplit('/').next().unwrap();
+        assert!(file_exists!(public, &format!("processed_images/{}", filename)));
+    }
+}
+
+#[test]
+fn can_render_implicit_sections_and_replace_them() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let (mut site, public) = build_copied_site(&tmp_dir, &root, |mut site| {
+        site.config.implicit_sections_render = true;
+        (site, true)
+    });
+    assert!(file_exists!(public, "posts/no-section/index.html"));
+    assert!(file_contains!(public, "posts/no-section/index.html", "Simple"));
+
+    let no_section = root.join("content/posts/no-section/_index.md");
+    std::fs::write(&no_section, "+++\ntitle = \"Not implicit anymore\"\n+++\n").unwrap();
+    site.add_and_render_section(&no_section).unwrap();
+    let library = site.library.read().unwrap();
+    let section = library.sections.get(&no_section).unwrap();
+    assert!(!section.implicit);
+    assert_eq!(section.meta.title.as_deref(), Some("Not implicit anymore"));
+    assert_eq!(section.pages, vec![root.join("content/posts/no-section/simple.md")]);
+}
+
+#[test]
+fn rerenders_parent_section_when_subsection_weight_changes() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let (mut site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+    let order = |public: &Path| {
+        let content = std::fs::read_to_string(public.join("posts/tutorials/index.html")).unwrap();
+        content.find("Programming").unwrap() < content.find("DevOps").unwrap()
+    };
+    assert!(order(&public));
+
+    let programming = root.join("content/posts/tutorials/programming/_index.md");
+    let content = std::fs::read_to_string(&programming).unwrap();
+    std::fs::write(&programming, content.replace("weight = 1\n", "weight = 20\n")).unwrap();
+    site.add_and_render_section(&programming).unwrap();
+    assert!(!order(&public));
+}
+
+#[test]
+fn can_detect_and_deduplicate_slug_collisions() {
+    let (_tmp_dir, root) = copy_site("test_site_i18n");
+    let blog = root.join("content").join("blog");
+    std::fs::write(blog.join("Something.md"), "+++\ntitle = \"Shouting\"\n+++\n").unwrap();
+
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    let err = format!("{:?}", site.load().unwrap_err());
+    assert!(err.contains(&blog.join("Something.md").display().to_string()));
+    assert!(err.contains(&blog.join("something.md").display().to_string()));
+
+    std::fs::write(blog.join("other.md"), "+++\ntitle = \"Other\"\nslug = \"something\"\n+++\n")
+        .unwrap();
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    site.config.deduplicate_slugs = true;
+    site.load().unwrap();
+    let library = site.library.read().unwrap();
+    let path_of = |name: &str| library.pages[&blog.join(name)].path.to_string();
+    assert_eq!(path_of("Something.md"), "/blog/something/");
+    assert_eq!(path_of("other.md"), "/blog/something-1/");
+    assert_eq!(path_of("something.md"), "/blog/something-2/");
+    // Translations are in their own directory so they don't collide
+    assert_eq!(path_of("something.fr.md"), "/fr/blog/something/");
+}
+
+#[test]
+fn can_load_only_part_of_the_content() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.load_only(Path::new("content/posts/tutorials")).unwrap();
+        (site, true)
+    });
+
+    let library = site.library.read().unwrap();
+    let posts = site.base_path.join("content").join("posts");
+    assert!(library.pages.keys().all(|p| p.starts_with(posts.join("tutorials"))));
+    assert!(library.pages.contains_key(&posts.join("tutorials").join("devops").join("nix.md")));
+    // The sections above are still loaded
+    assert!(library.sections.contains_key(&posts.join("_index.md")));
+    assert!(!library.sections.contains_key(&posts.join("2018").join("_index.md")));
+    assert!(site.is_content_loaded(&posts.join("_index.md")));
+    assert!(!site.is_content_loaded(&posts.join("python.md")));
+
+    assert!(file_exists!(public, "posts/tutorials/devops/nix/index.html"));
+    assert!(!file_exists!(public, "posts/python/index.html"));
+    assert!(file_contains!(
+        public,
+        "index.html",
+        "Partial build: only <code>content/posts/tutorials</code> is loaded"
+    ));
+}
+
+#[test]
+fn can_restrict_main_feed_to_some_sections() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.feed_sections = vec!["posts/tutorials".to_string()];
+        (site, true)
+    });
+
+    // The pages of the subsections of a listed section are in it too
+    assert!(file_contains!(public, "atom.xml", "posts/tutorials/devops/nix/"));
+    assert!(file_contains!(public, "atom.xml", "posts/tutorials/programming/rust/"));
+    assert!(!file_contains!(public, "atom.xml", "posts/simple/"));
+    assert!(!file_contains!(public, "atom.xml", "rebuild/first/"));
+    // Taxonomy feeds are not affected
+    assert!(file_contains!(public, "categories/a-category/atom.xml", "rebuild/first/"));
+}
+
+#[test]
+fn can_validate_generated_html() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let public = tmp_dir.path().join("public");
+    std::fs::write(
+        root.join("content/posts/broken.md"),
+        "+++\ntitle = \"Broken\"\ndate = 2024-01-01\n+++\n<div id=\"intro\">\n\n<span id=\"intro\">Hello</span> <img src=\"a.png\">",
+    )
+    .unwrap();
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    site.config.validate_html = true;
+    site.load().unwrap();
+    site.set_output_path(&public);
+    let err = site.build().unwrap_err().to_string();
+    assert!(err.starts_with("Found 3 problem(s) in the generated HTML:"), "{}", err);
+    assert!(err.contains("content/posts/broken.md: <div> opened on line"));
+    assert!(err.contains("content/posts/broken.md: the id `intro`"));
+    // The youtube shortcodes end up in the paragraph of the content_link one
+    assert!(err.contains("content/posts/simple.md: <div> on line"));
+    // Warnings don't fail the build
+    assert!(!err.contains("alt"));
+
+    site.config.html_validation.unclosed_elements = HtmlValidationLevel::Warn;
+    site.config.html_validation.duplicate_ids = HtmlValidationLevel::Off;
+    site.config.html_validation.invalid_nesting = HtmlValidationLevel::Warn;
+    site.build().unwrap();
+    assert!(file_exists!(public, "posts/broken/index.html"));
+
+    // `zola check` renders the site in memory to validate it
+    site.config.enable_check_mode();
+    site.config.link_checker.skip_prefixes = vec!["http".to_string()];
+    site.config.html_validation.duplicate_ids = HtmlValidationLevel::Error;
+    let err = site.load().unwrap_err().to_string();
+    assert!(err.starts_with("Found 1 problem(s) in the generated HTML:"), "{}", err);
+    assert!(err.contains("content/posts/broken.md: the id `intro`"));
+}
+
+#[test]
+fn can_set_canonical_url_and_robots_of_pages() {
+    let (tmp_dir, root) = copy_site("test_site");
+    std::fs::write(
+        root.join("content/posts/syndicated.md"),
+        "+++\ntitle = \"Syndicated\"\ndate = 2024-01-01\ncanonical_url = \"@/posts/simple.md\"\nrobots = \"noindex, nofollow\"\n+++\nHello",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("templates/page.html"),
+        "{{ page.canonical_url | safe }}|{{ page.robots }}",
+    )
+    .unwrap();
+    let (_, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+
+    assert!(file_contains!(
+        public,
+        "posts/syndicated/index.html",
+        "https://replace-this-with-your-url.com/posts/simple/|noindex, nofollow"
+    ));
+    assert!(!file_contains!(public, "sitemap.xml", "posts/syndicated/"));
+    assert!(file_contains!(public, "sitemap.xml", "posts/simple/"));
+
+    std::fs::write(
+        root.join("content/posts/syndicated.md"),
+        "+++\ntitle = \"Syndicated\"\ndate = 2024-01-01\ncanonical_url = \"@/posts/missing.md\"\n+++\nHello",
+    )
+    .unwrap();
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    let err = format!("{:#}", site.load().unwrap_err());
+    assert!(err.contains("The `canonical_url` of"), "{}", err);
+    assert!(err.contains("syndicated.md"), "{}", err);
+}
+
+#[test]
+fn can_check_accessibility() {
+    let (_tmp_dir, root) = copy_site("test_site");
+    std::fs::write(
+        root.join("content/posts/inaccessible.md"),
+        "+++\ntitle = \"Inaccessible\"\ndate = 2024-01-01\n+++\n## Part\n\n#### Detail\n\nRead more [here](@/posts/simple.md). <img src=\"a.png\">",
+    )
+    .unwrap();
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    site.config.enable_check_mode();
+    site.config.link_checker.skip_prefixes = vec!["http".to_string()];
+    site.load().unwrap();
+    // Everything is a warning by default
+    site.check_accessibility().unwrap();
+
+    site.config.check.a11y.heading_skips = HtmlValidationLevel::Error;
+    site.config.check.a11y.vague_link_text = HtmlValidationLevel::Error;
+    site.config.check.a11y.missing_main = HtmlValidationLevel::Off;
+    let err = site.check_accessibility().unwrap_err().to_string();
+    assert!(err.starts_with("Found 3 accessibility problem(s) in the generated HTML:"), "{}", err);
+    assert!(err.contains("\nHeading levels skipped (1):\n- content/posts/inaccessible.md: <h4>"));
+    assert!(
+        err.contains("\nLinks without a descriptive text (2):\n- content/posts/inaccessible.md:")
+    );
+    assert!(!err.contains("alt"));
+}
+
+#[test]
+fn can_report_mixed_self_links() {
+    let (_tmp_dir, root) = copy_site("test_site");
+    std::fs::write(
+        root.join("content/posts/old-links.md"),
+        "+++\ntitle = \"Old links\"\ndate = 2016-01-01\n+++\n[Python](http://replace-this-with-your-url.com/posts/python/)",
+    )
+    .unwrap();
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    site.config.link_checker.self_links_level = LinkCheckerLevel::Error;
+    let err = site.load().unwrap_err().to_string();
+    assert!(err.starts_with("Found 1 broken self link(s)"), "{}", err);
+    assert!(err.contains(
+        "The link `http://replace-this-with-your-url.com/posts/python/` in content/posts/old-links.md \
+         points to the site without using its base_url, use `@/posts/python.md` instead."
+    ));
+
+    site.config.link_checker.rewrite_self_links = true;
+    site.load().unwrap();
+    let library = site.library.read().unwrap();
+    let page = &library.pages[&root.join("content/posts/old-links.md")];
+    assert!(page.content.contains("href=\"https://replace-this-with-your-url.com/posts/python/\""));
+    assert_eq!(page.internal_links, vec![("posts/python.md".to_string(), None)]);
+}
+
+#[test]
+fn injects_livereload_once_per_document() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let public = tmp_dir.path().join("public");
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    site.enable_live_reload_with_port(1234);
+    site.load().unwrap();
+    site.set_output_path(&public);
+
+    for minify in [false, true] {
+        site.config.minify_html = minify;
+        site.build().unwrap();
+        let index = std::fs::read_to_string(public.join("index.html")).unwrap();
+        if !minify {
+            assert!(index.contains(&format!("{}</body>", site::LIVE_RELOAD_SCRIPT)));
+        }
+        for entry in libs::walkdir::WalkDir::new(&public) {
+            let path = entry.unwrap().into_path();
+            if path.extension().map(|e| e != "html").unwrap_or(true) {
+                continue;
+            }
+            // Only the rendered documents are injected, not the static files
+            let relative_path = path.strip_prefix(&public).unwrap();
+            if root.join("static").join(relative_path).exists()
+                || root.join("themes/sample/static").join(relative_path).exists()
+            {
+                continue;
+            }
+            let html = std::fs::read_to_string(&path).unwrap();
+            // Redirections are not injected
+            let expected =
+                if html.contains("http-equiv=\"refresh\"") || html.contains("http-equiv=refresh") {
+                    0
+                } else {
+                    1
+                };
+            assert_eq!(html.matches("livereload.js").count(), expected, "{}", path.display());
+        }
+    }
+}
+
+#[test]
+fn can_use_site_index_in_templates_and_shortcodes() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let templates = root.join("templates");
+    let index = std::fs::read_to_string(templates.join("index.html")).unwrap();
+    std::fs::write(
+        templates.join("index.html"),
+        index.replace(
+            "{% endblock content %}",
+            "Indexed {{ site.sections | length }} sections and {{ site.pages_count }} pages\n{% endblock content %}",
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        templates.join("shortcodes/top_sections.html"),
+        r#"{% set index = get_site_index(full=true) %}{% for s in index.sections[0].subsections %}[{{ s.title }}:{{ s.page_count }}:{{ s.subsections | length }}]{% endfor %}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("content/posts/sitemap-page.md"),
+        "+++\ntitle = \"Site map\"\ndate = 2024-01-01\n+++\n{{ top_sections() }}",
+    )
+    .unwrap();
+
+    let (mut site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+
+    let (sections, pages) = {
+        let library = site.library.read().unwrap();
+        let posts = &library.site_index.sections[1];
+        assert_eq!(posts.relative_path, "posts/_index.md");
+        assert_eq!(posts.parent.as_deref(), Some("_index.md"));
+        (library.site_index.sections.len(), library.site_index.pages_count)
+    };
+    assert!(file_contains!(
+        public,
+        "index.html",
+        &format!("Indexed {} sections and {} pages", sections, pages)
+    ));
+    assert!(file_contains!(public, "posts/sitemap-page/index.html", "[Posts:"));
+
+    // Adding a section in `zola serve` refreshes the index
+    let new_section = root.join("content/news/_index.md");
+    std::fs::create_dir_all(new_section.parent().unwrap()).unwrap();
+    std::fs::write(&new_section, "+++\ntitle = \"News\"\n+++\n").unwrap();
+    site.add_and_render_section(&new_section).unwrap();
+    let library = site.library.read().unwrap();
+    assert_eq!(library.site_index.sections.len(), sections + 1);
+    assert!(library.site_index.sections.iter().any(|s| s.title.as_deref() == Some("News")));
+}
+
+#[test]
+fn gives_the_content_stats_to_the_templates() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let templates = root.join("templates");
+    let index = std::fs::read_to_string(templates.join("index.html")).unwrap();
+    std::fs::write(
+        templates.join("index.html"),
+        index.replace(
+            "{% endblock content %}",
+            "Inventory: {{ site.stats.pages }} pages, {{ site.stats.unlisted }} unlisted, {{ site.stats.missing_description }} without description\n{% endblock content %}",
+        ),
+    )
+    .unwrap();
+
+    let (mut site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+    let stats = site.library.read().unwrap().site_index.stats;
+
+    let pages = site.library.read().unwrap().pages.values().cloned().collect::<Vec<_>>();
+    assert_eq!(stats, ContentStats::new(&pages));
+    assert_eq!(stats.drafts, 0);
+    assert!(stats.word_count > 0);
+    assert!(file_contains!(
+        public,
+        "index.html",
+        &format!(
+            "Inventory: {} pages, {} unlisted, {} without description",
+            stats.pages, stats.unlisted, stats.missing_description
+        )
+    ));
+
+    // Adding a page in `zola serve` updates them
+    let new_page = root.join("content/posts/inventory.md");
+    std::fs::write(&new_page, "+++\ntitle = \"Inventory\"\nunlisted = true\n+++\nA few words")
+        .unwrap();
+    site.add_and_render_page(&new_page).unwrap();
+    let library = site.library.read().unwrap();
+    assert_eq!(library.site_index.stats.pages, stats.pages + 1);
+    assert_eq!(library.site_index.stats.unlisted, stats.unlisted + 1);
+    assert_eq!(library.site_index.stats.missing_description, stats.missing_description + 1);
+    assert_eq!(library.site_index.stats.word_count, stats.word_count + 3);
+}
+
+#[test]
+fn can_build_with_missing_content_in_warn_mode() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let public = tmp_dir.path().join("public");
+    let index = root.join("templates/index.html");
+    let template = std::fs::read_to_string(&index).unwrap();
+    std::fs::write(
+        &index,
+        template.replace(
+            "{% endblock content %}",
+            r#"{% set old = get_page(path="blog/old-post.md") %}[{% if old %}{{ old.title }}{% else %}gone{% endif %}]{% endblock content %}"#,
+        ),
+    )
+    .unwrap();
+
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    site.load().unwrap();
+    site.set_output_path(&public);
+    assert!(site.build().is_err());
+
+    site.config.missing_content = config::MissingContent::Warn;
+    site.load().unwrap();
+    site.build().unwrap();
+    assert!(file_contains!(public, "index.html", "[gone]"));
+}
+
+#[test]
+fn can_generate_favicons() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let index = root.join("templates/index.html");
+    let template = std::fs::read_to_string(&index).unwrap();
+    std::fs::write(
+        &index,
+        template.replace("{% endblock content %}", "{{ favicon_tags() }}{% endblock content %}"),
+    )
+    .unwrap();
+
+    let (_, public) = build_copied_site(&tmp_dir, &root, |mut site| {
+        site.config.favicon.source = Some("content/posts/with-assets/zola.png".to_string());
+        site.config.favicon.background = Some("#fff".to_string());
+        (site, true)
+    });
+
+    for file in ["favicon.ico", "favicon-32x32.png", "apple-touch-icon.png", "site.webmanifest"] {
+        assert!(file_exists!(public, &format!("processed_images/favicon/{}", file)));
+    }
+    assert!(file_contains!(
+        public,
+        "index.html",
+        r#"<link rel="manifest" href="https://replace-this-with-your-url.com/processed_images/favicon/site.webmanifest">"#
+    ));
+}
+
+#[test]
+fn can_build_protected_pages() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let secret = "The treasure is buried under the old oak";
+    for (filename, protected) in
+        [("members.md", r#"{ name = "members" }"#), ("secret.md", r#"{ password = "hunter2" }"#)]
+    {
+        std::fs::write(
+            root.join("content/posts").join(filename),
+            format!(
+                "+++\ntitle = \"Members\"\ndate = 2030-01-01\nprotected = {}\n+++\n{}\n<!-- more -->\nMore",
+                protected, secret
+            ),
+        )
+        .unwrap();
+    }
+
+    let (mut site, public) = build_copied_site(&tmp_dir, &root, |mut site| {
+        site.config.passwords.insert("members".to_string(), "correct horse".to_string());
+        site.config.build_search_index = true;
+        site.include_future();
+        (site, true)
+    });
+
+    assert!(file_contains!(public, "posts/members/index.html", "zola-protected"));
+    assert!(file_contains!(public, "posts/secret/index.html", r#""iterations":100000"#));
+    assert!(!file_contains!(public, "atom.xml", "posts/members/"));
+    for entry in libs::walkdir::WalkDir::new(&public) {
+        let path = entry.unwrap().into_path();
+        if path.is_file() {
+            let content = std::fs::read(&path).unwrap();
+            assert!(
+                !String::from_utf8_lossy(&content).contains(secret),
+                "{} contains the protected content",
+                path.display()
+            );
+        }
+    }
+
+    // A name that isn't in the config is an error
+    site.config.passwords.clear();
+    assert!(site.load().is_err());
+}
+
+#[test]
+fn can_use_timestamps_and_times_as_page_dates() {
+    let (tmp_dir, root) = copy_site("test_site");
+    for (filename, date) in [
+        ("timestamp.md", "date = 1525163400"),
+        ("with-time.md", "date = 2018-05-02\ntime = \"08:30\""),
+    ] {
+        std::fs::write(
+            root.join("content/posts").join(filename),
+            format!("+++\ntitle = \"{}\"\n{}\n+++\nHello", filename, date),
+        )
+        .unwrap();
+    }
+
+    let (site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+
+    let library = site.library.read().unwrap();
+    let page = &library.pages[&root.join("content/posts/with-time.md")];
+    assert_eq!(page.meta.datetime_tuple, Some((2018, 5, 2)));
+    assert_eq!(page.meta.date.as_deref(), Some("2018-05-02T08:30:00"));
+    assert!(file_contains!(public, "atom.xml", "<published>2018-05-01T08:30:00+00:00</published>"));
+    assert!(file_contains!(public, "atom.xml", "<published>2018-05-02T08:30:00"));
+}
+
+#[test]
+fn can_leave_out_future_pages() {
+    let (tmp_dir, root) = copy_site("test_site");
+    std::fs::write(
+        root.join("content/posts/upcoming.md"),
+        "+++\ntitle = \"Upcoming\"\ndate = 2999-01-01T09:00:00+02:00\n+++\nSoon",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("content/posts/tutorials/programming/conference.md"),
+        "+++\ntitle = \"Conference\"\nweight = 10\ndate = 2999-01-01\n+++\nSoon",
+    )
+    .unwrap();
+    let programming = root.join("content/posts/tutorials/programming/_index.md");
+    let section = std::fs::read_to_string(&programming).unwrap();
+    std::fs::write(&programming, section.replacen("+++\n", "+++\ninclude_future = true\n", 1))
+        .unwrap();
+
+    let (site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+    assert!(!file_exists!(public, "posts/upcoming/index.html"));
+    assert!(!file_contains!(public, "atom.xml", "Upcoming"));
+    assert!(!file_contains!(public, "sitemap.xml", "posts/upcoming/"));
+    assert!(!file_contains!(public, "posts/index.html", "Upcoming"));
+    // Its section includes the future pages
+    assert!(file_exists!(public, "posts/tutorials/programming/conference/index.html"));
+    {
+        let library = site.library.read().unwrap();
+        let conference = root.join("content/posts/tutorials/programming/conference.md");
+        assert!(library.pages[&conference].future);
+    }
+
+    build_copied_site(&tmp_dir, &root, |mut site| {
+        site.include_future();
+        (site, true)
+    });
+    assert!(file_exists!(public, "posts/upcoming/index.html"));
+    assert!(file_contains!(public, "atom.xml", "Upcoming"));
+}
+
+#[test]
+fn can_get_page_weights_from_filenames() {
+    let (tmp_dir, root) = copy_site("test_site");
+    let programming = root.join("content/posts/tutorials/programming");
+    std::fs::write(programming.join("05_debugging.md"), "+++\ntitle = \"Debugging\"\n+++\nBugs")
+        .unwrap();
+    std::fs::write(programming.join("no-weight.md"), "+++\ntitle = \"No weight\"\n+++\nLost")
+        .unwrap();
+
+    let (site, public) = build_copied_site(&tmp_dir, &root, |site| (site, true));
+
+    assert!(file_exists!(public, "posts/tutorials/programming/debugging/index.html"));
+    let library = site.library.read().unwrap();
+    let section = &library.sections[&programming.join("_index.md")];
+    let debugging = programming.join("05_debugging.md");
+    assert_eq!(library.pages[&debugging].meta.weight, Some(5));
+    assert_eq!(section.pages.last(), Some(&debugging));
+    assert_eq!(section.ignored_pages, vec![programming.join("no-weight.md")]);
+}
+
+#[test]
+fn can_make_templates_strict() {
+    // The undefined variables of the shortcodes and templates of the test site are only
+    // errors with strict templates
+    let (site, _tmp_dir, _public) = build_site("test_site");
+    let root = site.base_path.clone();
+    assert!(site.check_templates().is_ok());
+
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    site.config.strict_templates = true;
+    let err = site.load().unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(message.contains("posts/simple.md"), "{}", message);
+    assert!(
+        message.contains("Variable `class` used in template `templates/shortcodes/youtube.html`")
+    );
+
+    // `zola check` reports everything at once, with the content file being rendered
+    let mut site = Site::new(&root, "config.toml").unwrap();
+    site.load().unwrap();
+    site.config.strict_templates = true;
+    let message = format!("{:#}", site.check_templates().unwrap_err());
+    assert!(message.contains("posts/simple.md"), "{}", message);
+    assert!(message.contains("Failed to render page"), "{}", message);
+    assert!(message.contains("Variable `page.earlier` used in template `templates/page.html`"));
+}
+
+#[test]
+fn picks_the_same_random_page_for_the_whole_build() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+    site.load().unwrap();
+
+    let template = r#"{% set page = get_random_page(section="posts") %}{{ page.permalink }}"#;
+    let context = libs::tera::Context::new();
+    let first = site.tera.render_str(template, &context).unwrap();
+    let second = site.tera.render_str(template, &context).unwrap();
+    assert_eq!(first, second);
+    let library = site.library.read().unwrap();
+    let page = library.pages.values().find(|p| *p.permalink == first).unwrap();
+    assert!(page.file.components.starts_with(&["posts".to_string()]));
+    assert!(!page.meta.draft && !page.meta.unlisted);
+}
+
+#[test]
+fn gives_the_build_info_to_the_templates() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+    site.set_version("1.2.3");
+    site.load().unwrap();
+
+    let build_info = &site.config.build_info;
+    assert_eq!(build_info.version, "1.2.3");
+    let mut context = libs::tera::Context::new();
+    context.insert("build_info", build_info);
+    let rendered = site
+        .tera
+        .render_str("{{ now() }} {{ now(timestamp=true) }} {{ build_info.timestamp }}", &context)
+        .unwrap();
+    assert_eq!(
+        rendered,
+        format!("{} {} {}", build_info.timestamp, build_info.unix_timestamp, build_info.timestamp)
+    );
+}
+
+#[test]
+fn can_render_content_files_in_templates() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+    site.load().unwrap();
+
+    let html = site
+        .tera
+        .render_str(
+            r#"{{ render_content_file(path="posts/simple.md") }}"#,
+            &libs::tera::Context::new(),
+        )
+        .unwrap();
+    assert!(html.starts_with("<p>A simple page</p>"));
+    // The shortcodes are rendered and the internal links resolved
+    assert!(html.contains("https://www.youtube-nocookie.com/embed/e1C9kpMV2e8"));
+    assert!(html.contains("https://replace-this-with-your-url.com/posts/python/"));
+}
+
+#[test]
+fn can_link_assets_from_the_asset_base_url() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.asset_base_url = Some("https://cdn.example.com".to_string());
+        (site, true)
+    });
+
+    assert!(file_contains!(public, "index.html", "https://cdn.example.com/scripts/hello.js"));
+    let library = site.library.read().unwrap();
+    let page =
+        library.pages.values().find(|p| p.file.relative == "posts/with-assets/index.md").unwrap();
+    // The pages stay on the `base_url`
+    assert_eq!(&*page.permalink, "https://replace-this-with-your-url.com/posts/with-assets/");
+    assert!(page
+        .serialized_assets
+        .contains(&"https://cdn.example.com/posts/with-assets/zola.png".to_string()));
+}
+
+#[test]
+fn can_change_where_feeds_and_sitemap_are_written() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.feed_filename = Some("feed/index.xml".to_string());
+        site.config.sitemap_filename = "sitemap_index.xml".to_string();
+        (site, true)
+    });
+
+    assert!(!file_exists!(public, "atom.xml"));
+    assert!(!file_exists!(public, "sitemap.xml"));
+    assert!(file_contains!(
+        public,
+        "feed/index.xml",
+        r#"<link rel="self" type="application/atom+xml" href="https://replace-this-with-your-url.com/feed/index.xml"/>"#
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/feed/index.xml",
+        "https://replace-this-with-your-url.com/posts/feed/index.xml"
+    ));
+    assert!(file_contains!(
+        public,
+        "sitemap_index.xml",
+        "https://replace-this-with-your-url.com/posts/"
+    ));
+
+    // Content can't be written where they are
+    let mut site = Site::new(&site.base_path, "config.toml").unwrap();
+    site.config.sitemap_filename = "top-level.html".to_string();
+    let err = site.load().unwrap_err();
+    assert!(err.to_string().contains("`top-level.html`"), "{}", err);
+}
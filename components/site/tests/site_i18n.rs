@@ -53,6 +53,8 @@ fn can_parse_multilingual_site() {
 #[test]
 fn can_build_multilingual_site() {
     let (_, _tmp_dir, public) = build_site("test_site_i18n");
+    // There is no 50x.html template
+    assert!(!file_exists!(public, "50x.html"));
 
     assert!(public.exists());
 
@@ -191,7 +193,7 @@ fn correct_translations_on_all_pages() {
 
     let translations = find_expected_translations("test_site_i18n", &site.config.default_language);
 
-    for (path, link) in &site.permalinks {
+    for (path, link) in site.permalinks.iter() {
         // link ends with /, does not add index.html
         let link = format!("{}index.html", link);
 
@@ -11,18 +11,27 @@ use libs::tera::{
     to_value, try_get_value, Error as TeraError, Filter as TeraFilter, Result as TeraResult, Tera,
     Value,
 };
+use libs::time::{Month, Weekday};
 use markdown::{render_content, RenderContext};
 
+use crate::ZOLA_TERA;
+
 #[derive(Debug)]
 pub struct MarkdownFilter {
     config: Config,
-    permalinks: HashMap<String, String>,
+    data: Arc<Value>,
+    permalinks: Arc<HashMap<String, Arc<str>>>,
     tera: Tera,
 }
 
 impl MarkdownFilter {
-    pub fn new(config: Config, permalinks: HashMap<String, String>, tera: Tera) -> Self {
-        Self { config, permalinks, tera }
+    pub fn new(
+        config: Config,
+        data: Arc<Value>,
+        permalinks: Arc<HashMap<String, Arc<str>>>,
+        tera: Tera,
+    ) -> Self {
+        Self { config, data, permalinks, tera }
     }
 }
 
@@ -32,7 +41,7 @@ impl TeraFilter for MarkdownFilter {
         // However, it should not be a problem because the surrounding tera
         // template has language context, and will most likely call a piece of
         // markdown respecting language preferences.
-        let mut context = RenderContext::from_config(&self.config);
+        let mut context = RenderContext::from_config(&self.config, &self.data);
         context.permalinks = Cow::Borrowed(&self.permalinks);
         context.tera = Cow::Borrowed(&self.tera);
         let def = utils::templates::get_shortcodes(&self.tera);
@@ -81,6 +90,45 @@ pub fn base64_decode<S: BuildHasher>(
     Ok(to_value(as_str).unwrap())
 }
 
+/// Removes the HTML tags of the value and decodes its entities, eg to use `page.summary`
+/// in a meta tag
+pub fn plain_text<S: BuildHasher>(
+    value: &Value,
+    _: &HashMap<String, Value, S>,
+) -> TeraResult<Value> {
+    let s = try_get_value!("plain_text", "value", String, value);
+    Ok(to_value(utils::html::strip_html(&s)).unwrap())
+}
+
+/// Truncates the value to `length` characters, 200 by default, without cutting emojis,
+/// accented letters or HTML entities in half. The tags left open are closed, or removed first
+/// with `strip_html=true`. `suffix` is added if anything was removed, `…` by default.
+pub fn safe_truncate<S: BuildHasher>(
+    value: &Value,
+    args: &HashMap<String, Value, S>,
+) -> TeraResult<Value> {
+    let s = try_get_value!("safe_truncate", "value", String, value);
+    let length = match args.get("length") {
+        Some(val) => try_get_value!("safe_truncate", "length", usize, val),
+        None => 200,
+    };
+    let suffix = match args.get("suffix") {
+        Some(val) => try_get_value!("safe_truncate", "suffix", String, val),
+        None => "…".to_string(),
+    };
+    let strip = match args.get("strip_html") {
+        Some(val) => try_get_value!("safe_truncate", "strip_html", bool, val),
+        None => false,
+    };
+
+    let (truncated, _) = if strip {
+        utils::html::truncate_text(&utils::html::strip_html(&s), length, &suffix)
+    } else {
+        utils::html::truncate_chars(&s, length, &suffix)
+    };
+    Ok(to_value(truncated).unwrap())
+}
+
 #[derive(Debug)]
 pub struct RegexReplaceFilter {
     re_cache: Arc<Mutex<HashMap<String, Regex>>>,
@@ -158,21 +206,82 @@ impl TeraFilter for NumFormatFilter {
     }
 }
 
+/// Wraps the Tera builtin `date` filter to use `config.date_format` when no format is given
+/// and to take month/weekday names from the translations when a `locale` is given
+#[derive(Debug)]
+pub struct DateFilter {
+    config: Config,
+}
+
+impl DateFilter {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+}
+
+impl TeraFilter for DateFilter {
+    fn filter(&self, value: &Value, args: &HashMap<String, Value>) -> TeraResult<Value> {
+        let builtin = ZOLA_TERA.get_filter("date")?;
+        let mut args = args.clone();
+        let mut format = match args.get("format") {
+            Some(val) => try_get_value!("date", "format", String, val),
+            None => self.config.date_format.clone(),
+        };
+
+        let locale = match args.get("locale") {
+            Some(val) => Some(try_get_value!("date", "locale", String, val)),
+            None => None,
+        };
+        // Only handle the locales that are languages of the site, the other ones are
+        // forwarded as is to Tera
+        if let Some(lang) = locale.filter(|l| self.config.languages.contains_key(l)) {
+            // Let Tera find the month/weekday so it works with every input and timezone
+            let mut date_args = args.clone();
+            date_args.remove("locale");
+            date_args.insert("format".to_string(), to_value("%m %u").unwrap());
+            let numbers =
+                try_get_value!("date", "value", String, builtin.filter(value, &date_args)?);
+            let (month, weekday) = numbers.split_once(' ').expect("Got the format we asked for");
+            let month = Month::try_from(month.parse::<u8>().unwrap()).unwrap();
+            let weekday = match weekday.parse::<u8>().unwrap() {
+                1 => Weekday::Monday,
+                2 => Weekday::Tuesday,
+                3 => Weekday::Wednesday,
+                4 => Weekday::Thursday,
+                5 => Weekday::Friday,
+                6 => Weekday::Saturday,
+                _ => Weekday::Sunday,
+            };
+
+            format = format
+                .replace("%B", &self.config.get_month_name(&lang, month).replace('%', "%%"))
+                .replace("%A", &self.config.get_weekday_name(&lang, weekday).replace('%', "%%"));
+            args.remove("locale");
+        }
+
+        args.insert("format".to_string(), to_value(format).unwrap());
+        builtin.filter(value, &args)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
+    use std::sync::Arc;
 
     use libs::tera::{to_value, Filter, Tera};
 
     use super::{
-        base64_decode, base64_encode, MarkdownFilter, NumFormatFilter, RegexReplaceFilter,
+        base64_decode, base64_encode, plain_text, safe_truncate, DateFilter, MarkdownFilter,
+        NumFormatFilter, RegexReplaceFilter,
     };
     use config::Config;
 
     #[test]
     fn markdown_filter() {
-        let result = MarkdownFilter::new(Config::default(), HashMap::new(), Tera::default())
-            .filter(&to_value(&"# Hey").unwrap(), &HashMap::new());
+        let result =
+            MarkdownFilter::new(Config::default(), Arc::default(), Arc::default(), Tera::default())
+                .filter(&to_value(&"# Hey").unwrap(), &HashMap::new());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), to_value(&"<h1 id=\"hey\">Hey</h1>\n").unwrap());
     }
@@ -184,25 +293,42 @@ mod tests {
         // NOTE: This should also work for `nth` although i don't see a reason to do that
         let args = HashMap::new();
         let config = Config::default();
-        let permalinks = HashMap::new();
+        let permalinks = Arc::default();
         let mut tera = Tera::default();
         tera.add_raw_template("shortcodes/explicitlang.html", "a{{ lang }}a").unwrap();
-        let filter = MarkdownFilter { config, permalinks, tera };
+        let filter = MarkdownFilter { config, data: Arc::default(), permalinks, tera };
         let result = filter.filter(&to_value(&"{{ explicitlang(lang='jp') }}").unwrap(), &args);
         println!("{:?}", result);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), to_value(&"ajpa").unwrap());
     }
 
+    #[test]
+    fn markdown_filter_shortcodes_can_read_config() {
+        let mut config = Config::default_for_test();
+        config.languages.get_mut("en").unwrap().title = Some("My site".to_string());
+        config.extra.insert("plausible_domain".to_string(), "example.com".into());
+        let mut tera = Tera::default();
+        tera.add_raw_template(
+            "shortcodes/analytics.html",
+            "{{ config.title }} on {{ config.extra.plausible_domain }}",
+        )
+        .unwrap();
+        let filter = MarkdownFilter::new(config, Arc::default(), Arc::default(), tera);
+        let result = filter.filter(&to_value("{{ analytics() }}").unwrap(), &HashMap::new());
+        assert_eq!(result.unwrap(), to_value("My site on example.com").unwrap());
+    }
+
     #[test]
     fn markdown_filter_inline() {
         let mut args = HashMap::new();
         args.insert("inline".to_string(), to_value(true).unwrap());
-        let result = MarkdownFilter::new(Config::default(), HashMap::new(), Tera::default())
-            .filter(
-                &to_value(&"Using `map`, `filter`, and `fold` instead of `for`").unwrap(),
-                &args,
-            );
+        let result =
+            MarkdownFilter::new(Config::default(), Arc::default(), Arc::default(), Tera::default())
+                .filter(
+                    &to_value(&"Using `map`, `filter`, and `fold` instead of `for`").unwrap(),
+                    &args,
+                );
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), to_value(&"Using <code>map</code>, <code>filter</code>, and <code>fold</code> instead of <code>for</code>").unwrap());
     }
@@ -212,19 +338,20 @@ mod tests {
     fn markdown_filter_inline_tables() {
         let mut args = HashMap::new();
         args.insert("inline".to_string(), to_value(true).unwrap());
-        let result = MarkdownFilter::new(Config::default(), HashMap::new(), Tera::default())
-            .filter(
-                &to_value(
-                    &r#"
+        let result =
+            MarkdownFilter::new(Config::default(), Arc::default(), Arc::default(), Tera::default())
+                .filter(
+                    &to_value(
+                        &r#"
 |id|author_id|       timestamp_created|title                 |content           |
 |-:|--------:|-----------------------:|:---------------------|:-----------------|
 | 1|        1|2018-09-05 08:03:43.141Z|How to train your ORM |Badly written blog|
 | 2|        1|2018-08-22 13:11:50.050Z|How to bake a nice pie|Badly written blog|
         "#,
-                )
-                .unwrap(),
-                &args,
-            );
+                    )
+                    .unwrap(),
+                    &args,
+                );
         assert!(result.is_ok());
         assert!(result.unwrap().as_str().unwrap().contains("<table>"));
     }
@@ -238,13 +365,14 @@ mod tests {
         config.markdown.external_links_target_blank = true;
 
         let md = "Hello <https://google.com> :smile: ...";
-        let result = MarkdownFilter::new(config.clone(), HashMap::new(), Tera::default())
-            .filter(&to_value(&md).unwrap(), &HashMap::new());
+        let result =
+            MarkdownFilter::new(config.clone(), Arc::default(), Arc::default(), Tera::default())
+                .filter(&to_value(&md).unwrap(), &HashMap::new());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), to_value(&"<p>Hello <a rel=\"noopener\" target=\"_blank\" href=\"https://google.com\">https://google.com</a> 😄 …</p>\n").unwrap());
 
         let md = "```py\ni=0\n```";
-        let result = MarkdownFilter::new(config, HashMap::new(), Tera::default())
+        let result = MarkdownFilter::new(config, Arc::default(), Arc::default(), Tera::default())
             .filter(&to_value(&md).unwrap(), &HashMap::new());
         assert!(result.is_ok());
         assert!(result.unwrap().as_str().unwrap().contains("style"));
@@ -253,10 +381,15 @@ mod tests {
     #[test]
     fn mardown_filter_can_use_internal_links() {
         let mut permalinks = HashMap::new();
-        permalinks.insert("blog/_index.md".to_string(), "/foo/blog".to_string());
+        permalinks.insert("blog/_index.md".to_string(), "/foo/blog".into());
         let md = "Hello. Check out [my blog](@/blog/_index.md)!";
-        let result = MarkdownFilter::new(Config::default(), permalinks, Tera::default())
-            .filter(&to_value(&md).unwrap(), &HashMap::new());
+        let result = MarkdownFilter::new(
+            Config::default(),
+            Arc::default(),
+            Arc::new(permalinks),
+            Tera::default(),
+        )
+        .filter(&to_value(&md).unwrap(), &HashMap::new());
         assert!(result.is_ok());
         assert_eq!(
             result.unwrap(),
@@ -358,4 +491,76 @@ mod tests {
             assert_eq!(result.unwrap(), to_value(expected).unwrap());
         }
     }
+
+    #[test]
+    fn date_filter_uses_config_format() {
+        let mut config = Config::default_for_test();
+        config.date_format = "%d/%m/%Y".to_string();
+        let filter = DateFilter::new(config);
+        let result = filter.filter(&to_value("2024-03-05").unwrap(), &HashMap::new());
+        assert_eq!(result.unwrap(), to_value("05/03/2024").unwrap());
+
+        // Works with timestamps as well
+        let result = filter.filter(&to_value(1709596800).unwrap(), &HashMap::new());
+        assert_eq!(result.unwrap(), to_value("05/03/2024").unwrap());
+
+        let mut args = HashMap::new();
+        args.insert("format".to_string(), to_value("%Y").unwrap());
+        let result = filter.filter(&to_value("2024-03-05").unwrap(), &args);
+        assert_eq!(result.unwrap(), to_value("2024").unwrap());
+    }
+
+    #[test]
+    fn date_filter_uses_translations_for_names() {
+        let config = Config::parse(
+            r#"
+base_url = "https://example.com"
+date_format = "%A %e %B %Y"
+
+[languages.fr.translations]
+march = "mars"
+tuesday = "mardi"
+"#,
+        )
+        .unwrap();
+        let filter = DateFilter::new(config);
+        let mut args = HashMap::new();
+        args.insert("locale".to_string(), to_value("fr").unwrap());
+        let result = filter.filter(&to_value("2024-03-05T10:00:00Z").unwrap(), &args);
+        assert_eq!(result.unwrap(), to_value("mardi  5 mars 2024").unwrap());
+
+        // Not a site language: handled by Tera
+        args.insert("locale".to_string(), to_value("fr_FR").unwrap());
+        let result = filter.filter(&to_value("2024-03-05T10:00:00Z").unwrap(), &args);
+        assert_eq!(result.unwrap(), to_value("mardi  5 mars 2024").unwrap());
+    }
+
+    #[test]
+    fn plain_text_filter() {
+        let result = plain_text(
+            &to_value("<p>Hello <em>world</em> &amp; friends</p>\n<p>Bye</p>").unwrap(),
+            &HashMap::<String, _>::new(),
+        );
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), to_value("Hello world & friends Bye").unwrap());
+    }
+
+    #[test]
+    fn safe_truncate_filter() {
+        let html = "<p>Caf\u{e9} &amp; cr\u{e8}me br\u{fb}l\u{e9}e \u{1F36E}</p>";
+        let mut args = HashMap::new();
+        args.insert("length".to_string(), to_value(6).unwrap());
+        let result = safe_truncate(&to_value(html).unwrap(), &args).unwrap();
+        assert_eq!(result, to_value("<p>Caf\u{e9} &amp;\u{2026}</p>").unwrap());
+
+        args.insert("length".to_string(), to_value(19).unwrap());
+        args.insert("suffix".to_string(), to_value("...").unwrap());
+        args.insert("strip_html".to_string(), to_value(true).unwrap());
+        let result = safe_truncate(&to_value(html).unwrap(), &args).unwrap();
+        assert_eq!(result, to_value("Caf\u{e9} & cr\u{e8}me br\u{fb}l\u{e9}e...").unwrap());
+
+        // Nothing to remove with the default length
+        let result = safe_truncate(&to_value(html).unwrap(), &HashMap::new()).unwrap();
+        assert_eq!(result, to_value(html).unwrap());
+    }
 }
@@ -1,10 +1,69 @@
-use content::{Library, Taxonomy, TaxonomyTerm};
+use config::MissingContent;
+use content::{
+    parse_datetime, sort_pages, tie_break, Library, Page, SiteIndex, SortBy, Taxonomy, TaxonomyTerm,
+};
 use libs::tera::{from_value, to_value, Function as TeraFn, Result, Value};
+use libs::time::OffsetDateTime;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
+use utils::de::parse_yaml_datetime;
 use utils::slugs::{slugify_paths, SlugifyStrategy};
+use utils::templates::current_template;
+
+/// A call to `get_page`, `get_section` or `get_pages_by` for content that doesn't exist
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ContentMiss {
+    /// The template being rendered when the function was called, if known
+    pub template: Option<String>,
+    /// The call with its arguments, eg `get_page(path="blog/old-post.md")`
+    pub call: String,
+}
+
+/// Handles the content missing for the functions according to `missing_content` in the config,
+/// collecting the misses in `warn` mode to be reported at the end of the build
+#[derive(Debug, Clone)]
+pub struct MissingContentHandler {
+    level: MissingContent,
+    misses: Arc<Mutex<Vec<ContentMiss>>>,
+}
+impl MissingContentHandler {
+    pub fn new(level: MissingContent, misses: Arc<Mutex<Vec<ContentMiss>>>) -> Self {
+        Self { level, misses }
+    }
+
+    /// Errors with the message, or returns `fallback` if the missing content can be ignored
+    fn handle(
+        &self,
+        function: &str,
+        args: &HashMap<String, Value>,
+        message: String,
+        fallback: Value,
+    ) -> Result<Value> {
+        let template = current_template();
+        match self.level {
+            MissingContent::Error => match template {
+                Some(t) => Err(format!("{} (called from `{}`)", message, t).into()),
+                None => Err(message.into()),
+            },
+            MissingContent::Warn => {
+                let mut args: Vec<_> = args.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                args.sort();
+                let call = format!("{}({})", function, args.join(", "));
+                self.misses.lock().unwrap().push(ContentMiss { template, call });
+                Ok(fallback)
+            }
+            MissingContent::Ignore => Ok(fallback),
+        }
+    }
+}
+
+impl Default for MissingContentHandler {
+    fn default() -> Self {
+        Self::new(MissingContent::Error, Arc::new(Mutex::new(Vec::new())))
+    }
+}
 
 #[derive(Debug)]
 pub struct GetTaxonomyUrl {
@@ -112,6 +171,7 @@ pub struct GetPage {
     default_lang: String,
     supported_languages: Arc<Vec<String>>,
     library: Arc<RwLock<Library>>,
+    missing_content: MissingContentHandler,
 }
 impl GetPage {
     pub fn new(
@@ -119,12 +179,14 @@ impl GetPage {
         default_lang: &str,
         supported_languages: Arc<Vec<String>>,
         library: Arc<RwLock<Library>>,
+        missing_content: MissingContentHandler,
     ) -> Self {
         Self {
             base_path: base_path.join("content"),
             default_lang: default_lang.to_string(),
             supported_languages,
             library,
+            missing_content,
         }
     }
 }
@@ -146,13 +208,15 @@ impl TeraFn for GetPage {
 
                 match library.pages.get(&full_path) {
                     Some(p) => Ok(to_value(p.serialize(&library)).unwrap()),
-                    None => match lang {
-                        Some(lang_code) => {
-                            Err(format!("Page `{}` not found for language `{}`.", path, lang_code)
-                                .into())
-                        }
-                        None => Err(format!("Page `{}` not found.", path).into()),
-                    },
+                    None => {
+                        let message = match lang {
+                            Some(lang_code) => {
+                                format!("Page `{}` not found for language `{}`.", path, lang_code)
+                            }
+                            None => format!("Page `{}` not found.", path),
+                        };
+                        self.missing_content.handle("get_page", args, message, Value::Null)
+                    }
                 }
             },
         )
@@ -165,6 +229,7 @@ pub struct GetSection {
     default_lang: String,
     supported_languages: Arc<Vec<String>>,
     library: Arc<RwLock<Library>>,
+    missing_content: MissingContentHandler,
 }
 impl GetSection {
     pub fn new(
@@ -172,12 +237,14 @@ impl GetSection {
         default_lang: &str,
         supported_languages: Arc<Vec<String>>,
         library: Arc<RwLock<Library>>,
+        missing_content: MissingContentHandler,
     ) -> Self {
         Self {
             base_path: base_path.join("content"),
             default_lang: default_lang.to_string(),
             supported_languages,
             library,
+            missing_content,
         }
     }
 }
@@ -209,16 +276,662 @@ impl TeraFn for GetSection {
                             Ok(to_value(s.serialize(&library)).unwrap())
                         }
                     }
-                    None => match lang {
-                        Some(lang_code) => Err(format!(
-                            "Section `{}` not found for language `{}`.",
-                            path, lang_code
-                        )
-                        .into()),
-                        None => Err(format!("Section `{}` not found.", path).into()),
-                    },
+                    None => {
+                        let message = match lang {
+                            Some(lang_code) => format!(
+                                "Section `{}` not found for language `{}`.",
+                                path, lang_code
+                            ),
+                            None => format!("Section `{}` not found.", path),
+                        };
+                        self.missing_content.handle("get_section", args, message, Value::Null)
+                    }
+                }
+            })
+    }
+}
+
+/// The `site` index of the templates, also available in the shortcodes which are rendered
+/// before the library can be read from the templates
+#[derive(Debug)]
+pub struct GetSiteIndex {
+    site_index: Arc<RwLock<SiteIndex>>,
+}
+impl GetSiteIndex {
+    pub fn new(site_index: Arc<RwLock<SiteIndex>>) -> Self {
+        Self { site_index }
+    }
+}
+impl TeraFn for GetSiteIndex {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let full =
+            optional_arg!(bool, args.get("full"), "`get_site_index`: `full` must be a boolean")
+                .unwrap_or(false);
+        let site_index = self.site_index.read().unwrap();
+        if full {
+            Ok(site_index.tree())
+        } else {
+            Ok(to_value(&*site_index).unwrap())
+        }
+    }
+}
+
+/// The ancestor sections of a page or section followed by itself, for breadcrumbs. It uses the
+/// site index so it is available in the shortcodes as well
+#[derive(Debug)]
+pub struct GetAncestors {
+    default_lang: String,
+    supported_languages: Arc<Vec<String>>,
+    site_index: Arc<RwLock<SiteIndex>>,
+    missing_content: MissingContentHandler,
+}
+impl GetAncestors {
+    pub fn new(
+        default_lang: &str,
+        supported_languages: Arc<Vec<String>>,
+        site_index: Arc<RwLock<SiteIndex>>,
+        missing_content: MissingContentHandler,
+    ) -> Self {
+        Self {
+            default_lang: default_lang.to_string(),
+            supported_languages,
+            site_index,
+            missing_content,
+        }
+    }
+}
+impl TeraFn for GetAncestors {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`get_ancestors` requires a `path` argument with a string value"
+        );
+        let lang =
+            optional_arg!(String, args.get("lang"), "`get_ancestors`: `lang` must be a string");
+
+        let path_with_lang =
+            get_path_with_lang(&path, &lang, &self.default_lang, &self.supported_languages)?;
+        let site_index = self.site_index.read().unwrap();
+        match site_index.ancestors(&path_with_lang) {
+            Some(ancestors) => Ok(to_value(ancestors).unwrap()),
+            None => {
+                let message = match lang {
+                    Some(lang_code) => format!(
+                        "`get_ancestors`: `{}` not found for language `{}`.",
+                        path, lang_code
+                    ),
+                    None => format!("`get_ancestors`: `{}` not found.", path),
+                };
+                self.missing_content.handle("get_ancestors", args, message, Value::Array(vec![]))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GetPagesBy {
+    default_lang: String,
+    /// The names of the taxonomies of each language
+    taxonomies: HashMap<String, Vec<String>>,
+    slugify: SlugifyStrategy,
+    library: Arc<RwLock<Library>>,
+    missing_content: MissingContentHandler,
+}
+impl GetPagesBy {
+    pub fn new(
+        default_lang: &str,
+        taxonomies: HashMap<String, Vec<String>>,
+        slugify: SlugifyStrategy,
+        library: Arc<RwLock<Library>>,
+        missing_content: MissingContentHandler,
+    ) -> Self {
+        Self {
+            default_lang: default_lang.to_string(),
+            taxonomies,
+            slugify,
+            library,
+            missing_content,
+        }
+    }
+}
+impl TeraFn for GetPagesBy {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let taxonomy = optional_arg!(
+            String,
+            args.get("taxonomy"),
+            "`get_pages_by`: `taxonomy` must be a string"
+        );
+        let term =
+            optional_arg!(String, args.get("term"), "`get_pages_by`: `term` must be a string");
+        let section = optional_arg!(
+            String,
+            args.get("section"),
+            "`get_pages_by`: `section` must be a string"
+        );
+        let draft = optional_arg!(
+            bool,
+            args.get("draft"),
+            "`get_pages_by`: `draft` must be a boolean (true or false)"
+        );
+        let include_unlisted = optional_arg!(
+            bool,
+            args.get("include_unlisted"),
+            "`get_pages_by`: `include_unlisted` must be a boolean (true or false)"
+        )
+        .unwrap_or(false);
+        let after =
+            optional_arg!(String, args.get("after"), "`get_pages_by`: `after` must be a string");
+        let before =
+            optional_arg!(String, args.get("before"), "`get_pages_by`: `before` must be a string");
+        let sort_by = optional_arg!(
+            SortBy,
+            args.get("sort_by"),
+            "`get_pages_by`: `sort_by` must be one of `date`, `update_date`, `title`, `title_bytes`, `weight`, `slug` or `none`"
+        )
+        .unwrap_or(SortBy::Date);
+        let limit = optional_arg!(
+            usize,
+            args.get("limit"),
+            "`get_pages_by`: `limit` must be a positive integer"
+        );
+        let lang =
+            optional_arg!(String, args.get("lang"), "`get_pages_by`: `lang` must be a string")
+                .unwrap_or_else(|| self.default_lang.clone());
+
+        let taxonomy_term = match (taxonomy, term) {
+            (Some(taxonomy), Some(term)) => {
+                let exists = self
+                    .taxonomies
+                    .get(&lang)
+                    .map(|names| names.contains(&taxonomy))
+                    .unwrap_or(false);
+                if !exists {
+                    return Err(format!(
+                        "`get_pages_by`: there is no taxonomy `{}` for the language `{}`",
+                        taxonomy, lang
+                    )
+                    .into());
+                }
+                Some((taxonomy, slugify_paths(&term, self.slugify)))
+            }
+            (None, None) => None,
+            _ => {
+                return Err("`get_pages_by`: `taxonomy` and `term` need to be given together".into())
+            }
+        };
+        let parse_date = |arg: &str, date: Option<String>| match date {
+            Some(d) => match parse_datetime(&d) {
+                Some(dt) => Ok(Some(dt)),
+                None => Err(format!("`get_pages_by`: `{}` is not a valid date: {}", arg, d)),
+            },
+            None => Ok(None),
+        };
+        let after = parse_date("after", after)?;
+        let before = parse_date("before", before)?;
+
+        let library = self.library.read().unwrap();
+        let section_components: Option<Vec<String>> = match section {
+            Some(section) => {
+                let components: Vec<String> = section
+                    .trim_end_matches(".md")
+                    .trim_end_matches("_index")
+                    .split('/')
+                    .filter(|c| !c.is_empty())
+                    .map(|c| c.to_string())
+                    .collect();
+                let exists = library.sections.values().any(|s| s.file.components == components);
+                if !exists {
+                    return self.missing_content.handle(
+                        "get_pages_by",
+                        args,
+                        format!("`get_pages_by`: section `{}` not found", section),
+                        Value::Array(Vec::new()),
+                    );
+                }
+                Some(components)
+            }
+            None => None,
+        };
+
+        let is_wanted = |page: &Page| {
+            if page.lang != lang {
+                return false;
+            }
+            if let Some(ref components) = section_components {
+                if !page.file.components.starts_with(components) {
+                    return false;
+                }
+            }
+            if let Some((ref taxonomy, ref term)) = taxonomy_term {
+                let has_term = page
+                    .meta
+                    .taxonomies
+                    .get(taxonomy)
+                    .map(|terms| terms.iter().any(|t| &slugify_paths(t, self.slugify) == term))
+                    .unwrap_or(false);
+                if !has_term {
+                    return false;
+                }
+            }
+            if draft.is_some_and(|d| page.meta.draft != d) {
+                return false;
+            }
+            if page.meta.unlisted && !include_unlisted {
+                return false;
+            }
+            if after.is_some() || before.is_some() {
+                let datetime = match page.meta.datetime {
+                    Some(dt) => dt,
+                    None => return false,
+                };
+                if after.is_some_and(|a| datetime <= a) || before.is_some_and(|b| datetime >= b) {
+                    return false;
+                }
+            }
+            true
+        };
+        let pages: Vec<&Page> = library.pages.values().filter(|p| is_wanted(p)).collect();
+
+        // Pages without the field to sort them by come last, ordered by permalink like all of
+        // them with `none` so the order is stable
+        let (mut paths, mut rest) = if sort_by == SortBy::None {
+            (Vec::new(), pages.iter().map(|p| p.file.path.clone()).collect())
+        } else {
+            sort_pages(&pages, sort_by)
+        };
+        rest.sort_by(|a, b| library.pages[a].permalink.cmp(&library.pages[b].permalink));
+        paths.extend(rest);
+
+        let pages: Vec<_> = paths
+            .iter()
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|path| library.pages[path].serialize(&library))
+            .collect();
+        Ok(to_value(pages).unwrap())
+    }
+}
+
+/// A front matter field read by `group_pages_by_date` that isn't a date
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InvalidDate {
+    pub path: PathBuf,
+    pub field: String,
+    pub value: String,
+}
+
+/// How precisely `group_pages_by_date` groups the pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateGranularity {
+    Year,
+    Month,
+    Day,
+}
+
+/// The value of a front matter field of the page, like `date` or `extra.event.start`
+fn page_field(page: &Page, field: &str) -> Option<Value> {
+    let (first, rest) = match field.split_once('.') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (field, None),
+    };
+    let mut value = match (first, rest) {
+        ("date", None) => return page.meta.date.clone().map(Value::String),
+        ("updated", None) => return page.meta.updated.clone().map(Value::String),
+        ("extra", Some(_)) => Value::Object(page.meta.extra.clone()),
+        _ => return None,
+    };
+    for key in rest.unwrap_or_default().split('.') {
+        value = value.get(key)?.clone();
+    }
+    Some(value)
+}
+
+/// Parses a date of the front matter: the same formats as the `date` of the pages, as well as
+/// the other timestamps YAML front matter can have
+fn parse_field_datetime(value: &Value) -> Option<OffsetDateTime> {
+    match value {
+        Value::String(s) => parse_datetime(s).or_else(|| parse_yaml_datetime(s).ok()),
+        Value::Number(n) => OffsetDateTime::from_unix_timestamp(n.as_i64()?).ok(),
+        _ => None,
+    }
+}
+
+/// The pages of each year, month or day, with the date they are grouped by
+type DateGroups<'a> = BTreeMap<(i32, u8, u8), Vec<(OffsetDateTime, &'a Page)>>;
+
+#[derive(Debug)]
+pub struct GroupPagesByDate {
+    default_lang: String,
+    library: Arc<RwLock<Library>>,
+    missing_content: MissingContentHandler,
+    invalid_dates: Arc<Mutex<BTreeSet<InvalidDate>>>,
+    /// What `filter_future` and `filter_past` compare with: when the build started
+    now: OffsetDateTime,
+}
+impl GroupPagesByDate {
+    pub fn new(
+        default_lang: &str,
+        library: Arc<RwLock<Library>>,
+        missing_content: MissingContentHandler,
+        invalid_dates: Arc<Mutex<BTreeSet<InvalidDate>>>,
+        now: OffsetDateTime,
+    ) -> Self {
+        Self {
+            default_lang: default_lang.to_string(),
+            library,
+            missing_content,
+            invalid_dates,
+            now,
+        }
+    }
+}
+impl TeraFn for GroupPagesByDate {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let field = required_arg!(
+            String,
+            args.get("field"),
+            "`group_pages_by_date` requires a `field` argument with a string value"
+        );
+        let section = optional_arg!(
+            String,
+            args.get("section"),
+            "`group_pages_by_date`: `section` must be a string"
+        );
+        let granularity = match optional_arg!(
+            String,
+            args.get("granularity"),
+            "`group_pages_by_date`: `granularity` must be a string"
+        )
+        .as_deref()
+        {
+            Some("year") => DateGranularity::Year,
+            Some("month") | None => DateGranularity::Month,
+            Some("day") => DateGranularity::Day,
+            Some(g) => {
+                return Err(format!(
+                    "`group_pages_by_date`: `granularity` must be one of `year`, `month` or `day`, got `{}`",
+                    g
+                )
+                .into())
+            }
+        };
+        let filter_future = optional_arg!(
+            bool,
+            args.get("filter_future"),
+            "`group_pages_by_date`: `filter_future` must be a boolean (true or false)"
+        )
+        .unwrap_or(false);
+        let filter_past = optional_arg!(
+            bool,
+            args.get("filter_past"),
+            "`group_pages_by_date`: `filter_past` must be a boolean (true or false)"
+        )
+        .unwrap_or(false);
+        let lang = optional_arg!(
+            String,
+            args.get("lang"),
+            "`group_pages_by_date`: `lang` must be a string"
+        )
+        .unwrap_or_else(|| self.default_lang.clone());
+
+        let library = self.library.read().unwrap();
+        let section_components: Option<Vec<String>> = match section {
+            Some(section) => {
+                let components: Vec<String> = section
+                    .trim_end_matches(".md")
+                    .trim_end_matches("_index")
+                    .split('/')
+                    .filter(|c| !c.is_empty())
+                    .map(|c| c.to_string())
+                    .collect();
+                if !library.sections.values().any(|s| s.file.components == components) {
+                    return self.missing_content.handle(
+                        "group_pages_by_date",
+                        args,
+                        format!("`group_pages_by_date`: section `{}` not found", section),
+                        to_value(HashMap::from([
+                            ("groups", Vec::<Value>::new()),
+                            ("invalid", Vec::new()),
+                        ]))
+                        .unwrap(),
+                    );
+                }
+                Some(components)
+            }
+            None => None,
+        };
+
+        let now = self.now;
+        let mut groups: DateGroups = BTreeMap::new();
+        let mut invalid = Vec::new();
+        let pages = library.pages.values().filter(|p| {
+            p.lang == lang
+                && !p.meta.unlisted
+                && match &section_components {
+                    Some(c) => p.file.components.starts_with(c),
+                    None => true,
+                }
+        });
+        for page in pages {
+            let Some(value) = page_field(page, &field) else {
+                continue;
+            };
+            let Some(datetime) = parse_field_datetime(&value) else {
+                let value = match value {
+                    Value::String(s) => s,
+                    v => v.to_string(),
+                };
+                invalid.push(InvalidDate {
+                    path: page.file.path.clone(),
+                    field: field.clone(),
+                    value,
+                });
+                continue;
+            };
+            if (filter_future && datetime <= now) || (filter_past && datetime > now) {
+                continue;
+            }
+            let key = match granularity {
+                DateGranularity::Year => (datetime.year(), 0, 0),
+                DateGranularity::Month => (datetime.year(), u8::from(datetime.month()), 0),
+                DateGranularity::Day => {
+                    (datetime.year(), u8::from(datetime.month()), datetime.day())
+                }
+            };
+            groups.entry(key).or_default().push((datetime, page));
+        }
+
+        let groups: Vec<_> = groups
+            .into_iter()
+            .map(|((year, month, day), mut pages)| {
+                pages.sort_by(|(a, page_a), (b, page_b)| {
+                    a.cmp(b).then_with(|| tie_break(page_a, page_b))
+                });
+                let key = match granularity {
+                    DateGranularity::Year => year.to_string(),
+                    DateGranularity::Month => format!("{}-{:02}", year, month),
+                    DateGranularity::Day => format!("{}-{:02}-{:02}", year, month, day),
+                };
+                let mut group = libs::tera::Map::new();
+                group.insert("key".to_string(), to_value(key).unwrap());
+                group.insert("year".to_string(), to_value(year).unwrap());
+                if granularity != DateGranularity::Year {
+                    group.insert("month".to_string(), to_value(month).unwrap());
+                }
+                if granularity == DateGranularity::Day {
+                    group.insert("day".to_string(), to_value(day).unwrap());
+                }
+                let pages: Vec<_> = pages.iter().map(|(_, p)| p.serialize(&library)).collect();
+                group.insert("pages".to_string(), to_value(pages).unwrap());
+                Value::Object(group)
+            })
+            .collect();
+        invalid.sort();
+        let invalid_values: Vec<_> = invalid
+            .iter()
+            .map(|i| {
+                HashMap::from([
+                    ("path", i.path.to_string_lossy().into_owned()),
+                    ("value", i.value.clone()),
+                ])
+            })
+            .collect();
+        self.invalid_dates.lock().unwrap().extend(invalid);
+
+        let mut result = libs::tera::Map::new();
+        result.insert("groups".to_string(), Value::Array(groups));
+        result.insert("invalid".to_string(), to_value(invalid_values).unwrap());
+        Ok(Value::Object(result))
+    }
+}
+
+/// Mixes the bits of the seed (splitmix64) so close seeds pick unrelated pages
+fn mix_seed(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[derive(Debug)]
+pub struct GetRandomPage {
+    default_lang: String,
+    /// The names of the taxonomies of each language
+    taxonomies: HashMap<String, Vec<String>>,
+    slugify: SlugifyStrategy,
+    library: Arc<RwLock<Library>>,
+    missing_content: MissingContentHandler,
+    /// The seed of the build, so every call with the same arguments picks the same page
+    seed: u64,
+}
+impl GetRandomPage {
+    pub fn new(
+        default_lang: &str,
+        taxonomies: HashMap<String, Vec<String>>,
+        slugify: SlugifyStrategy,
+        library: Arc<RwLock<Library>>,
+        missing_content: MissingContentHandler,
+        seed: u64,
+    ) -> Self {
+        Self {
+            default_lang: default_lang.to_string(),
+            taxonomies,
+            slugify,
+            library,
+            missing_content,
+            seed,
+        }
+    }
+}
+impl TeraFn for GetRandomPage {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let section = optional_arg!(
+            String,
+            args.get("section"),
+            "`get_random_page`: `section` must be a string"
+        );
+        let taxonomy = optional_arg!(
+            String,
+            args.get("taxonomy"),
+            "`get_random_page`: `taxonomy` must be a string"
+        );
+        let term =
+            optional_arg!(String, args.get("term"), "`get_random_page`: `term` must be a string");
+        let seed = optional_arg!(
+            u64,
+            args.get("seed"),
+            "`get_random_page`: `seed` must be a positive integer"
+        )
+        .unwrap_or(self.seed);
+        let lang =
+            optional_arg!(String, args.get("lang"), "`get_random_page`: `lang` must be a string")
+                .unwrap_or_else(|| self.default_lang.clone());
+
+        let taxonomy_term = match (taxonomy, term) {
+            (Some(taxonomy), Some(term)) => {
+                let exists = self
+                    .taxonomies
+                    .get(&lang)
+                    .map(|names| names.contains(&taxonomy))
+                    .unwrap_or(false);
+                if !exists {
+                    return Err(format!(
+                        "`get_random_page`: there is no taxonomy `{}` for the language `{}`",
+                        taxonomy, lang
+                    )
+                    .into());
                 }
+                let slug = slugify_paths(&term, self.slugify);
+                Some((taxonomy, term, slug))
+            }
+            (None, None) => None,
+            _ => {
+                return Err(
+                    "`get_random_page`: `taxonomy` and `term` need to be given together".into()
+                )
+            }
+        };
+
+        let library = self.library.read().unwrap();
+        let section_components: Option<Vec<String>> = match section {
+            Some(ref section) => {
+                let components: Vec<String> = section
+                    .trim_end_matches(".md")
+                    .trim_end_matches("_index")
+                    .split('/')
+                    .filter(|c| !c.is_empty())
+                    .map(|c| c.to_string())
+                    .collect();
+                if !library.sections.values().any(|s| s.file.components == components) {
+                    return self.missing_content.handle(
+                        "get_random_page",
+                        args,
+                        format!("`get_random_page`: section `{}` not found", section),
+                        Value::Null,
+                    );
+                }
+                Some(components)
+            }
+            None => None,
+        };
+
+        let mut pages: Vec<&Page> = library
+            .pages
+            .values()
+            .filter(|p| p.lang == lang && p.meta.render && !p.meta.draft && !p.meta.unlisted)
+            .filter(|p| match &section_components {
+                Some(c) => p.file.components.starts_with(c),
+                None => true,
+            })
+            .filter(|p| match &taxonomy_term {
+                Some((taxonomy, _, slug)) => p
+                    .meta
+                    .taxonomies
+                    .get(taxonomy)
+                    .map(|terms| terms.iter().any(|t| &slugify_paths(t, self.slugify) == slug))
+                    .unwrap_or(false),
+                None => true,
             })
+            .collect();
+        if pages.is_empty() {
+            let mut message = "`get_random_page`: there is no page to pick".to_string();
+            if let Some(section) = section {
+                message.push_str(&format!(" in the section `{}`", section));
+            }
+            if let Some((taxonomy, term, _)) = taxonomy_term {
+                message.push_str(&format!(" with the `{}` term `{}`", taxonomy, term));
+            }
+            message.push_str(&format!(
+                " for the language `{}`. Drafts and unlisted pages are never picked",
+                lang
+            ));
+            return Err(message.into());
+        }
+
+        // The library isn't ordered, the permalinks make the pick only depend on the seed
+        pages.sort_by(|a, b| a.permalink.cmp(&b.permalink));
+        let page = pages[(mix_seed(seed) % pages.len() as u64) as usize];
+        Ok(to_value(page.serialize(&library)).unwrap())
     }
 }
 
@@ -393,8 +1106,13 @@ mod tests {
         let base_path = "/test/base/path".into();
         let lang_list = vec!["en".to_string(), "fr".to_string()];
 
-        let static_fn =
-            GetPage::new(base_path, "en", Arc::new(lang_list), Arc::new(RwLock::new(library)));
+        let static_fn = GetPage::new(
+            base_path,
+            "en",
+            Arc::new(lang_list),
+            Arc::new(RwLock::new(library)),
+            MissingContentHandler::default(),
+        );
 
         // Find with lang argument
         let mut args = HashMap::new();
@@ -442,6 +1160,342 @@ mod tests {
         section
     }
 
+    #[test]
+    fn can_handle_missing_content() {
+        let library = Arc::new(RwLock::new(Library::default()));
+        let lang_list = Arc::new(vec!["en".to_string()]);
+        let misses = Arc::new(Mutex::new(Vec::new()));
+        let handler = |level| MissingContentHandler::new(level, misses.clone());
+        let get_page = |level| {
+            GetPage::new("/test".into(), "en", lang_list.clone(), library.clone(), handler(level))
+        };
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("blog/old-post.md").unwrap());
+
+        assert!(get_page(MissingContent::Error).call(&args).is_err());
+        assert_eq!(get_page(MissingContent::Ignore).call(&args).unwrap(), Value::Null);
+        assert!(misses.lock().unwrap().is_empty());
+        assert_eq!(get_page(MissingContent::Warn).call(&args).unwrap(), Value::Null);
+        let get_pages_by = GetPagesBy::new(
+            "en",
+            HashMap::new(),
+            SlugifyStrategy::On,
+            library.clone(),
+            handler(MissingContent::Warn),
+        );
+        let mut args = HashMap::new();
+        args.insert("section".to_string(), to_value("blog").unwrap());
+        assert_eq!(get_pages_by.call(&args).unwrap(), to_value(Vec::<Value>::new()).unwrap());
+        assert_eq!(
+            *misses.lock().unwrap(),
+            vec![
+                ContentMiss { template: None, call: r#"get_page(path="blog/old-post.md")"#.into() },
+                ContentMiss { template: None, call: r#"get_pages_by(section="blog")"#.into() },
+            ]
+        );
+
+        // The template calling the function is known when rendering it
+        let mut tera = libs::tera::Tera::default();
+        tera.add_raw_template("page.html", r#"{{ get_page(path="blog/old-post.md") }}"#).unwrap();
+        tera.register_function("get_page", get_page(MissingContent::Error));
+        let err = utils::templates::render_template("page.html", &tera, Default::default(), &None)
+            .unwrap_err();
+        assert!(format!("{:?}", err).contains("(called from `page.html`)"));
+        tera.register_function("get_page", get_page(MissingContent::Warn));
+        utils::templates::render_template("page.html", &tera, Default::default(), &None).unwrap();
+        assert_eq!(misses.lock().unwrap()[2].template.as_deref(), Some("page.html"));
+    }
+
+    #[test]
+    fn can_get_ancestors() {
+        let mut library = Library::default();
+        for (t, f) in [
+            ("Home", "content/_index.md"),
+            ("Docs", "content/docs/_index.md"),
+            ("Guides", "content/docs/guides/_index.md"),
+        ] {
+            let mut section = create_section(t, f, "en");
+            section.permalink =
+                format!("https://example.com/{}", section.file.components.join("/")).into();
+            library.insert_section(section);
+        }
+        library.insert_page(create_page("Install", "content/docs/guides/install.md", "en"));
+        library
+            .populate_sections(&Config::default_for_test(), Path::new("/test/base/path/content"));
+        let site_index = Arc::new(RwLock::new(SiteIndex::new(&library, "en")));
+        let misses = Arc::new(Mutex::new(Vec::new()));
+        let get_ancestors = |level| {
+            GetAncestors::new(
+                "en",
+                Arc::new(vec!["en".to_string()]),
+                site_index.clone(),
+                MissingContentHandler::new(level, misses.clone()),
+            )
+        };
+        let titles = |path: &str| -> Vec<Value> {
+            let args = HashMap::from([("path".to_string(), to_value(path).unwrap())]);
+            let res = get_ancestors(MissingContent::Error).call(&args).unwrap();
+            res.as_array().unwrap().iter().map(|a| a["title"].clone()).collect()
+        };
+
+        assert_eq!(titles("docs/guides/install.md"), vec!["Home", "Docs", "Guides", "Install"]);
+        assert_eq!(titles("docs/guides/_index.md"), vec!["Home", "Docs", "Guides"]);
+        // The root section is its only ancestor
+        assert_eq!(titles("_index.md"), vec!["Home"]);
+
+        let args = HashMap::from([("path".to_string(), to_value("docs/_index.md").unwrap())]);
+        let res = get_ancestors(MissingContent::Error).call(&args).unwrap();
+        assert_eq!(res[0]["permalink"], "https://example.com/");
+        assert_eq!(res[1]["permalink"], "https://example.com/docs");
+
+        let args = HashMap::from([("path".to_string(), to_value("docs/missing.md").unwrap())]);
+        assert!(get_ancestors(MissingContent::Error).call(&args).is_err());
+        assert_eq!(
+            get_ancestors(MissingContent::Warn).call(&args).unwrap(),
+            to_value(Vec::<Value>::new()).unwrap()
+        );
+        assert_eq!(misses.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn can_get_pages_by() {
+        let mut library = Library::default();
+        for (t, f) in [
+            ("Blog", "content/blog/_index.md"),
+            ("2024", "content/blog/2024/_index.md"),
+            ("Notes", "content/notes/_index.md"),
+        ] {
+            library.insert_section(create_section(t, f, "en"));
+        }
+        let pages = vec![
+            ("A", "content/blog/a.md", Some("2024-01-10"), "Rust", false),
+            ("B", "content/blog/2024/b.md", Some("2024-03-01"), "rust", false),
+            ("C", "content/blog/c.md", Some("2023-06-01"), "python", true),
+            ("D", "content/notes/d.md", Some("2024-02-01"), "rust", false),
+            ("E", "content/blog/e.md", None, "rust", false),
+            ("F", "content/blog/f.md", Some("2024-04-01"), "rust", false),
+        ];
+        for (t, f, date, tag, draft) in pages {
+            let mut page = create_page(t, f, "en");
+            page.meta.date = date.map(|d| d.to_string());
+            page.meta.date_to_datetime();
+            page.meta.taxonomies.insert("tags".to_string(), vec![tag.to_string()]);
+            page.meta.draft = draft;
+            page.meta.unlisted = t == "F";
+            page.permalink = format!("https://example.com/{}", f).into();
+            library.pages.insert(page.file.path.clone(), page);
+        }
+        let taxonomies = HashMap::from([("en".to_string(), vec!["tags".to_string()])]);
+        let static_fn = GetPagesBy::new(
+            "en",
+            taxonomies,
+            SlugifyStrategy::On,
+            Arc::new(RwLock::new(library)),
+            MissingContentHandler::default(),
+        );
+
+        let titles = |args: Vec<(&str, Value)>| -> Vec<String> {
+            let args = args.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+            let res = static_fn.call(&args).unwrap();
+            res.as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["title"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // Subsections are included and pages without a date come last
+        let rust_in_blog = vec![
+            ("section", to_value("blog").unwrap()),
+            ("taxonomy", to_value("tags").unwrap()),
+            ("term", to_value("rust").unwrap()),
+        ];
+        assert_eq!(titles(rust_in_blog.clone()), vec!["B", "A", "E"]);
+        let mut limited = rust_in_blog.clone();
+        limited.push(("limit", to_value(1).unwrap()));
+        assert_eq!(titles(limited), vec!["B"]);
+        let mut with_unlisted = rust_in_blog.clone();
+        with_unlisted.push(("include_unlisted", to_value(true).unwrap()));
+        assert_eq!(titles(with_unlisted), vec!["F", "B", "A", "E"]);
+        assert_eq!(
+            titles(vec![
+                ("section", to_value("blog").unwrap()),
+                ("sort_by", to_value("title").unwrap())
+            ]),
+            vec!["A", "B", "C", "E"]
+        );
+        assert_eq!(
+            titles(vec![
+                ("section", to_value("blog/").unwrap()),
+                ("draft", to_value(false).unwrap()),
+                ("after", to_value("2024-01-01").unwrap()),
+            ]),
+            vec!["B", "A"]
+        );
+        assert_eq!(titles(vec![("before", to_value("2024-02-15").unwrap())]), vec!["D", "A", "C"]);
+
+        let call = |args: Vec<(&str, Value)>| {
+            static_fn.call(&args.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+        };
+        assert!(call(vec![
+            ("taxonomy", to_value("categories").unwrap()),
+            ("term", to_value("a").unwrap())
+        ])
+        .is_err());
+        assert!(call(vec![("taxonomy", to_value("tags").unwrap())]).is_err());
+        assert!(call(vec![("section", to_value("projects").unwrap())]).is_err());
+        assert!(call(vec![("sort_by", to_value("colour").unwrap())]).is_err());
+        assert!(call(vec![("after", to_value("yesterday").unwrap())]).is_err());
+    }
+
+    #[test]
+    fn can_get_random_page() {
+        let mut library = Library::default();
+        library.insert_section(create_section("Blog", "content/blog/_index.md", "en"));
+        library.insert_section(create_section("Notes", "content/notes/_index.md", "en"));
+        for (t, f, tag) in [
+            ("A", "content/blog/a.md", "rust"),
+            ("B", "content/blog/b.md", "rust"),
+            ("C", "content/blog/c.md", "python"),
+            ("D", "content/blog/d.md", "rust"),
+            ("E", "content/blog/e.md", "rust"),
+            ("F", "content/notes/f.md", "rust"),
+        ] {
+            let mut page = create_page(t, f, "en");
+            page.meta.taxonomies.insert("tags".to_string(), vec![tag.to_string()]);
+            page.meta.draft = t == "D";
+            page.meta.unlisted = t == "E";
+            page.permalink = format!("https://example.com/{}", f).into();
+            library.pages.insert(page.file.path.clone(), page);
+        }
+        let library = Arc::new(RwLock::new(library));
+        let taxonomies = HashMap::from([("en".to_string(), vec!["tags".to_string()])]);
+        let random_page = |seed: u64| {
+            GetRandomPage::new(
+                "en",
+                taxonomies.clone(),
+                SlugifyStrategy::On,
+                library.clone(),
+                MissingContentHandler::default(),
+                seed,
+            )
+        };
+        let call = |static_fn: &GetRandomPage, args: Vec<(&str, Value)>| {
+            let args = args.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+            static_fn.call(&args).map(|p| p["title"].as_str().unwrap().to_string())
+        };
+        let rust_in_blog = || {
+            vec![
+                ("section", to_value("blog").unwrap()),
+                ("taxonomy", to_value("tags").unwrap()),
+                ("term", to_value("Rust").unwrap()),
+            ]
+        };
+
+        // The same seed always picks the same page and drafts and unlisted pages are never picked
+        let mut picked = BTreeSet::new();
+        for seed in 0..50 {
+            let static_fn = random_page(seed);
+            let title = call(&static_fn, rust_in_blog()).unwrap();
+            assert_eq!(call(&static_fn, rust_in_blog()).unwrap(), title);
+            picked.insert(title);
+        }
+        assert_eq!(picked, BTreeSet::from(["A".to_string(), "B".to_string()]));
+
+        // The `seed` argument takes precedence over the one of the build
+        let mut args = rust_in_blog();
+        args.push(("seed", to_value(7).unwrap()));
+        assert_eq!(
+            call(&random_page(1), args.clone()).unwrap(),
+            call(&random_page(2), args).unwrap()
+        );
+
+        let err = call(
+            &random_page(0),
+            vec![
+                ("section", to_value("notes").unwrap()),
+                ("taxonomy", to_value("tags").unwrap()),
+                ("term", to_value("python").unwrap()),
+            ],
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`get_random_page`: there is no page to pick in the section `notes` with the `tags` term `python` for the language `en`. Drafts and unlisted pages are never picked"
+        );
+        assert!(call(&random_page(0), vec![("section", to_value("wiki").unwrap())]).is_err());
+    }
+
+    #[test]
+    fn can_group_pages_by_date() {
+        let mut library = Library::default();
+        library.insert_section(create_section("Events", "content/events/_index.md", "en"));
+        let pages = vec![
+            ("A", "content/events/a.md", Some("2024-05-20")),
+            ("B", "content/events/b.md", Some("2024-05-03")),
+            ("C", "content/events/c.md", Some("2024-06-01T10:00:00Z")),
+            ("D", "content/events/d.md", Some("3000-01-01")),
+            ("E", "content/events/e.md", Some("soon")),
+            ("F", "content/events/f.md", None),
+        ];
+        for (t, f, date) in pages {
+            let mut page = create_page(t, f, "en");
+            if let Some(date) = date {
+                page.meta.extra.insert("event_date".to_string(), to_value(date).unwrap());
+            }
+            library.pages.insert(page.file.path.clone(), page);
+        }
+        let invalid_dates = Arc::new(Mutex::new(BTreeSet::new()));
+        let static_fn = GroupPagesByDate::new(
+            "en",
+            Arc::new(RwLock::new(library)),
+            MissingContentHandler::default(),
+            invalid_dates.clone(),
+            OffsetDateTime::now_utc(),
+        );
+        let call = |args: Vec<(&str, Value)>| {
+            let mut args: HashMap<_, _> =
+                args.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+            args.insert("section".to_string(), to_value("events").unwrap());
+            args.insert("field".to_string(), to_value("extra.event_date").unwrap());
+            static_fn.call(&args)
+        };
+        let groups = |res: &Value| -> Vec<(String, Vec<String>)> {
+            res["groups"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|g| {
+                    let titles = g["pages"].as_array().unwrap().iter();
+                    let titles = titles.map(|p| p["title"].as_str().unwrap().to_string());
+                    (g["key"].as_str().unwrap().to_string(), titles.collect())
+                })
+                .collect()
+        };
+
+        let res = call(vec![]).unwrap();
+        assert_eq!(
+            groups(&res),
+            vec![
+                ("2024-05".to_string(), vec!["B".to_string(), "A".to_string()]),
+                ("2024-06".to_string(), vec!["C".to_string()]),
+                ("3000-01".to_string(), vec!["D".to_string()]),
+            ]
+        );
+        assert_eq!(res["invalid"][0]["value"], to_value("soon").unwrap());
+        assert!(res["invalid"][0]["path"].as_str().unwrap().ends_with("content/events/e.md"));
+        assert_eq!(invalid_dates.lock().unwrap().len(), 1);
+
+        let res = call(vec![("granularity", to_value("year").unwrap())]).unwrap();
+        assert_eq!(groups(&res)[0].0, "2024");
+        assert_eq!(res["groups"][0]["year"], to_value(2024).unwrap());
+        let res = call(vec![("filter_future", to_value(true).unwrap())]).unwrap();
+        assert_eq!(groups(&res), vec![("3000-01".to_string(), vec!["D".to_string()])]);
+        let res = call(vec![("filter_past", to_value(true).unwrap())]).unwrap();
+        assert_eq!(groups(&res).len(), 2);
+        assert!(call(vec![("granularity", to_value("week").unwrap())]).is_err());
+    }
+
     #[test]
     fn can_get_section() {
         let mut library = Library::default();
@@ -464,8 +1518,13 @@ mod tests {
         let base_path = "/test/base/path".into();
         let lang_list = vec!["en".to_string(), "fr".to_string()];
 
-        let static_fn =
-            GetSection::new(base_path, "en", Arc::new(lang_list), Arc::new(RwLock::new(library)));
+        let static_fn = GetSection::new(
+            base_path,
+            "en",
+            Arc::new(lang_list),
+            Arc::new(RwLock::new(library)),
+            MissingContentHandler::default(),
+        );
 
         // Find with lang argument
         let mut args = HashMap::new();
@@ -0,0 +1,284 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+
+use config::Config;
+use content::strip_front_matter;
+use libs::tera::{from_value, to_value, Function as TeraFn, Result, Tera, Value};
+use markdown::{render_content, RenderContext};
+use utils::fs::{is_path_in_directory, read_content_file};
+use utils::templates::get_shortcodes;
+use utils::types::InsertAnchor;
+
+thread_local! {
+    /// The files being rendered on this thread, outermost first, to catch a file including itself
+    /// through the shortcodes of its content
+    static RENDERING: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The Tera instance used for the shortcodes of the files
+#[derive(Debug, Clone)]
+enum ShortcodesTera {
+    /// Held by the function registered in the site templates
+    Owned(Arc<Tera>),
+    /// Held by the copy of the function in that Tera instance, which would otherwise own itself
+    Shared(Weak<Tera>),
+}
+
+/// `render_content_file`: renders the markdown of a file of the `content` directory, without its
+/// front matter, to include it in a template
+#[derive(Debug, Clone)]
+pub struct RenderContentFile {
+    content_path: PathBuf,
+    config: Arc<Config>,
+    data: Arc<Value>,
+    permalinks: Arc<HashMap<String, Arc<str>>>,
+    /// The HTML of the files already rendered in this build, by path and language
+    cache: Arc<Mutex<HashMap<(String, String), String>>>,
+    tera: ShortcodesTera,
+}
+
+impl RenderContentFile {
+    /// `tera` is the site Tera instance, copied for the shortcodes along with this function so
+    /// they can call it too
+    pub fn new(
+        content_path: PathBuf,
+        config: Config,
+        data: Arc<Value>,
+        permalinks: Arc<HashMap<String, Arc<str>>>,
+        tera: &Tera,
+    ) -> Self {
+        let config = Arc::new(config);
+        let cache = Arc::new(Mutex::new(HashMap::new()));
+        let tera = Arc::new_cyclic(|weak| {
+            let mut tera = tera.clone();
+            tera.register_function(
+                "render_content_file",
+                RenderContentFile {
+                    content_path: content_path.clone(),
+                    config: config.clone(),
+                    data: data.clone(),
+                    permalinks: permalinks.clone(),
+                    cache: cache.clone(),
+                    tera: ShortcodesTera::Shared(weak.clone()),
+                },
+            );
+            tera
+        });
+        Self { content_path, config, data, permalinks, cache, tera: ShortcodesTera::Owned(tera) }
+    }
+
+    fn render(&self, path: &str, lang: &str) -> Result<String> {
+        let tera = match &self.tera {
+            ShortcodesTera::Owned(tera) => tera.clone(),
+            ShortcodesTera::Shared(tera) => {
+                tera.upgrade().ok_or("`render_content_file`: the templates of the site are gone")?
+            }
+        };
+        let file_path = self.content_path.join(path);
+        let content = read_content_file(&file_path)
+            .map_err(|e| format!("`render_content_file`: failed to read `{}`: {}", path, e))?;
+
+        let mut context = RenderContext::new(
+            &tera,
+            &self.config,
+            &self.data,
+            lang,
+            "",
+            &self.permalinks,
+            InsertAnchor::None,
+        );
+        let shortcode_definitions = get_shortcodes(&tera);
+        context.set_shortcode_definitions(&shortcode_definitions);
+        context.set_current_page_path(path);
+        render_content(strip_front_matter(&content), &context)
+            .map(|rendered| rendered.body)
+            .map_err(|e| {
+                format!("`render_content_file`: failed to render `{}`: {:#}", path, e).into()
+            })
+    }
+}
+
+/// Whether the path stays in the directory it is relative to, lexically
+fn is_relative_and_contained(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+impl TeraFn for RenderContentFile {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`render_content_file` requires a `path` argument with a string value"
+        );
+        let lang = optional_arg!(
+            String,
+            args.get("lang"),
+            "`render_content_file`: `lang` must be a string."
+        )
+        .unwrap_or_else(|| self.config.default_language.clone());
+        if !self.config.languages.contains_key(&lang) {
+            return Err(format!(
+                "`render_content_file`: `{}` is not an authorized language (check config.languages).",
+                lang
+            )
+            .into());
+        }
+
+        let path = path.trim_start_matches("@/").to_string();
+        let is_inside = is_relative_and_contained(Path::new(&path))
+            && is_path_in_directory(&self.content_path, &self.content_path.join(&path))
+                .map(|location| location.is_inside())
+                .unwrap_or(false);
+        if !is_inside {
+            return Err(format!(
+                "`render_content_file`: `{}` is not a file of the `content` directory",
+                path
+            )
+            .into());
+        }
+
+        let key = (path, lang);
+        if let Some(html) = self.cache.lock().unwrap().get(&key) {
+            return Ok(to_value(html).unwrap());
+        }
+
+        let (path, lang) = &key;
+        let cycle = RENDERING.with(|rendering| {
+            let mut rendering = rendering.borrow_mut();
+            if rendering.contains(path) {
+                let mut chain = rendering.clone();
+                chain.push(path.clone());
+                return Some(chain.join(" -> "));
+            }
+            rendering.push(path.clone());
+            None
+        });
+        if let Some(chain) = cycle {
+            return Err(
+                format!("`render_content_file`: `{}` includes itself: {}", path, chain).into()
+            );
+        }
+        let res = self.render(path, lang);
+        RENDERING.with(|rendering| rendering.borrow_mut().pop());
+
+        let html = res?;
+        self.cache.lock().unwrap().insert(key.clone(), html.clone());
+        Ok(to_value(html).unwrap())
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::{create_dir, write};
+
+    use libs::tera::Context;
+    use tempfile::tempdir;
+
+    fn setup() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempdir().unwrap();
+        let content_path = dir.path().join("content");
+        create_dir(&content_path).unwrap();
+        create_dir(content_path.join("snippets")).unwrap();
+        write(
+            content_path.join("snippets/pricing.md"),
+            "+++\nrender = false\n+++\n# Pricing\n\nFree, [read more](@/about.md).\n",
+        )
+        .unwrap();
+        write(content_path.join("snippets/loop.md"), "Before {{ include(path=\"loop.md\") }}\n")
+            .unwrap();
+        write(dir.path().join("secret.md"), "Secret\n").unwrap();
+        (dir, content_path)
+    }
+
+    fn render(tera: &Tera, template: &str) -> Result<String> {
+        let mut tera = tera.clone();
+        tera.add_raw_template("tpl.html", template).unwrap();
+        tera.render("tpl.html", &Context::new())
+    }
+
+    #[test]
+    fn can_render_content_file() {
+        let (_dir, content_path) = setup();
+        let permalinks =
+            HashMap::from([("about.md".to_string(), "https://example.com/about/".into())]);
+        let mut tera = Tera::default();
+        let function = RenderContentFile::new(
+            content_path,
+            Config::default_for_test(),
+            Arc::default(),
+            Arc::new(permalinks),
+            &tera,
+        );
+        tera.register_function("render_content_file", function);
+
+        let html = render(&tera, r#"{{ render_content_file(path="snippets/pricing.md") }}"#);
+        assert_eq!(
+            html.unwrap(),
+            "<h1 id=\"pricing\">Pricing</h1>\n<p>Free, <a href=\"https://example.com/about/\">read more</a>.</p>\n"
+        );
+        let html = render(&tera, r#"{{ render_content_file(path="@/snippets/pricing.md") }}"#);
+        assert!(html.unwrap().contains("Pricing"));
+    }
+
+    #[test]
+    fn rejects_files_outside_of_the_content_directory() {
+        let (_dir, content_path) = setup();
+        let absolute = content_path.join("snippets/pricing.md");
+        let function = RenderContentFile::new(
+            content_path,
+            Config::default_for_test(),
+            Arc::default(),
+            Arc::default(),
+            &Tera::default(),
+        );
+
+        for path in ["../secret.md", "snippets/../../secret.md", absolute.to_str().unwrap()] {
+            let args = HashMap::from([("path".to_string(), to_value(path).unwrap())]);
+            let err = function.call(&args).unwrap_err().to_string();
+            assert!(err.contains("is not a file of the `content` directory"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn errors_on_files_including_themselves() {
+        let (_dir, content_path) = setup();
+        let mut tera = Tera::default();
+        tera.add_raw_template(
+            "shortcodes/include.html",
+            r#"{{ render_content_file(path="snippets/" ~ path) }}"#,
+        )
+        .unwrap();
+        let function = RenderContentFile::new(
+            content_path,
+            Config::default_for_test(),
+            Arc::default(),
+            Arc::default(),
+            &tera,
+        );
+        tera.register_function("render_content_file", function);
+
+        let err =
+            render(&tera, r#"{{ render_content_file(path="snippets/loop.md") }}"#).unwrap_err();
+        let mut message = err.to_string();
+        let mut source = std::error::Error::source(&err);
+        while let Some(e) = source {
+            message.push_str(&format!("\n{}", e));
+            source = e.source();
+        }
+        assert!(
+            message.contains(
+                "`snippets/loop.md` includes itself: snippets/loop.md -> snippets/loop.md"
+            ),
+            "{}",
+            message
+        );
+    }
+}
@@ -1,15 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::io::Read;
 use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 
 use crate::global_fns::helpers::search_for_file;
-use config::Config;
+use config::{Config, LinkCheckerLevel};
 
 use libs::base64::engine::{general_purpose::STANDARD as standard_b64, Engine};
 use libs::sha2::{digest, Sha256, Sha384, Sha512};
 use libs::tera::{from_value, to_value, Function as TeraFn, Result, Value};
-use utils::site::resolve_internal_link;
+use utils::site::{is_internal_link, resolve_internal_link_in_lang};
 
 fn compute_hash<D: digest::Digest>(data: &[u8], as_base64: bool) -> String
 where
@@ -29,24 +30,47 @@ where
 pub struct GetUrl {
     base_path: PathBuf,
     config: Config,
-    permalinks: HashMap<String, String>,
+    permalinks: Arc<HashMap<String, Arc<str>>>,
     output_path: PathBuf,
+    /// Original path -> content-hashed path of the static assets, filled when building the site
+    asset_manifest: Arc<RwLock<BTreeMap<String, String>>>,
 }
 
 impl GetUrl {
     pub fn new(
         base_path: PathBuf,
         config: Config,
-        permalinks: HashMap<String, String>,
+        permalinks: Arc<HashMap<String, Arc<str>>>,
         output_path: PathBuf,
+        asset_manifest: Arc<RwLock<BTreeMap<String, String>>>,
     ) -> Self {
-        Self { base_path, config, permalinks, output_path }
+        Self { base_path, config, permalinks, output_path, asset_manifest }
     }
+
+    /// Whether the path is a file of the static directories or of the output directory, like the
+    /// compiled Sass files
+    fn is_static_file(&self, path: &str) -> bool {
+        let path = path.trim_start_matches('/');
+        if path.is_empty() {
+            return false;
+        }
+        let mut dirs = vec![self.base_path.join("static"), self.output_path.clone()];
+        if let Some(ref theme) = self.config.theme {
+            dirs.push(self.base_path.join("themes").join(theme).join("static"));
+        }
+        dirs.iter().any(|dir| dir.join(path).is_file())
+    }
+}
+
+/// The path without its query string and fragment
+fn file_path(path: &str) -> &str {
+    path.split(['?', '#']).next().unwrap_or_default()
 }
 
-fn make_path_with_lang(path: String, lang: &str, config: &Config) -> Result<String> {
+/// Returns the language to resolve internal links in, `None` for the default language
+fn get_link_lang<'a>(lang: &'a str, config: &Config) -> Result<Option<&'a str>> {
     if lang == config.default_language {
-        return Ok(path);
+        return Ok(None);
     }
 
     if !config.other_languages().contains_key(lang) {
@@ -55,10 +79,7 @@ fn make_path_with_lang(path: String, lang: &str, config: &Config) -> Result<Stri
         );
     }
 
-    let mut split_path: Vec<String> = path.split('.').map(String::from).collect();
-    let ilast = split_path.len() - 1;
-    split_path[ilast] = format!("{}.{}", lang, split_path[ilast]);
-    Ok(split_path.join("."))
+    Ok(Some(lang))
 }
 
 impl TeraFn for GetUrl {
@@ -84,22 +105,35 @@ impl TeraFn for GetUrl {
             .unwrap_or_else(|| self.config.default_language.clone());
 
         // if it starts with @/, resolve it as an internal link
-        if path.starts_with("@/") {
-            let path_with_lang = match make_path_with_lang(path, &lang, &self.config) {
-                Ok(x) => x,
-                Err(e) => return Err(e),
-            };
+        if is_internal_link(&path) {
+            let link_lang = get_link_lang(&lang, &self.config)?;
 
-            match resolve_internal_link(&path_with_lang, &self.permalinks) {
+            match resolve_internal_link_in_lang(&path, link_lang, &self.permalinks) {
                 Ok(resolved) => Ok(to_value(resolved.permalink).unwrap()),
-                Err(_) => Err(format!(
-                    "`get_url`: could not resolve URL for link `{}` not found.",
-                    path_with_lang
-                )
-                .into()),
+                Err(e) => {
+                    let msg =
+                        format!("`get_url`: could not resolve URL for link `{}`: {}", path, e);
+                    match self.config.link_checker.internal_level {
+                        LinkCheckerLevel::Error => Err(msg.into()),
+                        LinkCheckerLevel::Warn => {
+                            console::warn(&msg);
+                            Ok(to_value(path).unwrap())
+                        }
+                    }
+                }
             }
         } else {
             // anything else
+            // Assets with a content hash in their filename don't need any cachebusting
+            let (file, suffix) = path.split_at(path.find(['?', '#']).unwrap_or(path.len()));
+            let hashed_path =
+                self.asset_manifest.read().unwrap().get(file.trim_start_matches('/')).cloned();
+            let is_hashed = hashed_path.is_some();
+            let cachebust = cachebust && !is_hashed;
+            let path = match hashed_path {
+                Some(hashed) => format!("{}{}", hashed, suffix),
+                None => path,
+            };
             let mut segments = vec![];
 
             if lang != self.config.default_language
@@ -112,15 +146,32 @@ impl TeraFn for GetUrl {
 
             let path_with_lang = segments.join("/");
 
-            let mut permalink = self.config.make_permalink(&path_with_lang);
+            // The static files and the hashed assets are served from `asset_base_url` if it is set
+            let is_asset = self.config.get_asset_base_url().is_some()
+                && (is_hashed || self.is_static_file(file_path(&path_with_lang)));
+            let permalink = if is_asset {
+                self.config.make_asset_permalink(&path_with_lang)
+            } else {
+                self.config.make_permalink(&path_with_lang)
+            };
+            // The query string and fragment go after the slash removal and the cachebust hash
+            let (mut permalink, suffix) = match permalink.find(['?', '#']) {
+                Some(idx) => (permalink[..idx].to_string(), permalink[idx..].to_string()),
+                None => (permalink, String::new()),
+            };
+            let (mut query, fragment) = match suffix.find('#') {
+                Some(idx) => (suffix[..idx].to_string(), suffix[idx..].to_string()),
+                None => (suffix, String::new()),
+            };
             if !trailing_slash && permalink.ends_with('/') {
                 permalink.pop(); // Removes the slash
             }
 
             if cachebust {
+                let file_path = file_path(&path_with_lang);
                 match search_for_file(
                     &self.base_path,
-                    &path_with_lang,
+                    file_path,
                     &self.config.theme,
                     &self.output_path,
                 )
@@ -133,18 +184,20 @@ impl TeraFn for GetUrl {
                 }) {
                     Some(hash) => {
                         let shorthash = &hash[..20]; // 2^-80 chance of false positive
-                        permalink = format!("{}?h={}", permalink, shorthash);
+                        query = match query.as_str() {
+                            "" | "?" => format!("?h={}", shorthash),
+                            _ => format!("{}&h={}", query, shorthash),
+                        };
                     }
                     None => {
-                        return Err(format!(
-                            "`get_url`: Could not find or open file {}",
-                            path_with_lang
+                        return Err(
+                            format!("`get_url`: Could not find or open file {}", file_path).into()
                         )
-                        .into())
                     }
                 };
             }
 
+            let permalink = format!("{}{}{}", permalink, query, fragment);
             Ok(to_value(permalink).unwrap())
         }
     }
@@ -247,14 +300,15 @@ impl TeraFn for GetHash {
 mod tests {
     use super::{GetHash, GetUrl};
 
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
     use std::fs::{copy, create_dir};
     use std::path::PathBuf;
+    use std::sync::{Arc, RwLock};
 
     use libs::tera::{to_value, Function};
     use tempfile::{tempdir, TempDir};
 
-    use config::Config;
+    use config::{Config, LinkCheckerLevel};
     use utils::fs::create_file;
 
     fn create_temp_dir() -> TempDir {
@@ -281,8 +335,9 @@ title = "A title"
         let static_fn = GetUrl::new(
             dir.path().to_path_buf(),
             Config::default(),
-            HashMap::new(),
+            Arc::default(),
             PathBuf::new(),
+            Arc::default(),
         );
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("app.css").unwrap());
@@ -301,6 +356,18 @@ title = "A title"
             static_fn.call(&args).unwrap(),
             "http://a-website.com/gutenberg.jpg?h=93fff9d0ecde9b119c0c"
         );
+
+        // The hash composes with an existing query string and goes before the fragment
+        for (path, expected) in [
+            ("app.css?v=2", "http://a-website.com/app.css?v=2&h=572e691dc68c3fcd653a"),
+            ("/app.css#x", "http://a-website.com/app.css?h=572e691dc68c3fcd653a#x"),
+            ("app.css?v=2#x", "http://a-website.com/app.css?v=2&h=572e691dc68c3fcd653a#x"),
+        ] {
+            let mut args = HashMap::new();
+            args.insert("path".to_string(), to_value(path).unwrap());
+            args.insert("cachebust".to_string(), to_value(true).unwrap());
+            assert_eq!(static_fn.call(&args).unwrap(), expected);
+        }
     }
 
     #[test]
@@ -309,8 +376,9 @@ title = "A title"
         let static_fn = GetUrl::new(
             dir.path().to_path_buf(),
             Config::default(),
-            HashMap::new(),
+            Arc::default(),
             PathBuf::new(),
+            Arc::default(),
         );
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("app.css").unwrap());
@@ -324,8 +392,9 @@ title = "A title"
         let static_fn = GetUrl::new(
             dir.path().to_path_buf(),
             Config::default(),
-            HashMap::new(),
+            Arc::default(),
             PathBuf::new(),
+            Arc::default(),
         );
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("app.css").unwrap());
@@ -343,8 +412,9 @@ title = "A title"
         let static_fn = GetUrl::new(
             dir.path().to_path_buf(),
             Config::default(),
-            HashMap::new(),
+            Arc::default(),
             PathBuf::new(),
+            Arc::default(),
         );
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("app.css").unwrap());
@@ -363,19 +433,86 @@ title = "A title"
         create_file(&public.join("style.css"), "// Hello world")
             .expect("Failed to create file in output directory");
 
-        let static_fn =
-            GetUrl::new(dir.path().to_path_buf(), Config::default(), HashMap::new(), public);
+        let static_fn = GetUrl::new(
+            dir.path().to_path_buf(),
+            Config::default(),
+            Arc::default(),
+            public,
+            Arc::default(),
+        );
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("style.css").unwrap());
         assert_eq!(static_fn.call(&args).unwrap(), "http://a-website.com/style.css");
     }
 
+    #[test]
+    fn uses_hashed_filename_from_asset_manifest() {
+        let dir = create_temp_dir();
+        let manifest = Arc::new(RwLock::new(BTreeMap::from([(
+            "app.css".to_string(),
+            "app.0123456789.css".to_string(),
+        )])));
+        let static_fn = GetUrl::new(
+            dir.path().to_path_buf(),
+            Config::default(),
+            Arc::default(),
+            PathBuf::new(),
+            manifest,
+        );
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("/app.css").unwrap());
+        args.insert("cachebust".to_string(), to_value(true).unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), "http://a-website.com/app.0123456789.css");
+    }
+
+    #[test]
+    fn links_static_files_from_the_asset_base_url() {
+        let dir = create_temp_dir();
+        create_file(&dir.path().join("static").join("logo.png"), "").unwrap();
+        create_file(&dir.path().join("public").join("main.css"), "").unwrap();
+        let manifest = Arc::new(RwLock::new(BTreeMap::from([(
+            "app.css".to_string(),
+            "app.0123456789.css".to_string(),
+        )])));
+        let mut config = Config::default();
+        config.asset_base_url = Some("https://cdn.a-website.com".to_string());
+        let static_fn = GetUrl::new(
+            dir.path().to_path_buf(),
+            config,
+            Arc::default(),
+            dir.path().join("public"),
+            manifest,
+        );
+
+        for (path, expected) in [
+            ("logo.png", "https://cdn.a-website.com/logo.png"),
+            ("/main.css?v=2", "https://cdn.a-website.com/main.css?v=2"),
+            ("app.css", "https://cdn.a-website.com/app.0123456789.css"),
+            // Not a file, so a page
+            ("blog/", "http://a-website.com/blog"),
+        ] {
+            let args = HashMap::from([("path".to_string(), to_value(path).unwrap())]);
+            assert_eq!(static_fn.call(&args).unwrap(), expected, "{}", path);
+        }
+        let args = HashMap::from([
+            ("path".to_string(), to_value("logo.png").unwrap()),
+            ("cachebust".to_string(), to_value(true).unwrap()),
+        ]);
+        let url = static_fn.call(&args).unwrap();
+        assert!(url.as_str().unwrap().starts_with("https://cdn.a-website.com/logo.png?h="));
+    }
+
     #[test]
     fn error_when_language_not_available() {
         let config = Config::parse(CONFIG_DATA).unwrap();
         let dir = create_temp_dir();
-        let static_fn =
-            GetUrl::new(dir.path().to_path_buf(), config, HashMap::new(), PathBuf::new());
+        let static_fn = GetUrl::new(
+            dir.path().to_path_buf(),
+            config,
+            Arc::default(),
+            PathBuf::new(),
+            Arc::default(),
+        );
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("@/a_section/a_page.md").unwrap());
         args.insert("lang".to_string(), to_value("it").unwrap());
@@ -386,24 +523,51 @@ title = "A title"
         );
     }
 
+    #[test]
+    fn only_warns_on_missing_internal_link_with_warn_level() {
+        let mut config = Config::parse(CONFIG_DATA).unwrap();
+        let dir = create_temp_dir();
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("@/a_section/missing.md").unwrap());
+        let static_fn = GetUrl::new(
+            dir.path().to_path_buf(),
+            config.clone(),
+            Arc::default(),
+            PathBuf::new(),
+            Arc::default(),
+        );
+        assert!(static_fn.call(&args).is_err());
+
+        config.link_checker.internal_level = LinkCheckerLevel::Warn;
+        let static_fn = GetUrl::new(
+            dir.path().to_path_buf(),
+            config,
+            Arc::default(),
+            PathBuf::new(),
+            Arc::default(),
+        );
+        assert_eq!(static_fn.call(&args).unwrap(), "@/a_section/missing.md");
+    }
+
     #[test]
     fn can_get_url_with_default_language() {
         let mut permalinks = HashMap::new();
         permalinks.insert(
             "a_section/a_page.md".to_string(),
-            "https://remplace-par-ton-url.fr/a_section/a_page/".to_string(),
+            "https://remplace-par-ton-url.fr/a_section/a_page/".into(),
         );
         permalinks.insert(
             "a_section/a_page.en.md".to_string(),
-            "https://remplace-par-ton-url.fr/en/a_section/a_page/".to_string(),
+            "https://remplace-par-ton-url.fr/en/a_section/a_page/".into(),
         );
         let config = Config::parse(CONFIG_DATA).unwrap();
         let dir = create_temp_dir();
         let static_fn = GetUrl::new(
             dir.path().to_path_buf(),
             config.clone(),
-            permalinks.clone(),
+            Arc::new(permalinks.clone()),
             PathBuf::new(),
+            Arc::default(),
         );
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("@/a_section/a_page.md").unwrap());
@@ -414,20 +578,58 @@ title = "A title"
         );
     }
 
+    #[test]
+    fn can_get_url_of_content_without_extension() {
+        let config = Config::parse(CONFIG_DATA).unwrap();
+        let mut permalinks = HashMap::new();
+        permalinks.insert(
+            "a_section/_index.md".to_string(),
+            "https://remplace-par-ton-url.fr/a_section/".into(),
+        );
+        permalinks.insert(
+            "a_section/a_page.en.md".to_string(),
+            "https://remplace-par-ton-url.fr/en/a_section/a_page/".into(),
+        );
+        let dir = create_temp_dir();
+        let static_fn = GetUrl::new(
+            dir.path().to_path_buf(),
+            config,
+            Arc::new(permalinks),
+            PathBuf::new(),
+            Arc::default(),
+        );
+        for (path, lang, expected) in [
+            ("@/a_section", "fr", "https://remplace-par-ton-url.fr/a_section/"),
+            ("./a_section/_index.md", "fr", "https://remplace-par-ton-url.fr/a_section/"),
+            ("@/a_section/a_page", "en", "https://remplace-par-ton-url.fr/en/a_section/a_page/"),
+        ] {
+            let mut args = HashMap::new();
+            args.insert("path".to_string(), to_value(path).unwrap());
+            args.insert("lang".to_string(), to_value(lang).unwrap());
+            assert_eq!(static_fn.call(&args).unwrap(), expected);
+        }
+    }
+
     #[test]
     fn can_get_url_with_other_language() {
         let config = Config::parse(CONFIG_DATA).unwrap();
         let mut permalinks = HashMap::new();
         permalinks.insert(
             "a_section/a_page.md".to_string(),
-            "https://remplace-par-ton-url.fr/a_section/a_page/".to_string(),
+            "https://remplace-par-ton-url.fr/a_section/a_page/".into(),
         );
         permalinks.insert(
             "a_section/a_page.en.md".to_string(),
-            "https://remplace-par-ton-url.fr/en/a_section/a_page/".to_string(),
+            "https://remplace-par-ton-url.fr/en/a_section/a_page/".into(),
         );
         let dir = create_temp_dir();
-        let static_fn = GetUrl::new(dir.path().to_path_buf(), config, permalinks, PathBuf::new());
+        let static_fn = GetUrl::new(
+            dir.path().to_path_buf(),
+            config,
+            Arc::new(permalinks),
+            PathBuf::new(),
+            Arc::default(),
+        );
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("@/a_section/a_page.md").unwrap());
         args.insert("lang".to_string(), to_value("en").unwrap());
@@ -443,14 +645,20 @@ title = "A title"
         let mut permalinks = HashMap::new();
         permalinks.insert(
             "a_section/a_page.md".to_string(),
-            "https://remplace-par-ton-url.fr/a_section/a_page/".to_string(),
+            "https://remplace-par-ton-url.fr/a_section/a_page/".into(),
         );
         permalinks.insert(
             "a_section/a_page.en.md".to_string(),
-            "https://remplace-par-ton-url.fr/en/a_section/a_page/".to_string(),
+            "https://remplace-par-ton-url.fr/en/a_section/a_page/".into(),
         );
         let dir = create_temp_dir();
-        let static_fn = GetUrl::new(dir.path().to_path_buf(), config, permalinks, PathBuf::new());
+        let static_fn = GetUrl::new(
+            dir.path().to_path_buf(),
+            config,
+            Arc::new(permalinks),
+            PathBuf::new(),
+            Arc::default(),
+        );
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("/en/a_section/a_page/").unwrap());
         args.insert("lang".to_string(), to_value("en").unwrap());
@@ -464,8 +672,13 @@ title = "A title"
     fn can_get_feed_urls_with_default_language() {
         let config = Config::parse(CONFIG_DATA).unwrap();
         let dir = create_temp_dir();
-        let static_fn =
-            GetUrl::new(dir.path().to_path_buf(), config.clone(), HashMap::new(), PathBuf::new());
+        let static_fn = GetUrl::new(
+            dir.path().to_path_buf(),
+            config.clone(),
+            Arc::default(),
+            PathBuf::new(),
+            Arc::default(),
+        );
         for feed_filename in &config.feed_filenames {
             let mut args = HashMap::new();
             args.insert("path".to_string(), to_value(feed_filename).unwrap());
@@ -478,8 +691,13 @@ title = "A title"
     fn can_get_feed_urls_with_other_language() {
         let config = Config::parse(CONFIG_DATA).unwrap();
         let dir = create_temp_dir();
-        let static_fn =
-            GetUrl::new(dir.path().to_path_buf(), config.clone(), HashMap::new(), PathBuf::new());
+        let static_fn = GetUrl::new(
+            dir.path().to_path_buf(),
+            config.clone(),
+            Arc::default(),
+            PathBuf::new(),
+            Arc::default(),
+        );
         for feed_filename in &config.feed_filenames {
             let mut args = HashMap::new();
             args.insert("path".to_string(), to_value(feed_filename).unwrap());
@@ -35,7 +35,7 @@ pub fn search_for_file(
     let mut file_path = base_path.join(&*actual_path);
     let mut file_exists = file_path.exists();
 
-    if file_exists && !is_path_in_directory(base_path, &file_path)? {
+    if file_exists && !is_path_in_directory(base_path, &file_path)?.is_inside() {
         bail!("{:?} is not inside the base site directory {:?}", path, base_path);
     }
 
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+use config::Config;
 use libs::tera::{from_value, to_value, Function as TeraFn, Result, Value};
 
 use crate::global_fns::helpers::search_for_file;
@@ -53,6 +54,13 @@ impl TeraFn for ResizeImage {
             optional_arg!(String, args.get("format"), "`resize_image`: `format` must be a string")
                 .unwrap_or_else(|| DEFAULT_FMT.to_string());
 
+        let formats = optional_arg!(
+            Vec<String>,
+            args.get("formats"),
+            "`resize_image`: `formats` must be an array of strings"
+        )
+        .unwrap_or_default();
+
         let quality =
             optional_arg!(u8, args.get("quality"), "`resize_image`: `quality` must be a number");
         if let Some(quality) = quality {
@@ -60,6 +68,12 @@ impl TeraFn for ResizeImage {
                 return Err("`resize_image`: `quality` must be in range 1-100".to_string().into());
             }
         }
+        let keep_metadata = optional_arg!(
+            bool,
+            args.get("keep_metadata"),
+            "`resize_image`: `keep_metadata` must be a boolean (true or false)"
+        )
+        .unwrap_or(false);
         let resize_op = imageproc::ResizeOperation::from_args(&op, width, height)
             .map_err(|e| format!("`resize_image`: {}", e))?;
         let mut imageproc = self.imageproc.lock().unwrap();
@@ -74,7 +88,15 @@ impl TeraFn for ResizeImage {
             };
 
         let response = imageproc
-            .enqueue(resize_op, unified_path, file_path, &format, quality)
+            .enqueue_with_formats(
+                resize_op,
+                unified_path,
+                file_path,
+                &format,
+                &formats,
+                quality,
+                keep_metadata,
+            )
             .map_err(|e| format!("`resize_image`: {}", e))?;
 
         to_value(response).map_err(Into::into)
@@ -109,6 +131,15 @@ impl TeraFn for GetImageMetadata {
             "`get_image_metadata`: `allow_missing` must be a boolean (true or false)"
         )
         .unwrap_or(false);
+        let placeholder = optional_arg!(
+            String,
+            args.get("placeholder"),
+            "`get_image_metadata`: `placeholder` must be a string"
+        );
+        let placeholder = placeholder
+            .map(|p| imageproc::Placeholder::from_arg(&p))
+            .transpose()
+            .map_err(|e| format!("`get_image_metadata`: {}", e))?;
 
         let (src_path, unified_path) =
             match search_for_file(&self.base_path, &path, &self.theme, &self.output_path)
@@ -123,15 +154,33 @@ impl TeraFn for GetImageMetadata {
                 }
             };
 
+        let cache_key = match placeholder {
+            Some(p) => format!("{}#{:?}", unified_path, p),
+            None => unified_path,
+        };
         let mut cache = self.result_cache.lock().expect("result cache lock");
-        if let Some(cached_result) = cache.get(&unified_path) {
+        if let Some(cached_result) = cache.get(&cache_key) {
             return Ok(cached_result.clone());
         }
 
-        let response = imageproc::read_image_metadata(src_path)
+        let response = imageproc::read_image_metadata(&src_path)
             .map_err(|e| format!("`resize_image`: {}", e))?;
-        let out = to_value(response).unwrap();
-        cache.insert(unified_path, out.clone());
+        let mut out = to_value(response).unwrap();
+        if let Some(placeholder) = placeholder {
+            // A missing placeholder only makes the page load less nicely
+            let value = match imageproc::get_placeholder(&self.base_path, &src_path, placeholder) {
+                Ok(p) => Value::String(p),
+                Err(e) => {
+                    console::warn(&format!(
+                        "`get_image_metadata`: no placeholder for {}: {:#}",
+                        path, e
+                    ));
+                    Value::Null
+                }
+            };
+            out.as_object_mut().unwrap().insert("placeholder".to_string(), value);
+        }
+        cache.insert(cache_key, out.clone());
 
         Ok(out)
     }
@@ -299,4 +348,56 @@ mod tests {
         assert_eq!(data["format"], to_value("jpg").unwrap());
         assert_eq!(data["mime"], to_value("image/jpeg").unwrap());
     }
+
+    #[test]
+    fn can_get_image_placeholders() {
+        let dir = create_dir_with_image();
+        // A truncated PNG: its size can be read but not its pixels
+        let broken = dir.path().join("static").join("broken.png");
+        libs::image::RgbImage::new(4, 4).save(&broken).unwrap();
+        let png = std::fs::read(&broken).unwrap();
+        std::fs::write(&broken, &png[..png.len() - 20]).unwrap();
+        let static_fn = GetImageMetadata::new(dir.path().to_path_buf(), None, PathBuf::new());
+        let call = |path: &str, placeholder: &str| {
+            let mut args = HashMap::new();
+            args.insert("path".to_string(), to_value(path).unwrap());
+            args.insert("placeholder".to_string(), to_value(placeholder).unwrap());
+            static_fn.call(&args)
+        };
+
+        let data = call("static/gutenberg.jpg", "color").unwrap();
+        assert_eq!(data["width"], to_value(300).unwrap());
+        let color = data["placeholder"].as_str().unwrap();
+        assert!(color.starts_with('#') && color.len() == 7);
+        let data = call("static/gutenberg.jpg", "lqip").unwrap();
+        assert!(data["placeholder"].as_str().unwrap().starts_with("data:image/jpeg;base64,"));
+        assert!(dir.path().join("static/processed_images/placeholders").exists());
+        assert_eq!(
+            call("static/broken.png", "color").unwrap()["placeholder"],
+            libs::tera::Value::Null
+        );
+        assert!(call("static/gutenberg.jpg", "blurhash").is_err());
+    }
+}
+
+/// The tags for the favicons and web manifest generated from `favicon.source`
+#[derive(Debug)]
+pub struct FaviconTags {
+    tags: String,
+}
+
+impl FaviconTags {
+    pub fn new(config: &Config) -> Self {
+        Self { tags: imageproc::favicon_tags(config) }
+    }
+}
+
+impl TeraFn for FaviconTags {
+    fn call(&self, _: &HashMap<String, Value>) -> Result<Value> {
+        Ok(to_value(&self.tags).unwrap())
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
 }
@@ -2,14 +2,22 @@
 mod macros;
 
 mod content;
+mod content_file;
 mod files;
 mod helpers;
 mod i18n;
 mod images;
 mod load_data;
+mod time;
 
-pub use self::content::{GetPage, GetSection, GetTaxonomy, GetTaxonomyTerm, GetTaxonomyUrl};
+pub use self::content::{
+    ContentMiss, GetAncestors, GetPage, GetPagesBy, GetRandomPage, GetSection, GetSiteIndex,
+    GetTaxonomy, GetTaxonomyTerm, GetTaxonomyUrl, GroupPagesByDate, InvalidDate,
+    MissingContentHandler,
+};
+pub use self::content_file::RenderContentFile;
 pub use self::files::{GetHash, GetUrl};
 pub use self::i18n::Trans;
-pub use self::images::{GetImageMetadata, ResizeImage};
+pub use self::images::{FaviconTags, GetImageMetadata, ResizeImage};
 pub use self::load_data::LoadData;
+pub use self::time::Now;
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use libs::chrono::format::{Item, StrftimeItems};
+use libs::chrono::{DateTime, SecondsFormat};
+use libs::tera::{from_value, to_value, Function as TeraFn, Result, Value};
+
+/// Replaces the `now` of Tera: it returns when the build started rather than the current time,
+/// so every page of a build shows the same time and `SOURCE_DATE_EPOCH` can fix it
+#[derive(Debug)]
+pub struct Now {
+    /// In seconds since the Unix epoch
+    timestamp: i64,
+}
+impl Now {
+    pub fn new(timestamp: i64) -> Self {
+        Self { timestamp }
+    }
+}
+impl TeraFn for Now {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let format = optional_arg!(String, args.get("format"), "`now`: `format` must be a string");
+        let as_timestamp = optional_arg!(
+            bool,
+            args.get("timestamp"),
+            "`now`: `timestamp` must be a boolean (true or false)"
+        )
+        .unwrap_or(false);
+
+        if as_timestamp {
+            return Ok(to_value(self.timestamp).unwrap());
+        }
+        let datetime = DateTime::from_timestamp(self.timestamp, 0)
+            .ok_or_else(|| format!("`now`: {} is not a valid timestamp", self.timestamp))?;
+        match format {
+            Some(format) => {
+                if StrftimeItems::new(&format).any(|item| matches!(item, Item::Error)) {
+                    return Err(format!("`now`: invalid date format `{}`", format).into());
+                }
+                Ok(to_value(datetime.format(&format).to_string()).unwrap())
+            }
+            None => Ok(to_value(datetime.to_rfc3339_opts(SecondsFormat::Secs, true)).unwrap()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_get_build_time() {
+        let now = Now::new(1714564800);
+        let call = |args: Vec<(&str, Value)>| {
+            let args = args.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+            now.call(&args)
+        };
+        assert_eq!(call(vec![]).unwrap(), to_value("2024-05-01T12:00:00Z").unwrap());
+        assert_eq!(
+            call(vec![("format", to_value("%Y").unwrap())]).unwrap(),
+            to_value("2024").unwrap()
+        );
+        assert_eq!(
+            call(vec![("timestamp", to_value(true).unwrap())]).unwrap(),
+            to_value(1714564800).unwrap()
+        );
+        assert!(call(vec![("format", to_value("%Q").unwrap())]).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 pub mod filters;
 pub mod global_fns;
+mod strict;
 
 use std::path::Path;
 
@@ -10,6 +11,8 @@ use libs::tera::{Context, Tera};
 use errors::{bail, Context as ErrorContext, Result};
 use utils::templates::rewrite_theme_paths;
 
+pub use crate::strict::make_templates_strict;
+
 pub static ZOLA_TERA: Lazy<Tera> = Lazy::new(|| {
     let mut tera = Tera::default();
     tera.add_raw_templates(vec![
@@ -18,17 +21,28 @@ pub static ZOLA_TERA: Lazy<Tera> = Lazy::new(|| {
         ("__zola_builtins/rss.xml", include_str!("builtins/rss.xml")),
         ("__zola_builtins/sitemap.xml", include_str!("builtins/sitemap.xml")),
         ("__zola_builtins/robots.txt", include_str!("builtins/robots.txt")),
+        ("__zola_builtins/opensearch.xml", include_str!("builtins/opensearch.xml")),
         (
             "__zola_builtins/split_sitemap_index.xml",
             include_str!("builtins/split_sitemap_index.xml"),
         ),
         ("__zola_builtins/anchor-link.html", include_str!("builtins/anchor-link.html")),
+        ("__zola_builtins/toc.html", include_str!("builtins/toc.html")),
+        ("__zola_builtins/code-block.html", include_str!("builtins/code-block.html")),
+        ("__zola_builtins/protected.html", include_str!("builtins/protected.html")),
+        ("__zola_builtins/shortcodes/toc.html", include_str!("builtins/shortcodes/toc.html")),
+        ("__zola_builtins/shortcodes/image.html", include_str!("builtins/shortcodes/image.html")),
         ("internal/alias.html", include_str!("builtins/internal/alias.html")),
+        // Not prefixed as they are meant to be included by the user templates, which can override them
+        ("seo.html", include_str!("builtins/seo.html")),
+        ("zola/macros.html", include_str!("builtins/zola/macros.html")),
     ])
     .unwrap();
     tera.register_filter("base64_encode", filters::base64_encode);
     tera.register_filter("base64_decode", filters::base64_decode);
     tera.register_filter("regex_replace", filters::RegexReplaceFilter::new());
+    tera.register_filter("plain_text", filters::plain_text);
+    tera.register_filter("safe_truncate", filters::safe_truncate);
     tera
 });
 
@@ -74,6 +88,9 @@ pub fn load_tera(path: &Path, config: &Config) -> Result<Tera> {
                 Some("robots.txt"),
             )?;
         }
+        // The built-in templates come with the ones of the theme as extending checks that the
+        // imported macros, like the ones of `zola/macros.html`, exist
+        tera_theme.extend(&ZOLA_TERA)?;
         tera.extend(&tera_theme)?;
     }
     tera.extend(&ZOLA_TERA)?;
@@ -85,3 +102,489 @@ pub fn load_tera(path: &Path, config: &Config) -> Result<Tera> {
 
     Ok(tera)
 }
+
+/// Loads the given templates, typically the ones of the page output formats, if they are not
+/// already loaded: only the `*ml` and `md` files are loaded by `load_tera`.
+/// The templates of the site take precedence over the ones of the theme.
+pub fn load_extra_templates(
+    tera: &mut Tera,
+    path: &Path,
+    theme: &Option<String>,
+    names: &[&str],
+) -> Result<()> {
+    for name in names {
+        let theme_name = theme.as_ref().map(|t| format!("{}/templates/{}", t, name));
+        if tera.templates.contains_key(*name)
+            || theme_name.as_ref().is_some_and(|n| tera.templates.contains_key(n))
+        {
+            continue;
+        }
+        let site_file = path.join("templates").join(name);
+        if site_file.exists() {
+            tera.add_template_file(site_file, Some(name))?;
+        } else if let Some(theme) = theme {
+            let theme_file = path.join("themes").join(theme).join("templates").join(name);
+            if theme_file.exists() {
+                tera.add_template_file(theme_file, theme_name.as_deref())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reloads a single template of the `templates` directory of the site after it was modified,
+/// created or deleted, instead of parsing all of them again like `Tera::full_reload`.
+/// The templates extending it get its new blocks when the inheritance chains are built again,
+/// and the ones including it or importing its macros look it up by name when rendering.
+/// A deleted template that was overriding one of the theme or a built-in one, like `seo.html`,
+/// is replaced by it.
+/// Returns `false` if the path isn't a template file of that directory, like a directory, in
+/// which case all the templates need to be reloaded.
+pub fn reload_template(
+    tera: &mut Tera,
+    templates_dir: &Path,
+    theme: &Option<String>,
+    path: &Path,
+) -> Result<bool> {
+    let name = match path.strip_prefix(templates_dir) {
+        Ok(p) if !path.is_dir() => p.to_string_lossy().replace('\\', "/"),
+        _ => return Ok(false),
+    };
+
+    if path.exists() {
+        // Same as the glob of `load_tera`, the other files are only loaded when they are used
+        // as the template of an output format
+        let is_globbed = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.ends_with("ml") || e == "md");
+        if is_globbed || name == "robots.txt" || tera.templates.contains_key(&name) {
+            tera.add_template_file(path, Some(&name))
+                .with_context(|| format!("Failed to reload template {}", path.display()))?;
+        }
+    } else if tera.templates.remove(&name).is_some() {
+        // What `load_tera` would have added under that name if the site didn't have it
+        let fallback = theme
+            .as_ref()
+            .and_then(|t| tera.templates.get(&format!("{}/templates/{}", t, name)))
+            .or_else(|| ZOLA_TERA.templates.get(&name))
+            .cloned();
+        if let Some(mut template) = fallback {
+            template.name = name.clone();
+            template.from_extend = true;
+            tera.templates.insert(name, template);
+        }
+        // Errors if a template still extends it
+        tera.build_inheritance_chains()?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use libs::serde_json::json;
+    use libs::tera::{to_value, Context, Tera, Value};
+
+    use super::ZOLA_TERA;
+
+    fn seo_tera() -> Tera {
+        let mut tera = Tera::default();
+        tera.extend(&ZOLA_TERA).unwrap();
+        tera.register_function("get_url", |args: &HashMap<String, Value>| {
+            let path = args["path"].as_str().unwrap();
+            Ok(to_value(format!("https://example.com/{}", path.trim_start_matches('/'))).unwrap())
+        });
+        tera
+    }
+
+    fn seo_context() -> Context {
+        let mut context = Context::new();
+        context.insert(
+            "config",
+            &json!({"title": "My site", "description": "A site", "base_url": "https://example.com"}),
+        );
+        context
+    }
+
+    #[test]
+    fn can_reload_a_single_template() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let templates = tmp_dir.path().join("templates");
+        std::fs::create_dir(&templates).unwrap();
+        let base = templates.join("base.html");
+        let child = templates.join("child.html");
+        std::fs::write(&base, "{% block main %}base{% endblock main %}").unwrap();
+        std::fs::write(&child, "{% extends \"base.html\" %}").unwrap();
+        let mut tera = Tera::default();
+        tera.add_template_files(vec![(&base, Some("base.html")), (&child, Some("child.html"))])
+            .unwrap();
+
+        // The templates extending it see the change
+        std::fs::write(&base, "{% block main %}edited{% endblock main %}").unwrap();
+        assert!(super::reload_template(&mut tera, &templates, &None, &base).unwrap());
+        assert_eq!(tera.render("child.html", &Context::new()).unwrap(), "edited");
+
+        // New templates are added
+        let new = templates.join("partials").join("new.html");
+        std::fs::create_dir(templates.join("partials")).unwrap();
+        std::fs::write(&new, "new").unwrap();
+        assert!(super::reload_template(&mut tera, &templates, &None, &new).unwrap());
+        assert_eq!(tera.render("partials/new.html", &Context::new()).unwrap(), "new");
+
+        // Deleted ones are removed, unless another template extends them
+        std::fs::remove_file(&new).unwrap();
+        assert!(super::reload_template(&mut tera, &templates, &None, &new).unwrap());
+        assert!(tera.get_template("partials/new.html").is_err());
+        std::fs::remove_file(&base).unwrap();
+        assert!(super::reload_template(&mut tera, &templates, &None, &base).is_err());
+
+        // The templates of the theme and the built-in ones are back when a site template
+        // overriding them is deleted
+        let mut tera = seo_tera();
+        let theme_page = tmp_dir.path().join("page.html");
+        std::fs::write(&theme_page, "theme").unwrap();
+        tera.add_template_file(&theme_page, Some("sample/templates/page.html")).unwrap();
+        for (name, content) in [("seo.html", "site seo"), ("page.html", "site page")] {
+            let path = templates.join(name);
+            std::fs::write(&path, content).unwrap();
+            let theme = Some("sample".to_string());
+            assert!(super::reload_template(&mut tera, &templates, &theme, &path).unwrap());
+            assert_eq!(tera.render(name, &Context::new()).unwrap(), content);
+            std::fs::remove_file(&path).unwrap();
+            assert!(super::reload_template(&mut tera, &templates, &theme, &path).unwrap());
+        }
+        assert_eq!(tera.render("page.html", &Context::new()).unwrap(), "theme");
+        assert_eq!(
+            tera.render("seo.html", &seo_context()).unwrap(),
+            seo_tera().render("seo.html", &seo_context()).unwrap()
+        );
+
+        // Directories and files from elsewhere need a full reload
+        let partials = templates.join("partials");
+        assert!(!super::reload_template(&mut tera, &templates, &None, &partials).unwrap());
+        assert!(!super::reload_template(&mut tera, &templates, &None, tmp_dir.path()).unwrap());
+    }
+
+    #[test]
+    fn robots_txt_points_to_the_sitemap() {
+        let mut context = Context::new();
+        context.insert("config", &json!({"sitemap_filename": "maps/sitemap_index.xml"}));
+        let res = seo_tera().render("__zola_builtins/robots.txt", &context).unwrap();
+        assert!(res.contains("Sitemap: https://example.com/maps/sitemap_index.xml\n"), "{}", res);
+    }
+
+    #[test]
+    fn can_render_seo_tags_of_page_with_image_and_date() {
+        let mut context = seo_context();
+        context.insert("current_url", "https://example.com/blog/hello/");
+        context.insert(
+            "page",
+            &json!({
+                "title": "Hello <world>",
+                "description": null,
+                "description_resolved": "The first post & more",
+                "summary": "<p>The <em>first</em> post &amp; more</p>",
+                "content": "<p>The <em>first</em> post &amp; more</p><p>Rest</p>",
+                "permalink": "https://example.com/blog/hello/",
+                "date": "2024-01-02",
+                "updated": null,
+                "authors": ["Jane"],
+                "assets": ["/blog/hello/data.json", "/blog/hello/cover.png"],
+                "extra": {},
+            }),
+        );
+        let res = seo_tera().render("seo.html", &context).unwrap();
+        assert_eq!(
+            res,
+            r#"
+<meta property="og:type" content="article">
+<meta property="og:title" content="Hello &lt;world&gt;">
+<meta property="og:url" content="https://example.com/blog/hello/">
+<meta property="og:site_name" content="My site">
+<meta property="og:description" content="The first post &amp; more">
+<meta property="og:image" content="https://example.com/blog/hello/cover.png">
+<meta property="article:published_time" content="2024-01-02">
+<meta name="twitter:card" content="summary_large_image">
+<meta name="twitter:title" content="Hello &lt;world&gt;">
+<meta name="twitter:description" content="The first post &amp; more">
+<meta name="twitter:image" content="https://example.com/blog/hello/cover.png">
+<script type="application/ld+json">
+{
+  "@context": "https://schema.org",
+  "@type": "Article",
+  "headline": "Hello \u003cworld>",
+  "url": "https://example.com/blog/hello/",
+  "description": "The first post & more",
+  "image": "https://example.com/blog/hello/cover.png",
+  "datePublished": "2024-01-02",
+  "author": [
+    {"@type": "Person", "name": "Jane"}
+  ]
+}
+</script>
+"#
+        );
+    }
+
+    #[test]
+    fn can_render_seo_tags_of_section_with_relative_image() {
+        let mut context = seo_context();
+        context.insert("current_url", "https://example.com/");
+        context.insert(
+            "section",
+            &json!({
+                "title": null,
+                "description": null,
+                "summary": null,
+                "content": "",
+                "permalink": "https://example.com/",
+                "assets": [],
+                "extra": {"image": "cover.jpg"},
+            }),
+        );
+        let res = seo_tera().render("seo.html", &context).unwrap();
+        assert_eq!(
+            res,
+            r#"
+<meta property="og:type" content="website">
+<meta property="og:title" content="My site">
+<meta property="og:url" content="https://example.com/">
+<meta property="og:site_name" content="My site">
+<meta property="og:description" content="A site">
+<meta property="og:image" content="https://example.com/cover.jpg">
+<meta name="twitter:card" content="summary_large_image">
+<meta name="twitter:title" content="My site">
+<meta name="twitter:description" content="A site">
+<meta name="twitter:image" content="https://example.com/cover.jpg">
+<script type="application/ld+json">
+{
+  "@context": "https://schema.org",
+  "@type": "WebSite",
+  "name": "My site",
+  "url": "https://example.com/",
+  "description": "A site",
+  "image": "https://example.com/cover.jpg"
+}
+</script>
+"#
+        );
+    }
+
+    #[test]
+    fn can_render_seo_tags_of_page_without_image_and_date() {
+        let mut context = seo_context();
+        context.insert("current_url", "https://example.com/about/");
+        context.insert(
+            "page",
+            &json!({
+                "title": "About",
+                "description": "Who we are",
+                "description_resolved": "Who we are",
+                "summary": null,
+                "content": "<p>Hi</p>",
+                "permalink": "https://example.com/about/",
+                "date": null,
+                "updated": null,
+                "authors": [],
+                "assets": [],
+                "extra": {},
+            }),
+        );
+        let res = seo_tera().render("seo.html", &context).unwrap();
+        assert!(res.contains(r#"<meta property="og:description" content="Who we are">"#));
+        assert!(res.contains(r#"<meta name="twitter:card" content="summary">"#));
+        assert!(!res.contains("og:image"));
+        assert!(!res.contains("article:published_time"));
+        assert!(!res.contains("datePublished"));
+        assert!(!res.contains("author"));
+    }
+
+    #[test]
+    fn can_render_canonical_url_and_robots_of_page() {
+        let mut context = seo_context();
+        context.insert("current_url", "https://example.com/blog/syndicated/");
+        context.insert(
+            "page",
+            &json!({
+                "title": "Syndicated",
+                "description": null,
+                "summary": null,
+                "content": "",
+                "permalink": "https://example.com/blog/syndicated/",
+                "canonical_url": "https://original.com/post/",
+                "robots": "noindex, nofollow",
+                "date": null,
+                "updated": null,
+                "authors": [],
+                "assets": [],
+                "extra": {},
+            }),
+        );
+        let res = seo_tera().render("seo.html", &context).unwrap();
+        assert!(res.starts_with(
+            "\n<link rel=\"canonical\" href=\"https://original.com/post/\">\n<meta name=\"robots\" content=\"noindex, nofollow\">\n"
+        ));
+        assert!(res.contains(r#"<meta property="og:url" content="https://original.com/post/">"#));
+    }
+
+    fn render_macro(call: &str, context: &Context) -> String {
+        let mut tera = Tera::default();
+        tera.extend(&ZOLA_TERA).unwrap();
+        let template = format!(r#"{{% import "zola/macros.html" as zm %}}{{{{ zm::{} }}}}"#, call);
+        tera.add_raw_template("macro_test.html", &template).unwrap();
+        tera.render("macro_test.html", context).unwrap()
+    }
+
+    #[test]
+    fn can_render_pagination_macro() {
+        let mut context = Context::new();
+        let paginator = |current_index: usize| {
+            json!({
+                "paginate_by": 2,
+                "base_url": "https://example.com/posts/page/",
+                "number_pagers": 7,
+                "first": "https://example.com/posts/",
+                "last": "https://example.com/posts/page/7/",
+                "previous": if current_index > 1 { Some("https://example.com/posts/page/prev/") } else { None },
+                "next": if current_index < 7 { Some("https://example.com/posts/page/next/") } else { None },
+                "pages": [],
+                "current_index": current_index,
+                "total_pages": 13,
+            })
+        };
+        context.insert("paginator", &paginator(1));
+        assert_eq!(
+            render_macro("pagination(paginator=paginator)", &context),
+            r#"<nav class="pagination" aria-label="Pagination">
+<ol>
+<li><a href="https://example.com/posts/" aria-current="page">1</a></li>
+<li><a href="https://example.com/posts/page/2/">2</a></li>
+<li><a href="https://example.com/posts/page/3/">3</a></li>
+<li>…</li>
+<li><a href="https://example.com/posts/page/7/">7</a></li>
+</ol>
+<a class="pagination-next" href="https://example.com/posts/page/next/" rel="next">Next ›</a>
+</nav>"#
+        );
+        context.insert("paginator", &paginator(4));
+        assert_eq!(
+            render_macro("pagination(paginator=paginator, window=1)", &context),
+            r#"<nav class="pagination" aria-label="Pagination">
+<a class="pagination-previous" href="https://example.com/posts/page/prev/" rel="prev">‹ Previous</a>
+<ol>
+<li><a href="https://example.com/posts/">1</a></li>
+<li>…</li>
+<li><a href="https://example.com/posts/page/3/">3</a></li>
+<li><a href="https://example.com/posts/page/4/" aria-current="page">4</a></li>
+<li><a href="https://example.com/posts/page/5/">5</a></li>
+<li>…</li>
+<li><a href="https://example.com/posts/page/7/">7</a></li>
+</ol>
+<a class="pagination-next" href="https://example.com/posts/page/next/" rel="next">Next ›</a>
+</nav>"#
+        );
+        let mut single = paginator(1);
+        single["number_pagers"] = json!(1);
+        context.insert("paginator", &single);
+        assert_eq!(render_macro("pagination(paginator=paginator)", &context), "");
+    }
+
+    #[test]
+    fn can_render_toc_macro() {
+        let mut context = Context::new();
+        context.insert(
+            "toc",
+            &json!([
+                {"level": 1, "id": "intro", "title": "Intro & more", "permalink": "https://example.com/#intro", "children": [
+                    {"level": 2, "id": "why", "title": "Why", "permalink": "https://example.com/#why", "children": [
+                        {"level": 3, "id": "deep", "title": "Deep", "permalink": "https://example.com/#deep", "children": []}
+                    ]}
+                ]},
+                {"level": 1, "id": "end", "title": "End", "permalink": "https://example.com/#end", "children": []}
+            ]),
+        );
+        assert_eq!(
+            render_macro("toc(headers=toc, depth=2)", &context),
+            r#"<ul>
+<li><a href="https://example.com/#intro">Intro &amp; more</a><ul>
+<li><a href="https://example.com/#why">Why</a></li>
+</ul></li>
+<li><a href="https://example.com/#end">End</a></li>
+</ul>"#
+        );
+        assert!(render_macro("toc(headers=toc)", &context).contains("#deep"));
+        context.insert("toc", &json!([]));
+        assert_eq!(render_macro("toc(headers=toc)", &context), "");
+    }
+
+    #[test]
+    fn can_render_translations_macro() {
+        let mut context = Context::new();
+        context.insert(
+            "page",
+            &json!({
+                "lang": "fr",
+                "translations": [
+                    {"lang": "en", "title": "Hello", "permalink": "https://example.com/hello/", "path": "hello.md"},
+                    {"lang": "fr", "title": "Bonjour", "permalink": "https://example.com/fr/hello/", "path": "hello.fr.md"},
+                ],
+            }),
+        );
+        assert_eq!(
+            render_macro("translations(page=page)", &context),
+            r#"<nav class="translations" aria-label="Translations">
+<ul>
+<li><a href="https://example.com/hello/" hreflang="en" lang="en">EN</a></li>
+<li><a href="https://example.com/fr/hello/" hreflang="fr" lang="fr" aria-current="page">FR</a></li>
+</ul>
+</nav>"#
+        );
+        context.insert("page", &json!({"lang": "en", "translations": []}));
+        assert_eq!(render_macro("translations(page=page)", &context), "");
+    }
+
+    #[test]
+    fn can_render_alert_and_picture_macros() {
+        let mut context = Context::new();
+        assert_eq!(
+            render_macro(
+                r#"alert(content="<p>Careful</p>", kind="warning", title="Watch out")"#,
+                &context
+            ),
+            r#"<div class="alert alert-warning" role="alert">
+<p class="alert-title">Watch out</p>
+<p>Careful</p>
+</div>"#
+        );
+        assert_eq!(
+            render_macro(r#"alert(content="<p>FYI</p>")"#, &context),
+            "<div class=\"alert alert-note\" role=\"note\">\n<p>FYI</p>\n</div>"
+        );
+        context.insert(
+            "image",
+            &json!({
+                "url": "https://example.com/processed_images/cat.1234.jpg",
+                "static_path": "static/processed_images/cat.1234.jpg",
+                "width": 800,
+                "height": 600,
+                "orig_width": 1600,
+                "orig_height": 1200,
+                "formats": [{
+                    "format": "webp",
+                    "mime": "image/webp",
+                    "url": "https://example.com/processed_images/cat.1234.webp",
+                    "static_path": "static/processed_images/cat.1234.webp",
+                }],
+            }),
+        );
+        assert_eq!(
+            render_macro(r#"picture(image=image, alt="A <cat>")"#, &context),
+            r#"<picture>
+<source type="image/webp" srcset="https://example.com/processed_images/cat.1234.webp">
+<img src="https://example.com/processed_images/cat.1234.jpg" width="800" height="600" alt="A &lt;cat&gt;" loading="lazy" decoding="async">
+</picture>"#
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Strict templates, for `strict_templates = true`.
+//!
+//! Tera errors on undefined variables, except when they are used as conditions: in an `if`,
+//! with `and`/`or` or with `not`, where they are silently falsy. The templates are made strict
+//! by replacing each of these conditions by a call to a function that looks the variable up
+//! like the rest of Tera does, so it errors if the variable doesn't exist. `is defined` and the
+//! `default` filter keep working as before to handle optional variables.
+
+use std::collections::HashMap;
+
+use libs::tera::ast::{Expr, ExprVal, LogicOperator, Node};
+use libs::tera::{Result as TeraResult, Tera, Value};
+
+/// The function the conditions are replaced by
+const STRICT_CONDITION_FN: &str = "__zola_strict_condition";
+
+/// Same as Tera's truthiness, which isn't public
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|f| f != 0.0 && !f.is_nan()),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+fn strict_condition(args: &HashMap<String, Value>) -> TeraResult<Value> {
+    Ok(Value::Bool(args.get("value").is_some_and(is_truthy)))
+}
+
+/// Makes that expression error if it is a variable that doesn't exist, in a boolean context
+fn make_condition_strict(expr: &mut Expr) {
+    if let ExprVal::Ident(_) = expr.val {
+        let inner = Expr {
+            val: std::mem::replace(&mut expr.val, ExprVal::Bool(false)),
+            negated: false,
+            filters: std::mem::take(&mut expr.filters),
+        };
+        expr.val = ExprVal::FunctionCall(libs::tera::ast::FunctionCall {
+            name: STRICT_CONDITION_FN.to_string(),
+            args: HashMap::from([("value".to_string(), inner)]),
+        });
+    } else {
+        make_expr_strict(expr);
+    }
+}
+
+fn make_expr_strict(expr: &mut Expr) {
+    if expr.negated {
+        if let ExprVal::Ident(_) = expr.val {
+            return make_condition_strict(expr);
+        }
+    }
+    for filter in &mut expr.filters {
+        filter.args.values_mut().for_each(make_expr_strict);
+    }
+    match expr.val {
+        ExprVal::Logic(ref mut logic) => match logic.operator {
+            LogicOperator::And | LogicOperator::Or => {
+                make_condition_strict(&mut logic.lhs);
+                make_condition_strict(&mut logic.rhs);
+            }
+            _ => {
+                make_expr_strict(&mut logic.lhs);
+                make_expr_strict(&mut logic.rhs);
+            }
+        },
+        ExprVal::Math(ref mut math) => {
+            make_expr_strict(&mut math.lhs);
+            make_expr_strict(&mut math.rhs);
+        }
+        ExprVal::In(ref mut in_cond) => {
+            make_expr_strict(&mut in_cond.lhs);
+            make_expr_strict(&mut in_cond.rhs);
+        }
+        ExprVal::Test(ref mut test) => test.args.iter_mut().for_each(make_expr_strict),
+        ExprVal::MacroCall(ref mut call) => call.args.values_mut().for_each(make_expr_strict),
+        ExprVal::FunctionCall(ref mut call) => call.args.values_mut().for_each(make_expr_strict),
+        ExprVal::Array(ref mut values) => values.iter_mut().for_each(make_expr_strict),
+        _ => (),
+    }
+}
+
+fn make_nodes_strict(nodes: &mut [Node]) {
+    for node in nodes {
+        match node {
+            Node::VariableBlock(_, expr) => make_expr_strict(expr),
+            Node::Set(_, set) => make_expr_strict(&mut set.value),
+            Node::MacroDefinition(_, definition, _) => {
+                definition.args.values_mut().flatten().for_each(make_expr_strict);
+                make_nodes_strict(&mut definition.body);
+            }
+            Node::FilterSection(_, section, _) => {
+                section.filter.args.values_mut().for_each(make_expr_strict);
+                make_nodes_strict(&mut section.body);
+            }
+            Node::Block(_, block, _) => make_nodes_strict(&mut block.body),
+            Node::Forloop(_, forloop, _) => {
+                make_expr_strict(&mut forloop.container);
+                make_nodes_strict(&mut forloop.body);
+                if let Some(ref mut body) = forloop.empty_body {
+                    make_nodes_strict(body);
+                }
+            }
+            Node::If(if_node, _) => {
+                for (_, condition, body) in &mut if_node.conditions {
+                    make_condition_strict(condition);
+                    make_nodes_strict(body);
+                }
+                if let Some((_, ref mut body)) = if_node.otherwise {
+                    make_nodes_strict(body);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Makes the templates error on undefined variables used as conditions.
+/// The built-in templates of Zola are left as they are, only the ones loaded from files are
+/// made strict. Calling it again, eg after adding templates, is fine.
+pub fn make_templates_strict(tera: &mut Tera) {
+    tera.register_function(STRICT_CONDITION_FN, strict_condition);
+    for template in tera.templates.values_mut().filter(|t| t.path.is_some()) {
+        make_nodes_strict(&mut template.ast);
+        for definition in template.macros.values_mut() {
+            make_nodes_strict(&mut definition.body);
+        }
+        for block in template.blocks.values_mut() {
+            make_nodes_strict(&mut block.body);
+        }
+        for (_, block) in template.blocks_definitions.values_mut().flatten() {
+            make_nodes_strict(&mut block.body);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libs::tera::Context;
+
+    fn render(template: &str, context: &Context) -> TeraResult<String> {
+        let mut tera = Tera::default();
+        tera.add_raw_template("tpl.html", template).unwrap();
+        // Only the templates coming from files are made strict
+        tera.templates.get_mut("tpl.html").unwrap().path = Some("tpl.html".to_string());
+        make_templates_strict(&mut tera);
+        tera.render("tpl.html", context)
+    }
+
+    #[test]
+    fn errors_on_undefined_conditions() {
+        let mut context = Context::new();
+        context.insert("page", &HashMap::from([("title", "Hello")]));
+
+        for template in [
+            "{% if page.titel %}yes{% endif %}",
+            "{% if not page.titel %}yes{% endif %}",
+            "{% if page.title and page.titel %}yes{% endif %}",
+            "{% if page.titel or page.title %}yes{% endif %}",
+            "{% set x = page.titel or true %}{{ x }}",
+            "{% if page.titel | length %}yes{% endif %}",
+            "{% macro m() %}{% if nope %}yes{% endif %}{% endmacro m %}{{ self::m() }}",
+            "{{ not page.titel }}",
+        ] {
+            let err = render(template, &context).unwrap_err();
+            let mut message = err.to_string();
+            let mut source = std::error::Error::source(&err);
+            while let Some(e) = source {
+                message.push_str(&format!("\n{}", e));
+                source = e.source();
+            }
+            assert!(message.contains("not found in context"), "{}: {}", template, message);
+        }
+    }
+
+    #[test]
+    fn keeps_defined_conditions_working() {
+        let mut context = Context::new();
+        context.insert("page", &HashMap::from([("title", "Hello"), ("empty", "")]));
+
+        for (template, expected) in [
+            ("{% if page.title %}yes{% else %}no{% endif %}", "yes"),
+            ("{% if page.empty %}yes{% else %}no{% endif %}", "no"),
+            ("{% if not page.title %}yes{% else %}no{% endif %}", "no"),
+            ("{% if not page.empty %}yes{% else %}no{% endif %}", "yes"),
+            ("{% if page.title and not page.empty %}yes{% endif %}", "yes"),
+            ("{% if page.titel is defined %}yes{% else %}no{% endif %}", "no"),
+            ("{% if page.titel | default(value=false) %}yes{% else %}no{% endif %}", "no"),
+            ("{% if page.title | length > 3 %}yes{% endif %}", "yes"),
+            ("{{ page.title | upper }}", "HELLO"),
+        ] {
+            assert_eq!(render(template, &context).unwrap(), expected, "{}", template);
+        }
+    }
+}
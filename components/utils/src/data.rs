@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use errors::{bail, Context, Result};
+use libs::csv::Reader;
+use libs::tera::{to_value, Map, Value};
+use libs::{serde_json, serde_yaml, toml};
+
+use crate::de::fix_toml_dates;
+use crate::fs::read_file;
+
+/// The extensions of the files we know how to load as data
+pub const DATA_FILE_EXTENSIONS: [&str; 5] = ["toml", "yaml", "yml", "json", "csv"];
+
+/// The filenames of the data files that can be colocated with an `index.md` file
+pub const PAGE_DATA_FILENAMES: [&str; 3] = ["data.toml", "data.yaml", "data.json"];
+
+/// Returns whether the file at that path can be loaded with `load_data_file`
+pub fn is_data_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| DATA_FILE_EXTENSIONS.contains(&e))
+        .unwrap_or(false)
+}
+
+/// Parses the given string as the given format into a Tera value
+pub fn parse_data(content: &str, format: &str) -> Result<Value> {
+    match format {
+        "toml" => {
+            let value: toml::Value = toml::from_str(content)?;
+            match to_value(value).expect("Got invalid JSON that was valid TOML somehow") {
+                Value::Object(m) => Ok(fix_toml_dates(m)),
+                _ => bail!("Loaded something other than a TOML table"),
+            }
+        }
+        "yaml" | "yml" => Ok(serde_yaml::from_str(content)?),
+        "json" => Ok(serde_json::from_str(content)?),
+        "csv" => parse_csv(content),
+        _ => bail!("Unknown data format `{}`", format),
+    }
+}
+
+/// Parses a CSV string into the same shape `load_data` uses: `{headers: [..], records: [[..]]}`
+fn parse_csv(content: &str) -> Result<Value> {
+    let mut reader = Reader::from_reader(content.as_bytes());
+    let mut csv_map = Map::new();
+
+    let headers = reader.headers().context("Unable to read the CSV header line")?;
+    csv_map.insert(
+        "headers".to_string(),
+        Value::Array(headers.iter().map(|v| Value::String(v.to_string())).collect()),
+    );
+
+    let mut records = Vec::new();
+    for record in reader.records() {
+        let record = record.context("Error encountered when parsing CSV records")?;
+        records.push(Value::Array(record.iter().map(|v| Value::String(v.to_string())).collect()));
+    }
+    csv_map.insert("records".to_string(), Value::Array(records));
+
+    Ok(Value::Object(csv_map))
+}
+
+/// Reads and parses a data file, the format being decided by its extension
+pub fn load_data_file(path: &Path) -> Result<Value> {
+    let format = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let content = read_file(path)?;
+    parse_data(&content, format)
+        .with_context(|| format!("Failed to parse data file {}", path.display()))
+}
+
+/// Loads all the data files found in the given directory into a single object,
+/// keyed by file stem and nested by subdirectory: `data/authors/bob.toml` ends up
+/// being `authors.bob`.
+/// Returns an empty object if the directory does not exist.
+pub fn load_data_dir(dir: &Path) -> Result<Value> {
+    let mut data = Map::new();
+    if !dir.is_dir() {
+        return Ok(Value::Object(data));
+    }
+
+    let mut entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read data directory {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    for path in entries {
+        let is_hidden =
+            path.file_name().map(|n| n.to_string_lossy().starts_with('.')).unwrap_or(true);
+        if is_hidden || !(path.is_dir() || is_data_file(&path)) {
+            continue;
+        }
+
+        let key = path.file_stem().unwrap().to_string_lossy().to_string();
+        let value = if path.is_dir() { load_data_dir(&path)? } else { load_data_file(&path)? };
+        if data.contains_key(&key) {
+            bail!(
+                "Data key `{}` is defined more than once in {}: a file and a directory (or two files) share that name",
+                key,
+                dir.display()
+            );
+        }
+        data.insert(key, value);
+    }
+
+    Ok(Value::Object(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, File};
+    use std::io::Write;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn can_load_data_files() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let toml_path = tmp_dir.path().join("data.toml");
+        File::create(&toml_path).unwrap().write_all(b"a = 1\nd = 2024-01-01").unwrap();
+        let value = load_data_file(&toml_path).unwrap();
+        assert_eq!(value["a"], to_value(1).unwrap());
+        assert_eq!(value["d"], to_value("2024-01-01").unwrap());
+
+        let yaml_path = tmp_dir.path().join("data.yaml");
+        File::create(&yaml_path).unwrap().write_all(b"a: 1").unwrap();
+        assert_eq!(load_data_file(&yaml_path).unwrap()["a"], to_value(1).unwrap());
+
+        let json_path = tmp_dir.path().join("data.json");
+        File::create(&json_path).unwrap().write_all(b"{\"a\": 1}").unwrap();
+        assert_eq!(load_data_file(&json_path).unwrap()["a"], to_value(1).unwrap());
+    }
+
+    #[test]
+    fn can_load_data_directory() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let dir = tmp_dir.path();
+        create_dir_all(dir.join("authors")).unwrap();
+        File::create(dir.join("site.toml")).unwrap().write_all(b"name = 'hello'").unwrap();
+        File::create(dir.join("authors").join("bob.json"))
+            .unwrap()
+            .write_all(b"{\"age\": 42}")
+            .unwrap();
+        File::create(dir.join("prices.csv")).unwrap().write_all(b"item,price\na,1").unwrap();
+        File::create(dir.join("notes.txt")).unwrap().write_all(b"ignored").unwrap();
+
+        let data = load_data_dir(dir).unwrap();
+        assert_eq!(data["site"]["name"], to_value("hello").unwrap());
+        assert_eq!(data["authors"]["bob"]["age"], to_value(42).unwrap());
+        assert_eq!(data["prices"]["headers"], to_value(["item", "price"]).unwrap());
+        assert_eq!(data["prices"]["records"], to_value([["a", "1"]]).unwrap());
+        assert!(data.get("notes").is_none());
+    }
+
+    #[test]
+    fn errors_on_data_file_and_directory_with_same_stem() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let dir = tmp_dir.path();
+        create_dir_all(dir.join("authors")).unwrap();
+        File::create(dir.join("authors.toml")).unwrap().write_all(b"a = 1").unwrap();
+        File::create(dir.join("authors").join("bob.toml")).unwrap().write_all(b"a = 1").unwrap();
+
+        let err = load_data_dir(dir).unwrap_err();
+        assert!(err.to_string().contains("`authors`"));
+    }
+
+    #[test]
+    fn missing_data_directory_is_empty() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let data = load_data_dir(&tmp_dir.path().join("data")).unwrap();
+        assert_eq!(data, Value::Object(Map::new()));
+    }
+
+    #[test]
+    fn errors_name_the_data_file() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let path = tmp_dir.path().join("data.json");
+        File::create(&path).unwrap().write_all(b"{\"a\": ").unwrap();
+        let err = load_data_file(&path).unwrap_err();
+        assert!(err.to_string().contains("data.json"));
+    }
+}
@@ -7,9 +7,12 @@ use libs::time::format_description::well_known::Rfc3339;
 use libs::toml;
 use serde::{Deserialize, Deserializer};
 
+/// Parses a YAML 1.1 timestamp, also accepting some forms found in the wild: offsets without a
+/// colon (`-0530`), a `UTC` or `GMT` suffix instead of `Z` and more than 9 fractional digits,
+/// which are truncated to nanoseconds.
 pub fn parse_yaml_datetime(date_string: &str) -> Result<time::OffsetDateTime> {
     // See https://github.com/getzola/zola/issues/2071#issuecomment-1530610650
-    let re = Regex::new(r#"^"?(?P<year>[0-9]{4})-(?P<month>[0-9][0-9]?)-(?P<day>[0-9][0-9]?)(?:(?:[Tt]|[ \t]+)(?P<hour>[0-9][0-9]?):(?P<minute>[0-9]{2}):(?P<second>[0-9]{2})(?P<fraction>\.[0-9]{0,9})?[ \t]*(?:(?P<utc>Z)|(?P<offset>(?P<offset_hour>[-+][0-9][0-9]?)(?::(?P<offset_minute>[0-9][0-9]))?))?)?"?$"#).unwrap();
+    let re = Regex::new(r#"^"?(?P<year>[0-9]{4})-(?P<month>[0-9][0-9]?)-(?P<day>[0-9][0-9]?)(?:(?:[Tt]|[ \t]+)(?P<hour>[0-9][0-9]?):(?P<minute>[0-9]{2}):(?P<second>[0-9]{2})(?P<fraction>\.[0-9]*)?[ \t]*(?:(?P<utc>Z|UTC|GMT)|(?P<offset>(?P<compact_hour>[-+][0-9]{2})(?P<compact_minute>[0-9]{2})|(?P<offset_hour>[-+][0-9][0-9]?)(?::(?P<offset_minute>[0-9][0-9]))?))?)?"?$"#).unwrap();
     let captures = if let Some(captures_) = re.captures(date_string) {
         Ok(captures_)
     } else {
@@ -21,25 +24,34 @@ pub fn parse_yaml_datetime(date_string: &str) -> Result<time::OffsetDateTime> {
     let hour = if let Some(hour_) = captures.name("hour") { hour_.as_str() } else { "0" };
     let minute = if let Some(minute_) = captures.name("minute") { minute_.as_str() } else { "0" };
     let second = if let Some(second_) = captures.name("second") { second_.as_str() } else { "0" };
-    let fraction_raw =
-        if let Some(fraction_) = captures.name("fraction") { fraction_.as_str() } else { "" };
-    let fraction_intermediate = fraction_raw.trim_end_matches("0");
-    //
-    // Prepare for eventual conversion into nanoseconds
-    let fraction = if fraction_intermediate.len() > 0 { fraction_intermediate } else { "0" };
-    let maybe_timezone_hour = captures.name("offset_hour");
-    let maybe_timezone_minute = captures.name("offset_minute");
+    // Only nanoseconds can be represented: the digits after the 9th are dropped
+    let mut fraction: String = captures
+        .name("fraction")
+        .map(|f| f.as_str().trim_start_matches('.'))
+        .unwrap_or_default()
+        .chars()
+        .take(9)
+        .collect();
+    while fraction.len() < 9 {
+        fraction.push('0');
+    }
+    let maybe_timezone_hour =
+        captures.name("compact_hour").or_else(|| captures.name("offset_hour"));
+    let maybe_timezone_minute =
+        captures.name("compact_minute").or_else(|| captures.name("offset_minute"));
 
     let mut offset_datetime = time::OffsetDateTime::UNIX_EPOCH;
 
     if let Some(hour) = maybe_timezone_hour {
         let minute_str =
             if let Some(minute_) = maybe_timezone_minute { minute_.as_str() } else { "0" };
-        offset_datetime = offset_datetime.to_offset(time::UtcOffset::from_hms(
-            hour.as_str().parse()?,
-            minute_str.parse()?,
-            0,
-        )?);
+        let hour: i8 = hour.as_str().parse()?;
+        let mut minute: i8 = minute_str.parse()?;
+        // The minutes of `-05:30` are behind UTC as well, which `-00:30` only tells with its sign
+        if captures.name("offset").is_some_and(|o| o.as_str().starts_with('-')) {
+            minute = -minute;
+        }
+        offset_datetime = offset_datetime.to_offset(time::UtcOffset::from_hms(hour, minute, 0)?);
     }
 
     // Free parse unwraps since we know everything is a digit courtesy of prior regex.
@@ -50,12 +62,21 @@ pub fn parse_yaml_datetime(date_string: &str) -> Result<time::OffsetDateTime> {
         .replace_hour(hour.parse().unwrap())?
         .replace_minute(minute.parse().unwrap())?
         .replace_second(second.parse().unwrap())?
-        .replace_nanosecond((fraction.parse::<f64>().unwrap_or(0.0) * 1_000_000_000.0) as u32)?)
+        .replace_nanosecond(fraction.parse().unwrap())?)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MaybeDatetime {
+    Datetime(toml::value::Datetime),
+    Timestamp(i64),
+    String(String),
 }
 
 /// Used as an attribute when we want to convert from TOML to a string date
 /// If a TOML datetime isn't present, it will accept a string and push it through
 /// TOML's date time parser to ensure only valid dates are accepted.
+/// An integer is taken as a unix timestamp.
 /// Inspired by this proposal: <https://github.com/alexcrichton/toml-rs/issues/269>
 pub fn from_unknown_datetime<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
 where
@@ -64,15 +85,11 @@ where
     use serde::de::Error;
     use std::str::FromStr;
 
-    #[derive(Deserialize)]
-    #[serde(untagged)]
-    enum MaybeDatetime {
-        Datetime(toml::value::Datetime),
-        String(String),
-    }
-
     match MaybeDatetime::deserialize(deserializer)? {
         MaybeDatetime::Datetime(d) => Ok(Some(d.to_string())),
+        MaybeDatetime::Timestamp(t) => time::OffsetDateTime::from_unix_timestamp(t)
+            .map(|d| Some(d.format(&Rfc3339).unwrap()))
+            .map_err(|_| D::Error::custom(format!("Unix timestamp {} is out of range", t))),
         MaybeDatetime::String(s) => {
             if let Ok(d) = toml::value::Datetime::from_str(&s) {
                 Ok(Some(d.to_string()))
@@ -89,6 +106,19 @@ where
     }
 }
 
+/// Used as an attribute for a time of the day, given as a TOML local time or as a string.
+/// It is only validated once we know which date it goes with.
+pub fn from_unknown_time<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match MaybeDatetime::deserialize(deserializer)? {
+        MaybeDatetime::Datetime(d) => Ok(Some(d.to_string())),
+        MaybeDatetime::Timestamp(t) => Ok(Some(t.to_string())),
+        MaybeDatetime::String(s) => Ok(Some(s)),
+    }
+}
+
 /// Returns key/value for a converted date from TOML.
 /// If the table itself is the TOML struct, only return its value without the key
 fn convert_toml_date(table: Map<String, Value>) -> Value {
@@ -174,6 +204,54 @@ mod tests {
         assert_eq!(parse_yaml_datetime(date).unwrap(), datetime!(2002-12-14 00:00:00.000 +00:00));
     }
 
+    #[test]
+    fn yaml_real_world_timestamps_pass() {
+        let compact_offset = "2001-12-14 21:59:43.10 -0530";
+        let negative_half_hour = "2001-12-14T21:59:43-00:30";
+        let utc_suffix = "2001-12-15 02:59:43 UTC";
+        let gmt_suffix = "2001-12-15T02:59:43.5GMT";
+        let long_fraction = "2001-12-15T02:59:43.1234567891234Z";
+        assert_eq!(
+            parse_yaml_datetime(compact_offset).unwrap(),
+            datetime!(2001-12-14 21:59:43.100 -05:30)
+        );
+        assert_eq!(
+            parse_yaml_datetime(negative_half_hour).unwrap(),
+            datetime!(2001-12-14 21:59:43 -00:30)
+        );
+        assert_eq!(parse_yaml_datetime(utc_suffix).unwrap(), datetime!(2001-12-15 02:59:43 +00:00));
+        assert_eq!(
+            parse_yaml_datetime(gmt_suffix).unwrap(),
+            datetime!(2001-12-15 02:59:43.5 +00:00)
+        );
+        assert_eq!(
+            parse_yaml_datetime(long_fraction).unwrap(),
+            datetime!(2001-12-15 02:59:43.123456789 +00:00)
+        );
+    }
+
+    #[test]
+    fn yaml_ambiguous_timestamps_fail() {
+        // Is it 1901 or 2001?
+        let two_digit_year = "01-12-15";
+        assert!(parse_yaml_datetime(two_digit_year).is_err());
+
+        let two_digit_year = "01-12-15T02:59:43Z";
+        assert!(parse_yaml_datetime(two_digit_year).is_err());
+
+        let too_many_offset_digits = "2001-12-15 02:59:43 -05300";
+        assert!(parse_yaml_datetime(too_many_offset_digits).is_err());
+
+        let three_offset_digits = "2001-12-15 02:59:43 -530";
+        assert!(parse_yaml_datetime(three_offset_digits).is_err());
+
+        let named_timezone = "2001-12-15 02:59:43 EST";
+        assert!(parse_yaml_datetime(named_timezone).is_err());
+
+        let utc_and_offset = "2001-12-15 02:59:43 UTC+05:00";
+        assert!(parse_yaml_datetime(utc_and_offset).is_err());
+    }
+
     #[test]
     fn yaml_spec_invalid_dates_fail() {
         let invalid_month = "2001-13-15";
@@ -0,0 +1,127 @@
+//! Encryption of the content of the password protected pages, in a way that can be decrypted
+//! in the browser with the Web Crypto API: AES-256-GCM with a key derived from the password
+//! with PBKDF2-HMAC-SHA256.
+
+use std::num::NonZeroU32;
+
+use libs::base64::engine::{general_purpose::STANDARD as standard_b64, Engine};
+use libs::ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use libs::ring::hmac;
+use libs::ring::pbkdf2::{self, PBKDF2_HMAC_SHA256};
+use libs::ring::rand::{SecureRandom, SystemRandom};
+use serde::Serialize;
+
+use errors::{anyhow, Result};
+
+/// How many PBKDF2 iterations are used to derive the key from the password
+pub const PBKDF2_ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+
+/// Some encrypted content and what is needed to decrypt it with the password, base64 encoded.
+/// The ciphertext ends with the authentication tag, like the Web Crypto API expects it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Encrypted {
+    pub ciphertext: String,
+    pub salt: String,
+    pub iv: String,
+    pub iterations: u32,
+}
+
+fn derive_key(password: &str, salt: &[u8], iterations: u32) -> LessSafeKey {
+    let mut key = [0; 32];
+    pbkdf2::derive(
+        PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(iterations).unwrap(),
+        salt,
+        password.as_bytes(),
+        &mut key,
+    );
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &key).unwrap())
+}
+
+/// Encrypts the text with the password, with a random salt and IV. For reproducible builds,
+/// a `seed` can be given instead: the salt and IV are then an HMAC of the seed and the text with
+/// the password, the same on every build but still different as soon as the text changes so
+/// an IV is never used twice with the same key.
+pub fn encrypt(plaintext: &str, password: &str, seed: Option<&str>) -> Result<Encrypted> {
+    let mut salt = [0; SALT_LEN];
+    let mut iv = [0; NONCE_LEN];
+    match seed {
+        Some(seed) => {
+            let key = hmac::Key::new(hmac::HMAC_SHA256, password.as_bytes());
+            let mut context = hmac::Context::with_key(&key);
+            context.update(seed.as_bytes());
+            context.update(&[0]);
+            context.update(plaintext.as_bytes());
+            let tag = context.sign();
+            salt.copy_from_slice(&tag.as_ref()[..SALT_LEN]);
+            iv.copy_from_slice(&tag.as_ref()[SALT_LEN..SALT_LEN + NONCE_LEN]);
+        }
+        None => {
+            let rng = SystemRandom::new();
+            rng.fill(&mut salt).map_err(|_| anyhow!("Failed to generate a salt"))?;
+            rng.fill(&mut iv).map_err(|_| anyhow!("Failed to generate an IV"))?;
+        }
+    }
+
+    let key = derive_key(password, &salt, PBKDF2_ITERATIONS);
+    let mut in_out = plaintext.as_bytes().to_vec();
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(iv), Aad::empty(), &mut in_out)
+        .map_err(|_| anyhow!("Failed to encrypt the content"))?;
+
+    Ok(Encrypted {
+        ciphertext: standard_b64.encode(in_out),
+        salt: standard_b64.encode(salt),
+        iv: standard_b64.encode(iv),
+        iterations: PBKDF2_ITERATIONS,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    /// What the decryption script of the protected pages does
+    fn decrypt(encrypted: &Encrypted, password: &str) -> Option<String> {
+        let salt = standard_b64.decode(&encrypted.salt).unwrap();
+        let iv: [u8; NONCE_LEN] = standard_b64.decode(&encrypted.iv).unwrap().try_into().unwrap();
+        let mut in_out = standard_b64.decode(&encrypted.ciphertext).unwrap();
+        let key = derive_key(password, &salt, encrypted.iterations);
+        let plaintext =
+            key.open_in_place(Nonce::assume_unique_for_key(iv), Aad::empty(), &mut in_out).ok()?;
+        Some(String::from_utf8(plaintext.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn can_encrypt_and_decrypt() {
+        let encrypted = encrypt("<p>Meeting notes</p>", "hunter2", None).unwrap();
+        assert!(!encrypted.ciphertext.contains("Meeting"));
+        assert_eq!(decrypt(&encrypted, "hunter2").as_deref(), Some("<p>Meeting notes</p>"));
+        assert_eq!(decrypt(&encrypted, "hunter3"), None);
+    }
+
+    #[test]
+    fn uses_a_different_salt_and_iv_each_time() {
+        let a = encrypt("same", "same", None).unwrap();
+        let b = encrypt("same", "same", None).unwrap();
+        assert_ne!(a.salt, b.salt);
+        assert_ne!(a.iv, b.iv);
+        assert_ne!(a.ciphertext, b.ciphertext);
+    }
+
+    #[test]
+    fn can_encrypt_the_same_way_with_a_seed() {
+        let a = encrypt("same", "same", Some("secret.md")).unwrap();
+        assert_eq!(a, encrypt("same", "same", Some("secret.md")).unwrap());
+        assert_eq!(decrypt(&a, "same").as_deref(), Some("same"));
+
+        for b in [
+            encrypt("other", "same", Some("secret.md")).unwrap(),
+            encrypt("same", "same", Some("other.md")).unwrap(),
+        ] {
+            assert_ne!(a.salt, b.salt);
+            assert_ne!(a.iv, b.iv);
+        }
+    }
+}
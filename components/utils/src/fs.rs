@@ -1,22 +1,155 @@
 use libs::filetime::{set_file_mtime, FileTime};
 use libs::globset::GlobSet;
-use libs::walkdir::WalkDir;
+use libs::rayon::prelude::*;
+use libs::walkdir::{self, DirEntry, FilterEntry, WalkDir};
 use std::fs::{copy, create_dir_all, metadata, remove_dir_all, remove_file, File};
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf, Prefix};
+use std::sync::Once;
 use std::time::SystemTime;
 
-use errors::{Context, Result};
+use serde::{Deserialize, Serialize};
 
-pub fn is_path_in_directory(parent: &Path, path: &Path) -> Result<bool> {
-    let canonical_path = path
-        .canonicalize()
-        .with_context(|| format!("Failed to canonicalize {}", path.display()))?;
-    let canonical_parent = parent
-        .canonicalize()
-        .with_context(|| format!("Failed to canonicalize {}", parent.display()))?;
+use errors::{bail, Context, Result};
 
-    Ok(canonical_path.starts_with(canonical_parent))
+use crate::globs::IgnoreRules;
+
+/// Where a path is relative to a directory, as returned by `is_path_in_directory`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PathLocation {
+    Inside,
+    Outside,
+    /// The directory itself does not exist so we cannot tell
+    NonexistentParent,
+}
+
+impl PathLocation {
+    pub fn is_inside(self) -> bool {
+        self == PathLocation::Inside
+    }
+}
+
+/// Checks whether `path` is located inside the `parent` directory. `path` does not need to exist.
+/// A path is inside if it is located in `parent`, even through a symlink pointing outside of it,
+/// or if it resolves to somewhere in `parent`, eg when `parent` itself is a symlink.
+/// `..` components going above `parent` put the path outside of it.
+pub fn is_path_in_directory(parent: &Path, path: &Path) -> Result<PathLocation> {
+    if !parent.exists() {
+        return Ok(PathLocation::NonexistentParent);
+    }
+
+    let canonical_parent = canonicalize_existing_ancestor(parent)?.unwrap();
+    let lexical_path = normalize_path(path)?;
+    if lexical_path.starts_with(normalize_path(parent)?)
+        || lexical_path.starts_with(&canonical_parent)
+    {
+        return Ok(PathLocation::Inside);
+    }
+
+    match canonicalize_existing_ancestor(path)? {
+        Some(p) if p.starts_with(&canonical_parent) => Ok(PathLocation::Inside),
+        _ => Ok(PathLocation::Outside),
+    }
+}
+
+/// Canonicalizes the deepest ancestor of the path that exists and lexically joins the remaining
+/// components to it.
+/// Returns `None` if a `..` in those remaining components escapes the canonicalized ancestor.
+fn canonicalize_existing_ancestor(path: &Path) -> Result<Option<PathBuf>> {
+    let path = make_absolute(path)?;
+    let existing = path.ancestors().find(|p| p.exists()).unwrap_or(&path);
+    let mut canonical = strip_verbatim_prefix(
+        existing
+            .canonicalize()
+            .with_context(|| format!("Failed to canonicalize {}", existing.display()))?,
+    );
+
+    let mut depth = 0;
+    for component in path.strip_prefix(existing).unwrap().components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir if depth == 0 => return Ok(None),
+            Component::ParentDir => {
+                canonical.pop();
+                depth -= 1;
+            }
+            c => {
+                canonical.push(c);
+                depth += 1;
+            }
+        }
+    }
+    Ok(Some(canonical))
+}
+
+/// `canonicalize` returns verbatim paths on Windows (`\\?\C:\...`), which never start with
+/// a non-verbatim one so we turn them back into regular paths.
+fn strip_verbatim_prefix(path: PathBuf) -> PathBuf {
+    let mut components = path.components();
+    let stripped = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::VerbatimDisk(letter) => format!("{}:\\", letter as char),
+            Prefix::VerbatimUNC(server, share) => {
+                format!("\\\\{}\\{}\\", server.to_string_lossy(), share.to_string_lossy())
+            }
+            _ => return path,
+        },
+        _ => return path,
+    };
+    let mut stripped = PathBuf::from(stripped);
+    stripped.extend(components.filter(|c| *c != Component::RootDir));
+    stripped
+}
+
+fn make_absolute(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_path_buf())
+    } else {
+        Ok(std::env::current_dir().context("Failed to get the current directory")?.join(path))
+    }
+}
+
+/// Makes the path absolute and resolves `.` and `..` components without touching the filesystem,
+/// so symlinks are not followed
+fn normalize_path(path: &Path) -> Result<PathBuf> {
+    let path = make_absolute(path)?;
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            c => normalized.push(c),
+        }
+    }
+    Ok(normalized)
+}
+
+/// Walks the directory given to the `WalkDir`, following symlinks to files and directories.
+/// We keep track of the canonical paths of the directories we are currently in and do not
+/// enter a directory a second time, so a symlink pointing to one of its ancestors cannot
+/// make the walk loop forever.
+pub fn walk_dir_following_links(
+    walker: WalkDir,
+) -> FilterEntry<walkdir::IntoIter, impl FnMut(&DirEntry) -> bool> {
+    let mut ancestors: Vec<PathBuf> = Vec::new();
+    walker.follow_links(true).into_iter().filter_entry(move |entry| {
+        if !entry.file_type().is_dir() {
+            return true;
+        }
+        let canonical_path = match entry.path().canonicalize() {
+            Ok(p) => p,
+            Err(_) => return false,
+        };
+        ancestors.truncate(entry.depth());
+        if ancestors.contains(&canonical_path) {
+            return false;
+        }
+        ancestors.push(canonical_path);
+        true
+    })
 }
 
 /// Creates the parent of a directory, if needed.
@@ -47,77 +180,397 @@ pub fn create_directory(path: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Return the content of a file, with error handling added
-pub fn read_file(path: &Path) -> Result<String> {
-    let mut content = String::new();
+/// How many bytes from the start of a file are looked at to tell whether it is binary, like git
+const BINARY_SNIFF_LEN: usize = 8000;
+const UTF16_LE_BOM: [u8; 2] = [0xFF, 0xFE];
+const UTF16_BE_BOM: [u8; 2] = [0xFE, 0xFF];
+
+/// Whether these bytes from the start of a file look binary: they contain a NUL byte, which
+/// text files never do unless they are UTF-16
+fn looks_binary(bytes: &[u8]) -> bool {
+    let start = &bytes[..bytes.len().min(BINARY_SNIFF_LEN)];
+    !start.starts_with(&UTF16_LE_BOM) && !start.starts_with(&UTF16_BE_BOM) && start.contains(&0)
+}
+
+/// Whether the file looks like a binary file rather than a text one.
+/// Files that cannot be read are not considered binary so reading them errors later on.
+pub fn is_binary_file(path: &Path) -> bool {
+    let mut start = Vec::with_capacity(BINARY_SNIFF_LEN);
+    match File::open(path) {
+        Ok(file) => {
+            file.take(BINARY_SNIFF_LEN as u64).read_to_end(&mut start).is_ok()
+                && looks_binary(&start)
+        }
+        Err(_) => false,
+    }
+}
+
+fn decode_utf16(bytes: &[u8], big_endian: bool) -> Option<String> {
+    let chunks = bytes.chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+    let units = chunks.map(|c| {
+        if big_endian {
+            u16::from_be_bytes([c[0], c[1]])
+        } else {
+            u16::from_le_bytes([c[0], c[1]])
+        }
+    });
+    char::decode_utf16(units).collect::<std::result::Result<String, _>>().ok()
+}
+
+/// What bytes that couldn't be decoded probably are, for the error message
+fn guess_encoding(bytes: &[u8]) -> &'static str {
+    let start = &bytes[..bytes.len().min(BINARY_SNIFF_LEN)];
+    let (even_nuls, odd_nuls) =
+        start.iter().enumerate().filter(|(_, b)| **b == 0).fold((0, 0), |(even, odd), (i, _)| {
+            if i % 2 == 0 {
+                (even + 1, odd)
+            } else {
+                (even, odd + 1)
+            }
+        });
+    // Mostly ASCII text in UTF-16 has a NUL byte in every character
+    if odd_nuls > start.len() / 4 && even_nuls == 0 {
+        "UTF-16LE without a BOM"
+    } else if even_nuls > start.len() / 4 && odd_nuls == 0 {
+        "UTF-16BE without a BOM"
+    } else {
+        "a binary file"
+    }
+}
+
+/// How a text file was encoded
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Encoding {
+    Utf8,
+    Utf16,
+    Latin1,
+}
+
+/// Decodes the content of a text file: UTF-8, UTF-16 with a BOM in either endianness or,
+/// as a last resort, Latin-1. Returns what the content looks like if it is none of them.
+fn decode_text(bytes: Vec<u8>) -> std::result::Result<(String, Encoding), &'static str> {
+    if let Some(rest) = bytes.strip_prefix(&UTF16_LE_BOM) {
+        return decode_utf16(rest, false).map(|c| (c, Encoding::Utf16)).ok_or("invalid UTF-16LE");
+    }
+    if let Some(rest) = bytes.strip_prefix(&UTF16_BE_BOM) {
+        return decode_utf16(rest, true).map(|c| (c, Encoding::Utf16)).ok_or("invalid UTF-16BE");
+    }
+
+    match String::from_utf8(bytes) {
+        Ok(mut content) => {
+            // Remove utf-8 BOM if any.
+            if content.starts_with('\u{feff}') {
+                content.drain(..3);
+            }
+            Ok((content, Encoding::Utf8))
+        }
+        Err(e) => {
+            let bytes = e.into_bytes();
+            if bytes.contains(&0) {
+                Err(guess_encoding(&bytes))
+            } else {
+                // Every byte is a valid Latin-1 character
+                Ok((bytes.into_iter().map(char::from).collect(), Encoding::Latin1))
+            }
+        }
+    }
+}
+
+fn read_bytes(path: &Path) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
     File::open(path)
         .with_context(|| format!("Failed to open file {}", path.display()))?
-        .read_to_string(&mut content)?;
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("Failed to read file {}", path.display()))?;
+    Ok(bytes)
+}
 
-    // Remove utf-8 BOM if any.
-    if content.starts_with('\u{feff}') {
-        content.drain(..3);
+/// Return the content of a file, with error handling added.
+/// Files in UTF-16 with a BOM are transcoded to UTF-8. Anything else that isn't valid UTF-8 is
+/// an error, see `read_content_file` for the content.
+pub fn read_file(path: &Path) -> Result<String> {
+    match decode_text(read_bytes(path)?) {
+        Ok((content, Encoding::Utf8 | Encoding::Utf16)) => Ok(content),
+        Ok((_, Encoding::Latin1)) => bail!(
+            "Failed to read file {}: it is not valid UTF-8. Convert it to UTF-8.",
+            path.display()
+        ),
+        Err(guess) => bail!(
+            "Failed to read file {}: it is not text in UTF-8 or UTF-16 and looks like {}. Convert it to UTF-8.",
+            path.display(),
+            guess
+        ),
     }
+}
 
-    Ok(content)
+/// Same as `read_file` for the pages and sections of the `content` directory, which can also be
+/// in Latin-1 as they are often imported from older tools. A warning is shown when a file is
+/// read as Latin-1: a UTF-8 file with a bad byte would be too, with its accents garbled.
+pub fn read_content_file(path: &Path) -> Result<String> {
+    match decode_text(read_bytes(path)?) {
+        Ok((content, Encoding::Latin1)) => {
+            console::warn(&format!(
+                "{} is not valid UTF-8, it was read as Latin-1. Convert it to UTF-8 if its accents look wrong.",
+                path.display()
+            ));
+            Ok(content)
+        }
+        Ok((content, _)) => Ok(content),
+        Err(guess) => bail!(
+            "Failed to read file {}: it is not text in UTF-8, UTF-16 or Latin-1 and looks like {}. \
+            Convert it to UTF-8 or, if Zola shouldn't read it, add it to `ignored_content` in the config.",
+            path.display(),
+            guess
+        ),
+    }
+}
+
+/// How files are put in the output directory
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CopyMode {
+    /// Regular copy, skipped if the file is already there with the same mtime and size
+    #[default]
+    Copy,
+    /// Hard link the file, falling back to a copy if the output is on another filesystem
+    Hardlink,
+    /// Clone the file with copy-on-write if the filesystem supports it, copy otherwise
+    ReflinkOrCopy,
 }
 
 /// Copy a file but takes into account where to start the copy as
 /// there might be folders we need to create on the way.
-pub fn copy_file(src: &Path, dest: &Path, base_path: &Path, hard_link: bool) -> Result<()> {
+pub fn copy_file(src: &Path, dest: &Path, base_path: &Path, copy_mode: CopyMode) -> Result<()> {
     let relative_path = src.strip_prefix(base_path).unwrap();
     let target_path = dest.join(relative_path);
 
     create_parent(&target_path)?;
-    copy_file_if_needed(src, &target_path, hard_link)
+    copy_file_if_needed(src, &target_path, copy_mode)
 }
 
-/// No copy occurs if all of the following conditions are satisfied:
+/// Puts `src` at `dest` according to the copy mode.
+/// When copying, no copy occurs if all of the following conditions are satisfied:
 /// 1. A file with the same name already exists in the dest path.
 /// 2. Its modification timestamp is identical to that of the src file.
 /// 3. Its filesize is identical to that of the src file.
-pub fn copy_file_if_needed(src: &Path, dest: &Path, hard_link: bool) -> Result<()> {
-    create_parent(&dest)?;
+pub fn copy_file_if_needed(src: &Path, dest: &Path, copy_mode: CopyMode) -> Result<()> {
+    put_file(src, dest, copy_mode).map(|_| ())
+}
+
+/// What `put_file` did with the file
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum PutOutcome {
+    Copied,
+    /// Hard linked or cloned, no data was written
+    Linked,
+    Skipped,
+}
+
+fn put_file(src: &Path, dest: &Path, copy_mode: CopyMode) -> Result<PutOutcome> {
+    create_parent(dest)?;
 
-    if hard_link {
-        if dest.exists() {
-            std::fs::remove_file(dest)
-                .with_context(|| format!("Error removing file: {:?}", dest))?;
+    match copy_mode {
+        CopyMode::Copy => copy_if_changed(src, dest),
+        CopyMode::Hardlink => {
+            hard_link_or_copy(src, dest, |src, dest| std::fs::hard_link(src, dest))
         }
-        std::fs::hard_link(src, dest)
-            .with_context(|| format!("Error hard linking file, src: {:?}, dst: {:?}", src, dest))?;
-    } else {
-        let src_metadata = metadata(src)
-            .with_context(|| format!("Failed to get metadata of {}", src.display()))?;
-        let src_mtime = FileTime::from_last_modification_time(&src_metadata);
-        if Path::new(&dest).is_file() {
-            let target_metadata = metadata(dest)?;
-            let target_mtime = FileTime::from_last_modification_time(&target_metadata);
-            if !(src_mtime == target_mtime && src_metadata.len() == target_metadata.len()) {
-                copy(src, dest).with_context(|| {
-                    format!("Was not able to copy file {} to {}", src.display(), dest.display())
-                })?;
+        CopyMode::ReflinkOrCopy => {
+            // Left by a previous hard linked build: writing to it would write to the source
+            if is_same_file(src, dest) {
+                remove_file(dest).with_context(|| format!("Error removing file: {:?}", dest))?;
+            }
+            if reflink(src, dest).is_ok() {
+                let src_mtime = FileTime::from_last_modification_time(&metadata(src)?);
                 set_file_mtime(dest, src_mtime)?;
+                Ok(PutOutcome::Linked)
+            } else {
+                copy_if_changed(src, dest)
             }
-        } else {
-            copy(src, dest).with_context(|| {
-                format!("Was not able to copy directory {} to {}", src.display(), dest.display())
-            })?;
-            set_file_mtime(dest, src_mtime)?;
         }
     }
+}
+
+fn copy_if_changed(src: &Path, dest: &Path) -> Result<PutOutcome> {
+    let src_metadata =
+        metadata(src).with_context(|| format!("Failed to get metadata of {}", src.display()))?;
+    let src_mtime = FileTime::from_last_modification_time(&src_metadata);
+    if dest.is_file() {
+        let target_metadata = metadata(dest)?;
+        let target_mtime = FileTime::from_last_modification_time(&target_metadata);
+        if src_mtime == target_mtime && src_metadata.len() == target_metadata.len() {
+            return Ok(PutOutcome::Skipped);
+        }
+    }
+
+    copy(src, dest).with_context(|| {
+        format!("Was not able to copy file {} to {}", src.display(), dest.display())
+    })?;
+    set_file_mtime(dest, src_mtime)?;
+    Ok(PutOutcome::Copied)
+}
+
+static CROSS_DEVICE_WARNING: Once = Once::new();
+
+/// Hard links `src` to `dest` using the given `link` function, replacing `dest` if it is not
+/// already a link to the same file.
+/// If the output directory is on another filesystem, we copy the file instead.
+fn hard_link_or_copy<F>(src: &Path, dest: &Path, link: F) -> Result<PutOutcome>
+where
+    F: Fn(&Path, &Path) -> std::io::Result<()>,
+{
+    if dest.exists() {
+        if is_same_file(src, dest) {
+            return Ok(PutOutcome::Skipped);
+        }
+        remove_file(dest).with_context(|| format!("Error removing file: {:?}", dest))?;
+    }
+
+    match link(src, dest) {
+        Ok(_) => Ok(PutOutcome::Linked),
+        Err(e) if is_cross_device_error(&e) => {
+            CROSS_DEVICE_WARNING.call_once(|| {
+                console::warn(
+                    "The output directory is on a different filesystem, files will be copied instead of hard linked.",
+                );
+            });
+            copy_if_changed(src, dest)
+        }
+        Err(e) => Err(e)
+            .with_context(|| format!("Error hard linking file, src: {:?}, dst: {:?}", src, dest)),
+    }
+}
+
+#[cfg(unix)]
+fn is_cross_device_error(e: &std::io::Error) -> bool {
+    e.raw_os_error() == Some(libs::libc::EXDEV)
+}
+
+#[cfg(windows)]
+fn is_cross_device_error(e: &std::io::Error) -> bool {
+    // ERROR_NOT_SAME_DEVICE
+    e.raw_os_error() == Some(17)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_cross_device_error(_: &std::io::Error) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (metadata(a), metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_same_file(_: &Path, _: &Path) -> bool {
+    false
+}
+
+/// Creates `dest` as a copy-on-write clone of `src`, replacing it if it exists.
+#[cfg(target_os = "linux")]
+fn reflink(src: &Path, dest: &Path) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let src_file = File::open(src)?;
+    // Never write through an existing file, it could be a link to another one
+    match remove_file(dest) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => (),
+    }
+    let dest_file = std::fs::OpenOptions::new().write(true).create_new(true).open(dest)?;
+    // SAFETY: both file descriptors are valid for the duration of the call
+    let ret = unsafe {
+        libs::libc::ioctl(dest_file.as_raw_fd(), libs::libc::FICLONE as _, src_file.as_raw_fd())
+    };
+    if ret == -1 {
+        let err = std::io::Error::last_os_error();
+        drop(dest_file);
+        let _ = remove_file(dest);
+        return Err(err);
+    }
     Ok(())
 }
 
+/// Creates `dest` as a copy-on-write clone of `src`, replacing it if it exists.
+#[cfg(target_os = "macos")]
+fn reflink(src: &Path, dest: &Path) -> std::io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let to_cstring = |p: &Path| {
+        CString::new(p.as_os_str().as_bytes())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+    };
+    let (src_c, dest_c) = (to_cstring(src)?, to_cstring(dest)?);
+    if dest.exists() {
+        remove_file(dest)?;
+    }
+    // SAFETY: both paths are valid NUL-terminated strings
+    if unsafe { libs::libc::clonefile(src_c.as_ptr(), dest_c.as_ptr(), 0) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn reflink(_: &Path, _: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "reflinks are not supported"))
+}
+
+/// What happened when copying a directory with `copy_directory`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CopySummary {
+    /// How many files were copied, linked or cloned to the destination
+    pub copied: usize,
+    /// How many files were already up to date in the destination
+    pub skipped: usize,
+    /// How many bytes were written. Linked or cloned files do not count
+    pub bytes_written: u64,
+}
+
+impl CopySummary {
+    pub fn merge(&mut self, other: CopySummary) {
+        self.copied += other.copied;
+        self.skipped += other.skipped;
+        self.bytes_written += other.bytes_written;
+    }
+}
+
+impl std::fmt::Display for CopySummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} files copied, {} unchanged, {} bytes written",
+            self.copied, self.skipped, self.bytes_written
+        )
+    }
+}
+
+/// Copies all the files of `src` into `dest`, in parallel.
+/// The directories matching the ignore rules are not walked at all.
+/// Errors are collected so every file that could not be copied gets reported, not just the first one.
 pub fn copy_directory(
     src: &Path,
     dest: &Path,
-    hard_link: bool,
+    copy_mode: CopyMode,
     ignore_globset: Option<&GlobSet>,
-) -> Result<()> {
-    for entry in
-        WalkDir::new(src).follow_links(true).into_iter().filter_map(std::result::Result::ok)
-    {
+    ignore_rules: Option<&IgnoreRules>,
+) -> Result<CopySummary> {
+    let mut files = Vec::new();
+    let mut walker = walk_dir_following_links(WalkDir::new(src));
+    while let Some(entry) = walker.next() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
         let relative_path = entry.path().strip_prefix(src).unwrap();
 
         if let Some(gs) = ignore_globset {
@@ -125,6 +578,12 @@ pub fn copy_directory(
                 continue;
             }
         }
+        if ignore_rules.is_some_and(|r| r.is_ignored(entry.path(), entry.file_type().is_dir())) {
+            if entry.file_type().is_dir() {
+                walker.skip_current_dir();
+            }
+            continue;
+        }
 
         let target_path = dest.join(relative_path);
 
@@ -133,17 +592,46 @@ pub fn copy_directory(
                 create_directory(&target_path)?;
             }
         } else {
-            copy_file(entry.path(), dest, src, hard_link).with_context(|| {
-                format!(
-                    "Was not able to copy {} to {} (hard_link={})",
-                    entry.path().display(),
-                    dest.display(),
-                    hard_link
-                )
-            })?;
+            files.push((entry.into_path(), target_path));
         }
     }
-    Ok(())
+
+    let results: Vec<_> = files
+        .par_iter()
+        .map(|(src_path, target_path)| {
+            let src_size = metadata(src_path).map(|m| m.len()).unwrap_or_default();
+            put_file(src_path, target_path, copy_mode)
+                .map(|outcome| (outcome, src_size))
+                .map_err(|e| format!("- {}: {:#}", src_path.display(), e))
+        })
+        .collect();
+
+    let mut summary = CopySummary::default();
+    let mut errors = Vec::new();
+    for res in results {
+        match res {
+            Ok((PutOutcome::Copied, size)) => {
+                summary.copied += 1;
+                summary.bytes_written += size;
+            }
+            Ok((PutOutcome::Linked, _)) => summary.copied += 1,
+            Ok((PutOutcome::Skipped, _)) => summary.skipped += 1,
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!(
+            "Was not able to copy {} file(s) from {} to {} (copy_mode={:?}):\n{}",
+            errors.len(),
+            src.display(),
+            dest.display(),
+            copy_mode,
+            errors.join("\n")
+        );
+    }
+
+    Ok(summary)
 }
 
 pub fn get_file_time(path: &Path) -> Option<SystemTime> {
@@ -157,6 +645,19 @@ pub fn get_file_time(path: &Path) -> Option<SystemTime> {
     })
 }
 
+/// Sets the modification time of everything in that directory, and of the directory itself, to
+/// that time in seconds since the Unix epoch
+pub fn set_modification_times(dir: &Path, unix_timestamp: i64) -> Result<()> {
+    let time = FileTime::from_unix_time(unix_timestamp, 0);
+    for entry in WalkDir::new(dir) {
+        let entry = entry.with_context(|| format!("Failed to walk {}", dir.display()))?;
+        set_file_mtime(entry.path(), time).with_context(|| {
+            format!("Failed to set the modification time of {}", entry.path().display())
+        })?;
+    }
+    Ok(())
+}
+
 /// Compares source and target files' timestamps and returns true if the source file
 /// has been created _or_ updated after the target file has
 pub fn file_stale<PS, PT>(p_source: PS, p_target: PT) -> bool
@@ -253,15 +754,21 @@ pub fn clean_site_output_folder(
 
 #[cfg(test)]
 mod tests {
-    use std::fs::{metadata, read_to_string, File};
+    use std::fs::{create_dir, create_dir_all, metadata, read_to_string, File};
     use std::io::Write;
     use std::path::PathBuf;
     use std::str::FromStr;
 
     use libs::filetime;
-    use tempfile::tempdir_in;
+    use tempfile::{tempdir, tempdir_in};
 
-    use super::copy_file;
+    #[cfg(windows)]
+    use super::strip_verbatim_prefix;
+    use super::{
+        copy_directory, copy_file, copy_file_if_needed, hard_link_or_copy, is_binary_file,
+        is_path_in_directory, read_content_file, read_file, set_modification_times, CopyMode,
+        CopySummary, PathLocation,
+    };
 
     #[test]
     fn test_copy_file_timestamp_preserved() {
@@ -273,7 +780,7 @@ mod tests {
         let src_file_path = src_dir.path().join("test.txt");
         let dest_file_path = dest_dir.path().join(src_file_path.strip_prefix(&base_path).unwrap());
         File::create(&src_file_path).unwrap();
-        copy_file(&src_file_path, dest_dir.path(), &base_path, false).unwrap();
+        copy_file(&src_file_path, dest_dir.path(), &base_path, CopyMode::Copy).unwrap();
 
         assert_eq!(
             metadata(&src_file_path).and_then(|m| m.modified()).unwrap(),
@@ -294,7 +801,7 @@ mod tests {
             let mut src_file = File::create(&src_file_path).unwrap();
             src_file.write_all(b"file1").unwrap();
         }
-        copy_file(&src_file_path, dest_dir.path(), &base_path, false).unwrap();
+        copy_file(&src_file_path, dest_dir.path(), &base_path, CopyMode::Copy).unwrap();
         {
             let mut dest_file = File::create(&dest_file_path).unwrap();
             dest_file.write_all(b"file2").unwrap();
@@ -304,14 +811,14 @@ mod tests {
         filetime::set_file_mtime(&src_file_path, filetime::FileTime::from_unix_time(0, 0)).unwrap();
         filetime::set_file_mtime(&dest_file_path, filetime::FileTime::from_unix_time(0, 0))
             .unwrap();
-        copy_file(&src_file_path, dest_dir.path(), &base_path, false).unwrap();
+        copy_file(&src_file_path, dest_dir.path(), &base_path, CopyMode::Copy).unwrap();
         assert_eq!(read_to_string(&src_file_path).unwrap(), "file1");
         assert_eq!(read_to_string(&dest_file_path).unwrap(), "file2");
 
         // Copy occurs if the timestamps are different while the filesizes are same.
         filetime::set_file_mtime(&dest_file_path, filetime::FileTime::from_unix_time(42, 42))
             .unwrap();
-        copy_file(&src_file_path, dest_dir.path(), &base_path, false).unwrap();
+        copy_file(&src_file_path, dest_dir.path(), &base_path, CopyMode::Copy).unwrap();
         assert_eq!(read_to_string(&src_file_path).unwrap(), "file1");
         assert_eq!(read_to_string(&dest_file_path).unwrap(), "file1");
 
@@ -322,8 +829,298 @@ mod tests {
         }
         filetime::set_file_mtime(&dest_file_path, filetime::FileTime::from_unix_time(0, 0))
             .unwrap();
-        copy_file(&src_file_path, dest_dir.path(), &base_path, false).unwrap();
+        copy_file(&src_file_path, dest_dir.path(), &base_path, CopyMode::Copy).unwrap();
         assert_eq!(read_to_string(&src_file_path).unwrap(), "file1");
         assert_eq!(read_to_string(&dest_file_path).unwrap(), "file1");
     }
+
+    #[test]
+    fn can_set_modification_times() {
+        let tmp = tempdir().unwrap();
+        let nested = tmp.path().join("a").join("b");
+        create_dir_all(&nested).unwrap();
+        File::create(tmp.path().join("index.html")).unwrap();
+        File::create(nested.join("style.css")).unwrap();
+
+        set_modification_times(tmp.path(), 1714564800).unwrap();
+        let paths =
+            [tmp.path().to_path_buf(), tmp.path().join("index.html"), nested.join("style.css")];
+        for path in paths.iter().chain([&nested]) {
+            let modified = metadata(path).and_then(|m| m.modified()).unwrap();
+            assert_eq!(filetime::FileTime::from_system_time(modified).unix_seconds(), 1714564800);
+        }
+    }
+
+    #[test]
+    fn can_check_path_is_in_directory() {
+        let tmp = tempdir().unwrap();
+        let base = tmp.path().join("site");
+        create_dir(&base).unwrap();
+        create_dir(base.join("static")).unwrap();
+        File::create(base.join("static/a.jpg")).unwrap();
+
+        let location = |p: &str| is_path_in_directory(&base, &base.join(p)).unwrap();
+        assert_eq!(location("static/a.jpg"), PathLocation::Inside);
+        assert_eq!(location("static/../content/a.md"), PathLocation::Inside);
+        assert_eq!(location("not/created/yet.html"), PathLocation::Inside);
+        assert_eq!(location("../../etc/passwd"), PathLocation::Outside);
+        assert_eq!(location("static/../../other/a.jpg"), PathLocation::Outside);
+        assert_eq!(location("not/created/../../../other.html"), PathLocation::Outside);
+        assert_eq!(
+            is_path_in_directory(&tmp.path().join("nope"), &base).unwrap(),
+            PathLocation::NonexistentParent
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_checked_where_they_are_not_where_they_point_to() {
+        let outside = tempdir().unwrap();
+        let site = tempdir().unwrap();
+        File::create(outside.path().join("photo.jpg")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), site.path().join("photos")).unwrap();
+
+        assert!(is_path_in_directory(site.path(), &site.path().join("photos/photo.jpg"))
+            .unwrap()
+            .is_inside());
+        assert!(!is_path_in_directory(site.path(), &site.path().join("photos/../../photo.jpg"))
+            .unwrap()
+            .is_inside());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn can_check_path_in_symlinked_directory() {
+        let tmp = tempdir().unwrap();
+        let real_site = tmp.path().join("real");
+        create_dir(&real_site).unwrap();
+        File::create(real_site.join("a.md")).unwrap();
+        let site = tmp.path().join("site");
+        std::os::unix::fs::symlink(&real_site, &site).unwrap();
+
+        assert!(is_path_in_directory(&site, &real_site.join("a.md")).unwrap().is_inside());
+        assert!(is_path_in_directory(&site, &real_site.join("b/c.md")).unwrap().is_inside());
+        assert!(is_path_in_directory(&real_site, &site.join("b/c.md")).unwrap().is_inside());
+        assert!(!is_path_in_directory(&site, &site.join("b/../../c.md")).unwrap().is_inside());
+        assert!(!is_path_in_directory(&site, &tmp.path().join("c.md")).unwrap().is_inside());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn can_strip_verbatim_prefix() {
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from(r"\\?\C:\site\static")),
+            PathBuf::from(r"C:\site\static")
+        );
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from(r"\\?\UNC\server\share\site")),
+            PathBuf::from(r"\\server\share\site")
+        );
+        assert_eq!(strip_verbatim_prefix(PathBuf::from(r"C:\site")), PathBuf::from(r"C:\site"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_directory_follows_symlinks() {
+        let shared = tempdir().unwrap();
+        let src = tempdir().unwrap();
+        let dest = tempdir().unwrap();
+        File::create(shared.path().join("photo.jpg")).unwrap().write_all(b"photo").unwrap();
+        File::create(shared.path().join("notes.txt")).unwrap().write_all(b"notes").unwrap();
+        std::os::unix::fs::symlink(shared.path(), src.path().join("photos")).unwrap();
+        std::os::unix::fs::symlink(shared.path().join("notes.txt"), src.path().join("notes.txt"))
+            .unwrap();
+
+        copy_directory(src.path(), dest.path(), CopyMode::Copy, None, None).unwrap();
+        assert_eq!(read_to_string(dest.path().join("photos/photo.jpg")).unwrap(), "photo");
+        assert_eq!(read_to_string(dest.path().join("notes.txt")).unwrap(), "notes");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_directory_does_not_loop_on_self_referencing_symlinks() {
+        let src = tempdir().unwrap();
+        let dest = tempdir().unwrap();
+        std::fs::create_dir(src.path().join("nested")).unwrap();
+        File::create(src.path().join("nested/file.txt")).unwrap().write_all(b"hello").unwrap();
+        std::os::unix::fs::symlink(src.path(), src.path().join("nested/loop")).unwrap();
+
+        copy_directory(src.path(), dest.path(), CopyMode::Copy, None, None).unwrap();
+        assert_eq!(read_to_string(dest.path().join("nested/file.txt")).unwrap(), "hello");
+        assert!(!dest.path().join("nested/loop").exists());
+    }
+
+    #[test]
+    fn hard_link_falls_back_to_copy_across_filesystems() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src.txt");
+        let dest = dir.path().join("dest.txt");
+        File::create(&src).unwrap().write_all(b"hello").unwrap();
+
+        hard_link_or_copy(&src, &dest, |_, _| {
+            #[cfg(unix)]
+            let code = libs::libc::EXDEV;
+            #[cfg(not(unix))]
+            let code = 17;
+            Err(std::io::Error::from_raw_os_error(code))
+        })
+        .unwrap();
+        assert_eq!(read_to_string(&dest).unwrap(), "hello");
+    }
+
+    #[test]
+    fn hard_link_errors_on_other_errors() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src.txt");
+        let dest = dir.path().join("dest.txt");
+        File::create(&src).unwrap().write_all(b"hello").unwrap();
+
+        let res = hard_link_or_copy(&src, &dest, |_, _| {
+            Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "nope"))
+        });
+        assert!(res.is_err());
+        assert!(!dest.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_link_replaces_links_to_other_files() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src.txt");
+        let other = dir.path().join("other.txt");
+        let dest = dir.path().join("dest.txt");
+        File::create(&src).unwrap().write_all(b"hello").unwrap();
+        File::create(&other).unwrap().write_all(b"other").unwrap();
+        std::fs::hard_link(&other, &dest).unwrap();
+
+        copy_file_if_needed(&src, &dest, CopyMode::Hardlink).unwrap();
+        assert_eq!(read_to_string(&dest).unwrap(), "hello");
+        assert_eq!(metadata(&src).unwrap().ino(), metadata(&dest).unwrap().ino());
+        // the file the destination was previously linked to is left untouched
+        assert_eq!(read_to_string(&other).unwrap(), "other");
+        // linking again is a no-op
+        copy_file_if_needed(&src, &dest, CopyMode::Hardlink).unwrap();
+        assert_eq!(metadata(&src).unwrap().ino(), metadata(&dest).unwrap().ino());
+    }
+
+    #[test]
+    fn reflink_or_copy_always_results_in_a_copy() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src.txt");
+        let dest = dir.path().join("dest.txt");
+        File::create(&src).unwrap().write_all(b"hello").unwrap();
+
+        copy_file_if_needed(&src, &dest, CopyMode::ReflinkOrCopy).unwrap();
+        assert_eq!(read_to_string(&dest).unwrap(), "hello");
+        assert_eq!(
+            metadata(&src).and_then(|m| m.modified()).unwrap(),
+            metadata(&dest).and_then(|m| m.modified()).unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reflink_or_copy_replaces_hard_links_to_the_source() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src.txt");
+        let dest = dir.path().join("dest.txt");
+        File::create(&src).unwrap().write_all(b"hello").unwrap();
+        copy_file_if_needed(&src, &dest, CopyMode::Hardlink).unwrap();
+
+        copy_file_if_needed(&src, &dest, CopyMode::ReflinkOrCopy).unwrap();
+        assert_eq!(read_to_string(&src).unwrap(), "hello");
+        assert_eq!(read_to_string(&dest).unwrap(), "hello");
+        assert_ne!(metadata(&src).unwrap().ino(), metadata(&dest).unwrap().ino());
+    }
+
+    #[test]
+    fn copy_directory_reports_what_it_did() {
+        let src = tempdir().unwrap();
+        let dest = tempdir().unwrap();
+        std::fs::create_dir(src.path().join("nested")).unwrap();
+        File::create(src.path().join("a.txt")).unwrap().write_all(b"hello").unwrap();
+        File::create(src.path().join("nested/b.txt")).unwrap().write_all(b"world!").unwrap();
+
+        let summary = copy_directory(src.path(), dest.path(), CopyMode::Copy, None, None).unwrap();
+        assert_eq!(summary, CopySummary { copied: 2, skipped: 0, bytes_written: 11 });
+        assert_eq!(read_to_string(dest.path().join("nested/b.txt")).unwrap(), "world!");
+
+        let summary = copy_directory(src.path(), dest.path(), CopyMode::Copy, None, None).unwrap();
+        assert_eq!(summary, CopySummary { copied: 0, skipped: 2, bytes_written: 0 });
+    }
+
+    #[test]
+    fn copy_directory_reports_all_errors() {
+        let src = tempdir().unwrap();
+        let dest = tempdir().unwrap();
+        File::create(src.path().join("a.txt")).unwrap();
+        File::create(src.path().join("b.txt")).unwrap();
+        File::create(src.path().join("c.txt")).unwrap();
+        // directories where files should go make the copy fail
+        std::fs::create_dir(dest.path().join("a.txt")).unwrap();
+        std::fs::create_dir(dest.path().join("b.txt")).unwrap();
+
+        let err = copy_directory(src.path(), dest.path(), CopyMode::Copy, None, None).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("2 file(s)"));
+        assert!(msg.contains("a.txt"));
+        assert!(msg.contains("b.txt"));
+        assert!(dest.path().join("c.txt").is_file());
+    }
+
+    #[test]
+    fn can_read_files_in_other_encodings() {
+        let dir = tempdir().unwrap();
+        let text = "+++\ntitle = \"Café\"\n+++\nHello";
+        let utf16 = |big_endian: bool| -> Vec<u8> {
+            let bom: u16 = 0xFEFF;
+            std::iter::once(bom)
+                .chain(text.encode_utf16())
+                .flat_map(|u| if big_endian { u.to_be_bytes() } else { u.to_le_bytes() })
+                .collect()
+        };
+        let latin1: Vec<u8> = text.chars().map(|c| c as u8).collect();
+        let utf8_bom = [&[0xEF, 0xBB, 0xBF][..], text.as_bytes()].concat();
+
+        for (name, bytes) in [
+            ("le.md", utf16(false)),
+            ("be.md", utf16(true)),
+            ("latin1.md", latin1),
+            ("bom.md", utf8_bom),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, bytes).unwrap();
+            assert!(!is_binary_file(&path), "{}", name);
+            assert_eq!(read_content_file(&path).unwrap(), text, "{}", name);
+            if name == "latin1.md" {
+                // Only the content can be in Latin-1
+                assert!(read_file(&path).unwrap_err().to_string().contains("not valid UTF-8"));
+            } else {
+                assert_eq!(read_file(&path).unwrap(), text, "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn errors_with_a_guess_on_binary_files() {
+        let dir = tempdir().unwrap();
+        let binary = dir.path().join("image.md");
+        std::fs::write(&binary, [0x89, b'P', b'N', b'G', 0, 0, 0, 0x0D, 0xFF, 0x00]).unwrap();
+        assert!(is_binary_file(&binary));
+        let msg = read_content_file(&binary).unwrap_err().to_string();
+        assert!(msg.contains("image.md"));
+        assert!(msg.contains("a binary file"));
+        assert!(msg.contains("ignored_content"));
+
+        let no_bom = dir.path().join("no_bom.md");
+        let bytes: Vec<u8> = "Hello é".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        std::fs::write(&no_bom, bytes).unwrap();
+        assert!(read_file(&no_bom).unwrap_err().to_string().contains("UTF-16LE without a BOM"));
+
+        assert!(!is_binary_file(&dir.path().join("missing.md")));
+    }
 }
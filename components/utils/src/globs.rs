@@ -1,20 +1,117 @@
+use std::path::{Path, PathBuf};
+
 use libs::globset::{Glob, GlobSet, GlobSetBuilder};
+use libs::ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use errors::{anyhow, bail, Result};
 
-use errors::{bail, Result};
+/// The file at the root of the site listing the paths to ignore, in the gitignore syntax
+pub const ZOLAIGNORE_FILENAME: &str = ".zolaignore";
 
-pub fn build_ignore_glob_set(ignore: &Vec<String>, name: &str) -> Result<GlobSet> {
+pub fn build_ignore_glob_set(ignore: &[String], name: &str) -> Result<GlobSet> {
+    build_glob_set(ignore, &format!("ignored_{}", name))
+}
+
+/// Compiles the patterns of the `option` config option into a single `GlobSet`
+pub fn build_glob_set(patterns: &[String], option: &str) -> Result<GlobSet> {
     // Convert the file glob strings into a compiled glob set matcher. We want to do this once,
     // at program initialization, rather than for every page, for example. We arrange for the
     // globset matcher to always exist (even though it has to be inside an Option at the
     // moment because of the TOML serializer); if the glob set is empty the `is_match` function
     // of the globber always returns false.
     let mut glob_set_builder = GlobSetBuilder::new();
-    for pat in ignore {
+    for pat in patterns {
         let glob = match Glob::new(pat) {
             Ok(g) => g,
-            Err(e) => bail!("Invalid ignored_{} glob pattern: {}, error = {}", name, pat, e),
+            Err(e) => bail!("Invalid {} glob pattern: {}, error = {}", option, pat, e),
         };
         glob_set_builder.add(glob);
     }
     Ok(glob_set_builder.build()?)
 }
+
+/// The paths of the site that are skipped when loading the content, copying the static files
+/// and watching for changes: the ones matching the `.zolaignore` file at the root of the site
+/// or the `ignore_patterns` of the config.
+/// Both use the gitignore syntax, anchored to the root of the site, and the patterns of the
+/// config come last so they can override the ones of the file, eg with a negation.
+#[derive(Clone, Debug)]
+pub struct IgnoreRules {
+    root: PathBuf,
+    gitignore: Gitignore,
+}
+
+impl IgnoreRules {
+    pub fn new(root: &Path, patterns: &[String]) -> Result<Self> {
+        let mut builder = GitignoreBuilder::new(root);
+        let zolaignore = root.join(ZOLAIGNORE_FILENAME);
+        if zolaignore.exists() {
+            if let Some(e) = builder.add(&zolaignore) {
+                bail!("Invalid pattern in {}: {}", zolaignore.display(), e);
+            }
+        }
+        for pattern in patterns {
+            builder.add_line(None, pattern).map_err(|e| {
+                anyhow!("Invalid ignore_patterns pattern: {}, error = {}", pattern, e)
+            })?;
+        }
+        let gitignore = builder.build()?;
+        Ok(IgnoreRules { root: root.to_path_buf(), gitignore })
+    }
+
+    /// Nothing is ignored
+    pub fn empty() -> Self {
+        IgnoreRules { root: PathBuf::new(), gitignore: Gitignore::empty() }
+    }
+
+    /// Whether the path, or one of its parents, is ignored. Paths outside of the site are never
+    /// ignored
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        match path.strip_prefix(&self.root) {
+            Ok(relative) if relative.components().next().is_some() => {
+                self.gitignore.matched_path_or_any_parents(relative, is_dir).is_ignore()
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn can_ignore_paths_with_gitignore_rules() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let root = tmp_dir.path();
+        fs::write(
+            root.join(ZOLAIGNORE_FILENAME),
+            "node_modules/\n*.psd\n!keep.psd\n/content/drafts/*\n",
+        )
+        .unwrap();
+        let rules = IgnoreRules::new(root, &["!/content/drafts/published.md".to_string()]).unwrap();
+
+        assert!(rules.is_ignored(&root.join("static/node_modules"), true));
+        assert!(rules.is_ignored(&root.join("static/node_modules/pkg/index.js"), false));
+        assert!(!rules.is_ignored(&root.join("static/node_modules.js"), false));
+        assert!(rules.is_ignored(&root.join("static/design.psd"), false));
+        assert!(!rules.is_ignored(&root.join("static/keep.psd"), false));
+        // Anchored to the root of the site
+        assert!(rules.is_ignored(&root.join("content/drafts/wip.md"), false));
+        assert!(!rules.is_ignored(&root.join("content/blog/drafts/wip.md"), false));
+        // The config patterns come after the file
+        assert!(!rules.is_ignored(&root.join("content/drafts/published.md"), false));
+        assert!(!rules.is_ignored(&root.join("content/blog/post.md"), false));
+        assert!(!rules.is_ignored(root, true));
+        assert!(!rules.is_ignored(Path::new("/somewhere/else/node_modules"), true));
+    }
+
+    #[test]
+    fn ignores_nothing_without_rules() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let rules = IgnoreRules::new(tmp_dir.path(), &[]).unwrap();
+        assert!(!rules.is_ignored(&tmp_dir.path().join("static/node_modules"), true));
+        assert!(!IgnoreRules::empty().is_ignored(Path::new("node_modules"), true));
+    }
+}
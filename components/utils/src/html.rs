@@ -0,0 +1,400 @@
+use std::borrow::Cow;
+
+use libs::unicode_segmentation::UnicodeSegmentation;
+
+/// The tags that don't separate words when stripped
+const INLINE_TAGS: &[&str] = &[
+    "a", "abbr", "b", "cite", "code", "del", "em", "i", "ins", "kbd", "mark", "q", "s", "small",
+    "span", "strong", "sub", "sup", "time", "u",
+];
+
+/// Turns some HTML into plain text: tags, comments and the content of `<script>` and `<style>`
+/// are removed, the common character references are decoded and whitespace is collapsed.
+/// Meant for meta descriptions and the like, not for full HTML documents.
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|i| i + 3)
+        } else if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') {
+            rest.find('>').map(|i| i + 1)
+        } else {
+            // A lone `<` in the text
+            text.push('<');
+            rest = &rest[1..];
+            continue;
+        };
+        let end = end.unwrap_or(rest.len());
+        let tag = &rest[..end];
+        rest = &rest[end..];
+
+        let lowercase_tag = tag.to_ascii_lowercase();
+        for raw in ["script", "style"] {
+            if lowercase_tag.starts_with(&format!("<{}", raw)) {
+                let closing = format!("</{}>", raw);
+                let content_end = find_ignore_ascii_case(rest, &closing)
+                    .map(|i| i + closing.len())
+                    .unwrap_or(rest.len());
+                rest = &rest[content_end..];
+            }
+        }
+        // Block tags separate words, eg `<p>a</p><p>b</p>`
+        let name: String = lowercase_tag
+            .trim_start_matches(['<', '/'])
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        if !INLINE_TAGS.contains(&name.as_str()) {
+            text.push(' ');
+        }
+    }
+    text.push_str(rest);
+
+    decode_entities(&text).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The elements without a closing tag
+const VOID_TAGS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// The length of the tag or comment at the start of `html` and its lowercase name, empty for
+/// comments. `None` if `html` doesn't start with a complete one, eg for a lone `<` in the text
+fn tag_at(html: &str) -> Option<(usize, String)> {
+    let after_lt = html.strip_prefix('<')?;
+    let end = if html.starts_with("<!--") {
+        html.find("-->")? + 3
+    } else if after_lt.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') {
+        html.find('>')? + 1
+    } else {
+        return None;
+    };
+    let name = html[..end]
+        .trim_start_matches(['<', '/'])
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    Some((end, name))
+}
+
+/// Updates the elements open after `tag`. Returns the length of the content of `<script>` and
+/// `<style>` elements, which is not text and needs to be skipped
+fn update_open_tags(open: &mut Vec<String>, tag: &str, name: String, rest: &str) -> usize {
+    if name.is_empty() {
+        return 0;
+    }
+    if tag.starts_with("</") {
+        if let Some(i) = open.iter().rposition(|t| t == &name) {
+            open.truncate(i);
+        }
+    } else if !VOID_TAGS.contains(&name.as_str()) && !tag.ends_with("/>") {
+        if name == "script" || name == "style" {
+            let closing = format!("</{}>", name);
+            return find_ignore_ascii_case(rest, &closing)
+                .map(|i| i + closing.len())
+                .unwrap_or(rest.len());
+        }
+        open.push(name);
+    }
+    0
+}
+
+/// Cuts `html` at `cut`, adds the suffix and closes the tags in `open`
+fn close_cut(html: &str, cut: usize, suffix: &str, open: &[String]) -> String {
+    let mut truncated = html[..cut].trim_end().to_string();
+    truncated.push_str(suffix);
+    for tag in open.iter().rev() {
+        truncated.push_str(&format!("</{}>", tag));
+    }
+    truncated
+}
+
+/// The length of the character reference at the start of `text`, eg `&amp;` or `&#8220;`
+fn entity_len(text: &str) -> Option<usize> {
+    let end = text.strip_prefix('&')?.find(';').filter(|&end| end > 0 && end < 10)?;
+    text[1..=end].chars().all(|c| c.is_ascii_alphanumeric() || c == '#').then_some(end + 2)
+}
+
+/// Keeps the first `max_words` words of the text of some HTML, cutting after the last paragraph
+/// or sentence that fits if there is one, and closes the tags left open by the cut.
+/// Returns whether anything was removed.
+pub fn truncate_html(html: &str, max_words: usize) -> (String, bool) {
+    // The open tags, and the last place it would be nice to cut at with the tags open there
+    let mut open: Vec<String> = Vec::new();
+    let mut boundary: Option<(usize, Vec<String>)> = None;
+    let mut words = 0;
+    let mut pos = 0;
+
+    while pos < html.len() {
+        let rest = &html[pos..];
+        if let Some((end, name)) = tag_at(rest) {
+            let tag = &rest[..end];
+            pos += end;
+            let block_end = tag.starts_with("</") && !INLINE_TAGS.contains(&name.as_str());
+            pos += update_open_tags(&mut open, tag, name, &html[pos..]);
+            if block_end {
+                boundary = Some((pos, open.clone()));
+            }
+            continue;
+        }
+
+        // Some text, until the next tag
+        let text_end = rest[1..].find('<').map(|i| i + 1).unwrap_or(rest.len());
+        let in_code = open.iter().any(|t| t == "pre" || t == "code");
+        let mut prev: Option<char> = None;
+        for (i, c) in rest[..text_end].char_indices() {
+            let after_space = prev.map(char::is_whitespace).unwrap_or(true);
+            if !c.is_whitespace() && after_space {
+                if words == max_words {
+                    let (cut, open) = boundary.unwrap_or_else(|| (pos + i, open.clone()));
+                    return (close_cut(html, cut, "", &open), true);
+                }
+                words += 1;
+            }
+            if !in_code && c.is_whitespace() && matches!(prev, Some('.' | '!' | '?')) {
+                boundary = Some((pos + i, open.clone()));
+            }
+            prev = Some(c);
+        }
+        pos += text_end;
+    }
+
+    (html.to_string(), false)
+}
+
+/// Keeps the first `max_chars` characters of the text of some HTML, followed by `suffix` if
+/// anything was removed, and closes the tags left open by the cut.
+/// Characters are counted as graphemes so that emojis and accented letters are never split,
+/// and a character reference like `&amp;` counts as a single character. Tags are kept and
+/// don't count. Returns whether anything was removed.
+pub fn truncate_chars(html: &str, max_chars: usize, suffix: &str) -> (String, bool) {
+    let mut open: Vec<String> = Vec::new();
+    // Where to cut and the tags open there, known once `max_chars` characters have been seen
+    let mut cut: Option<(usize, Vec<String>)> = (max_chars == 0).then(|| (0, Vec::new()));
+    let mut chars = 0;
+    let mut pos = 0;
+
+    while pos < html.len() {
+        let rest = &html[pos..];
+        if let Some((end, name)) = tag_at(rest) {
+            let tag = &rest[..end];
+            pos += end;
+            pos += update_open_tags(&mut open, tag, name, &html[pos..]);
+            continue;
+        }
+
+        if let Some((cut, open)) = cut {
+            return (close_cut(html, cut, suffix, &open), true);
+        }
+        pos += match entity_len(rest) {
+            Some(len) => len,
+            None => rest.graphemes(true).next().map(str::len).unwrap_or(rest.len()),
+        };
+        chars += 1;
+        if chars >= max_chars {
+            cut = Some((pos, open.clone()));
+        }
+    }
+
+    (html.to_string(), false)
+}
+
+/// Keeps the first `max_chars` graphemes of some plain text, followed by `suffix` if anything
+/// was removed. Returns whether anything was removed.
+pub fn truncate_text(text: &str, max_chars: usize, suffix: &str) -> (String, bool) {
+    match text.grapheme_indices(true).nth(max_chars) {
+        Some((cut, _)) => (format!("{}{}", text[..cut].trim_end(), suffix), true),
+        None => (text.to_string(), false),
+    }
+}
+
+/// Finds the first occurrence of an ASCII `needle` in `haystack`, ignoring the ASCII case,
+/// without lowercasing all of `haystack`
+pub fn find_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|w| w.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Decodes the common named character references, like `&amp;` or `&nbsp;`, and the numeric
+/// ones. Unknown references are left as is.
+pub fn decode_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = entity_len(rest).and_then(|len| {
+            let name = &rest[1..len - 1];
+            let c = match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => match name.strip_prefix('#') {
+                    Some(n) => match n.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => n.parse().ok(),
+                    }
+                    .and_then(char::from_u32),
+                    None => None,
+                },
+            };
+            c.map(|c| (c, len))
+        });
+
+        match entity {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    Cow::Owned(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_entities, find_ignore_ascii_case, strip_html, truncate_chars, truncate_html,
+        truncate_text,
+    };
+
+    #[test]
+    fn can_strip_html() {
+        let html = r#"<h1 id="intro">Intro</h1>
+<p>Some <em>emphasis</em> &amp; a <a href="/a/?b=1&amp;c=2">link</a>.</p><p>Next&nbsp;paragraph &#8220;quoted&#x201D;</p>
+<!-- a comment --><script>var a = "<p>no</p>";</script><style>p { color: red; }</style>
+<pre><code>1 &lt; 2 && 3 > 2</code></pre>"#;
+        assert_eq!(
+            strip_html(html),
+            "Intro Some emphasis & a link. Next paragraph \u{201C}quoted\u{201D} 1 < 2 && 3 > 2"
+        );
+    }
+
+    #[test]
+    fn keeps_text_without_html_as_is() {
+        assert_eq!(strip_html("Hello world"), "Hello world");
+        assert_eq!(strip_html("a < b & c"), "a < b & c");
+        assert_eq!(strip_html(""), "");
+    }
+
+    #[test]
+    fn can_truncate_html_at_paragraphs() {
+        let html = "<p>One two.</p>\n<p>Three <em>four</em> five</p>\n<p>Six</p>\n";
+        assert_eq!(truncate_html(html, 4), ("<p>One two.</p>".to_string(), true));
+        assert_eq!(
+            truncate_html(html, 5),
+            ("<p>One two.</p>\n<p>Three <em>four</em> five</p>".to_string(), true)
+        );
+        assert_eq!(truncate_html(html, 6), (html.to_string(), false));
+    }
+
+    #[test]
+    fn can_truncate_html_at_sentences() {
+        let html = "<ul><li><p>One <strong>two. Three</strong> four</p></li></ul>";
+        assert_eq!(
+            truncate_html(html, 3),
+            ("<ul><li><p>One <strong>two.</strong></p></li></ul>".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn can_truncate_html_without_boundaries() {
+        let html = r#"<p>One <a href="/two/">two <img src="a.png"> three</a> four</p>"#;
+        assert_eq!(
+            truncate_html(html, 2),
+            (r#"<p>One <a href="/two/">two <img src="a.png"></a></p>"#.to_string(), true)
+        );
+        // Code and comments don't have sentences
+        let html = "<!-- a. b --><pre><code>a. b c</code></pre>";
+        assert_eq!(
+            truncate_html(html, 2),
+            ("<!-- a. b --><pre><code>a. b</code></pre>".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn can_truncate_chars_without_splitting_graphemes() {
+        assert_eq!(truncate_chars("Hello world", 5, "…"), ("Hello…".to_string(), true));
+        assert_eq!(truncate_chars("Hello", 5, "…"), ("Hello".to_string(), false));
+        // A family emoji is a single grapheme made of 7 code points
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+        assert_eq!(
+            truncate_chars(&format!("a{}b", family), 2, "…"),
+            (format!("a{}…", family), true)
+        );
+        // `e` followed by a combining acute accent
+        assert_eq!(
+            truncate_chars("Cafe\u{301} au lait", 4, "..."),
+            ("Cafe\u{301}...".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn can_truncate_chars_without_splitting_entities() {
+        assert_eq!(truncate_chars("Tom &amp; Jerry", 5, "…"), ("Tom &amp;…".to_string(), true));
+        assert_eq!(truncate_chars("Tom &amp; Jerry", 4, "…"), ("Tom…".to_string(), true));
+        assert_eq!(
+            truncate_chars("&#8220;Hi&#x201D; there", 4, ""),
+            ("&#8220;Hi&#x201D;".to_string(), true)
+        );
+        // Not an entity
+        assert_eq!(truncate_chars("a & b;c", 3, ""), ("a &".to_string(), true));
+        // Plain text has no entities or tags
+        assert_eq!(truncate_text("&amp; <b>", 3, "…"), ("&am…".to_string(), true));
+        assert_eq!(truncate_text("e\u{301}e\u{301}", 1, ""), ("e\u{301}".to_string(), true));
+        assert_eq!(truncate_text("short", 10, "…"), ("short".to_string(), false));
+    }
+
+    #[test]
+    fn can_truncate_chars_in_html() {
+        let html = "<p>One <em>two</em> three</p><!-- four --><script>five</script><p>six</p>";
+        assert_eq!(truncate_chars(html, 6, "…"), ("<p>One <em>tw…</em></p>".to_string(), true));
+        assert_eq!(
+            truncate_chars(html, 13, "…"),
+            ("<p>One <em>two</em> three…</p>".to_string(), true)
+        );
+        assert_eq!(truncate_chars(html, 16, "…"), (html.to_string(), false));
+        assert_eq!(truncate_chars("1 < 2 <br> 3", 5, ""), ("1 < 2".to_string(), true));
+        assert_eq!(truncate_chars("<p>Hi</p>", 0, "…"), ("…".to_string(), true));
+    }
+
+    #[test]
+    fn can_find_ignoring_ascii_case() {
+        assert_eq!(find_ignore_ascii_case("a</SCRIPT>", "</script>"), Some(1));
+        assert_eq!(find_ignore_ascii_case("é</Style>", "</style>"), Some(2));
+        assert_eq!(find_ignore_ascii_case("</scrip", "</script>"), None);
+    }
+
+    #[test]
+    fn can_decode_entities() {
+        assert_eq!(decode_entities("/a/?b=1&amp;c=2"), "/a/?b=1&c=2");
+        assert_eq!(
+            decode_entities("&#8220;a&#x201D;&nbsp;&lt;&unknown; & b;"),
+            "\u{201C}a\u{201D}\u{a0}<&unknown; & b;"
+        );
+    }
+}
@@ -1,7 +1,10 @@
 pub mod anchors;
+pub mod data;
 pub mod de;
+pub mod encryption;
 pub mod fs;
 pub mod globs;
+pub mod html;
 pub mod net;
 pub mod site;
 pub mod slugs;
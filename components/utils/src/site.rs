@@ -1,7 +1,9 @@
 use libs::percent_encoding::percent_decode;
+use libs::url::Url;
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use errors::{anyhow, Result};
+use errors::{bail, Result};
 
 /// Result of a successful resolution of an internal link.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -15,43 +17,191 @@ pub struct ResolvedInternalLink {
     pub anchor: Option<String>,
 }
 
+/// Whether the link points to a content file: either `@/path/from/content/file.md` or the
+/// deprecated `./path/from/content/file.md` form
+pub fn is_internal_link(link: &str) -> bool {
+    link.starts_with("@/") || is_deprecated_internal_link(link)
+}
+
+fn is_deprecated_internal_link(link: &str) -> bool {
+    link.strip_prefix("./")
+        .map(|l| l.split('#').next().unwrap_or_default().ends_with(".md"))
+        .unwrap_or(false)
+}
+
+/// The keys of the permalinks map a link path could point to, in order of preference.
+/// The `.md` extension is optional: `blog/hello` can be `blog/hello.md` or `blog/hello/_index.md`.
+fn candidate_paths(path: &str, lang: Option<&str>) -> Vec<String> {
+    let lang = lang.map(|l| format!(".{}", l)).unwrap_or_default();
+    if let Some(stem) = path.strip_suffix(".md") {
+        // The link might already have the language in it
+        if lang.is_empty() || stem.ends_with(&lang) {
+            vec![path.to_string()]
+        } else {
+            vec![format!("{}{}.md", stem, lang)]
+        }
+    } else if path.is_empty() || path.ends_with('/') {
+        vec![format!("{}_index{}.md", path, lang)]
+    } else {
+        vec![format!("{}{}.md", path, lang), format!("{}/_index{}.md", path, lang)]
+    }
+}
+
 /// Resolves an internal link (of the `@/posts/something.md#hey` sort) to its absolute link and
 /// returns the path + anchor as well
 pub fn resolve_internal_link(
     link: &str,
-    permalinks: &HashMap<String, String>,
+    permalinks: &HashMap<String, Arc<str>>,
 ) -> Result<ResolvedInternalLink> {
-    // First we remove the ./ since that's zola specific
-    let clean_link = link.replacen("@/", "", 1);
+    resolve_internal_link_in_lang(link, None, permalinks)
+}
+
+/// Same as `resolve_internal_link` but for a link in another language than the default one:
+/// `@/posts/something.md` will point to `posts/something.{lang}.md`
+pub fn resolve_internal_link_in_lang(
+    link: &str,
+    lang: Option<&str>,
+    permalinks: &HashMap<String, Arc<str>>,
+) -> Result<ResolvedInternalLink> {
+    // First we remove the @/ (or ./) since that's zola specific
+    let clean_link = if let Some(l) = link.strip_prefix("@/") {
+        l
+    } else if is_deprecated_internal_link(link) {
+        console::warn(&format!(
+            "Internal link `{}` uses the deprecated `./` prefix, use `@/{}` instead.",
+            link,
+            &link[2..]
+        ));
+        &link[2..]
+    } else {
+        link
+    };
     // Then we remove any potential anchor
     // parts[0] will be the file path and parts[1] the anchor if present
     let parts = clean_link.split('#').collect::<Vec<_>>();
     // If we have slugification turned off, we might end up with some escaped characters so we need
     // to decode them first
     let decoded = percent_decode(parts[0].as_bytes()).decode_utf8_lossy().to_string();
-    let target =
-        permalinks.get(&decoded).ok_or_else(|| anyhow!("Relative link {} not found.", link))?;
+    let candidates = candidate_paths(&decoded, lang);
+    let (md_path, target) = match candidates.iter().find_map(|c| permalinks.get_key_value(c)) {
+        Some(found) => found,
+        None => {
+            // Paths are case-sensitive but a typo in the case is easy to miss
+            let close_match = permalinks
+                .keys()
+                .filter(|k| candidates.iter().any(|c| c.eq_ignore_ascii_case(k)))
+                .min();
+            match close_match {
+                Some(k) => {
+                    bail!("Relative link {} not found, did you mean `@/{}`? Links are case-sensitive.", link, k)
+                }
+                None => bail!("Relative link {} not found.", link),
+            }
+        }
+    };
     if parts.len() > 1 {
         Ok(ResolvedInternalLink {
             permalink: format!("{}#{}", target, parts[1]),
-            md_path: decoded,
+            md_path: md_path.to_string(),
             anchor: Some(parts[1].to_string()),
         })
     } else {
-        Ok(ResolvedInternalLink { permalink: target.to_string(), md_path: decoded, anchor: None })
+        Ok(ResolvedInternalLink {
+            permalink: target.to_string(),
+            md_path: md_path.to_string(),
+            anchor: None,
+        })
     }
 }
 
+/// An absolute link to the site that doesn't start with its `base_url`, like the `http://` links
+/// left in the content after moving the site to `https://`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MixedSelfLink {
+    /// The page or section the link points to, if there is one
+    pub internal: Option<ResolvedInternalLink>,
+    /// The link with the scheme of the `base_url`
+    pub normalized: String,
+}
+
+impl MixedSelfLink {
+    /// What the link should be replaced with in the content
+    pub fn suggestion(&self) -> String {
+        match self.internal {
+            Some(ResolvedInternalLink { ref md_path, anchor: Some(ref anchor), .. }) => {
+                format!("@/{}#{}", md_path, anchor)
+            }
+            Some(ref resolved) => format!("@/{}", resolved.md_path),
+            None => self.normalized.clone(),
+        }
+    }
+}
+
+/// Finds whether that link points to the same host as the `base_url` but with another scheme or
+/// outside of the path of the `base_url`. A link outside of it is looked up as if the site had been
+/// moved there, so `http://example.com/about/` can be found with a `base_url` of
+/// `https://example.com/blog`.
+pub fn find_mixed_self_link(
+    link: &str,
+    base_url: &str,
+    permalinks: &HashMap<String, Arc<str>>,
+) -> Option<MixedSelfLink> {
+    let base = Url::parse(base_url).ok()?;
+    let url = Url::parse(link).ok()?;
+    if !matches!(url.scheme(), "http" | "https")
+        || url.host_str().is_none()
+        || url.host_str() != base.host_str()
+        || url.port() != base.port()
+    {
+        return None;
+    }
+
+    let base_path = base.path().trim_end_matches('/');
+    let in_base_path =
+        url.path().strip_prefix(base_path).filter(|p| p.is_empty() || p.starts_with('/'));
+    if in_base_path.is_some() && url.scheme() == base.scheme() {
+        return None;
+    }
+
+    let path = in_base_path.unwrap_or(url.path()).trim_end_matches('/');
+    let target = format!("{}{}", base_url.trim_end_matches('/'), path);
+    let internal = if url.query().is_some() {
+        None
+    } else {
+        permalinks
+            .iter()
+            .filter(|(_, permalink)| permalink.trim_end_matches('/') == target)
+            .min()
+            .map(|(md_path, permalink)| ResolvedInternalLink {
+                permalink: match url.fragment() {
+                    Some(anchor) => format!("{}#{}", permalink, anchor),
+                    None => permalink.to_string(),
+                },
+                md_path: md_path.clone(),
+                anchor: url.fragment().map(|a| a.to_string()),
+            })
+    };
+
+    let mut normalized = url.clone();
+    // Can only fail between special and non-special schemes, both are http(s) here
+    let _ = normalized.set_scheme(base.scheme());
+    Some(MixedSelfLink { internal, normalized: normalized.to_string() })
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
+    use std::sync::Arc;
 
-    use super::resolve_internal_link;
+    use super::{
+        find_mixed_self_link, is_internal_link, resolve_internal_link,
+        resolve_internal_link_in_lang,
+    };
 
     #[test]
     fn can_resolve_valid_internal_link() {
         let mut permalinks = HashMap::new();
-        permalinks.insert("pages/about.md".to_string(), "https://vincent.is/about".to_string());
+        permalinks.insert("pages/about.md".to_string(), "https://vincent.is/about".into());
         let res = resolve_internal_link("@/pages/about.md", &permalinks).unwrap();
         assert_eq!(res.permalink, "https://vincent.is/about");
     }
@@ -59,7 +209,7 @@ mod tests {
     #[test]
     fn can_resolve_valid_root_internal_link() {
         let mut permalinks = HashMap::new();
-        permalinks.insert("about.md".to_string(), "https://vincent.is/about".to_string());
+        permalinks.insert("about.md".to_string(), "https://vincent.is/about".into());
         let res = resolve_internal_link("@/about.md", &permalinks).unwrap();
         assert_eq!(res.permalink, "https://vincent.is/about");
     }
@@ -67,7 +217,7 @@ mod tests {
     #[test]
     fn can_resolve_internal_links_with_anchors() {
         let mut permalinks = HashMap::new();
-        permalinks.insert("pages/about.md".to_string(), "https://vincent.is/about".to_string());
+        permalinks.insert("pages/about.md".to_string(), "https://vincent.is/about".into());
         let res = resolve_internal_link("@/pages/about.md#hello", &permalinks).unwrap();
         assert_eq!(res.permalink, "https://vincent.is/about#hello");
         assert_eq!(res.md_path, "pages/about.md".to_string());
@@ -77,10 +227,8 @@ mod tests {
     #[test]
     fn can_resolve_escaped_internal_links() {
         let mut permalinks = HashMap::new();
-        permalinks.insert(
-            "pages/about space.md".to_string(),
-            "https://vincent.is/about%20space/".to_string(),
-        );
+        permalinks
+            .insert("pages/about space.md".to_string(), "https://vincent.is/about%20space/".into());
         let res = resolve_internal_link("@/pages/about%20space.md#hello", &permalinks).unwrap();
         assert_eq!(res.permalink, "https://vincent.is/about%20space/#hello");
         assert_eq!(res.md_path, "pages/about space.md".to_string());
@@ -92,4 +240,137 @@ mod tests {
         let res = resolve_internal_link("@/pages/about.md#hello", &HashMap::new());
         assert!(res.is_err());
     }
+
+    fn get_permalinks() -> HashMap<String, Arc<str>> {
+        let mut permalinks = HashMap::new();
+        for (path, permalink) in [
+            ("blog/_index.md", "https://vincent.is/blog/"),
+            ("blog/_index.fr.md", "https://vincent.is/fr/blog/"),
+            ("blog/hello.md", "https://vincent.is/blog/hello/"),
+            ("blog/hello.fr.md", "https://vincent.is/fr/blog/hello/"),
+            ("Blog/Upper.md", "https://vincent.is/blog/upper/"),
+        ] {
+            permalinks.insert(path.to_string(), permalink.into());
+        }
+        permalinks
+    }
+
+    #[test]
+    fn can_resolve_internal_links_with_or_without_extension() {
+        let permalinks = get_permalinks();
+        for (link, expected) in [
+            ("@/blog/hello.md", "https://vincent.is/blog/hello/"),
+            ("@/blog/hello", "https://vincent.is/blog/hello/"),
+            ("@/blog/hello#top", "https://vincent.is/blog/hello/#top"),
+            ("@/blog/_index.md", "https://vincent.is/blog/"),
+            ("@/blog", "https://vincent.is/blog/"),
+            ("@/blog/", "https://vincent.is/blog/"),
+        ] {
+            assert_eq!(resolve_internal_link(link, &permalinks).unwrap().permalink, expected);
+        }
+        assert_eq!(resolve_internal_link("@/blog", &permalinks).unwrap().md_path, "blog/_index.md");
+    }
+
+    #[test]
+    fn can_resolve_internal_links_in_other_language() {
+        let permalinks = get_permalinks();
+        for (link, expected) in [
+            ("@/blog/hello.md", "https://vincent.is/fr/blog/hello/"),
+            ("@/blog/hello.fr.md", "https://vincent.is/fr/blog/hello/"),
+            ("@/blog/hello", "https://vincent.is/fr/blog/hello/"),
+            ("@/blog/_index.md", "https://vincent.is/fr/blog/"),
+            ("@/blog", "https://vincent.is/fr/blog/"),
+        ] {
+            let res = resolve_internal_link_in_lang(link, Some("fr"), &permalinks).unwrap();
+            assert_eq!(res.permalink, expected);
+        }
+    }
+
+    #[test]
+    fn can_resolve_deprecated_internal_links() {
+        let permalinks = get_permalinks();
+        assert!(is_internal_link("./blog/hello.md#top"));
+        assert!(!is_internal_link("./image.png"));
+        let res = resolve_internal_link("./blog/hello.md#top", &permalinks).unwrap();
+        assert_eq!(res.permalink, "https://vincent.is/blog/hello/#top");
+        assert_eq!(res.md_path, "blog/hello.md");
+    }
+
+    #[test]
+    fn internal_links_are_case_sensitive() {
+        let permalinks = get_permalinks();
+        assert!(resolve_internal_link("@/Blog/Upper.md", &permalinks).is_ok());
+        let err = resolve_internal_link("@/blog/upper.md", &permalinks).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Relative link @/blog/upper.md not found, did you mean `@/Blog/Upper.md`? Links are case-sensitive."
+        );
+        assert!(resolve_internal_link("@/BLOG/hello.md", &permalinks).is_err());
+    }
+
+    #[test]
+    fn can_find_mixed_self_links() {
+        let mut permalinks = HashMap::new();
+        permalinks.insert("posts/hello.md".to_string(), "https://example.com/posts/hello/".into());
+        permalinks.insert("_index.md".to_string(), "https://example.com/".into());
+
+        let found = find_mixed_self_link(
+            "http://example.com/posts/hello/#intro",
+            "https://example.com",
+            &permalinks,
+        )
+        .unwrap();
+        let internal = found.internal.clone().unwrap();
+        assert_eq!(internal.permalink, "https://example.com/posts/hello/#intro");
+        assert_eq!(internal.md_path, "posts/hello.md");
+        assert_eq!(found.suggestion(), "@/posts/hello.md#intro");
+
+        let found =
+            find_mixed_self_link("http://example.com", "https://example.com", &permalinks).unwrap();
+        assert_eq!(found.suggestion(), "@/_index.md");
+
+        let found =
+            find_mixed_self_link("http://example.com/logo.png", "https://example.com", &permalinks)
+                .unwrap();
+        assert_eq!(found.internal, None);
+        assert_eq!(found.suggestion(), "https://example.com/logo.png");
+
+        for link in [
+            "https://example.com/posts/hello/",
+            "http://example.org/posts/hello/",
+            "http://example.com:8080/posts/hello/",
+        ] {
+            assert_eq!(find_mixed_self_link(link, "https://example.com", &permalinks), None);
+        }
+    }
+
+    #[test]
+    fn can_find_self_links_outside_of_base_url_path() {
+        let mut permalinks = HashMap::new();
+        permalinks.insert("about.md".to_string(), "https://example.com/blog/about/".into());
+
+        let found = find_mixed_self_link(
+            "https://example.com/about",
+            "https://example.com/blog",
+            &permalinks,
+        )
+        .unwrap();
+        assert_eq!(found.suggestion(), "@/about.md");
+        assert_eq!(
+            find_mixed_self_link(
+                "https://example.com/blog/about/",
+                "https://example.com/blog",
+                &permalinks
+            ),
+            None
+        );
+        // A path that only starts like the one of the base_url is not in it
+        let found = find_mixed_self_link(
+            "https://example.com/blogroll",
+            "https://example.com/blog",
+            &permalinks,
+        )
+        .unwrap();
+        assert_eq!(found.internal, None);
+    }
 }
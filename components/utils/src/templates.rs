@@ -1,11 +1,42 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
-use libs::tera::{Context, Tera};
+use libs::once_cell::sync::Lazy;
+use libs::regex::Regex;
+use libs::tera::ast::{Expr, ExprVal, FunctionCall, Node};
+use libs::tera::{self, Context, Map, Tera, Value};
 
-use errors::{bail, Result};
+use errors::{bail, Error, LocatedError, Result};
 
 static DEFAULT_TPL: &str = include_str!("default_tpl.html");
 
+static MISSING_VARIABLE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^Variable `([^`]+)` not found in context while rendering '([^']+)'").unwrap()
+});
+static ERROR_LOCATION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\(error happened in '([^']+)'\)").unwrap());
+
+thread_local! {
+    /// The template being rendered on this thread, so that the Tera functions can mention it
+    static CURRENT_TEMPLATE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// The template being rendered on the current thread, if any. Shortcodes rendered in a template
+/// with the `markdown` filter are the current template while they are rendered.
+pub fn current_template() -> Option<String> {
+    CURRENT_TEMPLATE.with(|t| t.borrow().clone())
+}
+
+/// Renders a template, keeping track of it for `current_template`
+fn render_tracked(tera: &Tera, template: &str, context: &Context) -> tera::Result<String> {
+    let previous = CURRENT_TEMPLATE.with(|t| t.replace(Some(template.to_string())));
+    let res = tera.render(template, context);
+    CURRENT_TEMPLATE.with(|t| *t.borrow_mut() = previous);
+    res
+}
+
 macro_rules! render_default_tpl {
     ($filename: expr, $url: expr) => {{
         let mut context = Context::new();
@@ -78,9 +109,44 @@ pub fn render_template(
     tera: &Tera,
     context: Context,
     theme: &Option<String>,
+) -> Result<String> {
+    render_template_with_options(name, tera, context, theme, true)
+}
+
+/// Same as `render_template` but if `missing_extra_is_error` is false, the keys of `page.extra`
+/// and `section.extra` used by the template that are not set are rendered as empty strings
+/// instead of erroring.
+pub fn render_template_with_options(
+    name: &str,
+    tera: &Tera,
+    mut context: Context,
+    theme: &Option<String>,
+    missing_extra_is_error: bool,
 ) -> Result<String> {
     if let Some(template) = check_template_fallbacks(name, tera, theme) {
-        return tera.render(template, &context).map_err(std::convert::Into::into);
+        if !missing_extra_is_error {
+            insert_missing_extras(&mut context, &find_extra_variables(tera, template));
+        }
+        // Whatever the AST couldn't tell, like variables used in macros, is found by rendering
+        let mut defaulted = Vec::new();
+        loop {
+            match render_tracked(tera, template, &context) {
+                Ok(s) => return Ok(s),
+                Err(e) => {
+                    if !missing_extra_is_error {
+                        if let Some(variable) = find_missing_variable(&e) {
+                            if !defaulted.contains(&variable)
+                                && insert_missing_extras(&mut context, &[variable.as_str()])
+                            {
+                                defaulted.push(variable);
+                                continue;
+                            }
+                        }
+                    }
+                    return Err(describe_render_error(template, theme, &e));
+                }
+            }
+        }
     }
 
     // maybe it's a default one?
@@ -100,6 +166,278 @@ pub fn render_template(
     }
 }
 
+/// Finds the variable that couldn't be found in the context, if that's what the error is about
+fn find_missing_variable(error: &tera::Error) -> Option<String> {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(e) = source {
+        if let Some(caps) = MISSING_VARIABLE_RE.captures(&e.to_string()) {
+            return Some(caps[1].to_string());
+        }
+        source = e.source();
+    }
+    None
+}
+
+/// The `page.extra.*`, `section.extra.*` and `effective_extra` variables used in a template,
+/// the templates it extends and the ones it includes
+#[derive(Debug, Default)]
+struct ExtraVariables {
+    /// Variables that are printed or passed to filters and functions
+    used: Vec<String>,
+    /// Variables that are tested, defaulted, iterated on or used in conditions: setting them would
+    /// change what is rendered
+    observed: Vec<String>,
+    /// `page` or `section` when the template sets a variable of that name
+    shadowed: Vec<String>,
+}
+
+impl ExtraVariables {
+    fn add(&mut self, ident: &str, observed: bool) {
+        if observed {
+            self.observed.push(ident.to_string());
+        } else {
+            self.used.push(ident.to_string());
+        }
+    }
+
+    fn shadow(&mut self, name: &str) {
+        if name == "page" || name == "section" {
+            self.shadowed.push(name.to_string());
+        }
+    }
+
+    fn walk_template(&mut self, tera: &Tera, name: &str, visited: &mut HashSet<String>) {
+        if !visited.insert(name.to_string()) {
+            return;
+        }
+        let template = match tera.get_template(name) {
+            Ok(t) => t,
+            Err(_) => return,
+        };
+        for parent in &template.parents {
+            self.walk_template(tera, parent, visited);
+        }
+        self.walk_nodes(tera, &template.ast, visited);
+    }
+
+    fn walk_nodes(&mut self, tera: &Tera, nodes: &[Node], visited: &mut HashSet<String>) {
+        for node in nodes {
+            match node {
+                Node::VariableBlock(_, expr) => self.walk_expr(expr, false),
+                Node::Set(_, set) => {
+                    self.shadow(&set.key);
+                    self.walk_expr(&set.value, false);
+                }
+                Node::FilterSection(_, section, _) => {
+                    self.walk_call(&section.filter);
+                    self.walk_nodes(tera, &section.body, visited);
+                }
+                Node::Block(_, block, _) => self.walk_nodes(tera, &block.body, visited),
+                Node::Forloop(_, forloop, _) => {
+                    self.shadow(&forloop.value);
+                    if let Some(key) = &forloop.key {
+                        self.shadow(key);
+                    }
+                    self.walk_expr(&forloop.container, true);
+                    self.walk_nodes(tera, &forloop.body, visited);
+                    if let Some(body) = &forloop.empty_body {
+                        self.walk_nodes(tera, body, visited);
+                    }
+                }
+                Node::If(condition, _) => {
+                    for (_, expr, body) in &condition.conditions {
+                        self.walk_expr(expr, true);
+                        self.walk_nodes(tera, body, visited);
+                    }
+                    if let Some((_, body)) = &condition.otherwise {
+                        self.walk_nodes(tera, body, visited);
+                    }
+                }
+                Node::Include(_, names, _) => {
+                    for name in names {
+                        self.walk_template(tera, name, visited);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn walk_call(&mut self, call: &FunctionCall) {
+        for arg in call.args.values() {
+            self.walk_expr(arg, false);
+        }
+    }
+
+    fn walk_expr(&mut self, expr: &Expr, observed: bool) {
+        let observed = observed || expr.filters.iter().any(|f| f.name == "default");
+        for filter in &expr.filters {
+            self.walk_call(filter);
+        }
+        self.walk_expr_val(&expr.val, observed);
+    }
+
+    fn walk_expr_val(&mut self, val: &ExprVal, observed: bool) {
+        match val {
+            ExprVal::Ident(ident) => self.add(ident, observed),
+            ExprVal::Math(e) => {
+                self.walk_expr(&e.lhs, observed);
+                self.walk_expr(&e.rhs, observed);
+            }
+            ExprVal::Logic(e) => {
+                self.walk_expr(&e.lhs, observed);
+                self.walk_expr(&e.rhs, observed);
+            }
+            ExprVal::In(e) => {
+                self.walk_expr(&e.lhs, observed);
+                self.walk_expr(&e.rhs, true);
+            }
+            ExprVal::Test(test) => {
+                self.add(&test.ident, true);
+                for arg in &test.args {
+                    self.walk_expr(arg, false);
+                }
+            }
+            ExprVal::MacroCall(call) => {
+                for arg in call.args.values() {
+                    self.walk_expr(arg, false);
+                }
+            }
+            ExprVal::FunctionCall(call) => self.walk_call(call),
+            ExprVal::Array(values) => {
+                for value in values {
+                    self.walk_expr(value, observed);
+                }
+            }
+            ExprVal::StringConcat(concat) => {
+                for value in &concat.values {
+                    self.walk_expr_val(value, observed);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Whether setting the variable can't change how the rest of the template renders: it isn't
+    /// observed and neither is any of its parents, and none of its parents below `extra` is used
+    /// as a whole
+    fn can_default(&self, variable: &str) -> bool {
+        let is_parent = |parent: &String| variable.starts_with(&format!("{}.", parent));
+        let root = variable.split('.').next().unwrap_or_default();
+        !self.shadowed.iter().any(|s| s == root)
+            && !self.observed.iter().any(|o| o == variable || is_parent(o))
+            && !self.used.iter().any(|u| u.contains('.') && is_parent(u))
+    }
+}
+
+/// Finds the extra variables used by a template that can be set to an empty string before
+/// rendering it, so that a template using several missing ones is only rendered once
+fn find_extra_variables(tera: &Tera, template: &str) -> Vec<String> {
+    let mut variables = ExtraVariables::default();
+    variables.walk_template(tera, template, &mut HashSet::new());
+    let mut defaults: Vec<String> =
+        variables.used.iter().filter(|v| variables.can_default(v)).cloned().collect();
+    defaults.sort();
+    defaults.dedup();
+    defaults
+}
+
+/// Sets the missing `page.extra.*`, `section.extra.*` or `effective_extra` variables to an empty
+/// string in the context. Returns false if none of them is an extra variable we can set.
+fn insert_missing_extras<S: AsRef<str>>(context: &mut Context, variables: &[S]) -> bool {
+    let mut roots: HashMap<&str, Value> = HashMap::new();
+    let mut inserted = false;
+    'variables: for variable in variables {
+        let mut parts = variable.as_ref().split('.');
+        let root = match parts.next() {
+            Some(r @ ("page" | "section")) => r,
+            _ => continue,
+        };
+        let field = match parts.next() {
+            Some(f @ ("extra" | "effective_extra")) => f,
+            _ => continue,
+        };
+        let keys: Vec<&str> = parts.collect();
+        if keys.is_empty() || keys.iter().any(|k| k.is_empty() || k.contains(['[', ']'])) {
+            continue;
+        }
+
+        let value = match roots.entry(root) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => match context.get(root) {
+                Some(v) => e.insert(v.clone()),
+                None => continue,
+            },
+        };
+        let mut current = match value.get_mut(field) {
+            Some(v) => v,
+            None => continue,
+        };
+        for (i, key) in keys.iter().enumerate() {
+            let map = match current {
+                Value::Object(map) => map,
+                _ => continue 'variables,
+            };
+            let default = if i == keys.len() - 1 {
+                Value::String(String::new())
+            } else {
+                Value::Object(Map::new())
+            };
+            current = map.entry(key.to_string()).or_insert(default);
+        }
+        inserted = true;
+    }
+    for (root, value) in roots {
+        context.insert(root, &value);
+    }
+    inserted
+}
+
+/// Turns a Tera rendering error, where the actual cause is nested in the error chain,
+/// into a single message mentioning the template file and the missing variable if there is one.
+fn describe_render_error(name: &str, theme: &Option<String>, error: &tera::Error) -> Error {
+    let mut messages = Vec::new();
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(e) = source {
+        messages.push(e.to_string());
+        source = e.source();
+    }
+
+    let mut template = name.to_string();
+    let mut missing_variable = None;
+    for message in &messages {
+        if let Some(caps) = ERROR_LOCATION_RE.captures(message) {
+            template = caps[1].to_string();
+        }
+        if let Some(caps) = MISSING_VARIABLE_RE.captures(message) {
+            template = caps[2].to_string();
+            missing_variable = Some(caps[1].to_string());
+        }
+    }
+
+    let path = match theme {
+        Some(t) if template.starts_with(&format!("{}/templates/", t)) => {
+            PathBuf::from("themes").join(&template)
+        }
+        _ => PathBuf::from("templates").join(&template),
+    };
+    let message = match missing_variable {
+        Some(variable) => format!(
+            "Variable `{}` used in template `{}` is not defined. \
+            Use the `default` filter or an `is defined` test to handle the content without it.",
+            variable,
+            path.display()
+        ),
+        None => {
+            // The first message is Tera's "Failed to render 'x'", not that useful if there's more
+            let skip = if messages.len() > 1 { 1 } else { 0 };
+            format!("Error in template `{}`: {}", path.display(), messages[skip..].join(": "))
+        }
+    };
+
+    Error::new(LocatedError { kind: "template", path, position: None, message })
+}
+
 /// Rewrites the path of duplicate templates to include the complete theme path
 /// Theme templates  will be injected into site templates, with higher priority for site
 /// templates. To keep a copy of the template in case it's being extended from a site template
@@ -151,8 +489,13 @@ pub fn check_template_fallbacks<'a>(
 mod tests {
     use crate::templates::{check_template_fallbacks, get_shortcodes};
 
-    use super::rewrite_theme_paths;
-    use libs::tera::Tera;
+    use super::{render_template, render_template_with_options, rewrite_theme_paths};
+    use errors::LocatedError;
+    use libs::serde_json;
+    use libs::tera::{Context, Tera, Value};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn can_rewrite_all_paths_of_theme() {
@@ -204,4 +547,94 @@ mod tests {
         let definitions = get_shortcodes(&tera);
         assert_eq!(definitions["youtube"].tera_name, "shortcodes/youtube.html");
     }
+
+    #[test]
+    fn render_errors_mention_template_and_missing_variable() {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(vec![
+            ("base.html", "{% block content %}{% endblock content %}"),
+            (
+                "page.html",
+                "{% extends \"base.html\" %}{% block content %}{{ page.extra.cover | safe }}{% endblock content %}",
+            ),
+        ])
+        .unwrap();
+        let mut context = Context::new();
+        context.insert("page", &serde_json::json!({"extra": {}}));
+
+        let err = render_template("page.html", &tera, context, &None).unwrap_err();
+        let located = err.downcast_ref::<LocatedError>().unwrap();
+        assert_eq!(located.kind, "template");
+        assert_eq!(located.path, PathBuf::from("templates/page.html"));
+        assert_eq!(
+            err.to_string(),
+            "Variable `page.extra.cover` used in template `templates/page.html` is not defined. \
+            Use the `default` filter or an `is defined` test to handle the content without it."
+        );
+    }
+
+    #[test]
+    fn can_render_missing_extra_as_empty_strings() {
+        let mut tera = Tera::default();
+        tera.add_raw_template(
+            "page.html",
+            "[{{ page.extra.cover | safe }}][{{ page.extra.image.alt }}][{{ page.extra.title }}]",
+        )
+        .unwrap();
+        let mut context = Context::new();
+        context.insert("page", &serde_json::json!({"extra": {"title": "Hello"}}));
+
+        assert!(render_template("page.html", &tera, context.clone(), &None).is_err());
+        let res = render_template_with_options("page.html", &tera, context.clone(), &None, false)
+            .unwrap();
+        assert_eq!(res, "[][][Hello]");
+
+        // Only extra is lenient
+        tera.add_raw_template("other.html", "{{ page.title }}").unwrap();
+        assert!(render_template_with_options("other.html", &tera, context, &None, false).is_err());
+    }
+
+    #[test]
+    fn renders_missing_extra_in_one_pass() {
+        static RENDERS: AtomicUsize = AtomicUsize::new(0);
+        let mut tera = Tera::default();
+        tera.register_function("count_render", |_: &HashMap<String, Value>| {
+            RENDERS.fetch_add(1, Ordering::Relaxed);
+            Ok(Value::String(String::new()))
+        });
+        tera.add_raw_templates(vec![
+            ("base.html", "{{ count_render() }}{% block content %}{% endblock content %}"),
+            ("cover.html", "[{{ page.extra.cover.src }}]"),
+            (
+                "page.html",
+                "{% extends \"base.html\" %}{% block content %}{% include \"cover.html\" %}\
+                [{{ page.extra.author | upper }}][{{ page.extra.a ~ page.extra.b }}]{% endblock content %}",
+            ),
+        ])
+        .unwrap();
+        let mut context = Context::new();
+        context.insert("page", &serde_json::json!({"extra": {}}));
+
+        let res = render_template_with_options("page.html", &tera, context, &None, false).unwrap();
+        assert_eq!(res, "[][][]");
+        assert_eq!(RENDERS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn does_not_set_missing_extra_that_are_tested() {
+        let mut tera = Tera::default();
+        tera.add_raw_template(
+            "page.html",
+            "{% if page.extra.cover is defined %}cover{% endif %}\
+            [{{ page.extra.title | default(value=\"Untitled\") }}]\
+            {% if page.extra.image %}[{{ page.extra.image.alt }}]{% endif %}\
+            [{{ page.extra.author }}]",
+        )
+        .unwrap();
+        let mut context = Context::new();
+        context.insert("page", &serde_json::json!({"extra": {}}));
+
+        let res = render_template_with_options("page.html", &tera, context, &None, false).unwrap();
+        assert_eq!(res, "[Untitled][]");
+    }
 }
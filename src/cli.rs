@@ -1,7 +1,7 @@
 use std::net::IpAddr;
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
 
 #[derive(Parser)]
@@ -15,10 +15,20 @@ pub struct Cli {
     #[clap(short = 'c', long, default_value = "config.toml")]
     pub config: PathBuf,
 
+    /// How to print warnings and errors: `json` prints one JSON object per line on stdout
+    #[clap(long, value_enum, global = true, default_value = "human")]
+    pub message_format: MessageFormat,
+
     #[clap(subcommand)]
     pub command: Command,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
 #[derive(Subcommand)]
 pub enum Command {
     /// Create a new Zola project
@@ -49,6 +59,32 @@ pub enum Command {
         /// Include drafts when loading the site
         #[clap(long)]
         drafts: bool,
+
+        /// Include the pages dated in the future when loading the site
+        #[clap(long)]
+        include_future: bool,
+
+        /// Warn about pages not reachable from anywhere and page assets not used by their page
+        #[clap(long)]
+        warn_orphans: bool,
+
+        /// Make the internal links relative so the site can be browsed from the filesystem
+        #[clap(long)]
+        offline: bool,
+
+        /// Warn about the page dates with a time but no offset when there is no `default_timezone`
+        #[clap(long)]
+        strict_dates: bool,
+
+        /// Render the site without writing it first and exit with code 3 if its build hash is
+        /// this one, to skip deploying an unchanged site
+        #[clap(long, value_name = "HASH")]
+        if_changed: Option<String>,
+
+        /// Print how long each step took, the slowest pages to render and how long the pages
+        /// took to render
+        #[clap(long)]
+        timings: bool,
     },
 
     /// Serve the site. Rebuild and reload on change automatically
@@ -89,6 +125,29 @@ pub enum Command {
         /// Default append port to the base url.
         #[clap(long)]
         no_port_append: bool,
+
+        /// Only load the content under that path, eg `content/docs`, and the sections above it.
+        /// Links to the rest of the content only give a warning
+        #[clap(long)]
+        only: Option<PathBuf>,
+
+        /// Only rebuild the site on change, without the web server and livereload.
+        /// The site is written to the output directory exactly like `zola build` would
+        #[clap(long)]
+        watch_only: bool,
+
+        /// Don't inject the livereload script in the pages nor start its websocket server
+        #[clap(long)]
+        no_livereload: bool,
+
+        /// Which port to use for the livereload websocket, another one is picked if it's not available
+        #[clap(long)]
+        ws_port: Option<u16>,
+
+        /// List the pages and sections rendered in the summary printed after each change, and
+        /// the changes that were ignored
+        #[clap(short = 'v', long)]
+        verbose: bool,
     },
 
     /// Try to build the project without rendering it. Checks links
@@ -96,6 +155,25 @@ pub enum Command {
         /// Include drafts when loading the site
         #[clap(long)]
         drafts: bool,
+
+        /// Warn about the page dates with a time but no offset when there is no `default_timezone`
+        #[clap(long)]
+        strict_dates: bool,
+
+        /// Look for accessibility problems in the generated HTML, like skipped heading levels
+        #[clap(long)]
+        a11y: bool,
+    },
+
+    /// Builds the site and publishes it to the target set in the `[deploy]` section of the config
+    Publish {
+        /// Publish the current output directory without building the site first
+        #[clap(long)]
+        no_build: bool,
+
+        /// Print what would be published without doing it
+        #[clap(long)]
+        dry_run: bool,
     },
 
     /// Generate shell completion
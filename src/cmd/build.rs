@@ -5,33 +5,120 @@ use site::Site;
 
 use crate::messages;
 
-pub fn build(
-    root_dir: &Path,
-    config_file: &Path,
-    base_url: Option<&str>,
-    output_dir: Option<&Path>,
-    force: bool,
-    include_drafts: bool,
-) -> Result<()> {
-    let mut site = Site::new(root_dir, config_file)?;
+/// Whether `zola build` wrote the site
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildOutcome {
+    Built,
+    /// The build hash is the one given to `--if-changed`, nothing was written
+    Unchanged,
+}
+
+/// The options of `zola build`, the defaults being the ones of a plain `zola build`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuildOptions<'a> {
+    pub base_url: Option<&'a str>,
+    pub output_dir: Option<&'a Path>,
+    pub force: bool,
+    pub include_drafts: bool,
+    pub include_future: bool,
+    pub warn_orphans: bool,
+    pub offline: bool,
+    pub strict_dates: bool,
+    /// The build hash of the previous build, to only write the site if it changed
+    pub if_changed: Option<&'a str>,
+    pub timings: bool,
+}
+
+pub fn build(root_dir: &Path, config_file: &Path, options: BuildOptions) -> Result<BuildOutcome> {
+    let BuildOptions {
+        base_url,
+        output_dir,
+        force,
+        include_drafts,
+        include_future,
+        warn_orphans,
+        offline,
+        strict_dates,
+        if_changed,
+        timings,
+    } = options;
+    let create_site = || -> Result<Site> {
+        let mut site = Site::new(root_dir, config_file)?;
+        site.set_version(env!("CARGO_PKG_VERSION"));
+        if let Some(output_dir) = output_dir {
+            site.set_output_path(output_dir);
+        }
+        if let Some(b) = base_url {
+            site.set_base_url(b.to_string());
+        }
+        if include_drafts {
+            site.include_drafts();
+        }
+        if include_future {
+            site.include_future();
+        }
+        if offline {
+            site.enable_relative_urls();
+        }
+        Ok(site)
+    };
+
     if let Some(output_dir) = output_dir {
-        if !force && output_dir.exists() {
+        if !force && output_dir.exists() && if_changed.is_none() {
             return Err(Error::msg(format!(
                 "Directory '{}' already exists. Use --force to overwrite.",
                 output_dir.display(),
             )));
         }
-
-        site.set_output_path(output_dir);
     }
-    if let Some(b) = base_url {
-        site.set_base_url(b.to_string());
+    let mut site = create_site()?;
+    // Rendered in memory first, and written only if its hash changed
+    let output_path = site.output_path.clone();
+    if if_changed.is_some() {
+        site.enable_dry_run();
     }
-    if include_drafts {
-        site.include_drafts();
+    if timings {
+        site.enable_timings();
     }
     site.load()?;
+    if if_changed.is_some() && !site.config.build_info.reproducible {
+        console::warn(
+            "`--if-changed` without `reproducible` in the config or `SOURCE_DATE_EPOCH`: the build time, random picks and encrypted pages change the hash of every build.",
+        );
+    }
     messages::notify_site_size(&site);
     messages::warn_about_ignored_pages(&site);
-    site.build()
+    if strict_dates {
+        messages::warn_about_dates_without_offset(&site);
+    }
+    let stats = site.build()?;
+    let hash = stats.build_hash.unwrap_or_default();
+
+    if let Some(expected) = if_changed {
+        if hash == expected.trim() {
+            site.discard_dry_run()?;
+            console::info(&format!("-> Build hash {} is unchanged, nothing to build", hash));
+            return Ok(BuildOutcome::Unchanged);
+        }
+        console::info(&format!("-> Build hash changed from {} to {}", expected.trim(), hash));
+        if let Some(output_dir) = output_dir {
+            if !force && output_dir.exists() {
+                site.discard_dry_run()?;
+                return Err(Error::msg(format!(
+                    "Directory '{}' already exists. Use --force to overwrite.",
+                    output_dir.display(),
+                )));
+            }
+        }
+        site.write_dry_run(&output_path)?;
+    }
+
+    console::plain(&format!("-> Build hash {}", hash));
+    if warn_orphans {
+        messages::warn_about_orphans(&site, true);
+    }
+    if timings {
+        messages::report_timings(&site);
+    }
+    Ok(BuildOutcome::Built)
 }
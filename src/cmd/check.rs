@@ -11,9 +11,12 @@ pub fn check(
     base_path: Option<&str>,
     base_url: Option<&str>,
     include_drafts: bool,
+    strict_dates: bool,
+    a11y: bool,
 ) -> Result<()> {
     let bp = base_path.map(PathBuf::from).unwrap_or_else(|| PathBuf::from(root_dir));
     let mut site = Site::new(bp, config_file)?;
+    site.set_version(env!("CARGO_PKG_VERSION"));
     // Force the checking of external links
     site.config.enable_check_mode();
     if let Some(b) = base_url {
@@ -25,5 +28,13 @@ pub fn check(
     site.load()?;
     messages::check_site_summary(&site);
     messages::warn_about_ignored_pages(&site);
+    messages::warn_about_orphans(&site, false);
+    if strict_dates {
+        messages::warn_about_dates_without_offset(&site);
+    }
+    site.check_templates()?;
+    if a11y {
+        site.check_accessibility()?;
+    }
     Ok(())
 }
@@ -1,9 +1,11 @@
 mod build;
 mod check;
 mod init;
+mod publish;
 mod serve;
 
-pub use self::build::build;
+pub use self::build::{build, BuildOptions, BuildOutcome};
 pub use self::check::check;
 pub use self::init::create_new_project;
+pub use self::publish::publish;
 pub use self::serve::serve;
@@ -26,7 +26,7 @@ use std::future::IntoFuture;
 use std::net::{IpAddr, SocketAddr, TcpListener};
 use std::path::{Path, PathBuf, MAIN_SEPARATOR};
 use std::sync::mpsc::channel;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -48,7 +48,9 @@ use ws::{Message, Sender, WebSocket};
 use errors::{anyhow, Context, Error, Result};
 use site::sass::compile_sass;
 use site::{Site, SITE_CONTENT};
+use utils::data::PAGE_DATA_FILENAMES;
 use utils::fs::{clean_site_output_folder, copy_file, create_directory};
+use utils::net::port_is_available;
 
 use crate::fs_utils::{filter_events, ChangeKind, SimpleFileSystemEventKind};
 use crate::messages;
@@ -84,6 +86,7 @@ async fn handle_request(
     req: Request<Body>,
     mut root: PathBuf,
     base_path: String,
+    ws_port: Option<u16>,
 ) -> Result<Response<Body>> {
     let path_str = req.uri().path();
     if !path_str.starts_with(&base_path) {
@@ -112,9 +115,9 @@ async fn handle_request(
     }
 
     // livereload.js is served using the LIVE_RELOAD str, not a file
-    if path == "livereload.js" {
+    if let (true, Some(port)) = (path == "livereload.js", ws_port) {
         if req.method() == Method::GET {
-            return Ok(livereload_js());
+            return Ok(livereload_js(port));
         } else {
             return Ok(method_not_allowed());
         }
@@ -234,14 +237,20 @@ async fn response_error_injector(
     Ok(req)
 }
 
-fn livereload_js() -> Response<Body> {
+/// The embedded livereload.js, connecting to the websocket on that port instead of the
+/// LiveReload default one
+fn livereload_js(ws_port: u16) -> Response<Body> {
     Response::builder()
         .header(header::CONTENT_TYPE, "text/javascript")
         .status(StatusCode::OK)
-        .body(LIVE_RELOAD.into())
+        .body(livereload_js_with_port(ws_port).into())
         .expect("Could not build livereload.js response")
 }
 
+fn livereload_js_with_port(ws_port: u16) -> String {
+    LIVE_RELOAD.replacen("this.port=35729", &format!("this.port={}", ws_port), 1)
+}
+
 fn in_memory_content(path: &RelativePathBuf, content: &str) -> Response<Body> {
     let content_type = match path.extension() {
         Some(ext) => match ext {
@@ -296,10 +305,15 @@ fn not_found() -> Response<Body> {
         .expect("Could not build Not Found response")
 }
 
-fn rebuild_done_handling(broadcaster: &Sender, res: Result<()>, reload_path: &str) {
+fn rebuild_done_handling(broadcaster: Option<&Sender>, res: Result<()>, reload_path: &str) {
     match res {
         Ok(_) => {
             clear_serve_error();
+            let broadcaster = match broadcaster {
+                Some(b) => b,
+                // Nothing to reload with `--watch-only`
+                None => return,
+            };
             broadcaster
                 .send(format!(
                     r#"
@@ -324,6 +338,30 @@ fn rebuild_done_handling(broadcaster: &Sender, res: Result<()>, reload_path: &st
     }
 }
 
+/// The colocated pages using that file if it's a page data file, eg `content/product/index.md`
+/// and its translations like `content/product/index.fr.md` for `content/product/data.toml`
+fn pages_of_data_file(path: &Path) -> Option<Vec<PathBuf>> {
+    let filename = path.file_name().and_then(|n| n.to_str())?;
+    if !PAGE_DATA_FILENAMES.contains(&filename) {
+        return None;
+    }
+    let is_index = |name: &str| {
+        name == "index.md"
+            || name
+                .strip_prefix("index.")
+                .and_then(|n| n.strip_suffix(".md"))
+                .is_some_and(|lang| !lang.is_empty() && !lang.contains('.'))
+    };
+    let mut pages: Vec<PathBuf> = std::fs::read_dir(path.parent()?)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.file_name().and_then(|n| n.to_str()).is_some_and(is_index))
+        .collect();
+    pages.sort();
+    (!pages.is_empty()).then_some(pages)
+}
+
 fn construct_url(base_url: &str, no_port_append: bool, interface_port: u16) -> String {
     if base_url == "/" {
         return String::from("/");
@@ -368,15 +406,19 @@ fn create_new_site(
     config_file: &Path,
     include_drafts: bool,
     mut no_port_append: bool,
+    only: Option<&Path>,
     ws_port: Option<u16>,
+    watch_only: bool,
+    no_livereload: bool,
 ) -> Result<(Site, SocketAddr, String)> {
     SITE_CONTENT.write().unwrap().clear();
 
     let mut site = Site::new(root_dir, config_file)?;
+    site.set_version(env!("CARGO_PKG_VERSION"));
     let address = SocketAddr::new(interface, interface_port);
 
     // if no base URL provided, use socket address
-    let base_url = base_url.map_or_else(
+    let served_base_url = base_url.map_or_else(
         || {
             no_port_append = true;
             address.to_string()
@@ -384,14 +426,21 @@ fn create_new_site(
         |u| u.to_string(),
     );
 
-    let mut constructed_base_url = construct_url(&base_url, no_port_append, interface_port);
+    let mut constructed_base_url = construct_url(&served_base_url, no_port_append, interface_port);
 
     if !site.config.base_url.ends_with('/') && constructed_base_url != "/" {
         constructed_base_url.truncate(constructed_base_url.len() - 1);
     }
 
-    site.enable_serve_mode();
-    site.set_base_url(constructed_base_url.clone());
+    if watch_only {
+        // Built like `zola build` so the output is the same as in production
+        if let Some(base_url) = base_url {
+            site.set_base_url(base_url.to_string());
+        }
+    } else {
+        site.enable_serve_mode();
+        site.set_base_url(constructed_base_url.clone());
+    }
     if let Some(output_dir) = output_dir {
         if !force && output_dir.exists() {
             return Err(Error::msg(format!(
@@ -404,8 +453,17 @@ fn create_new_site(
     if include_drafts {
         site.include_drafts();
     }
+    // Serving is meant to preview the upcoming pages, unless the site is built like in production
+    if !watch_only {
+        site.include_future();
+    }
+    if let Some(only) = only {
+        site.load_only(only)?;
+    }
     site.load()?;
-    if let Some(p) = ws_port {
+    if watch_only || no_livereload {
+        // No livereload script to inject
+    } else if let Some(p) = ws_port {
         site.enable_live_reload_with_port(p);
     } else {
         site.enable_live_reload(interface_port);
@@ -429,9 +487,27 @@ pub fn serve(
     include_drafts: bool,
     fast_rebuild: bool,
     no_port_append: bool,
+    only: Option<&Path>,
+    watch_only: bool,
+    no_livereload: bool,
+    requested_ws_port: Option<u16>,
+    verbose: bool,
     utc_offset: UtcOffset,
 ) -> Result<()> {
+    if verbose {
+        console::enable_debug_output();
+    }
     let start = Instant::now();
+    let requested_ws_port = match requested_ws_port {
+        Some(port) if port == interface_port || !port_is_available(port) => {
+            console::warn(&format!(
+                "The port {} requested for livereload is not available, using another one",
+                port
+            ));
+            None
+        }
+        port => port,
+    };
     let (mut site, bind_address, constructed_base_url) = create_new_site(
         root_dir,
         interface,
@@ -442,8 +518,15 @@ pub fn serve(
         config_file,
         include_drafts,
         no_port_append,
-        None,
+        only,
+        requested_ws_port,
+        watch_only,
+        no_livereload,
     )?;
+    // The slowest pages are shown in the rebuild summary
+    if verbose {
+        site.enable_timings();
+    }
     let base_path = match constructed_base_url.splitn(4, '/').nth(3) {
         Some(path) => format!("/{}", path),
         None => "/".to_string(),
@@ -452,7 +535,7 @@ pub fn serve(
     messages::report_elapsed_time(start);
 
     // Stop right there if we can't bind to the address
-    if (TcpListener::bind(bind_address)).is_err() {
+    if !watch_only && (TcpListener::bind(bind_address)).is_err() {
         return Err(anyhow!("Cannot start server on address {}.", bind_address));
     }
 
@@ -471,6 +554,7 @@ pub fn serve(
         ("sass", WatchMode::Condition(site.config.compile_sass), RecursiveMode::Recursive),
         ("static", WatchMode::Optional, RecursiveMode::Recursive),
         ("templates", WatchMode::Optional, RecursiveMode::Recursive),
+        ("data", WatchMode::Optional, RecursiveMode::Recursive),
         ("themes", WatchMode::Condition(site.config.theme.is_some()), RecursiveMode::Recursive),
     ];
 
@@ -500,14 +584,15 @@ pub fn serve(
     }
 
     let ws_port = site.live_reload;
-    let ws_address = format!("{}:{}", interface, ws_port.unwrap());
     let output_path = site.output_path.clone();
     create_directory(&output_path)?;
 
-    // static_root needs to be canonicalized because we do the same for the http server.
-    let static_root = std::fs::canonicalize(&output_path).unwrap();
+    let broadcaster = if watch_only {
+        None
+    } else {
+        // static_root needs to be canonicalized because we do the same for the http server.
+        let static_root = std::fs::canonicalize(&output_path).unwrap();
 
-    let broadcaster = {
         thread::spawn(move || {
             let rt = tokio::runtime::Builder::new_current_thread()
                 .enable_all()
@@ -519,12 +604,13 @@ pub fn serve(
                     let static_root = static_root.clone();
                     let base_path = base_path.clone();
 
-                    async {
+                    async move {
                         Ok::<_, hyper::Error>(service_fn(move |req| {
                             response_error_injector(handle_request(
                                 req,
                                 static_root.clone(),
                                 base_path.clone(),
+                                ws_port,
                             ))
                         }))
                     }
@@ -532,13 +618,13 @@ pub fn serve(
 
                 let server = Server::bind(&bind_address).serve(make_service);
 
-                println!(
+                console::plain(&format!(
                     "Web server is available at {} (bound to {})\n",
                     &constructed_base_url, &bind_address
-                );
+                ));
                 if open {
                     if let Err(err) = open::that(&constructed_base_url) {
-                        eprintln!("Failed to open URL in your browser: {}", err);
+                        console::error(&format!("Failed to open URL in your browser: {}", err));
                     }
                 }
 
@@ -546,36 +632,42 @@ pub fn serve(
             });
         });
 
-        // The websocket for livereload
-        let ws_server = WebSocket::new(|output: Sender| {
-            move |msg: Message| {
-                if msg.into_text().unwrap().contains("\"hello\"") {
-                    return output.send(Message::text(
-                        r#"
-                        {
-                            "command": "hello",
-                            "protocols": [ "http://livereload.com/protocols/official-7" ],
-                            "serverName": "Zola"
+        match ws_port {
+            // No websocket with `--no-livereload`
+            None => None,
+            Some(ws_port) => {
+                let ws_address = format!("{}:{}", interface, ws_port);
+                let ws_server = WebSocket::new(|output: Sender| {
+                    move |msg: Message| {
+                        if msg.into_text().unwrap().contains("\"hello\"") {
+                            return output.send(Message::text(
+                                r#"
+                            {
+                                "command": "hello",
+                                "protocols": [ "http://livereload.com/protocols/official-7" ],
+                                "serverName": "Zola"
+                            }
+                        "#,
+                            ));
                         }
-                    "#,
-                    ));
-                }
-                Ok(())
-            }
-        })
-        .unwrap();
+                        Ok(())
+                    }
+                })
+                .unwrap();
 
-        let broadcaster = ws_server.broadcaster();
+                let broadcaster = ws_server.broadcaster();
 
-        let ws_server = ws_server
-            .bind(&*ws_address)
-            .map_err(|_| anyhow!("Cannot bind to address {} for the websocket server. Maybe the port is already in use?", &ws_address))?;
+                let ws_server = ws_server
+                .bind(&*ws_address)
+                .map_err(|_| anyhow!("Cannot bind to address {} for the websocket server. Maybe the port is already in use?", &ws_address))?;
 
-        thread::spawn(move || {
-            ws_server.run().unwrap();
-        });
+                thread::spawn(move || {
+                    ws_server.run().unwrap();
+                });
 
-        broadcaster
+                Some(broadcaster)
+            }
+        }
     };
 
     // We watch for changes in the config by monitoring its parent directory, but we ignore all
@@ -588,20 +680,40 @@ pub fn serve(
         .map(|w| if w == root_dir_str { config_name } else { w })
         .collect::<Vec<&str>>()
         .join(",");
-    println!("Listening for changes in {}{}{{{}}}", root_dir.display(), MAIN_SEPARATOR, watch_list);
+    console::plain(&format!(
+        "Listening for changes in {}{}{{{}}}",
+        root_dir.display(),
+        MAIN_SEPARATOR,
+        watch_list
+    ));
 
     let preserve_dotfiles_in_output = site.config.preserve_dotfiles_in_output;
 
-    println!("Press Ctrl+C to stop\n");
-    // Clean the output folder on ctrl+C
-    ctrlc::set_handler(move || {
-        match clean_site_output_folder(&output_path, preserve_dotfiles_in_output) {
-            Ok(()) => (),
-            Err(e) => println!("Errored while cleaning output folder: {}", e),
-        }
-        ::std::process::exit(0);
-    })
-    .expect("Error setting Ctrl-C handler");
+    if watch_only {
+        console::plain(&format!("Building the site in {} on change", output_path.display()));
+    }
+    console::plain("Press Ctrl+C to stop\n");
+    // Held while handling changes so stopping waits for the rebuild in progress
+    let rebuilding = Arc::new(Mutex::new(()));
+    if watch_only {
+        let rebuilding = rebuilding.clone();
+        ctrlc::set_handler(move || {
+            // The output is kept, it's the whole point
+            let _rebuilding = rebuilding.lock();
+            ::std::process::exit(0);
+        })
+        .expect("Error setting Ctrl-C handler");
+    } else {
+        // Clean the output folder on ctrl+C
+        ctrlc::set_handler(move || {
+            match clean_site_output_folder(&output_path, preserve_dotfiles_in_output) {
+                Ok(()) => (),
+                Err(e) => console::error(&format!("Errored while cleaning output folder: {}", e)),
+            }
+            ::std::process::exit(0);
+        })
+        .expect("Error setting Ctrl-C handler");
+    }
 
     let reload_sass = |site: &Site, paths: &Vec<&PathBuf>| {
         let combined_paths =
@@ -609,20 +721,22 @@ pub fn serve(
         let msg = format!("-> Sass file(s) changed {}", combined_paths);
         console::info(&msg);
         rebuild_done_handling(
-            &broadcaster,
-            compile_sass(&site.base_path, &site.output_path),
+            broadcaster.as_ref(),
+            compile_sass(&site.base_path, &site.output_path).map(|_| ()),
             &site.sass_path.to_string_lossy(),
         );
     };
 
-    let reload_templates = |site: &mut Site| {
-        rebuild_done_handling(
-            &broadcaster,
-            site.reload_templates(),
-            &site.templates_path.to_string_lossy(),
-        );
+    let reload_templates = |site: &mut Site, paths: &[&Path]| {
+        let res = site.reload_templates(paths).map(|_| ());
+        let reloaded = res.is_ok();
+        rebuild_done_handling(broadcaster.as_ref(), res, &site.templates_path.to_string_lossy());
+        reloaded
     };
 
+    let report_rebuild =
+        |site: &Site, trigger: &str| messages::report_rebuild(site, trigger, verbose);
+
     let copy_static = |site: &Site, path: &Path, partial_path: &Path| {
         // Do nothing if the file/dir is on the ignore list
         if let Some(gs) = &site.config.ignored_static_globset {
@@ -630,31 +744,25 @@ pub fn serve(
                 return;
             }
         }
-        // Do nothing if the file/dir was deleted
-        if !path.exists() {
-            return;
-        }
 
-        let msg = if path.is_dir() {
+        let msg = if !path.exists() {
+            format!("-> Static file or directory removed {}", path.display())
+        } else if path.is_dir() {
             format!("-> Directory in `static` folder changed {}", path.display())
         } else {
             format!("-> Static file changed {}", path.display())
         };
 
         console::info(&msg);
-        if path.is_dir() {
-            rebuild_done_handling(
-                &broadcaster,
-                site.copy_static_directories(),
-                &path.to_string_lossy(),
-            );
+        // The browser still gets reloaded for removed files so it notices they are gone
+        let res = if !path.exists() {
+            site.remove_static_file(path)
+        } else if path.is_dir() {
+            site.copy_static_directory(path)
         } else {
-            rebuild_done_handling(
-                &broadcaster,
-                copy_file(path, &site.output_path, &site.static_path, site.config.hard_link_static),
-                &partial_path.to_string_lossy(),
-            );
-        }
+            copy_file(path, &site.output_path, &site.static_path, site.config.copy_mode)
+        };
+        rebuild_done_handling(broadcaster.as_ref(), res, &partial_path.to_string_lossy());
     };
 
     let recreate_site = || match create_new_site(
@@ -662,16 +770,23 @@ pub fn serve(
         interface,
         interface_port,
         output_dir,
-        force,
+        // The output directory was already checked when starting, we are the ones writing to it
+        force || watch_only,
         base_url,
         config_file,
         include_drafts,
         no_port_append,
+        only,
         ws_port,
+        watch_only,
+        no_livereload,
     ) {
-        Ok((s, _, _)) => {
+        Ok((mut s, _, _)) => {
+            if verbose {
+                s.enable_timings();
+            }
             clear_serve_error();
-            rebuild_done_handling(&broadcaster, Ok(()), "/x.js");
+            rebuild_done_handling(broadcaster.as_ref(), Ok(()), "/x.js");
 
             Some(s)
         }
@@ -688,11 +803,13 @@ pub fn serve(
     loop {
         match rx.recv() {
             Ok(Ok(events)) => {
+                let _rebuilding = rebuilding.lock().unwrap();
                 let changes = filter_events(
                     events,
                     root_dir,
                     &config_path,
                     &site.config.ignored_content_globset,
+                    &site.ignore_rules,
                 );
                 if changes.is_empty() {
                     continue;
@@ -703,20 +820,28 @@ pub fn serve(
                     let current_time =
                         OffsetDateTime::now_utc().to_offset(utc_offset).format(&format);
                     if let Ok(time_str) = current_time {
-                        println!("Change detected @ {}", time_str);
+                        console::plain(&format!("Change detected @ {}", time_str));
                     } else {
                         // if formatting fails for some reason
-                        println!("Change detected");
+                        console::plain("Change detected");
                     };
 
                     let start = Instant::now();
                     match change_kind {
                         ChangeKind::Content => {
                             for (_, full_path, event_kind) in change_group.iter() {
+                                if !site.is_content_loaded(full_path) {
+                                    continue;
+                                }
                                 console::info(&format!(
                                     "-> Content changed {}",
                                     full_path.display()
                                 ));
+                                let trigger = full_path
+                                    .strip_prefix(root_dir)
+                                    .unwrap_or(full_path)
+                                    .display()
+                                    .to_string();
 
                                 let can_do_fast_reload =
                                     *event_kind != SimpleFileSystemEventKind::Remove;
@@ -729,8 +854,14 @@ pub fn serve(
                                             .to_string_lossy();
                                         let res = if filename == "_index.md" {
                                             site.add_and_render_section(full_path)
-                                        } else if filename.ends_with(".md") {
+                                        } else if site.config.markup.is_content_file(full_path) {
                                             site.add_and_render_page(full_path)
+                                        } else if let Some(pages) = pages_of_data_file(full_path) {
+                                            // The data of a colocated page changed, re-render that page
+                                            // in every language
+                                            pages
+                                                .iter()
+                                                .try_for_each(|p| site.add_and_render_page(p))
                                         } else {
                                             // an asset changed? a folder renamed?
                                             // should we make it smarter so it doesn't reload the whole site?
@@ -740,22 +871,26 @@ pub fn serve(
                                         if res.is_err() {
                                             if let Some(s) = recreate_site() {
                                                 site = s;
+                                                report_rebuild(&site, &trigger);
                                             }
                                         } else {
                                             rebuild_done_handling(
-                                                &broadcaster,
+                                                broadcaster.as_ref(),
                                                 res,
                                                 &full_path.to_string_lossy(),
                                             );
+                                            report_rebuild(&site, &trigger);
                                         }
                                     } else {
                                         // Should we be smarter than that? Is it worth it?
                                         if let Some(s) = recreate_site() {
                                             site = s;
+                                            report_rebuild(&site, &trigger);
                                         }
                                     }
                                 } else if let Some(s) = recreate_site() {
                                     site = s;
+                                    report_rebuild(&site, &trigger);
                                 }
                             }
                         }
@@ -771,18 +906,44 @@ pub fn serve(
                                 .join(", ");
                             let msg = format!("-> Template file(s) changed {}", combined_paths);
                             console::info(&msg);
+                            let trigger = partial_paths
+                                .iter()
+                                .map(|p| p.to_string_lossy().trim_start_matches('/').to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ");
 
-                            let shortcodes_updated = partial_paths
+                            console::plain("Reloading only template");
+                            let full_paths: Vec<&Path> =
+                                full_paths.iter().map(|p| p.as_path()).collect();
+                            if reload_templates(&mut site, &full_paths) {
+                                report_rebuild(&site, &trigger);
+                            }
+                        }
+                        ChangeKind::Shortcodes => {
+                            let full_paths: Vec<&Path> =
+                                change_group.iter().map(|(_, p, _)| p.as_path()).collect();
+                            let combined_paths = full_paths
                                 .iter()
-                                .any(|p| p.starts_with("/templates/shortcodes"));
-                            // Rebuild site if shortcodes change; otherwise, just update template.
-                            if shortcodes_updated {
-                                if let Some(s) = recreate_site() {
-                                    site = s;
-                                }
-                            } else {
-                                println!("Reloading only template");
-                                reload_templates(&mut site)
+                                .map(|p| p.display().to_string())
+                                .collect::<Vec<String>>()
+                                .join(", ");
+                            console::info(&format!("-> Shortcode(s) changed {}", combined_paths));
+                            let trigger = change_group
+                                .iter()
+                                .map(|(p, _, _)| {
+                                    p.to_string_lossy().trim_start_matches('/').to_string()
+                                })
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            let res = site.reload_shortcodes(&full_paths).map(|_| ());
+                            let reloaded = res.is_ok();
+                            rebuild_done_handling(
+                                broadcaster.as_ref(),
+                                res,
+                                &site.templates_path.to_string_lossy(),
+                            );
+                            if reloaded {
+                                report_rebuild(&site, &trigger);
                             }
                         }
                         ChangeKind::StaticFiles => {
@@ -794,20 +955,33 @@ pub fn serve(
                             let full_paths = change_group.iter().map(|(_, p, _)| p).collect();
                             reload_sass(&site, &full_paths);
                         }
+                        ChangeKind::Data => {
+                            // Any template or shortcode might use it so we rebuild everything
+                            console::info("-> Data changed.");
+
+                            if let Some(s) = recreate_site() {
+                                site = s;
+                                report_rebuild(&site, "data");
+                            }
+                        }
                         ChangeKind::Themes => {
                             // No need to iterate over change group since we're rebuilding the site.
                             console::info("-> Themes changed.");
 
                             if let Some(s) = recreate_site() {
                                 site = s;
+                                report_rebuild(&site, "themes");
                             }
                         }
+                        // Already left out by `filter_events`
+                        ChangeKind::Other => (),
                         ChangeKind::Config => {
                             // No need to iterate over change group since we're rebuilding the site.
                             console::info("-> Config changed. The browser needs to be refreshed to make the changes visible.");
 
                             if let Some(s) = recreate_site() {
                                 site = s;
+                                report_rebuild(&site, config_name);
                             }
                         }
                     };
@@ -822,7 +996,7 @@ pub fn serve(
 
 #[cfg(test)]
 mod tests {
-    use super::{construct_url, create_new_site};
+    use super::{construct_url, create_new_site, livereload_js_with_port, pages_of_data_file};
     use crate::get_config_file_path;
     use libs::url::Url;
     use std::net::{IpAddr, SocketAddr};
@@ -865,6 +1039,48 @@ mod tests {
         assert_eq!(result, "http://example.com:8080/");
     }
 
+    #[test]
+    fn test_livereload_js_uses_ws_port() {
+        let js = livereload_js_with_port(1234);
+        assert!(js.contains("this.port=1234"));
+        assert!(!js.contains("this.port=35729"));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_create_new_site_watch_only() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let output_dir = tmp_dir.path().join("public");
+        let cli_dir = Path::new("./test_site").canonicalize().unwrap();
+        let cli_config = Path::new("./test_site/config.toml").canonicalize().unwrap();
+        let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli_config);
+
+        let (site, _, _) = create_new_site(
+            &root_dir,
+            IpAddr::from_str("127.0.0.1").unwrap(),
+            1111,
+            Some(&output_dir),
+            false,
+            None,
+            &config_file,
+            false,
+            false,
+            None,
+            None,
+            true,
+            false,
+        )
+        .unwrap();
+
+        // Built like `zola build`: on disk, with the base_url of the config and no livereload
+        assert!(!site.config.is_in_serve_mode());
+        assert_eq!(site.config.base_url, "https://replace-this-with-your-url.com");
+        assert_eq!(site.live_reload, None);
+        let index = std::fs::read_to_string(output_dir.join("index.html")).unwrap();
+        assert!(index.contains("https://replace-this-with-your-url.com"));
+        assert!(!index.contains("livereload.js"));
+    }
+
     fn create_and_verify_new_site(
         interface: IpAddr,
         interface_port: u16,
@@ -894,7 +1110,10 @@ mod tests {
             &config_file,
             include_drafts,
             no_port_append,
+            None,
             ws_port,
+            false,
+            false,
         )
         .unwrap();
 
@@ -909,8 +1128,8 @@ mod tests {
         assert_eq!(site.static_path, root_dir.join("static"));
 
         let base_url = Url::parse(&expected_base_url).unwrap();
-        for (_, permalink) in site.permalinks {
-            let permalink_url = Url::parse(&permalink).unwrap();
+        for permalink in site.permalinks.values() {
+            let permalink_url = Url::parse(permalink).unwrap();
             assert_eq!(base_url.scheme(), permalink_url.scheme());
             assert_eq!(base_url.host(), permalink_url.host());
             assert_eq!(base_url.port(), permalink_url.port());
@@ -1059,4 +1278,22 @@ mod tests {
             expected_base_url,
         );
     }
+
+    #[test]
+    fn test_pages_of_data_file_in_every_language() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        for name in ["index.md", "index.fr.md", "data.toml", "cat.jpg", "index.fr.md.bak"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        assert_eq!(
+            pages_of_data_file(&dir.join("data.toml")),
+            Some(vec![dir.join("index.fr.md"), dir.join("index.md")])
+        );
+        assert_eq!(pages_of_data_file(&dir.join("cat.jpg")), None);
+        std::fs::remove_file(dir.join("index.md")).unwrap();
+        std::fs::remove_file(dir.join("index.fr.md")).unwrap();
+        assert_eq!(pages_of_data_file(&dir.join("data.toml")), None);
+    }
 }
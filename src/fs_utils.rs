@@ -1,22 +1,29 @@
 //! Utilities to simplify working with events raised by the `notify*` family of file system
 //! event-watching libraries.
 
+use config::extra_schema::EXTRA_SCHEMA_FILENAME;
 use libs::ahash::HashMap;
 use libs::globset::GlobSet;
 use notify_debouncer_full::notify::event::*;
 use notify_debouncer_full::DebouncedEvent;
 use std::fs::read_dir;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf, Prefix};
 use utils::fs::is_temp_file;
+use utils::globs::{IgnoreRules, ZOLAIGNORE_FILENAME};
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub enum ChangeKind {
     Content,
     Templates,
+    /// The templates in `templates/shortcodes`, which are used when rendering the markdown
+    Shortcodes,
     Themes,
     StaticFiles,
     Sass,
+    Data,
     Config,
+    /// A path that isn't in any of the directories we watch, which is ignored
+    Other,
 }
 
 /// This enum abstracts over the fine-grained group of enums in `notify`.
@@ -47,7 +54,10 @@ fn get_relevant_event_kind(event_kind: &EventKind) -> Option<SimpleFileSystemEve
         | EventKind::Modify(ModifyKind::Metadata(MetadataKind::Permissions))
         | EventKind::Modify(ModifyKind::Metadata(MetadataKind::Ownership))
         | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => Some(SimpleFileSystemEventKind::Modify),
-        EventKind::Remove(RemoveKind::File) | EventKind::Remove(RemoveKind::Folder) => {
+        EventKind::Remove(RemoveKind::File)
+        | EventKind::Remove(RemoveKind::Folder)
+        // The path a file or directory was moved away from
+        | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
             Some(SimpleFileSystemEventKind::Remove)
         }
         _ => None,
@@ -59,6 +69,7 @@ pub fn filter_events(
     root_dir: &Path,
     config_path: &Path,
     ignored_content_globset: &Option<GlobSet>,
+    ignore_rules: &IgnoreRules,
 ) -> HashMap<ChangeKind, Vec<MeaningfulEvent>> {
     // Arrange events from oldest to newest.
     events.sort_by(|e1, e2| e1.time.cmp(&e2.time));
@@ -68,12 +79,23 @@ pub fn filter_events(
     let mut meaningful_events: HashMap<PathBuf, (PathBuf, SimpleFileSystemEventKind, ChangeKind)> =
         HashMap::default();
 
+    let static_dir = root_dir.join("static");
+    let mut paths = Vec::new();
     for event in events.iter() {
-        let simple_kind = get_relevant_event_kind(&event.event.kind);
-        if simple_kind.is_none() {
+        // A rename with both paths is the removal of the first one and a change of the second one
+        if event.event.kind == EventKind::Modify(ModifyKind::Name(RenameMode::Both))
+            && event.event.paths.len() == 2
+        {
+            paths.push((event.event.paths[0].clone(), SimpleFileSystemEventKind::Remove));
+            paths.push((event.event.paths[1].clone(), SimpleFileSystemEventKind::Modify));
             continue;
         }
 
+        let simple_kind = match get_relevant_event_kind(&event.event.kind) {
+            Some(kind) => kind,
+            None => continue,
+        };
+
         // We currently only handle notify events that report a single path per event.
         if event.event.paths.len() != 1 {
             console::error(&format!(
@@ -82,19 +104,31 @@ pub fn filter_events(
             ));
             continue;
         }
-        let path = event.event.paths[0].clone();
+        paths.push((event.event.paths[0].clone(), simple_kind));
+    }
 
+    for (path, simple_kind) in paths {
         // Since we debounce things, some files might already not exist anymore by the
-        // time we get to them
-        if !path.exists() {
+        // time we get to them. Removed static files still need to be removed from the output.
+        let is_static_removal =
+            simple_kind == SimpleFileSystemEventKind::Remove && path.starts_with(&static_dir);
+        if !path.exists() && !is_static_removal {
+            continue;
+        }
+
+        if is_ignored_file(ignored_content_globset, &root_dir.join("content"), &path) {
             continue;
         }
 
-        if is_ignored_file(ignored_content_globset, &path) {
+        // `.zolaignore` has no extension, which is what most temporary files look like, and
+        // neither do the directories moved in or out of `static`
+        let is_static_dir = path.starts_with(&static_dir) && (is_static_removal || path.is_dir());
+        if is_temp_file(&path) && !is_zolaignore(root_dir, &path) && !is_static_dir {
             continue;
         }
 
-        if is_temp_file(&path) {
+        // Eg `npm install` in an ignored `node_modules` should not trigger any rebuild
+        if ignore_rules.is_ignored(&path, path.is_dir()) {
             continue;
         }
 
@@ -105,12 +139,21 @@ pub fn filter_events(
 
         // Ignore ordinary files peer to config.toml. This assumes all other files we care
         // about are nested more deeply than config.toml or are directories peer to config.toml.
-        if path != config_path && path.is_file() && path.parent() == config_path.parent() {
+        if path != config_path
+            && !is_extra_schema(root_dir, &path)
+            && !is_zolaignore(root_dir, &path)
+            && path.is_file()
+            && path.parent() == config_path.parent()
+        {
             continue;
         }
 
         let (change_k, partial_p) = detect_change_kind(root_dir, &path, config_path);
-        meaningful_events.insert(path, (partial_p, simple_kind.unwrap(), change_k));
+        if change_k == ChangeKind::Other {
+            console::debug(&format!("Ignoring a change in an unexpected path: {}", path.display()));
+            continue;
+        }
+        meaningful_events.insert(path, (partial_p, simple_kind, change_k));
     }
 
     // Bin changes by change kind to support later iteration over batches of changes.
@@ -123,13 +166,28 @@ pub fn filter_events(
     changes
 }
 
-fn is_ignored_file(ignored_content_globset: &Option<GlobSet>, path: &Path) -> bool {
-    match ignored_content_globset {
-        Some(gs) => gs.is_match(path),
-        None => false,
+/// `ignored_content` patterns are matched against the path relative to the content directory
+fn is_ignored_file(
+    ignored_content_globset: &Option<GlobSet>,
+    content_dir: &Path,
+    path: &Path,
+) -> bool {
+    match (ignored_content_globset, path.strip_prefix(content_dir)) {
+        (Some(gs), Ok(relative_path)) => gs.is_match(relative_path),
+        _ => false,
     }
 }
 
+/// The extra schema is validated when loading the site, like the config
+fn is_extra_schema(root_dir: &Path, path: &Path) -> bool {
+    path == root_dir.join(EXTRA_SCHEMA_FILENAME)
+}
+
+/// The ignore rules are loaded with the site, like the config
+fn is_zolaignore(root_dir: &Path, path: &Path) -> bool {
+    path == root_dir.join(ZOLAIGNORE_FILENAME)
+}
+
 /// Check if the directory at path contains any file
 fn is_folder_empty(dir: &Path) -> bool {
     // Can panic if we don't have the rights I guess?
@@ -137,13 +195,59 @@ fn is_folder_empty(dir: &Path) -> bool {
     read_dir(dir).expect("Failed to read a directory to see if it was empty").next().is_none()
 }
 
+/// Makes the paths reported by the watcher comparable with the root of the site: they can be
+/// verbatim paths (`\\?\C:\...`) or use another casing of the drive letter on Windows, or go
+/// through a symlink.
+fn normalize_path(path: &Path) -> PathBuf {
+    // Removed files can't be canonicalized, only their closest existing ancestor
+    let canonical = path
+        .ancestors()
+        .find_map(|a| Some(a.canonicalize().ok()?.join(path.strip_prefix(a).ok()?)))
+        .unwrap_or_else(|| path.to_path_buf());
+
+    let mut components = canonical.components();
+    let prefix = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => {
+                format!("{}:\\", letter.to_ascii_uppercase() as char)
+            }
+            Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share) => {
+                format!("\\\\{}\\{}\\", server.to_string_lossy(), share.to_string_lossy())
+            }
+            _ => return canonical,
+        },
+        _ => return canonical,
+    };
+    let mut normalized = PathBuf::from(prefix);
+    normalized.extend(components.filter(|c| *c != Component::RootDir));
+    normalized
+}
+
+/// The path relative to the root of the site, or `None` if it isn't in it.
+/// Relative paths are considered to be relative to the root already.
+fn relative_to_root(root: &Path, path: &Path) -> Option<PathBuf> {
+    if path.is_relative() {
+        return Some(path.to_path_buf());
+    }
+    if let Ok(relative) = path.strip_prefix(root) {
+        return Some(relative.to_path_buf());
+    }
+    normalize_path(path).strip_prefix(normalize_path(root)).ok().map(|p| p.to_path_buf())
+}
+
 /// Detect what changed from the given path so we have an idea what needs
 /// to be reloaded
 fn detect_change_kind(pwd: &Path, path: &Path, config_path: &Path) -> (ChangeKind, PathBuf) {
+    let relative = match relative_to_root(pwd, path) {
+        Some(relative) => relative,
+        None => return (ChangeKind::Other, path.to_path_buf()),
+    };
     let mut partial_path = PathBuf::from("/");
-    partial_path.push(path.strip_prefix(pwd).unwrap_or(path));
+    partial_path.push(&relative);
 
-    let change_kind = if partial_path.starts_with("/templates") {
+    let change_kind = if partial_path.starts_with("/templates/shortcodes") {
+        ChangeKind::Shortcodes
+    } else if partial_path.starts_with("/templates") {
         ChangeKind::Templates
     } else if partial_path.starts_with("/themes") {
         ChangeKind::Themes
@@ -153,10 +257,15 @@ fn detect_change_kind(pwd: &Path, path: &Path, config_path: &Path) -> (ChangeKin
         ChangeKind::StaticFiles
     } else if partial_path.starts_with("/sass") {
         ChangeKind::Sass
-    } else if path == config_path {
+    } else if partial_path.starts_with("/data") {
+        ChangeKind::Data
+    } else if Some(&relative) == relative_to_root(pwd, config_path).as_ref()
+        || relative == Path::new(EXTRA_SCHEMA_FILENAME)
+        || relative == Path::new(ZOLAIGNORE_FILENAME)
+    {
         ChangeKind::Config
     } else {
-        unreachable!("Got a change in an unexpected path: {}", partial_path.display());
+        ChangeKind::Other
     };
 
     (change_kind, partial_path)
@@ -164,12 +273,16 @@ fn detect_change_kind(pwd: &Path, path: &Path, config_path: &Path) -> (ChangeKin
 
 #[cfg(test)]
 mod tests {
+    use libs::globset::{Glob, GlobSetBuilder};
     use notify_debouncer_full::notify::event::*;
     use std::path::{Path, PathBuf};
 
+    use notify_debouncer_full::DebouncedEvent;
+    use utils::globs::IgnoreRules;
+
     use super::{
-        detect_change_kind, get_relevant_event_kind, is_temp_file, ChangeKind,
-        SimpleFileSystemEventKind,
+        detect_change_kind, filter_events, get_relevant_event_kind, is_ignored_file, is_temp_file,
+        ChangeKind, SimpleFileSystemEventKind,
     };
 
     // This test makes sure we at least have code coverage on the `notify` event kinds we care
@@ -215,6 +328,10 @@ mod tests {
             ),
             (EventKind::Remove(RemoveKind::File), Some(SimpleFileSystemEventKind::Remove)),
             (EventKind::Remove(RemoveKind::Folder), Some(SimpleFileSystemEventKind::Remove)),
+            (
+                EventKind::Modify(ModifyKind::Name(RenameMode::From)),
+                Some(SimpleFileSystemEventKind::Remove),
+            ),
         ];
         for (case, expected) in cases.iter() {
             let ek = get_relevant_event_kind(&case);
@@ -222,6 +339,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn keeps_removals_and_renames_in_static() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("static/new-css")).unwrap();
+        std::fs::write(root.join("static/new-css/site.css"), "").unwrap();
+        let event = |kind, paths: &[&str]| {
+            DebouncedEvent::from(Event {
+                kind,
+                paths: paths.iter().map(|p| root.join(p)).collect(),
+                attrs: Default::default(),
+            })
+        };
+        let events = vec![
+            event(EventKind::Remove(RemoveKind::File), &["static/old.css"]),
+            event(
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                &["static/css", "static/new-css"],
+            ),
+            // Deleted content is still left to the next full rebuild
+            event(EventKind::Remove(RemoveKind::File), &["content/deleted.md"]),
+        ];
+
+        let changes =
+            filter_events(events, root, &root.join("config.toml"), &None, &IgnoreRules::empty());
+        assert_eq!(changes.len(), 1);
+        let mut static_changes: Vec<_> = changes[&ChangeKind::StaticFiles]
+            .iter()
+            .map(|(partial, _, kind)| (partial.clone(), kind.clone()))
+            .collect();
+        static_changes.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            static_changes,
+            vec![
+                (PathBuf::from("/static/css"), SimpleFileSystemEventKind::Remove),
+                (PathBuf::from("/static/new-css"), SimpleFileSystemEventKind::Modify),
+                (PathBuf::from("/static/old.css"), SimpleFileSystemEventKind::Remove),
+            ]
+        );
+    }
+
     #[test]
     fn can_recognize_temp_files() {
         let test_cases = vec![
@@ -243,6 +401,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn can_ignore_content_relative_to_content_dir() {
+        let mut gsb = GlobSetBuilder::new();
+        gsb.add(Glob::new("drafts/**").unwrap());
+        let globset = Some(gsb.build().unwrap());
+        let content_dir = Path::new("/home/vincent/site/content");
+
+        assert!(is_ignored_file(&globset, content_dir, &content_dir.join("drafts/hello.md")));
+        assert!(!is_ignored_file(&globset, content_dir, &content_dir.join("posts/hello.md")));
+        assert!(!is_ignored_file(
+            &globset,
+            content_dir,
+            Path::new("/home/vincent/site/templates/drafts/hello.html")
+        ));
+    }
+
     #[test]
     fn can_detect_kind_of_changes() {
         let test_cases = vec![
@@ -252,6 +426,12 @@ mod tests {
                 Path::new("/home/vincent/site/templates/hello.html"),
                 Path::new("/home/vincent/site/config.toml"),
             ),
+            (
+                (ChangeKind::Shortcodes, PathBuf::from("/templates/shortcodes/note.html")),
+                Path::new("/home/vincent/site"),
+                Path::new("/home/vincent/site/templates/shortcodes/note.html"),
+                Path::new("/home/vincent/site/config.toml"),
+            ),
             (
                 (ChangeKind::Themes, PathBuf::from("/themes/hello.html")),
                 Path::new("/home/vincent/site"),
@@ -276,6 +456,12 @@ mod tests {
                 Path::new("/home/vincent/site/sass/print.scss"),
                 Path::new("/home/vincent/site/config.toml"),
             ),
+            (
+                (ChangeKind::Data, PathBuf::from("/data/authors.toml")),
+                Path::new("/home/vincent/site"),
+                Path::new("/home/vincent/site/data/authors.toml"),
+                Path::new("/home/vincent/site/config.toml"),
+            ),
             (
                 (ChangeKind::Config, PathBuf::from("/config.toml")),
                 Path::new("/home/vincent/site"),
@@ -288,6 +474,18 @@ mod tests {
                 Path::new("/home/vincent/site/config.staging.toml"),
                 Path::new("/home/vincent/site/config.staging.toml"),
             ),
+            (
+                (ChangeKind::Config, PathBuf::from("/extra_schema.toml")),
+                Path::new("/home/vincent/site"),
+                Path::new("/home/vincent/site/extra_schema.toml"),
+                Path::new("/home/vincent/site/config.toml"),
+            ),
+            (
+                (ChangeKind::Config, PathBuf::from("/.zolaignore")),
+                Path::new("/home/vincent/site"),
+                Path::new("/home/vincent/site/.zolaignore"),
+                Path::new("/home/vincent/site/config.toml"),
+            ),
         ];
 
         for (expected, pwd, path, config_filename) in test_cases {
@@ -305,6 +503,50 @@ mod tests {
         assert_eq!(expected, detect_change_kind(pwd, path, config_filename));
     }
 
+    #[test]
+    #[cfg(windows)]
+    fn windows_verbatim_and_drive_letter_casing() {
+        let pwd = Path::new(r#"C:\Users\johan\site"#);
+        let config_filename = Path::new(r#"C:\Users\johan\site\config.toml"#);
+        let test_cases = vec![
+            (
+                (ChangeKind::Templates, PathBuf::from("/templates/hello.html")),
+                Path::new(r#"\\?\C:\Users\johan\site\templates\hello.html"#),
+            ),
+            (
+                (ChangeKind::Content, PathBuf::from("/content/hello.md")),
+                Path::new(r#"c:\Users\johan\site\content\hello.md"#),
+            ),
+            (
+                (ChangeKind::Config, PathBuf::from("/config.toml")),
+                Path::new(r#"\\?\c:\Users\johan\site\config.toml"#),
+            ),
+            (
+                (ChangeKind::Other, PathBuf::from(r#"D:\Users\johan\site\content\hello.md"#)),
+                Path::new(r#"D:\Users\johan\site\content\hello.md"#),
+            ),
+        ];
+        for (expected, path) in test_cases {
+            assert_eq!(expected, detect_change_kind(pwd, path, config_filename));
+        }
+    }
+
+    #[test]
+    fn unexpected_paths_are_other_changes() {
+        let pwd = Path::new("/home/vincent/site");
+        let config_filename = Path::new("/home/vincent/site/config.toml");
+        let outside = Path::new("/home/vincent/other-site/content/hello.md");
+        assert_eq!(
+            detect_change_kind(pwd, outside, config_filename),
+            (ChangeKind::Other, outside.to_path_buf())
+        );
+        let unwatched = Path::new("/home/vincent/site/node_modules/pkg/index.js");
+        assert_eq!(
+            detect_change_kind(pwd, unwatched, config_filename),
+            (ChangeKind::Other, PathBuf::from("/node_modules/pkg/index.js"))
+        );
+    }
+
     #[test]
     fn relative_path() {
         let expected = (ChangeKind::Templates, PathBuf::from("/templates/hello.html"));
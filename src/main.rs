@@ -1,7 +1,7 @@
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 
-use cli::{Cli, Command};
+use cli::{Cli, Command, MessageFormat};
 use errors::anyhow;
 use utils::net::{get_available_port, port_is_available};
 
@@ -42,6 +42,9 @@ fn get_config_file_path(dir: &Path, config_path: &Path) -> (PathBuf, PathBuf) {
 
 fn main() {
     let cli = Cli::parse();
+    if cli.message_format == MessageFormat::Json {
+        console::enable_json_output();
+    }
     let cli_dir: PathBuf = cli.root.canonicalize().unwrap_or_else(|e| {
         messages::unravel_errors(
             &format!("Could not find canonical path of root dir: {}", cli.root.display()),
@@ -57,19 +60,39 @@ fn main() {
                 std::process::exit(1);
             }
         }
-        Command::Build { base_url, output_dir, force, drafts } => {
+        Command::Build {
+            base_url,
+            output_dir,
+            force,
+            drafts,
+            include_future,
+            warn_orphans,
+            offline,
+            strict_dates,
+            if_changed,
+            timings,
+        } => {
             console::info("Building site...");
             let start = Instant::now();
             let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli.config);
-            match cmd::build(
-                &root_dir,
-                &config_file,
-                base_url.as_deref(),
-                output_dir.as_deref(),
+            let options = cmd::BuildOptions {
+                base_url: base_url.as_deref(),
+                output_dir: output_dir.as_deref(),
                 force,
-                drafts,
-            ) {
-                Ok(()) => messages::report_elapsed_time(start),
+                include_drafts: drafts,
+                include_future,
+                warn_orphans,
+                offline,
+                strict_dates,
+                if_changed: if_changed.as_deref(),
+                timings,
+            };
+            match cmd::build(&root_dir, &config_file, options) {
+                Ok(cmd::BuildOutcome::Built) => messages::report_elapsed_time(start),
+                Ok(cmd::BuildOutcome::Unchanged) => {
+                    messages::report_elapsed_time(start);
+                    std::process::exit(3);
+                }
                 Err(e) => {
                     messages::unravel_errors("Failed to build the site", &e);
                     std::process::exit(1);
@@ -86,13 +109,18 @@ fn main() {
             open,
             fast,
             no_port_append,
+            only,
+            watch_only,
+            no_livereload,
+            ws_port,
+            verbose,
         } => {
-            if port != 1111 && !port_is_available(port) {
+            if !watch_only && port != 1111 && !port_is_available(port) {
                 console::error("The requested port is not available");
                 std::process::exit(1);
             }
 
-            if !port_is_available(port) {
+            if !watch_only && !port_is_available(port) {
                 port = get_available_port(1111).unwrap_or_else(|| {
                     console::error("No port available");
                     std::process::exit(1);
@@ -113,17 +141,22 @@ fn main() {
                 drafts,
                 fast,
                 no_port_append,
+                only.as_deref(),
+                watch_only,
+                no_livereload,
+                ws_port,
+                verbose,
                 UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
             ) {
                 messages::unravel_errors("Failed to serve the site", &e);
                 std::process::exit(1);
             }
         }
-        Command::Check { drafts } => {
+        Command::Check { drafts, strict_dates, a11y } => {
             console::info("Checking site...");
             let start = Instant::now();
             let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli.config);
-            match cmd::check(&root_dir, &config_file, None, None, drafts) {
+            match cmd::check(&root_dir, &config_file, None, None, drafts, strict_dates, a11y) {
                 Ok(()) => messages::report_elapsed_time(start),
                 Err(e) => {
                     messages::unravel_errors("Failed to check the site", &e);
@@ -131,6 +164,17 @@ fn main() {
                 }
             }
         }
+        Command::Publish { no_build, dry_run } => {
+            let start = Instant::now();
+            let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli.config);
+            match cmd::publish(&root_dir, &config_file, !no_build, dry_run) {
+                Ok(()) => messages::report_elapsed_time(start),
+                Err(e) => {
+                    messages::unravel_errors("Failed to publish the site", &e);
+                    std::process::exit(1);
+                }
+            }
+        }
         Command::Completion { shell } => {
             let cmd = &mut Cli::command();
             clap_complete::generate(shell, cmd, cmd.get_name().to_string(), &mut std::io::stdout());
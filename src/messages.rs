@@ -1,72 +1,399 @@
-use libs::time::Duration;
-use std::convert::TryInto;
-use std::time::Instant;
+use libs::serde_json::json;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
+use console::{Diagnostic, Severity};
+use content::{ContentStats, SortBy};
 use errors::Error;
-use site::Site;
+use site::{orphans, BuildSummary, PageTiming, Site};
 
 /// Display in the console the number of pages/sections in the site
 pub fn notify_site_size(site: &Site) {
     let library = site.library.read().unwrap();
-    println!(
+    console::plain(&format!(
         "-> Creating {} pages ({} orphan) and {} sections",
         library.pages.len(),
         library.get_all_orphan_pages().len(),
         library.sections.len() - 1, // -1 since we do not count the index as a section there
-    );
+    ));
+    console::plain(&format_content_stats(&library.site_index.stats));
 }
 
 /// Display in the console only the number of pages/sections in the site
 pub fn check_site_summary(site: &Site) {
     let library = site.library.read().unwrap();
     let orphans = library.get_all_orphan_pages();
-    println!(
+    console::plain(&format!(
         "-> Site content: {} pages ({} orphan), {} sections",
         library.pages.len(),
         orphans.len(),
         library.sections.len() - 1, // -1 since we do not count the index as a section there
-    );
+    ));
+    console::plain(&format_content_stats(&library.site_index.stats));
 
     for orphan in orphans {
         console::warn(&format!("Orphan page found: {}", orphan.path));
     }
 }
 
-/// Display a warning in the console if there are ignored pages in the site
+/// The inventory of the content shown after the number of pages and sections
+fn format_content_stats(stats: &ContentStats) -> String {
+    format!(
+        "-> Content inventory: {} draft(s), {} future, {} unlisted, {} without description, {} words",
+        stats.drafts, stats.future, stats.unlisted, stats.missing_description, stats.word_count
+    )
+}
+
+/// Display a warning in the console if there are ignored pages in the site, listing apart the ones
+/// of the sections sorted by weight since they can get their weight from their filename
 pub fn warn_about_ignored_pages(site: &Site) {
     let library = site.library.read().unwrap();
-    let ignored_pages: Vec<_> = library
+    let pages = &library.pages;
+    let (by_weight, others): (Vec<_>, Vec<_>) = library
         .sections
         .values()
-        .flat_map(|s| s.ignored_pages.iter().map(|k| library.pages[k].file.path.clone()))
+        .flat_map(|s| {
+            let by_weight = s.meta.sort_by == SortBy::Weight;
+            s.ignored_pages.iter().map(move |k| (pages[k].file.path.clone(), by_weight))
+        })
+        .partition(|(_, by_weight)| *by_weight);
+
+    let lists = [
+        (others, "missing date or weight in a sorted section"),
+        (
+            by_weight,
+            "missing weight in a section sorted by weight, set `weight` in their front matter or start their filename with it like `03_setup.md`",
+        ),
+    ];
+    for (ignored_pages, reason) in lists {
+        if console::is_json_output() {
+            for (path, _) in ignored_pages {
+                emit_warning("ignored_page", path, &format!("Page ignored ({})", reason));
+            }
+            continue;
+        }
+
+        if !ignored_pages.is_empty() {
+            console::warn(&format!("{} page(s) ignored ({}):", ignored_pages.len(), reason));
+            for (path, _) in ignored_pages {
+                console::warn(&format!("- {}", path.display()));
+            }
+        }
+    }
+}
+
+/// Display a warning for each page with a date that has a time but no offset, which is taken as
+/// UTC since there is no `default_timezone` in the config
+pub fn warn_about_dates_without_offset(site: &Site) {
+    let library = site.library.read().unwrap();
+    let mut paths: Vec<_> = library
+        .pages
+        .values()
+        .filter(|p| p.meta.has_datetime_without_offset())
+        .map(|p| p.file.path.clone())
         .collect();
+    paths.sort();
 
-    if !ignored_pages.is_empty() {
+    for path in paths {
+        if console::is_json_output() {
+            emit_warning("timezone", path, "Date without a timezone, taken as UTC");
+        } else {
+            console::warn(&format!(
+                "{}: date without a timezone, taken as UTC (set `default_timezone` in the config or an offset in the date)",
+                path.display()
+            ));
+        }
+    }
+}
+
+/// Display warnings for the pages that cannot be reached from anywhere and for the page assets
+/// not referenced by their page.
+/// `check_output` should be set if the site has been built to look for assets in the rendered HTML
+pub fn warn_about_orphans(site: &Site, check_output: bool) {
+    let orphan_pages = orphans::find_orphan_pages(site);
+    let unreferenced_assets = orphans::find_unreferenced_assets(site, check_output);
+
+    if console::is_json_output() {
+        for path in orphan_pages {
+            emit_warning(
+                "orphan",
+                path,
+                "Page not linked from any section, taxonomy, feed or other page",
+            );
+        }
+        for (_, assets) in unreferenced_assets {
+            for asset in assets {
+                emit_warning("asset", asset, "Asset not referenced by its page");
+            }
+        }
+        return;
+    }
+
+    if !orphan_pages.is_empty() {
         console::warn(&format!(
-            "{} page(s) ignored (missing date or weight in a sorted section):",
-            ignored_pages.len()
+            "{} page(s) not linked from any section, taxonomy, feed or other page (set `orphan = true` in their front matter if it is intended):",
+            orphan_pages.len()
         ));
-        for path in ignored_pages {
+        for path in orphan_pages {
             console::warn(&format!("- {}", path.display()));
         }
     }
+
+    if !unreferenced_assets.is_empty() {
+        console::warn(&format!(
+            "{} asset(s) not referenced by their page:",
+            unreferenced_assets.iter().map(|(_, assets)| assets.len()).sum::<usize>()
+        ));
+        for (page, assets) in unreferenced_assets {
+            console::warn(&format!("- {}", page.display()));
+            for asset in assets {
+                let name = asset.strip_prefix(page.parent().unwrap()).unwrap_or(&asset);
+                console::warn(&format!("  - {}", name.display()));
+            }
+        }
+    }
+}
+
+fn emit_warning(kind: &str, path: PathBuf, message: &str) {
+    console::emit(&Diagnostic::new(Severity::Warning, kind, message).in_file(path));
 }
 
 /// Print the time elapsed rounded to 1 decimal
 pub fn report_elapsed_time(instant: Instant) {
-    let duration: Duration = instant.elapsed().try_into().unwrap();
-    let duration_ms = duration.whole_milliseconds() as f64;
+    console::success(&format!("Done in {}.\n", format_duration(instant.elapsed())));
+}
 
-    if duration_ms < 1000.0 {
-        console::success(&format!("Done in {}ms.\n", duration_ms));
+/// Milliseconds under a second, seconds rounded to 1 decimal above
+fn format_duration(duration: Duration) -> String {
+    let duration_ms = duration.as_millis();
+    if duration_ms < 1000 {
+        format!("{}ms", duration_ms)
     } else {
-        let duration_sec = duration_ms / 1000.0;
-        console::success(&format!("Done in {:.1}s.\n", ((duration_sec * 10.0).round() / 10.0)));
+        format!("{:.1}s", (duration_ms as f64 / 100.0).round() / 10.0)
+    }
+}
+
+/// Display what was rendered after a change in `zola serve`, as a tree or as a line of JSON
+pub fn report_rebuild(site: &Site, trigger: &str, verbose: bool) {
+    let summary = site.build_summary();
+    if console::is_json_output() {
+        let relative = |paths: &[PathBuf]| -> Vec<String> {
+            paths.iter().map(|p| relative_path(p, &site.base_path)).collect()
+        };
+        let timings: Vec<_> = summary
+            .phases
+            .iter()
+            .map(|(step, duration)| json!({ "step": step, "ms": duration.as_millis() as u64 }))
+            .collect();
+        console::emit_json(
+            &json!({
+                "kind": "rebuild",
+                "trigger": trigger,
+                "pages": relative(&summary.pages),
+                "sections": relative(&summary.sections),
+                "aggregates": summary.aggregates,
+                "unchanged_aggregates": summary.unchanged_aggregates,
+                "timings": timings,
+            })
+            .to_string(),
+        );
+        return;
+    }
+
+    for line in format_rebuild_summary(&summary, &site.base_path, trigger, verbose) {
+        console::plain(&line);
     }
 }
 
+/// How many of the slowest pages `zola build --timings` shows
+const SLOWEST_PAGES: usize = 20;
+/// How many of the slowest pages the rebuild summary of `zola serve -v` shows
+const SLOWEST_PAGES_IN_REBUILD: usize = 5;
+
+/// Display how long each step of `zola build --timings` took, the slowest pages and how many
+/// pages took how long to render, or all of that as a line of JSON
+pub fn report_timings(site: &Site) {
+    let summary = site.build_summary();
+    if console::is_json_output() {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let phases: Vec<_> = summary
+            .phases
+            .iter()
+            .map(|(step, duration)| json!({ "step": step, "ms": ms(*duration) }))
+            .collect();
+        let pages: Vec<_> = summary
+            .slowest_pages(usize::MAX)
+            .into_iter()
+            .map(|(path, timing)| {
+                json!({
+                    "path": relative_path(path, &site.base_path),
+                    "markdown_ms": ms(timing.markdown),
+                    "template_ms": ms(timing.template),
+                })
+            })
+            .collect();
+        let histogram: Vec<_> = summary
+            .timing_histogram()
+            .into_iter()
+            .map(|(bucket, count)| json!({ "bucket": bucket, "pages": count }))
+            .collect();
+        console::emit_json(
+            &json!({
+                "kind": "timings",
+                "phases": phases,
+                "pages": pages,
+                "histogram": histogram,
+            })
+            .to_string(),
+        );
+        return;
+    }
+
+    for line in format_timings(&summary, &site.base_path) {
+        console::plain(&line);
+    }
+}
+
+/// The lines of `zola build --timings`: the steps, the totals of the pages, the slowest pages
+/// and the histogram of their render times
+fn format_timings(summary: &BuildSummary, base_path: &Path) -> Vec<String> {
+    let mut lines = vec!["-> Timings".to_string()];
+    for (step, duration) in &summary.phases {
+        lines.push(format!("   {}: {}", step, format_duration(*duration)));
+    }
+    let markdown: Duration = summary.page_timings.values().map(|t| t.markdown).sum();
+    let template: Duration = summary.page_timings.values().map(|t| t.template).sum();
+    lines.push(format!(
+        "   Pages, summed over all threads: Markdown {}, templates {}",
+        format_duration(markdown),
+        format_duration(template)
+    ));
+
+    let slowest = summary.slowest_pages(SLOWEST_PAGES);
+    if !slowest.is_empty() {
+        lines.push(format!("-> Slowest {}", pluralize(slowest.len(), "page")));
+        for (i, (path, timing)) in slowest.into_iter().enumerate() {
+            lines.push(format!("   {}. {}", i + 1, format_page_timing(path, timing, base_path)));
+        }
+    }
+
+    let histogram = summary.timing_histogram();
+    if let Some(max) = histogram.iter().map(|(_, count)| *count).max() {
+        lines.push("-> Pages by render time".to_string());
+        let width = histogram.iter().map(|(bucket, _)| bucket.len()).max().unwrap_or_default();
+        for (bucket, count) in histogram {
+            // Bars of at most 40 characters, at least one for the non-empty buckets
+            let bar = match count {
+                0 => String::new(),
+                _ => format!("{} ", "#".repeat((count * 40).div_ceil(max))),
+            };
+            lines.push(format!("   {:>width$} | {}{}", bucket, bar, count));
+        }
+    }
+    lines
+}
+
+fn format_page_timing(path: &Path, timing: &PageTiming, base_path: &Path) -> String {
+    format!(
+        "{}: {} (Markdown {}, templates {})",
+        relative_path(path, base_path),
+        format_duration(timing.total()),
+        format_duration(timing.markdown),
+        format_duration(timing.template)
+    )
+}
+
+fn relative_path(path: &Path, base_path: &Path) -> String {
+    path.strip_prefix(base_path).unwrap_or(path).display().to_string()
+}
+
+fn pluralize(count: usize, name: &str) -> String {
+    format!("{} {}{}", count, name, if count == 1 { "" } else { "s" })
+}
+
+/// The lines of the tree showing what a rebuild did. The rendered content and the slowest pages
+/// are only listed when `verbose` is set and the steps taking less than a millisecond are left out
+fn format_rebuild_summary(
+    summary: &BuildSummary,
+    base_path: &Path,
+    trigger: &str,
+    verbose: bool,
+) -> Vec<String> {
+    let mut branches = Vec::new();
+
+    let rendered = if verbose {
+        summary.sections.iter().chain(&summary.pages).map(|p| relative_path(p, base_path)).collect()
+    } else {
+        Vec::new()
+    };
+    branches.push((
+        format!(
+            "{} and {} rendered",
+            pluralize(summary.pages.len(), "page"),
+            pluralize(summary.sections.len(), "section")
+        ),
+        rendered,
+    ));
+
+    if !summary.aggregates.is_empty() {
+        branches.push((format!("Regenerated {}", summary.aggregates.join(", ")), Vec::new()));
+    }
+    if !summary.unchanged_aggregates.is_empty() {
+        branches.push((
+            format!("Kept {} unchanged", summary.unchanged_aggregates.join(", ")),
+            Vec::new(),
+        ));
+    }
+
+    if verbose {
+        let slowest: Vec<_> = summary
+            .slowest_pages(SLOWEST_PAGES_IN_REBUILD)
+            .into_iter()
+            .map(|(path, timing)| format_page_timing(path, timing, base_path))
+            .collect();
+        if !slowest.is_empty() {
+            branches.push(("Slowest pages".to_string(), slowest));
+        }
+    }
+
+    let timings: Vec<_> = summary
+        .phases
+        .iter()
+        .filter(|(_, duration)| duration.as_millis() > 0)
+        .map(|(step, duration)| format!("{}: {}", step, format_duration(*duration)))
+        .collect();
+    if !timings.is_empty() {
+        branches.push(("Timings".to_string(), timings));
+    }
+
+    let mut lines = vec![format!("Rebuilt because of {}", trigger)];
+    for (i, (label, children)) in branches.iter().enumerate() {
+        let is_last = i == branches.len() - 1;
+        lines.push(format!("{} {}", if is_last { "└─" } else { "├─" }, label));
+        for (j, child) in children.iter().enumerate() {
+            lines.push(format!(
+                "{}  {} {}",
+                if is_last { " " } else { "│" },
+                if j == children.len() - 1 { "└─" } else { "├─" },
+                child
+            ));
+        }
+    }
+    lines
+}
+
 /// Display an error message and the actual error(s)
 pub fn unravel_errors(message: &str, error: &Error) {
+    if console::is_json_output() {
+        for mut diagnostic in Diagnostic::from_error(Severity::Error, error) {
+            if !message.is_empty() && diagnostic.file.is_none() {
+                diagnostic.message = format!("{}: {}", message, diagnostic.message);
+            }
+            console::emit(&diagnostic);
+        }
+        return;
+    }
+
     if !message.is_empty() {
         console::error(message);
     }
@@ -77,3 +404,116 @@ pub fn unravel_errors(message: &str, error: &Error) {
         cause = e.source();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn can_format_content_stats() {
+        let stats = ContentStats {
+            pages: 10,
+            drafts: 2,
+            future: 1,
+            unlisted: 0,
+            missing_description: 4,
+            word_count: 5120,
+        };
+        assert_eq!(
+            format_content_stats(&stats),
+            "-> Content inventory: 2 draft(s), 1 future, 0 unlisted, 4 without description, 5120 words"
+        );
+    }
+
+    #[test]
+    fn can_format_rebuild_summary() {
+        let summary = BuildSummary {
+            pages: vec![PathBuf::from("/site/content/blog/a.md")],
+            sections: vec![
+                PathBuf::from("/site/content/_index.md"),
+                PathBuf::from("/site/content/blog/_index.md"),
+            ],
+            aggregates: vec!["sitemap", "feeds"],
+            unchanged_aggregates: vec!["search index"],
+            phases: vec![
+                ("Loaded content".to_string(), Duration::from_millis(1200)),
+                ("Rendered aliases".to_string(), Duration::from_micros(10)),
+                ("Rendered sections".to_string(), Duration::from_millis(42)),
+            ],
+            page_timings: HashMap::from([(
+                PathBuf::from("/site/content/blog/a.md"),
+                PageTiming {
+                    markdown: Duration::from_millis(30),
+                    template: Duration::from_millis(12),
+                },
+            )]),
+        };
+        let base_path = Path::new("/site");
+
+        assert_eq!(
+            format_rebuild_summary(&summary, base_path, "content/blog/a.md", false),
+            vec![
+                "Rebuilt because of content/blog/a.md",
+                "├─ 1 page and 2 sections rendered",
+                "├─ Regenerated sitemap, feeds",
+                "├─ Kept search index unchanged",
+                "└─ Timings",
+                "   ├─ Loaded content: 1.2s",
+                "   └─ Rendered sections: 42ms",
+            ]
+        );
+        assert_eq!(
+            format_rebuild_summary(&summary, base_path, "content/blog/a.md", true)[1..5],
+            [
+                "├─ 1 page and 2 sections rendered",
+                "│  ├─ content/_index.md",
+                "│  ├─ content/blog/_index.md",
+                "│  └─ content/blog/a.md",
+            ]
+        );
+        assert_eq!(
+            format_rebuild_summary(&summary, base_path, "content/blog/a.md", true)[7..9],
+            ["├─ Slowest pages", "│  └─ content/blog/a.md: 42ms (Markdown 30ms, templates 12ms)",]
+        );
+    }
+
+    #[test]
+    fn can_format_timings() {
+        let timing = |markdown: u64, template: u64| PageTiming {
+            markdown: Duration::from_millis(markdown),
+            template: Duration::from_millis(template),
+        };
+        let summary = BuildSummary {
+            phases: vec![("Rendered sections".to_string(), Duration::from_millis(1500))],
+            page_timings: HashMap::from([
+                (PathBuf::from("/site/content/a.md"), timing(2, 1)),
+                (PathBuf::from("/site/content/b.md"), timing(300, 20)),
+                (PathBuf::from("/site/content/c.md"), timing(1, 2)),
+            ]),
+            ..BuildSummary::default()
+        };
+
+        assert_eq!(
+            format_timings(&summary, Path::new("/site")),
+            vec![
+                "-> Timings",
+                "   Rendered sections: 1.5s",
+                "   Pages, summed over all threads: Markdown 303ms, templates 23ms",
+                "-> Slowest 3 pages",
+                "   1. content/b.md: 320ms (Markdown 300ms, templates 20ms)",
+                "   2. content/a.md: 3ms (Markdown 2ms, templates 1ms)",
+                "   3. content/c.md: 3ms (Markdown 1ms, templates 2ms)",
+                "-> Pages by render time",
+                "       < 1ms | 0",
+                "       1-5ms | ######################################## 2",
+                "      5-10ms | 0",
+                "     10-50ms | 0",
+                "    50-100ms | 0",
+                "   100-500ms | #################### 1",
+                "    >= 500ms | 0",
+            ]
+        );
+    }
+}